@@ -4,81 +4,33 @@
 //!   CrabbyBot chat          — Start an interactive chat session
 //!   CrabbyBot onboard       — Create a default configuration
 //!   CrabbyBot status        — Show current configuration and health
+//!   CrabbyBot config set     — Change one setting (`config get` / `config list` to read)
 //!   CrabbyBot cron list      — List scheduled jobs
+//!   CrabbyBot cron edit      — Change a job in $EDITOR (`cron export` / `cron import` to copy jobs)
 //!   CrabbyBot sessions       — List conversation sessions
+//!   CrabbyBot sessions fork  — Copy a session to explore an alternative
+//!   CrabbyBot tools stats    — Show per-tool usage statistics
+//!   CrabbyBot support-bundle — Zip redacted config, logs, and traces for a bug report
+//!   CrabbyBot self-update    — Install the latest release for the configured channel
+//!   CrabbyBot --self-test    — Check providers, tools, and cron, then exit
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::io::{self, Write};
-use std::path::PathBuf;
-use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
-use crabbybot_core::agent::{AgentConfig, AgentLoop};
-use crabbybot_core::bus::MessageBus;
-use crabbybot_core::config::Config;
-use crabbybot_core::cron::{CronService, Schedule};
-#[cfg(feature = "discord")]
-use crabbybot_core::gateway::channels::discord::DiscordTransport;
-#[cfg(feature = "telegram")]
-use crabbybot_core::gateway::channels::telegram::TelegramTransport;
-use crabbybot_core::gateway::AgentBridge;
+use crabbybot_core::agent::locale::LocaleSettings;
+use crabbybot_core::backup::{self, Backup, Locations, Part};
+use crabbybot_core::config::{self, Config};
+use crabbybot_core::cron::{self as cron_jobs, CronService, JobKind, Schedule};
 use tracing::warn;
-use crabbybot_core::provider::openai::OpenAiProvider;
-use crabbybot_core::provider::LlmProvider;
+use crabbybot_core::provider::ollama::OllamaProvider;
+use crabbybot_core::runtime::Runtime;
+use crabbybot_core::selftest::{self, SelfTestMode};
 use crabbybot_core::session::SessionManager;
-use crabbybot_core::tools::alpha_summary::AlphaSummaryTool;
-use crabbybot_core::tools::filesystem::{EditFileTool, ListDirTool, ReadFileTool, WriteFileTool};
-use crabbybot_core::tools::polymarket::{
-    PolymarketMarketTool, PolymarketSearchTool, PolymarketTrendingTool,
-};
-use crabbybot_core::tools::polymarket_approve::PolymarketApproveTool;
-use crabbybot_core::tools::polymarket_bridge::PolymarketBridgeTool;
-use crabbybot_core::tools::polymarket_comments::PolymarketCommentsTool;
-use crabbybot_core::tools::polymarket_ctf::{
-    PolymarketCtfMergeTool, PolymarketCtfRedeemTool, PolymarketCtfSplitTool,
-};
-use crabbybot_core::tools::polymarket_data::{
-    PolymarketActivityTool, PolymarketBuilderLeaderboardTool, PolymarketClosedPositionsTool,
-    PolymarketHoldersTool, PolymarketLeaderboardTool, PolymarketOpenInterestTool,
-    PolymarketPositionsTool, PolymarketTradesTool, PolymarketVolumeTool,
-};
-use crabbybot_core::tools::polymarket_events::{PolymarketEventDetailTool, PolymarketEventsTool};
-use crabbybot_core::tools::polymarket_orderbook::{
-    PolymarketClobMarketTool, PolymarketLastTradeTool, PolymarketOrderbookTool,
-    PolymarketTickSizeTool,
-};
-use crabbybot_core::tools::polymarket_orders::{
-    PolymarketAccountStatusTool, PolymarketApiKeysTool, PolymarketBalanceTool,
-    PolymarketCancelOrderTool, PolymarketMyOrdersTool, PolymarketNotificationsTool,
-    PolymarketRewardsTool,
-};
-use crabbybot_core::tools::polymarket_prices::{PolymarketPriceHistoryTool, PolymarketPriceTool};
-use crabbybot_core::tools::polymarket_profiles::PolymarketProfileTool;
-use crabbybot_core::tools::polymarket_series::PolymarketSeriesTool;
-use crabbybot_core::tools::polymarket_sports::PolymarketSportsTool;
-use crabbybot_core::tools::polymarket_status::PolymarketStatusTool;
-use crabbybot_core::tools::polymarket_stream::PolymarketStreamTool;
-use crabbybot_core::tools::polymarket_tags::PolymarketTagsTool;
-use crabbybot_core::tools::polymarket_trade::{
-    PolymarketCreateOrderTool, PolymarketMarketOrderTool,
-};
-use crabbybot_core::tools::polymarket_wallet::{
-    PolymarketWalletCreateTool, PolymarketWalletImportTool, PolymarketWalletTool,
-};
-use crabbybot_core::tools::rugcheck::RugCheckTool;
-use crabbybot_core::tools::schedule::{CancelScheduleTool, ListSchedulesTool, ScheduleTaskTool};
-use crabbybot_core::tools::sentiment::SentimentTool;
-use crabbybot_core::tools::shell::ExecTool;
-use crabbybot_core::tools::solana::{
-    SolanaBalanceTool, SolanaTokenBalancesTool, SolanaTransactionsTool,
-};
-use crabbybot_core::tools::web::{WebFetchTool, WebSearchTool};
-use crabbybot_core::tools::betting_control::BettingControlTool;
-use crabbybot_core::tools::prediction::{GraphQueryTool, PredictTool, SimulateTool};
-use crabbybot_core::tools::prediction::tool_predict::PredictionState;
-use crabbybot_core::tools::ToolRegistry;
-use crabbybot_core::service::betting::{BettingService, BettingState};
+use crabbybot_core::support;
+use crabbybot_core::tools::stats::{self as tool_stats, ToolStats};
+use crabbybot_core::update::{self, Channel, InstalledRelease};
+use crabbybot_core::usage::UsageTracker;
 
 #[derive(Parser)]
 #[command(
@@ -88,10 +40,32 @@ use crabbybot_core::service::betting::{BettingService, BettingState};
     long_about = "🦀 CrabbyBot — a blazing-fast AI assistant written in Rust.\n\nZero runtime dependencies. Single binary. Direct LLM API access."
 )]
 struct Cli {
+    /// Check providers, tool schemas, and the cron store, then exit
+    #[arg(long)]
+    self_test: bool,
+
+    /// Log output format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Refuse every tool call that writes files, runs commands, trades or
+    /// changes schedules (same as `tools.readOnly`)
+    #[arg(long, global = true)]
+    read_only: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogFormat {
+    /// Human-readable compact lines
+    Text,
+    /// One JSON object per line, including the `request_id` of the
+    /// conversation turn each line belongs to
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start an interactive chat session
@@ -111,6 +85,12 @@ enum Commands {
     /// Show configuration status and health
     Status,
 
+    /// Read or change single settings in config.json
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+
     /// Manage scheduled jobs
     Cron {
         #[command(subcommand)]
@@ -125,6 +105,126 @@ enum Commands {
         #[command(subcommand)]
         action: Option<SessionCommands>,
     },
+
+    /// Inspect tools
+    Tools {
+        #[command(subcommand)]
+        action: ToolCommands,
+    },
+
+    /// List the models pulled on the Ollama server (providers.ollama)
+    Models,
+
+    /// Zip redacted config, recent logs, and traces for a bug report
+    SupportBundle {
+        /// Where to write the archive (default: ./crabbybot-support-<timestamp>.zip)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Write without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Download, verify, and install the latest release
+    SelfUpdate {
+        /// Release channel: stable or nightly (default: update.channel)
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+
+        /// Restore the binary replaced by the last update
+        #[arg(long, conflicts_with = "check")]
+        rollback: bool,
+    },
+
+    /// Back up or restore the bot's state (encrypted with a passphrase)
+    Backup {
+        #[command(subcommand)]
+        action: BackupCommands,
+    },
+
+    /// Sync workspace files with the configured S3 or WebDAV storage once
+    #[cfg(feature = "sync")]
+    Sync,
+
+    /// Show or send the opt-in telemetry report (noised usage counts only)
+    Telemetry {
+        #[command(subcommand)]
+        action: TelemetryCommands,
+    },
+
+    /// Chat with a running bot over its WebChat socket
+    #[cfg(feature = "attach")]
+    Attach {
+        /// Bot address, e.g. wss://bot.example.com (default: the local gateway)
+        url: Option<String>,
+
+        /// WebChat token (default: $CRABBYBOT_ATTACH_TOKEN or channels.webchat.token)
+        #[arg(short, long)]
+        token: Option<String>,
+
+        /// Session name on the bot
+        #[arg(short, long, default_value = "cli")]
+        session: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupCommands {
+    /// Write config, sessions, memory, cron jobs, knowledge graph and profiles to one file
+    Create {
+        /// Backup file to write
+        file: std::path::PathBuf,
+        /// Comma-separated parts to include (config, sessions, memory, cron, knowledge, profiles)
+        #[arg(long, default_value = "")]
+        only: String,
+    },
+    /// Restore state from a backup file
+    Restore {
+        /// Backup file to read
+        file: std::path::PathBuf,
+        /// Comma-separated parts to restore (default: everything in the backup)
+        #[arg(long, default_value = "")]
+        only: String,
+        /// Restore without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TelemetryCommands {
+    /// Print the report for yesterday exactly as it would be sent
+    Preview,
+    /// Send yesterday's report now (needs telemetry.enabled)
+    Send,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Set a setting, e.g. `config set agents.defaults.model gpt-4o`
+    Set {
+        /// Dotted key path (e.g. tools.betting.maxBetSizeUsdc) or short name (e.g. openai_key)
+        key: String,
+        /// New value, read as JSON when it parses (asked for without echo if left out)
+        value: Option<String>,
+    },
+    /// Show a setting's current value
+    Get {
+        /// Dotted key path or short name
+        key: String,
+    },
+    /// Show every setting with its current value
+    List,
+    /// Remove a setting from config.json so it falls back to its default
+    Reset {
+        /// Dotted key path or short name
+        key: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -142,61 +242,195 @@ enum CronCommands {
         /// Message/prompt to execute
         #[arg(short, long)]
         message: String,
+        /// Keep running when the daily token budget is nearly used up
+        #[arg(long)]
+        critical: bool,
+        /// IANA timezone for the expression (default: agents.defaults.timezone)
+        #[arg(long)]
+        timezone: Option<String>,
     },
     /// Remove a job
     Remove {
         /// Job ID
         id: String,
     },
+    /// Change a job in $EDITOR
+    Edit {
+        /// Job ID
+        id: String,
+    },
+    /// Resume a paused job
+    Enable {
+        /// Job ID
+        id: String,
+    },
+    /// Pause a job without removing it
+    Disable {
+        /// Job ID
+        id: String,
+    },
+    /// Write all jobs as JSON, for a backup or another workspace
+    Export {
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Add the jobs from a `cron export` file; jobs already here are skipped
+    Import {
+        /// File written by `cron export`, or another workspace's cron.json
+        file: std::path::PathBuf,
+        /// Send the imported jobs' output to this channel instead
+        #[arg(long)]
+        channel: Option<String>,
+        /// Send the imported jobs' output to this chat instead
+        #[arg(long)]
+        chat_id: Option<String>,
+    },
+    /// List the built-in job templates, or add the named one
+    Template {
+        /// Template name, e.g. journal-review
+        name: Option<String>,
+        /// IANA timezone for the schedule (default: agents.defaults.timezone)
+        #[arg(long)]
+        timezone: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum SessionCommands {
     /// List all sessions
-    List,
+    List {
+        /// Only sessions tagged with this topic, e.g. trading
+        #[arg(long)]
+        topic: Option<String>,
+    },
     /// Delete a session
     Delete {
         /// Session key
         key: String,
     },
+    /// Copy a session's history into a new session linked to it
+    Fork {
+        /// Session to copy
+        source: String,
+        /// Key of the new session (continue it with `chat --session <key>`)
+        new_key: String,
+    },
+    /// Delete sessions that have not been used for a while
+    Purge {
+        /// Age of the last message, e.g. 30d or 12h
+        #[arg(long, value_name = "AGE")]
+        older_than: String,
+        /// Only list the sessions that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Give a session a new key
+    Rename {
+        /// Current key
+        old: String,
+        /// New key
+        new_key: String,
+    },
+    /// Append one session's history to another and delete it
+    Merge {
+        /// Session that keeps the combined history
+        into: String,
+        /// Session merged in and then deleted
+        from: String,
+        /// Only show what the merge would do
+        #[arg(long)]
+        dry_run: bool,
+        /// Merge without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Find messages containing a text in all sessions
+    Search {
+        /// Text to look for (case-insensitive)
+        query: String,
+        /// Only search sessions tagged with this topic
+        #[arg(long)]
+        topic: Option<String>,
+        /// Most matches to show
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolCommands {
+    /// Show call counts, error rates, and latency per tool
+    Stats {
+        /// Number of days to aggregate, including today
+        #[arg(short, long, default_value_t = 1)]
+        days: u32,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .with_target(false)
-        .compact()
-        .init();
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    match cli.log_format {
+        LogFormat::Text => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_target(false)
+            .compact()
+            .init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
 
-    let cli = Cli::parse();
+    if cli.read_only {
+        // Picked up by every `Config::load`, so each command sees it.
+        std::env::set_var("CRABBYBOT_READ_ONLY", "1");
+    }
+
+    if cli.self_test {
+        return cmd_self_test().await;
+    }
 
     match cli.command {
         Some(Commands::Chat { session, model }) => cmd_chat(&session, model.as_deref()).await?,
         Some(Commands::Bot) => cmd_bot().await?,
         Some(Commands::Onboard) => cmd_onboard()?,
         Some(Commands::Status) => cmd_status()?,
+        Some(Commands::Config { action }) => cmd_config(action)?,
         Some(Commands::Cron { action }) => cmd_cron(action)?,
         Some(Commands::Sessions { action }) => cmd_sessions(action)?,
+        Some(Commands::Tools { action }) => cmd_tools(action)?,
+        Some(Commands::Models) => cmd_models().await?,
+        Some(Commands::SupportBundle { output, yes }) => cmd_support_bundle(output, yes)?,
+        Some(Commands::SelfUpdate { channel, check, rollback }) => {
+            cmd_self_update(channel.as_deref(), check, rollback).await?
+        }
+        Some(Commands::Backup { action }) => cmd_backup(action)?,
+        #[cfg(feature = "sync")]
+        Some(Commands::Sync) => cmd_sync().await?,
+        Some(Commands::Telemetry { action }) => cmd_telemetry(action).await?,
+        #[cfg(feature = "attach")]
+        Some(Commands::Attach { url, token, session }) => cmd_attach(url, token, &session).await?,
         None => cmd_chat("default", None).await?,
     }
 
     Ok(())
 }
 
-use crabbybot_core::tools::IntentCategory;
-
 // ── Shared Setup ────────────────────────────────────────────────────
 
-/// Shared helper that loads config, validates it, and builds a fully
-/// wired `AgentLoop` with providers and tools.
-///
-/// Returns `(agent, config, workspace_path)` so both `cmd_chat` and
-/// `cmd_bot` can avoid duplicating this boilerplate.
+/// Validate config, tolerating errors when a chat channel is enabled so the
+/// bot can still start in setup mode.
 fn validate_config(config: &Config) -> Result<()> {
     if let Err(errors) = config.validate() {
         let is_tg_enabled = config.channels.telegram.as_ref().is_some_and(|c| c.enabled && !c.token.is_empty());
@@ -220,199 +454,6 @@ fn validate_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn setup_agent(
-    config: &Config,
-    model_override: Option<&str>,
-    cron: Option<Arc<tokio::sync::Mutex<CronService>>>,
-    bus: Arc<MessageBus>,
-    default_channel: &str,
-    default_chat_id: &str,
-    betting_state: Option<Arc<tokio::sync::Mutex<BettingState>>>,
-) -> Result<(AgentLoop, PathBuf, Arc<ToolRegistry>)> {
-    let model = model_override
-        .unwrap_or(&config.agents.defaults.model)
-        .to_string();
-
-    // Resolve providers
-    let active_providers = config.providers.find_all_active();
-    
-    let provider: Box<dyn LlmProvider> = if active_providers.is_empty() {
-        warn!("No active LLM providers. Bot will start in limited setup mode.");
-        Box::new(crabbybot_core::provider::NoopProvider { model: model.clone() })
-    } else {
-        let client = reqwest::Client::new();
-        let mut inner_providers = Vec::new();
-        for (name, entry) in active_providers {
-            let p_model = entry.model.as_deref().unwrap_or(&model);
-            
-            let api_key = crabbybot_core::vault::decrypt(&entry.api_key).unwrap_or_else(|e| {
-                tracing::warn!("Failed to decrypt API key for provider {}: {}", name, e);
-                entry.api_key.clone()
-            });
-
-            let p = OpenAiProvider::new(
-                name,
-                &api_key,
-                entry.api_base.as_deref(),
-                p_model,
-                client.clone(),
-            );
-            inner_providers.push((name.to_string(), Box::new(p) as Box<dyn LlmProvider>));
-        }
-        Box::new(crabbybot_core::provider::FallbackProvider::new(inner_providers))
-    };
-
-    let provider: Arc<tokio::sync::Mutex<Box<dyn LlmProvider>>> =
-        Arc::new(tokio::sync::Mutex::new(provider));
-
-    let client = reqwest::Client::new();
-
-    // Set up tools
-    let workspace = config.workspace_path();
-    let restrict = config.tools.restrict_to_workspace;
-    let mut tools = ToolRegistry::new();
-
-    tools.register(Box::new(ReadFileTool::new(workspace.clone(), restrict)), IntentCategory::System);
-    tools.register(Box::new(WriteFileTool::new(workspace.clone(), restrict)), IntentCategory::System);
-    tools.register(Box::new(EditFileTool::new(workspace.clone(), restrict)), IntentCategory::System);
-    tools.register(Box::new(ListDirTool::new(workspace.clone(), restrict)), IntentCategory::System);
-    tools.register(Box::new(ExecTool::new(
-        workspace.clone(),
-        restrict,
-        config.tools.exec.timeout_seconds,
-    )), IntentCategory::System);
-    tools.register(Box::new(WebFetchTool::new(client.clone())), IntentCategory::Research);
-
-    if !config.tools.web_search.api_key.is_empty() {
-        let ws_key = crabbybot_core::vault::decrypt(&config.tools.web_search.api_key).unwrap_or_else(|e| {
-            tracing::warn!("Failed to decrypt WebSearch API key: {}", e);
-            config.tools.web_search.api_key.clone()
-        });
-        tools.register(Box::new(WebSearchTool::new(
-            client.clone(),
-            &ws_key,
-            config.tools.web_search.max_results,
-        )), IntentCategory::Research);
-    }
-
-    // Schedule tools (LLM-powered cron via natural language)
-    if let Some(ref cron_arc) = cron {
-        tools.register(Box::new(ScheduleTaskTool::new(
-            Arc::clone(cron_arc),
-            default_channel.to_string(),
-            default_chat_id.to_string(),
-        )), IntentCategory::System);
-        tools.register(Box::new(ListSchedulesTool::new(Arc::clone(cron_arc))), IntentCategory::System);
-        tools.register(Box::new(CancelScheduleTool::new(Arc::clone(cron_arc))), IntentCategory::System);
-    }
-
-    // Solana tools (crypto-native on-chain data)
-    tools.register(Box::new(SolanaBalanceTool::new(
-        client.clone(),
-        &config.tools.solana_rpc_url,
-    )), IntentCategory::CryptoTokens);
-    tools.register(Box::new(SolanaTransactionsTool::new(
-        client.clone(),
-        &config.tools.solana_rpc_url,
-    )), IntentCategory::CryptoTokens);
-    tools.register(Box::new(SolanaTokenBalancesTool::new(
-        client.clone(),
-        &config.tools.solana_rpc_url,
-    )), IntentCategory::CryptoTokens);
-
-    // Polymarket read-only tools (markets, events, prices, data)
-    let mut pm = config.tools.polymarket.clone();
-    if let Some(ref pk) = pm.private_key {
-        pm.private_key = Some(crabbybot_core::vault::decrypt(pk).unwrap_or_else(|e| {
-            tracing::warn!("Failed to decrypt Polymarket private key: {}", e);
-            pk.clone()
-        }));
-    }
-    tools.register(Box::new(PolymarketTrendingTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketSearchTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketMarketTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketEventsTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketEventDetailTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketPriceTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketPriceHistoryTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketOrderbookTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketLastTradeTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketClobMarketTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketTickSizeTool::new(pm.clone())), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketPositionsTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketLeaderboardTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketClosedPositionsTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketTradesTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketActivityTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketHoldersTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketOpenInterestTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketVolumeTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketBuilderLeaderboardTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketBridgeTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketStatusTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketStreamTool::new()), IntentCategory::PolymarketRead);
-
-    // Polymarket Gamma browsing (tags, series, comments, profiles, sports)
-    tools.register(Box::new(PolymarketTagsTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketSeriesTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketCommentsTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketProfileTool::new()), IntentCategory::PolymarketRead);
-    tools.register(Box::new(PolymarketSportsTool::new()), IntentCategory::PolymarketRead);
-
-    // Polymarket authenticated trading tools (need POLYMARKET_PRIVATE_KEY)
-    let pm = pm.clone();
-    tools.register(Box::new(PolymarketCreateOrderTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketMarketOrderTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketMyOrdersTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketCancelOrderTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketBalanceTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketWalletTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketWalletCreateTool::new()), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketWalletImportTool::new()), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketRewardsTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketNotificationsTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketApiKeysTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketAccountStatusTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-
-    // Polymarket on-chain tools (need wallet + MATIC)
-    tools.register(Box::new(PolymarketCtfSplitTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketCtfMergeTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketCtfRedeemTool::new(pm.clone())), IntentCategory::PolymarketTrade);
-    tools.register(Box::new(PolymarketApproveTool::new(pm)), IntentCategory::PolymarketTrade);
-
-    // Token Analysis
-    tools.register(Box::new(RugCheckTool::new(client.clone())), IntentCategory::CryptoTokens);
-    tools.register(Box::new(SentimentTool::new(client.clone())), IntentCategory::CryptoTokens);
-    tools.register(Box::new(AlphaSummaryTool::new(client.clone())), IntentCategory::CryptoTokens);
-
-    // Betting control tool (if betting state is provided)
-    if let Some(ref bs) = betting_state {
-        tools.register(Box::new(BettingControlTool::new(Arc::clone(bs))), IntentCategory::PolymarketTrade);
-    }
-
-    let agent_config = AgentConfig {
-        model: model_override.map(|s| s.to_string()),
-        max_tokens: config.agents.defaults.max_tokens,
-        temperature: config.agents.defaults.temperature,
-        max_iterations: config.agents.defaults.max_tool_iterations,
-        workspace: workspace.clone(),
-        max_context_tokens: 4_000,
-    };
-
-    // Prediction engine tools (share LLM provider via Arc<Mutex<...>>)
-    let prediction_state = Arc::new(PredictionState {
-        provider: Arc::clone(&provider),
-        workspace: workspace.clone(),
-    });
-    tools.register(Box::new(PredictTool { state: Arc::clone(&prediction_state) }), IntentCategory::Prediction);
-    tools.register(Box::new(SimulateTool { state: Arc::clone(&prediction_state) }), IntentCategory::Prediction);
-    tools.register(Box::new(GraphQueryTool { workspace: workspace.clone() }), IntentCategory::Prediction);
-
-    let tools = Arc::new(tools);
-    let agent = AgentLoop::new(provider, Arc::clone(&tools), agent_config);
-    Ok((agent, workspace, tools))
-}
-
 // ── Bot Command ─────────────────────────────────────────────────────
 
 async fn cmd_bot() -> Result<()> {
@@ -487,170 +528,28 @@ async fn cmd_bot_once(cancel: CancellationToken) -> Result<()> {
     let config = Config::load()?;
     validate_config(&config)?;
 
-    let workspace = config.workspace_path();
-
-    // Shared CronService for both the LLM tools and the cron ticker.
-    let cron = Arc::new(tokio::sync::Mutex::new(CronService::new(&workspace)));
-
-    // Derive default chat_id for cron jobs from the first allowed Telegram user.
-    // In Telegram private chats, chat_id == user_id.
-    let default_chat_id = config
-        .channels
-        .telegram
-        .as_ref()
-        .and_then(|t| t.allow_from.first())
-        .cloned()
-        .unwrap_or_default();
-
-    let (bus, receivers) = crabbybot_core::bus::MessageBus::new(100);
-    let bus_arc = Arc::new(bus);
-
-    // 1.5 Initialize betting engine state
-    let betting_state = Arc::new(tokio::sync::Mutex::new(
-        BettingState::new(config.tools.betting.clone()),
-    ));
-
-    let (agent, workspace, tools_arc) = setup_agent(
-        &config,
-        None,
-        Some(Arc::clone(&cron)),
-        Arc::clone(&bus_arc),
-        "telegram",
-        &default_chat_id,
-        Some(Arc::clone(&betting_state)),
-    )?;
-
-    let inbound_rx = receivers.inbound_rx;
-
-    let mut services = tokio::task::JoinSet::new();
-
     println!("  🦀 CrabbyBot bot mode starting...");
     println!(
         "  Active channels: Telegram: {}, Discord: {}",
         config.channels.telegram.as_ref().is_some_and(|c| c.enabled),
         config.channels.discord.as_ref().is_some_and(|c| c.enabled)
     );
-    {
-        let cron_locked = cron.lock().await;
-        println!("  Cron: {}", cron_locked.status());
+    if config.gateway.api.enabled {
+        println!("  REST API: http://{}:{}/v1", config.gateway.host, config.gateway.port);
     }
+    println!("  Cron: {}", CronService::new(&config.workspace_path()).status());
     println!("  Press Ctrl+C for graceful shutdown.");
     println!("  Betting: {}", if config.tools.betting.enabled { "🟢 ENABLED" } else { "🔴 DISABLED (use betting_control to start)" });
     println!("  ─────────────────────────────────────");
 
-    // 1. Start transports FIRST so they register their outbound subscribers
-    //    before the dispatch loop begins processing messages.
-
-    #[cfg(feature = "telegram")]
-    {
-        if let Some(ref tel_config) = config.channels.telegram {
-            if tel_config.enabled && !tel_config.token.is_empty() {
-                let bus_for_tel = Arc::clone(&bus_arc);
-                let allow_from = tel_config.allow_from.clone();
-                let transport =
-                    TelegramTransport::new(tel_config.token.clone(), bus_for_tel, allow_from, cancel.clone());
-                services.spawn(async move {
-                    if let Err(e) = transport.run().await {
-                        tracing::error!("Telegram transport failed: {}", e);
-                    }
-                });
-            }
-        }
-    }
-
-    #[cfg(feature = "discord")]
-    {
-        if let Some(ref disc_config) = config.channels.discord {
-            if disc_config.enabled && !disc_config.token.is_empty() {
-                let bus_for_disc = Arc::clone(&bus_arc);
-                let allow_from = disc_config.allow_from.clone();
-                let transport =
-                    DiscordTransport::new(disc_config.token.clone(), bus_for_disc, allow_from);
-                services.spawn(async move {
-                    if let Err(e) = transport.run().await {
-                        tracing::error!("Discord transport failed: {}", e);
-                    }
-                });
-            }
-        }
-    }
-
-    if services.is_empty() {
+    let mut bot = crabbybot_core::run_bot(config, cancel.clone()).await?;
+    print!("{}", bot.self_test.render());
+    println!("  ─────────────────────────────────────");
+    if bot.transports.is_empty() {
         println!("  ⚠️ No bot channels enabled. Please check your config.");
         return Ok(());
     }
 
-    // 2. Outbound Dispatcher — uses the shared subscriber map, no bus lock needed
-    let subs = bus_arc.subscribers();
-    services.spawn(async move {
-        crabbybot_core::bus::dispatch_outbound(subs, receivers.outbound_rx).await;
-    });
-
-    // 3. Agent Bridge Task — with CancellationToken for graceful shutdown
-    let bus_for_bridge = Arc::clone(&bus_arc);
-    let bridge = AgentBridge::new(
-        bus_for_bridge,
-        agent,
-        cancel.clone(),
-        Arc::clone(&cron),
-        workspace.clone(),
-    );
-    services.spawn(async move {
-        if let Err(e) = bridge.run(inbound_rx).await {
-            tracing::error!("Agent bridge failed: {}", e);
-        }
-    });
-
-    // 3.5 Betting Engine — spawns the autonomous scan/trade loop
-    {
-        let betting_tools = Arc::clone(&tools_arc);
-        let betting_st = Arc::clone(&betting_state);
-        services.spawn(async move {
-            let _ = BettingService::spawn(betting_st, betting_tools).await;
-        });
-    }
-
-    // 4. Cron Ticker — checks for due jobs every 30 seconds.
-    {
-        let cron_tick = Arc::clone(&cron);
-        let bus_tick = Arc::clone(&bus_arc);
-        let cancel_tick = cancel.clone();
-        services.spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-            loop {
-                tokio::select! {
-                    _ = cancel_tick.cancelled() => break,
-                    _ = interval.tick() => {
-                        let due_jobs = {
-                            let mut cron_locked = cron_tick.lock().await;
-                            cron_locked.get_due_jobs()
-                        };
-                        for job in due_jobs {
-                            tracing::info!(
-                                job_id = %job.id,
-                                job_name = %job.name,
-                                "Cron job fired"
-                            );
-                            if let Err(e) = bus_tick.inbound_sender().send(
-                                crabbybot_core::bus::events::InboundMessage {
-                                    channel: job.channel.clone(),
-                                    chat_id: job.chat_id.clone(),
-                                    user_id: "cron".to_string(),
-                                    content: job.message.clone(),
-                                    media: Vec::new(),
-                                    is_system: true,
-                                },
-                            ).await {
-                                tracing::error!("Failed to send cron job to bus: {}", e);
-                            }
-                        }
-                    }
-                }
-            }
-            tracing::info!("Cron ticker stopped");
-        });
-    }
-
     // Wait for cancel token, Ctrl+C, or for any critical service to exit unexpectedly.
     tokio::select! {
         _ = cancel.cancelled() => {
@@ -661,7 +560,7 @@ async fn cmd_bot_once(cancel: CancellationToken) -> Result<()> {
             tracing::info!("Shutdown signal received via Ctrl-C!");
             println!("\n  ⏳ Shutting down gracefully...");
         }
-        res = services.join_next() => {
+        res = bot.join_next() => {
             if let Some(Err(e)) = res {
                 tracing::error!("Critical service task panicked: {}", e);
             } else {
@@ -670,12 +569,8 @@ async fn cmd_bot_once(cancel: CancellationToken) -> Result<()> {
         }
     }
 
-    tracing::info!("Cancelling remaining tasks...");
-    cancel.cancel();
-    
     tracing::info!("Waiting for services to cleanly shutdown (max 2s)...");
-    // Give services a moment to clean up before hard-exiting.
-    let _ = tokio::time::timeout(std::time::Duration::from_secs(2), services.shutdown()).await;
+    bot.shutdown(std::time::Duration::from_secs(2)).await;
 
     tracing::info!("Shutdown complete!");
     println!("  ✅ Shutdown complete.");
@@ -691,16 +586,6 @@ async fn cmd_chat(session_key: &str, model_override: Option<&str>) -> Result<()>
     let model = model_override
         .unwrap_or(&config.agents.defaults.model)
         .to_string();
-    let (bus, _receivers) = crabbybot_core::bus::MessageBus::new(10);
-    let (mut agent, workspace, _tools_arc) = setup_agent(
-        &config,
-        model_override,
-        None,
-        Arc::new(bus),
-        "cli",
-        "direct",
-        None,
-    )?;
 
     // Print header
     println!();
@@ -719,56 +604,20 @@ async fn cmd_chat(session_key: &str, model_override: Option<&str>) -> Result<()>
     println!(
         "  Session: {} | Workspace: {}",
         session_key,
-        workspace.display()
+        config.workspace_path().display()
     );
     println!();
-    println!("  Type your message, or /quit to exit.");
+    println!("  Type your message, /help for commands, or /quit to exit.");
     println!("  ─────────────────────────────────────");
     println!();
 
-    // Interactive loop
-    let stdin = io::stdin();
-    loop {
-        print!("  \x1b[36m>\x1b[0m ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        stdin.read_line(&mut input)?;
-        let input = input.trim();
-
-        if input.is_empty() {
-            continue;
-        }
-
-        // Handle commands
-        match input {
-            "/quit" | "/exit" | "/q" => {
-                println!("  Goodbye! 👋");
-                break;
-            }
-            "/clear" => {
-                let mut mgr = SessionManager::new(&workspace);
-                let session = mgr.get_or_create(session_key);
-                session.clear();
-                println!("  Session cleared.");
-                continue;
-            }
-            "/status" => {
-                cmd_status()?;
-                continue;
-            }
-            _ => {}
-        }
-
-        // Process message — pass None because CLI doesn't need a bus for typing events
-        println!();
-        match agent.process(input, session_key, None).await {
-            Ok(response) => {
-                println!("  \x1b[32m{}\x1b[0m\n", response.content);
-            }
-            Err(e) => {
-                eprintln!("  \x1b[31mError: {}\x1b[0m\n", e);
-            }
+    let cancel = CancellationToken::new();
+    let repl = crabbybot_core::run_repl(config, session_key, model_override, cancel.clone());
+    tokio::select! {
+        res = repl => res??,
+        _ = tokio::signal::ctrl_c() => {
+            cancel.cancel();
+            println!("\n  Goodbye! 👋");
         }
     }
 
@@ -816,6 +665,9 @@ fn cmd_status() -> Result<()> {
 
     // Model
     println!("  Model:     {}", config.agents.defaults.model);
+    if config.tools.read_only {
+        println!("  Tools:     🔒 read-only (mutating tools are refused)");
+    }
 
     // Workspace
     let ws = config.workspace_path();
@@ -831,7 +683,7 @@ fn cmd_status() -> Result<()> {
     );
 
     // Sessions
-    let mgr = SessionManager::new(&ws);
+    let mgr = SessionManager::new(&ws).with_chat_workspaces(config.chat_workspaces());
     let sessions = mgr.list_sessions();
     println!("  Sessions:  {} saved", sessions.len());
 
@@ -839,16 +691,92 @@ fn cmd_status() -> Result<()> {
     let cron = CronService::new(&ws);
     println!("  Cron:      {}", cron.status());
 
+    // Token usage
+    let usage = UsageTracker::new(&ws, config.usage.clone());
+    let used = usage.today().total_tokens;
+    match usage.budget() {
+        Some(budget) => println!("  Tokens:    {} / {} today", used, budget),
+        None => println!("  Tokens:    {} today", used),
+    }
+
     println!();
     Ok(())
 }
 
+// ── Self-test ───────────────────────────────────────────────────────
+
+async fn cmd_self_test() -> Result<()> {
+    let config = Config::load()?;
+    println!("  🦀 CrabbyBot self-test\n");
+
+    let runtime = Runtime::from_config(config);
+    let report = selftest::run(
+        &runtime.config,
+        &runtime.client,
+        &runtime.tools,
+        SelfTestMode::Full,
+    )
+    .await;
+    println!("{}", report.render());
+
+    if !report.passed() {
+        anyhow::bail!("{} check(s) failed", report.failures().count());
+    }
+    println!("  ✅ All checks passed.");
+    Ok(())
+}
+
+// ── Config Commands ─────────────────────────────────────────────────
+
+fn cmd_config(action: ConfigCommands) -> Result<()> {
+    match action {
+        ConfigCommands::Set { key, value } => {
+            let value = match value {
+                Some(value) => value,
+                None => rpassword::prompt_password(format!("  Value for {}: ", key))?,
+            };
+            let setting = config::mutate(&key, &value)?;
+            println!("\n  \x1b[32m✓\x1b[0m {} = {}", setting.key, setting.value);
+            if setting.secret {
+                println!("    Stored encrypted.");
+            }
+            println!("    Saved to {}. Restart the bot to apply it.\n", config::target_path().display());
+        }
+        ConfigCommands::Get { key } => {
+            let setting = config::get(&key)?;
+            println!("{}", setting.value);
+        }
+        ConfigCommands::List => {
+            let settings = config::list()?;
+            let width = settings.iter().map(|s| s.key.len()).max().unwrap_or(0);
+            for setting in settings {
+                println!("{:<width$}  {}", setting.key, setting.value);
+            }
+        }
+        ConfigCommands::Reset { key } => {
+            let key = config::resolve_alias(&key);
+            if config::reset(key)? {
+                println!("\n  \x1b[32m✓\x1b[0m {} reset to its default\n", key);
+            } else {
+                println!("\n  {} was not set.\n", key);
+            }
+        }
+    }
+    Ok(())
+}
+
 // ── Cron Commands ───────────────────────────────────────────────────
 
 fn cmd_cron(action: CronCommands) -> Result<()> {
     let config = Config::load()?;
     let ws = config.workspace_path();
     let mut cron = CronService::new(&ws);
+    let default_timezone = || {
+        LocaleSettings::from_config(&config.agents)
+            .resolve("cli", "direct", None)
+            .timezone_name()
+            .map(String::from)
+    };
 
     match action {
         CronCommands::List => {
@@ -861,12 +789,21 @@ fn cmd_cron(action: CronCommands) -> Result<()> {
                     let status = if job.enabled { "✅" } else { "⏸️ " };
                     println!("  {} {} [{}]", status, job.name, job.id);
                     match &job.schedule {
-                        Schedule::Cron { expression } => println!("     Cron: {}", expression),
+                        Schedule::Cron { expression, timezone } => match timezone {
+                            Some(tz) => println!("     Cron: {} ({})", expression, tz),
+                            None => println!("     Cron: {}", expression),
+                        },
                         Schedule::Interval { seconds } => {
                             println!("     Every {} seconds", seconds)
                         }
                     }
-                    println!("     Message: {}", job.message);
+                    match &job.kind {
+                        JobKind::ToolCall(call) => println!("     Tool: {}", call.name),
+                        JobKind::Agent => println!("     Message: {}", job.message),
+                    }
+                    if job.critical {
+                        println!("     Critical: runs even when the token budget is low");
+                    }
                     if let Some(ref last) = job.last_run {
                         println!("     Last run: {}", last);
                     }
@@ -878,11 +815,18 @@ fn cmd_cron(action: CronCommands) -> Result<()> {
             name,
             schedule,
             message,
+            critical,
+            timezone,
         } => {
+            let timezone = timezone.or_else(default_timezone);
             let sched = Schedule::Cron {
                 expression: schedule,
+                timezone,
             };
             let id = cron.add_job(&name, sched, &message, "cli", "direct")?;
+            if critical {
+                cron.set_critical(&id, true)?;
+            }
             println!("  ✅ Job added: {} ({})", name, id);
         }
         CronCommands::Remove { id } => {
@@ -892,39 +836,619 @@ fn cmd_cron(action: CronCommands) -> Result<()> {
                 println!("  ❌ Job not found: {}", id);
             }
         }
+        CronCommands::Edit { id } => {
+            let Some(job) = cron.list_jobs(true).into_iter().find(|j| j.id == id).cloned() else {
+                anyhow::bail!("Job not found: {}", id);
+            };
+            // A fresh file only this user can read, not a guessable path
+            // someone else could have planted a link at.
+            let file = tempfile::Builder::new()
+                .prefix("crabbybot-cron-")
+                .suffix(".toml")
+                .tempfile()?;
+            let path = file.path().to_path_buf();
+            let mut text = cron_jobs::edit::to_toml(&job)?;
+            let edited = loop {
+                std::fs::write(&path, &text)?;
+                run_editor(&path)?;
+                text = std::fs::read_to_string(&path)?;
+                if text.lines().all(|l| l.trim().is_empty() || l.trim_start().starts_with('#')) {
+                    break None;
+                }
+                match cron_jobs::edit::from_toml(&text, &job) {
+                    Ok(edited) => break Some(edited),
+                    Err(e) => {
+                        println!("  ❌ {}", e);
+                        if !confirm("Edit again?")? {
+                            break None;
+                        }
+                    }
+                }
+            };
+            drop(file);
+            match edited {
+                Some(edited) => {
+                    cron.update_job(edited)?;
+                    println!("  ✅ Job updated: {}", id);
+                }
+                None => println!("  Job left unchanged."),
+            }
+        }
+        CronCommands::Enable { id } | CronCommands::Disable { id } if !cron.list_jobs(true).iter().any(|j| j.id == id) => {
+            println!("  ❌ Job not found: {}", id);
+        }
+        CronCommands::Enable { id } => {
+            cron.enable_job(&id, true)?;
+            println!("  ✅ Job enabled: {}", id);
+        }
+        CronCommands::Disable { id } => {
+            cron.enable_job(&id, false)?;
+            println!("  ⏸️  Job disabled: {}", id);
+        }
+        CronCommands::Export { output } => {
+            let jobs: Vec<_> = cron.list_jobs(true);
+            let json = serde_json::to_string_pretty(&jobs)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json + "\n")?;
+                    println!("  ✅ Exported {} job(s) to {}", jobs.len(), path.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        CronCommands::Import { file, channel, chat_id } => {
+            let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            // cron.json wraps the list in {"jobs": [...]}.
+            let list = match value {
+                serde_json::Value::Object(mut store) => store.remove("jobs").unwrap_or_default(),
+                list => list,
+            };
+            let mut jobs: Vec<cron_jobs::CronJob> = serde_json::from_value(list)?;
+            for job in &mut jobs {
+                if let Some(ref channel) = channel {
+                    job.channel = channel.clone();
+                }
+                if let Some(ref chat_id) = chat_id {
+                    job.chat_id = chat_id.clone();
+                }
+            }
+            let total = jobs.len();
+            let tool_jobs: Vec<(String, String, String)> = jobs
+                .iter()
+                .filter_map(|job| match &job.kind {
+                    JobKind::ToolCall(call) => Some((job.id.clone(), job.name.clone(), call.name.clone())),
+                    JobKind::Agent => None,
+                })
+                .collect();
+            let added = cron.import_jobs(jobs)?;
+            println!("  ✅ Imported {} job(s)", added.len());
+            if added.len() < total {
+                println!("     Skipped {} already in this workspace", total - added.len());
+            }
+            let disabled: Vec<_> = tool_jobs.iter().filter(|(id, ..)| added.contains(id)).collect();
+            if !disabled.is_empty() {
+                println!("\n  ⏸️  These jobs call tools directly and were imported disabled:");
+                for (id, name, tool) in disabled {
+                    println!("     {} ({}) runs {}", name, id, tool);
+                }
+                println!("     Check each one, then `crabbybot cron enable <id>`.");
+            }
+        }
+        CronCommands::Template { name: None, .. } => {
+            println!();
+            for template in cron_jobs::JOB_TEMPLATES {
+                println!("  {} — {}", template.name, template.summary);
+                println!("     Cron: {}", template.schedule);
+            }
+            println!("\n  Add one with `crabbybot cron template <name>`.");
+        }
+        CronCommands::Template {
+            name: Some(name),
+            timezone,
+        } => {
+            let Some(template) = cron_jobs::job_template(&name) else {
+                anyhow::bail!("No job template named '{}'. Run `crabbybot cron template` to list them.", name);
+            };
+            let timezone = timezone.or_else(default_timezone);
+            let id = cron.add_from_template(template, timezone, "cli", "direct")?;
+            println!("  ✅ Job added: {} ({})", template.name, id);
+        }
     }
 
     Ok(())
 }
 
+/// Open `path` in `$VISUAL` or `$EDITOR` (default `vi`) and wait for it
+/// to close.
+fn run_editor(path: &std::path::Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // Allow arguments, as in EDITOR="code --wait".
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Could not start {}: {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
 // ── Session Commands ────────────────────────────────────────────────
 
 fn cmd_sessions(action: Option<SessionCommands>) -> Result<()> {
     let config = Config::load()?;
     let ws = config.workspace_path();
-    let mut mgr = SessionManager::new(&ws);
+    let mut mgr = SessionManager::new(&ws).with_chat_workspaces(config.chat_workspaces());
 
     match action {
         Some(SessionCommands::Delete { key }) => {
-            if mgr.delete(&key) {
+            if mgr.purge(&key)? {
                 println!("  ✅ Session deleted: {}", key);
             } else {
                 println!("  ❌ Session not found: {}", key);
             }
         }
-        Some(SessionCommands::List) | None => {
-            let sessions = mgr.list_sessions();
-            if sessions.is_empty() {
-                println!("  No saved sessions.");
-            } else {
-                println!();
-                for (key, updated) in sessions {
-                    println!("  📝 {} (updated: {})", key, updated);
+        Some(SessionCommands::Fork { source, new_key }) => match mgr.fork(&source, &new_key) {
+            Ok(fork) => println!(
+                "  ✅ Forked {} → {} ({} messages)",
+                source,
+                new_key,
+                fork.messages.len()
+            ),
+            Err(e) => println!("  ❌ {}", e),
+        },
+        Some(SessionCommands::Purge { older_than, dry_run, yes }) => {
+            let Some(age) = crabbybot_core::alerts::parse_duration(&older_than) else {
+                anyhow::bail!("Invalid age '{}': use a number and s, m, h or d, e.g. 30d", older_than);
+            };
+            let stale = mgr.not_updated_for(age);
+            if stale.is_empty() {
+                println!("  No sessions older than {}.", older_than);
+                return Ok(());
+            }
+            println!("\n  Sessions not updated for {}:\n", older_than);
+            for (key, updated) in &stale {
+                let messages = mgr.get_or_create(key).messages.len();
+                println!("    {} (updated: {}, {} messages)", key, updated, messages);
+            }
+            if dry_run {
+                println!("\n  Dry run: {} session(s) would be deleted.\n", stale.len());
+                return Ok(());
+            }
+            if !yes && !confirm(&format!("Delete {} session(s)?", stale.len()))? {
+                println!("  Cancelled.\n");
+                return Ok(());
+            }
+            let mut deleted = 0;
+            for (key, _) in &stale {
+                if mgr.purge(key)? {
+                    deleted += 1;
+                }
+            }
+            println!("\n  ✅ Deleted {} session(s)\n", deleted);
+        }
+        Some(SessionCommands::Rename { old, new_key }) => match mgr.rename(&old, &new_key) {
+            Ok(_) => println!("  ✅ Renamed {} → {}", old, new_key),
+            Err(e) => println!("  ❌ {}", e),
+        },
+        Some(SessionCommands::Merge { into, from, dry_run, yes }) => {
+            if into == from {
+                anyhow::bail!("Cannot merge a session into itself");
+            }
+            for key in [&into, &from] {
+                if !mgr.exists(key) {
+                    println!("  ❌ Session not found: {}", key);
+                    return Ok(());
+                }
+            }
+            let kept = mgr.get_or_create(&into).messages.len();
+            let merged = mgr.get_or_create(&from).messages.len();
+            println!(
+                "\n  {} ({} messages) will be appended to {} ({} messages), in the order they started,",
+                from, merged, into, kept
+            );
+            println!("  and {} will be deleted.", from);
+            if dry_run {
+                println!("\n  Dry run: nothing changed.\n");
+                return Ok(());
+            }
+            if !yes && !confirm("Merge?")? {
+                println!("  Cancelled.\n");
+                return Ok(());
+            }
+            match mgr.merge(&into, &from) {
+                Ok(session) => println!(
+                    "\n  ✅ Merged {} into {} ({} messages)\n",
+                    from,
+                    into,
+                    session.messages.len()
+                ),
+                Err(e) => println!("  ❌ {}", e),
+            }
+        }
+        Some(SessionCommands::Search { query, topic, limit }) => {
+            if topic.is_some() {
+                mgr.tag_sessions()?;
+            }
+            let hits = mgr.search(&query, topic.as_deref(), limit);
+            if hits.is_empty() {
+                println!("  No messages matching '{}'.", query);
+                return Ok(());
+            }
+            println!();
+            for hit in &hits {
+                let icon = if hit.role == "user" { "👤" } else { "🤖" };
+                println!("  {} \x1b[1m{}\x1b[0m  {}", icon, hit.key, hit.timestamp);
+                println!("     {}\n", hit.snippet);
+            }
+            if hits.len() == limit {
+                println!("  Showing the first {} matches; pass --limit for more.\n", limit);
+            }
+        }
+        Some(SessionCommands::List { topic }) => list_sessions(&mut mgr, topic.as_deref())?,
+        None => list_sessions(&mut mgr, None)?,
+
+    }
+
+    Ok(())
+}
+
+/// Print the saved sessions, only those tagged with `topic` if given.
+fn list_sessions(mgr: &mut SessionManager, topic: Option<&str>) -> Result<()> {
+    if topic.is_some() {
+        mgr.tag_sessions()?;
+    }
+    let mut listed = 0;
+    for (key, updated) in mgr.list_sessions() {
+        let session = mgr.get_or_create(&key);
+        if topic.is_some_and(|t| !session.has_topic(t)) {
+            continue;
+        }
+        if listed == 0 {
+            println!();
+        }
+        listed += 1;
+        match &session.parent {
+            Some(parent) => println!("  🔀 {} (updated: {}, fork of {})", key, updated, parent),
+            None => println!("  📝 {} (updated: {})", key, updated),
+        }
+        if !session.topics.is_empty() || session.sentiment.is_some() {
+            let mut tags = session.topics.join(", ");
+            if let Some(sentiment) = &session.sentiment {
+                if !tags.is_empty() {
+                    tags.push_str(" · ");
+                }
+                tags.push_str(sentiment);
+            }
+            println!("     \x1b[2m{}\x1b[0m", tags);
+        }
+    }
+    match (listed, topic) {
+        (0, Some(topic)) => println!("  No sessions tagged '{}'.", topic),
+        (0, None) => println!("  No saved sessions."),
+        _ => println!(),
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on stdin; anything but "y" or "yes" is a no.
+fn confirm(question: &str) -> Result<bool> {
+    print!("\n  {} [y/N] ", question);
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// ── Tool Commands ───────────────────────────────────────────────────
+
+fn cmd_tools(action: ToolCommands) -> Result<()> {
+    let config = Config::load()?;
+    let ws = config.workspace_path();
+
+    match action {
+        ToolCommands::Stats { days } => {
+            let stats = ToolStats::load_recent(&ws, days);
+            println!();
+            for line in tool_stats::render(&stats).lines() {
+                println!("  {}", line);
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+// ── Models ──────────────────────────────────────────────────────────
+
+async fn cmd_models() -> Result<()> {
+    let config = Config::load()?;
+    let entry = config.providers.ollama.clone().unwrap_or_default();
+    let ollama = OllamaProvider::new(entry.api_base.as_deref(), "", reqwest::Client::new());
+    let models = ollama
+        .list_models()
+        .await
+        .map_err(|e| anyhow::anyhow!("Could not reach Ollama at {}: {}", ollama.base_url(), e))?;
+
+    println!("\n  Ollama models at {}\n", ollama.base_url());
+    if models.is_empty() {
+        println!("  None pulled yet. Try `ollama pull llama3.2`.\n");
+        return Ok(());
+    }
+    for model in &models {
+        let details = [model.details.parameter_size.as_str(), model.details.quantization_level.as_str()]
+            .into_iter()
+            .filter(|d| !d.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "  {:<32} {:>8.1} GB  {}",
+            model.name,
+            model.size as f64 / 1e9,
+            details
+        );
+    }
+    println!("\n  Use one with: crabbybot config set model ollama/<name>\n");
+    Ok(())
+}
+
+// ── Support Bundle ──────────────────────────────────────────────────
+
+fn cmd_support_bundle(output: Option<std::path::PathBuf>, yes: bool) -> Result<()> {
+    let config = Config::load()?;
+    let items = support::collect(&config);
+    let path = output.unwrap_or_else(|| support::default_file_name().into());
+
+    println!("\n  The support bundle will contain:\n");
+    for item in &items {
+        println!(
+            "    {:<36} {:>9}  {}",
+            item.name,
+            format!("{:.1} KB", item.content.len() as f64 / 1024.0),
+            item.description
+        );
+    }
+    println!("\n  Session histories, memory, and secrets are not included.");
+
+    if !yes {
+        print!("\n  Write {}? [y/N] ", path.display());
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("  Cancelled.\n");
+            return Ok(());
+        }
+    }
+
+    support::write_zip(&items, &path)?;
+    println!("\n  \x1b[32m✓\x1b[0m Wrote {}\n", path.display());
+    Ok(())
+}
+
+// ── Backup ──────────────────────────────────────────────────────────
+
+/// `CRABBYBOT_BACKUP_PASSPHRASE`, or asked for without echo (twice when
+/// creating a backup).
+fn backup_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("CRABBYBOT_BACKUP_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("  Backup passphrase: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("The passphrase must not be empty");
+    }
+    if confirm && rpassword::prompt_password("  Repeat passphrase: ")? != passphrase {
+        anyhow::bail!("The passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+fn cmd_backup(action: BackupCommands) -> Result<()> {
+    match action {
+        BackupCommands::Create { file, only } => {
+            let parts = backup::parse_parts(&only)?;
+            let config = Config::load()?;
+            let passphrase = backup_passphrase(true)?;
+            let manifest = backup::create(&Locations::current(&config), &parts, &passphrase, &file)?;
+            let names: Vec<&str> = manifest.parts.iter().map(|p| p.name()).collect();
+            println!(
+                "\n  \x1b[32m✓\x1b[0m Backed up {} files ({}) to {}\n",
+                manifest.files,
+                names.join(", "),
+                file.display()
+            );
+            println!("  Keep the passphrase safe: the backup cannot be opened without it.\n");
+        }
+        BackupCommands::Restore { file, only, yes } => {
+            let passphrase = backup_passphrase(false)?;
+            let mut backup = Backup::open(&file, &passphrase)?;
+            let wanted = backup::parse_parts(&only)?;
+            let parts: Vec<Part> = backup
+                .manifest
+                .parts
+                .iter()
+                .copied()
+                .filter(|p| wanted.contains(p))
+                .collect();
+            if parts.is_empty() {
+                println!("\n  The backup contains none of the requested parts.\n");
+                return Ok(());
+            }
+
+            let names: Vec<&str> = parts.iter().map(|p| p.name()).collect();
+            println!(
+                "\n  Backup from {} (CrabbyBot v{})",
+                backup.manifest.created_at.format("%Y-%m-%d %H:%M UTC"),
+                backup.manifest.version
+            );
+            println!("  Restoring: {}", names.join(", "));
+            println!("  Files in the backup replace the local copies.");
+            if !yes {
+                print!("\n  Continue? [y/N] ");
+                std::io::Write::flush(&mut std::io::stdout())?;
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                    println!("  Cancelled.\n");
+                    return Ok(());
                 }
-                println!();
             }
+
+            // The config goes first: it decides where the workspace is.
+            let mut restored = 0;
+            if parts.contains(&Part::Config) {
+                restored += backup.restore(Part::Config, &Locations::current(&Config::load()?))?;
+            }
+            let at = Locations::current(&Config::load()?);
+            for part in parts.iter().filter(|p| **p != Part::Config) {
+                restored += backup.restore(*part, &at)?;
+            }
+            println!("\n  \x1b[32m✓\x1b[0m Restored {} files\n", restored);
+        }
+    }
+    Ok(())
+}
+
+// ── Sync ────────────────────────────────────────────────────────────
+
+#[cfg(feature = "sync")]
+async fn cmd_sync() -> Result<()> {
+    let config = Config::load()?;
+    if config.sync.url.is_empty() {
+        anyhow::bail!("Sync is not set up. Set sync.url, sync.encryptionPassword and the backend credentials first");
+    }
+    let mut engine = crabbybot_core::sync::SyncEngine::from_config(
+        &config.sync,
+        &config.workspace_path(),
+        reqwest::Client::new(),
+    )?;
+    let report = engine.run().await?;
+    println!("\n  \x1b[32m✓\x1b[0m {}", report.summary());
+    if !report.conflicts.is_empty() {
+        println!("\n  \x1b[33m⚠\x1b[0m Changed here and on another machine. This copy was kept; the other was saved as:");
+        for copy in &report.conflicts {
+            println!("    {}", copy);
         }
     }
+    println!();
+    Ok(())
+}
+
+// ── Telemetry ───────────────────────────────────────────────────────
 
+async fn cmd_telemetry(action: TelemetryCommands) -> Result<()> {
+    use crabbybot_core::telemetry::Telemetry;
+
+    let config = Config::load()?;
+    let telemetry = Telemetry::new(&config.workspace_path(), config.telemetry.clone());
+    let day = Telemetry::last_day();
+    match action {
+        TelemetryCommands::Preview => {
+            let (report, note) = match telemetry.sent(day) {
+                Some(report) => (report, format!("Sent to {}", telemetry.endpoint())),
+                None if telemetry.is_enabled() => {
+                    (telemetry.report(day), format!("Will be sent to {}", telemetry.endpoint()))
+                }
+                None => (telemetry.report(day), "Telemetry is off; nothing is sent".to_string()),
+            };
+            println!("\n  {} — report for {}:\n", note, day);
+            println!("{}\n", serde_json::to_string_pretty(&report)?);
+        }
+        TelemetryCommands::Send => {
+            telemetry.send(&reqwest::Client::new(), day).await?;
+            println!("\n  \x1b[32m✓\x1b[0m Sent the report for {} to {}\n", day, telemetry.endpoint());
+        }
+    }
+    Ok(())
+}
+
+// ── Attach Command ──────────────────────────────────────────────────
+
+#[cfg(feature = "attach")]
+async fn cmd_attach(url: Option<String>, token: Option<String>, session: &str) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let url = url.unwrap_or_else(|| format!("ws://{}:{}", config.gateway.host, config.gateway.port));
+    let token = token
+        .or_else(|| std::env::var("CRABBYBOT_ATTACH_TOKEN").ok())
+        .or_else(|| config.channels.webchat.as_ref().map(|w| w.token.clone()))
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("No token. Pass --token or set CRABBYBOT_ATTACH_TOKEN"))?;
+
+    println!();
+    println!("  🦀 CrabbyBot v{} — attached", env!("CARGO_PKG_VERSION"));
+    println!("  Bot: {} | Session: webchat:{}", url, session);
+    println!();
+    println!("  Type your message, /help for commands, or /quit to exit.");
+    println!("  ─────────────────────────────────────");
+    println!();
+
+    let cancel = CancellationToken::new();
+    let chat = crabbybot_core::run_attach(&url, &token, session, cancel.clone());
+    tokio::select! {
+        res = chat => res??,
+        _ = tokio::signal::ctrl_c() => {
+            cancel.cancel();
+            println!("\n  Goodbye! 👋");
+        }
+    }
+
+    Ok(())
+}
+
+// ── Self Update ─────────────────────────────────────────────────────
+
+async fn cmd_self_update(channel: Option<&str>, check: bool, rollback: bool) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    if rollback {
+        update::rollback(&exe)?;
+        let _ = std::fs::remove_file(Config::config_dir().join("update.json"));
+        println!("\n  ✅ Restored the previous binary at {}\n", exe.display());
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let channel: Channel = channel.unwrap_or(&config.update.channel).parse()?;
+    let Some(release) = update::check(&config.update, channel).await? else {
+        println!("\n  ✅ CrabbyBot v{} is up to date ({:?})\n", env!("CARGO_PKG_VERSION"), channel);
+        return Ok(());
+    };
+    println!(
+        "\n  Update available: {} (running v{})",
+        release.tag_name,
+        env!("CARGO_PKG_VERSION")
+    );
+    if check {
+        println!("  Run `crabbybot self-update` to install it.\n");
+        return Ok(());
+    }
+
+    println!("  Downloading {}...", update::asset_name());
+    let binary = update::download(&release, &config.update).await?;
+    println!("  Checksum and signature verified");
+
+    update::install(&binary, &exe)?;
+    if !update::probe(&exe) {
+        update::rollback(&exe)?;
+        anyhow::bail!("{} failed to start; the previous binary was restored", release.tag_name);
+    }
+    InstalledRelease {
+        tag: release.tag_name.clone(),
+        published_at: release.published_at,
+    }
+    .save()?;
+    println!(
+        "\n  \x1b[32m✓\x1b[0m Installed {}. Restart running bots to pick it up; \
+         `crabbybot self-update --rollback` restores the previous binary.\n",
+        release.tag_name
+    );
     Ok(())
 }
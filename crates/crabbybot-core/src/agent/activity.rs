@@ -0,0 +1,192 @@
+//! Human-readable activity log per session.
+//!
+//! Every turn, tool call, tool result, progress update, and final reply is
+//! appended to `logs/<session>.md` in the workspace, so unattended cron and
+//! heartbeat runs can be audited afterwards without digging through traces.
+//! Chats with a workspace of their own keep their logs there.
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Longest tool argument or result excerpt written to the log, in chars.
+const MAX_EXCERPT_CHARS: usize = 500;
+
+/// One event in a session's activity.
+#[derive(Debug, Clone, Copy)]
+pub enum Activity<'a> {
+    /// A message arrived. `user_id` is empty for system-triggered turns.
+    TurnStarted { user_id: &'a str, content: &'a str },
+    /// Text the model wrote alongside its tool calls.
+    Thinking(&'a str),
+    ToolCall { name: &'a str, args: &'a Map<String, Value> },
+    ToolResult { name: &'a str, result: &'a str, elapsed: Duration },
+    /// A progress update pushed by a tool.
+    Progress(&'a str),
+    /// The request outgrew the context window and `summarized` messages
+    /// from the middle of it were replaced by a summary.
+    Compressed { summarized: usize, tokens_before: usize, tokens_after: usize },
+    Reply(&'a str),
+    Failed(&'a str),
+}
+
+/// Appends [`Activity`] entries to per-session markdown files.
+#[derive(Debug, Clone)]
+pub struct ActivityLog {
+    dir: PathBuf,
+    /// Log directories of chats with their own workspace, by `channel:chat_id`.
+    chat_dirs: Arc<HashMap<String, PathBuf>>,
+}
+
+impl ActivityLog {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join("logs"),
+            chat_dirs: Arc::default(),
+        }
+    }
+
+    /// Keep the logs of these chats (`channel:chat_id`) in `logs/` of their
+    /// own workspace.
+    pub fn with_chat_workspaces(mut self, workspaces: impl IntoIterator<Item = (String, PathBuf)>) -> Self {
+        let dirs = workspaces.into_iter().map(|(chat, workspace)| (chat, workspace.join("logs")));
+        self.chat_dirs = Arc::new(dirs.collect());
+        self
+    }
+
+    /// Log file for a session; characters unsafe in file names become `_`.
+    pub fn path(&self, session_key: &str) -> PathBuf {
+        let name: String = session_key
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        let (channel, chat_id) = crate::session::chat_of(session_key);
+        let dir = self.chat_dirs.get(&format!("{}:{}", channel, chat_id)).unwrap_or(&self.dir);
+        dir.join(format!("{}.md", name))
+    }
+
+    /// Append an entry. Failures are logged and otherwise ignored; the
+    /// activity log must never break a turn.
+    pub fn record(&self, session_key: &str, activity: Activity<'_>) {
+        if let Err(e) = self.append(session_key, &render(&activity)) {
+            debug!(session = session_key, "Failed to write activity log: {}", e);
+        }
+    }
+
+    fn append(&self, session_key: &str, entry: &str) -> std::io::Result<()> {
+        let path = self.path(session_key);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        // One write per entry so concurrent tool calls don't interleave.
+        file.write_all(entry.as_bytes())
+    }
+}
+
+fn render(activity: &Activity<'_>) -> String {
+    let now = Utc::now();
+    let time = now.format("%H:%M:%S");
+    match *activity {
+        Activity::TurnStarted { user_id, content } => {
+            let from = if user_id.is_empty() { "system" } else { user_id };
+            format!(
+                "\n## {} UTC — message from {}\n\n{}\n\n",
+                now.format("%Y-%m-%d %H:%M:%S"),
+                from,
+                quote(content)
+            )
+        }
+        Activity::Thinking(text) => format!("- `{}` 💭 {}\n", time, one_line(text)),
+        Activity::ToolCall { name, args } => {
+            let args = serde_json::to_string(args).unwrap_or_default();
+            format!("- `{}` 🔧 **{}** `{}`\n", time, name, excerpt(&args).replace('`', "'"))
+        }
+        Activity::ToolResult { name, result, elapsed } => {
+            let icon = if crate::tools::stats::is_error_output(result) { "❌" } else { "✅" };
+            format!(
+                "- `{}` {} **{}** ({} ms)\n{}\n",
+                time,
+                icon,
+                name,
+                elapsed.as_millis(),
+                indent(&quote(&excerpt(result)))
+            )
+        }
+        Activity::Progress(text) => format!("- `{}` ⚙️ {}\n", time, one_line(text)),
+        Activity::Compressed { summarized, tokens_before, tokens_after } => format!(
+            "- `{}` 🗜️ Summarized {} messages from the middle of the context (~{} → ~{} tokens)\n",
+            time, summarized, tokens_before, tokens_after
+        ),
+        Activity::Reply(text) => format!("- `{}` 💬 Reply:\n{}\n", time, indent(&quote(text))),
+        Activity::Failed(error) => format!("- `{}` ⚠️ Failed: {}\n", time, one_line(error)),
+    }
+}
+
+fn excerpt(text: &str) -> String {
+    let mut out: String = text.chars().take(MAX_EXCERPT_CHARS).collect();
+    if out.len() < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+fn one_line(text: &str) -> String {
+    excerpt(text.trim()).replace('\n', " ")
+}
+
+fn quote(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|l| format!("> {}", l).trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|l| format!("  {}", l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_log_appends_markdown() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_activity");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let log = ActivityLog::new(&tmp);
+
+        let args: Map<String, Value> = serde_json::from_str(r#"{"url":"https://x.io"}"#).unwrap();
+        log.record("telegram:-100", Activity::TurnStarted { user_id: "", content: "hourly check" });
+        log.record("telegram:-100", Activity::ToolCall { name: "web_fetch", args: &args });
+        log.record(
+            "telegram:-100",
+            Activity::ToolResult {
+                name: "web_fetch",
+                result: &format!("Error: timeout\n{}", "x".repeat(600)),
+                elapsed: Duration::from_millis(1200),
+            },
+        );
+        log.record("telegram:-100", Activity::Reply("All quiet."));
+
+        let path = log.path("telegram:-100");
+        assert_eq!(path.file_name().unwrap(), "telegram_-100.md");
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.contains("message from system\n\n> hourly check"), "{text}");
+        assert!(text.contains("🔧 **web_fetch** `{\"url\":\"https://x.io\"}`"), "{text}");
+        assert!(text.contains("❌ **web_fetch** (1200 ms)\n  > Error: timeout"), "{text}");
+        assert!(text.contains("…"));
+        assert!(text.contains("💬 Reply:\n  > All quiet."));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,241 @@
+//! Middle-out compression of an oversized request.
+//!
+//! When history plus this turn's tool results outgrow the model's window,
+//! the request is split in three: the system prompt and the first exchange
+//! (the conversation's framing), the most recent turns (what the model is
+//! working on), and everything in between. Only the middle is replaced, by
+//! a single summary message; dropping from the front alone would lose the
+//! framing first.
+//!
+//! Cuts fall on user messages, so no tool result is separated from the
+//! assistant call it answers. The current turn is always kept whole.
+
+use std::collections::BTreeSet;
+
+use crate::provider::types::ChatMessage;
+
+/// User turns kept verbatim at the tail, the current one included.
+pub const KEEP_RECENT_TURNS: usize = 2;
+
+/// Longest excerpt of one message handed to the summarizer, in chars.
+const MESSAGE_EXCERPT_CHARS: usize = 1_500;
+
+/// Longest transcript handed to the summarizer, in chars.
+const TRANSCRIPT_CHARS: usize = 24_000;
+
+/// Estimated tokens of one image, about what a chart costs on the
+/// vision models.
+pub const IMAGE_TOKENS: usize = 1_000;
+
+/// Instructions for the summary that replaces the middle.
+pub const SUMMARY_PROMPT: &str = "Summarize this excerpt of a conversation between a user and an \
+     assistant so the assistant can continue without it. Keep facts, numbers, addresses, \
+     decisions and open questions; drop pleasantries. Reply with the summary only.";
+
+/// Instructions for the running summary of history that no longer fits the
+/// budget; the previous summary, if any, comes first in the text.
+pub const HISTORY_SUMMARY_PROMPT: &str = "Update the summary of the earlier part of a conversation \
+     between a user and an assistant with the messages that follow it, so the assistant can \
+     continue without them. Keep facts, numbers, addresses, decisions, preferences and open \
+     questions; drop pleasantries. Reply with the updated summary only, in under 300 words.";
+
+/// Rough token count of a request, at the four chars per token the
+/// history budget uses. Images count as [`IMAGE_TOKENS`] each rather than
+/// by the length of their encoding.
+pub fn estimate_tokens(messages: &[ChatMessage]) -> usize {
+    let chars: usize = messages
+        .iter()
+        .map(|m| {
+            let content = match &m.content {
+                Some(serde_json::Value::String(s)) => s.len(),
+                Some(serde_json::Value::Array(_)) => {
+                    m.text().map_or(0, |t| t.len()) + m.image_count() * IMAGE_TOKENS * 4
+                }
+                Some(other) => other.to_string().len(),
+                None => 0,
+            };
+            let calls: usize = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|c| c.function.name.len() + c.function.arguments.len())
+                .sum();
+            content + calls
+        })
+        .sum();
+    chars / 4
+}
+
+/// Which messages of a request survive compression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// System prompt, first user message and its final reply.
+    head: Vec<usize>,
+    /// Messages folded into the summary.
+    middle: Vec<usize>,
+    /// `messages[tail..]` is kept verbatim.
+    tail: usize,
+}
+
+impl Plan {
+    /// Split `messages`, whose current turn starts at `turn_start`. `None`
+    /// when there is no middle worth summarizing.
+    pub fn new(messages: &[ChatMessage], turn_start: usize) -> Option<Self> {
+        let turn_start = turn_start.min(messages.len().checked_sub(1)?);
+        let users: Vec<usize> = (1..=turn_start).filter(|&i| messages[i].role == "user").collect();
+        let first = *users.first()?;
+        let first_end = users.get(1).copied().unwrap_or(turn_start);
+
+        // The first exchange is its question and the last plain answer to
+        // it; tool traffic in between goes to the summary.
+        let mut head = vec![0, first];
+        head.extend((first + 1..first_end).rev().find(|&i| {
+            messages[i].role == "assistant" && messages[i].tool_calls.as_ref().is_none_or(|c| c.is_empty())
+        }));
+        head.dedup();
+
+        let recent = users[users.len().saturating_sub(KEEP_RECENT_TURNS)..]
+            .iter()
+            .copied()
+            .find(|&i| i >= first_end)?;
+        let kept: BTreeSet<usize> = head.iter().copied().collect();
+        let middle: Vec<usize> = (first + 1..recent).filter(|i| !kept.contains(i)).collect();
+        (middle.len() >= 2).then_some(Self {
+            head,
+            middle,
+            tail: recent,
+        })
+    }
+
+    /// Number of messages the summary replaces.
+    pub fn summarized(&self) -> usize {
+        self.middle.len()
+    }
+
+    /// The middle as a `role: text` transcript for the summarizer, cut to
+    /// a bounded length.
+    pub fn transcript(&self, messages: &[ChatMessage]) -> String {
+        transcript(self.middle.iter().map(|&i| &messages[i]))
+    }
+
+    /// The compressed request and where its current turn now starts.
+    pub fn apply(&self, messages: &[ChatMessage], turn_start: usize, summary: &str) -> (Vec<ChatMessage>, usize) {
+        let mut out: Vec<ChatMessage> = self.head.iter().map(|&i| messages[i].clone()).collect();
+        out.push(ChatMessage::user(&format!(
+            "[Summary of {} earlier messages, condensed to fit the context window]\n{}",
+            self.middle.len(),
+            summary.trim()
+        )));
+        let turn_start = out.len() + turn_start.saturating_sub(self.tail);
+        out.extend(messages[self.tail..].iter().cloned());
+        (out, turn_start)
+    }
+}
+
+/// `messages` as a `role: text` transcript for the summarizer, cut to a
+/// bounded length.
+pub fn transcript<'a>(messages: impl IntoIterator<Item = &'a ChatMessage>) -> String {
+    let mut out = String::new();
+    for m in messages {
+        let text = match (&m.content, &m.tool_calls) {
+            (_, Some(calls)) if !calls.is_empty() => calls
+                .iter()
+                .map(|c| format!("calls {}({})", c.function.name, c.function.arguments))
+                .collect::<Vec<_>>()
+                .join("; "),
+            (Some(serde_json::Value::String(s)), _) => s.clone(),
+            (Some(serde_json::Value::Array(_)), _) => {
+                let images = "[image] ".repeat(m.image_count());
+                format!("{}{}", images, m.text().unwrap_or_default())
+            }
+            (Some(other), _) => other.to_string(),
+            (None, _) => continue,
+        };
+        let role = m.name.as_deref().filter(|_| m.role == "tool").unwrap_or(&m.role);
+        let mut excerpt: String = text.chars().take(MESSAGE_EXCERPT_CHARS).collect();
+        if excerpt.len() < text.len() {
+            excerpt.push('…');
+        }
+        out.push_str(&format!("{}: {}\n\n", role, excerpt.trim()));
+        if out.len() > TRANSCRIPT_CHARS {
+            out.push_str("[…]");
+            break;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::types::{FunctionCall, ToolCallMessage};
+
+    fn call(id: &str) -> ChatMessage {
+        ChatMessage::assistant_with_tool_calls(
+            None,
+            vec![ToolCallMessage {
+                id: id.into(),
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: "price".into(),
+                    arguments: "{}".into(),
+                },
+            }],
+        )
+    }
+
+    fn conversation() -> Vec<ChatMessage> {
+        vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("q1"),
+            call("c1"),
+            ChatMessage::tool_result("c1", "price", "SOL 150"),
+            ChatMessage::assistant("a1"),
+            ChatMessage::user("q2"),
+            ChatMessage::assistant("a2"),
+            ChatMessage::user("q3"),
+            ChatMessage::assistant("a3"),
+            ChatMessage::user("q4"),
+            ChatMessage::assistant("a4"),
+            ChatMessage::user("q5"),
+            call("c2"),
+            ChatMessage::tool_result("c2", "price", "SOL 160"),
+        ]
+    }
+
+    #[test]
+    fn test_plan_keeps_head_and_tail() {
+        let messages = conversation();
+        let plan = Plan::new(&messages, 11).unwrap();
+        assert_eq!(plan.summarized(), 6);
+        let transcript = plan.transcript(&messages);
+        assert!(transcript.starts_with("assistant: calls price({})"), "{}", transcript);
+        assert!(transcript.contains("price: SOL 150") && transcript.contains("q3"));
+        assert!(!transcript.contains("q4"));
+
+        let (out, turn_start) = plan.apply(&messages, 11, "they asked about SOL");
+        let texts: Vec<_> = out.iter().map(|m| m.content_as_str().unwrap_or(&m.role)).collect();
+        assert_eq!(texts[..3], ["sys", "q1", "a1"]);
+        assert!(texts[3].ends_with("they asked about SOL"));
+        assert_eq!(texts[4..7], ["q4", "a4", "q5"]);
+        assert_eq!(turn_start, 6);
+        assert_eq!(out[7].role, "assistant");
+        assert_eq!(out[8].role, "tool");
+    }
+
+    #[test]
+    fn test_plan_needs_a_middle() {
+        let messages = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("q1"),
+            ChatMessage::assistant("a1"),
+            ChatMessage::user("q2"),
+            ChatMessage::assistant("a2"),
+            ChatMessage::user("q3"),
+        ];
+        // Only the first exchange precedes the recent turns.
+        assert!(Plan::new(&messages, 5).is_none());
+        assert!(Plan::new(&messages[..2], 1).is_none());
+        assert_eq!(estimate_tokens(&messages), 3);
+    }
+}
@@ -0,0 +1,283 @@
+//! Contact book linking one person's identities across channels.
+//!
+//! An identity is `channel:user_id` (e.g. `telegram:123`, `discord:456`).
+//! Linked identities resolve to the identity that started the link, so
+//! per-user state such as [`ProfileStore`] profiles and per-user sessions
+//! follows the person rather than the platform account. Links are proven
+//! with a short code: `/link` on one account issues it, `/link <code>` on
+//! the other redeems it. An account that sends [`MAX_FAILED_ATTEMPTS`]
+//! wrong codes is locked out for [`LOCKOUT_MINUTES`], so a code can't be
+//! guessed within its lifetime. The book lives in `contacts.json` in the
+//! workspace and, like profiles, is read from disk on every access.
+//!
+//! [`ProfileStore`]: crate::agent::profile::ProfileStore
+
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// How long a link code stays valid.
+const CODE_TTL_MINUTES: i64 = 10;
+
+/// Wrong codes an account may send before it is locked out.
+pub const MAX_FAILED_ATTEMPTS: u32 = 5;
+
+/// How long a lockout lasts, and the window failed attempts count in.
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+/// Why a link code could not be redeemed.
+#[derive(Debug, thiserror::Error)]
+pub enum LinkError {
+    #[error("unknown link code")]
+    UnknownCode,
+
+    #[error("link code expired")]
+    Expired,
+
+    #[error("the code was issued to this same account")]
+    SameIdentity,
+
+    #[error("too many wrong codes, try again after {} UTC", .0.format("%H:%M"))]
+    LockedOut(DateTime<Utc>),
+
+    #[error("can't read {path}: {source}")]
+    Corrupt {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("contact book I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingLink {
+    identity: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Wrong codes one account sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Failures {
+    count: u32,
+    /// When the first of them was sent.
+    since: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Contacts {
+    /// Linked identity → the identity it resolves to.
+    links: BTreeMap<String, String>,
+    /// Issued codes that have not been redeemed yet.
+    pending: BTreeMap<String, PendingLink>,
+    /// Recent wrong codes by the identity that sent them.
+    failures: BTreeMap<String, Failures>,
+}
+
+/// `channel:user_id`.
+pub fn identity(channel: &str, user_id: &str) -> String {
+    format!("{}:{}", channel, user_id)
+}
+
+/// File-backed book of linked identities.
+pub struct ContactBook {
+    path: PathBuf,
+}
+
+impl ContactBook {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            path: workspace.join("contacts.json"),
+        }
+    }
+
+    /// The book. A file that doesn't parse is an error rather than an empty
+    /// book, so it is never overwritten.
+    fn load(&self) -> Result<Contacts, LinkError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Contacts::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&content).map_err(|source| LinkError::Corrupt {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    fn save(&self, contacts: &Contacts) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(contacts)?)
+    }
+
+    /// The identity `channel:user_id` resolves to: the person's primary
+    /// identity when linked, otherwise itself. A book that can't be read is
+    /// logged and treated as having no links.
+    pub fn resolve(&self, channel: &str, user_id: &str) -> String {
+        let id = identity(channel, user_id);
+        match self.load() {
+            Ok(mut contacts) => contacts.links.remove(&id).unwrap_or(id),
+            Err(e) => {
+                warn!(identity = %id, "Ignoring linked accounts: {}", e);
+                id
+            }
+        }
+    }
+
+    /// The user id a per-user store on `channel` files `user_id` under: the
+    /// primary identity's user id when that is on the same channel, the
+    /// whole primary identity when it is elsewhere, and `user_id` itself
+    /// when it isn't linked.
+    pub fn member(&self, channel: &str, user_id: &str) -> String {
+        let primary = self.resolve(channel, user_id);
+        match primary.strip_prefix(channel).and_then(|rest| rest.strip_prefix(':')) {
+            Some(id) => id.to_string(),
+            None => primary,
+        }
+    }
+
+    /// Issue a six-digit code that links another account to this one.
+    pub fn start_link(&self, channel: &str, user_id: &str) -> Result<String, LinkError> {
+        let mut contacts = self.load()?;
+        let now = Utc::now();
+        contacts.pending.retain(|_, p| p.expires_at > now);
+        let code = loop {
+            let code = format!("{:06}", rand::thread_rng().gen_range(0..1_000_000));
+            if !contacts.pending.contains_key(&code) {
+                break code;
+            }
+        };
+        contacts.pending.insert(
+            code.clone(),
+            PendingLink {
+                identity: identity(channel, user_id),
+                expires_at: now + Duration::minutes(CODE_TTL_MINUTES),
+            },
+        );
+        self.save(&contacts)?;
+        Ok(code)
+    }
+
+    /// Redeem `code` from `channel:user_id`, linking it to the identity the
+    /// code was issued to. Identities already linked to the redeeming one
+    /// move along with it. Returns the primary identity.
+    pub fn complete_link(&self, code: &str, channel: &str, user_id: &str) -> Result<String, LinkError> {
+        let mut contacts = self.load()?;
+        let now = Utc::now();
+        let me = identity(channel, user_id);
+        let window = Duration::minutes(LOCKOUT_MINUTES);
+        contacts.failures.retain(|_, f| f.since + window > now);
+        if let Some(f) = contacts.failures.get(&me).filter(|f| f.count >= MAX_FAILED_ATTEMPTS) {
+            return Err(LinkError::LockedOut(f.since + window));
+        }
+        let Some(pending) = contacts.pending.remove(code.trim()) else {
+            let f = contacts.failures.entry(me).or_insert(Failures { count: 0, since: now });
+            f.count += 1;
+            self.save(&contacts)?;
+            return Err(LinkError::UnknownCode);
+        };
+        contacts.failures.remove(&me);
+        if pending.expires_at <= now {
+            self.save(&contacts)?;
+            return Err(LinkError::Expired);
+        }
+
+        let resolve = |c: &Contacts, id: &str| c.links.get(id).cloned().unwrap_or_else(|| id.to_string());
+        let primary = resolve(&contacts, &pending.identity);
+        let joining = resolve(&contacts, &identity(channel, user_id));
+        if joining == primary {
+            return Err(LinkError::SameIdentity);
+        }
+        for target in contacts.links.values_mut() {
+            if *target == joining {
+                *target = primary.clone();
+            }
+        }
+        contacts.links.insert(joining, primary.clone());
+        contacts.links.insert(identity(channel, user_id), primary.clone());
+        contacts.links.remove(&primary);
+        self.save(&contacts)?;
+        Ok(primary)
+    }
+
+    /// Detach `channel:user_id` from whoever it is linked to. Returns
+    /// whether it was linked.
+    pub fn unlink(&self, channel: &str, user_id: &str) -> Result<bool, LinkError> {
+        let mut contacts = self.load()?;
+        let removed = contacts.links.remove(&identity(channel, user_id)).is_some();
+        if removed {
+            self.save(&contacts)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_and_resolve() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_contacts_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let book = ContactBook::new(&tmp);
+        assert_eq!(book.resolve("discord", "9"), "discord:9");
+
+        let code = book.start_link("telegram", "1").unwrap();
+        assert_eq!(code.len(), 6);
+        assert!(matches!(
+            book.complete_link(&code, "telegram", "1"),
+            Err(LinkError::SameIdentity)
+        ));
+
+        let code = book.start_link("telegram", "1").unwrap();
+        assert_eq!(book.complete_link(&code, "discord", "9").unwrap(), "telegram:1");
+        assert_eq!(book.resolve("discord", "9"), "telegram:1");
+        assert_eq!(book.member("discord", "9"), "telegram:1");
+        assert_eq!(book.member("telegram", "1"), "1");
+        assert!(matches!(
+            book.complete_link(&code, "discord", "9"),
+            Err(LinkError::UnknownCode)
+        ));
+
+        // Linking a third account through the second joins the same person.
+        let code = book.start_link("discord", "9").unwrap();
+        book.complete_link(&code, "webchat", "abc").unwrap();
+        assert_eq!(book.resolve("webchat", "abc"), "telegram:1");
+
+        assert!(book.unlink("discord", "9").unwrap());
+        assert_eq!(book.resolve("discord", "9"), "discord:9");
+        assert!(!book.unlink("discord", "9").unwrap());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_wrong_codes_lock_out() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_contacts_lockout_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let book = ContactBook::new(&tmp);
+        let code = book.start_link("telegram", "1").unwrap();
+        let wrong = if code == "000000" { "000001" } else { "000000" };
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert!(matches!(book.complete_link(wrong, "discord", "9"), Err(LinkError::UnknownCode)));
+        }
+        // Even the right code is refused now, but only for that account.
+        assert!(matches!(book.complete_link(&code, "discord", "9"), Err(LinkError::LockedOut(_))));
+        assert_eq!(book.complete_link(&code, "webchat", "abc").unwrap(), "telegram:1");
+
+        // A book that doesn't parse is reported and left alone.
+        std::fs::write(&book.path, "{ not json").unwrap();
+        assert!(matches!(book.start_link("telegram", "1"), Err(LinkError::Corrupt { .. })));
+        assert_eq!(book.resolve("webchat", "abc"), "webchat:abc");
+        assert_eq!(std::fs::read_to_string(&book.path).unwrap(), "{ not json");
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -5,9 +5,23 @@
 
 use std::path::Path;
 
+use chrono::{DateTime, Utc};
+
+use crate::agent::locale::ChatLocale;
 use crate::agent::memory::MemoryStore;
+use crate::agent::profile::UserProfile;
 use crate::agent::skills::SkillsLoader;
 use crate::provider::types::ChatMessage;
+use crate::session::SessionMessage;
+
+/// Gap after which the model is told not to treat the conversation as
+/// continuous.
+const RESUME_GAP_HOURS: i64 = 6;
+
+/// User messages at the end of the history that get an age prefix. The
+/// prefixes change every turn, so older messages go without to keep the
+/// start of the conversation the same for provider prompt caching.
+const AGED_USER_MESSAGES: usize = 3;
 
 /// Builds the context (system prompt + messages) for the agent.
 pub struct ContextBuilder<'a> {
@@ -17,6 +31,11 @@ pub struct ContextBuilder<'a> {
     channel: String,
     chat_id: String,
     service_status: String,
+    user_profile: Option<UserProfile>,
+    locale: ChatLocale,
+    language_hint: Option<String>,
+    persona: Option<String>,
+    earlier: Option<String>,
 }
 
 impl<'a> ContextBuilder<'a> {
@@ -35,9 +54,46 @@ impl<'a> ContextBuilder<'a> {
             channel: channel.to_string(),
             chat_id: chat_id.to_string(),
             service_status: service_status.to_string(),
+            user_profile: None,
+            locale: ChatLocale::default(),
+            language_hint: None,
+            persona: None,
+            earlier: None,
         }
     }
 
+    /// Include the sending user's profile in the system prompt.
+    pub fn with_user_profile(mut self, profile: Option<UserProfile>) -> Self {
+        self.user_profile = profile.filter(|p| !p.is_empty());
+        self
+    }
+
+    /// Show dates in this timezone and locale instead of server local time.
+    pub fn with_locale(mut self, locale: ChatLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Tell the model which language to reply in (see
+    /// [`ChatLocale::language_hint`]).
+    pub fn with_language_hint(mut self, hint: Option<String>) -> Self {
+        self.language_hint = hint;
+        self
+    }
+
+    /// Open the system prompt with this persona instead of the built-in
+    /// one (see [`persona`](crate::agent::persona)).
+    pub fn with_persona(mut self, persona: Option<String>) -> Self {
+        self.persona = persona;
+        self
+    }
+
+    /// Include the summary of messages too old to fit the history budget.
+    pub fn with_earlier_summary(mut self, summary: Option<String>) -> Self {
+        self.earlier = summary.filter(|s| !s.trim().is_empty());
+        self
+    }
+
     /// Build the complete system prompt.
     pub fn build_system_prompt(&self, skill_names: &[String]) -> String {
         let mut sections = Vec::new();
@@ -56,6 +112,24 @@ impl<'a> ContextBuilder<'a> {
             sections.push(format!("# Memory\n\n{}", memory_ctx));
         }
 
+        // 3.5 Profile of the user who sent the message
+        if let Some(ref profile) = self.user_profile {
+            sections.push(format!(
+                "# User Profile\n\nPreferences of the user you are talking to \
+                 (update with `update_profile` when they change):\n{}",
+                profile.context()
+            ));
+        }
+
+        // 3.6 What happened before the history that is sent along
+        if let Some(ref earlier) = self.earlier {
+            sections.push(format!(
+                "# Earlier in This Conversation\n\nSummary of older messages that no longer fit \
+                 the context window:\n{}",
+                earlier.trim()
+            ));
+        }
+
         // 4. Skills
         if !skill_names.is_empty() {
             let skills_content = self.skills.load_skills_for_context(skill_names);
@@ -74,17 +148,43 @@ impl<'a> ContextBuilder<'a> {
     }
 
     /// Build the complete message list for an LLM call.
+    ///
+    /// The latest user messages from the history are prefixed with how long
+    /// ago they were sent, and the system prompt says when the last exchange
+    /// was, so a conversation resumed after days isn't answered as if it
+    /// never paused. Assistant messages are left as-is so the model doesn't
+    /// start writing the prefixes itself.
     pub fn build_messages(
         &self,
-        history: &[ChatMessage],
+        history: &[SessionMessage],
         current_message: &str,
         skill_names: &[String],
     ) -> Vec<ChatMessage> {
-        let system_prompt = self.build_system_prompt(skill_names);
+        let now = Utc::now();
+        let mut system_prompt = self.build_system_prompt(skill_names);
+        if let Some(last) = history.iter().rev().find_map(SessionMessage::time) {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&self.timing_section(last, now));
+        }
         let mut messages = vec![ChatMessage::system(&system_prompt)];
 
         // Add conversation history
-        messages.extend_from_slice(history);
+        let aged_from = history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, m)| m.role == "user")
+            .nth(AGED_USER_MESSAGES - 1)
+            .map_or(0, |(i, _)| i);
+        messages.extend(history.iter().enumerate().map(|(i, m)| {
+            let mut msg = m.to_chat_message();
+            if let (true, true, Some(time), Some(text)) =
+                (i >= aged_from, m.role == "user", m.time(), m.content.as_deref())
+            {
+                msg.content = Some(format!("[{}] {}", format_elapsed(now - time), text).into());
+            }
+            msg
+        }));
 
         // Add current user message
         messages.push(ChatMessage::user(current_message));
@@ -113,22 +213,54 @@ impl<'a> ContextBuilder<'a> {
 
     // ── Private helpers ─────────────────────────────────────────────
 
+    /// When the previous exchange happened, relative to `now`.
+    fn timing_section(&self, last: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let mut section = format!(
+            "# Conversation Timing\n\nThe previous message in this conversation was sent {} ({}). \
+             The latest messages from the user are prefixed with their age.",
+            format_elapsed(now - last),
+            self.locale.describe(last)
+        );
+        if now - last >= chrono::Duration::hours(RESUME_GAP_HOURS) {
+            section.push_str(
+                " The conversation is being resumed after a pause: don't continue as if no time \
+                 has passed, and treat prices, plans and \"today\" from earlier messages as stale.",
+            );
+        }
+        section
+    }
+
     fn identity(&self) -> String {
-        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S %Z");
+        let now = chrono::Utc::now();
+        let timestamp = self.locale.describe(now);
+        let format_hint = self
+            .locale
+            .format_hint(now)
+            .map(|hint| format!("\n- {}", hint))
+            .unwrap_or_default();
+        let language_hint = self
+            .language_hint
+            .as_ref()
+            .map(|hint| format!("\n- {}", hint))
+            .unwrap_or_default();
+        let persona = self
+            .persona
+            .as_deref()
+            .unwrap_or("You are **CrabbyBot** 🦀, an ultra-lightweight personal AI assistant.");
         let os = std::env::consts::OS;
         let arch = std::env::consts::ARCH;
 
         format!(
             r#"# Identity
 
-You are **CrabbyBot** 🦀, an ultra-lightweight personal AI assistant.
+{}
 
 ## Environment (LIVE STATUS - ALWAYS TRUST THIS OVER MEMORY)
 - Workspace: `{}`
 - Channel: `{}`
 - Chat ID: `{}`
 - Service Status: {}
-- Current time: {}
+- Current time: {}{}
 - Platform: {} ({})
 
 ## Capabilities
@@ -141,16 +273,21 @@ You have access to tools for:
 ## Guidelines
 - Be concise, accurate, and helpful.
 - Use tools when needed — don't guess about file contents or command outputs.
+- Each tool description ends with its rough cost. Prefer cheap tools, and use expensive ones (fetching pages, simulations, streams) only when a cheap one can't answer.
 - When making changes to files, show what you changed.
 - If unsure, ask for clarification.
+- Read "today", "tomorrow" and clock times the user mentions in the timezone of the current time above.{}
 - Prefer simple, correct solutions over clever ones."#,
+            persona,
             self.workspace.display(),
             self.channel,
             self.chat_id,
             self.service_status,
             timestamp,
+            format_hint,
             os,
             arch,
+            language_hint,
         )
     }
 
@@ -172,3 +309,162 @@ You have access to tools for:
         }
     }
 }
+
+/// How much of the context budget a conversation takes up, for
+/// `/context` and the optional reply footer. Token counts are the same
+/// chars / 4 estimate the history budget uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextUsage {
+    /// `max_context_tokens`.
+    pub budget: usize,
+    /// The system prompt of the last turn; 0 before the first one.
+    pub system_tokens: usize,
+    /// The history sent with the next message.
+    pub history_tokens: usize,
+    /// Messages in the session.
+    pub messages: usize,
+    /// The oldest of those, left out because they no longer fit.
+    pub trimmed: usize,
+    /// The summary sent in place of trimmed messages; 0 without one.
+    pub summary_tokens: usize,
+    /// When the middle of the context was last summarized (RFC 3339).
+    pub compressed_at: Option<String>,
+}
+
+impl ContextUsage {
+    /// Share of the budget in use, 0–100, rounded up.
+    pub fn percent(&self) -> usize {
+        ((self.system_tokens + self.summary_tokens + self.history_tokens) * 100)
+            .div_ceil(self.budget.max(1))
+            .min(100)
+    }
+
+    /// One line for the end of a reply; `summarized` when this turn
+    /// summarized part of the context.
+    pub fn footer(&self, summarized: bool) -> String {
+        let mut footer = format!("📊 Context {}% full", self.percent());
+        if self.trimmed > 0 && self.summary_tokens > 0 {
+            footer.push_str(&format!(" · {} older messages sent as a summary", self.trimmed));
+        } else if self.trimmed > 0 {
+            footer.push_str(&format!(" · {} older messages no longer sent", self.trimmed));
+        }
+        if summarized {
+            footer.push_str(" · earlier messages summarized this turn");
+        }
+        footer
+    }
+
+    /// The `/context` report.
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "📊 **Context**: {}% of {} tokens\n\n\
+             • System prompt: ~{} tokens\n\
+             • History: ~{} tokens, {} of {} messages",
+            self.percent(),
+            self.budget,
+            self.system_tokens,
+            self.history_tokens,
+            self.messages - self.trimmed,
+            self.messages,
+        );
+        if self.trimmed > 0 && self.summary_tokens > 0 {
+            text.push_str(&format!(
+                "\n\n✂️ The oldest {} messages no longer fit; the model gets a summary of them \
+                 (~{} tokens) instead. `/fork <name>` keeps them in a copy; `/clear` starts afresh.",
+                self.trimmed, self.summary_tokens
+            ));
+        } else if self.trimmed > 0 {
+            text.push_str(&format!(
+                "\n\n✂️ The oldest {} messages no longer fit and are not sent to the model, so it has \
+                 forgotten them. `/fork <name>` keeps them in a copy; `/clear` starts afresh.",
+                self.trimmed
+            ));
+        }
+        if let Some(at) = &self.compressed_at {
+            let at = chrono::DateTime::parse_from_rfc3339(at)
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| at.clone());
+            text.push_str(&format!(
+                "\n\n🗜️ A long turn outgrew the window on {}; the middle of it was summarized.",
+                at
+            ));
+        }
+        text
+    }
+}
+
+/// `just now`, `12 min ago`, `3 h ago`, `2 days ago`.
+fn format_elapsed(elapsed: chrono::Duration) -> String {
+    let minutes = elapsed.num_minutes();
+    match minutes {
+        ..1 => "just now".into(),
+        1..60 => format!("{} min ago", minutes),
+        60..1440 => format!("{} h ago", minutes / 60),
+        1440..2880 => "1 day ago".into(),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str, age: chrono::Duration) -> SessionMessage {
+        SessionMessage {
+            role: role.into(),
+            content: Some(content.into()),
+            timestamp: (Utc::now() - age).to_rfc3339(),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn test_history_carries_elapsed_time() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_context_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let memory = MemoryStore::new(&dir);
+        let skills = SkillsLoader::new(&dir, None);
+        let ctx = ContextBuilder::new(&dir, &memory, &skills, "cli", "direct", "ok");
+
+        let history = [
+            message("user", "Is SOL up?", chrono::Duration::days(2)),
+            message("assistant", "Yes, +4% today.", chrono::Duration::days(2)),
+        ];
+        let messages = ctx.build_messages(&history, "And now?", &[]);
+
+        let system = messages[0].content_as_str().unwrap();
+        assert!(system.contains("The previous message in this conversation was sent 2 days ago"));
+        assert!(system.contains("resumed after a pause"));
+        assert_eq!(messages[1].content_as_str(), Some("[2 days ago] Is SOL up?"));
+        assert_eq!(messages[2].content_as_str(), Some("Yes, +4% today."));
+        assert_eq!(messages[3].content_as_str(), Some("And now?"));
+
+        // Only the latest user messages carry an age, so the older part of
+        // the conversation reads the same from turn to turn.
+        let long: Vec<_> = (0..5)
+            .map(|i| message("user", &format!("q{}", i), chrono::Duration::hours(5 - i)))
+            .collect();
+        let texts: Vec<_> = ctx.build_messages(&long, "q5", &[])[1..6]
+            .iter()
+            .map(|m| m.content_as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(texts, ["q0", "q1", "[3 h ago] q2", "[2 h ago] q3", "[1 h ago] q4"]);
+
+        // A fresh conversation gets no timing section at all.
+        let fresh = ctx.build_messages(&[], "Hi", &[]);
+        assert!(!fresh[0].content_as_str().unwrap().contains("Conversation Timing"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(chrono::Duration::seconds(20)), "just now");
+        assert_eq!(format_elapsed(chrono::Duration::minutes(12)), "12 min ago");
+        assert_eq!(format_elapsed(chrono::Duration::minutes(200)), "3 h ago");
+        assert_eq!(format_elapsed(chrono::Duration::hours(30)), "1 day ago");
+        assert_eq!(format_elapsed(chrono::Duration::days(9)), "9 days ago");
+    }
+}
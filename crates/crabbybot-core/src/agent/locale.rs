@@ -0,0 +1,425 @@
+//! Timezone and locale of a conversation.
+//!
+//! The server clock says nothing about where the user is. The timezone
+//! comes from the sender's profile, then `agents.chats["channel:chat_id"]`,
+//! then `agents.defaults`, and only then the server's local time. The
+//! locale picks the date style the agent is told to write in and how tools
+//! and the reply post-processor write numbers, amounts and times
+//! (`1.234,56 €` vs `$1,234.56`, `18:30` vs `6:30 PM`). The reply language
+//! is either configured or detected from each message.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use tracing::warn;
+
+use crate::config::AgentsConfig;
+
+/// Parse an IANA timezone name such as `Europe/Berlin`.
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("unknown timezone '{}' (expected an IANA name like 'Europe/Berlin')", name))
+}
+
+/// `(language, region)` of a BCP 47 tag, lowercased: `en-US` → `("en", "us")`.
+fn split_locale(locale: &str) -> (String, String) {
+    let lower = locale.trim().to_lowercase().replace('_', "-");
+    match lower.split_once('-') {
+        Some((lang, region)) => (lang.to_string(), region.to_string()),
+        None => (lower, String::new()),
+    }
+}
+
+/// strftime date pattern customary for `locale`.
+fn date_pattern(locale: &str) -> &'static str {
+    let (lang, region) = split_locale(locale);
+    match (lang.as_str(), region.as_str()) {
+        (_, "us") | ("en", "") => "%m/%d/%Y",
+        ("de" | "ru" | "pl" | "cs" | "tr" | "fi" | "nb" | "no" | "uk" | "ro" | "da", _) => "%d.%m.%Y",
+        ("ja" | "zh" | "hu", _) => "%Y/%m/%d",
+        ("sv" | "lt", _) | ("fr", "ca") => "%Y-%m-%d",
+        ("nl", _) => "%d-%m-%Y",
+        _ => "%d/%m/%Y",
+    }
+}
+
+/// Thousands separator and decimal mark customary for `locale`.
+fn separators(locale: &str) -> (&'static str, &'static str) {
+    let (lang, region) = split_locale(locale);
+    match (lang.as_str(), region.as_str()) {
+        (_, "ch" | "li") => ("'", "."),
+        ("en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" | "ms", _) | ("es", "mx" | "us") => (",", "."),
+        ("fr" | "ru" | "pl" | "cs" | "sk" | "uk" | "fi" | "sv" | "nb" | "no" | "hu" | "lt" | "bg", _)
+        | ("pt", "pt") => ("\u{a0}", ","),
+        _ => (".", ","),
+    }
+}
+
+/// Whether `locale` puts the currency symbol before the amount.
+fn symbol_first(locale: &str) -> bool {
+    let (lang, _) = split_locale(locale);
+    matches!(lang.as_str(), "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" | "ms")
+}
+
+/// Symbol of a currency code; codes without one (`USDC`, `SOL`) are
+/// written out after the amount.
+fn currency_symbol(code: &str) -> Option<&'static str> {
+    match code.to_ascii_uppercase().as_str() {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" => Some("¥"),
+        _ => None,
+    }
+}
+
+/// Shortest text, in letters, whose language is worth guessing.
+const MIN_DETECT_LETTERS: usize = 12;
+
+/// English name of the language `text` is written in, when the guess is
+/// reliable. Links, commands, tickers, addresses and numbers are ignored
+/// so a mint address doesn't read as Latvian.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|w| {
+            !w.starts_with("http")
+                && !w.starts_with(['/', '$', '@', '#'])
+                && !w.chars().any(|c| c.is_ascii_digit())
+        })
+        .collect();
+    let prose = words.join(" ");
+    if prose.chars().filter(|c| c.is_alphabetic()).count() < MIN_DETECT_LETTERS {
+        return None;
+    }
+    whatlang::detect(&prose)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().eng_name())
+}
+
+/// Whether `locale` customarily uses a 12-hour clock.
+fn uses_12h(locale: &str) -> bool {
+    let (lang, region) = split_locale(locale);
+    lang == "en" && matches!(region.as_str(), "" | "us" | "ca" | "au" | "in" | "ph")
+}
+
+/// Timezone and locale that apply to one conversation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatLocale {
+    /// `None` means the server's local time.
+    pub timezone: Option<Tz>,
+    /// BCP 47 tag such as `en-US` or `de-DE`.
+    pub locale: Option<String>,
+    /// Language every reply is written in; `None` follows the user.
+    pub language: Option<String>,
+}
+
+impl ChatLocale {
+    fn date_pattern(&self) -> &'static str {
+        self.locale.as_deref().map(date_pattern).unwrap_or("%Y-%m-%d")
+    }
+
+    fn time_pattern(&self) -> &'static str {
+        if self.uses_12h() {
+            "%-I:%M %p"
+        } else {
+            "%H:%M"
+        }
+    }
+
+    /// Whether times are written with AM/PM.
+    pub fn uses_12h(&self) -> bool {
+        self.locale.as_deref().is_some_and(uses_12h)
+    }
+
+    /// `value` with `decimals` places and the locale's separators, e.g.
+    /// `1.234,56` for `de-DE`. Without a locale, `1,234.56`.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let (group, mark) = self.locale.as_deref().map(separators).unwrap_or((",", "."));
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut out = String::new();
+        if value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            out.push('-');
+        }
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                out.push_str(group);
+            }
+            out.push(c);
+        }
+        if !fraction.is_empty() {
+            out.push_str(mark);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// `amount` of `currency` (an ISO code like `USD`, or a token such as
+    /// `USDC`), e.g. `$1,234.56` or `1.234,56 $`.
+    pub fn money(&self, amount: f64, currency: &str, decimals: usize) -> String {
+        let number = self.number(amount, decimals);
+        match number.strip_prefix('-') {
+            Some(positive) => format!("-{}", self.with_currency(positive, currency)),
+            None => self.with_currency(&number, currency),
+        }
+    }
+
+    /// An already formatted amount, such as `2,41T`, with the currency
+    /// placed the way the locale does.
+    pub fn with_currency(&self, number: &str, currency: &str) -> String {
+        let first = self.locale.as_deref().is_none_or(symbol_first);
+        match currency_symbol(currency) {
+            Some(symbol) if first => format!("{}{}", symbol, number),
+            Some(symbol) => format!("{} {}", number, symbol),
+            None => format!("{} {}", number, currency.to_uppercase()),
+        }
+    }
+
+    /// Time of day of `at` in this timezone, e.g. `18:30` or `6:30 PM`.
+    pub fn time(&self, at: DateTime<Utc>) -> String {
+        self.format_local(at, self.time_pattern())
+    }
+
+    /// Date of `at` in this timezone, e.g. `17.10.2026`.
+    pub fn date(&self, at: DateTime<Utc>) -> String {
+        self.format_local(at, self.date_pattern())
+    }
+
+    /// IANA name of the timezone, if one is configured.
+    pub fn timezone_name(&self) -> Option<&'static str> {
+        self.timezone.map(|tz| tz.name())
+    }
+
+    /// Format `now` in this timezone (server local time without one).
+    fn format_local(&self, now: DateTime<Utc>, pattern: &str) -> String {
+        match self.timezone {
+            Some(tz) => now.with_timezone(&tz).format(pattern).to_string(),
+            None => now.with_timezone(&Local).format(pattern).to_string(),
+        }
+    }
+
+    /// `now` in this timezone, e.g. `Saturday, 17.10.2026 16:05 Europe/Berlin (UTC+02:00)`.
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        let pattern = format!("%A, {} {}", self.date_pattern(), self.time_pattern());
+        format!(
+            "{} {} (UTC{})",
+            self.format_local(now, &pattern),
+            self.timezone_name().unwrap_or("server time"),
+            self.format_local(now, "%:z")
+        )
+    }
+
+    /// Instruction on which language to reply in: the configured one, else
+    /// the one `message` is written in. `None` when neither is known.
+    pub fn language_hint(&self, message: &str) -> Option<String> {
+        if let Some(language) = &self.language {
+            return Some(format!(
+                "Always reply in {}, whatever language the user writes in.",
+                language
+            ));
+        }
+        detect_language(message).map(|language| {
+            format!(
+                "The user is writing in {0}: reply in {0} unless they ask for another language.",
+                language
+            )
+        })
+    }
+
+    /// Instruction on how to write dates, times and amounts, or `None`
+    /// without a locale.
+    pub fn format_hint(&self, now: DateTime<Utc>) -> Option<String> {
+        let locale = self.locale.as_deref()?;
+        Some(format!(
+            "Locale `{}`: write dates like {}, times like {} and amounts like {}.",
+            locale,
+            self.date(now),
+            self.time(now),
+            self.money(1234.56, "USD", 2)
+        ))
+    }
+}
+
+/// Configured defaults plus per-chat overrides, resolved per conversation.
+#[derive(Debug, Clone, Default)]
+pub struct LocaleSettings {
+    default: ChatLocale,
+    chats: HashMap<String, ChatLocale>,
+}
+
+impl LocaleSettings {
+    /// Read `agents.defaults` and `agents.chats`. Unknown timezones are
+    /// logged and ignored; `Config::validate` reports them at startup.
+    pub fn from_config(agents: &AgentsConfig) -> Self {
+        let tz = |name: &Option<String>, scope: &str| {
+            name.as_deref().filter(|n| !n.trim().is_empty()).and_then(|n| {
+                parse_timezone(n)
+                    .map_err(|e| warn!(scope, "Ignoring timezone: {}", e))
+                    .ok()
+            })
+        };
+        let default = ChatLocale {
+            timezone: tz(&agents.defaults.timezone, "agents.defaults"),
+            locale: agents.defaults.locale.clone().filter(|l| !l.trim().is_empty()),
+            language: agents.defaults.language.clone().filter(|l| !l.trim().is_empty()),
+        };
+        let chats = agents
+            .chats
+            .iter()
+            .map(|(key, chat)| {
+                let locale = ChatLocale {
+                    timezone: tz(&chat.timezone, key),
+                    locale: chat.locale.clone().filter(|l| !l.trim().is_empty()),
+                    language: chat.language.clone().filter(|l| !l.trim().is_empty()),
+                };
+                (key.clone(), locale)
+            })
+            .collect();
+        Self { default, chats }
+    }
+
+    /// Locale of a conversation. `profile_timezone` is the sender's
+    /// profile setting and wins over chat and global configuration.
+    pub fn resolve(&self, channel: &str, chat_id: &str, profile_timezone: Option<&str>) -> ChatLocale {
+        let chat = self.chats.get(&format!("{}:{}", channel, chat_id));
+        let timezone = profile_timezone
+            .and_then(|n| parse_timezone(n).ok())
+            .or(chat.and_then(|c| c.timezone))
+            .or(self.default.timezone);
+        let locale = chat
+            .and_then(|c| c.locale.clone())
+            .or_else(|| self.default.locale.clone());
+        // A chat's "auto" switches a globally fixed language back to detection.
+        let language = chat
+            .and_then(|c| c.language.clone())
+            .or_else(|| self.default.language.clone())
+            .filter(|l| !l.eq_ignore_ascii_case("auto"));
+        ChatLocale {
+            timezone,
+            locale,
+            language,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChatConfig;
+
+    fn settings() -> LocaleSettings {
+        let mut agents = AgentsConfig::default();
+        agents.defaults.timezone = Some("America/New_York".into());
+        agents.defaults.locale = Some("en-US".into());
+        agents.defaults.language = Some("English".into());
+        agents.chats.insert(
+            "telegram:42".into(),
+            ChatConfig {
+                timezone: Some("Europe/Berlin".into()),
+                locale: Some("de-DE".into()),
+                language: Some("auto".into()),
+                ..Default::default()
+            },
+        );
+        agents.chats.insert(
+            "telegram:7".into(),
+            ChatConfig {
+                timezone: Some("Mars/Olympus".into()),
+                locale: None,
+                language: None,
+                ..Default::default()
+            },
+        );
+        LocaleSettings::from_config(&agents)
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let s = settings();
+        let global = s.resolve("cli", "direct", None);
+        assert_eq!(global.timezone_name(), Some("America/New_York"));
+        assert_eq!(global.locale.as_deref(), Some("en-US"));
+
+        let chat = s.resolve("telegram", "42", None);
+        assert_eq!(chat.timezone_name(), Some("Europe/Berlin"));
+        assert_eq!(chat.locale.as_deref(), Some("de-DE"));
+
+        let user = s.resolve("telegram", "42", Some("Asia/Tokyo"));
+        assert_eq!(user.timezone_name(), Some("Asia/Tokyo"));
+        assert_eq!(user.locale.as_deref(), Some("de-DE"));
+
+        // An invalid override falls back to the global default.
+        assert_eq!(s.resolve("telegram", "7", Some("nowhere")).timezone_name(), Some("America/New_York"));
+    }
+
+    #[test]
+    fn test_describe_and_hint() {
+        let now = DateTime::parse_from_rfc3339("2026-10-17T22:30:00Z").unwrap().with_timezone(&Utc);
+        let s = settings();
+
+        let berlin = s.resolve("telegram", "42", None);
+        assert_eq!(berlin.describe(now), "Sunday, 18.10.2026 00:30 Europe/Berlin (UTC+02:00)");
+        assert_eq!(
+            berlin.format_hint(now).unwrap(),
+            "Locale `de-DE`: write dates like 18.10.2026, times like 00:30 and amounts like 1.234,56 $."
+        );
+
+        let ny = s.resolve("cli", "direct", None);
+        assert_eq!(ny.describe(now), "Saturday, 10/17/2026 6:30 PM America/New_York (UTC-04:00)");
+
+        assert!(ChatLocale::default().format_hint(now).is_none());
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_numbers_and_amounts() {
+        let locale = |tag: &str| ChatLocale {
+            locale: Some(tag.into()),
+            ..Default::default()
+        };
+        let (us, de, fr, ch) = (locale("en-US"), locale("de-DE"), locale("fr-FR"), locale("de-CH"));
+        assert_eq!(us.money(1234.56, "USD", 2), "$1,234.56");
+        assert_eq!(de.money(1234.56, "EUR", 2), "1.234,56 €");
+        assert_eq!(fr.money(-1234567.891, "eur", 2), "-1\u{a0}234\u{a0}567,89 €");
+        assert_eq!(ch.number(1234.5, 1), "1'234.5");
+        assert_eq!(de.money(0.5, "USDC", 2), "0,50 USDC");
+        assert_eq!(us.money(-0.001, "USD", 2), "$0.00");
+        assert_eq!(ChatLocale::default().money(64210.0, "USD", 0), "$64,210");
+        assert_eq!(de.with_currency("2,41T", "USD"), "2,41T $");
+
+        let evening = DateTime::parse_from_rfc3339("2026-10-17T18:05:00Z").unwrap().with_timezone(&Utc);
+        let utc = |tag: &str| ChatLocale {
+            timezone: Some(chrono_tz::UTC),
+            ..locale(tag)
+        };
+        assert_eq!(utc("en-US").time(evening), "6:05 PM");
+        assert_eq!(utc("de-DE").time(evening), "18:05");
+        assert_eq!(utc("de-DE").date(evening), "17.10.2026");
+    }
+
+    #[test]
+    fn test_language_hint() {
+        let s = settings();
+        // Fixed globally; chat 42 opts back into detection.
+        let fixed = s.resolve("telegram", "7", None);
+        assert_eq!(
+            fixed.language_hint("Wie steht der Kurs heute?").unwrap(),
+            "Always reply in English, whatever language the user writes in."
+        );
+        let auto = s.resolve("telegram", "42", None);
+        assert!(auto
+            .language_hint("Wie steht der Kurs von Solana heute im Vergleich zu gestern?")
+            .unwrap()
+            .contains("writing in German"));
+        assert!(auto.language_hint("gm").is_none());
+
+        assert_eq!(
+            detect_language("¿Cuál es el precio de Solana hoy y por qué está bajando tanto?"),
+            Some("Spanish")
+        );
+        assert_eq!(detect_language("/alpha So11111111111111111111111111111111111111112"), None);
+    }
+}
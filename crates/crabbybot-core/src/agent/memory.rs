@@ -1,24 +1,148 @@
 //! Persistent memory system for the agent.
 //!
-//! Supports daily notes (`memory/YYYY-MM-DD.md`) and long-term memory (`MEMORY.md`).
-//! All storage is plain markdown files — easy to read, edit, and version.
+//! Supports daily notes (`memory/YYYY-MM-DD.md`), long-term memory (`MEMORY.md`)
+//! and named facts (`memory/facts.json`) the agent keeps with the
+//! `remember` / `recall` / `forget` tools.
+//! Notes are plain markdown files — easy to read, edit, and version.
 
-use chrono::Local;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+/// A named fact, e.g. `home_city` → "Lisbon".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fact {
+    pub value: String,
+    /// Date it was last set (YYYY-MM-DD).
+    pub updated: String,
+}
+
+/// What [`MemoryStore::recall`] found.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recollection {
+    pub facts: Vec<(String, Fact)>,
+    /// Matching lines of `MEMORY.md` and the daily notes.
+    pub notes: Vec<String>,
+}
+
+impl Recollection {
+    pub fn is_empty(&self) -> bool {
+        self.facts.is_empty() && self.notes.is_empty()
+    }
+}
+
 pub struct MemoryStore {
     memory_dir: PathBuf,
     memory_file: PathBuf,
+    facts_file: PathBuf,
 }
 
 impl MemoryStore {
     pub fn new(workspace: &Path) -> Self {
         let memory_dir = workspace.join("memory");
         let memory_file = memory_dir.join("MEMORY.md");
+        let facts_file = memory_dir.join("facts.json");
         Self {
             memory_dir,
             memory_file,
+            facts_file,
+        }
+    }
+
+    /// Fact keys are matched ignoring case and surrounding spaces.
+    pub fn fact_key(key: &str) -> String {
+        key.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("_")
+    }
+
+    /// All named facts, by key.
+    pub fn facts(&self) -> BTreeMap<String, Fact> {
+        std::fs::read_to_string(&self.facts_file)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default()
+    }
+
+    /// Set the fact `key`, replacing what it said before. Returns the
+    /// previous value.
+    pub fn set_fact(&self, key: &str, value: &str) -> std::io::Result<Option<String>> {
+        let mut facts = self.facts();
+        let fact = Fact {
+            value: value.trim().to_string(),
+            updated: self.today_str(),
+        };
+        let previous = facts.insert(Self::fact_key(key), fact).map(|f| f.value);
+        self.write_facts(&facts)?;
+        Ok(previous)
+    }
+
+    /// Add a line to long-term memory.
+    pub fn append_long_term(&self, note: &str) -> std::io::Result<()> {
+        self.ensure_dir();
+        let mut memory = self.read_long_term();
+        if !memory.is_empty() && !memory.ends_with('\n') {
+            memory.push('\n');
+        }
+        memory.push_str(&format!("- {}\n", note.trim()));
+        std::fs::write(&self.memory_file, memory)
+    }
+
+    /// Facts whose key or value contains `query`, and matching lines of the
+    /// notes from the last `days` days and long-term memory. An empty query
+    /// returns all facts and long-term memory.
+    pub fn recall(&self, query: &str, days: u32) -> Recollection {
+        let query = query.trim().to_lowercase();
+        let matches = |text: &str| query.is_empty() || text.to_lowercase().contains(&query);
+        let facts = self
+            .facts()
+            .into_iter()
+            .filter(|(key, fact)| matches(key) || matches(&fact.value))
+            .collect();
+        let mut notes: Vec<String> = self
+            .read_long_term()
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#') && matches(l))
+            .map(String::from)
+            .collect();
+        if !query.is_empty() {
+            let recent = self.recent_memories(days);
+            notes.extend(
+                recent
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#') && l != &"---" && matches(l))
+                    .map(String::from),
+            );
+        }
+        Recollection { facts, notes }
+    }
+
+    /// Drop the fact `key`, or else every long-term memory line containing
+    /// it. Returns how many entries were removed.
+    pub fn forget(&self, key_or_text: &str) -> std::io::Result<usize> {
+        let mut facts = self.facts();
+        if facts.remove(&Self::fact_key(key_or_text)).is_some() {
+            self.write_facts(&facts)?;
+            return Ok(1);
+        }
+        let needle = key_or_text.trim().to_lowercase();
+        if needle.is_empty() {
+            return Ok(0);
+        }
+        let memory = self.read_long_term();
+        let (gone, kept): (Vec<&str>, Vec<&str>) = memory
+            .lines()
+            .partition(|l| !l.starts_with('#') && l.to_lowercase().contains(&needle));
+        if !gone.is_empty() {
+            std::fs::write(&self.memory_file, kept.join("\n") + "\n")?;
         }
+        Ok(gone.len())
+    }
+
+    fn write_facts(&self, facts: &BTreeMap<String, Fact>) -> std::io::Result<()> {
+        self.ensure_dir();
+        std::fs::write(&self.facts_file, serde_json::to_string_pretty(facts)?)
     }
 
     /// Ensure the memory directory exists.
@@ -44,17 +168,23 @@ impl MemoryStore {
 
     /// Append content to today's memory notes.
     pub fn append_today(&self, content: &str) {
+        let _ = self.append_on(Local::now().date_naive(), content);
+    }
+
+    /// Append content to the daily notes of `date`.
+    pub fn append_on(&self, date: NaiveDate, content: &str) -> std::io::Result<()> {
         self.ensure_dir();
-        let path = self.today_file();
+        let day = date.format("%Y-%m-%d").to_string();
+        let path = self.memory_dir.join(format!("{}.md", day));
 
         let full_content = if path.exists() {
-            let existing = std::fs::read_to_string(&path).unwrap_or_default();
+            let existing = std::fs::read_to_string(&path)?;
             format!("{}\n{}", existing, content)
         } else {
-            format!("# {}\n\n{}", self.today_str(), content)
+            format!("# {}\n\n{}", day, content)
         };
 
-        let _ = std::fs::write(path, full_content);
+        std::fs::write(path, full_content)
     }
 
     /// Read long-term memory (MEMORY.md).
@@ -70,22 +200,21 @@ impl MemoryStore {
 
     /// Get memories from the last N days.
     pub fn recent_memories(&self, days: u32) -> String {
-        use chrono::Duration;
+        let memories: Vec<String> = self.daily_notes(days).into_iter().map(|(_, content)| content).collect();
+        memories.join("\n\n---\n\n")
+    }
 
+    /// The daily notes of the last `days` days with their dates, newest
+    /// first.
+    pub fn daily_notes(&self, days: u32) -> Vec<(NaiveDate, String)> {
         let today = Local::now().date_naive();
-        let mut memories = Vec::new();
-
-        for i in 0..days {
-            let date = today - Duration::days(i as i64);
-            let date_str = date.format("%Y-%m-%d").to_string();
-            let path = self.memory_dir.join(format!("{}.md", date_str));
-
-            if let Ok(content) = std::fs::read_to_string(path) {
-                memories.push(content);
-            }
-        }
-
-        memories.join("\n\n---\n\n")
+        (0..days)
+            .map(|i| today - chrono::Duration::days(i as i64))
+            .filter_map(|date| {
+                let path = self.memory_dir.join(format!("{}.md", date.format("%Y-%m-%d")));
+                std::fs::read_to_string(path).ok().map(|content| (date, content))
+            })
+            .collect()
     }
 
     /// Get formatted memory context for inclusion in the system prompt.
@@ -97,6 +226,12 @@ impl MemoryStore {
             parts.push(format!("## Long-term Memory\n{}", long_term));
         }
 
+        let facts = self.facts();
+        if !facts.is_empty() {
+            let lines: Vec<String> = facts.iter().map(|(k, f)| format!("- {}: {}", k, f.value)).collect();
+            parts.push(format!("## Facts\n{}", lines.join("\n")));
+        }
+
         let today = self.read_today();
         if !today.is_empty() {
             parts.push(format!("## Today's Notes\n{}", today));
@@ -132,4 +267,32 @@ mod tests {
         // Cleanup
         let _ = fs::remove_dir_all(&tmp);
     }
+
+    #[test]
+    fn test_facts_recall_and_forget() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_memory_facts_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        let store = MemoryStore::new(&tmp);
+
+        assert_eq!(store.set_fact("Home City", "Porto").unwrap(), None);
+        assert_eq!(store.set_fact("home city ", "Lisbon").unwrap().as_deref(), Some("Porto"));
+        store.append_long_term("Prefers limit orders over market orders").unwrap();
+        store.append_long_term("Allergic to meme coins").unwrap();
+        store.append_today("Asked about ORDER types");
+
+        let found = store.recall("order", 7);
+        assert!(found.facts.is_empty());
+        assert_eq!(found.notes, ["- Prefers limit orders over market orders", "Asked about ORDER types"]);
+        assert_eq!(store.recall("lisbon", 7).facts[0].0, "home_city");
+        assert_eq!(store.recall("", 7).notes.len(), 2);
+        assert!(store.context().contains("## Facts\n- home_city: Lisbon"));
+
+        assert_eq!(store.forget("HOME CITY").unwrap(), 1);
+        assert_eq!(store.forget("meme coins").unwrap(), 1);
+        assert_eq!(store.forget("nothing like this").unwrap(), 0);
+        assert!(store.facts().is_empty());
+        assert_eq!(store.read_long_term(), "- Prefers limit orders over market orders\n");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 }
@@ -4,33 +4,54 @@
 //! 1. Receives a user message
 //! 2. Emits a `Typing` indicator so the channel can show a spinner
 //! 3. Builds context (system prompt + token-budget history + current message)
-//! 4. Calls the LLM
-//! 5. If the LLM returns tool calls → executes them **concurrently** → feeds results back → repeats
+//! 4. Calls the LLM, first summarizing the middle of the context if it has
+//!    outgrown the window (see [`compress`]), and streams the reply to the
+//!    channel as `Partial` events while it is written (see [`stream`])
+//! 5. If the LLM returns tool calls → executes them **concurrently** (one at a time for
+//!    models without parallel tool-call support) → feeds results back → repeats
 //! 6. When the LLM returns a final text response → publishes `Reply` and returns
 
+pub mod activity;
+pub mod compress;
+pub mod contacts;
 pub mod context;
+pub mod locale;
 pub mod memory;
+pub mod persona;
+pub mod profile;
 pub mod skills;
 pub mod router;
+pub mod stream;
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
+use tokio_util::sync::CancellationToken;
 
 use futures::future;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, info_span, warn, Instrument};
 
 use crate::bus::events::{Button, OutboundMessage};
 use crate::bus::MessageBus;
-use crate::provider::types::{ChatMessage, FunctionCall, ToolCallMessage};
-use crate::provider::LlmProvider;
-use crate::session::SessionManager;
-use context::ContextBuilder;
+use crate::provider::types::{
+    image_data_url, ChatMessage, FunctionCall, LlmResponse, ToolCallMessage, ToolCallRequest, ToolDefinition,
+};
+use crate::provider::{repair, LlmProvider, ProviderError};
+use crate::scheduler::{Lane, LlmScheduler};
+use crate::session::{self, SessionError, SessionManager, SessionMessage};
+use activity::{Activity, ActivityLog};
+use context::{ContextBuilder, ContextUsage};
+use locale::{ChatLocale, LocaleSettings};
+use persona::PersonaSettings;
 use memory::MemoryStore;
+use profile::ProfileStore;
 use skills::SkillsLoader;
 use router::IntentRouter;
-use crate::tools::ToolRegistry;
+use stream::PartialReply;
+use crate::tools::{ToolContext, ToolRegistry};
+use crate::usage::UsageTracker;
 
 /// Structured result from the agent loop.
 #[derive(Debug, Clone)]
@@ -49,7 +70,7 @@ pub struct AgentResult {
 pub enum AgentError {
     /// The LLM provider returned an error (network, auth, rate-limit, quota…).
     #[error("LLM provider error: {0}")]
-    Provider(#[from] anyhow::Error),
+    Provider(#[from] ProviderError),
 
     /// The agent executed `max_iterations` tool rounds without a final response.
     #[error("Max tool iterations ({0}) exceeded without a final answer")]
@@ -57,7 +78,21 @@ pub enum AgentError {
 
     /// A session I/O error (disk full, corrupt JSONL, etc.).
     #[error("Session error: {0}")]
-    Session(#[source] anyhow::Error),
+    Session(#[from] SessionError),
+
+    /// The prompt exceeded the model's context window even after history
+    /// was dropped and tool results were truncated.
+    #[error("Conversation too long for the model's context window: {0}")]
+    ContextTooLong(ProviderError),
+
+    /// The turn ran past the bridge's watchdog limit and was cancelled.
+    #[error("Turn cancelled after exceeding the {0:?} time limit")]
+    TimedOut(std::time::Duration),
+
+    /// The turn's token (see [`AgentLoop::cancel_with`]) was cancelled; the
+    /// session was rolled back to its last saved state.
+    #[error("Turn cancelled")]
+    Cancelled,
 }
 
 // ── Configuration ─────────────────────────────────────────────────────────────
@@ -65,30 +100,82 @@ pub enum AgentError {
 /// Configuration for the agent loop.
 pub struct AgentConfig {
     pub model: Option<String>,
+    /// Model for [`AgentLoop::summarize`]; `model` when unset.
+    pub summary_model: Option<String>,
+    /// Further models `/model` may switch a session to, besides `model`
+    /// and `summary_model`.
+    pub models: Vec<String>,
     pub max_tokens: u32,
     pub temperature: f32,
     pub max_iterations: u32,
+    /// Follow-up requests for a reply cut off at `max_tokens`; the pieces
+    /// are joined into one reply.
+    pub max_continuations: u32,
+    /// Stream replies to the bus as `Partial` events while they are written.
+    pub stream: bool,
     pub workspace: PathBuf,
+    /// Chats with a workspace of their own, keyed by `channel:chat_id`. Their
+    /// files, sessions and memory live there instead of in `workspace`.
+    pub chat_workspaces: BTreeMap<String, PathBuf>,
     /// Token budget for conversation history.
     ///
     /// History will be trimmed to keep the total estimated token count
     /// (chars / 4) under this value. Defaults to 30 000 (~120 KB of text).
     pub max_context_tokens: usize,
+    /// Timezone and locale defaults plus per-chat overrides.
+    pub locale: LocaleSettings,
+    /// Configured system prompts; see [`persona`].
+    pub persona: PersonaSettings,
+    /// Mutating tools each webhook's turns may call, by hook name. Turns
+    /// started by a webhook can't call any other mutating tool.
+    pub webhook_tools: HashMap<String, Vec<String>>,
 }
 
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
             model: None,
+            summary_model: None,
+            models: Vec::new(),
             max_tokens: 4096,
             temperature: 0.7,
             max_iterations: 10,
+            max_continuations: 2,
+            stream: true,
             workspace: PathBuf::from("."),
+            chat_workspaces: BTreeMap::new(),
             max_context_tokens: 30_000,
+            locale: LocaleSettings::default(),
+            persona: PersonaSettings::default(),
+            webhook_tools: HashMap::new(),
         }
     }
 }
 
+impl AgentConfig {
+    /// Workspace of a conversation: its chat's own, or the default one.
+    pub fn workspace_for(&self, channel: &str, chat_id: &str) -> &Path {
+        self.chat_workspaces
+            .get(&format!("{}:{}", channel, chat_id))
+            .unwrap_or(&self.workspace)
+    }
+
+    /// The mutating tools a turn started by `user_id` may call: for a
+    /// webhook, the ones its config lists; `None` (no limit) otherwise.
+    pub fn mutating_tools(&self, user_id: &str) -> Option<Vec<String>> {
+        let hook = user_id.strip_prefix("webhook:")?;
+        Some(self.webhook_tools.get(hook).cloned().unwrap_or_default())
+    }
+
+    /// Whether sessions may use `model`: the configured model, the
+    /// summary model or one listed in `models`.
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.model.as_deref() == Some(model)
+            || self.summary_model.as_deref() == Some(model)
+            || self.models.iter().any(|m| m == model)
+    }
+}
+
 // ── Agent loop ────────────────────────────────────────────────────────────────
 
 /// The core agent loop.
@@ -100,9 +187,27 @@ impl Default for AgentConfig {
 pub struct AgentLoop {
     provider: Arc<Mutex<Box<dyn LlmProvider>>>,
     tools: Arc<ToolRegistry>,
-    memory: MemoryStore,
-    skills: SkillsLoader,
+    /// Bumped when tools are registered or removed at runtime.
+    tool_changes: watch::Receiver<u64>,
+    profiles: ProfileStore,
     sessions: SessionManager,
+    activity: ActivityLog,
+    usage: Option<Arc<UsageTracker>>,
+    /// Paces LLM calls; unpaced when `None`.
+    scheduler: Option<Arc<LlmScheduler>>,
+    /// Lane of the turn being processed.
+    lane: Lane,
+    /// Whether the caller already took the next call's token, see
+    /// [`admit`](Self::admit).
+    admitted: AtomicBool,
+    /// Whether the next turn's reply is kept from streaming, see
+    /// [`hold_partials`](Self::hold_partials).
+    partials_held: bool,
+    /// Stops the next turn's LLM and tool calls, see
+    /// [`cancel_with`](Self::cancel_with).
+    cancel: CancellationToken,
+    /// Estimated system prompt tokens of each session's last turn.
+    prompt_tokens: HashMap<String, usize>,
     config: AgentConfig,
 }
 
@@ -112,29 +217,282 @@ impl AgentLoop {
         tools: Arc<ToolRegistry>,
         config: AgentConfig,
     ) -> Self {
-        let memory = MemoryStore::new(&config.workspace);
-        let skills = SkillsLoader::new(&config.workspace, None);
-        let sessions = SessionManager::new(&config.workspace);
+        let profiles = ProfileStore::new(&config.workspace);
+        let sessions = SessionManager::new(&config.workspace).with_chat_workspaces(config.chat_workspaces.clone());
+        let activity = ActivityLog::new(&config.workspace).with_chat_workspaces(config.chat_workspaces.clone());
+        let tool_changes = tools.subscribe();
 
         Self {
             provider,
             tools,
-            memory,
-            skills,
+            tool_changes,
+            profiles,
             sessions,
+            activity,
+            usage: None,
+            scheduler: None,
+            lane: Lane::Interactive,
+            admitted: AtomicBool::new(false),
+            partials_held: false,
+            cancel: CancellationToken::new(),
+            prompt_tokens: HashMap::new(),
             config,
         }
     }
 
+    /// Record the token usage of every LLM call in `usage`.
+    pub fn with_usage(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Take every LLM call's turn from `scheduler`, in the lane of the
+    /// message's sender (see [`Lane::of`]).
+    pub fn with_scheduler(mut self, scheduler: Arc<LlmScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// The scheduler pacing LLM calls, for callers that wait for it before
+    /// locking the agent (see [`admit`](Self::admit)).
+    pub fn scheduler(&self) -> Option<Arc<LlmScheduler>> {
+        self.scheduler.clone()
+    }
+
+    /// Mark the next turn's first LLM call as paid for: the caller took its
+    /// token in the sender's lane before locking the agent. The turn's
+    /// later calls then go in the interactive lane, since everyone else is
+    /// waiting for the agent, so a background turn never holds it while
+    /// queued behind users.
+    pub fn admit(&mut self) {
+        self.admitted.store(true, Ordering::Relaxed);
+    }
+
+    /// Send the next turn's reply only when it is finished: the caller
+    /// rewrites it first, so streamed drafts would show text the user never
+    /// gets.
+    pub fn hold_partials(&mut self) {
+        self.partials_held = true;
+    }
+
+    /// Stop the next turn when `token` is cancelled: its pending LLM and
+    /// tool calls are dropped and it fails with [`AgentError::Cancelled`].
+    pub fn cancel_with(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    /// Clean up after a turn whose future was dropped mid-way: forget what
+    /// it added to `session_key` since the last save and clear the flags
+    /// set for it, so the next turn starts from a consistent history.
+    /// `error` is recorded as the turn's outcome in the activity log.
+    pub fn abort_turn(&mut self, session_key: &str, error: &AgentError) {
+        self.activity.record(session_key, Activity::Failed(&error.to_string()));
+        self.sessions.discard_unsaved(session_key);
+        self.lane = Lane::Interactive;
+        *self.admitted.get_mut() = false;
+        self.partials_held = false;
+        self.cancel = CancellationToken::new();
+    }
+
+    /// Wait for the scheduler's go-ahead for one LLM call.
+    async fn take_turn(&self) {
+        let Some(ref scheduler) = self.scheduler else {
+            return;
+        };
+        if !self.admitted.swap(false, Ordering::Relaxed) {
+            scheduler.acquire(self.lane).await;
+        }
+    }
+
+    /// Workspace of a chat, see [`AgentConfig::workspace_for`].
+    pub fn workspace(&self, channel: &str, chat_id: &str) -> &Path {
+        self.config.workspace_for(channel, chat_id)
+    }
+
+    /// Timezone and locale of a chat, without a sender's profile.
+    pub fn chat_locale(&self, channel: &str, chat_id: &str) -> ChatLocale {
+        self.config.locale.resolve(channel, chat_id, None)
+    }
+
+    /// The tool registry this agent dispatches to.
+    pub fn tools(&self) -> &Arc<ToolRegistry> {
+        &self.tools
+    }
+
     /// Clear the history for a specific session.
     pub fn clear_session(&mut self, session_key: &str) -> bool {
         self.sessions.delete(session_key)
     }
 
+    /// Tag the sessions that changed since they were last tagged, see
+    /// [`SessionManager::tag_sessions`](crate::session::SessionManager::tag_sessions).
+    pub fn tag_sessions(&mut self) -> Result<usize, SessionError> {
+        self.sessions.tag_sessions()
+    }
+
+    /// Copy the history of `source` into the new session `new_key`.
+    pub fn fork_session(&mut self, source: &str, new_key: &str) -> Result<(), SessionError> {
+        self.sessions.fork(source, new_key).map(|_| ())
+    }
+
+    /// Key of the session `session_key` was forked from, if any.
+    pub fn session_parent(&mut self, session_key: &str) -> Option<String> {
+        if !self.sessions.exists(session_key) {
+            return None;
+        }
+        self.sessions.get_or_create(session_key).parent.clone()
+    }
+
+    /// The model picked with `/model` for a session, if any.
+    pub fn session_model(&mut self, session_key: &str) -> Option<String> {
+        if !self.sessions.exists(session_key) {
+            return None;
+        }
+        self.sessions.get_or_create(session_key).model.clone()
+    }
+
+    /// The model sessions use unless they picked one.
+    pub async fn default_model(&self) -> String {
+        match &self.config.model {
+            Some(model) => model.clone(),
+            None => self.provider.lock().await.default_model().to_string(),
+        }
+    }
+
+    /// Models `/model` may switch a session to, the default first.
+    pub async fn model_choices(&self) -> Vec<String> {
+        let mut choices = vec![self.default_model().await];
+        for model in self.config.summary_model.iter().chain(&self.config.models) {
+            if !choices.contains(model) {
+                choices.push(model.clone());
+            }
+        }
+        choices
+    }
+
+    /// Whether `/model` may switch a session to `model`.
+    pub async fn allows_model(&self, model: &str) -> bool {
+        self.config.allows_model(model) || self.default_model().await == model
+    }
+
+    /// Use `model` for this session from the next message on; `None`
+    /// returns it to the configured default. Saved with the session, so
+    /// the choice survives restarts.
+    pub fn set_session_model(&mut self, session_key: &str, model: Option<String>) -> Result<(), SessionError> {
+        self.sessions.get_or_create(session_key).model = model;
+        self.sessions.save(session_key)
+    }
+
+    /// How much of the context budget the session takes up, as the next
+    /// message would see it.
+    pub fn context_usage(&mut self, session_key: &str) -> ContextUsage {
+        let budget = self.config.max_context_tokens;
+        let system_tokens = self.prompt_tokens.get(session_key).copied().unwrap_or(0);
+        let session = self.sessions.get_or_create(session_key);
+        let summary_tokens = session.summary.as_deref().map_or(0, |s| s.len() / 4);
+        // The same budget `run_turn` gives the history, less the new message.
+        let sent = session.messages_within_budget(budget.saturating_sub(system_tokens + summary_tokens + 50));
+        let history_tokens = sent
+            .iter()
+            .map(|m| (m.content.as_deref().map_or(0, str::len) / 4).max(1))
+            .sum();
+        ContextUsage {
+            budget,
+            system_tokens,
+            history_tokens,
+            messages: session.messages.len(),
+            trimmed: session.messages.len() - sent.len(),
+            summary_tokens,
+            compressed_at: session.compressed_at.clone(),
+        }
+    }
+
+    /// Whether replies in this session end with a context usage footer.
+    pub fn context_footer(&mut self, session_key: &str) -> bool {
+        self.sessions.exists(session_key) && self.sessions.get_or_create(session_key).context_footer
+    }
+
+    /// Turn the context usage footer on or off for this session.
+    pub fn set_context_footer(&mut self, session_key: &str, on: bool) -> Result<(), SessionError> {
+        self.sessions.get_or_create(session_key).context_footer = on;
+        self.sessions.save(session_key)
+    }
+
+    /// Hold back the rest of a long reply until the user asks for more;
+    /// `None` drops whatever was held.
+    pub fn set_pending_reply(&mut self, session_key: &str, rest: Option<String>) {
+        let session = self.sessions.get_or_create(session_key);
+        if session.pending_reply == rest {
+            return;
+        }
+        session.pending_reply = rest;
+        if let Err(e) = self.sessions.save(session_key) {
+            warn!(session = session_key, "Failed to save the held reply: {}", e);
+        }
+    }
+
+    /// Take the part of the last reply held back by [`set_pending_reply`](Self::set_pending_reply).
+    pub fn take_pending_reply(&mut self, session_key: &str) -> Option<String> {
+        if !self.sessions.exists(session_key) {
+            return None;
+        }
+        let rest = self.sessions.get_or_create(session_key).pending_reply.take()?;
+        if let Err(e) = self.sessions.save(session_key) {
+            warn!(session = session_key, "Failed to save the held reply: {}", e);
+        }
+        Some(rest)
+    }
+
+    /// One tool-less completion of `text` under `instructions`, outside any
+    /// session: nothing is read from or written to conversation history.
+    pub async fn summarize(&self, instructions: &str, text: &str) -> Result<String, AgentError> {
+        let messages = [ChatMessage::system(instructions), ChatMessage::user(text)];
+        self.take_turn().await;
+        let provider = self.provider.lock().await;
+        let model = self.config.summary_model.as_deref().or(self.config.model.as_deref());
+        let response = tokio::select! {
+            response = provider.chat(&messages, &[], model, self.config.max_tokens, self.config.temperature) => response?,
+            _ = self.cancel.cancelled() => return Err(AgentError::Cancelled),
+        };
+        if let Some(ref usage) = self.usage {
+            usage.record(&response.usage);
+        }
+        Ok(response.content.unwrap_or_default())
+    }
+
+    /// One LLM call with `model` and the configured limits, streamed into
+    /// `partial` when given.
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        tool_defs: &[ToolDefinition],
+        model: Option<&str>,
+        partial: Option<&PartialReply>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let call = async {
+            self.take_turn().await;
+            let provider = self.provider.lock().await;
+            let (max_tokens, temperature) = (self.config.max_tokens, self.config.temperature);
+            match partial {
+                Some(partial) => {
+                    let on_delta = |delta: &str| partial.push(delta);
+                    provider
+                        .chat_stream(messages, tool_defs, model, max_tokens, temperature, &on_delta)
+                        .await
+                }
+                None => provider.chat(messages, tool_defs, model, max_tokens, temperature).await,
+            }
+        };
+        tokio::select! {
+            response = call => response,
+            _ = self.cancel.cancelled() => Err(ProviderError::Cancelled),
+        }
+    }
+
     /// Process a single user message and return the agent's response.
     ///
-    /// Publishes `Typing` and `Progress` events to `bus` during processing
-    /// so channels can show real-time feedback. Pass `None` if you don't
+    /// Publishes `Typing`, `Progress` and `Partial` events to `bus` during
+    /// processing so channels can show real-time feedback. Pass `None` if you don't
     /// need streaming events (e.g., in tests or direct CLI usage).
     ///
     /// Returns a typed [`AgentError`] so callers can pattern-match on the
@@ -144,16 +502,76 @@ impl AgentLoop {
         content: &str,
         session_key: &str,
         bus: Option<&Arc<MessageBus>>,
+    ) -> Result<AgentResult, AgentError> {
+        self.process_as(content, session_key, "", bus).await
+    }
+
+    /// Like [`process`](Self::process), but records which user sent the
+    /// message so tools see it in their [`ToolContext`].
+    ///
+    /// The turn is also written to the session's [`ActivityLog`].
+    pub async fn process_as(
+        &mut self,
+        content: &str,
+        session_key: &str,
+        user_id: &str,
+        bus: Option<&Arc<MessageBus>>,
+    ) -> Result<AgentResult, AgentError> {
+        let (channel, chat_id) = session::chat_of(session_key);
+        let (channel, chat_id) = (channel.to_owned(), chat_id.to_owned());
+        self.process_in(content, session_key, &channel, &chat_id, user_id, bus)
+            .await
+    }
+
+    /// Like [`process_as`](Self::process_as), for a session that is not
+    /// named after the chat the message came from, such as a channel's
+    /// shared session (see [`SessionScope`](crate::config::SessionScope)).
+    /// Typing indicators, progress and tools go to `channel:chat_id`.
+    pub async fn process_in(
+        &mut self,
+        content: &str,
+        session_key: &str,
+        channel: &str,
+        chat_id: &str,
+        user_id: &str,
+        bus: Option<&Arc<MessageBus>>,
+    ) -> Result<AgentResult, AgentError> {
+        self.activity
+            .record(session_key, Activity::TurnStarted { user_id, content });
+        self.lane = if *self.admitted.get_mut() { Lane::Interactive } else { Lane::of(user_id) };
+        let mut result = self
+            .run_turn(content, session_key, channel, chat_id, user_id, bus)
+            .await;
+        if result.is_err() && self.cancel.is_cancelled() {
+            // Cancelled calls leave tool calls without results behind.
+            self.sessions.discard_unsaved(session_key);
+            result = Err(AgentError::Cancelled);
+        }
+        self.lane = Lane::Interactive;
+        *self.admitted.get_mut() = false;
+        self.partials_held = false;
+        self.cancel = CancellationToken::new();
+        match &result {
+            Ok(reply) => self.activity.record(session_key, Activity::Reply(&reply.content)),
+            Err(e) => self.activity.record(session_key, Activity::Failed(&e.to_string())),
+        }
+        result
+    }
+
+    async fn run_turn(
+        &mut self,
+        content: &str,
+        session_key: &str,
+        channel: &str,
+        chat_id: &str,
+        user_id: &str,
+        bus: Option<&Arc<MessageBus>>,
     ) -> Result<AgentResult, AgentError> {
         info!(session = session_key, "Processing user message");
 
         // ── 1. Typing indicator ───────────────────────────────────────
-        let channel = session_key.split(':').next().unwrap_or("cli").to_owned();
-        let chat_id = session_key
-            .split_once(':')
-            .map(|(_, c)| c)
-            .unwrap_or("direct")
-            .to_owned();
+        let channel = channel.to_owned();
+        let chat_id = chat_id.to_owned();
 
         if let Some(bus) = bus {
             bus.publish_outbound(OutboundMessage::typing(&channel, &chat_id))
@@ -163,28 +581,64 @@ impl AgentLoop {
         // ── 2. Build context components ─────────────────────────────────
         let service_status = "Pump.fun Discovery: INACTIVE (Removed)";
 
+        // Memory, skills and bootstrap files come from the chat's workspace.
+        let workspace = self.config.workspace_for(&channel, &chat_id).to_path_buf();
+        let memory = MemoryStore::new(&workspace);
+        let skills = SkillsLoader::new(&workspace, None);
         let ctx = ContextBuilder::new(
-            &self.config.workspace,
-            &self.memory,
-            &self.skills,
+            &workspace,
+            &memory,
+            &skills,
             &channel,
             &chat_id,
             &service_status,
         );
+        let profile = if user_id.is_empty() {
+            None
+        } else {
+            self.profiles.get(&channel, user_id)
+        };
+        let locale = self.config.locale.resolve(
+            &channel,
+            &chat_id,
+            profile.as_ref().and_then(|p| p.timezone.as_deref()),
+        );
+        let language_hint = locale.language_hint(content);
+        let persona = self.config.persona.resolve(&workspace, &channel);
+        let ctx = ctx
+            .with_persona(persona)
+            .with_user_profile(profile)
+            .with_locale(locale.clone())
+            .with_language_hint(language_hint);
 
         // Estimate system prompt tokens so history budget doesn't overflow
         let system_prompt = ctx.build_system_prompt(&[]);
         let system_prompt_tokens = system_prompt.len() / 4;
         let current_msg_tokens = content.len() / 4;
         let overhead = system_prompt_tokens + current_msg_tokens + 50; // +50 token safety margin
+        self.prompt_tokens.insert(session_key.to_string(), system_prompt_tokens);
         let history_budget = self.config.max_context_tokens.saturating_sub(overhead);
 
         let session = self.sessions.get_or_create(session_key);
-        let history = session.get_history_within_budget(history_budget);
+        // The summary of older messages rides in the system prompt.
+        let summary_tokens = session.summary.as_deref().map_or(0, |s| s.len() / 4);
+        let history = session
+            .messages_within_budget(history_budget.saturating_sub(summary_tokens))
+            .to_vec();
+        let dropped = session.messages.len() - history.len();
+        // A model dropped from the config since it was picked no longer counts.
+        let model = session
+            .model
+            .clone()
+            .filter(|m| self.config.allows_model(m))
+            .or_else(|| self.config.model.clone());
 
         // Add user message to session
         session.add_message("user", content);
 
+        let earlier = self.summarize_dropped(session_key, dropped).await;
+        let ctx = ctx.with_earlier_summary(earlier);
+
 
 
         // ── 3.5 Intent Routing ────────────────────────────────────────
@@ -194,7 +648,7 @@ impl AgentLoop {
         info!(session = session_key, category = category.as_str(), "Loaded filtered tools");
 
         // ── 3.6 Auto-activate skills for this intent ─────────────────
-        let skill_names = self.skills.skills_for_intent(category);
+        let skill_names = skills.skills_for_intent(category);
         if !skill_names.is_empty() {
             info!(
                 skills = ?skill_names,
@@ -205,12 +659,33 @@ impl AgentLoop {
 
         // Rebuild messages with activated skills in the system prompt
         let mut messages = ctx.build_messages(&history, content, &skill_names);
+        // Index of this turn's user message; everything before it (bar the
+        // system prompt) is history that can be dropped under pressure.
+        let mut turn_start = history.len() + 1;
 
         // ── 4. Tool definitions ───────────────────────────────────────
+        // Built fresh every turn, so tools plugged in since the last one
+        // are offered right away.
+        if self.tool_changes.has_changed().unwrap_or(false) {
+            self.tool_changes.mark_unchanged();
+            info!(tools = self.tools.len(), "Tool set changed since the last turn");
+        }
         let tool_defs = self.tools.definitions_for(category);
+        let (parallel_tools, vision) = {
+            let provider = self.provider.lock().await;
+            (
+                provider.supports_parallel_tool_calls(model.as_deref()),
+                provider.supports_images(model.as_deref()),
+            )
+        };
 
         let mut iterations = 0u32;
         let max_iterations = self.config.max_iterations;
+        // Text of a reply cut off at `max_tokens`, while it is continued.
+        let mut cut_off = String::new();
+        let mut continuations = 0u32;
+        // Whether part of the context was summarized, for the footer.
+        let mut summarized = false;
 
         loop {
             iterations += 1;
@@ -244,47 +719,86 @@ impl AgentLoop {
                     .await;
             }
 
-            // ── 5. LLM call (with 413 retry-with-trim) ────────────────
-            let response = match self
-                .provider
-                .lock()
-                .await
-                .chat(
-                    &messages,
-                    &tool_defs,
-                    self.config.model.as_deref(),
-                    self.config.max_tokens,
-                    self.config.temperature,
-                )
-                .await
-            {
+            // ── 5. LLM call (retried once, trimmed, when it doesn't fit) ──
+            if compress::estimate_tokens(&messages) > self.config.max_context_tokens {
+                summarized |= self.compress_middle(session_key, &mut messages, &mut turn_start).await;
+            }
+
+            // A continuation picks up where the shown text left off.
+            let partial = bus
+                .filter(|_| self.config.stream && !self.partials_held)
+                .map(|bus| PartialReply::new(Arc::clone(bus), &channel, &chat_id, &cut_off));
+            let first = self
+                .complete(&messages, &tool_defs, model.as_deref(), partial.as_ref())
+                .instrument(info_span!("llm", iteration = iterations))
+                .await;
+            let mut response = match first {
                 Ok(r) => r,
-                Err(e) if e.to_string().contains("413") || e.to_string().contains("Payload Too Large") => {
-                    // Trim history by keeping only the system prompt + last 2 messages
-                    warn!("Request too large, trimming history and retrying");
-                    let keep = 3.min(messages.len()); // system + at most 2 recent
-                    let system_msg = messages[0].clone();
-                    let tail: Vec<_> = messages[messages.len().saturating_sub(keep - 1)..].to_vec();
-                    messages = vec![system_msg];
-                    messages.extend(tail);
-
-                    self.provider
-                        .lock()
-                        .await
-                        .chat(
-                            &messages,
-                            &tool_defs,
-                            self.config.model.as_deref(),
-                            self.config.max_tokens,
-                            self.config.temperature,
-                        )
-                        .await
-                        .map_err(AgentError::Provider)?
+                Err(e) if e.is_payload_too_large() || e.is_context_length_exceeded() => {
+                    warn!(error = %e, "Request does not fit the model, trimming history and retrying");
+                    // Summarize the middle first; if that still doesn't fit,
+                    // drop the history altogether.
+                    let mut compressed = self.compress_middle(session_key, &mut messages, &mut turn_start).await;
+                    summarized |= compressed;
+                    if compressed {
+                        truncate_tool_results(&mut messages[turn_start..]);
+                    } else {
+                        messages = shrink_for_retry(&messages, turn_start);
+                        turn_start = 1;
+                    }
+
+                    loop {
+                        let retry = self
+                            .complete(&messages, &tool_defs, model.as_deref(), partial.as_ref())
+                            .instrument(info_span!("llm", iteration = iterations, retry = true))
+                            .await;
+                        match retry {
+                            Ok(r) => break r,
+                            Err(e) if compressed && (e.is_payload_too_large() || e.is_context_length_exceeded()) => {
+                                warn!(error = %e, "Still too long after compression, dropping history");
+                                messages = shrink_for_retry(&messages, turn_start);
+                                turn_start = 1;
+                                compressed = false;
+                            }
+                            Err(e) if e.is_payload_too_large() || e.is_context_length_exceeded() => {
+                                return Err(AgentError::ContextTooLong(e));
+                            }
+                            Err(e) => return Err(AgentError::Provider(e)),
+                        }
+                    }
                 }
                 Err(e) => return Err(AgentError::Provider(e)),
             };
+            if let Some(partial) = &partial {
+                partial.flush();
+            }
+            if let Some(ref usage) = self.usage {
+                usage.record(&response.usage);
+            }
+
+            // ── 5.5 Continue a reply cut off at max_tokens ─────────────
+            // The pieces only go to the model; the session gets the joined
+            // reply once it is complete.
+            if response.is_truncated() && response.tool_calls.is_empty() && continuations < self.config.max_continuations {
+                continuations += 1;
+                info!(continuations, "Reply hit max_tokens, asking for the rest");
+                let piece = response.content.unwrap_or_default();
+                messages.push(ChatMessage::assistant(&piece));
+                messages.push(ChatMessage::user(CONTINUE_PROMPT));
+                cut_off.push_str(&piece);
+                // Continuations aren't tool rounds.
+                iterations -= 1;
+                continue;
+            }
+            if !cut_off.is_empty() {
+                cut_off.push_str(response.content.as_deref().unwrap_or_default());
+                response.content = Some(std::mem::take(&mut cut_off));
+            }
 
             // ── 6. Build assistant message ────────────────────────────
+            // Near-miss tool names are corrected first so the history shows
+            // the call that actually ran.
+            repair_tool_names(&self.tools, &mut response.tool_calls);
             let tool_call_messages: Vec<ToolCallMessage> = response
                 .tool_calls
                 .iter()
@@ -298,19 +812,19 @@ impl AgentLoop {
                 })
                 .collect();
 
-            let assistant_msg = if tool_call_messages.is_empty() {
-                ChatMessage::assistant(response.content.as_deref().unwrap_or_default())
-            } else {
-                ChatMessage::assistant_with_tool_calls(
-                    response.content.as_deref(),
-                    tool_call_messages,
-                )
-            };
-
-            messages.push(assistant_msg.clone());
-            {
-                let session = self.sessions.get_or_create(session_key);
-                session.add_chat_message(&assistant_msg);
+            // Sequential-only models get one assistant turn per tool call,
+            // each followed by its result (see step 8).
+            let sequential = !parallel_tools && tool_call_messages.len() > 1;
+            if !sequential {
+                let assistant_msg = if tool_call_messages.is_empty() {
+                    ChatMessage::assistant(response.content.as_deref().unwrap_or_default())
+                } else {
+                    ChatMessage::assistant_with_tool_calls(
+                        response.content.as_deref(),
+                        tool_call_messages.clone(),
+                    )
+                };
+                self.record(session_key, &mut messages, assistant_msg);
             }
 
             // ── 7. Final response? ────────────────────────────────────
@@ -355,13 +869,22 @@ impl AgentLoop {
                     }
                 }
 
+                if self.context_footer(session_key) {
+                    let footer = self.context_usage(session_key).footer(summarized);
+                    reply = format!("{}\n\n{}", reply, footer);
+                }
+
                 return Ok(AgentResult {
                     content: reply,
                     buttons,
                 });
             }
 
-            // ── 8. Concurrent tool execution ──────────────────────────
+            if let Some(text) = response.content.as_deref().filter(|t| !t.trim().is_empty()) {
+                self.activity.record(session_key, Activity::Thinking(text));
+            }
+
+            // ── 8. Tool execution (concurrent unless the model is sequential-only)
             // Emit a progress event for each tool call before launching them.
             if let Some(bus) = bus {
                 let names: Vec<_> = response.tool_calls.iter().map(|tc| &tc.name).collect();
@@ -369,8 +892,9 @@ impl AgentLoop {
                     format!("⚙️ Running tool: `{}`…", names[0])
                 } else {
                     format!(
-                        "⚙️ Running {} tools in parallel: {}…",
+                        "⚙️ Running {} tools {}: {}…",
                         names.len(),
+                        if sequential { "one at a time" } else { "in parallel" },
                         names
                             .iter()
                             .map(|n| format!("`{n}`"))
@@ -382,48 +906,286 @@ impl AgentLoop {
                     .await;
             }
 
-            // Launch all tool calls concurrently; collect (id, name, result) tuples
-            // and then append them in the *original order* to keep the conversation
+            let tool_ctx = Arc::new(
+                ToolContext::new(&channel, &chat_id)
+                    .with_session(session_key)
+                    .with_user(user_id)
+                    .with_mutating_tools(self.config.mutating_tools(user_id))
+                    .with_workspace(&workspace)
+                    .with_locale(locale.clone())
+                    .with_bus(bus.cloned())
+                    .with_activity(self.activity.clone())
+                    .with_cancel(self.cancel.clone())
+                    .with_tools(Arc::clone(&self.tools)),
+            );
+
+            if sequential {
+                let calls = response.tool_calls.iter().zip(tool_call_messages);
+                for (i, (tc, call_msg)) in calls.enumerate() {
+                    let content = if i == 0 { response.content.as_deref() } else { None };
+                    let assistant_msg = ChatMessage::assistant_with_tool_calls(content, vec![call_msg]);
+                    self.record(session_key, &mut messages, assistant_msg);
+
+                    let activity = self.activity.clone();
+                    let result = run_tool_call(Arc::clone(&self.tools), Arc::clone(&tool_ctx), activity, vision, tc).await;
+                    self.record(session_key, &mut messages, result);
+                }
+                continue;
+            }
+
+            // Launch all tool calls concurrently; collect their results and
+            // then append them in the *original order* to keep the conversation
             // schema valid (tool results must follow the matching tool calls).
-            let tools = Arc::clone(&self.tools);
             let tool_futures: Vec<_> = response
                 .tool_calls
                 .iter()
                 .map(|tc| {
-                    let tools = Arc::clone(&tools);
-                    let name = tc.name.clone();
-                    let id = tc.id.clone();
-                    let args: HashMap<String, serde_json::Value> =
-                        tc.arguments.clone().into_iter().collect();
-
-                    async move {
-                        debug!(tool = %name, id = %id, "Executing tool call");
-                        let result = tools.execute(&name, args).await;
-                        debug!(tool = %name, result_len = result.len(), "Tool execution complete");
-                        let out: (String, String, String) = (id, name, result);
-                        out
-                    }
+                    let activity = self.activity.clone();
+                    run_tool_call(Arc::clone(&self.tools), Arc::clone(&tool_ctx), activity, vision, tc)
                 })
                 .collect();
 
-            let results: Vec<(String, String, String)> = future::join_all(tool_futures).await;
+            for result in future::join_all(tool_futures).await {
+                self.record(session_key, &mut messages, result);
+            }
+        }
+    }
+
+    /// The summary of the first `dropped` messages of the session, the ones
+    /// left out of this turn's history. Messages dropped since the last
+    /// summary are folded into it with one tool-less call, and the result is
+    /// kept in the session so each message is summarized only once. When
+    /// that call fails the previous summary is used as is.
+    async fn summarize_dropped(&mut self, session_key: &str, dropped: usize) -> Option<String> {
+        let session = self.sessions.get_or_create(session_key);
+        let through = session.summarized_through.min(dropped);
+        if dropped == through {
+            return session.summary.clone();
+        }
+        let previous = session.summary.clone();
+        let newly_dropped: Vec<ChatMessage> = session.messages[through..dropped]
+            .iter()
+            .map(SessionMessage::to_chat_message)
+            .collect();
+        let mut text = String::new();
+        if let Some(previous) = &previous {
+            text.push_str(&format!("Summary so far:\n{}\n\nLater messages:\n", previous.trim()));
+        }
+        text.push_str(&compress::transcript(&newly_dropped));
+
+        let summary = match self.summarize(compress::HISTORY_SUMMARY_PROMPT, &text).await {
+            Ok(summary) if !summary.trim().is_empty() => summary.trim().to_string(),
+            Ok(_) => return previous,
+            Err(e) => {
+                warn!(session = session_key, error = %e, "Failed to summarize older history");
+                return previous;
+            }
+        };
+        info!(
+            session = session_key,
+            summarized = dropped - through,
+            through = dropped,
+            "Summarized history that no longer fits the context budget"
+        );
+        let session = self.sessions.get_or_create(session_key);
+        session.summary = Some(summary.clone());
+        session.summarized_through = dropped;
+        Some(summary)
+    }
+
+    /// Replace the middle of an oversized request with a summary (see
+    /// [`compress`]). The session itself is untouched. Returns whether
+    /// anything was compressed.
+    async fn compress_middle(
+        &mut self,
+        session_key: &str,
+        messages: &mut Vec<ChatMessage>,
+        turn_start: &mut usize,
+    ) -> bool {
+        let Some(plan) = compress::Plan::new(messages, *turn_start) else {
+            return false;
+        };
+        let tokens_before = compress::estimate_tokens(messages);
+        let summary = match self.summarize(compress::SUMMARY_PROMPT, &plan.transcript(messages)).await {
+            Ok(summary) if !summary.trim().is_empty() => summary,
+            Ok(_) => "(no summary available)".to_string(),
+            Err(e) => {
+                warn!(error = %e, "Failed to summarize context, dropping the middle instead");
+                "(earlier messages omitted)".to_string()
+            }
+        };
+        let (compressed, start) = plan.apply(messages, *turn_start, &summary);
+        let tokens_after = compress::estimate_tokens(&compressed);
+        info!(
+            session = session_key,
+            summarized = plan.summarized(),
+            tokens_before,
+            tokens_after,
+            "Compressed the middle of the context"
+        );
+        self.activity.record(
+            session_key,
+            Activity::Compressed { summarized: plan.summarized(), tokens_before, tokens_after },
+        );
+        *messages = compressed;
+        *turn_start = start;
+        self.sessions.get_or_create(session_key).compressed_at = Some(chrono::Local::now().to_rfc3339());
+        true
+    }
+
+    /// Append a message to both the outgoing request and the session.
+    fn record(&mut self, session_key: &str, messages: &mut Vec<ChatMessage>, msg: ChatMessage) {
+        self.sessions.get_or_create(session_key).add_chat_message(&msg);
+        messages.push(msg);
+    }
+}
+
+/// Sent after a reply cut off at `max_tokens` to get the rest of it.
+const CONTINUE_PROMPT: &str = "Your reply was cut off. Continue exactly where it stopped, \
+     without repeating anything or adding an introduction.";
+
+/// Longest tool result kept verbatim when retrying an oversized request.
+const RETRY_TOOL_RESULT_CHARS: usize = 2_000;
+
+/// Largest image shown to the model with a tool result; the APIs reject
+/// images of 5 MB and more once encoded.
+const MAX_TOOL_IMAGE_BYTES: u64 = 3_750_000;
+
+/// Messages for a retry after the provider rejected the prompt as too long.
+///
+/// Keeps the system prompt and the current turn (`messages[turn_start..]`)
+/// whole, so no tool result loses the assistant call it answers, and drops
+/// the older history. Long tool results in the turn are cut down, since
+/// they are usually what blew the budget.
+fn shrink_for_retry(messages: &[ChatMessage], turn_start: usize) -> Vec<ChatMessage> {
+    let turn_start = turn_start.clamp(1, messages.len().max(1));
+    let mut shrunk: Vec<ChatMessage> = messages[..1.min(messages.len())].to_vec();
+    shrunk.extend(messages[turn_start..].iter().cloned());
+    truncate_tool_results(&mut shrunk);
+    shrunk
+}
+
+/// Cut tool results longer than [`RETRY_TOOL_RESULT_CHARS`] and drop
+/// their images.
+fn truncate_tool_results(messages: &mut [ChatMessage]) {
+    for m in messages.iter_mut().filter(|m| m.role == "tool") {
+        if m.image_count() > 0 {
+            m.content = m.text().map(serde_json::Value::String);
+        }
+        if let Some(text) = m.content_as_str() {
+            if text.chars().count() > RETRY_TOOL_RESULT_CHARS {
+                let cut: String = text.chars().take(RETRY_TOOL_RESULT_CHARS).collect();
+                m.content = Some(serde_json::Value::String(format!("{}\n… [truncated]", cut)));
+            }
+        }
+    }
+}
 
-            for (id, name, result) in results {
-                let tool_msg = ChatMessage::tool_result(&id, &name, &result);
-                messages.push(tool_msg.clone());
-                let session = self.sessions.get_or_create(session_key);
-                session.add_chat_message(&tool_msg);
+/// Run one tool call, or return corrective feedback if it can't run as
+/// issued. Resolves to the tool result message; with `vision`, images the
+/// tool showed the model are attached to it.
+fn run_tool_call(
+    tools: Arc<ToolRegistry>,
+    ctx: Arc<ToolContext>,
+    activity: ActivityLog,
+    vision: bool,
+    tc: &ToolCallRequest,
+) -> impl std::future::Future<Output = ChatMessage> {
+    let name = tc.name.clone();
+    let id = tc.id.clone();
+    // Each call collects its own images.
+    let ctx = if vision { Arc::new(ctx.as_ref().clone().with_images()) } else { ctx };
+    activity.record(&ctx.session_key, Activity::ToolCall { name: &name, args: &tc.arguments });
+    let args: HashMap<String, serde_json::Value> = tc.arguments.clone().into_iter().collect();
+    let rejected = rejected_call_feedback(&tools, tc);
+
+    let span = info_span!("tool", tool = %name, call_id = %id);
+    async move {
+        let started = std::time::Instant::now();
+        let result = match rejected {
+            Some(feedback) => {
+                warn!("Rejected malformed tool call");
+                feedback
+            }
+            None => {
+                debug!("Executing tool call");
+                tools.execute(&name, args, &ctx).await
             }
+        };
+        debug!(result_len = result.len(), "Tool execution complete");
+        activity.record(
+            &ctx.session_key,
+            Activity::ToolResult { name: &name, result: &result, elapsed: started.elapsed() },
+        );
+        let images: Vec<String> = ctx
+            .take_images()
+            .iter()
+            .filter_map(|path| {
+                let url = image_data_url(path, MAX_TOOL_IMAGE_BYTES);
+                if url.is_none() {
+                    warn!(path = %path.display(), "Not showing the model an image it cannot read or that is too large");
+                }
+                url
+            })
+            .collect();
+        ChatMessage::tool_result_with_images(&id, &name, &result, &images)
+    }
+    .instrument(span)
+}
+
+// ── Tool-call repair ──────────────────────────────────────────────────────────
+
+/// Point calls with a near-miss tool name at the tool the model meant.
+fn repair_tool_names(tools: &ToolRegistry, calls: &mut [ToolCallRequest]) {
+    let names = tools.names();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    for call in calls.iter_mut().filter(|c| !tools.has(&c.name)) {
+        if let Some(fixed) = repair::closest_tool_name(&call.name, &names) {
+            warn!(requested = %call.name, resolved = fixed, "Repaired tool name");
+            call.name = fixed.to_string();
         }
     }
 }
 
+/// Feedback for a call that can't run as issued (unknown tool or unparsable
+/// arguments). It is returned to the model as the tool result so the model
+/// can correct itself and retry.
+fn rejected_call_feedback(tools: &ToolRegistry, call: &ToolCallRequest) -> Option<String> {
+    let Some(tool) = tools.get(&call.name) else {
+        let names = tools.names();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let hint = match repair::suggest_tool_names(&call.name, &names, 3).as_slice() {
+            [] => "Use a tool name exactly as listed in your tool definitions.".to_string(),
+            similar => format!(
+                "Did you mean {}? Retry with the exact tool name.",
+                similar
+                    .iter()
+                    .map(|n| format!("`{}`", n))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        };
+        return Some(format!(
+            "Error: tool call rejected: there is no tool named `{}`. {}",
+            call.name, hint
+        ));
+    };
+    let error = call.parse_error.as_ref()?;
+    Some(format!(
+        "Error: tool call rejected: the arguments for `{}` were not a valid JSON object ({}). \
+         Retry with arguments matching this schema: {}",
+        call.name,
+        error,
+        tool.parameters()
+    ))
+}
+
 // ── Tests ─────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tools::Tool;
+    use crate::tools::{Tool, ToolContext};
     use async_trait::async_trait;
     use serde_json::Value;
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -436,15 +1198,22 @@ mod tests {
     struct FakeProvider {
         /// Responses to return in sequence. After exhausting them, panics.
         responses: std::sync::Mutex<std::collections::VecDeque<LlmResponse>>,
+        parallel: bool,
     }
 
     impl FakeProvider {
         fn new(responses: Vec<LlmResponse>) -> Self {
             Self {
                 responses: std::sync::Mutex::new(responses.into()),
+                parallel: true,
             }
         }
 
+        fn sequential(mut self) -> Self {
+            self.parallel = false;
+            self
+        }
+
         fn final_response(content: &str) -> LlmResponse {
             LlmResponse {
                 content: Some(content.into()),
@@ -465,6 +1234,7 @@ mod tests {
                     id: id.into(),
                     name: name.into(),
                     arguments: serde_json::Map::new(),
+                    parse_error: None,
                 }],
                 finish_reason: "tool_calls".into(),
                 usage: Usage {
@@ -481,6 +1251,9 @@ mod tests {
         fn default_model(&self) -> &str {
             "fake-model"
         }
+        fn supports_parallel_tool_calls(&self, _model: Option<&str>) -> bool {
+            self.parallel
+        }
         async fn chat(
             &self,
             _messages: &[ChatMessage],
@@ -488,7 +1261,7 @@ mod tests {
             _model: Option<&str>,
             _max_tokens: u32,
             _temperature: f32,
-        ) -> anyhow::Result<LlmResponse> {
+        ) -> Result<LlmResponse, ProviderError> {
             Ok(self
                 .responses
                 .lock()
@@ -516,7 +1289,7 @@ mod tests {
         fn parameters(&self) -> Value {
             serde_json::json!({"type": "object", "properties": {}})
         }
-        async fn execute(&self, _args: HashMap<String, Value>) -> String {
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
             self.counter.fetch_add(1, Ordering::SeqCst);
             "ok".into()
         }
@@ -525,11 +1298,19 @@ mod tests {
     fn make_config(workspace: std::path::PathBuf) -> AgentConfig {
         AgentConfig {
             model: None,
+            summary_model: None,
+            models: vec!["big-model".into()],
             max_tokens: 100,
             temperature: 0.0,
             max_iterations: 5,
+            max_continuations: 2,
+            stream: true,
             workspace,
+            chat_workspaces: Default::default(),
             max_context_tokens: 30_000,
+            locale: LocaleSettings::default(),
+            persona: PersonaSettings::default(),
+            webhook_tools: Default::default(),
         }
     }
 
@@ -550,6 +1331,103 @@ mod tests {
         assert_eq!(reply.content, "Hello!");
     }
 
+    // ── Test: an admitted turn doesn't queue for its first call again ─────────
+
+    #[tokio::test]
+    async fn test_admitted_turn_uses_the_token_taken_for_it() {
+        let tmp = tempdir();
+        let provider = FakeProvider::new(vec![FakeProvider::final_response("gm")]);
+        // One call per minute, and the bridge already took the only token.
+        let scheduler = Arc::new(LlmScheduler::new(1, 1, std::time::Duration::from_secs(3600)));
+        scheduler.acquire(Lane::Interactive).await;
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(provider))),
+            Arc::new(ToolRegistry::new()),
+            make_config(tmp),
+        )
+        .with_scheduler(scheduler);
+
+        agent.admit();
+        let turn = agent.process_in("digest", "telegram:1", "telegram", "1", "cron", None);
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(5), turn).await.expect("queued again");
+        assert_eq!(reply.unwrap().content, "gm");
+    }
+
+    // ── Test: replies stream to the bus as they are written ───────────────────
+
+    #[tokio::test]
+    async fn test_reply_is_streamed_to_the_bus() {
+        let tmp = tempdir();
+        let provider = FakeProvider::new(vec![
+            FakeProvider::final_response("Streamed!"),
+            FakeProvider::final_response("Held."),
+            FakeProvider::final_response("Again!"),
+            FakeProvider::final_response("Quiet."),
+        ]);
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(provider))),
+            Arc::new(ToolRegistry::new()),
+            make_config(tmp.clone()),
+        );
+        let (bus, mut receivers) = MessageBus::new(16);
+        let bus = Arc::new(bus);
+
+        agent.process("Hi", "cli:streamed", Some(&bus)).await.unwrap();
+        // A held reply isn't streamed; the hold only lasts one turn.
+        agent.hold_partials();
+        agent.process("Hi", "cli:streamed", Some(&bus)).await.unwrap();
+        agent.process("Hi", "cli:streamed", Some(&bus)).await.unwrap();
+        let mut partials = Vec::new();
+        while let Ok(msg) = receivers.outbound_rx.try_recv() {
+            if let OutboundMessage::Partial { chat_id, content, .. } = msg {
+                partials.push((chat_id, content));
+            }
+        }
+        let streamed = |text: &str| ("streamed".to_string(), text.to_string());
+        assert_eq!(partials, [streamed("Streamed!"), streamed("Again!")]);
+
+        agent.config.stream = false;
+        agent.process("Hi", "cli:streamed", Some(&bus)).await.unwrap();
+        while let Ok(msg) = receivers.outbound_rx.try_recv() {
+            assert!(!matches!(msg, OutboundMessage::Partial { .. }));
+        }
+    }
+
+    // ── Test: replies cut off at max_tokens are continued ─────────────────────
+
+    #[tokio::test]
+    async fn test_truncated_reply_is_continued() {
+        let tmp = tempdir();
+        let cut = |content: &str| LlmResponse {
+            finish_reason: "length".into(),
+            ..FakeProvider::final_response(content)
+        };
+        let provider = FakeProvider::new(vec![
+            cut("The three risks are: 1) liq"),
+            cut("uidity, 2) resolution"),
+            FakeProvider::final_response(" disputes, 3) fees."),
+            cut("Second turn, cut"),
+            cut(" and cut again"),
+            cut(" and left as is"),
+        ]);
+        let config = AgentConfig {
+            max_iterations: 1,
+            ..make_config(tmp)
+        };
+        let mut agent = AgentLoop::new(Arc::new(Mutex::new(Box::new(provider))), Arc::new(ToolRegistry::new()), config);
+
+        agent.clear_session("cli:continued");
+        let reply = agent.process("Risks?", "cli:continued", None).await.unwrap();
+        assert_eq!(reply.content, "The three risks are: 1) liquidity, 2) resolution disputes, 3) fees.");
+        let session = agent.sessions.get_or_create("cli:continued");
+        let roles: Vec<&str> = session.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+
+        // After `max_continuations` (2) the reply is returned as it stands.
+        let reply = agent.process("Again", "cli:continued", None).await.unwrap();
+        assert_eq!(reply.content, "Second turn, cut and cut again and left as is");
+    }
+
     // ── Test: concurrent tool execution ───────────────────────────────────────
 
     #[tokio::test]
@@ -565,11 +1443,13 @@ mod tests {
                         id: "1".into(),
                         name: "counter_a".into(),
                         arguments: serde_json::Map::new(),
+                        parse_error: None,
                     },
                     ToolCallRequest {
                         id: "2".into(),
                         name: "counter_b".into(),
                         arguments: serde_json::Map::new(),
+                        parse_error: None,
                     },
                 ],
                 finish_reason: "tool_calls".into(),
@@ -585,7 +1465,7 @@ mod tests {
         let counter_a = Arc::new(AtomicU32::new(0));
         let counter_b = Arc::new(AtomicU32::new(0));
 
-        let mut registry = ToolRegistry::new();
+        let registry = ToolRegistry::new();
         registry.register(Box::new(CounterTool {
             counter: Arc::clone(&counter_a),
             name: "counter_a".into(),
@@ -630,7 +1510,7 @@ mod tests {
         let provider = FakeProvider::new(responses);
         let counter = Arc::new(AtomicU32::new(0));
 
-        let mut registry = ToolRegistry::new();
+        let registry = ToolRegistry::new();
         registry.register(Box::new(CounterTool {
             counter: Arc::clone(&counter),
             name: "counter_a".into(),
@@ -653,6 +1533,457 @@ mod tests {
         );
     }
 
+    // ── Test: sequential-only models get one tool call per turn ──────────────
+
+    #[tokio::test]
+    async fn test_sequential_tool_calls() {
+        let tmp = tempdir();
+        let call = |id: &str, name: &str| ToolCallRequest {
+            id: id.into(),
+            name: name.into(),
+            arguments: serde_json::Map::new(),
+            parse_error: None,
+        };
+        let provider = FakeProvider::new(vec![
+            LlmResponse {
+                content: Some("Checking both.".into()),
+                tool_calls: vec![call("1", "counter_a"), call("2", "counter_b")],
+                finish_reason: "tool_calls".into(),
+                usage: Usage::default(),
+            },
+            FakeProvider::final_response("done"),
+        ])
+        .sequential();
+
+        let counter = Arc::new(AtomicU32::new(0));
+        let registry = ToolRegistry::new();
+        for name in ["counter_a", "counter_b"] {
+            registry.register(Box::new(CounterTool {
+                counter: Arc::clone(&counter),
+                name: name.into(),
+            }), IntentCategory::General);
+        }
+
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(provider))),
+            Arc::new(registry),
+            make_config(tmp),
+        );
+        agent.process("run both", "test:sequential", None).await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let session = agent.sessions.get_or_create("test:sequential");
+        let tail = &session.messages[session.messages.len() - 5..];
+        let shape: Vec<_> = tail
+            .iter()
+            .map(|m| (m.role.as_str(), m.tool_calls.as_ref().map_or(0, |c| c.len())))
+            .collect();
+        assert_eq!(
+            shape,
+            vec![("assistant", 1), ("tool", 0), ("assistant", 1), ("tool", 0), ("assistant", 0)]
+        );
+        assert_eq!(tail[0].content.as_deref(), Some("Checking both."));
+        assert_eq!(tail[1].tool_call_id.as_deref(), Some("1"));
+        assert_eq!(tail[3].tool_call_id.as_deref(), Some("2"));
+        agent.clear_session("test:sequential");
+    }
+
+    // ── Test: images a tool shows reach a model that can see them ─────────────
+
+    struct Snapshot(PathBuf);
+
+    #[async_trait]
+    impl Tool for Snapshot {
+        fn name(&self) -> &str {
+            "snapshot"
+        }
+        fn description(&self) -> &str {
+            "snapshot"
+        }
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+            std::fs::write(&self.0, b"\x89PNG").unwrap();
+            if ctx.show_image(&self.0) {
+                "Snapshot attached.".into()
+            } else {
+                format!("Snapshot saved at {}.", self.0.display())
+            }
+        }
+    }
+
+    /// A [`FakeProvider`] that can see, recording every request.
+    struct Seeing(FakeProvider, Arc<std::sync::Mutex<Vec<Vec<ChatMessage>>>>);
+
+    #[async_trait]
+    impl LlmProvider for Seeing {
+        fn default_model(&self) -> &str {
+            "fake-vision-model"
+        }
+        fn supports_images(&self, _model: Option<&str>) -> bool {
+            true
+        }
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            tools: &[ToolDefinition],
+            model: Option<&str>,
+            max_tokens: u32,
+            temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            self.1.lock().unwrap().push(messages.to_vec());
+            self.0.chat(messages, tools, model, max_tokens, temperature).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_images_reach_vision_models() {
+        let tmp = tempdir();
+        std::fs::create_dir_all(&tmp).unwrap();
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Box::new(Snapshot(tmp.join("shot.png"))), IntentCategory::General);
+        let turn = || {
+            FakeProvider::new(vec![
+                FakeProvider::tool_response("snapshot", "1"),
+                FakeProvider::final_response("done"),
+            ])
+        };
+        let key = format!("cli:vision-test-{}", std::process::id());
+
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let provider: Box<dyn LlmProvider> = Box::new(Seeing(turn(), Arc::clone(&requests)));
+        let mut agent = AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::clone(&registry), make_config(tmp.clone()));
+        agent.process("what does it look like?", &key, None).await.unwrap();
+        {
+            let requests = requests.lock().unwrap();
+            let result = requests[1].last().unwrap();
+            assert_eq!(result.role, "tool");
+            assert_eq!(result.text().as_deref(), Some("Snapshot attached."));
+            assert_eq!(result.content.as_ref().unwrap()[1]["image_url"]["url"], "data:image/png;base64,iVBORw==");
+        }
+        // The session keeps the text only.
+        let session = agent.sessions.get_or_create(&key);
+        let saved = &session.messages[session.messages.len() - 2];
+        assert_eq!(saved.content.as_deref(), Some("Snapshot attached."));
+        agent.clear_session(&key);
+
+        // A model that can't see gets the plain result.
+        let mut agent = AgentLoop::new(Arc::new(Mutex::new(Box::new(turn()))), registry, make_config(tmp.clone()));
+        agent.process("what does it look like?", &key, None).await.unwrap();
+        let session = agent.sessions.get_or_create(&key);
+        let saved = &session.messages[session.messages.len() - 2];
+        assert!(saved.content.as_deref().unwrap().starts_with("Snapshot saved at"));
+        agent.clear_session(&key);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    // ── Test: a chat with its own workspace keeps files and history there ─────
+
+    struct WorkspaceProbe(Arc<std::sync::Mutex<Option<PathBuf>>>);
+
+    #[async_trait]
+    impl Tool for WorkspaceProbe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+        fn description(&self) -> &str {
+            "probe"
+        }
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+            *self.0.lock().unwrap() = Some(ctx.workspace.clone());
+            "ok".into()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_workspace_scopes_tools_and_sessions() {
+        let tmp = tempdir();
+        let work = tmp.join("work");
+        let provider = FakeProvider::new(vec![
+            FakeProvider::tool_response("probe", "1"),
+            FakeProvider::final_response("done"),
+        ]);
+        let seen = Arc::new(std::sync::Mutex::new(None));
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(WorkspaceProbe(Arc::clone(&seen))), IntentCategory::General);
+        let config = AgentConfig {
+            chat_workspaces: BTreeMap::from([("telegram:work".to_string(), work.clone())]),
+            ..make_config(tmp.clone())
+        };
+        let mut agent = AgentLoop::new(Arc::new(Mutex::new(Box::new(provider))), Arc::new(registry), config);
+
+        agent.process("note this", "telegram:work", None).await.unwrap();
+        assert_eq!(seen.lock().unwrap().as_deref(), Some(work.as_path()));
+        assert!(work.join("sessions").join("telegram_work.jsonl").exists());
+        assert_eq!(agent.workspace("telegram", "other"), tmp.as_path());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    // ── Test: a session's /model choice is used and survives a restart ────────
+
+    struct ModelProbe(Arc<std::sync::Mutex<Vec<Option<String>>>>);
+
+    #[async_trait]
+    impl LlmProvider for ModelProbe {
+        fn default_model(&self) -> &str {
+            "cheap-model"
+        }
+        async fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: &[ToolDefinition],
+            model: Option<&str>,
+            _max_tokens: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            self.0.lock().unwrap().push(model.map(String::from));
+            Ok(FakeProvider::final_response("ok"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_model_override() {
+        let tmp = tempdir();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let new_agent = || {
+            let provider: Box<dyn LlmProvider> = Box::new(ModelProbe(Arc::clone(&seen)));
+            AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::new(ToolRegistry::new()), make_config(tmp.clone()))
+        };
+        let key = format!("telegram:model-test-{}", std::process::id());
+        let mut agent = new_agent();
+        assert_eq!(agent.default_model().await, "cheap-model");
+
+        agent.process("hi", &key, None).await.unwrap();
+        agent.set_session_model(&key, Some("big-model".into())).unwrap();
+        agent.process("think hard", &key, None).await.unwrap();
+        agent.process("elsewhere", "telegram:model-test-other", None).await.unwrap();
+
+        let mut restarted = new_agent();
+        assert_eq!(restarted.session_model(&key).as_deref(), Some("big-model"));
+        restarted.process("still big?", &key, None).await.unwrap();
+        restarted.set_session_model(&key, None).unwrap();
+        restarted.process("back to cheap", &key, None).await.unwrap();
+
+        let big = Some("big-model".to_string());
+        assert_eq!(*seen.lock().unwrap(), [None, big.clone(), None, big, None]);
+
+        restarted.clear_session(&key);
+        restarted.clear_session("telegram:model-test-other");
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    // ── Test: /context reports usage and the footer stays out of history ──────
+
+    #[tokio::test]
+    async fn test_context_usage_and_footer() {
+        let tmp = tempdir();
+        let provider: Box<dyn LlmProvider> = Box::new(ModelProbe(Default::default()));
+        let mut agent = AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::new(ToolRegistry::new()), make_config(tmp.clone()));
+        let key = format!("cli:context-test-{}", std::process::id());
+        agent.clear_session(&key);
+
+        assert_eq!(agent.process("hi", &key, None).await.unwrap().content, "ok");
+        agent.set_context_footer(&key, true).unwrap();
+        let reply = agent.process("and again", &key, None).await.unwrap();
+        assert!(reply.content.starts_with("ok\n\n📊 Context "), "{}", reply.content);
+        let usage = agent.context_usage(&key);
+        assert!(usage.system_tokens > 0 && usage.percent() > 0);
+        assert_eq!((usage.messages, usage.trimmed), (4, 0));
+        assert_eq!(agent.sessions.get_or_create(&key).messages[3].content.as_deref(), Some("ok"));
+
+        // With room for two short messages beside the system prompt, the
+        // first exchange is no longer sent.
+        agent.config.max_context_tokens = usage.system_tokens + 50 + 3;
+        let usage = agent.context_usage(&key);
+        assert_eq!(usage.trimmed, 2);
+        assert!(usage.render().contains("The oldest 2 messages no longer fit"));
+        assert!(usage.footer(true).ends_with("2 older messages no longer sent · earlier messages summarized this turn"));
+
+        agent.clear_session(&key);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    /// Model, system prompt and last user message of each recorded call.
+    type ProbeCalls = Arc<std::sync::Mutex<Vec<(Option<String>, String, String)>>>;
+
+    /// Answers summary requests with a fixed summary and everything else
+    /// with "ok", recording the model and the system prompt of each call.
+    struct SummaryProbe(ProbeCalls);
+
+    #[async_trait]
+    impl LlmProvider for SummaryProbe {
+        fn default_model(&self) -> &str {
+            "big-model"
+        }
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: &[ToolDefinition],
+            model: Option<&str>,
+            _max_tokens: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            let system = messages[0].content_as_str().unwrap_or_default().to_string();
+            let user = messages.last().and_then(|m| m.content_as_str()).unwrap_or_default().to_string();
+            let reply = if system == compress::HISTORY_SUMMARY_PROMPT { "Wallet is ABC" } else { "ok" };
+            self.0.lock().unwrap().push((model.map(String::from), system, user));
+            Ok(FakeProvider::final_response(reply))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_history_is_summarized() {
+        let tmp = tempdir();
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let new_agent = || {
+            let provider: Box<dyn LlmProvider> = Box::new(SummaryProbe(Arc::clone(&calls)));
+            let mut config = make_config(tmp.clone());
+            config.summary_model = Some("cheap-model".into());
+            AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::new(ToolRegistry::new()), config)
+        };
+        let key = format!("cli:summary-test-{}", std::process::id());
+        let mut agent = new_agent();
+        agent.clear_session(&key);
+
+        agent.process("my wallet is ABC", &key, None).await.unwrap();
+        agent.process("second", &key, None).await.unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 2, "nothing to summarize while it all fits");
+
+        // Room for two one-token messages: the first exchange is dropped and
+        // summarized with the cheap model, and the summary is sent along.
+        let system_tokens = agent.prompt_tokens[&key];
+        agent.config.max_context_tokens = system_tokens + 50 + 1 + 2;
+        agent.process("third", &key, None).await.unwrap();
+        {
+            let calls = calls.lock().unwrap();
+            let (model, _, text) = &calls[2];
+            assert_eq!(model.as_deref(), Some("cheap-model"));
+            assert!(text.contains("user: my wallet is ABC") && !text.contains("second"), "{}", text);
+            let (model, system, _) = &calls[3];
+            assert_eq!(model, &None);
+            assert!(system.contains("# Earlier in This Conversation") && system.contains("Wallet is ABC"));
+        }
+        assert_eq!(agent.sessions.get_or_create(&key).summarized_through, 2);
+
+        // After a restart, only newly dropped messages are summarized, on
+        // top of the stored summary.
+        let mut agent = new_agent();
+        agent.config.max_context_tokens = system_tokens + 50 + 1 + 2 + "Wallet is ABC".len() / 4;
+        agent.process("fourth", &key, None).await.unwrap();
+        {
+            let calls = calls.lock().unwrap();
+            let (_, _, text) = &calls[4];
+            assert!(text.starts_with("Summary so far:\nWallet is ABC\n\nLater messages:\nuser: second"), "{}", text);
+            assert!(!text.contains("third"), "{}", text);
+        }
+        assert_eq!(agent.sessions.get_or_create(&key).summarized_through, 4);
+        let usage = agent.context_usage(&key);
+        assert!(usage.summary_tokens > 0);
+        assert!(usage.render().contains("the model gets a summary of them"));
+
+        agent.clear_session(&key);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    // ── Test: malformed tool calls are repaired or rejected with feedback ─────
+
+    #[tokio::test]
+    async fn test_malformed_tool_calls_get_feedback() {
+        let tmp = tempdir();
+        let call = |id: &str, name: &str, parse_error: Option<&str>| ToolCallRequest {
+            id: id.into(),
+            name: name.into(),
+            arguments: serde_json::Map::new(),
+            parse_error: parse_error.map(String::from),
+        };
+        let provider = FakeProvider::new(vec![
+            LlmResponse {
+                content: None,
+                tool_calls: vec![
+                    call("1", "Counter-A", None),
+                    call("2", "launch_rocket", None),
+                    call("3", "counter_a", Some("expected value at line 1 column 2")),
+                ],
+                finish_reason: "tool_calls".into(),
+                usage: Usage::default(),
+            },
+            FakeProvider::final_response("done"),
+        ]);
+
+        let counter = Arc::new(AtomicU32::new(0));
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(CounterTool {
+            counter: Arc::clone(&counter),
+            name: "counter_a".into(),
+        }), IntentCategory::General);
+
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(provider))),
+            Arc::new(registry),
+            make_config(tmp),
+        );
+        agent.process("go", "test:malformed", None).await.unwrap();
+
+        // Only the misspelled-but-resolvable call ran.
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        let session = agent.sessions.get_or_create("test:malformed");
+        let mut results: Vec<String> = session
+            .messages
+            .iter()
+            .filter(|m| m.role == "tool")
+            .filter_map(|m| m.content.clone())
+            .collect();
+        let results = results.split_off(results.len() - 3);
+        agent.clear_session("test:malformed");
+
+        let log = std::fs::read_to_string(agent.activity.path("test:malformed")).unwrap();
+        assert!(log.contains("🔧 **counter_a**"), "{log}");
+        assert!(log.contains("❌ **launch_rocket**"), "{log}");
+        assert!(log.contains("💬 Reply:\n  > done"), "{log}");
+        assert_eq!(results[0], "ok");
+        assert!(results[1].contains("no tool named `launch_rocket`"), "{}", results[1]);
+        assert!(results[2].contains("not a valid JSON object"), "{}", results[2]);
+    }
+
+    // ── Test: tools registered between turns ───────────────────────────────────
+
+    #[tokio::test]
+    async fn test_tools_plugged_in_at_runtime_are_used_next_turn() {
+        let tmp = tempdir();
+        let provider = FakeProvider::new(vec![
+            FakeProvider::final_response("no tools yet"),
+            FakeProvider::tool_response("counter_a", "1"),
+            FakeProvider::final_response("done"),
+        ]);
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(provider))),
+            Arc::new(ToolRegistry::new()),
+            make_config(tmp),
+        );
+        agent.process("hi", "test:hotplug", None).await.unwrap();
+
+        let counter = Arc::new(AtomicU32::new(0));
+        agent.tools().register_from(
+            "plugin.counter",
+            Box::new(CounterTool {
+                counter: Arc::clone(&counter),
+                name: "counter_a".into(),
+            }),
+            IntentCategory::General,
+        );
+        assert!(agent.tool_changes.has_changed().unwrap());
+
+        agent.process("count", "test:hotplug", None).await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(!agent.tool_changes.has_changed().unwrap());
+        agent.clear_session("test:hotplug");
+    }
+
     // ── Test: token-budget history trimming ────────────────────────────────────
 
     #[tokio::test]
@@ -683,6 +2014,118 @@ mod tests {
         );
     }
 
+    // ── Test: context-length errors ───────────────────────────────────────────
+
+    /// Rejects any request with more than `max_messages` messages the way
+    /// OpenAI reports an oversized prompt.
+    struct ContextLimitProvider {
+        max_messages: usize,
+        sizes: Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ContextLimitProvider {
+        fn default_model(&self) -> &str {
+            "fake-model"
+        }
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: &[ToolDefinition],
+            _model: Option<&str>,
+            _max_tokens: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            self.sizes.lock().unwrap().push(messages.len());
+            if messages.len() > self.max_messages {
+                return Err(ProviderError::Api {
+                    status: 400,
+                    message: "This model's maximum context length is 8192 tokens \
+                              (context_length_exceeded)"
+                        .into(),
+                });
+            }
+            Ok(FakeProvider::final_response("ok"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_length_retry() {
+        let tmp = tempdir();
+        let provider = Arc::new(Mutex::new(Box::new(ContextLimitProvider {
+            max_messages: 2,
+            sizes: Default::default(),
+        }) as Box<dyn LlmProvider>));
+        let mut agent = AgentLoop::new(provider, Arc::new(ToolRegistry::new()), make_config(tmp.clone()));
+
+        agent.process("first", "cli:direct", None).await.unwrap();
+        // system + user + assistant + user is rejected; the retry drops history.
+        let reply = agent.process("second", "cli:direct", None).await.unwrap();
+        assert_eq!(reply.content, "ok");
+
+        let mut agent = AgentLoop::new(
+            Arc::new(Mutex::new(Box::new(ContextLimitProvider {
+                max_messages: 1,
+                sizes: Default::default(),
+            }))),
+            Arc::new(ToolRegistry::new()),
+            make_config(tmp),
+        );
+        let err = agent.process("too big", "cli:other", None).await.unwrap_err();
+        assert!(matches!(err, AgentError::ContextTooLong(_)));
+    }
+
+    #[tokio::test]
+    async fn test_context_length_retry_compresses_middle() {
+        let tmp = tempdir();
+        let sizes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let provider = Arc::new(Mutex::new(Box::new(ContextLimitProvider {
+            max_messages: 7,
+            sizes: sizes.clone(),
+        }) as Box<dyn LlmProvider>));
+        let mut agent = AgentLoop::new(provider, Arc::new(ToolRegistry::new()), make_config(tmp));
+        let key = "cli:compress-test";
+        agent.clear_session(key);
+
+        for turn in ["q1", "q2", "q3", "q4"] {
+            agent.process(turn, key, None).await.unwrap();
+        }
+        // The fourth turn (8 messages) is rejected; q2/a2 are summarized in
+        // one call and system, q1/a1, the summary and q3..q4 are retried.
+        assert_eq!(*sizes.lock().unwrap(), [2, 4, 6, 8, 2, 7]);
+        let log = std::fs::read_to_string(agent.activity.path(key)).unwrap();
+        assert!(log.contains("Summarized 2 messages"), "{}", log);
+        agent.clear_session(key);
+    }
+
+    #[test]
+    fn test_shrink_for_retry_keeps_turn_whole() {
+        let call = ToolCallMessage {
+            id: "c1".into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "dump".into(),
+                arguments: "{}".into(),
+            },
+        };
+        let messages = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("old question"),
+            ChatMessage::assistant_with_tool_calls(None, vec![]),
+            ChatMessage::user("new question"),
+            ChatMessage::assistant_with_tool_calls(None, vec![call]),
+            ChatMessage::tool_result("c1", "dump", &"y".repeat(10_000)),
+        ];
+
+        let shrunk = shrink_for_retry(&messages, 3);
+        let roles: Vec<_> = shrunk.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool"]);
+        assert_eq!(shrunk[1].content_as_str(), Some("new question"));
+        let result = shrunk[3].content_as_str().unwrap();
+        assert!(result.ends_with("… [truncated]"));
+        assert!(result.len() < 2_100);
+    }
+
     fn tempdir() -> PathBuf {
         let path = std::env::temp_dir().join(format!(
             "CrabbyBot_test_agent_{}",
@@ -0,0 +1,99 @@
+//! Who the assistant says it is.
+//!
+//! The opening of the system prompt ("You are **CrabbyBot** 🦀, ...") can be
+//! replaced without recompiling, per channel or for all of them. The first
+//! of these that exists wins:
+//!
+//! 1. `persona.<channel>.md` in the chat's workspace (e.g. `persona.telegram.md`)
+//! 2. `agents.channels.<channel>.system_prompt` in the config
+//! 3. `persona.md` in the chat's workspace
+//! 4. `agents.defaults.system_prompt`
+//!
+//! Files are read on every turn, so edits apply to the next message.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::AgentsConfig;
+
+/// Configured system prompts, resolved per conversation.
+#[derive(Debug, Clone, Default)]
+pub struct PersonaSettings {
+    default: Option<String>,
+    channels: HashMap<String, String>,
+}
+
+impl PersonaSettings {
+    /// Read `agents.defaults.system_prompt` and `agents.channels`.
+    pub fn from_config(agents: &AgentsConfig) -> Self {
+        let text = |prompt: &Option<String>| prompt.as_deref().map(str::trim).filter(|p| !p.is_empty()).map(String::from);
+        Self {
+            default: text(&agents.defaults.system_prompt),
+            channels: agents
+                .channels
+                .iter()
+                .filter_map(|(channel, c)| Some((channel.clone(), text(&c.system_prompt)?)))
+                .collect(),
+        }
+    }
+
+    /// The persona for a conversation on `channel` whose files live in
+    /// `workspace`, or `None` for the built-in one.
+    pub fn resolve(&self, workspace: &Path, channel: &str) -> Option<String> {
+        read(&workspace.join(format!("persona.{}.md", channel)))
+            .or_else(|| self.channels.get(channel).cloned())
+            .or_else(|| read(&workspace.join("persona.md")))
+            .or_else(|| self.default.clone())
+    }
+}
+
+/// The trimmed file contents, unless missing or empty.
+fn read(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    Some(content.trim().to_string()).filter(|c| !c.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::context::ContextBuilder;
+    use crate::agent::memory::MemoryStore;
+    use crate::agent::skills::SkillsLoader;
+    use crate::config::ChannelAgentConfig;
+
+    #[test]
+    fn test_channel_files_and_config_take_precedence() {
+        let ws = std::env::temp_dir().join(format!("CrabbyBot_test_persona_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&ws);
+        std::fs::create_dir_all(&ws).unwrap();
+
+        let mut agents = AgentsConfig::default();
+        agents.defaults.system_prompt = Some("You are Ferris.".into());
+        agents.channels.insert(
+            "discord".into(),
+            ChannelAgentConfig {
+                system_prompt: Some("You are Ferris, on Discord.".into()),
+            },
+        );
+        let settings = PersonaSettings::from_config(&agents);
+        assert_eq!(settings.resolve(&ws, "cli").as_deref(), Some("You are Ferris."));
+        assert!(PersonaSettings::default().resolve(&ws, "cli").is_none());
+
+        std::fs::write(ws.join("persona.md"), "  You are a terse trading desk.\n").unwrap();
+        std::fs::write(ws.join("persona.telegram.md"), "You are a pirate.").unwrap();
+        assert_eq!(settings.resolve(&ws, "cli").as_deref(), Some("You are a terse trading desk."));
+        assert_eq!(settings.resolve(&ws, "telegram").as_deref(), Some("You are a pirate."));
+        assert_eq!(settings.resolve(&ws, "discord").as_deref(), Some("You are Ferris, on Discord."));
+
+        // The persona replaces the built-in opening; the rest stays.
+        let memory = MemoryStore::new(&ws);
+        let skills = SkillsLoader::new(&ws, None);
+        let prompt = ContextBuilder::new(&ws, &memory, &skills, "telegram", "1", "ok")
+            .with_persona(settings.resolve(&ws, "telegram"))
+            .build_system_prompt(&[]);
+        assert!(prompt.starts_with("# Identity\n\nYou are a pirate.\n"));
+        assert!(!prompt.contains("CrabbyBot** 🦀") && prompt.contains("## Guidelines"));
+
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+}
@@ -0,0 +1,214 @@
+//! Per-user long-term profiles.
+//!
+//! Session memory is keyed by chat, so in group chats it mixes everyone's
+//! preferences together. Profiles are keyed by `(channel, user_id)` instead
+//! and stored in `profiles.json` in the workspace. Like [`MemoryStore`], the
+//! store reads from disk on every access so edits made by tools are picked up
+//! on the next turn without shared state. Identities linked in the
+//! [`ContactBook`] share one profile. A `profiles.json` that doesn't parse is
+//! reported and left alone rather than overwritten.
+//!
+//! [`MemoryStore`]: crate::agent::memory::MemoryStore
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::agent::contacts::ContactBook;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("can't read {path}: {source}")]
+    Corrupt {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("profile I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Long-term preferences for a single user.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserProfile {
+    /// How the user wants to be addressed.
+    pub preferred_name: Option<String>,
+    /// Preferred reply language (e.g. "en", "Spanish").
+    pub language: Option<String>,
+    /// IANA timezone (e.g. "Europe/Berlin").
+    pub timezone: Option<String>,
+    /// Risk tolerance for trading suggestions (e.g. "low", "medium", "high").
+    pub risk_tolerance: Option<String>,
+    /// Wallet addresses the user cares about.
+    pub wallets: Vec<String>,
+    /// Tokens or markets the user follows.
+    pub tokens: Vec<String>,
+    /// RFC 3339 timestamp of the last update.
+    pub updated_at: Option<String>,
+}
+
+impl UserProfile {
+    pub fn is_empty(&self) -> bool {
+        self.preferred_name.is_none()
+            && self.language.is_none()
+            && self.timezone.is_none()
+            && self.risk_tolerance.is_none()
+            && self.wallets.is_empty()
+            && self.tokens.is_empty()
+    }
+
+    /// Render the profile as a markdown list for the system prompt.
+    pub fn context(&self) -> String {
+        let mut lines = Vec::new();
+        let fields = [
+            ("Preferred name", &self.preferred_name),
+            ("Language", &self.language),
+            ("Timezone", &self.timezone),
+            ("Risk tolerance", &self.risk_tolerance),
+        ];
+        for (label, value) in fields {
+            if let Some(v) = value {
+                lines.push(format!("- {}: {}", label, v));
+            }
+        }
+        if !self.wallets.is_empty() {
+            lines.push(format!("- Wallets: {}", self.wallets.join(", ")));
+        }
+        if !self.tokens.is_empty() {
+            lines.push(format!("- Favorite tokens: {}", self.tokens.join(", ")));
+        }
+        lines.join("\n")
+    }
+}
+
+/// File-backed store of [`UserProfile`]s.
+pub struct ProfileStore {
+    path: PathBuf,
+    contacts: ContactBook,
+}
+
+impl ProfileStore {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            path: workspace.join("profiles.json"),
+            contacts: ContactBook::new(workspace),
+        }
+    }
+
+    fn key(&self, channel: &str, user_id: &str) -> String {
+        self.contacts.resolve(channel, user_id)
+    }
+
+    fn load(&self) -> Result<HashMap<String, UserProfile>, ProfileError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&content).map_err(|source| ProfileError::Corrupt {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    /// Get the profile for a user, if one exists. An unreadable store is
+    /// logged and treated as having none.
+    pub fn get(&self, channel: &str, user_id: &str) -> Option<UserProfile> {
+        match self.load() {
+            Ok(mut profiles) => profiles.remove(&self.key(channel, user_id)),
+            Err(e) => {
+                warn!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// Apply `f` to a user's profile (creating it if needed) and persist it.
+    pub fn update<F>(&self, channel: &str, user_id: &str, f: F) -> Result<UserProfile, ProfileError>
+    where
+        F: FnOnce(&mut UserProfile),
+    {
+        let mut profiles = self.load()?;
+        let profile = profiles.entry(self.key(channel, user_id)).or_default();
+        f(profile);
+        profile.updated_at = Some(chrono::Utc::now().to_rfc3339());
+        let updated = profile.clone();
+        self.save(&profiles)?;
+        Ok(updated)
+    }
+
+    /// After `identity` was linked to `primary`, keep the profile it built
+    /// up on its own if the primary identity has none yet.
+    pub fn adopt(&self, identity: &str, primary: &str) -> Result<(), ProfileError> {
+        let mut profiles = self.load()?;
+        if profiles.contains_key(primary) {
+            return Ok(());
+        }
+        if let Some(profile) = profiles.remove(identity) {
+            profiles.insert(primary.to_string(), profile);
+            self.save(&profiles)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, profiles: &HashMap<String, UserProfile>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(profiles)?;
+        std::fs::write(&self.path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_profiles_are_per_user() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_profiles");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+
+        let store = ProfileStore::new(&tmp);
+        assert!(store.get("telegram", "1").is_none());
+
+        store
+            .update("telegram", "1", |p| {
+                p.preferred_name = Some("Ada".into());
+                p.tokens.push("SOL".into());
+            })
+            .unwrap();
+        store
+            .update("telegram", "2", |p| p.timezone = Some("UTC".into()))
+            .unwrap();
+
+        let ada = store.get("telegram", "1").unwrap();
+        assert_eq!(ada.preferred_name.as_deref(), Some("Ada"));
+        assert!(ada.context().contains("Favorite tokens: SOL"));
+        assert!(store.get("telegram", "2").unwrap().preferred_name.is_none());
+        assert!(store.get("discord", "1").is_none());
+
+        // A linked account sees the same profile.
+        let contacts = ContactBook::new(&tmp);
+        let code = contacts.start_link("telegram", "1").unwrap();
+        contacts.complete_link(&code, "discord", "1").unwrap();
+        assert_eq!(store.get("discord", "1").unwrap().preferred_name.as_deref(), Some("Ada"));
+
+        // A corrupt file is reported, never replaced by one empty profile.
+        fs::write(tmp.join("profiles.json"), "{ not json").unwrap();
+        assert!(store.get("telegram", "1").is_none());
+        assert!(matches!(
+            store.update("telegram", "1", |p| p.language = Some("en".into())),
+            Err(ProfileError::Corrupt { .. })
+        ));
+        assert!(store.adopt("telegram:1", "discord:1").is_err());
+        assert_eq!(fs::read_to_string(tmp.join("profiles.json")).unwrap(), "{ not json");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -15,7 +15,7 @@ const POLYMARKET_READ_KEYWORDS: &[&str] = &[
     "election", "trump", "biden", "poll", "price", "volume",
     "trending", "sports", "outcome", "probability", "wager",
     "leaderboard", "holders", "comments", "series", "tags",
-    "deport", "deportation", "tariff",
+    "deport", "deportation", "tariff", "scores", "fixtures",
 ];
 
 const POLYMARKET_TRADE_KEYWORDS: &[&str] = &[
@@ -42,7 +42,7 @@ const SYSTEM_KEYWORDS: &[&str] = &[
 
 const RESEARCH_KEYWORDS: &[&str] = &[
     "search", "google", "web", "fetch", "url", "http",
-    "look up", "find out", "research",
+    "look up", "find out", "research", "news", "headlines",
 ];
 
 const PREDICTION_KEYWORDS: &[&str] = &[
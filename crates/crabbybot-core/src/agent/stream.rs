@@ -0,0 +1,100 @@
+//! Partial replies: model text forwarded to the chat while it is written.
+//!
+//! Providers report reply text piece by piece through
+//! [`LlmProvider::chat_stream`](crate::provider::LlmProvider::chat_stream).
+//! [`PartialReply`] collects the pieces and publishes the text so far as
+//! [`OutboundMessage::Partial`] at most once per [`PARTIAL_INTERVAL`], so
+//! channels that edit a message in place stay under platform rate limits.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::bus::events::OutboundMessage;
+use crate::bus::MessageBus;
+
+/// Minimum time between two `Partial` updates for the same reply.
+pub const PARTIAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The reply of one model call as it streams in.
+pub struct PartialReply {
+    bus: Arc<MessageBus>,
+    channel: String,
+    chat_id: String,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    text: String,
+    sent_at: Option<Instant>,
+    unsent: bool,
+}
+
+impl PartialReply {
+    /// Stream to `chat_id` on `channel`. `prefix` is text already written
+    /// before this call, when the call continues a cut-off reply.
+    pub fn new(bus: Arc<MessageBus>, channel: &str, chat_id: &str, prefix: &str) -> Self {
+        Self {
+            bus,
+            channel: channel.to_owned(),
+            chat_id: chat_id.to_owned(),
+            state: Mutex::new(State {
+                text: prefix.to_owned(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Append `delta`, publishing the text unless an update went out less
+    /// than [`PARTIAL_INTERVAL`] ago.
+    pub fn push(&self, delta: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.text.push_str(delta);
+        state.unsent = true;
+        if state.sent_at.is_some_and(|at| at.elapsed() < PARTIAL_INTERVAL) {
+            return;
+        }
+        self.send(&mut state);
+    }
+
+    /// Publish text that arrived since the last update.
+    pub fn flush(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.unsent {
+            self.send(&mut state);
+        }
+    }
+
+    fn send(&self, state: &mut State) {
+        // A full queue only loses an update the next one supersedes.
+        self.bus.try_publish_outbound(OutboundMessage::partial(
+            &self.channel,
+            &self.chat_id,
+            state.text.clone(),
+        ));
+        state.sent_at = Some(Instant::now());
+        state.unsent = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_updates_are_throttled_and_flushed() {
+        let (bus, mut receivers) = MessageBus::new(16);
+        let partial = PartialReply::new(Arc::new(bus), "cli", "direct", "Risks: ");
+        for delta in ["liquidity", ", fees", ", disputes"] {
+            partial.push(delta);
+        }
+        partial.flush();
+        partial.flush();
+
+        let mut updates = Vec::new();
+        while let Ok(OutboundMessage::Partial { content, .. }) = receivers.outbound_rx.try_recv() {
+            updates.push(content);
+        }
+        assert_eq!(updates, ["Risks: liquidity", "Risks: liquidity, fees, disputes"]);
+    }
+}
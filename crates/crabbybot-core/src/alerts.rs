@@ -0,0 +1,299 @@
+//! Deduplication, cooldowns and muting for unattended alerts.
+//!
+//! Monitoring jobs post on every tick, so an unchanged price or balance
+//! would repeat every minute. The cron ticker gates the output of
+//! scheduled `tool_call` jobs, and the bridge gates the agent's replies to
+//! scheduled agent jobs (price alerts the agent checks) and to webhook
+//! events (wallet monitors, pump.fun feeds). Each alert is
+//! fingerprinted by its source and normalized text; a repeat within the
+//! cooldown is suppressed and counted, and the next alert that does go out
+//! says how many were held back ("12 similar alerts in the last hour").
+//!
+//! `/mute alerts 2h` silences a chat's alerts until the given time; muted
+//! alerts are counted the same way. Mutes live in `alert_mutes.json` in the
+//! workspace and are read on every check, so the bridge and the cron ticker
+//! see the same state.
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::clock::{self, SharedClock};
+use crate::config::AlertsConfig;
+
+/// Repeats of one fingerprint since it was last sent.
+#[derive(Debug, Clone)]
+struct Seen {
+    last_sent: DateTime<Utc>,
+    suppressed: u32,
+    first_suppressed: Option<DateTime<Utc>>,
+}
+
+/// Hex fingerprint of an alert: its source plus the text with case and
+/// whitespace normalized.
+pub fn fingerprint(source: &str, text: &str) -> String {
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    format!("{:x}", Sha256::digest(format!("{}\n{}", source, normalized)))
+}
+
+/// `90s`, `30m`, `2h`, `1d` → duration.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let unit = s.chars().last()?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().ok().filter(|n| *n > 0)?;
+    match unit {
+        's' => Some(Duration::seconds(n)),
+        'm' => Some(Duration::minutes(n)),
+        'h' => Some(Duration::hours(n)),
+        'd' => Some(Duration::days(n)),
+        _ => None,
+    }
+}
+
+/// `the last 3 minutes`, `the last hour`, …: the largest whole unit of `d`.
+fn describe(d: Duration) -> String {
+    let (n, unit) = if d.num_days() >= 1 {
+        (d.num_days(), "day")
+    } else if d.num_hours() >= 1 {
+        (d.num_hours(), "hour")
+    } else {
+        (d.num_minutes().max(1), "minute")
+    };
+    if n == 1 {
+        format!("the last {}", unit)
+    } else {
+        format!("the last {} {}s", n, unit)
+    }
+}
+
+/// An alert cleared to go out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Admitted {
+    /// Repeats held back since it last went out, e.g. `12 similar alerts in
+    /// the last hour held back`.
+    pub held_back: Option<String>,
+}
+
+impl Admitted {
+    /// `text` with the held-back note appended.
+    pub fn decorate(&self, text: String) -> String {
+        match &self.held_back {
+            Some(note) => format!("{}\n\n_({})_", text, note),
+            None => text,
+        }
+    }
+}
+
+/// Shared gate for outgoing alerts.
+pub struct AlertManager {
+    mutes_path: PathBuf,
+    default_cooldown: Duration,
+    seen: Mutex<HashMap<(String, String), Seen>>,
+    clock: SharedClock,
+}
+
+impl AlertManager {
+    pub fn new(workspace: &Path, config: &AlertsConfig) -> Self {
+        Self {
+            mutes_path: workspace.join("alert_mutes.json"),
+            default_cooldown: Duration::seconds(config.cooldown_secs as i64),
+            seen: Mutex::default(),
+            clock: clock::system(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn mutes(&self) -> BTreeMap<String, DateTime<Utc>> {
+        std::fs::read_to_string(&self.mutes_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_mutes(&self, mutes: &BTreeMap<String, DateTime<Utc>>) -> std::io::Result<()> {
+        if let Some(parent) = self.mutes_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.mutes_path, serde_json::to_string_pretty(mutes)?)
+    }
+
+    /// Silence alerts for `chat_key` (`channel:chat_id`) for `duration`.
+    /// Returns when the mute ends.
+    pub fn mute(&self, chat_key: &str, duration: Duration) -> std::io::Result<DateTime<Utc>> {
+        let now = self.clock.now();
+        let until = now + duration;
+        let mut mutes = self.mutes();
+        mutes.retain(|_, until| *until > now);
+        mutes.insert(chat_key.to_string(), until);
+        self.save_mutes(&mutes)?;
+        Ok(until)
+    }
+
+    /// Lift a mute. Returns whether the chat was muted.
+    pub fn unmute(&self, chat_key: &str) -> std::io::Result<bool> {
+        let mut mutes = self.mutes();
+        let was_muted = mutes.remove(chat_key).is_some_and(|until| until > self.clock.now());
+        self.save_mutes(&mutes)?;
+        Ok(was_muted)
+    }
+
+    /// When the chat's mute ends, if it is muted.
+    pub fn muted_until(&self, chat_key: &str) -> Option<DateTime<Utc>> {
+        self.mutes().get(chat_key).copied().filter(|until| *until > self.clock.now())
+    }
+
+    /// Decide whether an alert from `source` to `chat_key` goes out. `None`
+    /// when it is a repeat within the cooldown (`cooldown` overrides the
+    /// configured default) or the chat is muted.
+    pub fn admit(&self, chat_key: &str, source: &str, text: &str, cooldown: Option<Duration>) -> Option<Admitted> {
+        self.admit_at(chat_key, source, text, cooldown, self.clock.now())
+    }
+
+    fn admit_at(
+        &self,
+        chat_key: &str,
+        source: &str,
+        text: &str,
+        cooldown: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Option<Admitted> {
+        let cooldown = cooldown.unwrap_or(self.default_cooldown);
+        let key = (chat_key.to_string(), fingerprint(source, text));
+        let muted = self.mutes().get(chat_key).is_some_and(|until| *until > now);
+        let mut seen = self.seen.lock().ok()?;
+        seen.retain(|_, s| now - s.last_sent < cooldown.max(Duration::days(1)));
+
+        if let Some(entry) = seen.get_mut(&key) {
+            if muted || now - entry.last_sent < cooldown {
+                entry.suppressed += 1;
+                entry.first_suppressed.get_or_insert(now);
+                return None;
+            }
+        } else if muted {
+            seen.insert(
+                key,
+                Seen {
+                    last_sent: now - cooldown,
+                    suppressed: 1,
+                    first_suppressed: Some(now),
+                },
+            );
+            return None;
+        }
+
+        let previous = seen.insert(
+            key,
+            Seen {
+                last_sent: now,
+                suppressed: 0,
+                first_suppressed: None,
+            },
+        );
+        let held_back = match previous {
+            Some(Seen {
+                suppressed: n @ 1..,
+                first_suppressed: Some(since),
+                ..
+            }) => Some(format!(
+                "{} similar alert{} in {} held back",
+                n,
+                if n == 1 { "" } else { "s" },
+                describe(now - since)
+            )),
+            _ => None,
+        };
+        Some(Admitted { held_back })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(name: &str) -> (AlertManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_alerts_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = AlertsConfig { cooldown_secs: 600 };
+        (AlertManager::new(&dir, &config), dir)
+    }
+
+    #[test]
+    fn test_cooldown_and_burst_summary() {
+        let (alerts, dir) = manager("cooldown");
+        let t0 = Utc::now();
+        let at = |mins: i64| t0 + Duration::minutes(mins);
+
+        assert!(alerts.admit_at("tg:1", "job1", "SOL  below $100", None, at(0)).is_some());
+        // Same text modulo whitespace/case is a repeat.
+        assert!(alerts.admit_at("tg:1", "job1", "sol below $100", None, at(1)).is_none());
+        assert!(alerts.admit_at("tg:1", "job1", "SOL below $100", None, at(2)).is_none());
+        // Different text, source or chat is not.
+        assert!(alerts.admit_at("tg:1", "job1", "SOL below $90", None, at(3)).is_some());
+        assert!(alerts.admit_at("tg:1", "job2", "SOL below $100", None, at(3)).is_some());
+        assert!(alerts.admit_at("tg:2", "job1", "SOL below $100", None, at(3)).is_some());
+
+        let admitted = alerts.admit_at("tg:1", "job1", "SOL below $100", None, at(11)).unwrap();
+        assert_eq!(
+            admitted.decorate("SOL below $100".into()),
+            "SOL below $100\n\n_(2 similar alerts in the last 10 minutes held back)_"
+        );
+
+        // A per-alert cooldown overrides the default.
+        let short = Some(Duration::minutes(1));
+        assert!(alerts.admit_at("tg:1", "job1", "SOL below $100", short, at(13)).is_some());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mute() {
+        let (alerts, dir) = manager("mute");
+        alerts.mute("tg:1", Duration::hours(2)).unwrap();
+        assert!(alerts.muted_until("tg:1").is_some());
+        assert!(alerts.admit("tg:1", "job1", "alert", None).is_none());
+        assert!(alerts.admit("tg:2", "job1", "alert", None).is_some());
+
+        assert!(alerts.unmute("tg:1").unwrap());
+        assert!(!alerts.unmute("tg:1").unwrap());
+        let note = alerts.admit("tg:1", "job1", "alert", None).unwrap().held_back.unwrap();
+        assert!(note.starts_with("1 similar alert in"), "{}", note);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mute_expires_on_the_injected_clock() {
+        use crate::clock::MockClock;
+        use std::sync::Arc;
+
+        let (alerts, dir) = manager("mute_clock");
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let alerts = alerts.with_clock(clock.clone());
+        alerts.mute("tg:1", Duration::hours(2)).unwrap();
+
+        clock.advance(Duration::minutes(119));
+        assert!(alerts.admit("tg:1", "job1", "alert", None).is_none());
+        clock.advance(Duration::minutes(2));
+        assert!(alerts.muted_until("tg:1").is_none());
+        let note = alerts.admit("tg:1", "job1", "alert", None).unwrap().held_back.unwrap();
+        assert_eq!(note, "1 similar alert in the last 2 minutes held back");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("2h"), Some(Duration::hours(2)));
+        assert_eq!(parse_duration("30m"), Some(Duration::minutes(30)));
+        assert_eq!(parse_duration("1d"), Some(Duration::days(1)));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+}
@@ -0,0 +1,499 @@
+//! Sign-off for risky tool calls: M-of-N approvals with expiry and
+//! escalation.
+//!
+//! Rules in `approvals.rules` pick the mutating calls that must wait (by
+//! tool name, optionally above a USD amount the tool reports through
+//! [`Tool::trade_usd`](crate::tools::Tool::trade_usd)) and who may approve
+//! them. Such a call doesn't run: the registry files a request, posts it to
+//! the chat with Approve / Reject buttons, and tells the agent it is
+//! pending. `/approve <id>` records a vote, and once `required` distinct
+//! approvers agreed the call runs. A single `/reject` cancels it.
+//!
+//! Requests still pending after `expiryMinutes` are cancelled. With
+//! `escalateAfterMinutes` set, a request nobody answered is reposted once to
+//! the `escalateTo` chat. Both happen in the cron ticker's sweep; `/approve`
+//! refuses expired requests on its own too.
+//!
+//! Requests live in `approvals.json` in the workspace and are read on every
+//! call, so the registry, the chat commands and the ticker share one state.
+//! Resolved requests are kept for a week.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::bus::events::Button;
+use crate::config::{ApprovalRule, ApprovalsConfig};
+use crate::tools::ToolContext;
+
+/// How long resolved requests stay in the file.
+const KEEP_RESOLVED_DAYS: i64 = 7;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalError {
+    #[error("no approval request #{0}")]
+    NotFound(String),
+
+    #[error("request #{id} is already {status}")]
+    Closed { id: String, status: Status },
+
+    #[error("you are not an approver for request #{0}")]
+    NotAnApprover(String),
+
+    #[error("you already approved request #{0}")]
+    AlreadyApproved(String),
+
+    #[error("you can't approve your own request #{0}")]
+    OwnRequest(String),
+
+    #[error("can't read {path}: {source}")]
+    Corrupt {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("failed to save approvals: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pending,
+    Approved,
+    Rejected { by: String },
+    Expired,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Pending => f.write_str("pending"),
+            Status::Approved => f.write_str("approved"),
+            Status::Rejected { by } => write!(f, "rejected by {}", by),
+            Status::Expired => f.write_str("expired"),
+        }
+    }
+}
+
+/// One tool call waiting for, or done with, sign-off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub id: String,
+    pub tool: String,
+    pub args: HashMap<String, Value>,
+    /// USD the call commits, when the tool reports it.
+    pub usd: Option<f64>,
+    /// Chat the call was made in; the result is reported there.
+    pub channel: String,
+    pub chat_id: String,
+    pub requested_by: String,
+    pub required: u32,
+    /// Who may approve; empty means anyone. Never the requester.
+    pub approvers: Vec<String>,
+    pub approved_by: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// When to repost the request to the escalation chat; cleared once done.
+    pub escalate_at: Option<DateTime<Utc>>,
+    pub status: Status,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Approval {
+    /// The call in one line: tool, amount and arguments.
+    pub fn summary(&self) -> String {
+        let args: BTreeMap<_, _> = self.args.iter().collect();
+        let usd = self.usd.map(|u| format!(" (${:.2})", u)).unwrap_or_default();
+        format!(
+            "`{}`{} {}",
+            self.tool,
+            usd,
+            serde_json::to_string(&args).unwrap_or_default()
+        )
+    }
+
+    /// The message asking for sign-off.
+    pub fn request_text(&self) -> String {
+        let who = if self.approvers.is_empty() {
+            "anyone here but the requester".to_string()
+        } else {
+            self.approvers.join(", ")
+        };
+        format!(
+            "🔐 **Approval needed** (#{})\n{}\n\nNeeds {} of: {}. Expires {} UTC.",
+            self.id,
+            self.summary(),
+            self.required,
+            who,
+            self.expires_at.format("%H:%M")
+        )
+    }
+
+    pub fn buttons(&self) -> Vec<Button> {
+        vec![
+            Button {
+                text: "Approve ✅".into(),
+                data: Some(format!("/approve {}", self.id)),
+                url: None,
+            },
+            Button {
+                text: "Reject ❌".into(),
+                data: Some(format!("/reject {}", self.id)),
+                url: None,
+            },
+        ]
+    }
+
+    /// Whether `user_id` may vote: one of the approvers, or anyone without
+    /// a list of them, but never the requester, so a call can't sign
+    /// itself off.
+    fn may_vote(&self, user_id: &str) -> Result<(), ApprovalError> {
+        if user_id == self.requested_by {
+            return Err(ApprovalError::OwnRequest(self.id.clone()));
+        }
+        if !self.approvers.is_empty() && !self.approvers.iter().any(|a| a == user_id) {
+            return Err(ApprovalError::NotAnApprover(self.id.clone()));
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, status: Status, now: DateTime<Utc>) {
+        self.status = status;
+        self.resolved_at = Some(now);
+    }
+}
+
+/// Outcome of `/approve`.
+#[derive(Debug)]
+pub enum Vote {
+    /// Counted; more approvals are needed.
+    Recorded { have: usize, need: usize },
+    /// That was the last approval needed: run the call.
+    Granted(Box<Approval>),
+}
+
+/// What the ticker's sweep has to post.
+#[derive(Debug)]
+pub enum Notice {
+    /// Cancelled unanswered; tell the chat it came from.
+    Expired(Approval),
+    /// Repost to the escalation chat.
+    Escalate(Approval),
+}
+
+/// Shared store of approval requests.
+pub struct Approvals {
+    path: PathBuf,
+    config: ApprovalsConfig,
+    lock: Mutex<()>,
+}
+
+impl Approvals {
+    pub fn new(workspace: &Path, config: ApprovalsConfig) -> Self {
+        Self {
+            path: workspace.join("approvals.json"),
+            config,
+            lock: Mutex::default(),
+        }
+    }
+
+    /// The rule a call of `tool` committing `usd` falls under, if any.
+    pub fn rule_for(&self, tool: &str, usd: Option<f64>) -> Option<&ApprovalRule> {
+        self.config.rules.iter().find(|rule| {
+            let name_matches = match rule.tool.strip_suffix('*') {
+                Some(prefix) => tool.starts_with(prefix),
+                None => tool == rule.tool,
+            };
+            name_matches && (rule.min_usd <= 0.0 || usd.is_some_and(|usd| usd >= rule.min_usd))
+        })
+    }
+
+    /// The chat escalations go to, as `(channel, chat_id)`.
+    pub fn escalation_target(&self) -> Option<(&str, &str)> {
+        self.config.escalate_to.split_once(':')
+    }
+
+    /// File a request for a call that `rule` holds back.
+    pub fn request(
+        &self,
+        tool: &str,
+        args: &HashMap<String, Value>,
+        usd: Option<f64>,
+        ctx: &ToolContext,
+        rule: &ApprovalRule,
+    ) -> Result<Approval, ApprovalError> {
+        let now = Utc::now();
+        let mut id = uuid::Uuid::new_v4().simple().to_string();
+        id.truncate(6);
+        let escalate_after = self.config.escalate_after_minutes;
+        let approval = Approval {
+            id,
+            tool: tool.to_string(),
+            args: args.clone(),
+            usd,
+            channel: ctx.channel.clone(),
+            chat_id: ctx.chat_id.clone(),
+            requested_by: ctx.user_id.clone(),
+            required: rule.required.max(1),
+            approvers: rule.approvers.clone(),
+            approved_by: Vec::new(),
+            created_at: now,
+            expires_at: now + Duration::minutes(self.config.expiry_minutes.max(1) as i64),
+            escalate_at: (escalate_after > 0 && self.escalation_target().is_some())
+                .then(|| now + Duration::minutes(escalate_after as i64)),
+            status: Status::Pending,
+            resolved_at: None,
+        };
+        self.update(|all| all.push(approval.clone()))?;
+        Ok(approval)
+    }
+
+    /// Record `user_id`'s approval of request `id`.
+    pub fn approve(&self, id: &str, user_id: &str) -> Result<Vote, ApprovalError> {
+        let now = Utc::now();
+        self.update(|all| {
+            let approval = Self::open(all, id, now)?;
+            approval.may_vote(user_id)?;
+            if approval.approved_by.iter().any(|u| u == user_id) {
+                return Err(ApprovalError::AlreadyApproved(id.to_string()));
+            }
+            approval.approved_by.push(user_id.to_string());
+            let (have, need) = (approval.approved_by.len(), approval.required as usize);
+            if have < need {
+                return Ok(Vote::Recorded { have, need });
+            }
+            approval.resolve(Status::Approved, now);
+            Ok(Vote::Granted(Box::new(approval.clone())))
+        })?
+    }
+
+    /// Cancel request `id` on `user_id`'s word.
+    pub fn reject(&self, id: &str, user_id: &str) -> Result<Approval, ApprovalError> {
+        let now = Utc::now();
+        self.update(|all| {
+            let approval = Self::open(all, id, now)?;
+            approval.may_vote(user_id)?;
+            approval.resolve(Status::Rejected { by: user_id.to_string() }, now);
+            Ok(approval.clone())
+        })?
+    }
+
+    /// Requests still waiting, oldest first.
+    pub fn pending(&self) -> Result<Vec<Approval>, ApprovalError> {
+        let now = Utc::now();
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|a| a.status == Status::Pending && a.expires_at > now)
+            .collect())
+    }
+
+    /// Expire and escalate what is due at `now`, and forget requests
+    /// resolved over a week ago.
+    pub fn sweep(&self, now: DateTime<Utc>) -> std::io::Result<Vec<Notice>> {
+        let result = self.update(|all| {
+            let mut notices = Vec::new();
+            for approval in all.iter_mut().filter(|a| a.status == Status::Pending) {
+                if approval.expires_at <= now {
+                    approval.resolve(Status::Expired, now);
+                    notices.push(Notice::Expired(approval.clone()));
+                } else if approval.escalate_at.is_some_and(|at| at <= now) {
+                    approval.escalate_at = None;
+                    notices.push(Notice::Escalate(approval.clone()));
+                }
+            }
+            let cutoff = now - Duration::days(KEEP_RESOLVED_DAYS);
+            all.retain(|a| a.resolved_at.is_none_or(|at| at > cutoff));
+            notices
+        });
+        match result {
+            Ok(notices) => Ok(notices),
+            Err(ApprovalError::Io(e)) => Err(e),
+            Err(e) => Err(std::io::Error::other(e.to_string())),
+        }
+    }
+
+    /// Request `id`, if it can still be voted on. Marks it expired when its
+    /// time is up.
+    fn open<'a>(all: &'a mut [Approval], id: &str, now: DateTime<Utc>) -> Result<&'a mut Approval, ApprovalError> {
+        let approval = all
+            .iter_mut()
+            .find(|a| a.id == id.trim_start_matches('#'))
+            .ok_or_else(|| ApprovalError::NotFound(id.to_string()))?;
+        if approval.status == Status::Pending && approval.expires_at <= now {
+            approval.resolve(Status::Expired, now);
+        }
+        if approval.status != Status::Pending {
+            return Err(ApprovalError::Closed {
+                id: approval.id.clone(),
+                status: approval.status.clone(),
+            });
+        }
+        Ok(approval)
+    }
+
+    /// The stored requests. A file that doesn't parse is an error rather
+    /// than an empty list, so it is never overwritten.
+    fn load(&self) -> Result<Vec<Approval>, ApprovalError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&content).map_err(|source| ApprovalError::Corrupt {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    /// Apply `f` to the stored requests and save them, even when `f`
+    /// reports an error (it may have expired a request on the way).
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<Approval>) -> T) -> Result<T, ApprovalError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.load()?;
+        let out = f(&mut all);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write aside and rename, so a crash mid-write never leaves a torn file.
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&all).map_err(std::io::Error::other)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approvals(name: &str, config: ApprovalsConfig) -> Approvals {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_approvals_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Approvals::new(&dir, config)
+    }
+
+    fn two_admins() -> ApprovalsConfig {
+        ApprovalsConfig {
+            rules: vec![ApprovalRule {
+                tool: "polymarket_*".into(),
+                min_usd: 500.0,
+                required: 2,
+                approvers: vec!["alice".into(), "bob".into(), "carol".into()],
+            }],
+            escalate_after_minutes: 15,
+            escalate_to: "telegram:-100777".into(),
+            ..Default::default()
+        }
+    }
+
+    fn file(store: &Approvals, usd: f64, requester: &str) -> Approval {
+        let rule = store.rule_for("polymarket_create_order", Some(usd)).unwrap().clone();
+        let ctx = ToolContext::new("telegram", "42").with_user(requester);
+        store
+            .request("polymarket_create_order", &HashMap::new(), Some(usd), &ctx, &rule)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rules_match_by_tool_and_amount() {
+        let store = approvals("rules", two_admins());
+        assert!(store.rule_for("polymarket_create_order", Some(600.0)).is_some());
+        assert!(store.rule_for("polymarket_create_order", Some(100.0)).is_none());
+        assert!(store.rule_for("polymarket_create_order", None).is_none());
+        assert!(store.rule_for("exec", Some(600.0)).is_none());
+    }
+
+    #[test]
+    fn test_two_of_three_approvers() {
+        let store = approvals("votes", two_admins());
+        let id = file(&store, 600.0, "dave").id;
+
+        assert!(matches!(store.approve(&id, "mallory"), Err(ApprovalError::NotAnApprover(_))));
+        assert!(matches!(store.approve(&id, "alice"), Ok(Vote::Recorded { have: 1, need: 2 })));
+        assert!(matches!(store.approve(&id, "alice"), Err(ApprovalError::AlreadyApproved(_))));
+        let Ok(Vote::Granted(approval)) = store.approve(&format!("#{}", id), "bob") else {
+            panic!("expected the second approval to grant the call");
+        };
+        assert_eq!(approval.approved_by, ["alice", "bob"]);
+        assert!(matches!(store.approve(&id, "carol"), Err(ApprovalError::Closed { .. })));
+        assert!(store.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reject_cancels() {
+        let store = approvals("reject", two_admins());
+        let id = file(&store, 900.0, "dave").id;
+        assert_eq!(store.reject(&id, "carol").unwrap().status, Status::Rejected { by: "carol".into() });
+        assert!(matches!(store.approve(&id, "bob"), Err(ApprovalError::Closed { .. })));
+    }
+
+    #[test]
+    fn test_requester_cant_approve_without_approvers() {
+        let store = approvals(
+            "self",
+            ApprovalsConfig {
+                rules: vec![ApprovalRule {
+                    tool: "polymarket_*".into(),
+                    required: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        let id = file(&store, 10.0, "alice").id;
+        assert!(matches!(store.approve(&id, "alice"), Err(ApprovalError::OwnRequest(_))));
+        assert!(matches!(store.reject(&id, "alice"), Err(ApprovalError::OwnRequest(_))));
+        assert!(matches!(store.approve(&id, "bob"), Ok(Vote::Granted(_))));
+    }
+
+    #[test]
+    fn test_requester_cant_approve_as_approver() {
+        let store = approvals("self_approver", two_admins());
+        let id = file(&store, 600.0, "alice").id;
+        assert!(matches!(store.approve(&id, "alice"), Err(ApprovalError::OwnRequest(_))));
+        assert!(matches!(store.approve(&id, "bob"), Ok(Vote::Recorded { have: 1, need: 2 })));
+        assert!(matches!(store.approve(&id, "carol"), Ok(Vote::Granted(_))));
+    }
+
+    #[test]
+    fn test_corrupt_file_is_reported_and_kept() {
+        let store = approvals("corrupt", two_admins());
+        std::fs::create_dir_all(store.path.parent().unwrap()).unwrap();
+        std::fs::write(&store.path, "[{ not json").unwrap();
+        assert!(matches!(store.pending(), Err(ApprovalError::Corrupt { .. })));
+        let rule = store.rule_for("polymarket_create_order", Some(600.0)).unwrap().clone();
+        let ctx = ToolContext::new("telegram", "42").with_user("alice");
+        assert!(store.request("polymarket_create_order", &HashMap::new(), Some(600.0), &ctx, &rule).is_err());
+        assert_eq!(std::fs::read_to_string(&store.path).unwrap(), "[{ not json");
+    }
+
+    #[test]
+    fn test_sweep_escalates_once_then_expires() {
+        let store = approvals("sweep", two_admins());
+        let approval = file(&store, 600.0, "dave");
+        assert!(store.sweep(Utc::now()).unwrap().is_empty());
+
+        let notices = store.sweep(Utc::now() + Duration::minutes(20)).unwrap();
+        assert!(matches!(notices.as_slice(), [Notice::Escalate(a)] if a.id == approval.id));
+        assert!(store.sweep(Utc::now() + Duration::minutes(30)).unwrap().is_empty());
+
+        let notices = store.sweep(Utc::now() + Duration::minutes(61)).unwrap();
+        assert!(matches!(notices.as_slice(), [Notice::Expired(_)]));
+        assert!(matches!(
+            store.approve(&approval.id, "bob"),
+            Err(ApprovalError::Closed { status: Status::Expired, .. })
+        ));
+
+        // Resolved requests are forgotten after a week.
+        store.sweep(Utc::now() + Duration::days(8)).unwrap();
+        assert!(matches!(store.approve(&approval.id, "bob"), Err(ApprovalError::NotFound(_))));
+    }
+}
@@ -0,0 +1,389 @@
+//! Encrypted snapshots of the bot's state.
+//!
+//! A backup is one file holding a zip of the selected [`Part`]s plus a
+//! `backup.json` manifest, encrypted with AES-256-GCM under a key derived
+//! from a passphrase (Argon2id). Unlike config secrets, which the vault
+//! encrypts with a key tied to this machine, a backup can be restored
+//! anywhere the passphrase is known. The vault key travels inside it with
+//! the config, so `vault:` values keep working after a move.
+//!
+//! Restoring writes each file in the archive over its counterpart; files
+//! that exist locally but not in the backup are left alone.
+
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+const MAGIC: &[u8] = b"CRABBYBAK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MANIFEST: &str = "backup.json";
+const VAULT_KEY: &str = "config/vault.key";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("not a CrabbyBot backup")]
+    NotABackup,
+
+    #[error("wrong passphrase, or the backup is corrupted")]
+    Decrypt,
+
+    #[error("backup archive is damaged: {0}")]
+    Archive(String),
+
+    #[error("unknown backup part '{0}' (expected config, sessions, memory, cron, knowledge or profiles)")]
+    UnknownPart(String),
+}
+
+/// A slice of state that can be backed up and restored on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Part {
+    /// The config file and the vault key that decrypts its secrets.
+    Config,
+    /// Conversation histories.
+    Sessions,
+    /// Long-term memory files.
+    Memory,
+    /// The scheduled job store.
+    Cron,
+    /// The prediction knowledge graph.
+    Knowledge,
+    /// User profiles and linked contacts.
+    Profiles,
+}
+
+impl Part {
+    pub const ALL: [Part; 6] = [
+        Part::Config,
+        Part::Sessions,
+        Part::Memory,
+        Part::Cron,
+        Part::Knowledge,
+        Part::Profiles,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Part::Config => "config",
+            Part::Sessions => "sessions",
+            Part::Memory => "memory",
+            Part::Cron => "cron",
+            Part::Knowledge => "knowledge",
+            Part::Profiles => "profiles",
+        }
+    }
+
+    /// `(archive path, local path)` of every file or directory in the part.
+    fn sources(self, at: &Locations) -> Vec<(&'static str, PathBuf)> {
+        let ws = &at.workspace;
+        match self {
+            Part::Config => vec![
+                ("config/config.json", at.config_file.clone()),
+                (VAULT_KEY, at.home.join("vault.key")),
+            ],
+            Part::Sessions => vec![("sessions", at.home.join("sessions"))],
+            Part::Memory => vec![("workspace/memory", ws.join("memory"))],
+            Part::Cron => vec![
+                ("workspace/cron.json", ws.join("cron.json")),
+                ("workspace/cron.log", ws.join("cron.log")),
+            ],
+            Part::Knowledge => vec![("workspace/prediction_graph.json", ws.join("prediction_graph.json"))],
+            Part::Profiles => vec![
+                ("workspace/profiles.json", ws.join("profiles.json")),
+                ("workspace/contacts.json", ws.join("contacts.json")),
+            ],
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = BackupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Part::ALL
+            .into_iter()
+            .find(|p| p.name() == s.trim().to_lowercase())
+            .ok_or_else(|| BackupError::UnknownPart(s.to_string()))
+    }
+}
+
+/// `config,sessions` → parts; an empty list means all of them.
+pub fn parse_parts(list: &str) -> Result<Vec<Part>, BackupError> {
+    let parts: Vec<Part> = list
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()?;
+    Ok(if parts.is_empty() { Part::ALL.to_vec() } else { parts })
+}
+
+/// Where the state lives on this machine.
+#[derive(Debug, Clone)]
+pub struct Locations {
+    pub config_file: PathBuf,
+    /// `~/.CrabbyBot`: vault key and sessions.
+    pub home: PathBuf,
+    pub workspace: PathBuf,
+}
+
+impl Locations {
+    pub fn current(config: &Config) -> Self {
+        Self {
+            config_file: Config::existing_path().unwrap_or_else(Config::default_path),
+            home: Config::config_dir(),
+            workspace: config.workspace_path(),
+        }
+    }
+}
+
+/// What a backup holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub parts: Vec<Part>,
+    pub files: usize,
+}
+
+pub(crate) fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], BackupError> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| BackupError::Archive(e.to_string()))?;
+    Ok(key)
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, BackupError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt)?).map_err(|_| BackupError::Decrypt)?;
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), plaintext)
+        .map_err(|_| BackupError::Decrypt)?;
+    Ok([MAGIC, &salt, &nonce, &ciphertext].concat())
+}
+
+fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, BackupError> {
+    let rest = data.strip_prefix(MAGIC).ok_or(BackupError::NotABackup)?;
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        return Err(BackupError::NotABackup);
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| BackupError::NotABackup)?;
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, salt)?).map_err(|_| BackupError::Decrypt)?;
+    cipher
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .map_err(|_| BackupError::Decrypt)
+}
+
+/// Every file under `path` (or `path` itself), with its path relative to it.
+pub(crate) fn walk(path: &Path) -> Vec<(String, PathBuf)> {
+    if path.is_file() {
+        return vec![(String::new(), path.to_path_buf())];
+    }
+    let mut files = Vec::new();
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let p = entry.path();
+            if p.is_dir() {
+                stack.push(p);
+            } else if let Ok(rel) = p.strip_prefix(path) {
+                files.push((rel.to_string_lossy().replace('\\', "/"), p));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Write an encrypted backup of `parts` to `output`. Parts with nothing on
+/// disk are recorded as empty.
+pub fn create(at: &Locations, parts: &[Part], passphrase: &str, output: &Path) -> Result<Manifest, BackupError> {
+    let archive_err = |e: zip::result::ZipError| BackupError::Archive(e.to_string());
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut files = 0;
+    for part in parts {
+        for (name, path) in part.sources(at) {
+            for (rel, file) in walk(&path) {
+                let entry = if rel.is_empty() { name.to_string() } else { format!("{}/{}", name, rel) };
+                zip.start_file(entry, options).map_err(archive_err)?;
+                zip.write_all(&std::fs::read(&file)?)?;
+                files += 1;
+            }
+        }
+    }
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now(),
+        parts: parts.to_vec(),
+        files,
+    };
+    zip.start_file(MANIFEST, options).map_err(archive_err)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).unwrap_or_default())?;
+    let plaintext = zip.finish().map_err(archive_err)?.into_inner();
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output, encrypt(&plaintext, passphrase)?)?;
+    Ok(manifest)
+}
+
+/// A decrypted backup, ready to restore from.
+pub struct Backup {
+    archive: zip::ZipArchive<Cursor<Vec<u8>>>,
+    pub manifest: Manifest,
+}
+
+impl Backup {
+    /// Read and decrypt the backup at `path`.
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self, BackupError> {
+        let plaintext = decrypt(&std::fs::read(path)?, passphrase)?;
+        let mut archive =
+            zip::ZipArchive::new(Cursor::new(plaintext)).map_err(|e| BackupError::Archive(e.to_string()))?;
+        let manifest = {
+            let mut entry = archive
+                .by_name(MANIFEST)
+                .map_err(|_| BackupError::Archive(format!("{} is missing", MANIFEST)))?;
+            let mut text = String::new();
+            entry.read_to_string(&mut text)?;
+            serde_json::from_str(&text).map_err(|e| BackupError::Archive(e.to_string()))?
+        };
+        Ok(Self { archive, manifest })
+    }
+
+    /// Write the files of `part` to their places under `at`. Returns how many
+    /// files were restored.
+    pub fn restore(&mut self, part: Part, at: &Locations) -> Result<usize, BackupError> {
+        let sources = part.sources(at);
+        let mut restored = 0;
+        for i in 0..self.archive.len() {
+            let mut entry = self.archive.by_index(i).map_err(|e| BackupError::Archive(e.to_string()))?;
+            // Reject absolute paths and `..` before mapping the entry anywhere.
+            let Some(name) = entry.enclosed_name().map(|p| p.to_string_lossy().replace('\\', "/")) else {
+                continue;
+            };
+            let target = sources.iter().find_map(|(prefix, local)| {
+                if name == *prefix {
+                    Some(local.clone())
+                } else {
+                    name.strip_prefix(&format!("{}/", prefix)).map(|rel| local.join(rel))
+                }
+            });
+            let Some(target) = target else { continue };
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if name == VAULT_KEY {
+                write_private(&target, &data)?;
+            } else {
+                std::fs::write(&target, data)?;
+            }
+            restored += 1;
+        }
+        Ok(restored)
+    }
+}
+
+/// Write a file only its owner can read, like the vault key. An existing
+/// file is narrowed to that before the new contents go in.
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        (&file).write_all(data)
+    }
+    #[cfg(not(unix))]
+    options.open(path)?.write_all(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locations(root: &Path) -> Locations {
+        Locations {
+            config_file: root.join("home/config.json"),
+            home: root.join("home"),
+            workspace: root.join("workspace"),
+        }
+    }
+
+    #[test]
+    fn test_create_and_selective_restore() {
+        let root = std::env::temp_dir().join(format!("CrabbyBot_test_backup_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let src = locations(&root.join("src"));
+        let write = |path: PathBuf, text: &str| {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        };
+        write(src.config_file.clone(), r#"{"agents": {}}"#);
+        write(src.home.join("vault.key"), "k");
+        write(src.home.join("sessions/cli_direct.jsonl"), "{}");
+        write(src.workspace.join("memory/MEMORY.md"), "likes SOL");
+        write(src.workspace.join("memory/notes/2026.md"), "nested");
+        write(src.workspace.join("cron.json"), "[]");
+
+        let file = root.join("bot.backup");
+        let manifest = create(&src, &Part::ALL, "hunter2", &file).unwrap();
+        assert_eq!(manifest.files, 6);
+        assert!(!std::fs::read(&file).unwrap().windows(9).any(|w| w == b"likes SOL"));
+
+        assert!(matches!(Backup::open(&file, "wrong"), Err(BackupError::Decrypt)));
+        let mut backup = Backup::open(&file, "hunter2").unwrap();
+        assert_eq!(backup.manifest.parts, Part::ALL);
+
+        let dst = locations(&root.join("dst"));
+        assert_eq!(backup.restore(Part::Memory, &dst).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(dst.workspace.join("memory/notes/2026.md")).unwrap(),
+            "nested"
+        );
+        assert!(!dst.config_file.exists());
+        assert!(!dst.workspace.join("cron.json").exists());
+
+        assert_eq!(backup.restore(Part::Config, &dst).unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(dst.home.join("vault.key")).unwrap(), "k");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dst.home.join("vault.key")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_parts() {
+        assert_eq!(parse_parts("").unwrap(), Part::ALL);
+        assert_eq!(parse_parts("Cron, memory").unwrap(), [Part::Cron, Part::Memory]);
+        assert!(matches!(parse_parts("kb"), Err(BackupError::UnknownPart(_))));
+        assert!(matches!(decrypt(b"PK\x03\x04", "x"), Err(BackupError::NotABackup)));
+    }
+}
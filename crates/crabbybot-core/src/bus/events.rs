@@ -2,6 +2,9 @@
 //!
 //! Defines the messages that flow between channels and the agent core.
 
+use serde::Serialize;
+use std::path::PathBuf;
+
 /// An inbound message from a chat channel to the agent.
 #[derive(Debug, Clone)]
 pub struct InboundMessage {
@@ -11,8 +14,14 @@ pub struct InboundMessage {
     pub chat_id: String,
     /// User identifier.
     pub user_id: String,
+    /// Display name of the sender, when the transport knows it. Used to
+    /// attribute messages in group digests.
+    pub sender_name: Option<String>,
     /// Message text content.
     pub content: String,
+    /// Platform ID of the message, so the reply can quote it. `None` for
+    /// system messages and button presses.
+    pub message_id: Option<String>,
     /// Optional media attachment paths (images, voice, etc.).
     pub media: Vec<String>,
     /// Whether this is a system-originated message (e.g., subagent result).
@@ -25,6 +34,9 @@ pub struct InboundMessage {
 /// - `Reply`    — final text response, always rendered.
 /// - `Typing`   — show a "typing…" indicator (best-effort, ignore if unsupported).
 /// - `Progress` — intermediate status line shown while tools are executing.
+/// - `Partial`  — the reply so far while the model is still writing it
+///   (best-effort, ignore if unsupported); the `Reply` follows.
+/// - `Attachment` — a file to send, e.g. a rendered chart.
 #[derive(Debug, Clone)]
 pub enum OutboundMessage {
     /// Final text reply from the agent.
@@ -33,6 +45,13 @@ pub enum OutboundMessage {
         chat_id: String,
         content: String,
         buttons: Option<Vec<Button>>,
+        /// Platform ID of the user message this answers. Transports that
+        /// support it quote that message (a Telegram reply in groups, a
+        /// Discord message reference).
+        reply_to_message_id: Option<String>,
+        /// Chunks of `content` an earlier attempt already delivered; the
+        /// channel sends only the rest. Set by the dispatcher on retries.
+        sent_chunks: usize,
     },
     /// Ask the channel to display a "typing…" indicator.
     Typing { channel: String, chat_id: String },
@@ -42,6 +61,78 @@ pub enum OutboundMessage {
         chat_id: String,
         content: String,
     },
+    /// Text of the model reply being written, as far as it has arrived.
+    /// Each one carries the whole text so far, so a channel can show the
+    /// latest and drop the rest. Starts over with every model call.
+    Partial {
+        channel: String,
+        chat_id: String,
+        content: String,
+    },
+    /// A file from the workspace, sent as a photo when it is an image.
+    Attachment {
+        channel: String,
+        chat_id: String,
+        path: PathBuf,
+        caption: String,
+    },
+}
+
+/// What a channel reports after trying to deliver a `Reply`:
+/// the platform message ID on success, or what went wrong.
+pub type DeliveryResult = Result<Option<String>, DeliveryError>;
+
+/// A failed delivery, and how far it got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryError {
+    pub error: String,
+    /// Chunks of the reply that were delivered before the failure,
+    /// counting those an earlier attempt sent.
+    pub sent_chunks: usize,
+}
+
+impl DeliveryError {
+    /// A failure after the first `sent_chunks` chunks went out.
+    pub fn after(sent_chunks: usize, error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            sent_chunks,
+        }
+    }
+}
+
+impl From<String> for DeliveryError {
+    fn from(error: String) -> Self {
+        Self::after(0, error)
+    }
+}
+
+impl std::fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.error)
+    }
+}
+
+/// Outcome of delivering an outbound `Reply`, published on the bus by the
+/// dispatcher for channels that report delivery results.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryStatus {
+    pub channel: String,
+    pub chat_id: String,
+    /// Platform message ID of the (last) delivered message, if known.
+    pub message_id: Option<String>,
+    /// Error from the final attempt; `None` if delivery succeeded.
+    pub error: Option<String>,
+    /// Number of send attempts made.
+    pub attempts: u32,
+    /// RFC 3339 timestamp of the final attempt.
+    pub timestamp: String,
+}
+
+impl DeliveryStatus {
+    pub fn is_delivered(&self) -> bool {
+        self.error.is_none()
+    }
 }
 
 /// A UI button that can be attached to a message.
@@ -64,6 +155,8 @@ impl OutboundMessage {
             chat_id: chat_id.into(),
             content: content.into(),
             buttons: None,
+            reply_to_message_id: None,
+            sent_chunks: 0,
         }
     }
 
@@ -79,9 +172,20 @@ impl OutboundMessage {
             chat_id: chat_id.into(),
             content: content.into(),
             buttons: Some(buttons),
+            reply_to_message_id: None,
+            sent_chunks: 0,
         }
     }
 
+    /// Make a `Reply` quote the user message `message_id`. Other variants
+    /// are returned unchanged.
+    pub fn in_reply_to(mut self, message_id: Option<String>) -> Self {
+        if let Self::Reply { reply_to_message_id, .. } = &mut self {
+            *reply_to_message_id = message_id;
+        }
+        self
+    }
+
     /// Convenience: create a `Typing` message.
     pub fn typing(channel: impl Into<String>, chat_id: impl Into<String>) -> Self {
         Self::Typing {
@@ -103,12 +207,42 @@ impl OutboundMessage {
         }
     }
 
+    /// Convenience: create a `Partial` message.
+    pub fn partial(
+        channel: impl Into<String>,
+        chat_id: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        Self::Partial {
+            channel: channel.into(),
+            chat_id: chat_id.into(),
+            content: content.into(),
+        }
+    }
+
+    /// Convenience: create an `Attachment` message.
+    pub fn attachment(
+        channel: impl Into<String>,
+        chat_id: impl Into<String>,
+        path: impl Into<PathBuf>,
+        caption: impl Into<String>,
+    ) -> Self {
+        Self::Attachment {
+            channel: channel.into(),
+            chat_id: chat_id.into(),
+            path: path.into(),
+            caption: caption.into(),
+        }
+    }
+
     /// Extract the channel name regardless of variant.
     pub fn channel(&self) -> &str {
         match self {
             Self::Reply { channel, .. } => channel,
             Self::Typing { channel, .. } => channel,
             Self::Progress { channel, .. } => channel,
+            Self::Partial { channel, .. } => channel,
+            Self::Attachment { channel, .. } => channel,
         }
     }
 
@@ -118,10 +252,22 @@ impl OutboundMessage {
             Self::Reply { chat_id, .. } => chat_id,
             Self::Typing { chat_id, .. } => chat_id,
             Self::Progress { chat_id, .. } => chat_id,
+            Self::Partial { chat_id, .. } => chat_id,
+            Self::Attachment { chat_id, .. } => chat_id,
         }
     }
 }
 
+/// Generate a short correlation ID for one conversation turn.
+///
+/// Attached as `request_id` to the span covering the turn so every agent,
+/// provider, and tool log line it produces can be grouped together.
+pub fn new_request_id() -> String {
+    let mut id = uuid::Uuid::new_v4().simple().to_string();
+    id.truncate(12);
+    id
+}
+
 impl InboundMessage {
     /// Create a simple CLI inbound message.
     pub fn cli(content: &str) -> Self {
@@ -129,7 +275,9 @@ impl InboundMessage {
             channel: "cli".into(),
             chat_id: "direct".into(),
             user_id: "user".into(),
+            sender_name: None,
             content: content.into(),
+            message_id: None,
             media: Vec::new(),
             is_system: false,
         }
@@ -151,6 +299,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reply_quotes_trigger() {
+        let msg = OutboundMessage::reply("telegram", "-100", "Hi").in_reply_to(Some("42".into()));
+        match msg {
+            OutboundMessage::Reply { reply_to_message_id, .. } => {
+                assert_eq!(reply_to_message_id.as_deref(), Some("42"))
+            }
+            _ => panic!("Expected Reply variant"),
+        }
+        let typing = OutboundMessage::typing("telegram", "-100").in_reply_to(Some("42".into()));
+        assert!(matches!(typing, OutboundMessage::Typing { .. }));
+    }
+
     #[test]
     fn test_typing_variant() {
         let msg = OutboundMessage::typing("telegram", "chat123");
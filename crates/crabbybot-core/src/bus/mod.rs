@@ -9,15 +9,23 @@
 
 pub mod events;
 
-use events::{InboundMessage, OutboundMessage};
+use events::{DeliveryError, DeliveryResult, DeliveryStatus, InboundMessage, OutboundMessage};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, error};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::{debug, error, warn};
 
 /// Callback type for outbound message subscribers.
-type OutboundCallback =
-    Box<dyn Fn(OutboundMessage) -> futures::future::BoxFuture<'static, ()> + Send + Sync>;
+///
+/// Resolves to `None` for subscribers that don't report delivery results.
+type OutboundCallback = Box<
+    dyn Fn(OutboundMessage) -> futures::future::BoxFuture<'static, Option<DeliveryResult>>
+        + Send
+        + Sync,
+>;
 
 /// Shared subscriber map — can be cloned and read without locking the bus.
 pub type SubscriberMap = Arc<RwLock<HashMap<String, Vec<OutboundCallback>>>>;
@@ -31,6 +39,7 @@ pub struct MessageBus {
     inbound_tx: mpsc::Sender<InboundMessage>,
     outbound_tx: mpsc::Sender<OutboundMessage>,
     subscribers: SubscriberMap,
+    delivery_tx: broadcast::Sender<DeliveryStatus>,
 }
 
 pub struct MessageBusReceivers {
@@ -43,12 +52,14 @@ impl MessageBus {
     pub fn new(capacity: usize) -> (Self, MessageBusReceivers) {
         let (inbound_tx, inbound_rx) = mpsc::channel(capacity);
         let (outbound_tx, outbound_rx) = mpsc::channel(capacity);
+        let (delivery_tx, _) = broadcast::channel(capacity);
 
         (
             Self {
                 inbound_tx,
                 outbound_tx,
                 subscribers: Arc::new(RwLock::new(HashMap::new())),
+                delivery_tx,
             },
             MessageBusReceivers {
                 inbound_rx,
@@ -69,11 +80,29 @@ impl MessageBus {
         }
     }
 
+    /// Publish an outbound message without waiting, dropping it when the
+    /// queue is full. For updates a later message supersedes anyway.
+    pub fn try_publish_outbound(&self, msg: OutboundMessage) {
+        if let Err(e) = self.outbound_tx.try_send(msg) {
+            debug!("Dropped outbound message: {}", e);
+        }
+    }
+
     /// Get a clone of the subscriber map for use in dispatch or registration.
     pub fn subscribers(&self) -> SubscriberMap {
         Arc::clone(&self.subscribers)
     }
 
+    /// Sender for [`DeliveryStatus`] events, handed to the dispatcher.
+    pub fn delivery_sender(&self) -> broadcast::Sender<DeliveryStatus> {
+        self.delivery_tx.clone()
+    }
+
+    /// Receive [`DeliveryStatus`] events for replies sent from now on.
+    pub fn subscribe_delivery(&self) -> broadcast::Receiver<DeliveryStatus> {
+        self.delivery_tx.subscribe()
+    }
+
     /// Subscribe to outbound messages for a specific channel.
     ///
     /// The callback receives *all* `OutboundMessage` variants for the channel;
@@ -86,12 +115,54 @@ impl MessageBus {
         F: Fn(OutboundMessage) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = ()> + Send + 'static,
     {
-        let boxed: OutboundCallback = Box::new(move |msg| Box::pin(callback(msg)));
+        let callback = Arc::new(callback);
+        let boxed: OutboundCallback = Box::new(move |msg| {
+            let callback = Arc::clone(&callback);
+            Box::pin(async move {
+                callback(msg).await;
+                None
+            })
+        });
+        let mut subs = self.subscribers.write().await;
+        subs.entry(channel.to_string()).or_default().push(boxed);
+    }
+
+    /// Like [`subscribe_outbound`](Self::subscribe_outbound), but the callback
+    /// reports whether a `Reply` was delivered. Failed replies are retried per
+    /// the dispatcher's [`DeliveryPolicy`] and every outcome is published as a
+    /// [`DeliveryStatus`]. The result is ignored for other variants.
+    pub async fn subscribe_outbound_tracked<F, Fut>(&self, channel: &str, callback: F)
+    where
+        F: Fn(OutboundMessage) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = DeliveryResult> + Send + 'static,
+    {
+        let boxed: OutboundCallback = Box::new(move |msg| {
+            let fut = callback(msg);
+            Box::pin(async move { Some(fut.await) })
+        });
         let mut subs = self.subscribers.write().await;
         subs.entry(channel.to_string()).or_default().push(boxed);
     }
 }
 
+/// Retry policy for replies whose delivery failed.
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryPolicy {
+    /// Total attempts per reply, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry.
+    pub backoff: Duration,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
 /// Dispatch outbound messages to subscribers.
 ///
 /// Routes each `OutboundMessage` to all callbacks registered for
@@ -100,23 +171,191 @@ impl MessageBus {
 ///
 /// This is a **free function** — it does not hold the bus mutex, only the
 /// shared subscriber map. Run it as a background task via `tokio::spawn`.
+/// Failed replies are not retried.
 pub async fn dispatch_outbound(
+    subscribers: SubscriberMap,
+    outbound_rx: mpsc::Receiver<OutboundMessage>,
+) {
+    let policy = DeliveryPolicy {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    dispatch_outbound_tracked(subscribers, outbound_rx, None, policy).await
+}
+
+/// [`dispatch_outbound`] with delivery tracking.
+///
+/// Replies handled by tracked subscribers are retried per `policy` when they
+/// fail, and the final outcome is sent to `delivery_tx`. Retries run in their
+/// own task, so other messages keep flowing meanwhile, and resend only the
+/// chunks of the reply that were not delivered.
+pub async fn dispatch_outbound_tracked(
     subscribers: SubscriberMap,
     mut outbound_rx: mpsc::Receiver<OutboundMessage>,
+    delivery_tx: Option<broadcast::Sender<DeliveryStatus>>,
+    policy: DeliveryPolicy,
 ) {
     while let Some(msg) = outbound_rx.recv().await {
         let channel = msg.channel().to_owned();
-        let subs = subscribers.read().await;
-        if let Some(callbacks) = subs.get(&channel) {
+        let results = {
+            let subs = subscribers.read().await;
+            let Some(callbacks) = subs.get(&channel) else {
+                debug!(channel = %channel, "No subscribers for outbound message");
+                continue;
+            };
+            let mut results = Vec::with_capacity(callbacks.len());
             for callback in callbacks {
-                let fut = callback(msg.clone());
-                if let Err(e) = tokio::time::timeout(std::time::Duration::from_secs(10), fut).await
-                {
-                    error!(channel = %channel, "Outbound dispatch timed out: {}", e);
+                results.push(deliver(callback, msg.clone()).await);
+            }
+            results
+        };
+        if !matches!(msg, OutboundMessage::Reply { .. }) {
+            continue;
+        }
+
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Some(Err(error)) if policy.max_attempts > 1 => {
+                    let retry = Retry {
+                        subscribers: Arc::clone(&subscribers),
+                        msg: msg.clone(),
+                        index,
+                        delivery_tx: delivery_tx.clone(),
+                        policy,
+                    };
+                    tokio::spawn(retry.run(error));
+                }
+                Some(result) => report(delivery_tx.as_ref(), &msg, result, 1),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Hand `msg` to `callback`, giving up after ten seconds.
+async fn deliver(callback: &OutboundCallback, msg: OutboundMessage) -> Option<DeliveryResult> {
+    let channel = msg.channel().to_owned();
+    match tokio::time::timeout(Duration::from_secs(10), callback(msg)).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!(channel = %channel, "Outbound dispatch timed out: {}", e);
+            Some(Err(format!("timed out: {}", e).into()))
+        }
+    }
+}
+
+/// The retries of one reply to one subscriber, after its first attempt
+/// failed.
+struct Retry {
+    subscribers: SubscriberMap,
+    msg: OutboundMessage,
+    /// Position of the subscriber among its channel's callbacks.
+    index: usize,
+    delivery_tx: Option<broadcast::Sender<DeliveryStatus>>,
+    policy: DeliveryPolicy,
+}
+
+impl Retry {
+    async fn run(mut self, mut error: DeliveryError) {
+        let channel = self.msg.channel().to_owned();
+        let mut attempts = 1;
+        let result = loop {
+            let delay = self.policy.backoff * 2u32.saturating_pow(attempts - 1);
+            warn!(
+                channel = %channel,
+                attempt = attempts,
+                sent_chunks = error.sent_chunks,
+                "Reply delivery failed, retrying in {:?}: {}",
+                delay,
+                error
+            );
+            // The subscriber map is not held while waiting.
+            tokio::time::sleep(delay).await;
+            if let OutboundMessage::Reply { sent_chunks, .. } = &mut self.msg {
+                *sent_chunks = (*sent_chunks).max(error.sent_chunks);
+            }
+            attempts += 1;
+            let result = {
+                let subs = self.subscribers.read().await;
+                match subs.get(&channel).and_then(|callbacks| callbacks.get(self.index)) {
+                    Some(callback) => deliver(callback, self.msg.clone()).await,
+                    None => Some(Err(error.clone())),
+                }
+            };
+            match result {
+                Some(Err(e)) if attempts < self.policy.max_attempts => error = e,
+                Some(result) => break result,
+                None => break Ok(None),
+            }
+        };
+        report(self.delivery_tx.as_ref(), &self.msg, result, attempts);
+    }
+}
+
+/// Publish the final outcome of delivering the reply `msg`.
+fn report(
+    delivery_tx: Option<&broadcast::Sender<DeliveryStatus>>,
+    msg: &OutboundMessage,
+    result: DeliveryResult,
+    attempts: u32,
+) {
+    let Some(tx) = delivery_tx else {
+        return;
+    };
+    let (message_id, error) = match result {
+        Ok(id) => (id, None),
+        Err(e) => (None, Some(e.error)),
+    };
+    // No receivers is fine — tracking is opt-in.
+    let _ = tx.send(DeliveryStatus {
+        channel: msg.channel().to_owned(),
+        chat_id: msg.chat_id().to_owned(),
+        message_id,
+        error,
+        attempts,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    });
+}
+
+/// Append every [`DeliveryStatus`] to `path` as JSON lines until the bus closes.
+pub async fn record_deliveries(mut rx: broadcast::Receiver<DeliveryStatus>, path: PathBuf) {
+    if let Some(parent) = path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+    loop {
+        let status = match rx.recv().await {
+            Ok(status) => status,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Delivery log lagged, {} events dropped", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if !status.is_delivered() {
+            error!(
+                channel = %status.channel,
+                chat_id = %status.chat_id,
+                attempts = status.attempts,
+                "Reply not delivered: {}",
+                status.error.as_deref().unwrap_or_default()
+            );
+        }
+        let Ok(mut line) = serde_json::to_string(&status) else {
+            continue;
+        };
+        line.push('\n');
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await;
+        match file {
+            Ok(mut f) => {
+                if let Err(e) = f.write_all(line.as_bytes()).await {
+                    warn!("Failed to write delivery log: {}", e);
                 }
             }
-        } else {
-            debug!(channel = %channel, "No subscribers for outbound message");
+            Err(e) => warn!("Failed to open delivery log {}: {}", path.display(), e),
         }
     }
 }
@@ -210,4 +449,111 @@ mod tests {
         drop(bus);
         let _ = dispatch_handle.await;
     }
+
+    #[tokio::test]
+    async fn test_tracked_delivery_retries_and_reports() {
+        let (bus, receivers) = MessageBus::new(16);
+        let mut statuses = bus.subscribe_delivery();
+
+        // Fail the first attempt, succeed on the second.
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let calls_cb = Arc::clone(&calls);
+        bus.subscribe_outbound_tracked("flaky", move |_msg| {
+            let n = calls_cb.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if n == 0 {
+                    Err("network down".to_string().into())
+                } else {
+                    Ok(Some("msg-7".to_string()))
+                }
+            }
+        })
+        .await;
+
+        let policy = DeliveryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(1),
+        };
+        let dispatch_handle = tokio::spawn(dispatch_outbound_tracked(
+            bus.subscribers(),
+            receivers.outbound_rx,
+            Some(bus.delivery_sender()),
+            policy,
+        ));
+
+        bus.publish_outbound(OutboundMessage::reply("flaky", "c1", "hi"))
+            .await;
+        let status = statuses.recv().await.unwrap();
+        assert!(status.is_delivered());
+        assert_eq!(status.attempts, 2);
+        assert_eq!(status.message_id.as_deref(), Some("msg-7"));
+
+        drop(bus);
+        let _ = dispatch_handle.await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_resumes_after_sent_chunks_without_blocking() {
+        let (bus, receivers) = MessageBus::new(16);
+        let mut statuses = bus.subscribe_delivery();
+
+        // The first attempt gets two chunks out, then fails.
+        let skipped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let skipped_cb = Arc::clone(&skipped);
+        bus.subscribe_outbound_tracked("flaky", move |msg| {
+            let OutboundMessage::Reply { sent_chunks, .. } = msg else {
+                unreachable!()
+            };
+            let first = {
+                let mut skipped = skipped_cb.lock().unwrap();
+                skipped.push(sent_chunks);
+                skipped.len() == 1
+            };
+            async move {
+                if first {
+                    Err(DeliveryError::after(2, "flood wait"))
+                } else {
+                    Ok(None)
+                }
+            }
+        })
+        .await;
+        let (other_tx, mut other_rx) = mpsc::channel(1);
+        bus.subscribe_outbound_tracked("other", move |_msg| {
+            let other_tx = other_tx.clone();
+            async move {
+                let _ = other_tx.send(()).await;
+                Ok(None)
+            }
+        })
+        .await;
+
+        let policy = DeliveryPolicy {
+            max_attempts: 2,
+            backoff: Duration::from_millis(200),
+        };
+        let dispatch_handle = tokio::spawn(dispatch_outbound_tracked(
+            bus.subscribers(),
+            receivers.outbound_rx,
+            Some(bus.delivery_sender()),
+            policy,
+        ));
+
+        bus.publish_outbound(OutboundMessage::reply("flaky", "c1", "long reply")).await;
+        bus.publish_outbound(OutboundMessage::reply("other", "c2", "hi")).await;
+        // The other channel is served while the retry waits out its backoff.
+        other_rx.recv().await.unwrap();
+        assert_eq!(*skipped.lock().unwrap(), vec![0]);
+
+        let mut flaky = statuses.recv().await.unwrap();
+        if flaky.channel == "other" {
+            flaky = statuses.recv().await.unwrap();
+        }
+        assert!(flaky.is_delivered());
+        assert_eq!(flaky.attempts, 2);
+        assert_eq!(*skipped.lock().unwrap(), vec![0, 2]);
+
+        drop(bus);
+        let _ = dispatch_handle.await;
+    }
 }
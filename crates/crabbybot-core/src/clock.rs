@@ -0,0 +1,121 @@
+//! Time source for the scheduling subsystems.
+//!
+//! The cron service, heartbeats and the alert gate read the time and wait
+//! through a [`Clock`] instead of calling `Utc::now()` and
+//! `tokio::time::sleep` directly. Production code uses [`SystemClock`];
+//! tests (and backtests) inject a [`MockClock`] and move it forward with
+//! [`MockClock::advance`], so a week of schedules runs in milliseconds and
+//! always the same way.
+//!
+//! ```
+//! use chrono::{Duration, TimeZone, Utc};
+//! use crabbybot_core::clock::{Clock, MockClock};
+//!
+//! let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 5, 9, 0, 0).unwrap());
+//! clock.advance(Duration::hours(2));
+//! assert_eq!(clock.now(), Utc.with_ymd_and_hms(2026, 1, 5, 11, 0, 0).unwrap());
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Reads the time and waits for it to pass.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Return once `duration` has passed on this clock.
+    async fn sleep(&self, duration: std::time::Duration);
+}
+
+/// Clock handle shared between services.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The system clock, as a [`SharedClock`].
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Wall-clock time and real sleeps.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: std::time::Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Sleeps return as soon as [`advance`](Self::advance) or
+/// [`set`](Self::set) carries the time past their deadline.
+pub struct MockClock {
+    now: watch::Sender<DateTime<Utc>>,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: watch::Sender::new(start),
+        }
+    }
+
+    /// Move the time forward by `by`, waking sleeps that are now due.
+    pub fn advance(&self, by: Duration) {
+        self.now.send_modify(|now| *now += by);
+    }
+
+    /// Jump to `to`. Going backwards is allowed but wakes nothing.
+    pub fn set(&self, to: DateTime<Utc>) {
+        self.now.send_replace(to);
+    }
+}
+
+#[async_trait]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    async fn sleep(&self, duration: std::time::Duration) {
+        let deadline = self.now() + Duration::from_std(duration).unwrap_or(Duration::MAX);
+        let mut rx = self.now.subscribe();
+        // The sender lives as long as `self`, so this only errs if the clock
+        // is dropped mid-sleep.
+        let _ = rx.wait_for(|now| *now >= deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_mock_sleep_wakes_on_advance() {
+        let clock = Arc::new(MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()));
+        let sleeper = {
+            let clock = Arc::clone(&clock);
+            tokio::spawn(async move { clock.sleep(std::time::Duration::from_secs(3600)).await })
+        };
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::minutes(59));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+
+        clock.advance(Duration::minutes(1));
+        tokio::time::timeout(std::time::Duration::from_secs(1), sleeper)
+            .await
+            .expect("sleep did not wake after the clock passed its deadline")
+            .unwrap();
+    }
+}
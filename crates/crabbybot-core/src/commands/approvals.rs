@@ -0,0 +1,105 @@
+//! `/approve`, `/reject` and `/approvals`: sign off on held tool calls.
+
+use std::sync::Arc;
+use tracing::warn;
+
+use super::{CommandContext, CommandOutput, CommandRouter, CommandSpec, Invocation};
+use crate::approvals::{Approvals, Vote};
+use crate::gateway::errors;
+use crate::tools::{ToolContext, ToolRegistry};
+
+const NOT_CONFIGURED: &str = "ℹ️ No approval rules are configured (`approvals.rules` in config.json).";
+
+pub(super) fn register(router: &mut CommandRouter) {
+    router
+        .register(
+            CommandSpec::new("approve", "Approve a held tool call").usage("<id>").min_args(1),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(approve(cx, inv).await) }),
+        )
+        .register(
+            CommandSpec::new("reject", "Cancel a held tool call").usage("<id>").min_args(1),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(reject(cx, inv).await) }),
+        )
+        .register(
+            CommandSpec::new("approvals", "Tool calls waiting for approval"),
+            |cx, _| Box::pin(async move { CommandOutput::Reply(list(cx).await) }),
+        );
+}
+
+/// The registry and its approvals store, without holding the agent lock
+/// while a granted call runs.
+async fn store(cx: &CommandContext) -> Option<(Arc<ToolRegistry>, Arc<Approvals>)> {
+    let tools = Arc::clone(cx.agent.lock().await.tools());
+    let approvals = Arc::clone(tools.approvals()?);
+    Some((tools, approvals))
+}
+
+/// `/approve <id>`: count a vote; the last one needed runs the call in the
+/// chat that asked for it.
+async fn approve(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    let Some((tools, approvals)) = store(cx).await else {
+        return NOT_CONFIGURED.into();
+    };
+    match approvals.approve(inv.args, inv.user_id) {
+        Ok(Vote::Recorded { have, need }) => {
+            format!("👍 Approval recorded for #{} ({}/{}).", inv.args.trim_start_matches('#'), have, need)
+        }
+        Ok(Vote::Granted(approval)) => {
+            let workspace = cx.agent.lock().await.workspace(&approval.channel, &approval.chat_id).to_path_buf();
+            let ctx = ToolContext::new(&approval.channel, &approval.chat_id)
+                .with_user(&approval.requested_by)
+                .with_workspace(workspace)
+                .with_approval(true);
+            let output = match tools.try_execute(&approval.tool, approval.args.clone(), &ctx).await {
+                Ok(output) => output,
+                Err(e) => {
+                    warn!(approval = %approval.id, "Approved call failed: {}", e);
+                    errors::for_tool(&e).render()
+                }
+            };
+            format!(
+                "✅ #{} approved by {}. Ran {}:\n\n{}",
+                approval.id,
+                approval.approved_by.join(", "),
+                approval.summary(),
+                output
+            )
+        }
+        Err(e) => format!("⚠️ **Approval error**: {}", e),
+    }
+}
+
+async fn reject(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    let Some((_, approvals)) = store(cx).await else {
+        return NOT_CONFIGURED.into();
+    };
+    match approvals.reject(inv.args, inv.user_id) {
+        Ok(approval) => format!("❌ #{} rejected; {} won't run.", approval.id, approval.summary()),
+        Err(e) => format!("⚠️ **Approval error**: {}", e),
+    }
+}
+
+async fn list(cx: &CommandContext) -> String {
+    let Some((_, approvals)) = store(cx).await else {
+        return NOT_CONFIGURED.into();
+    };
+    let pending = match approvals.pending() {
+        Ok(pending) => pending,
+        Err(e) => return format!("⚠️ **Approval error**: {}", e),
+    };
+    if pending.is_empty() {
+        return "ℹ️ Nothing is waiting for approval.".into();
+    }
+    let mut out = String::from("🔐 **Pending approvals**\n");
+    for a in pending {
+        out.push_str(&format!(
+            "\n#{} {} — {}/{}, expires {} UTC",
+            a.id,
+            a.summary(),
+            a.approved_by.len(),
+            a.required,
+            a.expires_at.format("%H:%M")
+        ));
+    }
+    out
+}
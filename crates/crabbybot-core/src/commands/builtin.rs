@@ -0,0 +1,338 @@
+//! Conversation, account and crypto shortcut commands.
+
+use tracing::error;
+
+use super::{Category, CommandContext, CommandOutput, CommandRouter, CommandSpec, Invocation};
+use crate::agent::contacts::{self, ContactBook};
+use crate::agent::profile::ProfileStore;
+use crate::alerts;
+use crate::session::{fork_key, SessionError};
+
+pub(super) fn register(router: &mut CommandRouter) {
+    router
+        .register(
+            CommandSpec::new("status", "Bot status (uptime, tools, scheduler)"),
+            |cx, inv| Box::pin(status(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("stats", "Today's tool call counts, error rates and latency")
+                .usage("tools")
+                .min_args(1),
+            |cx, inv| Box::pin(stats(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("clear", "Clear conversation history").aliases(&["reset", "forget"]),
+            |cx, inv| Box::pin(clear(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("fork", "Continue in a copy of this conversation").usage("<name>"),
+            |cx, inv| Box::pin(fork(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("unfork", "Return to the conversation the fork came from"),
+            |cx, inv| Box::pin(unfork(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("model", "Show or switch the model for this conversation").usage("[name | default]"),
+            |cx, inv| Box::pin(model(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("context", "How full this conversation's context window is").usage("[footer on|off]"),
+            |cx, inv| Box::pin(context(cx, inv)),
+        )
+        .register(
+            CommandSpec::new("link", "Link your accounts on other channels to this one").usage("[code]"),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(link(cx, inv)) }),
+        )
+        .register(
+            CommandSpec::new("unlink", "Detach this account from your other ones"),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(unlink(cx, inv)) }),
+        )
+        .register(
+            CommandSpec::new("mute", "Pause scheduled alerts in this chat").usage("alerts <2h>"),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(mute(cx, inv)) }),
+        )
+        .register(
+            CommandSpec::new("unmute", "Resume scheduled alerts"),
+            |cx, inv| Box::pin(async move { CommandOutput::Reply(unmute(cx, inv)) }),
+        )
+        // Crypto shortcuts — rewritten into agent prompts.
+        .register(
+            CommandSpec::new("portfolio", "Your wallet's SOL + token balances").category(Category::Crypto),
+            |_, _| {
+                Box::pin(async {
+                    CommandOutput::Prompt(
+                        "Show my Solana wallet portfolio: SOL balance and all token balances.".into(),
+                    )
+                })
+            },
+        )
+        .register(
+            CommandSpec::new("alpha", "Full safety + sentiment report")
+                .usage("<mint>")
+                .min_args(1)
+                .category(Category::Crypto),
+            |_, inv| {
+                Box::pin(async move {
+                    CommandOutput::Prompt(format!("Give me a full alpha summary for token {}", inv.args))
+                })
+            },
+        )
+        .register(
+            CommandSpec::new("buy", "Buy token (default: 0.1 SOL)")
+                .usage("<mint> [amount]")
+                .min_args(1)
+                .category(Category::Crypto),
+            |_, inv| {
+                Box::pin(async move {
+                    let words = inv.words();
+                    let amount = words.get(1).unwrap_or(&"0.1");
+                    CommandOutput::Prompt(format!("Buy {} SOL of token {}", amount, words[0]))
+                })
+            },
+        );
+}
+
+async fn status(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let uptime = cx.started.elapsed();
+    let hours = uptime.as_secs() / 3600;
+    let mins = (uptime.as_secs() % 3600) / 60;
+    let secs = uptime.as_secs() % 60;
+
+    let (tools, workspace) = {
+        let agent = cx.agent.lock().await;
+        (agent.tools().len(), agent.workspace(inv.channel, inv.chat_id).to_path_buf())
+    };
+    let cron_status = match &cx.cron {
+        Some(cron) => {
+            let mut cron = cron.lock().await;
+            cron.refresh();
+            cron.status()
+        }
+        None => "not running".into(),
+    };
+
+    CommandOutput::Reply(format!(
+        "🤖 **CrabbyBot Status**\n\n\
+         ⏱ Uptime: {}h {}m {}s\n\
+         💬 Session: `{}`\n\
+         🛠️ Tools: {}\n\
+         📋 Cron: {}\n\
+         📂 Workspace: `{}`",
+        hours,
+        mins,
+        secs,
+        inv.session_key,
+        tools,
+        cron_status,
+        workspace.display(),
+    ))
+}
+
+async fn stats(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    CommandOutput::Reply(match inv.args {
+        "tools" => {
+            let stats = cx.agent.lock().await.tools().stats().today();
+            format!(
+                "🛠️ **Tool usage today (UTC)**\n\n```\n{}```",
+                crate::tools::stats::render(&stats)
+            )
+        }
+        _ => "Usage: `/stats tools`".to_string(),
+    })
+}
+
+async fn clear(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    CommandOutput::Reply(if cx.agent.lock().await.clear_session(inv.session_key) {
+        "✅ Conversation history cleared. I have forgotten our past messages.".to_string()
+    } else {
+        "ℹ️ No conversation history to clear.".to_string()
+    })
+}
+
+/// Copy the current session into a fork and switch the chat to it.
+async fn fork(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let (name, session_key) = (inv.args, inv.session_key);
+    if name.is_empty() {
+        return CommandOutput::Reply(format!(
+            "Usage: `/fork <name>` — continue in a copy of this conversation.\n\
+             Current session: `{}`",
+            session_key
+        ));
+    }
+    let Some(new_key) = fork_key(session_key, name) else {
+        return CommandOutput::Reply("⚠️ Fork names may only contain letters, digits and dashes (max 40).".into());
+    };
+    let reply = match cx.agent.lock().await.fork_session(session_key, &new_key) {
+        Ok(()) => None,
+        Err(SessionError::NotFound(_)) => Some("ℹ️ Nothing to fork yet — this conversation has no history.".into()),
+        Err(SessionError::AlreadyExists(_)) => Some(format!("⚠️ A fork named `{}` already exists.", name)),
+        Err(e) => Some(format!("⚠️ **Session error**: {}", e)),
+    };
+    if let Some(reply) = reply {
+        return CommandOutput::Reply(reply);
+    }
+    cx.switch_session(inv.session_base(), &new_key).await;
+    CommandOutput::Reply(format!(
+        "🔀 Forked into `{}`. New messages go to the fork; `/unfork` returns to `{}`.",
+        new_key, session_key
+    ))
+}
+
+/// Switch the chat back to the session the current fork was made from.
+async fn unfork(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let Some(parent) = cx.agent.lock().await.session_parent(inv.session_key) else {
+        return CommandOutput::Reply("ℹ️ This conversation is not a fork.".into());
+    };
+    cx.switch_session(inv.session_base(), &parent).await;
+    CommandOutput::Reply(format!("↩️ Back in `{}`. The fork `{}` is kept.", parent, inv.session_key))
+}
+
+/// `/model` shows the conversation's model, `/model <name>` switches it
+/// to one of the configured models and `/model default` goes back to the
+/// default one.
+async fn model(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let mut agent = cx.agent.lock().await;
+    let default = agent.default_model().await;
+    let chosen = match agent.session_model(inv.session_key) {
+        Some(model) if agent.allows_model(&model).await => Some(model),
+        _ => None,
+    };
+    let choices = agent
+        .model_choices()
+        .await
+        .iter()
+        .map(|m| format!("`{}`", m))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let reply = match inv.args {
+        "" => match chosen {
+            Some(model) => format!(
+                "🤖 This conversation uses `{}`.\nThe default is `{}`; `/model default` switches back.",
+                model, default
+            ),
+            None => format!(
+                "🤖 This conversation uses the default model, `{}`.\nSwitch with `/model <name>` to one of {}.",
+                default, choices
+            ),
+        },
+        "default" | "reset" => match agent.set_session_model(inv.session_key, None) {
+            Ok(()) if chosen.is_some() => format!("↩️ Back to the default model, `{}`.", default),
+            Ok(()) => format!("ℹ️ Already on the default model, `{}`.", default),
+            Err(e) => format!("⚠️ **Session error**: {}", e),
+        },
+        name if name.len() > 100 || name.contains(char::is_whitespace) => {
+            "⚠️ Model names have no spaces, e.g. `/model openai/gpt-4o-mini`.".into()
+        }
+        name if !agent.allows_model(name).await => {
+            format!("⚠️ `{}` is not a configured model. Pick one of {}.", name, choices)
+        }
+        name => match agent.set_session_model(inv.session_key, Some(name.to_string())) {
+            Ok(()) => format!("🤖 Switched this conversation to `{}`. Other chats keep their model.", name),
+            Err(e) => format!("⚠️ **Session error**: {}", e),
+        },
+    };
+    CommandOutput::Reply(reply)
+}
+
+/// `/context` shows how much of the context budget the conversation uses;
+/// `/context footer on|off` adds that to the end of every reply.
+async fn context(cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let mut agent = cx.agent.lock().await;
+    let args: Vec<&str> = inv.args.split_whitespace().collect();
+    let reply = match args.as_slice() {
+        [] => {
+            let mut text = agent.context_usage(inv.session_key).render();
+            if !agent.context_footer(inv.session_key) {
+                text.push_str("\n\n`/context footer on` shows this after every reply.");
+            }
+            text
+        }
+        ["footer", state @ ("on" | "off")] => match agent.set_context_footer(inv.session_key, *state == "on") {
+            Ok(()) if *state == "on" => "📊 Replies now end with how full the context is.".into(),
+            Ok(()) => "📊 Context footer off.".into(),
+            Err(e) => format!("⚠️ **Session error**: {}", e),
+        },
+        _ => "Usage: `/context` or `/context footer on|off`".into(),
+    };
+    CommandOutput::Reply(reply)
+}
+
+/// `/link` issues a code, `/link <code>` redeems it from another account.
+fn link(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    let (channel, user_id, code) = (inv.channel, inv.user_id, inv.args);
+    if user_id.is_empty() || user_id == "unknown" {
+        return "⚠️ Can't tell who you are on this channel, so it can't be linked.".into();
+    }
+    let contacts = ContactBook::new(&cx.workspace);
+    if code.is_empty() {
+        return match contacts.start_link(channel, user_id) {
+            Ok(code) => format!(
+                "🔗 Your link code is `{0}`. Send `/link {0}` from your other account \
+                 (any channel) within 10 minutes. Keep it private.",
+                code
+            ),
+            Err(e) => format!("⚠️ **Contacts error**: {}", e),
+        };
+    }
+    let me = contacts::identity(channel, user_id);
+    match contacts.complete_link(code, channel, user_id) {
+        Ok(primary) => {
+            if let Err(e) = ProfileStore::new(&cx.workspace).adopt(&me, &primary) {
+                error!(identity = %me, "Failed to move profile to linked contact: {}", e);
+            }
+            format!(
+                "✅ Linked `{}` with `{}`. Your profile now follows you across both.",
+                me, primary
+            )
+        }
+        Err(e) => format!("❌ Couldn't link: {}.", e),
+    }
+}
+
+fn unlink(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    let (channel, user_id) = (inv.channel, inv.user_id);
+    if user_id.is_empty() || user_id == "unknown" {
+        return "⚠️ Can't tell who you are on this channel, so it can't be linked.".into();
+    }
+    let me = contacts::identity(channel, user_id);
+    match ContactBook::new(&cx.workspace).unlink(channel, user_id) {
+        Ok(true) => format!("✅ `{}` is no longer linked to your other accounts.", me),
+        Ok(false) => "ℹ️ This account isn't linked to any other.".into(),
+        Err(e) => format!("⚠️ **Contacts error**: {}", e),
+    }
+}
+
+/// `/mute [alerts] <duration>`; without a duration, when the mute ends.
+fn mute(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    let args = inv.args.strip_prefix("alerts").unwrap_or(inv.args).trim();
+    let chat_key = inv.chat_key();
+    if args.is_empty() {
+        return match cx.alerts.muted_until(&chat_key) {
+            Some(until) => format!(
+                "🔕 Alerts are muted until {} UTC. `/unmute` turns them back on.",
+                until.format("%Y-%m-%d %H:%M")
+            ),
+            None => "Usage: `/mute alerts <duration>`, e.g. `/mute alerts 2h` (s, m, h or d).".into(),
+        };
+    }
+    let Some(duration) = alerts::parse_duration(args) else {
+        return format!("❌ Couldn't read `{}` as a duration. Try `30m`, `2h` or `1d`.", args);
+    };
+    match cx.alerts.mute(&chat_key, duration) {
+        Ok(until) => format!(
+            "🔕 Scheduled alerts muted until {} UTC. Repeats are counted and \
+             summarized once they resume; `/unmute` ends it early.",
+            until.format("%Y-%m-%d %H:%M")
+        ),
+        Err(e) => format!("⚠️ **Alerts error**: {}", e),
+    }
+}
+
+fn unmute(cx: &CommandContext, inv: &Invocation<'_>) -> String {
+    match cx.alerts.unmute(&inv.chat_key()) {
+        Ok(true) => "🔔 Alerts are back on in this chat.".into(),
+        Ok(false) => "ℹ️ Alerts weren't muted here.".into(),
+        Err(e) => format!("⚠️ **Alerts error**: {}", e),
+    }
+}
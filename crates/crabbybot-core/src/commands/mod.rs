@@ -0,0 +1,506 @@
+//! Slash commands shared by every chat channel and the CLI chat.
+//!
+//! A command is declared once on a [`CommandRouter`]: its name and aliases,
+//! a usage line, a summary for `/help`, how many arguments it needs, and an
+//! async handler. The agent bridge routes Telegram, Discord and WebChat
+//! messages through the router and `crabbybot chat` uses the same one, so a
+//! command added to [`CommandRouter::standard`] works, and shows up in
+//! `/help`, everywhere. A frontend registers the few commands that only make
+//! sense there on top (`/digest` and `/restart` in bot mode, `/quit` on the
+//! CLI). [`CommandRouter::menu`] lists the commands for the chat apps'
+//! command menus, so they autocomplete there too.
+
+mod approvals;
+mod builtin;
+#[cfg(feature = "polymarket")]
+mod polymarket;
+mod settings;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use tokio::sync::Mutex;
+
+use crate::agent::AgentLoop;
+use crate::alerts::AlertManager;
+use crate::config::AlertsConfig;
+use crate::cron::CronService;
+
+/// Heading a command is listed under in `/help`, in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    General,
+    Crypto,
+    Settings,
+}
+
+impl Category {
+    fn heading(&self) -> &'static str {
+        match self {
+            Self::General => "🛠️ **General:**",
+            Self::Crypto => "💰 **Crypto Shortcuts:**",
+            Self::Settings => "⚙️ **Settings:**",
+        }
+    }
+}
+
+/// What a command handler asks the frontend to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandOutput {
+    /// Send this text to the user.
+    Reply(String),
+    /// Run this prompt through the agent as if the user had sent it.
+    Prompt(String),
+    /// Send this text, then restart so a config change takes effect.
+    Restart(String),
+    /// End the chat (CLI).
+    Quit,
+}
+
+/// A command as offered in a chat app's command menu (Telegram's
+/// `setMyCommands`, Discord application commands).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuEntry {
+    pub name: &'static str,
+    /// The summary and usage, at most [`MENU_DESCRIPTION_MAX`] characters.
+    pub description: String,
+    /// Whether the command takes arguments.
+    pub takes_args: bool,
+    /// Whether it needs them.
+    pub requires_args: bool,
+}
+
+/// Longest menu description both Telegram (256) and Discord (100) accept.
+pub const MENU_DESCRIPTION_MAX: usize = 100;
+
+/// One call of a command.
+#[derive(Debug, Clone, Copy)]
+pub struct Invocation<'a> {
+    pub channel: &'a str,
+    pub chat_id: &'a str,
+    pub user_id: &'a str,
+    /// Session the chat currently talks to: a fork after `/fork`.
+    pub session_key: &'a str,
+    /// Everything after the command name, trimmed.
+    pub args: &'a str,
+}
+
+impl Invocation<'_> {
+    /// The chat the command came from, `channel:chat_id`.
+    pub fn chat_key(&self) -> String {
+        format!("{}:{}", self.channel, self.chat_id)
+    }
+
+    /// The conversation's own session key, before any fork suffix. The
+    /// chat key unless the channel scopes sessions per user or shares one.
+    pub fn session_base(&self) -> &str {
+        self.session_key.split('#').next().unwrap_or(self.session_key)
+    }
+
+    /// Arguments split on whitespace.
+    pub fn words(&self) -> Vec<&str> {
+        self.args.split_whitespace().collect()
+    }
+}
+
+/// State command handlers work on, shared by every chat of a frontend.
+pub struct CommandContext {
+    pub agent: Arc<Mutex<AgentLoop>>,
+    pub workspace: PathBuf,
+    /// The scheduler, when this frontend runs one (not the CLI chat).
+    pub cron: Option<Arc<Mutex<CronService>>>,
+    /// Only used for `/mute`; mutes are shared with the cron ticker on disk.
+    pub alerts: AlertManager,
+    pub started: Instant,
+    /// Session key → session currently in use, for conversations that
+    /// switched into a fork. Kept in memory, so a restart returns every
+    /// conversation to its own session; the fork itself stays on disk.
+    forks: Mutex<HashMap<String, String>>,
+}
+
+impl CommandContext {
+    pub fn new(agent: Arc<Mutex<AgentLoop>>, workspace: PathBuf) -> Self {
+        Self {
+            agent,
+            alerts: AlertManager::new(&workspace, &AlertsConfig::default()),
+            workspace,
+            cron: None,
+            started: Instant::now(),
+            forks: Mutex::default(),
+        }
+    }
+
+    pub fn with_cron(mut self, cron: Arc<Mutex<CronService>>) -> Self {
+        self.cron = Some(cron);
+        self
+    }
+
+    /// Session the conversation `base` talks to: its fork, or its own.
+    pub async fn session_for(&self, base: &str) -> String {
+        self.forks
+            .lock()
+            .await
+            .get(base)
+            .cloned()
+            .unwrap_or_else(|| base.to_string())
+    }
+
+    /// Send the conversation's messages to `session_key` from now on.
+    async fn switch_session(&self, base: &str, session_key: &str) {
+        let mut forks = self.forks.lock().await;
+        if session_key == base {
+            forks.remove(base);
+        } else {
+            forks.insert(base.to_string(), session_key.to_string());
+        }
+    }
+}
+
+type Handler = Box<
+    dyn for<'a> Fn(&'a CommandContext, &'a Invocation<'a>) -> BoxFuture<'a, CommandOutput> + Send + Sync,
+>;
+
+/// Name, help text and argument rules of a command.
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    usage: &'static str,
+    summary: &'static str,
+    category: Category,
+    min_args: usize,
+    bare: bool,
+}
+
+impl CommandSpec {
+    /// `/name`, listed under General with `summary`.
+    pub fn new(name: &'static str, summary: &'static str) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            usage: "",
+            summary,
+            category: Category::General,
+            min_args: 0,
+            bare: false,
+        }
+    }
+
+    /// Other names that run the same command.
+    pub fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Arguments as shown in `/help`, e.g. `<mint> [amount]`.
+    pub fn usage(mut self, usage: &'static str) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn category(mut self, category: Category) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Reply with the usage line instead of calling the handler when fewer
+    /// arguments are given.
+    pub fn min_args(mut self, n: usize) -> Self {
+        self.min_args = n;
+        self
+    }
+
+    /// Also match the name without the slash (`config set …`).
+    pub fn bare(mut self) -> Self {
+        self.bare = true;
+        self
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    fn usage_line(&self) -> String {
+        if self.usage.is_empty() {
+            format!("/{}", self.name)
+        } else {
+            format!("/{} {}", self.name, self.usage)
+        }
+    }
+}
+
+struct Command {
+    spec: CommandSpec,
+    handler: Handler,
+}
+
+/// Slash commands of one frontend.
+pub struct CommandRouter {
+    commands: Vec<Command>,
+}
+
+impl Default for CommandRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRouter {
+    /// A router that only knows `/help`.
+    pub fn new() -> Self {
+        let mut router = Self { commands: Vec::new() };
+        // `/help` is answered by the router itself, from the table.
+        router.register(
+            CommandSpec::new("help", "Show this help message").aliases(&["start"]),
+            |_, _| Box::pin(async { CommandOutput::Reply(String::new()) }),
+        );
+        router
+    }
+
+    /// The commands every channel and the CLI chat offer.
+    pub fn standard() -> Self {
+        let mut router = Self::new();
+        builtin::register(&mut router);
+        settings::register(&mut router);
+        approvals::register(&mut router);
+        #[cfg(feature = "polymarket")]
+        polymarket::register(&mut router);
+        router
+    }
+
+    /// Add a command, replacing any with the same name.
+    pub fn register<F>(&mut self, spec: CommandSpec, handler: F) -> &mut Self
+    where
+        F: for<'a> Fn(&'a CommandContext, &'a Invocation<'a>) -> BoxFuture<'a, CommandOutput>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.commands.retain(|c| c.spec.name != spec.name);
+        self.commands.push(Command {
+            spec,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// The command `text` calls and its arguments, if it is one.
+    fn parse<'t>(&self, text: &'t str) -> Option<(&Command, &'t str)> {
+        let text = text.trim();
+        let (head, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let (name, slashed) = match head.strip_prefix('/') {
+            // Telegram appends the bot's name in groups: `/help@CrabbyBot`.
+            Some(name) => (name.split('@').next().unwrap_or(name), true),
+            None => (head, false),
+        };
+        let name = name.to_lowercase();
+        let command = self
+            .commands
+            .iter()
+            .find(|c| c.spec.matches(&name) && (slashed || c.spec.bare))?;
+        Some((command, args.trim()))
+    }
+
+    /// Whether `text` is a known command.
+    pub fn is_command(&self, text: &str) -> bool {
+        self.parse(text).is_some()
+    }
+
+    /// Run the command in `text`. `None` when it isn't one, so the message
+    /// goes to the agent as-is.
+    pub async fn dispatch(
+        &self,
+        cx: &CommandContext,
+        text: &str,
+        channel: &str,
+        chat_id: &str,
+        user_id: &str,
+        session_key: &str,
+    ) -> Option<CommandOutput> {
+        let (command, args) = self.parse(text)?;
+        if command.spec.name == "help" {
+            return Some(CommandOutput::Reply(self.help()));
+        }
+        if args.split_whitespace().count() < command.spec.min_args {
+            return Some(CommandOutput::Reply(format!("Usage: `{}`", command.spec.usage_line())));
+        }
+        let invocation = Invocation {
+            channel,
+            chat_id,
+            user_id,
+            session_key,
+            args,
+        };
+        Some((command.handler)(cx, &invocation).await)
+    }
+
+    /// The commands for a chat app's command menu, in `/help` order.
+    /// Names the apps don't accept (anything but 1–32 lowercase letters,
+    /// digits and `_`) are left out; they still work when typed.
+    pub fn menu(&self) -> Vec<MenuEntry> {
+        let mut specs: Vec<&CommandSpec> = self.commands.iter().map(|c| &c.spec).collect();
+        specs.sort_by_key(|c| c.category);
+        specs
+            .into_iter()
+            .filter(|spec| {
+                (1..=32).contains(&spec.name.len())
+                    && spec.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            })
+            .map(|spec| {
+                let description = if spec.usage.is_empty() {
+                    spec.summary.to_string()
+                } else {
+                    format!("{} — {}", spec.summary, spec.usage)
+                };
+                let description = if description.chars().count() > MENU_DESCRIPTION_MAX {
+                    let cut: String = description.chars().take(MENU_DESCRIPTION_MAX - 1).collect();
+                    format!("{}…", cut.trim_end())
+                } else {
+                    description
+                };
+                MenuEntry {
+                    name: spec.name,
+                    description,
+                    takes_args: !spec.usage.is_empty(),
+                    requires_args: spec.min_args > 0,
+                }
+            })
+            .collect()
+    }
+
+    /// `/help`, generated from the registered commands.
+    pub fn help(&self) -> String {
+        let mut commands: Vec<&CommandSpec> = self.commands.iter().map(|c| &c.spec).collect();
+        commands.sort_by_key(|c| c.category);
+        let mut out = String::from("🦀 **CrabbyBot Commands**\n");
+        let mut heading = None;
+        for spec in commands {
+            if heading != Some(spec.category) {
+                heading = Some(spec.category);
+                out.push_str(&format!("\n{}\n", spec.category.heading()));
+            }
+            out.push_str(&format!("`{}`", spec.usage_line()));
+            if !spec.aliases.is_empty() {
+                let aliases: Vec<String> = spec.aliases.iter().map(|a| format!("`/{}`", a)).collect();
+                out.push_str(&format!(" (or {})", aliases.join(", ")));
+            }
+            out.push_str(&format!(" — {}\n", spec.summary));
+        }
+        out.push_str(
+            "\n⏰ **Scheduling:**\n\
+             Just ask! e.g. *\"Remind me to check SOL price every hour\"*\n\n\
+             Any other message is processed by the AI assistant.",
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentConfig;
+    use crate::tools::ToolRegistry;
+
+    fn context(name: &str) -> CommandContext {
+        let workspace = std::env::temp_dir().join(format!("CrabbyBot_test_commands_{}", name));
+        let provider: crate::runtime::SharedProvider = Arc::new(Mutex::new(Box::new(
+            crate::provider::openai::OpenAiProvider::new(
+                "test",
+                "",
+                Some("http://127.0.0.1:9"),
+                "test-model",
+                reqwest::Client::new(),
+            ),
+        )));
+        let config = AgentConfig {
+            workspace: workspace.clone(),
+            models: vec!["big-model".into()],
+            ..Default::default()
+        };
+        let agent = AgentLoop::new(provider, Arc::new(ToolRegistry::new()), config);
+        CommandContext::new(Arc::new(Mutex::new(agent)), workspace)
+    }
+
+    async fn run(router: &CommandRouter, cx: &CommandContext, text: &str) -> Option<CommandOutput> {
+        router.dispatch(cx, text, "telegram", "42", "7", "telegram:42").await
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_and_usage() {
+        let cx = context("dispatch");
+        let mut router = CommandRouter::standard();
+        router.register(
+            CommandSpec::new("echo", "Repeat the arguments").usage("<text>").min_args(1).bare(),
+            |_, inv| Box::pin(async move { CommandOutput::Reply(format!("{}|{}", inv.chat_key(), inv.args)) }),
+        );
+
+        assert_eq!(
+            run(&router, &cx, "/echo  hello  there ").await,
+            Some(CommandOutput::Reply("telegram:42|hello  there".into()))
+        );
+        assert_eq!(
+            run(&router, &cx, "echo hi").await,
+            Some(CommandOutput::Reply("telegram:42|hi".into()))
+        );
+        assert_eq!(
+            run(&router, &cx, "/ECHO@CrabbyBot").await,
+            Some(CommandOutput::Reply("Usage: `/echo <text>`".into()))
+        );
+        assert_eq!(
+            run(&router, &cx, "/alpha So11111111111111111111111111111111111111112").await,
+            Some(CommandOutput::Prompt(
+                "Give me a full alpha summary for token So11111111111111111111111111111111111111112".into()
+            ))
+        );
+        // Unknown commands and plain text go to the agent.
+        assert!(run(&router, &cx, "/nope").await.is_none());
+        assert!(run(&router, &cx, "status report please").await.is_none());
+        assert!(router.is_command("/forget"));
+    }
+
+    #[tokio::test]
+    async fn test_help_lists_every_command() {
+        let cx = context("help");
+        let router = CommandRouter::standard();
+        let Some(CommandOutput::Reply(help)) = run(&router, &cx, "/start").await else {
+            panic!("expected help");
+        };
+        assert!(help.contains("`/clear` (or `/reset`, `/forget`) — Clear conversation history"));
+        assert!(help.contains("`/buy <mint> [amount]`"));
+        assert!(help.find("General").unwrap() < help.find("Crypto").unwrap());
+        assert!(help.contains("⚙️ **Settings:**\n`/config [list|get|set|reset"));
+    }
+
+    #[tokio::test]
+    async fn test_model_only_switches_to_configured_models() {
+        let cx = context("model");
+        let router = CommandRouter::standard();
+        run(&router, &cx, "/model default").await;
+
+        assert_eq!(
+            run(&router, &cx, "/model gpt-9").await,
+            Some(CommandOutput::Reply(
+                "⚠️ `gpt-9` is not a configured model. Pick one of `test-model`, `big-model`.".into()
+            ))
+        );
+        assert_eq!(cx.agent.lock().await.session_model("telegram:42"), None);
+
+        run(&router, &cx, "/model big-model").await;
+        assert_eq!(cx.agent.lock().await.session_model("telegram:42").as_deref(), Some("big-model"));
+        run(&router, &cx, "/model default").await;
+    }
+
+    #[test]
+    fn test_menu_lists_commands_for_chat_apps() {
+        let menu = CommandRouter::standard().menu();
+        assert_eq!(menu[0].name, "help");
+        let approve = menu.iter().find(|e| e.name == "approve").unwrap();
+        assert_eq!(approve.description, "Approve a held tool call — <id>");
+        assert!(approve.takes_args && approve.requires_args);
+        assert!(menu.iter().all(|e| e.description.chars().count() <= MENU_DESCRIPTION_MAX));
+        assert!(!menu.iter().any(|e| e.name == "start"), "aliases stay out of the menu");
+    }
+}
@@ -0,0 +1,35 @@
+//! `/polymarket`: run the Polymarket CLI directly, without the agent.
+
+use super::{Category, CommandOutput, CommandRouter, CommandSpec, Invocation};
+use crate::config::Config;
+use crate::tools::polymarket_common::run_polymarket_cli;
+use crate::tools::polymarket_help::POLYMARKET_HELP;
+
+pub(super) fn register(router: &mut CommandRouter) {
+    router.register(
+        CommandSpec::new("polymarket", "Run a Polymarket CLI command")
+            .usage("<args>")
+            .category(Category::Crypto)
+            .bare(),
+        |_, inv| Box::pin(polymarket(inv)),
+    );
+}
+
+async fn polymarket(inv: &Invocation<'_>) -> CommandOutput {
+    let args = inv.args;
+    if args.is_empty() || args.eq_ignore_ascii_case("help") || args.eq_ignore_ascii_case("--help") {
+        return CommandOutput::Reply(POLYMARKET_HELP.to_string());
+    }
+    let Some(parsed_args) = shlex::split(args) else {
+        return CommandOutput::Reply("❌ Could not parse command arguments. Check your quoting.".into());
+    };
+
+    let config = Config::load().unwrap_or_default();
+    let str_args: Vec<&str> = parsed_args.iter().map(|s| s.as_str()).collect();
+    let header = format!("⚙️ `polymarket {}`", parsed_args.join(" "));
+    CommandOutput::Reply(match run_polymarket_cli(&config.tools.polymarket, &str_args).await {
+        Ok(output) if output.trim().is_empty() => format!("{}\n✅ Command completed (no output)", header),
+        Ok(output) => format!("{}\n\n{}", header, output),
+        Err(e) => format!("{}\n❌ CLI Error:\n{}", header, e),
+    })
+}
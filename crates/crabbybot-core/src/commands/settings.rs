@@ -0,0 +1,294 @@
+//! `/config`: view and change settings from any chat.
+
+use super::{Category, CommandContext, CommandOutput, CommandRouter, CommandSpec, Invocation};
+use crate::config::{self, Config, SettingError};
+
+pub(super) fn register(router: &mut CommandRouter) {
+    router.register(
+        CommandSpec::new("config", "Show settings, or change one with `set` / `reset`")
+            .usage("[list|get|set|reset <setting> …]")
+            .category(Category::Settings)
+            .bare(),
+        |cx, inv| Box::pin(async move { config(cx, inv) }),
+    );
+}
+
+/// Whether the transport deleted the message that called `/config set`,
+/// so a key sent along doesn't stay in the chat history.
+fn deletes_secrets(channel: &str) -> bool {
+    channel == "telegram"
+}
+
+fn config(_cx: &CommandContext, inv: &Invocation<'_>) -> CommandOutput {
+    let args_str = inv.args;
+    let args_lower = args_str.to_lowercase();
+    let deleted = deletes_secrets(inv.channel);
+    let deleted_note = if deleted {
+        "\n🔒 Your message was auto-deleted for security."
+    } else {
+        ""
+    };
+
+    let config = Config::load().unwrap_or_default();
+
+    // ── Helper: detect which provider the current model belongs to ──
+    let detect_model_provider = |model: &str| -> &'static str {
+        let m = model.to_lowercase();
+        if m.starts_with("ollama/") { "ollama" }
+        else if m.starts_with("gemini") { "gemini" }
+        else if m.starts_with("claude") || m.starts_with("anthropic/") { "anthropic" }
+        else if m.starts_with("gpt") || m.starts_with("o1") || m.starts_with("o3") || m.starts_with("openai/") { "openai" }
+        else if m.starts_with("deepseek") { "deepseek" }
+        else if m.starts_with("llama") || m.starts_with("mixtral") || m.starts_with("groq/") { "groq" }
+        else if m.contains('/') { "openrouter" }
+        else { "unknown" }
+    };
+
+    let provider_display_name = |id: &str| -> &'static str {
+        match id {
+            "groq" => "Groq", "openai" => "OpenAI", "anthropic" => "Anthropic",
+            "deepseek" => "DeepSeek", "gemini" => "Gemini", "openrouter" => "OpenRouter",
+            "ollama" => "Ollama (local)",
+            _ => "Unknown",
+        }
+    };
+
+    // ── /config model — quick check ──
+    if args_lower == "model" {
+        let model_str = &config.agents.defaults.model;
+        let prov_id = detect_model_provider(model_str);
+        let prov_name = provider_display_name(prov_id);
+        let reply = format!(
+            "🤖 Current Model\n\nModel: {}\nProvider: {}\nMax Tokens: {}",
+            model_str, prov_name, config.agents.defaults.max_tokens
+        );
+        return CommandOutput::Reply(reply);
+    }
+
+    if args_str.is_empty() || args_lower == "help" || args_lower == "--help" {
+        // Show current config summary with masked keys
+        let mask = |s: &str| -> String {
+            if s.is_empty() || s.contains("YOUR_") { return "❌ not set".into(); }
+            if s.len() <= 8 { return "••••••••".into(); }
+            format!("{}••••{}", &s[..4], &s[s.len()-4..])
+        };
+
+        let groq_key = config.providers.groq.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let openai_key = config.providers.openai.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let anthropic_key = config.providers.anthropic.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let deepseek_key = config.providers.deepseek.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let gemini_key = config.providers.gemini.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let openrouter_key = config.providers.openrouter.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
+        let poly_key = config.tools.polymarket.private_key.as_deref().map(&mask).unwrap_or("❌ not set".into());
+        let solana_key = config.tools.solana_private_key.as_deref().map(&mask).unwrap_or("❌ not set".into());
+
+        // Mark the provider that matches the MODEL as active (not just the first valid key)
+        let model_prov_id = detect_model_provider(&config.agents.defaults.model);
+        let p_label = |name: &str, label: &str| -> String {
+            if name == model_prov_id {
+                format!("{} 🟢 (Active)", label)
+            } else {
+                label.to_string()
+            }
+        };
+
+        // Check if the active model's provider has a valid key
+        let model_str = &config.agents.defaults.model;
+        let prov_name = provider_display_name(model_prov_id);
+        let provider_has_key = match model_prov_id {
+            "gemini" => gemini_key != "❌ not set",
+            "anthropic" => anthropic_key != "❌ not set",
+            "openai" => openai_key != "❌ not set",
+            "deepseek" => deepseek_key != "❌ not set",
+            "groq" => groq_key != "❌ not set",
+            "openrouter" => openrouter_key != "❌ not set",
+            "ollama" => config.providers.ollama.is_some(),
+            _ => false,
+        };
+
+        let model_status = if provider_has_key {
+            format!("{} → {} ✅", model_str, prov_name)
+        } else {
+            format!("{} → {} ⚠️ (no API key!)", model_str, prov_name)
+        };
+
+        let summary = format!(
+"⚙️ CrabbyBot Configuration
+
+━━━ 🔑 LLM Providers ━━━
+{}: {}
+{}: {}
+{}: {}
+{}: {}
+{}: {}
+{}: {}
+
+━━━ 🤖 Agent ━━━
+Model: {}
+Max Tokens: {}
+Temperature: {}
+
+━━━ 🔐 Wallet Keys ━━━
+Polymarket: {}
+Solana: {}
+
+━━━ 🎰 Betting ━━━
+Enabled: {}
+Max Bet: ${}
+Daily Loss Limit: ${}
+Strategy: {}
+Scan Interval: {} min
+
+━━━ ✏️ Set a value ━━━
+/config set model <MODEL>
+/config set max_tokens <NUMBER>
+/config set temperature <0.0-2.0>
+/config set groq_key <KEY>
+/config set openai_key <KEY>
+/config set anthropic_key <KEY>
+/config set deepseek_key <KEY>
+/config set gemini_key <KEY>
+/config set openrouter_key <KEY>
+/config set polymarket_key <KEY>
+/config set solana_key <KEY>
+/config set betting_enabled <true|false>
+/config set max_bet <AMOUNT>
+/config set daily_limit <AMOUNT>
+/config set strategy <value|momentum|contrarian>
+/config set scan_interval <MINUTES>
+Any path from /config list works too, e.g.
+/config set agents.defaults.timezone Europe/Berlin
+
+━━━ 🔍 Look up ━━━
+/config model
+/config get <SETTING>
+/config list
+
+━━━ 🔄 Reset a value ━━━
+/config reset <SETTING_NAME>
+/config reset all",
+            p_label("groq", "Groq"), groq_key,
+            p_label("openai", "OpenAI"), openai_key,
+            p_label("anthropic", "Anthropic"), anthropic_key,
+            p_label("deepseek", "DeepSeek"), deepseek_key,
+            p_label("gemini", "Gemini"), gemini_key,
+            p_label("openrouter", "OpenRouter"), openrouter_key,
+            model_status,
+            config.agents.defaults.max_tokens,
+            config.agents.defaults.temperature,
+            poly_key, solana_key,
+            if config.tools.betting.enabled { "🟢" } else { "🔴" },
+            config.tools.betting.max_bet_size_usdc,
+            config.tools.betting.daily_loss_limit_usdc,
+            config.tools.betting.strategy,
+            config.tools.betting.scan_interval_minutes,
+        );
+        return CommandOutput::Reply(summary);
+    }
+
+    // Handle "set <key> <value>"
+    if args_lower.starts_with("set ") {
+        let parts: Vec<&str> = args_str[4..].trim().splitn(2, ' ').collect();
+        if parts.len() < 2 {
+            return CommandOutput::Reply(format!("❌ Usage: /config set <setting_name> <value>{}", deleted_note));
+        }
+        if !config::chat_settable(parts[0]) {
+            return CommandOutput::Reply(format!("❌ {}{}", server_only(parts[0]), deleted_note));
+        }
+        return match config::mutate(parts[0], parts[1]) {
+            // Restart so the change takes effect.
+            Ok(setting) => {
+                let security_note = match (setting.secret, deleted) {
+                    (true, true) => "\n🔒 Message auto-deleted · 🔐 Value encrypted (AES-256-GCM)",
+                    (true, false) => "\n🔐 Value encrypted (AES-256-GCM)",
+                    _ => "",
+                };
+                CommandOutput::Restart(format!(
+                    "✅ {} set to {} — saved to config.json{}\n🔄 Restarting to apply changes…",
+                    setting.key, setting.value, security_note
+                ))
+            }
+            Err(e) => CommandOutput::Reply(format!("❌ {}{}{}", e, unknown_hint(&e), deleted_note)),
+        };
+    }
+
+    // Handle "get <key>"
+    if args_lower.starts_with("get ") {
+        return CommandOutput::Reply(match config::get(args_str[4..].trim()) {
+            Ok(setting) => format!("{} = {}", setting.key, setting.value),
+            Err(e) => format!("❌ {}{}", e, unknown_hint(&e)),
+        });
+    }
+
+    if args_lower == "list" {
+        return CommandOutput::Reply(match config::list() {
+            Ok(settings) => {
+                let lines: Vec<String> = settings.iter().map(|s| format!("{} = {}", s.key, s.value)).collect();
+                format!("⚙️ All settings\n\n{}", lines.join("\n"))
+            }
+            Err(e) => format!("❌ {}", e),
+        });
+    }
+
+    // Handle "reset <key>"
+    if args_lower.starts_with("reset ") {
+        let key = args_str[6..].trim();
+        if key.is_empty() {
+            return CommandOutput::Reply("❌ Usage: /config reset <setting_name> | /config reset all".into());
+        }
+
+        if !key.eq_ignore_ascii_case("all") && !config::chat_settable(key) {
+            return CommandOutput::Reply(format!("❌ {}", server_only(key)));
+        }
+        let keys = if key.eq_ignore_ascii_case("all") { RESET_ALL } else { &[key][..] };
+        let mut modified = false;
+        for key in keys {
+            match config::reset(key) {
+                Ok(removed) => modified |= removed,
+                Err(e) => return CommandOutput::Reply(format!("❌ {}{}", e, unknown_hint(&e))),
+            }
+        }
+
+        if !modified {
+            return CommandOutput::Reply("✅ Key was already unset or empty.".into());
+        }
+        return CommandOutput::Reply(if keys.len() > 1 {
+            "✅ All keys and the model have been reset.\n⚠️ Restart the bot to apply changes.".into()
+        } else {
+            "✅ Key has been reset to its default.\n⚠️ Restart the bot to apply changes.".into()
+        });
+    }
+
+    CommandOutput::Reply("❌ Unknown config command. Use /config for help.".into())
+}
+
+/// What `/config reset all` clears: every key and wallet, and the model.
+const RESET_ALL: &[&str] = &[
+    "groq_key",
+    "openai_key",
+    "anthropic_key",
+    "deepseek_key",
+    "gemini_key",
+    "openrouter_key",
+    "polymarket_key",
+    "solana_key",
+    "model",
+];
+
+/// Why `key` can't be changed from a chat.
+fn server_only(key: &str) -> String {
+    format!(
+        "{} can't be changed from a chat. Run `crabbybot config set {} …` on the server. \
+         From here you can change: {}",
+        key,
+        key,
+        config::ALIASES.iter().map(|(alias, _)| *alias).collect::<Vec<_>>().join(", ")
+    )
+}
+
+fn unknown_hint(e: &SettingError) -> &'static str {
+    match e {
+        SettingError::UnknownKey(_) => ". Use /config list to see every setting.",
+        _ => "",
+    }
+}
@@ -0,0 +1,523 @@
+//! Reading and changing single settings by key path, shared by the
+//! `/config` chat command and `crabbybot config`.
+//!
+//! Edits work on the config file's own JSON: settings the user never set
+//! stay absent and keep following the defaults, and environment overrides
+//! are never written back. Every change is checked by parsing the result as
+//! a [`Config`], so a misspelled key or a value of the wrong type is refused
+//! instead of being silently dropped on the next load.
+
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use super::{Config, ConfigError};
+
+/// Short names from the original `/config set` keys and the paths they
+/// stand for.
+pub const ALIASES: &[(&str, &str)] = &[
+    ("groq_key", "providers.groq.apiKey"),
+    ("openai_key", "providers.openai.apiKey"),
+    ("anthropic_key", "providers.anthropic.apiKey"),
+    ("deepseek_key", "providers.deepseek.apiKey"),
+    ("gemini_key", "providers.gemini.apiKey"),
+    ("openrouter_key", "providers.openrouter.apiKey"),
+    ("polymarket_key", "tools.polymarket.privateKey"),
+    ("solana_key", "tools.solanaPrivateKey"),
+    ("model", "agents.defaults.model"),
+    ("max_tokens", "agents.defaults.max_tokens"),
+    ("temperature", "agents.defaults.temperature"),
+    ("betting_enabled", "tools.betting.enabled"),
+    ("max_bet", "tools.betting.maxBetSizeUsdc"),
+    ("daily_limit", "tools.betting.dailyLossLimitUsdc"),
+    ("strategy", "tools.betting.strategy"),
+    ("scan_interval", "tools.betting.scanIntervalMinutes"),
+];
+
+/// Errors from reading or changing a setting.
+#[derive(Debug, thiserror::Error)]
+pub enum SettingError {
+    /// The key path names no setting.
+    #[error("Unknown setting '{0}'")]
+    UnknownKey(String),
+
+    /// The value doesn't have the setting's type.
+    #[error("Invalid value for {key}: {reason}")]
+    InvalidValue { key: String, reason: String },
+
+    /// The value has the right type but fails [`Config::validate`].
+    #[error("{key} was not changed: {}", .problems.join(" "))]
+    Rejected { key: String, problems: Vec<String> },
+
+    /// A secret could not be encrypted with the vault key.
+    #[error("Failed to encrypt {key}: {reason}")]
+    Encrypt { key: String, reason: String },
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+/// One setting as shown to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    /// Full key path, e.g. `agents.defaults.model`.
+    pub key: String,
+    /// The value for display; secrets are masked.
+    pub value: String,
+    /// Whether the setting holds a key or password (stored encrypted).
+    pub secret: bool,
+}
+
+/// The file [`mutate`] and [`reset`] write: the one [`Config::load`] reads,
+/// or `~/.CrabbyBot/config.json` when there is none yet.
+pub fn target_path() -> PathBuf {
+    Config::existing_path().unwrap_or_else(Config::default_path)
+}
+
+/// Set `key_path` (a dotted path or one of the [`ALIASES`]) to `value` and
+/// save the config file.
+///
+/// `value` is read as JSON when it parses as such (`8192`, `true`,
+/// `["exec"]`) and as a plain string otherwise. Secrets are encrypted before
+/// they are written.
+pub fn mutate(key_path: &str, value: &str) -> Result<Setting, SettingError> {
+    let mut file = ConfigFile::open(target_path())?;
+    let setting = file.set(key_path, value)?;
+    file.save()?;
+    Ok(setting)
+}
+
+/// Remove `key_path` from the config file so it falls back to its default.
+/// Returns `false` when it wasn't set.
+pub fn reset(key_path: &str) -> Result<bool, SettingError> {
+    let mut file = ConfigFile::open(target_path())?;
+    let removed = file.reset(key_path)?;
+    if removed {
+        file.save()?;
+    }
+    Ok(removed)
+}
+
+/// The value `key_path` has in the config file, defaults filled in.
+/// Environment overrides are not applied.
+pub fn get(key_path: &str) -> Result<Setting, SettingError> {
+    ConfigFile::open(target_path())?.get(key_path)
+}
+
+/// Every setting with its current value, in key order.
+pub fn list() -> Result<Vec<Setting>, SettingError> {
+    ConfigFile::open(target_path())?.list()
+}
+
+/// The full key path behind a short name, or `key` itself.
+pub fn resolve_alias(key: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(key))
+        .map_or(key, |(_, path)| path)
+}
+
+/// Whether `/config set` and `/config reset` may change `key` from a chat:
+/// only the settings behind the [`ALIASES`], by short name or full path.
+/// Access lists, guardrails, approvals, gateway tokens and the rest are
+/// changed with `crabbybot config` on the server.
+pub fn chat_settable(key: &str) -> bool {
+    let path = normalize(resolve_alias(key.trim()));
+    ALIASES.iter().any(|(_, target)| normalize(target) == path)
+}
+
+/// Whether a setting holds a key or password, judged by its last segment.
+fn is_secret(path: &[String]) -> bool {
+    let last = path.last().map(|s| normalize(s)).unwrap_or_default();
+    ["apikey", "privatekey", "token", "secret", "password"]
+        .iter()
+        .any(|suffix| last.ends_with(suffix))
+}
+
+/// Keys compare without case, `_` or `-`, so `api_key` finds `apiKey`.
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Ways to read a raw value, tried in order until one fits the setting.
+fn candidates(raw: &str) -> Vec<Value> {
+    let raw = raw.trim();
+    let mut values = Vec::new();
+    if let Ok(value) = serde_json::from_str(raw) {
+        values.push(value);
+    }
+    match raw.to_lowercase().as_str() {
+        "on" | "yes" => values.push(Value::Bool(true)),
+        "off" | "no" => values.push(Value::Bool(false)),
+        _ => {}
+    }
+    values.push(Value::String(raw.to_string()));
+    values
+}
+
+/// Numbers compare loosely: `0.7` stored in an `f32` reads back as
+/// `0.699999988`.
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => (x - y).abs() <= 1e-6 * x.abs().max(1.0),
+        _ => a == b,
+    }
+}
+
+fn display(value: &Value, secret: bool) -> String {
+    match value {
+        Value::Null => "(not set)".into(),
+        Value::String(s) if s.is_empty() && secret => "(not set)".into(),
+        _ if secret => "••••••••".into(),
+        Value::String(s) if !s.is_empty() => s.clone(),
+        // An `f32` setting reads back as its exact binary value; show the
+        // short form the user typed.
+        Value::Number(n) => match n.as_f64() {
+            Some(x) if n.is_f64() && (x as f32) as f64 == x => (x as f32).to_string(),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+fn problems(config: &Config) -> Vec<String> {
+    config.validate().err().unwrap_or_default()
+}
+
+fn node<'a>(root: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(root, |node, key| match node {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+fn node_mut<'a>(root: &'a mut Value, path: &[String]) -> Option<&'a mut Value> {
+    path.iter().try_fold(root, |node, key| match node {
+        Value::Object(map) => map.get_mut(key),
+        Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// The slot at `path`, creating objects for missing or null levels.
+/// `None` when a level is a scalar or an index is out of range.
+fn slot_mut<'a>(root: &'a mut Value, path: &[String]) -> Option<&'a mut Value> {
+    let mut node = root;
+    for key in path {
+        if node.is_null() {
+            *node = Value::Object(Map::new());
+        }
+        node = match node {
+            Value::Object(map) => map.entry(key.clone()).or_insert(Value::Null),
+            Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+/// The config file as raw JSON.
+struct ConfigFile {
+    path: PathBuf,
+    doc: Value,
+}
+
+impl ConfigFile {
+    fn open(path: PathBuf) -> Result<Self, ConfigError> {
+        let doc = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|source| ConfigError::Invalid {
+                path: path.clone(),
+                source,
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Value::Object(Map::new()),
+            Err(source) => return Err(ConfigError::Read { path, source }),
+        };
+        Ok(Self { path, doc })
+    }
+
+    fn save(&self) -> Result<(), ConfigError> {
+        let write_err = |source| ConfigError::Write {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(write_err)?;
+        }
+        let json = serde_json::to_string_pretty(&self.doc).map_err(|e| write_err(e.into()))?;
+        std::fs::write(&self.path, json).map_err(write_err)?;
+        tracing::info!("Config saved to {}", self.path.display());
+        Ok(())
+    }
+
+    fn parse(doc: &Value) -> Result<Config, serde_json::Error> {
+        serde_json::from_value(doc.clone())
+    }
+
+    /// `doc` parsed and serialized again: every setting present, defaults
+    /// filled in.
+    fn effective(doc: &Value, path: &Path) -> Result<Value, ConfigError> {
+        let config = Self::parse(doc).map_err(|source| ConfigError::Invalid {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(serde_json::to_value(config).expect("Config always serializes"))
+    }
+
+    /// Turn a user-typed key into the path of keys the file uses.
+    ///
+    /// Each level is matched against the settings that level can hold; an
+    /// unset optional section is filled with its defaults first so its keys
+    /// are known too. Map levels (`agents.chats`, `tools.wallets`) take any
+    /// new key as typed.
+    fn resolve(&self, key: &str) -> Result<Vec<String>, SettingError> {
+        let unknown = || SettingError::UnknownKey(key.to_string());
+        let segments: Vec<&str> = resolve_alias(key.trim()).split('.').map(str::trim).collect();
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(unknown());
+        }
+
+        let mut path = Vec::new();
+        for segment in segments {
+            let mut probe = self.doc.clone();
+            if !path.is_empty() {
+                let slot = slot_mut(&mut probe, &path).ok_or_else(unknown)?;
+                if slot.is_null() {
+                    *slot = Value::Object(Map::new());
+                }
+            }
+            let Ok(effective) = Self::effective(&probe, &self.path) else {
+                return Err(unknown());
+            };
+            let name = match node(&effective, &path) {
+                Some(Value::Object(map)) => map
+                    .keys()
+                    .find(|k| normalize(k) == normalize(segment))
+                    .cloned()
+                    .unwrap_or_else(|| segment.to_string()),
+                Some(Value::Array(items)) if segment.parse::<usize>().is_ok_and(|i| i < items.len()) => {
+                    segment.to_string()
+                }
+                _ => return Err(unknown()),
+            };
+            path.push(name);
+        }
+        Ok(path)
+    }
+
+    fn set(&mut self, key: &str, raw: &str) -> Result<Setting, SettingError> {
+        let path = self.resolve(key)?;
+        let key = path.join(".");
+        let secret = is_secret(&path);
+        let before = Self::parse(&self.doc).map(|c| problems(&c)).unwrap_or_default();
+
+        let candidates = if secret && !crate::vault::is_encrypted(raw.trim()) {
+            let encrypted = crate::vault::encrypt(raw.trim()).map_err(|e| SettingError::Encrypt {
+                key: key.clone(),
+                reason: e.to_string(),
+            })?;
+            vec![Value::String(encrypted)]
+        } else {
+            candidates(raw)
+        };
+
+        let mut reason = String::new();
+        for value in candidates {
+            let mut doc = self.doc.clone();
+            *slot_mut(&mut doc, &path).ok_or_else(|| SettingError::UnknownKey(key.clone()))? = value.clone();
+            let config = match Self::parse(&doc) {
+                Ok(config) => config,
+                Err(e) => {
+                    reason = e.to_string();
+                    continue;
+                }
+            };
+            let effective = serde_json::to_value(&config).expect("Config always serializes");
+            match node(&effective, &path) {
+                // Unknown fields are dropped by the parser.
+                None => return Err(SettingError::UnknownKey(key)),
+                Some(stored) if !same_value(stored, &value) => {
+                    reason = format!("expected a value like {}", stored);
+                    continue;
+                }
+                Some(_) => {}
+            }
+
+            let new: Vec<String> = problems(&config).into_iter().filter(|p| !before.contains(p)).collect();
+            if !new.is_empty() {
+                return Err(SettingError::Rejected { key, problems: new });
+            }
+            self.doc = doc;
+            let value = display(node(&effective, &path).unwrap_or(&Value::Null), secret);
+            return Ok(Setting { key, value, secret });
+        }
+        Err(SettingError::InvalidValue { key, reason })
+    }
+
+    fn reset(&mut self, key: &str) -> Result<bool, SettingError> {
+        let path = self.resolve(key)?;
+        let (last, parent) = path.split_last().expect("resolved paths are never empty");
+        let removed = match node_mut(&mut self.doc, parent) {
+            Some(Value::Object(map)) => map.remove(last).is_some(),
+            Some(Value::Array(items)) => match last.parse::<usize>() {
+                Ok(i) if i < items.len() => {
+                    items.remove(i);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        Ok(removed)
+    }
+
+    fn get(&self, key: &str) -> Result<Setting, SettingError> {
+        let path = self.resolve(key)?;
+        let effective = Self::effective(&self.doc, &self.path)?;
+        let secret = is_secret(&path);
+        let value = display(node(&effective, &path).unwrap_or(&Value::Null), secret);
+        Ok(Setting { key: path.join("."), value, secret })
+    }
+
+    fn list(&self) -> Result<Vec<Setting>, SettingError> {
+        fn walk(value: &Value, path: &mut Vec<String>, out: &mut Vec<Setting>) {
+            match value {
+                Value::Object(map) if !map.is_empty() => {
+                    let mut keys: Vec<&String> = map.keys().collect();
+                    keys.sort();
+                    for key in keys {
+                        path.push(key.clone());
+                        walk(&map[key], path, out);
+                        path.pop();
+                    }
+                }
+                leaf => {
+                    let secret = is_secret(path);
+                    out.push(Setting {
+                        key: path.join("."),
+                        value: display(leaf, secret),
+                        secret,
+                    });
+                }
+            }
+        }
+
+        let effective = Self::effective(&self.doc, &self.path)?;
+        let mut out = Vec::new();
+        walk(&effective, &mut Vec::new(), &mut out);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, content: &str) -> ConfigFile {
+        let dir = std::env::temp_dir().join("CrabbyBot_test_config_edit");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.json", name));
+        std::fs::write(&path, content).unwrap();
+        ConfigFile::open(path).unwrap()
+    }
+
+    #[test]
+    fn test_set_keeps_the_file_sparse_and_typed() {
+        let mut file = file("sparse", r#"{"agents": {"defaults": {"model": "gpt-4o"}}}"#);
+        let setting = file.set("max_tokens", "4096").unwrap();
+        assert_eq!(setting.key, "agents.defaults.max_tokens");
+        assert_eq!(setting.value, "4096");
+
+        file.set("tools.betting.enabled", "on").unwrap();
+        file.set("agents.defaults.temperature", "0.3").unwrap();
+        file.save().unwrap();
+
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&file.path).unwrap()).unwrap();
+        assert_eq!(saved["agents"]["defaults"]["max_tokens"], 4096);
+        assert_eq!(saved["tools"]["betting"], serde_json::json!({"enabled": true}));
+        assert!(saved["tools"].get("exec").is_none());
+        assert_eq!(Config::load_from(&file.path).unwrap().agents.defaults.model, "gpt-4o");
+    }
+
+    #[test]
+    fn test_chats_may_only_change_the_aliased_settings() {
+        assert!(chat_settable("model"));
+        assert!(chat_settable("OPENAI_KEY"));
+        assert!(chat_settable("tools.betting.max_bet_size_usdc"));
+        assert!(!chat_settable("channels.telegram.allowFrom"));
+        assert!(!chat_settable("approvals.approvers"));
+        assert!(!chat_settable("gateway.api.token"));
+        assert!(!chat_settable("tools.shell"));
+    }
+
+    #[test]
+    fn test_keys_match_loosely_and_unknown_keys_are_refused() {
+        let mut file = file("keys", "{}");
+        let setting = file.set("tools.betting.max_bet_size_usdc", "12.5").unwrap();
+        assert_eq!(setting.key, "tools.betting.maxBetSizeUsdc");
+        assert_eq!(file.set("tools.web_search.maxresults", "3").unwrap().key, "tools.webSearch.maxResults");
+
+        assert!(matches!(file.set("tools.betting.maxBet", "1"), Err(SettingError::UnknownKey(_))));
+        assert!(matches!(file.set("nope", "1"), Err(SettingError::UnknownKey(_))));
+        assert!(matches!(
+            file.set("agents.defaults.model.name", "x"),
+            Err(SettingError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_types_and_invalid_values_are_refused() {
+        let mut file = file("invalid", "{}");
+        assert!(matches!(
+            file.set("max_tokens", "lots"),
+            Err(SettingError::InvalidValue { .. })
+        ));
+        assert!(matches!(file.set("temperature", "3"), Err(SettingError::Rejected { .. })));
+        assert!(matches!(file.set("strategy", "yolo"), Err(SettingError::Rejected { .. })));
+        assert!(file.set("strategy", "momentum").is_ok());
+        // A number typed for a string setting is kept as text.
+        assert_eq!(file.set("agents.defaults.locale", "1234").unwrap().value, "1234");
+    }
+
+    #[test]
+    fn test_map_entries_can_be_added() {
+        let mut file = file("maps", "{}");
+        let setting = file.set("agents.chats.telegram:42.timezone", "Europe/Berlin").unwrap();
+        assert_eq!(setting.key, "agents.chats.telegram:42.timezone");
+        let config = ConfigFile::parse(&file.doc).unwrap();
+        assert_eq!(config.agents.chats["telegram:42"].timezone.as_deref(), Some("Europe/Berlin"));
+        assert!(matches!(
+            file.set("agents.chats.telegram:42.timezone", "Mars/Olympus"),
+            Err(SettingError::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn test_secrets_are_encrypted_and_masked() {
+        let mut file = file("secrets", "{}");
+        let setting = file.set("groq_key", "gsk-secret-value").unwrap();
+        assert!(setting.secret);
+        assert_eq!(setting.value, "••••••••");
+
+        let stored = file.doc["providers"]["groq"]["apiKey"].as_str().unwrap();
+        assert!(crate::vault::is_encrypted(stored));
+        assert_eq!(crate::vault::decrypt(stored).unwrap(), "gsk-secret-value");
+
+        let listed = file.list().unwrap();
+        let groq = listed.iter().find(|s| s.key == "providers.groq.apiKey").unwrap();
+        assert_eq!(groq.value, "••••••••");
+        assert!(!listed.iter().any(|s| s.value.contains("gsk-")));
+        assert!(!is_secret(&["agents".into(), "defaults".into(), "max_tokens".into()]));
+    }
+
+    #[test]
+    fn test_reset_falls_back_to_the_default() {
+        let mut file = file("reset", r#"{"agents": {"defaults": {"max_tokens": 100}}}"#);
+        assert!(file.reset("max_tokens").unwrap());
+        assert!(!file.reset("max_tokens").unwrap());
+        assert_eq!(file.get("max_tokens").unwrap().value, "8192");
+        assert_eq!(file.get("temperature").unwrap().value, "0.7");
+    }
+}
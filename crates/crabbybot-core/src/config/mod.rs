@@ -4,9 +4,55 @@
 //! All fields use `serde` for zero-boilerplate deserialization.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+mod edit;
+pub use edit::{
+    chat_settable, get, list, mutate, reset, resolve_alias, target_path, Setting, SettingError, ALIASES,
+};
+
+/// Errors from loading or saving configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// An explicitly requested config file does not exist.
+    #[error("Config file not found: {0}")]
+    NotFound(PathBuf),
+
+    /// The config file exists but could not be read.
+    #[error("Failed to read config {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The config file is not valid JSON or does not match the schema.
+    #[error("Invalid config {path}: {source}")]
+    Invalid {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A guardrail rule can't be compiled; running without it would let
+    /// through calls it was meant to stop.
+    #[error("Invalid config {path}: {source}")]
+    Guardrail {
+        path: PathBuf,
+        #[source]
+        source: crate::guardrails::GuardrailError,
+    },
+
+    /// The config could not be written back to disk.
+    #[error("Failed to write config {path}: {source}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
 /// Root configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -17,6 +63,16 @@ pub struct Config {
     pub tools: ToolsConfig,
     pub channels: ChannelsConfig,
     pub gateway: GatewayConfig,
+    pub heartbeats: Vec<HeartbeatConfig>,
+    pub announcements: Vec<AnnouncementConfig>,
+    pub usage: UsageConfig,
+    pub update: UpdateConfig,
+    pub alerts: AlertsConfig,
+    pub approvals: ApprovalsConfig,
+    pub guardrails: GuardrailsConfig,
+    pub sync: SyncConfig,
+    pub telemetry: TelemetryConfig,
+    pub consolidation: ConsolidationConfig,
 }
 
 impl Config {
@@ -26,72 +82,88 @@ impl Config {
     /// 1. local `config.json` in current directory
     /// 2. `~/.ferrobot/config.json`
     /// 3. `~/.CrabbyBot/config.json`
-    pub fn load() -> anyhow::Result<Self> {
-        let paths = vec![
-            PathBuf::from("config.json"),
-            Self::ferrobot_path(),
-            Self::default_path(),
-        ];
-
-        for path in paths {
-            if path.exists() {
-                tracing::debug!("Loading config from: {}", path.display());
-                let content = std::fs::read_to_string(&path)?;
-                let mut config: Config = serde_json::from_str(&content)?;
-
-                // Security: Override sensitive fields from environment variables if present
-                if let Ok(key) = std::env::var("SOLANA_PRIVATE_KEY") {
-                    tracing::info!("Using Solana private key from environment variable");
-                    config.tools.solana_private_key = Some(key);
-                }
-                if let Ok(key) = std::env::var("POLYMARKET_PRIVATE_KEY") {
-                    tracing::info!("Using Polymarket private key from environment variable");
-                    config.tools.polymarket.private_key = Some(key);
-                }
-
-                return Ok(config);
-            }
+    pub fn load() -> Result<Self, ConfigError> {
+        if let Some(path) = Self::existing_path() {
+            tracing::debug!("Loading config from: {}", path.display());
+            let mut config = Self::load_from(&path)?;
+            config.apply_env_overrides();
+            return Ok(config);
         }
 
         // No config found, return default with placeholders
         let mut config = Config::default();
+        config.apply_env_overrides();
+        Ok(config)
+    }
+
+    /// Settings taken from the environment over the config file.
+    fn apply_env_overrides(&mut self) {
+        // Security: Override sensitive fields from environment variables if present
         if let Ok(key) = std::env::var("SOLANA_PRIVATE_KEY") {
             tracing::info!("Using Solana private key from environment variable");
-            config.tools.solana_private_key = Some(key);
+            self.tools.solana_private_key = Some(key);
         }
         if let Ok(key) = std::env::var("POLYMARKET_PRIVATE_KEY") {
             tracing::info!("Using Polymarket private key from environment variable");
-            config.tools.polymarket.private_key = Some(key);
+            self.tools.polymarket.private_key = Some(key);
+        }
+        if std::env::var("CRABBYBOT_READ_ONLY").is_ok_and(|v| matches!(v.as_str(), "1" | "true")) {
+            self.tools.read_only = true;
         }
-        Ok(config)
     }
 
     /// Load configuration from a specific path.
-    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_json::from_str(&content)?;
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| {
+            if source.kind() == std::io::ErrorKind::NotFound {
+                ConfigError::NotFound(path.to_path_buf())
+            } else {
+                ConfigError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                }
+            }
+        })?;
+        let config: Self = serde_json::from_str(&content).map_err(|source| ConfigError::Invalid {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        crate::guardrails::Guardrails::new(&config.guardrails, config.agents.defaults.timezone.as_deref())
+            .map_err(|source| ConfigError::Guardrail {
+                path: path.to_path_buf(),
+                source,
+            })?;
         Ok(config)
     }
 
     /// Save configuration to disk.
     ///
     /// Writes to the first existing config path, or `config.json` as fallback.
-    pub fn save(&self) -> anyhow::Result<()> {
-        let paths = vec![
-            PathBuf::from("config.json"),
-            Self::ferrobot_path(),
-            Self::default_path(),
-        ];
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let target = Self::existing_path().unwrap_or_else(|| PathBuf::from("config.json"));
 
-        let target = paths.iter().find(|p| p.exists()).cloned()
-            .unwrap_or_else(|| PathBuf::from("config.json"));
-
-        let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(&target, json)?;
+        let write_err = |source| ConfigError::Write {
+            path: target.clone(),
+            source,
+        };
+        let json = serde_json::to_string_pretty(self).map_err(|e| write_err(e.into()))?;
+        std::fs::write(&target, json).map_err(write_err)?;
         tracing::info!("Config saved to {}", target.display());
         Ok(())
     }
 
+    /// The config file [`load`](Self::load) reads: the first of the
+    /// candidates that exists.
+    pub fn existing_path() -> Option<PathBuf> {
+        [
+            PathBuf::from("config.json"),
+            Self::ferrobot_path(),
+            Self::default_path(),
+        ]
+        .into_iter()
+        .find(|p| p.exists())
+    }
+
     /// Get the path to `~/.ferrobot/config.json`.
     pub fn ferrobot_path() -> PathBuf {
         dirs::home_dir()
@@ -117,21 +189,31 @@ impl Config {
 
     /// Get the resolved workspace path.
     pub fn workspace_path(&self) -> PathBuf {
-        let raw = &self.agents.defaults.workspace;
-        if raw.starts_with("~/") || raw.starts_with("~\\") {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(&raw[2..])
-        } else {
-            PathBuf::from(raw)
-        }
+        expand_home(&self.agents.defaults.workspace)
+    }
+
+    /// Workspaces of chats that set `agents.chats.<key>.workspace`, keyed by
+    /// `channel:chat_id`.
+    pub fn chat_workspaces(&self) -> BTreeMap<String, PathBuf> {
+        self.agents
+            .chats
+            .iter()
+            .filter_map(|(key, chat)| {
+                let raw = chat.workspace.as_deref().filter(|w| !w.trim().is_empty())?;
+                Some((key.clone(), expand_home(raw)))
+            })
+            .collect()
     }
 
     /// Write the default config template to disk.
-    pub fn write_default_template() -> anyhow::Result<PathBuf> {
+    pub fn write_default_template() -> Result<PathBuf, ConfigError> {
         let path = Self::default_path();
+        let write_err = |source| ConfigError::Write {
+            path: path.clone(),
+            source,
+        };
         if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).map_err(write_err)?;
         }
 
         let template = serde_json::json!({
@@ -147,7 +229,8 @@ impl Config {
             }
         });
 
-        std::fs::write(&path, serde_json::to_string_pretty(&template)?)?;
+        let json = serde_json::to_string_pretty(&template).map_err(|e| write_err(e.into()))?;
+        std::fs::write(&path, json).map_err(write_err)?;
         Ok(path)
     }
 
@@ -157,6 +240,7 @@ impl Config {
     /// - At least one provider has a real (non-placeholder) API key
     /// - The default model is not empty
     /// - Enabled channels have a token configured
+    /// - Numeric and named settings are in range
     pub fn validate(&self) -> std::result::Result<(), Vec<String>> {
         let mut errors = Vec::new();
 
@@ -174,6 +258,25 @@ impl Config {
             errors.push("agents.defaults.model is empty. Specify a model name.".into());
         }
 
+        let temperature = self.agents.defaults.temperature;
+        if !(0.0..=2.0).contains(&temperature) {
+            errors.push(format!(
+                "agents.defaults.temperature is {}. Use a value between 0.0 and 2.0.",
+                temperature
+            ));
+        }
+
+        let betting = &self.tools.betting;
+        if !matches!(betting.strategy.as_str(), "value" | "momentum" | "contrarian") {
+            errors.push(format!(
+                "tools.betting.strategy: unknown strategy '{}'. Use 'value', 'momentum' or 'contrarian'.",
+                betting.strategy
+            ));
+        }
+        if betting.scan_interval_minutes == 0 {
+            errors.push("tools.betting.scanIntervalMinutes must be at least 1.".into());
+        }
+
         // Check channels — enabled channels must have a token.
         if let Some(ref tg) = self.channels.telegram {
             if tg.enabled && (tg.token.is_empty() || tg.token.contains("YOUR_")) {
@@ -194,6 +297,227 @@ impl Config {
             }
         }
 
+        #[cfg(feature = "gateway")]
+        {
+            let allow_lists = [
+                ("telegram", self.channels.telegram.as_ref().map(|c| &c.allow_from)),
+                ("discord", self.channels.discord.as_ref().map(|c| &c.allow_from)),
+            ];
+            for (channel, rules) in allow_lists {
+                for rule in rules.into_iter().flatten() {
+                    if let Err(e) = rule.parse::<crate::gateway::acl::Rule>() {
+                        errors.push(format!("channels.{}.allowFrom: '{}': {}.", channel, rule, e));
+                    }
+                }
+            }
+        }
+
+        let timezones = std::iter::once(("agents.defaults", &self.agents.defaults.timezone))
+            .chain(self.agents.chats.iter().map(|(k, c)| (k.as_str(), &c.timezone)));
+        for (scope, tz) in timezones {
+            if let Some(tz) = tz.as_deref().filter(|t| t.parse::<chrono_tz::Tz>().is_err()) {
+                errors.push(format!("{}: unknown timezone '{}'. Use an IANA name like 'Europe/Berlin'.", scope, tz));
+            }
+        }
+
+        let sync = &self.sync;
+        if sync.enabled {
+            if !matches!(sync.backend.as_str(), "s3" | "webdav") {
+                errors.push(format!("sync.backend: unknown backend '{}'. Use 's3' or 'webdav'.", sync.backend));
+            }
+            if sync.url.is_empty() {
+                errors.push("sync.url is empty. Set the S3 endpoint or WebDAV folder URL.".into());
+            }
+            if sync.backend == "s3" && (sync.bucket.is_empty() || sync.access_key_id.is_empty() || sync.secret.is_empty()) {
+                errors.push("sync: the s3 backend needs bucket, accessKeyId and secret.".into());
+            }
+            if sync.encryption_password.is_empty() {
+                errors.push("sync.encryptionPassword is empty. Files are never uploaded unencrypted.".into());
+            }
+            if sync.interval_minutes == 0 {
+                errors.push("sync.intervalMinutes must be at least 1.".into());
+            }
+        }
+
+        let telemetry = &self.telemetry;
+        if telemetry.enabled {
+            if !telemetry.endpoint.starts_with("https://") && !telemetry.endpoint.starts_with("http://") {
+                errors.push("telemetry.endpoint must be an http(s) URL when telemetry is on.".into());
+            }
+            if !(telemetry.epsilon.is_finite() && telemetry.epsilon > 0.0) {
+                errors.push("telemetry.epsilon must be greater than 0.".into());
+            }
+        }
+
+        let consolidation = &self.consolidation;
+        if consolidation.enabled {
+            if chrono::NaiveTime::parse_from_str(&consolidation.time, "%H:%M").is_err() {
+                errors.push(format!("consolidation.time: '{}' is not a time like 03:00.", consolidation.time));
+            }
+            for dir in &consolidation.scratch_dirs {
+                let inside = Path::new(dir).components().all(|c| matches!(c, std::path::Component::Normal(_)));
+                if dir.is_empty() || !inside {
+                    errors.push(format!("consolidation.scratchDirs: '{}' is not a folder inside the workspace.", dir));
+                }
+            }
+        }
+
+        for (key, workspace) in self.chat_workspaces() {
+            if workspace.exists() && !workspace.is_dir() {
+                errors.push(format!("agents.chats.{}.workspace: '{}' is not a directory.", key, workspace.display()));
+            }
+        }
+
+        for (name, entry) in self.providers.find_all_active() {
+            if let Err(e) = entry.endpoint_strategy.parse::<crate::provider::endpoints::EndpointStrategy>() {
+                errors.push(format!("providers.{}.endpointStrategy: {}. Use 'latency' or 'round-robin'.", name, e));
+            }
+            if !matches!(entry.api.as_str(), "" | "anthropic" | "openai") {
+                errors.push(format!("providers.{}.api: unknown API '{}'. Use 'anthropic' or 'openai'.", name, entry.api));
+            } else if name != "anthropic" && entry.api == "anthropic" {
+                errors.push(format!("providers.{}.api: only the anthropic entry speaks the Anthropic API.", name));
+            }
+            let min_budget = crate::provider::anthropic::MIN_THINKING_BUDGET;
+            if entry.thinking_budget > 0 && entry.thinking_budget < min_budget {
+                errors.push(format!(
+                    "providers.{}.thinkingBudget: {} is below the minimum of {} tokens.",
+                    name, entry.thinking_budget, min_budget
+                ));
+            }
+        }
+
+        for (i, a) in self.announcements.iter().enumerate().filter(|(_, a)| a.enabled) {
+            let label = if a.name.is_empty() { format!("#{}", i + 1) } else { format!("'{}'", a.name) };
+            if a.message.is_empty() && a.template.is_none() {
+                errors.push(format!("Announcement {} has neither a message nor a template.", label));
+            }
+            let schedule = crate::cron::Schedule::Cron {
+                expression: a.schedule.clone(),
+                timezone: a.timezone.clone(),
+            };
+            if let Err(e) = schedule.validate() {
+                errors.push(format!("Announcement {}: {}", label, e));
+            }
+        }
+
+        for (i, rule) in self.approvals.rules.iter().enumerate() {
+            let label = format!("approvals.rules[{}] ({})", i, rule.tool);
+            if rule.tool.is_empty() {
+                errors.push(format!("approvals.rules[{}] names no tool.", i));
+            }
+            if rule.required == 0 {
+                errors.push(format!("{}: required must be at least 1.", label));
+            } else if !rule.approvers.is_empty() && rule.required as usize > rule.approvers.len() {
+                errors.push(format!(
+                    "{}: needs {} approvals but lists only {} approvers.",
+                    label,
+                    rule.required,
+                    rule.approvers.len()
+                ));
+            }
+        }
+        if !self.approvals.rules.is_empty() {
+            if self.approvals.expiry_minutes == 0 {
+                errors.push("approvals.expiryMinutes must be at least 1.".into());
+            }
+            if self.approvals.escalate_after_minutes > 0 && !self.approvals.escalate_to.contains(':') {
+                errors.push(
+                    "approvals.escalateTo must be a chat like 'telegram:-100123' when escalation is on.".into(),
+                );
+            }
+        }
+
+        for (i, rule) in self.guardrails.rules.iter().enumerate() {
+            let label = format!("guardrails.rules[{}] ({})", i, rule.label());
+            if let Some(Err(e)) = rule.hours.as_deref().map(str::parse::<crate::guardrails::Hours>) {
+                errors.push(format!("{}: hours {}.", label, e));
+            }
+            if let Some(tz) = rule.timezone.as_deref().filter(|t| t.parse::<chrono_tz::Tz>().is_err()) {
+                errors.push(format!("{}: unknown timezone '{}'.", label, tz));
+            }
+            if rule.action == GuardrailAction::RequireApproval
+                && !rule.approvers.is_empty()
+                && rule.required.max(1) as usize > rule.approvers.len()
+            {
+                errors.push(format!(
+                    "{}: needs {} approvals but lists only {} approvers.",
+                    label,
+                    rule.required,
+                    rule.approvers.len()
+                ));
+            }
+        }
+
+        let mut hook_names = std::collections::HashSet::new();
+        for (i, hook) in self.gateway.webhooks.iter().enumerate().filter(|(_, h)| h.enabled) {
+            let valid = !hook.name.is_empty()
+                && hook.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                errors.push(format!(
+                    "gateway.webhooks[{}]: name '{}' must be letters, digits, '-' or '_'.",
+                    i, hook.name
+                ));
+            } else if !hook_names.insert(hook.name.as_str()) {
+                errors.push(format!("Webhook '{}' is defined twice.", hook.name));
+            }
+            if hook.secret.is_empty() {
+                errors.push(format!("Webhook '{}' has no secret.", hook.name));
+            }
+        }
+
+        if let Some(socket) = self.channels.websocket.as_ref().filter(|s| s.enabled) {
+            let mut tokens = std::collections::HashSet::new();
+            for (name, token) in &socket.clients {
+                let valid = !name.is_empty()
+                    && name.len() <= 64
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if !valid {
+                    errors.push(format!(
+                        "channels.websocket.clients: name '{}' must be 1-64 letters, digits, '-' or '_'.",
+                        name
+                    ));
+                }
+                if token.is_empty() {
+                    errors.push(format!("channels.websocket.clients.{} has no token.", name));
+                } else if !tokens.insert(token.as_str()) {
+                    errors.push(format!("channels.websocket.clients.{} shares its token with another client.", name));
+                }
+            }
+        }
+
+        if self.gateway.api.enabled && self.gateway.api.token.is_empty() {
+            errors.push("gateway.api is enabled but has no token.".to_string());
+        }
+
+        for (name, pipeline) in &self.tools.pipelines {
+            if pipeline.steps.is_empty() {
+                errors.push(format!("tools.pipelines.{} has no steps.", name));
+            }
+            for (i, step) in pipeline.steps.iter().enumerate() {
+                let label = format!("tools.pipelines.{}.steps[{}]", name, i);
+                if step.tool.is_empty() {
+                    errors.push(format!("{} names no tool.", label));
+                } else if step.tool == crate::tools::pipeline::RUN_PIPELINE {
+                    errors.push(format!("{}: pipelines can't run other pipelines.", label));
+                }
+                if let Err(e) = crate::tools::pipeline::check_step(step) {
+                    errors.push(format!("{}: {}.", label, e));
+                }
+            }
+        }
+
+        for (name, wallet) in &self.tools.wallets {
+            if wallet.address.is_none() && wallet.private_key.is_none() {
+                errors.push(format!(
+                    "Wallet '{}' has neither an address nor a private key.",
+                    name
+                ));
+            }
+            if wallet.max_trade_usd < 0.0 || wallet.daily_limit_usd < 0.0 {
+                errors.push(format!("Wallet '{}' has a negative risk limit.", name));
+            }
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -202,6 +526,17 @@ impl Config {
     }
 }
 
+/// Resolve a leading `~/` to the home directory.
+fn expand_home(raw: &str) -> PathBuf {
+    if raw.starts_with("~/") || raw.starts_with("~\\") {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(&raw[2..])
+    } else {
+        PathBuf::from(raw)
+    }
+}
+
 // ── Provider Configuration ──────────────────────────────────────────
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -212,6 +547,29 @@ pub struct ProviderEntry {
     pub model: Option<String>,
     #[serde(default)]
     pub extra_headers: HashMap<String, String>,
+    /// Models on this provider that can't take parallel tool calls; their
+    /// calls are run one per turn. A trailing `*` matches by prefix.
+    pub sequential_tool_models: Vec<String>,
+    /// Further base URLs serving the same deployment (other regions or
+    /// cluster nodes), tried after `apiBase` when it is slow or failing.
+    pub api_bases: Vec<String>,
+    /// How requests are spread over the endpoints: `latency` (default,
+    /// fastest first) or `round-robin`.
+    pub endpoint_strategy: String,
+    /// Which API the `anthropic` entry speaks: the native Messages API
+    /// (default) or `openai` for Anthropic's OpenAI-compatible endpoint.
+    /// Other entries are always OpenAI-compatible.
+    pub api: String,
+    /// Native Anthropic API only: tokens of extended thinking before each
+    /// answer (at least 1024). 0 turns thinking off.
+    pub thinking_budget: u32,
+    /// Native Anthropic API only: mark the tools, system prompt and history
+    /// for prompt caching.
+    pub prompt_caching: bool,
+    /// Ollama only: how long the model stays loaded after a request (`10m`,
+    /// `1h`, `0` to unload at once, `-1` to keep it loaded). Unset leaves
+    /// the server default of 5 minutes.
+    pub keep_alive: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -224,6 +582,9 @@ pub struct ProvidersConfig {
     pub groq: Option<ProviderEntry>,
     pub gemini: Option<ProviderEntry>,
     pub vllm: Option<ProviderEntry>,
+    /// A local Ollama server. Needs no API key: the entry being present
+    /// enables it, with `apiBase` defaulting to `http://localhost:11434`.
+    pub ollama: Option<ProviderEntry>,
 }
 
 impl ProvidersConfig {
@@ -232,7 +593,8 @@ impl ProvidersConfig {
         self.find_all_active().into_iter().next()
     }
 
-    /// Find all configured providers that have a real API key.
+    /// Find all configured providers that have a real API key, plus Ollama
+    /// when it has an entry.
     pub fn find_all_active(&self) -> Vec<(&'static str, &ProviderEntry)> {
         let placeholder_prefixes = ["YOUR_", "sk-or-v1-YOUR", "sk-YOUR", "sk-ant-YOUR"];
 
@@ -256,6 +618,9 @@ impl ProvidersConfig {
                 }
             }
         }
+        if let Some(e) = &self.ollama {
+            active.push(("ollama", e));
+        }
         active
     }
 }
@@ -270,6 +635,33 @@ pub struct AgentDefaults {
     pub max_tokens: u32,
     pub temperature: f32,
     pub max_tool_iterations: u32,
+    /// How many times a reply cut off at `max_tokens` is continued before
+    /// it is returned as is. 0 disables continuations.
+    pub max_continuations: u32,
+    /// Show replies in chat while they are being written, where the
+    /// channel supports it.
+    pub stream: bool,
+    /// `reasoning_effort` sent to OpenAI-style reasoning models ("low",
+    /// "medium" or "high"). Unset leaves the provider default.
+    pub reasoning_effort: Option<String>,
+    /// IANA timezone for dates the agent sees and cron schedules (e.g.
+    /// "Europe/Berlin"). Unset means the server's local time.
+    pub timezone: Option<String>,
+    /// BCP 47 locale the agent formats dates for (e.g. "en-US", "de-DE").
+    pub locale: Option<String>,
+    /// Language to always reply in (e.g. "German" or "de"). Unset replies
+    /// in the language each message is written in.
+    pub language: Option<String>,
+    /// Replaces the built-in persona at the top of the system prompt. A
+    /// `persona.md` in the workspace takes precedence.
+    pub system_prompt: Option<String>,
+    /// Model for summaries: history that no longer fits the context budget,
+    /// compressed context and digests. Unset uses `model`; a cheaper one
+    /// keeps long conversations affordable.
+    pub summary_model: Option<String>,
+    /// Models `/model` may switch a conversation to, besides `model` and
+    /// `summary_model`. Leave empty to keep every chat on those.
+    pub models: Vec<String>,
 }
 
 impl Default for AgentDefaults {
@@ -280,6 +672,15 @@ impl Default for AgentDefaults {
             max_tokens: 8192,
             temperature: 0.7,
             max_tool_iterations: 20,
+            max_continuations: 2,
+            stream: true,
+            reasoning_effort: None,
+            timezone: None,
+            locale: None,
+            language: None,
+            system_prompt: None,
+            summary_model: None,
+            models: Vec::new(),
         }
     }
 }
@@ -288,6 +689,36 @@ impl Default for AgentDefaults {
 #[serde(default)]
 pub struct AgentsConfig {
     pub defaults: AgentDefaults,
+    /// Per-chat overrides keyed by `channel:chat_id` (e.g. "telegram:12345").
+    pub chats: BTreeMap<String, ChatConfig>,
+    /// Per-channel overrides keyed by channel name (e.g. "telegram").
+    pub channels: BTreeMap<String, ChannelAgentConfig>,
+}
+
+/// Settings of one channel that override `agents.defaults`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelAgentConfig {
+    /// Persona for this channel; `persona.<channel>.md` in the workspace
+    /// takes precedence.
+    pub system_prompt: Option<String>,
+}
+
+/// Settings of one chat that override `agents.defaults`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+    /// Reply language for this chat; `"auto"` restores detection when
+    /// `agents.defaults.language` fixes one.
+    pub language: Option<String>,
+    /// Record messages for `/digest` instead of answering them. Commands
+    /// still work.
+    pub listen_only: bool,
+    /// Workspace of this chat (`~/` allowed), replacing
+    /// `agents.defaults.workspace` for its files, sessions and memory.
+    pub workspace: Option<String>,
 }
 
 // ── Tools Configuration ─────────────────────────────────────────────
@@ -300,8 +731,67 @@ pub struct ToolsConfig {
     pub exec: ExecConfig,
     pub solana_rpc_url: String,
     pub solana_private_key: Option<String>,
+    /// Ethereum JSON-RPC URL, used for gas estimates.
+    pub ethereum_rpc_url: String,
     pub polymarket: PolymarketConfig,
     pub betting: BettingConfig,
+    /// Named wallets, e.g. `{"main": {...}, "degen": {...}}`.
+    pub wallets: BTreeMap<String, WalletConfig>,
+    pub nft: NftConfig,
+    pub sports: SportsConfig,
+    pub news: NewsConfig,
+    /// Allow-list of tool names. When non-empty, only these tools are registered.
+    pub enabled: Vec<String>,
+    /// Deny-list of tool names. Applied after `enabled`.
+    pub disabled: Vec<String>,
+    /// Names to expose tools under, settling clashes between sources:
+    /// `{"web_search": "mcp.web_search", "ddg_search": "builtin.web_search"}`.
+    /// Targets are registered names or `source.name`.
+    pub aliases: BTreeMap<String, String>,
+    /// Log a warning when a tool's p95 latency exceeds this many
+    /// milliseconds. 0 disables the warning.
+    pub slow_tool_p95_ms: u64,
+    /// Refuse every tool call that writes files, runs commands, trades or
+    /// changes schedules, whatever else is enabled. Also set by
+    /// `--read-only` / `CRABBYBOT_READ_ONLY=1`.
+    pub read_only: bool,
+    /// Refuse orders until the agent records why with `journal_decision`,
+    /// see [`crate::journal`].
+    pub require_decision_journal: bool,
+    /// Named chains of tool calls that `run_pipeline` runs without the
+    /// model, see [`crate::tools::pipeline`].
+    pub pipelines: BTreeMap<String, PipelineConfig>,
+}
+
+impl ToolsConfig {
+    /// Whether a tool with the given name should be registered.
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        if !self.enabled.is_empty() && !self.enabled.iter().any(|n| n == name) {
+            return false;
+        }
+        !self.disabled.iter().any(|n| n == name)
+    }
+
+    /// All configured wallets. The legacy `solanaPrivateKey` and
+    /// `polymarket.privateKey` fields show up as wallets named `solana` and
+    /// `polymarket` unless a wallet of that name is configured explicitly.
+    pub fn named_wallets(&self) -> BTreeMap<String, WalletConfig> {
+        let mut wallets = self.wallets.clone();
+        let legacy = [
+            ("solana", WalletChain::Solana, &self.solana_private_key),
+            ("polymarket", WalletChain::Polygon, &self.polymarket.private_key),
+        ];
+        for (name, chain, key) in legacy {
+            if let Some(key) = key.as_ref().filter(|k| !k.is_empty()) {
+                wallets.entry(name.to_string()).or_insert_with(|| WalletConfig {
+                    chain,
+                    private_key: Some(key.clone()),
+                    ..Default::default()
+                });
+            }
+        }
+        wallets
+    }
 }
 
 impl Default for ToolsConfig {
@@ -312,12 +802,85 @@ impl Default for ToolsConfig {
             exec: ExecConfig::default(),
             solana_rpc_url: "https://api.mainnet-beta.solana.com".into(),
             solana_private_key: None,
+            ethereum_rpc_url: "https://ethereum-rpc.publicnode.com".into(),
             polymarket: PolymarketConfig::default(),
             betting: BettingConfig::default(),
+            wallets: BTreeMap::new(),
+            nft: NftConfig::default(),
+            sports: SportsConfig::default(),
+            news: NewsConfig::default(),
+            enabled: Vec::new(),
+            disabled: Vec::new(),
+            aliases: BTreeMap::new(),
+            slow_tool_p95_ms: 15_000,
+            read_only: false,
+            require_decision_journal: true,
+            pipelines: BTreeMap::new(),
         }
     }
 }
 
+/// A pipeline: tool calls run one after another, each fed from the
+/// output of the one before.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PipelineConfig {
+    /// What the pipeline does, shown to the model in `run_pipeline`.
+    pub description: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// One call in a pipeline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PipelineStep {
+    pub tool: String,
+    /// Arguments for the call. String values starting with `$` are paths:
+    /// `$` is the previous step's output, `$input` the pipeline's input and
+    /// `$item` the current element of `forEach`, e.g. `"$.tokens[0].mint"`.
+    /// A leading `$$` stands for a literal `$`.
+    pub args: serde_json::Map<String, serde_json::Value>,
+    /// Path to an array in the previous step's output: the tool is called
+    /// once per element and the step's output is the array of results.
+    pub for_each: Option<String>,
+}
+
+// ── Wallet Configuration ────────────────────────────────────────────
+
+/// Chain a named wallet lives on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletChain {
+    #[default]
+    Solana,
+    /// Polygon (Polymarket).
+    Polygon,
+}
+
+impl WalletChain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Solana => "solana",
+            Self::Polygon => "polygon",
+        }
+    }
+}
+
+/// One entry of `tools.wallets`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WalletConfig {
+    pub chain: WalletChain,
+    /// Public address; derived from `private_key` when omitted.
+    pub address: Option<String>,
+    /// Signing key, plain or vault-encrypted. Without it the wallet is watch-only.
+    pub private_key: Option<String>,
+    /// Largest single trade from this wallet, in USD. 0 means no limit.
+    pub max_trade_usd: f64,
+    /// Total USD traded from this wallet per UTC day. 0 means no limit.
+    pub daily_limit_usd: f64,
+}
+
 // ── Betting Configuration ───────────────────────────────────────────
 
 /// Configuration for the autonomous Polymarket betting engine.
@@ -397,6 +960,87 @@ impl Default for WebSearchConfig {
     }
 }
 
+/// Indexers behind the NFT tools.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NftConfig {
+    /// RPC endpoint supporting the Digital Asset Standard (DAS) API, e.g.
+    /// a Helius URL with its `api-key`. Empty means `solanaRpcUrl`.
+    pub das_url: String,
+    /// Magic Eden API base used for collection floor prices. Empty disables
+    /// floor lookups.
+    pub marketplace_url: String,
+}
+
+impl Default for NftConfig {
+    fn default() -> Self {
+        Self {
+            das_url: String::new(),
+            marketplace_url: "https://api-mainnet.magiceden.dev/v2".into(),
+        }
+    }
+}
+
+/// Score providers behind `sports_scores`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SportsConfig {
+    /// ESPN site API base.
+    pub espn_url: String,
+    /// API-Football (api-sports.io) key, plain or vault-encrypted. When set,
+    /// soccer comes from API-Football instead of ESPN.
+    pub api_football_key: String,
+    pub api_football_url: String,
+}
+
+impl Default for SportsConfig {
+    fn default() -> Self {
+        Self {
+            espn_url: "https://site.api.espn.com/apis/site/v2/sports".into(),
+            api_football_key: String::new(),
+            api_football_url: "https://v3.football.api-sports.io".into(),
+        }
+    }
+}
+
+/// News index behind `news_search`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewsProvider {
+    /// Brave news search; shares `webSearch.apiKey` when `news.apiKey` is empty.
+    #[default]
+    Brave,
+    /// newsapi.org
+    NewsApi,
+    /// gnews.io
+    GNews,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NewsConfig {
+    pub provider: NewsProvider,
+    /// Provider API key, plain or vault-encrypted.
+    pub api_key: String,
+    /// When non-empty, only these sources (domains or names) are returned.
+    pub allow_sources: Vec<String>,
+    /// Sources (domains or names) never returned.
+    pub deny_sources: Vec<String>,
+    pub max_results: u32,
+}
+
+impl Default for NewsConfig {
+    fn default() -> Self {
+        Self {
+            provider: NewsProvider::default(),
+            api_key: String::new(),
+            allow_sources: Vec::new(),
+            deny_sources: Vec::new(),
+            max_results: 10,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ExecConfig {
@@ -420,6 +1064,50 @@ impl Default for ExecConfig {
 pub struct ChannelsConfig {
     pub telegram: Option<TelegramConfig>,
     pub discord: Option<DiscordConfig>,
+    pub webchat: Option<WebChatConfig>,
+    pub websocket: Option<WebSocketConfig>,
+}
+
+impl ChannelsConfig {
+    /// Session scope of every configured channel, keyed by channel name.
+    pub fn session_scopes(&self) -> HashMap<String, SessionScope> {
+        let scopes = [
+            ("telegram", self.telegram.as_ref().map(|c| c.session_scope)),
+            ("discord", self.discord.as_ref().map(|c| c.session_scope)),
+            ("webchat", self.webchat.as_ref().map(|c| c.session_scope)),
+            ("websocket", self.websocket.as_ref().map(|c| c.session_scope)),
+        ];
+        scopes
+            .into_iter()
+            .filter_map(|(channel, scope)| Some((channel.to_string(), scope?)))
+            .collect()
+    }
+}
+
+/// Which messages of a channel share one conversation history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionScope {
+    /// One session per chat, `channel:chat_id`.
+    #[default]
+    Chat,
+    /// One per user in each chat, `channel:chat_id:user_id`, so members of
+    /// a group don't see each other's conversations.
+    User,
+    /// One session for every chat of the channel, `channel`.
+    Shared,
+}
+
+impl SessionScope {
+    /// Session key for a message from `user_id` in `channel:chat_id`.
+    /// Messages without a user fall back to the chat's session.
+    pub fn session_key(self, channel: &str, chat_id: &str, user_id: &str) -> String {
+        match self {
+            Self::User if !user_id.is_empty() => format!("{}:{}:{}", channel, chat_id, user_id),
+            Self::Chat | Self::User => format!("{}:{}", channel, chat_id),
+            Self::Shared => channel.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -427,7 +1115,10 @@ pub struct ChannelsConfig {
 pub struct TelegramConfig {
     pub enabled: bool,
     pub token: String,
+    /// Who may use the bot: user ids, or `user:`, `chat:` rules with `*`
+    /// wildcards (see [`crate::gateway::acl`]). Empty allows everyone.
     pub allow_from: Vec<String>,
+    pub session_scope: SessionScope,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -435,16 +1126,67 @@ pub struct TelegramConfig {
 pub struct DiscordConfig {
     pub enabled: bool,
     pub token: String,
+    /// Like Telegram's, plus `guild:` and `role:` rules.
     pub allow_from: Vec<String>,
+    pub session_scope: SessionScope,
+}
+
+/// Browser chat UI served on `gateway.host:gateway.port`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebChatConfig {
+    pub enabled: bool,
+    /// Shared access token; clients pass it as `?token=`. Required unless
+    /// `telegramLogin` is on.
+    pub token: String,
+    pub session_scope: SessionScope,
+    /// Let browsers sign in through the Telegram bot: a deep link pairs
+    /// the page with a user `channels.telegram.allowFrom` lets in (see
+    /// [`crate::gateway::pairing`]). Needs the Telegram channel enabled.
+    pub telegram_login: bool,
+}
+
+/// JSON-over-WebSocket endpoint for custom frontends, served at `/socket`
+/// on `gateway.host:gateway.port` (see [`crate::gateway::channels::websocket`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebSocketConfig {
+    pub enabled: bool,
+    /// Client name → its access token. Names are 1–64 letters, digits,
+    /// `-` or `_` and keep each client's chats apart.
+    pub clients: BTreeMap<String, String>,
+    pub session_scope: SessionScope,
 }
 
 // ── Gateway Configuration ───────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, rename_all = "camelCase")]
 pub struct GatewayConfig {
     pub host: String,
     pub port: u16,
+    pub delivery: DeliveryConfig,
+    /// How long to wait for follow-up messages from the same user before
+    /// answering, so long texts split by the client arrive as one message.
+    /// 0 disables stitching.
+    pub coalesce_window_ms: u64,
+    /// Largest file accepted from chat uploads, in megabytes.
+    pub max_upload_mb: u64,
+    /// Cancel an agent turn still running after this many seconds, so a
+    /// hung provider or tool call can't freeze the bot. 0 disables it.
+    pub turn_timeout_secs: u64,
+    /// Endpoints external systems post events to (`webhooks` feature).
+    pub webhooks: Vec<WebhookConfig>,
+    /// Post-processing of agent replies before they are sent.
+    pub replies: RepliesConfig,
+    /// After a restart, tell the admin chat what state was recovered, see
+    /// [`crate::recovery`].
+    pub restart_report: bool,
+    /// Tag changed sessions with topics and sentiment this often, see
+    /// [`crate::session::tags`]. 0 disables it.
+    pub tag_sessions_minutes: u64,
+    /// The `/v1` REST API (`api` feature), see [`crate::gateway::http`].
+    pub api: ApiConfig,
 }
 
 impl Default for GatewayConfig {
@@ -452,6 +1194,482 @@ impl Default for GatewayConfig {
         Self {
             host: "0.0.0.0".into(),
             port: 18790,
+            delivery: DeliveryConfig::default(),
+            coalesce_window_ms: 1500,
+            max_upload_mb: 20,
+            turn_timeout_secs: 300,
+            webhooks: Vec::new(),
+            replies: RepliesConfig::default(),
+            restart_report: true,
+            tag_sessions_minutes: 60,
+            api: ApiConfig::default(),
+        }
+    }
+}
+
+/// The REST API other services drive the agent through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ApiConfig {
+    pub enabled: bool,
+    /// Bearer token clients must send, required. May be vault-encrypted.
+    pub token: String,
+    /// How long `POST /v1/messages` waits for the agent's reply.
+    pub reply_timeout_secs: u64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: String::new(),
+            reply_timeout_secs: 300,
+        }
+    }
+}
+
+/// How agent replies are rewritten before they are sent, see
+/// [`crate::gateway::postprocess`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RepliesConfig {
+    /// Appended to replies about trading or markets. Empty adds nothing.
+    pub disclaimer: String,
+    /// Appended to every reply. Empty adds nothing.
+    pub signature: String,
+    /// Remove phrases like "As an AI language model, …".
+    pub strip_self_references: bool,
+    /// Write dollar amounts, percentages and times the way the chat's
+    /// locale does (`agents.chats.*.locale`, then `agents.defaults.locale`).
+    pub localize: bool,
+    /// Longest reply sent at once, in characters; the rest follows when
+    /// the user says "more". 0 sends replies whole.
+    pub max_chars: usize,
+}
+
+/// An endpoint at `POST /hooks/<name>` on `gateway.host:gateway.port` that
+/// turns each request into a message for the agent, see
+/// [`crate::gateway::webhooks`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    /// Path segment of the URL: letters, digits, `-` and `_`.
+    pub name: String,
+    /// Shared secret, required. Checked against GitHub-style
+    /// `X-Hub-Signature-256` signatures; other senders pass it as
+    /// `?secret=`, an `X-Webhook-Secret` header, or a bearer token.
+    pub secret: String,
+    /// Template text (see [`crate::templates`]) for the agent's message;
+    /// gets `hook`, `event` and `payload` (the JSON body, or the raw text
+    /// when it isn't JSON). Empty sends the payload as is.
+    pub message: String,
+    /// Workspace template to render instead of `message`.
+    pub template: Option<String>,
+    /// Chat whose conversation the event joins (e.g. "telegram").
+    pub channel: String,
+    /// Target chat; empty means the runtime's default chat.
+    pub chat_id: String,
+    /// Mutating tools (orders, transfers, files, schedules) the agent may
+    /// call while handling this hook's events. Empty keeps those turns
+    /// read-only.
+    pub tools: Vec<String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: String::new(),
+            secret: String::new(),
+            message: String::new(),
+            template: None,
+            channel: "telegram".into(),
+            chat_id: String::new(),
+            tools: Vec::new(),
+        }
+    }
+}
+
+/// Outbound reply delivery tracking.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeliveryConfig {
+    /// Retry failed replies and log each outcome to `traces/delivery.jsonl`.
+    pub track: bool,
+    /// Total send attempts per reply, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry.
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            track: false,
+            max_attempts: 3,
+            retry_backoff_ms: 1000,
+        }
+    }
+}
+
+// ── Heartbeat Configuration ─────────────────────────────────────────
+
+/// A proactive wake-up prompt, turned into a [`crate::heartbeat::Heartbeat`] at startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HeartbeatConfig {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    pub message: String,
+    /// Target channel (e.g. "telegram").
+    pub channel: String,
+    /// Target chat; empty means the runtime's default chat.
+    pub chat_id: String,
+    /// Keep firing when the daily token budget is nearly used up.
+    pub critical: bool,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 60,
+            message: String::new(),
+            channel: "telegram".into(),
+            chat_id: String::new(),
+            critical: false,
+        }
+    }
+}
+
+/// A fixed message posted on a cron schedule without running the agent,
+/// turned into a [`crate::cron::Announcement`] at startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AnnouncementConfig {
+    pub enabled: bool,
+    pub name: String,
+    /// Cron expression with seconds, e.g. "0 0 9 * * Mon" for Mondays at 9:00.
+    pub schedule: String,
+    /// IANA timezone for the schedule; unset uses `agents.defaults.timezone`.
+    pub timezone: Option<String>,
+    /// Template text (see [`crate::templates`]); gets `name`, `date`,
+    /// `time` and `weekday`.
+    pub message: String,
+    /// Workspace template to render instead of `message`.
+    pub template: Option<String>,
+    /// Target channel (e.g. "telegram").
+    pub channel: String,
+    /// Target chat; empty means the runtime's default chat.
+    pub chat_id: String,
+}
+
+impl Default for AnnouncementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: String::new(),
+            schedule: String::new(),
+            timezone: None,
+            message: String::new(),
+            template: None,
+            channel: "telegram".into(),
+            chat_id: String::new(),
+        }
+    }
+}
+
+// ── Usage Configuration ─────────────────────────────────────────────
+
+/// Daily token budget, see [`crate::usage`], and the pacing of LLM calls,
+/// see [`crate::scheduler`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UsageConfig {
+    /// Tokens per UTC day; 0 means unlimited.
+    pub daily_token_budget: u64,
+    /// Share of the budget (in percent) after which heartbeats and
+    /// non-critical cron jobs are skipped.
+    pub background_cutoff_percent: u8,
+    /// LLM calls per minute shared by chat, cron and heartbeats; 0 leaves
+    /// calls unpaced.
+    pub calls_per_minute: u32,
+    /// Calls that may go out at once after a quiet spell.
+    pub burst: u32,
+    /// A call waiting this long goes ahead of higher-priority ones.
+    pub starvation_secs: u64,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            daily_token_budget: 0,
+            background_cutoff_percent: 90,
+            calls_per_minute: 0,
+            burst: 10,
+            starvation_secs: 120,
+        }
+    }
+}
+
+// ── Alerts Configuration ────────────────────────────────────────────
+
+/// Repeat suppression for scheduled alerts, see [`crate::alerts`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AlertsConfig {
+    /// Identical alerts to the same chat are sent at most once per this
+    /// many seconds; 0 disables deduplication.
+    pub cooldown_secs: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self { cooldown_secs: 900 }
+    }
+}
+
+// ── Approvals Configuration ─────────────────────────────────────────
+
+/// Sign-off required before risky tool calls run, see [`crate::approvals`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ApprovalsConfig {
+    /// Checked in order; the first rule matching a call applies. No rules,
+    /// no approvals.
+    pub rules: Vec<ApprovalRule>,
+    /// Cancel requests still pending after this many minutes.
+    pub expiry_minutes: u64,
+    /// Repost requests nobody answered within this many minutes to
+    /// `escalateTo`. 0 disables escalation.
+    pub escalate_after_minutes: u64,
+    /// Chat (`channel:chat_id`) that escalated requests go to.
+    pub escalate_to: String,
+}
+
+impl Default for ApprovalsConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            expiry_minutes: 60,
+            escalate_after_minutes: 0,
+            escalate_to: String::new(),
+        }
+    }
+}
+
+/// Which calls wait for whom.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ApprovalRule {
+    /// Tool name; a trailing `*` matches any suffix (`polymarket_*`).
+    pub tool: String,
+    /// Only calls committing at least this many USD. 0 matches every
+    /// mutating call, including those of tools that report no amount.
+    pub min_usd: f64,
+    /// Distinct approvals needed before the call runs.
+    pub required: u32,
+    /// User ids who may approve; empty lets anyone in the chat approve.
+    /// Whoever made the call never approves it, even when listed.
+    pub approvers: Vec<String>,
+}
+
+impl Default for ApprovalRule {
+    fn default() -> Self {
+        Self {
+            tool: String::new(),
+            min_usd: 0.0,
+            required: 1,
+            approvers: Vec::new(),
+        }
+    }
+}
+
+// ── Guardrail Configuration ─────────────────────────────────────────
+
+/// Rules checked before every tool call, see [`crate::guardrails`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GuardrailsConfig {
+    /// Every matching rule applies: any `block` refuses the call, the first
+    /// `requireApproval` holds it, and each `annotate` adds its message.
+    pub rules: Vec<GuardrailRule>,
+}
+
+/// What a matching guardrail does to the call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GuardrailAction {
+    #[default]
+    Block,
+    RequireApproval,
+    Annotate,
+}
+
+/// Conditions on a tool call; all that are set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GuardrailRule {
+    /// Shown in refusals and notes.
+    pub name: String,
+    /// Tool name; a trailing `*` matches any suffix. Empty matches every tool.
+    pub tool: String,
+    /// Argument name → text its value must contain (case-insensitive).
+    /// The name `*` matches any argument.
+    pub args: BTreeMap<String, String>,
+    /// User ids the rule applies to; empty means everyone.
+    pub users: Vec<String>,
+    /// Time-of-day window, e.g. `00:00-07:00`; may wrap past midnight.
+    pub hours: Option<String>,
+    /// IANA timezone for `hours`; defaults to `agents.defaults.timezone`.
+    pub timezone: Option<String>,
+    /// Only calls committing at least this many USD. 0 ignores amounts.
+    pub min_usd: f64,
+    pub action: GuardrailAction,
+    /// Why the call is blocked, or the note an `annotate` rule adds.
+    pub message: String,
+    /// For `requireApproval`: distinct approvals needed.
+    pub required: u32,
+    /// For `requireApproval`: who may approve; empty lets anyone.
+    pub approvers: Vec<String>,
+}
+
+// ── Update Configuration ────────────────────────────────────────────
+
+/// Where `self-update` looks for releases, see [`crate::update`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UpdateConfig {
+    /// `stable` or `nightly`.
+    pub channel: String,
+    /// GitHub `owner/name` publishing the releases.
+    pub repo: String,
+    /// Base64 ed25519 key release binaries are signed with. `self-update`
+    /// refuses to install anything without it, or without a valid `.sig`
+    /// asset.
+    pub public_key: String,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            channel: "stable".into(),
+            repo: "max-de-bug/CrabbyBot".into(),
+            public_key: String::new(),
+        }
+    }
+}
+
+/// Encrypted sync of workspace files with remote storage (`sync` feature),
+/// see [`crate::sync`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SyncConfig {
+    pub enabled: bool,
+    /// `s3` (any S3-compatible store) or `webdav`.
+    pub backend: String,
+    /// S3 endpoint (e.g. `https://s3.eu-central-1.amazonaws.com`) or the
+    /// WebDAV folder URL.
+    pub url: String,
+    /// Folder inside the bucket or WebDAV URL.
+    pub prefix: String,
+    pub bucket: String,
+    /// S3 signing region; empty means `us-east-1`.
+    pub region: String,
+    pub access_key_id: String,
+    /// S3 secret access key.
+    pub secret: String,
+    /// WebDAV user name; empty sends no credentials.
+    pub username: String,
+    pub password: String,
+    /// Everything is encrypted under a key derived from this before it is
+    /// uploaded. Use the same password on every machine.
+    pub encryption_password: String,
+    /// Workspace files and folders to sync.
+    pub paths: Vec<String>,
+    pub interval_minutes: u64,
+    /// Shown as the author of uploaded changes; empty uses the host name.
+    pub device_name: String,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: "s3".into(),
+            url: String::new(),
+            prefix: "crabbybot".into(),
+            bucket: String::new(),
+            region: String::new(),
+            access_key_id: String::new(),
+            secret: String::new(),
+            username: String::new(),
+            password: String::new(),
+            encryption_password: String::new(),
+            paths: ["memory", "skills", "journal", "prediction_graph.json"]
+                .map(String::from)
+                .to_vec(),
+            interval_minutes: 15,
+            device_name: String::new(),
+        }
+    }
+}
+
+/// Opt-in daily report of noised usage counts, see [`crate::telemetry`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TelemetryConfig {
+    /// Nothing is sent unless this is set.
+    pub enabled: bool,
+    /// URL the report is POSTed to.
+    pub endpoint: String,
+    /// Sent as a bearer token when set.
+    pub token: String,
+    /// Privacy budget of each count: smaller adds more noise.
+    pub epsilon: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            token: String::new(),
+            epsilon: 1.0,
+        }
+    }
+}
+
+// ── Consolidation Configuration ─────────────────────────────────────
+
+/// Nightly memory consolidation, see [`crate::consolidate`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConsolidationConfig {
+    pub enabled: bool,
+    /// Time of day (`HH:MM`) to run at, in `agents.defaults.timezone` or
+    /// the server's local time.
+    pub time: String,
+    /// Model for the summaries; empty uses `agents.defaults.summaryModel`,
+    /// then the default model.
+    pub model: String,
+    /// Workspace folders of generated files, such as charts, cleared out
+    /// on each run.
+    pub scratch_dirs: Vec<String>,
+    /// Files in `scratch_dirs` older than this many days are deleted; 0
+    /// keeps them.
+    pub scratch_days: u64,
+}
+
+impl Default for ConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: "03:00".into(),
+            model: String::new(),
+            scratch_dirs: vec!["charts".into(), "qr".into()],
+            scratch_days: 7,
         }
     }
 }
@@ -476,6 +1694,58 @@ mod tests {
         assert_eq!(entry.api_key, "test-key");
     }
 
+    #[test]
+    fn test_tool_enable_disable_lists() {
+        let json = r#"{"tools": {"disabled": ["exec"]}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(!config.tools.is_tool_enabled("exec"));
+        assert!(config.tools.is_tool_enabled("read_file"));
+
+        let json = r#"{"tools": {"enabled": ["read_file", "exec"], "disabled": ["exec"]}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.tools.is_tool_enabled("read_file"));
+        assert!(!config.tools.is_tool_enabled("exec"));
+        assert!(!config.tools.is_tool_enabled("web_fetch"));
+    }
+
+    #[test]
+    fn test_named_wallets_include_legacy_keys() {
+        let json = r#"{"tools": {
+            "solanaPrivateKey": "legacy-sol",
+            "polymarket": {"privateKey": "legacy-poly"},
+            "wallets": {
+                "main": {"address": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"},
+                "polymarket": {"chain": "polygon", "privateKey": "0xabc", "maxTradeUsd": 25}
+            }
+        }}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let wallets = config.tools.named_wallets();
+        assert_eq!(wallets.keys().collect::<Vec<_>>(), ["main", "polymarket", "solana"]);
+        assert_eq!(wallets["main"].chain, WalletChain::Solana);
+        assert_eq!(wallets["solana"].private_key.as_deref(), Some("legacy-sol"));
+        // An explicit wallet wins over the legacy key of the same name.
+        assert_eq!(wallets["polymarket"].private_key.as_deref(), Some("0xabc"));
+        assert_eq!(wallets["polymarket"].max_trade_usd, 25.0);
+    }
+
+    #[test]
+    fn test_load_from_reports_missing_and_invalid() {
+        let dir = std::env::temp_dir().join("crabbybot_config_errors");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = dir.join("missing.json");
+        let _ = std::fs::remove_file(&missing);
+        assert!(matches!(Config::load_from(&missing), Err(ConfigError::NotFound(_))));
+
+        let invalid = dir.join("invalid.json");
+        std::fs::write(&invalid, "{ not json").unwrap();
+        assert!(matches!(Config::load_from(&invalid), Err(ConfigError::Invalid { .. })));
+
+        let guardrail = dir.join("guardrail.json");
+        std::fs::write(&guardrail, r#"{"guardrails": {"rules": [{"tool": "shell_exec", "hours": "late"}]}}"#).unwrap();
+        assert!(matches!(Config::load_from(&guardrail), Err(ConfigError::Guardrail { .. })));
+    }
+
     #[test]
     fn test_find_active_provider() {
         let json = r#"{"providers": {"anthropic": {"apiKey": "sk-ant-xxx"}}}"#;
@@ -493,6 +1763,35 @@ mod tests {
         assert!(errors.iter().any(|e| e.contains("real API key")));
     }
 
+    #[test]
+    fn test_ollama_needs_no_key() {
+        let json = r#"{"providers": {"ollama": {"model": "llama3.2", "keepAlive": "1h"}}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let (name, entry) = config.providers.find_active().unwrap();
+        assert_eq!(name, "ollama");
+        assert_eq!(entry.keep_alive.as_deref(), Some("1h"));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_session_scopes() {
+        let json = r#"{"channels": {
+            "telegram": {"sessionScope": "user"},
+            "discord": {"sessionScope": "shared"},
+            "webchat": {}
+        }}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let scopes = config.channels.session_scopes();
+        assert_eq!(scopes["telegram"], SessionScope::User);
+        assert_eq!(scopes["discord"], SessionScope::Shared);
+        assert_eq!(scopes["webchat"], SessionScope::Chat);
+
+        assert_eq!(SessionScope::Chat.session_key("telegram", "-100", "7"), "telegram:-100");
+        assert_eq!(SessionScope::User.session_key("telegram", "-100", "7"), "telegram:-100:7");
+        assert_eq!(SessionScope::User.session_key("telegram", "-100", ""), "telegram:-100");
+        assert_eq!(SessionScope::Shared.session_key("discord", "5", "7"), "discord");
+    }
+
     #[test]
     fn test_validate_passes_with_real_key() {
         let json = r#"{"providers": {"openai": {"apiKey": "sk-abc123def456"}}}"#;
@@ -509,9 +1808,129 @@ mod tests {
             api_key: "sk-real-key-123".into(),
             api_base: None,
             model: None,
-            extra_headers: Default::default(),
+            ..Default::default()
         });
         let errors = config.validate().unwrap_err();
         assert!(errors.iter().any(|e| e.contains("model")));
     }
+
+    #[test]
+    fn test_validate_catches_unknown_timezone() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "agents": {
+                "defaults": {"timezone": "Europe/Berlin", "locale": "de-DE"},
+                "chats": {"telegram:42": {"timezone": "Mars/Olympus"}}
+            }
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.agents.defaults.locale.as_deref(), Some("de-DE"));
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("telegram:42") && errors[0].contains("Mars/Olympus"));
+    }
+
+    #[test]
+    fn test_validate_catches_bad_announcements() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "announcements": [
+                {"name": "standup", "schedule": "0 0 9 * * Mon-Fri", "message": "Standup in 5!"},
+                {"name": "empty", "schedule": "0 0 9 * * *"},
+                {"schedule": "daily", "message": "hi"},
+                {"enabled": false, "schedule": "daily"}
+            ]
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.announcements[0].channel, "telegram");
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("'empty'") && errors[0].contains("neither"));
+        assert!(errors[1].contains("#3") && errors[1].contains("daily"));
+    }
+
+    #[test]
+    fn test_validate_catches_bad_webhooks() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "gateway": {"webhooks": [
+                {"name": "github", "secret": "s3cret"},
+                {"name": "github", "secret": "other"},
+                {"name": "../x", "secret": "s3cret"},
+                {"name": "grafana"},
+                {"enabled": false}
+            ], "api": {"enabled": true}}
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].contains("'github' is defined twice"));
+        assert!(errors[1].contains("webhooks[2]"));
+        assert!(errors[2].contains("'grafana' has no secret"));
+        assert!(errors[3].contains("gateway.api"));
+    }
+
+    #[test]
+    fn test_validate_catches_bad_websocket_clients() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "channels": {"websocket": {"enabled": true, "clients": {
+                "webui": "t1", "kiosk": "t1", "bad.name": "t2", "empty": ""
+            }}}
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("'bad.name'")));
+        assert!(errors.iter().any(|e| e.contains("clients.empty has no token")));
+        assert!(errors.iter().any(|e| e.contains("shares its token")));
+    }
+
+    #[test]
+    fn test_validate_catches_bad_approval_rules() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "approvals": {
+                "rules": [
+                    {"tool": "polymarket_*", "minUsd": 500, "required": 2, "approvers": ["1", "2"]},
+                    {"tool": "transfer", "required": 3, "approvers": ["1", "2"]},
+                    {"required": 1}
+                ],
+                "escalateAfterMinutes": 15
+            }
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("(transfer): needs 3 approvals but lists only 2"));
+        assert!(errors[1].contains("rules[2] names no tool"));
+        assert!(errors[2].contains("approvals.escalateTo"));
+    }
+
+    #[test]
+    fn test_validate_catches_bad_pipelines() {
+        let json = r#"{
+            "providers": {"openai": {"apiKey": "sk-abc123def456"}},
+            "tools": {
+                "pipelines": {
+                    "empty": {},
+                    "report": {
+                        "steps": [
+                            {"tool": "web_fetch", "args": {"url": "https://example.com/coins.json"}},
+                            {"tool": "rugcheck", "forEach": "$[*].mint", "args": {"address": "$item"}},
+                            {"tool": "run_pipeline", "args": {"name": "report"}},
+                            {"args": {"address": "$item.mint"}}
+                        ]
+                    }
+                }
+            }
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].contains("tools.pipelines.empty has no steps"));
+        assert!(errors[1].contains("steps[2]: pipelines can't run other pipelines"));
+        assert!(errors[2].contains("steps[3] names no tool"));
+        assert!(errors[3].contains("steps[3]: '$item.mint' uses $item without forEach"));
+    }
 }
@@ -0,0 +1,613 @@
+//! Nightly memory consolidation: the assistant's sleep cycle.
+//!
+//! When `consolidation.enabled` is set, the bot wakes once a night at
+//! `consolidation.time` and goes over the day that just ended:
+//!
+//! - the user and assistant messages of every session from that day are
+//!   sent to the LLM in batches, and the summary of each conversation is
+//!   appended to the daily notes (`memory/YYYY-MM-DD.md`) of the workspace
+//!   the chat keeps its memory in;
+//! - action items found along the way are added to `memory/TODO.md` there,
+//!   skipping ones already listed;
+//! - files older than `consolidation.scratchDays` are deleted from the
+//!   `consolidation.scratchDirs` of every workspace (rendered charts and QR
+//!   codes by default).
+//!
+//! The covered day is the one that was under way twelve hours before the
+//! configured time, so `03:00` summarizes the previous day and `23:30` the
+//! current one. The last day done is kept in `consolidation.json`; a bot
+//! that was down at the configured time catches up on every day it missed
+//! when it comes back.
+//! The run counts as background work: it takes the heartbeat lane of the
+//! LLM scheduler and is skipped when the token budget is nearly spent.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::agent::locale::parse_timezone;
+use crate::agent::memory::MemoryStore;
+use crate::clock::{self, SharedClock};
+use crate::config::{Config, ConsolidationConfig};
+use crate::provider::types::ChatMessage;
+use crate::provider::ProviderError;
+use crate::runtime::SharedProvider;
+use crate::scheduler::{Lane, LlmScheduler};
+use crate::session::{self, SessionManager};
+use crate::usage::UsageTracker;
+
+/// How often the bot checks whether a night's run is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Characters kept of a single message.
+const MAX_MESSAGE_CHARS: usize = 600;
+
+/// Characters kept of one conversation's day, the latest ones.
+const MAX_TRANSCRIPT_CHARS: usize = 8_000;
+
+/// Characters of transcripts sent in one LLM call.
+const BATCH_CHARS: usize = 24_000;
+
+const INSTRUCTIONS: &str = "You file away a day of an assistant's conversations. \
+For each conversation below, write up to five short bullet points worth remembering later: \
+decisions, facts about the user, results and open questions. Skip small talk and greetings. \
+Also list the concrete action items the user or the assistant committed to or asked for. \
+Reply with JSON only, in this shape: \
+{\"conversations\": [{\"key\": \"<the key as given>\", \"summary\": [\"...\"], \"actions\": [\"...\"]}]}";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConsolidationError {
+    #[error("LLM call failed: {0}")]
+    Provider(#[from] ProviderError),
+
+    #[error("the summary is not the JSON asked for: {0}")]
+    Reply(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What one run did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsolidationReport {
+    pub day: NaiveDate,
+    /// Sessions with messages on `day`.
+    pub conversations: usize,
+    /// Conversations a summary was written for.
+    pub summarized: usize,
+    /// Action items added to the to-do lists.
+    pub actions: usize,
+    /// Scratch files deleted.
+    pub pruned: usize,
+}
+
+/// `consolidation.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct State {
+    last_day: Option<NaiveDate>,
+    /// Workspaces whose daily notes already got the summary of a day that
+    /// isn't done yet, so a retried run doesn't append it twice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    noted: Option<Noted>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Noted {
+    day: NaiveDate,
+    workspaces: BTreeSet<PathBuf>,
+}
+
+/// One conversation of the day, as sent to the LLM.
+struct Transcript {
+    key: String,
+    text: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Digest {
+    conversations: Vec<Entry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Entry {
+    key: String,
+    summary: Vec<String>,
+    actions: Vec<String>,
+}
+
+/// Runs the nightly consolidation of a workspace and its chat workspaces.
+pub struct Consolidator {
+    provider: SharedProvider,
+    model: Option<String>,
+    max_tokens: u32,
+    temperature: f32,
+    workspace: PathBuf,
+    chat_workspaces: BTreeMap<String, PathBuf>,
+    settings: ConsolidationConfig,
+    /// `None` is the server's local time.
+    timezone: Option<Tz>,
+    usage: Option<Arc<UsageTracker>>,
+    scheduler: Option<Arc<LlmScheduler>>,
+    clock: SharedClock,
+}
+
+impl Consolidator {
+    pub fn from_config(config: &Config, provider: SharedProvider) -> Self {
+        let defaults = &config.agents.defaults;
+        let model = Some(config.consolidation.model.clone())
+            .filter(|m| !m.is_empty())
+            .or_else(|| defaults.summary_model.clone());
+        let timezone = defaults.timezone.as_deref().and_then(|tz| {
+            parse_timezone(tz)
+                .inspect_err(|e| warn!("Consolidation runs on the server's local time: {}", e))
+                .ok()
+        });
+        Self {
+            provider,
+            model,
+            max_tokens: defaults.max_tokens,
+            temperature: defaults.temperature,
+            workspace: config.workspace_path(),
+            chat_workspaces: config.chat_workspaces(),
+            settings: config.consolidation.clone(),
+            timezone,
+            usage: None,
+            scheduler: None,
+            clock: clock::system(),
+        }
+    }
+
+    /// Record the tokens spent and skip runs once the budget is nearly
+    /// spent.
+    pub fn with_usage(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Take each LLM call's turn from `scheduler`, in the heartbeat lane.
+    pub fn with_scheduler(mut self, scheduler: Arc<LlmScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).naive_local(),
+            None => at.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.workspace.join("consolidation.json")
+    }
+
+    fn state(&self) -> State {
+        std::fs::read_to_string(self.state_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &State) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.workspace)?;
+        std::fs::write(self.state_path(), serde_json::to_string_pretty(state).unwrap_or_default())
+    }
+
+    /// The last day consolidated, if any.
+    pub fn last_day(&self) -> Option<NaiveDate> {
+        self.state().last_day
+    }
+
+    /// The days to consolidate at `now`, oldest first: every day after the
+    /// last one done, or just the latest day on the first run.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<NaiveDate> {
+        let Ok(time) = NaiveTime::parse_from_str(&self.settings.time, "%H:%M") else {
+            return Vec::new();
+        };
+        let now = self.local(now);
+        let mut slot = now.date().and_time(time);
+        if slot > now {
+            slot -= chrono::Duration::days(1);
+        }
+        let latest = (slot - chrono::Duration::hours(12)).date();
+        let first = self.last_day().and_then(|last| last.succ_opt()).unwrap_or(latest);
+        first.iter_days().take_while(|day| *day <= latest).collect()
+    }
+
+    /// The workspace the chat `channel:chat_id` keeps its memory in.
+    fn workspace_of(&self, key: &str) -> &Path {
+        let (channel, chat_id) = session::chat_of(key);
+        self.chat_workspaces
+            .get(&format!("{}:{}", channel, chat_id))
+            .unwrap_or(&self.workspace)
+    }
+
+    /// The user and assistant messages of `day`, one transcript per session.
+    fn transcripts(&self, day: NaiveDate) -> Vec<Transcript> {
+        let mut sessions = SessionManager::new(&self.workspace).with_chat_workspaces(self.chat_workspaces.clone());
+        let mut keys: Vec<String> = sessions.list_sessions().into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        let mut out = Vec::new();
+        for key in keys {
+            let lines: Vec<String> = sessions
+                .get_or_create(&key)
+                .messages
+                .iter()
+                .filter(|m| m.role == "user" || m.role == "assistant")
+                .filter_map(|m| {
+                    let at = self.local(m.time()?);
+                    let content = m.content.as_deref().map(str::trim).filter(|c| !c.is_empty())?;
+                    (at.date() == day).then(|| format!("[{}] {}: {}", at.format("%H:%M"), m.role, clip(content)))
+                })
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+            // Keep the end of a long day; it holds where things were left.
+            let mut text = String::new();
+            for line in lines.iter().rev() {
+                if !text.is_empty() && text.len() + line.len() > MAX_TRANSCRIPT_CHARS {
+                    break;
+                }
+                text.insert_str(0, &format!("{}\n", line));
+            }
+            out.push(Transcript { key, text });
+        }
+        out
+    }
+
+    /// Summaries and action items of `batch`, keyed by session.
+    async fn digest(&self, batch: &[Transcript]) -> Result<Vec<Entry>, ConsolidationError> {
+        let text: Vec<String> = batch.iter().map(|t| format!("### {}\n{}", t.key, t.text)).collect();
+        let messages = [ChatMessage::system(INSTRUCTIONS), ChatMessage::user(&text.join("\n"))];
+        if let Some(ref scheduler) = self.scheduler {
+            scheduler.acquire(Lane::Heartbeat).await;
+        }
+        let response = self
+            .provider
+            .lock()
+            .await
+            .chat(&messages, &[], self.model.as_deref(), self.max_tokens, self.temperature)
+            .await?;
+        if let Some(ref usage) = self.usage {
+            usage.record(&response.usage);
+        }
+        let reply = response.content.unwrap_or_default();
+        let json = match (reply.find('{'), reply.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &reply[start..=end],
+            _ => return Err(ConsolidationError::Reply(clip(&reply))),
+        };
+        let digest: Digest = serde_json::from_str(json).map_err(|e| ConsolidationError::Reply(e.to_string()))?;
+        let keys: BTreeSet<&str> = batch.iter().map(|t| t.key.as_str()).collect();
+        Ok(digest
+            .conversations
+            .into_iter()
+            .filter(|e| keys.contains(e.key.as_str()))
+            .collect())
+    }
+
+    /// Consolidate `day` and remember that it was done.
+    ///
+    /// Nothing is written unless every batch was summarized, and the
+    /// workspaces whose notes were written are recorded as it goes, so a
+    /// failed run can simply be tried again.
+    pub async fn run(&self, day: NaiveDate) -> Result<ConsolidationReport, ConsolidationError> {
+        let transcripts = self.transcripts(day);
+        let mut report = ConsolidationReport {
+            day,
+            conversations: transcripts.len(),
+            ..Default::default()
+        };
+
+        let mut entries = Vec::new();
+        let mut batch: Vec<Transcript> = Vec::new();
+        let mut size = 0;
+        for transcript in transcripts {
+            if !batch.is_empty() && size + transcript.text.len() > BATCH_CHARS {
+                entries.extend(self.digest(&batch).await?);
+                batch.clear();
+                size = 0;
+            }
+            size += transcript.text.len();
+            batch.push(transcript);
+        }
+        if !batch.is_empty() {
+            entries.extend(self.digest(&batch).await?);
+        }
+
+        let mut notes: BTreeMap<&Path, String> = BTreeMap::new();
+        let mut todos: BTreeMap<&Path, Vec<String>> = BTreeMap::new();
+        for entry in &entries {
+            let workspace = self.workspace_of(&entry.key);
+            let summary: Vec<&str> = entry.summary.iter().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+            if !summary.is_empty() {
+                let note = notes.entry(workspace).or_default();
+                note.push_str(&format!("\n### {}\n", entry.key));
+                for line in summary {
+                    note.push_str(&format!("- {}\n", line));
+                }
+                report.summarized += 1;
+            }
+            let actions = entry.actions.iter().map(|a| a.trim()).filter(|a| !a.is_empty());
+            todos
+                .entry(workspace)
+                .or_default()
+                .extend(actions.map(|a| format!("{} ({}, {})", a, entry.key, day)));
+        }
+        let mut state = self.state();
+        let mut noted = state.noted.take().filter(|n| n.day == day).unwrap_or(Noted {
+            day,
+            workspaces: BTreeSet::new(),
+        });
+        for (workspace, note) in notes {
+            if noted.workspaces.contains(workspace) {
+                continue;
+            }
+            MemoryStore::new(workspace).append_on(day, &format!("## Nightly summary\n{}", note))?;
+            noted.workspaces.insert(workspace.to_path_buf());
+            state.noted = Some(noted.clone());
+            self.save_state(&state)?;
+        }
+        for (workspace, items) in todos {
+            report.actions += add_todos(workspace, &items)?;
+        }
+
+        report.pruned = self.prune();
+        self.save_state(&State {
+            last_day: Some(day),
+            noted: None,
+        })?;
+        Ok(report)
+    }
+
+    /// Delete scratch files past their age, returning how many went.
+    fn prune(&self) -> usize {
+        if self.settings.scratch_days == 0 {
+            return 0;
+        }
+        let max_age = Duration::from_secs(self.settings.scratch_days * 86_400);
+        let cutoff = SystemTime::from(self.clock.now()) - max_age;
+        let workspaces: BTreeSet<&PathBuf> = std::iter::once(&self.workspace).chain(self.chat_workspaces.values()).collect();
+        let mut pruned = 0;
+        for workspace in workspaces {
+            for dir in &self.settings.scratch_dirs {
+                pruned += prune_dir(&workspace.join(dir), cutoff);
+            }
+        }
+        pruned
+    }
+}
+
+/// Append the items of `items` not yet listed to `memory/TODO.md` in
+/// `workspace`, returning how many were added.
+fn add_todos(workspace: &Path, items: &[String]) -> std::io::Result<usize> {
+    let path = workspace.join("memory").join("TODO.md");
+    let mut text = std::fs::read_to_string(&path).unwrap_or_else(|_| "# To do\n\n".to_string());
+    let mut listed: BTreeSet<String> = text
+        .lines()
+        .filter_map(|l| l.strip_prefix("- [ ] ").or_else(|| l.strip_prefix("- [x] ")))
+        .map(|l| task_of(l).to_lowercase())
+        .collect();
+    let mut added = 0;
+    for item in items {
+        if !listed.insert(task_of(item).to_lowercase()) {
+            continue;
+        }
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("- [ ] {}\n", item));
+        added += 1;
+    }
+    if added > 0 {
+        std::fs::create_dir_all(path.parent().unwrap_or(workspace))?;
+        std::fs::write(&path, text)?;
+    }
+    Ok(added)
+}
+
+/// A to-do line without the `(session, day)` it was found in.
+fn task_of(line: &str) -> &str {
+    match line.rfind(" (") {
+        Some(at) if line.ends_with(')') => &line[..at],
+        _ => line,
+    }
+}
+
+/// Delete the files under `dir` last changed before `cutoff`.
+fn prune_dir(dir: &Path, cutoff: SystemTime) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut pruned = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            pruned += prune_dir(&path, cutoff);
+        } else if meta.modified().is_ok_and(|m| m < cutoff) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => pruned += 1,
+                Err(e) => debug!(path = %path.display(), "Could not delete scratch file: {}", e),
+            }
+        }
+    }
+    pruned
+}
+
+/// Shorten `text` to [`MAX_MESSAGE_CHARS`], on one line.
+fn clip(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(MAX_MESSAGE_CHARS) {
+        Some((at, _)) => format!("{}…", &line[..at]),
+        None => line,
+    }
+}
+
+/// Consolidate each night once it is due, days missed included, trying
+/// again at the next check when a run fails.
+pub async fn run_periodic(consolidator: Consolidator, cancel: CancellationToken) {
+    loop {
+        for day in consolidator.due(consolidator.clock.now()) {
+            let admitted = match consolidator.usage {
+                Some(ref usage) => usage.admit_background("nightly memory consolidation").await,
+                None => true,
+            };
+            if !admitted {
+                break;
+            }
+            match consolidator.run(day).await {
+                Ok(report) => info!(?report, "Memory consolidated"),
+                Err(e) => {
+                    warn!(%day, "Memory consolidation failed: {}", e);
+                    break;
+                }
+            }
+        }
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = consolidator.clock.sleep(CHECK_INTERVAL) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChatConfig;
+    use crate::provider::types::{LlmResponse, ToolDefinition, Usage};
+    use crate::provider::LlmProvider;
+    use crate::session::SessionMessage;
+    use async_trait::async_trait;
+    use tokio::sync::Mutex;
+
+    /// Replies with a fixed digest and counts the calls.
+    struct Digester(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl LlmProvider for Digester {
+        fn default_model(&self) -> &str {
+            "model"
+        }
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: &[ToolDefinition],
+            _model: Option<&str>,
+            _max_tokens: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            let user = messages.last().and_then(|m| m.content_as_str()).unwrap_or_default().to_string();
+            self.0.lock().unwrap().push(user);
+            let reply = r#"Sure! {"conversations": [
+                {"key": "telegram:7", "summary": ["Wants alerts for SOL under $100"], "actions": ["Set a SOL alert", "Send the weekly report"]},
+                {"key": "telegram:999", "summary": ["Not a conversation of the day"]}
+            ]}"#;
+            Ok(LlmResponse {
+                content: Some(reply.into()),
+                tool_calls: Vec::new(),
+                finish_reason: "stop".into(),
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    fn message(role: &str, content: &str, timestamp: &str) -> SessionMessage {
+        SessionMessage {
+            role: role.into(),
+            content: Some(content.into()),
+            timestamp: timestamp.into(),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_day_is_summarized_into_chat_memory() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_consolidate_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let chat = tmp.join("chat");
+        let mut config = Config::default();
+        config.agents.defaults.workspace = tmp.to_string_lossy().into_owned();
+        config.agents.defaults.timezone = Some("Etc/UTC".into());
+        let chat_config = ChatConfig {
+            workspace: Some(chat.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        config.agents.chats.insert("telegram:7".into(), chat_config);
+
+        let mut sessions = SessionManager::new(&tmp).with_chat_workspaces(config.chat_workspaces());
+        sessions.get_or_create("telegram:7").messages.extend([
+            message("user", "Remind me when SOL drops under $100", "2026-03-13T22:00:00+00:00"),
+            message("user", "Alert me if SOL goes under $100", "2026-03-14T09:00:00+00:00"),
+            message("tool", "price: 142", "2026-03-14T09:00:01+00:00"),
+            message("assistant", "Will do.", "2026-03-14T09:00:02+00:00"),
+        ]);
+        sessions.save("telegram:7").unwrap();
+
+        std::fs::create_dir_all(tmp.join("charts")).unwrap();
+        std::fs::write(tmp.join("charts/old.png"), b"png").unwrap();
+        // A to-do list that can't be written fails the run after the notes.
+        std::fs::create_dir_all(chat.join("memory/TODO.md")).unwrap();
+
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let provider: Box<dyn LlmProvider> = Box::new(Digester(Arc::clone(&calls)));
+        // A week and a day after the chart was drawn.
+        let now = Utc::now() + chrono::Duration::days(8);
+        let clock = Arc::new(clock::MockClock::new(now));
+        let consolidator = Consolidator::from_config(&config, Arc::new(Mutex::new(provider))).with_clock(clock);
+
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        assert_eq!(consolidator.due(at("2026-03-15T02:59:00+00:00")), [day - chrono::Duration::days(1)]);
+        assert_eq!(consolidator.due(at("2026-03-15T03:00:00+00:00")), [day]);
+
+        assert!(consolidator.run(day).await.is_err());
+        assert_eq!(consolidator.last_day(), None);
+        std::fs::remove_dir(chat.join("memory/TODO.md")).unwrap();
+        std::fs::write(chat.join("memory/TODO.md"), "# To do\n\n- [x] Set a SOL alert (telegram:7, 2026-03-10)\n").unwrap();
+
+        let report = consolidator.run(day).await.unwrap();
+        assert_eq!(report.summarized, 1);
+        assert_eq!(report.actions, 1);
+        assert!(report.pruned >= 1);
+        assert!(!tmp.join("charts/old.png").exists());
+
+        let sent = calls.lock().unwrap().join("\n");
+        assert!(sent.contains("### telegram:7\n[09:00] user: Alert me if SOL goes under $100\n[09:00] assistant: Will do."), "{}", sent);
+        assert!(!sent.contains("Remind me"), "another day: {}", sent);
+        assert!(!sent.contains("price: 142"), "tool output: {}", sent);
+
+        let notes = std::fs::read_to_string(chat.join("memory/2026-03-14.md")).unwrap();
+        assert!(notes.contains("## Nightly summary\n\n### telegram:7\n- Wants alerts for SOL under $100"), "{}", notes);
+        assert_eq!(notes.matches("## Nightly summary").count(), 1, "retried: {}", notes);
+        assert!(!notes.contains("Not a conversation"), "{}", notes);
+        let todo = std::fs::read_to_string(chat.join("memory/TODO.md")).unwrap();
+        assert!(todo.contains("- [ ] Send the weekly report (telegram:7, 2026-03-14)"), "{}", todo);
+        assert_eq!(todo.matches("Set a SOL alert").count(), 1, "{}", todo);
+
+        assert_eq!(consolidator.last_day(), Some(day));
+        assert!(consolidator.due(at("2026-03-15T12:00:00+00:00")).is_empty());
+        assert_eq!(consolidator.due(at("2026-03-16T03:00:00+00:00")), [day + chrono::Duration::days(1)]);
+        let missed: Vec<NaiveDate> = (1..=3).map(|n| day + chrono::Duration::days(n)).collect();
+        assert_eq!(consolidator.due(at("2026-03-18T03:00:00+00:00")), missed);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
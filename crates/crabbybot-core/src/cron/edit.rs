@@ -0,0 +1,241 @@
+//! A job as TOML, for `crabbybot cron edit`.
+//!
+//! [`to_toml`] writes the fields a user may change; [`from_toml`] reads the
+//! edited text back on top of the original job, so `id`, `created_at` and
+//! the run bookkeeping stay as they were. TOML has no null: unset optional
+//! fields are left out, and null tool arguments are dropped.
+
+use serde_json::{Map, Value};
+use toml_edit::{DocumentMut, Item};
+
+use super::{CronError, CronJob};
+
+/// Top-level keys [`from_toml`] accepts.
+const EDITABLE: &[&str] = &["name", "message", "enabled", "critical", "channel", "chat_id", "schedule", "kind"];
+
+/// `job` as a TOML document, headed by a comment naming it.
+pub fn to_toml(job: &CronJob) -> Result<String, CronError> {
+    let Value::Object(fields) = serde_json::to_value(job)? else {
+        return Err(CronError::InvalidJob("not an object".into()));
+    };
+
+    let mut out = format!(
+        "# Cron job {} — save and close the editor to apply.\n# Delete everything to cancel.\n\n",
+        job.id
+    );
+    let mut tables = Vec::new();
+    for key in EDITABLE {
+        match fields.get(*key) {
+            Some(Value::Object(table)) => tables.push((key.to_string(), table)),
+            Some(value) => write_pair(&mut out, key, value),
+            None => {}
+        }
+    }
+    tables.reverse();
+    while let Some((name, table)) = tables.pop() {
+        out.push_str(&format!("\n[{}]\n", name));
+        let mut nested = Vec::new();
+        // The tag says what the other keys mean, so it goes first.
+        let tag = table.get_key_value("type");
+        for (key, value) in tag.into_iter().chain(table.iter().filter(|(k, _)| *k != "type")) {
+            match value {
+                // Tool arguments read better as a section of their own.
+                Value::Object(inner) if key == "args" => nested.push((format!("{}.{}", name, key), inner)),
+                value => write_pair(&mut out, key, value),
+            }
+        }
+        // Sections must follow the keys of the table they are nested in.
+        for (name, table) in nested.into_iter().rev() {
+            if !table.is_empty() {
+                tables.push((name, table));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// `original` with the fields from an edited [`to_toml`] document.
+pub fn from_toml(text: &str, original: &CronJob) -> Result<CronJob, CronError> {
+    let doc: DocumentMut = text.parse().map_err(|e: toml_edit::TomlError| CronError::InvalidJob(e.to_string()))?;
+
+    let Value::Object(mut fields) = serde_json::to_value(original)? else {
+        return Err(CronError::InvalidJob("not an object".into()));
+    };
+    for key in EDITABLE {
+        fields.remove(*key);
+    }
+    for (key, item) in doc.iter() {
+        if !EDITABLE.contains(&key) {
+            return Err(CronError::InvalidJob(format!("'{}' can't be edited", key)));
+        }
+        fields.insert(key.to_string(), item_to_json(item)?);
+    }
+
+    let job: CronJob = serde_json::from_value(Value::Object(fields))
+        .map_err(|e| CronError::InvalidJob(e.to_string()))?;
+    job.schedule.validate()?;
+    Ok(job)
+}
+
+fn write_pair(out: &mut String, key: &str, value: &Value) {
+    if !value.is_null() {
+        out.push_str(&format!("{} = {}\n", toml_key(key), toml_value(value)));
+    }
+}
+
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+fn toml_value(value: &Value) -> String {
+    match value {
+        Value::String(s) if is_multiline_literal(s) => format!("'''\n{}'''", s),
+        Value::String(s) => toml_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().filter(|v| !v.is_null()).map(toml_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(table) => {
+            let pairs: Vec<String> = table
+                .iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| format!("{} = {}", toml_key(k), toml_value(v)))
+                .collect();
+            format!("{{ {} }}", pairs.join(", "))
+        }
+        // Numbers, booleans; nulls are filtered out above.
+        other => other.to_string(),
+    }
+}
+
+/// A basic string. JSON's escapes are TOML's too, except that TOML also
+/// wants DEL escaped.
+fn toml_string(s: &str) -> String {
+    Value::String(s.to_string()).to_string().replace('\u{7f}', "\\u007F")
+}
+
+/// Whether `s` spans lines and fits a `'''` string as is.
+fn is_multiline_literal(s: &str) -> bool {
+    s.contains('\n') && !s.contains("'''") && s.chars().all(|c| c == '\n' || c == '\t' || !c.is_control())
+}
+
+fn item_to_json(item: &Item) -> Result<Value, CronError> {
+    match item {
+        Item::None => Ok(Value::Null),
+        Item::Value(value) => value_to_json(value),
+        Item::Table(table) => table
+            .iter()
+            .map(|(k, v)| Ok((k.to_string(), item_to_json(v)?)))
+            .collect::<Result<Map<_, _>, _>>()
+            .map(Value::Object),
+        Item::ArrayOfTables(tables) => tables
+            .iter()
+            .map(|t| item_to_json(&Item::Table(t.clone())))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+    }
+}
+
+fn value_to_json(value: &toml_edit::Value) -> Result<Value, CronError> {
+    use toml_edit::Value as Toml;
+    Ok(match value {
+        Toml::String(s) => Value::String(s.value().clone()),
+        Toml::Integer(i) => Value::from(*i.value()),
+        Toml::Float(f) => serde_json::Number::from_f64(*f.value())
+            .map(Value::Number)
+            .ok_or_else(|| CronError::InvalidJob(format!("{} is not a number JSON can hold", f.value())))?,
+        Toml::Boolean(b) => Value::Bool(*b.value()),
+        Toml::Datetime(d) => Value::String(d.value().to_string()),
+        Toml::Array(items) => Value::Array(items.iter().map(value_to_json).collect::<Result<_, _>>()?),
+        Toml::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| Ok((k.to_string(), value_to_json(v)?)))
+                .collect::<Result<Map<_, _>, CronError>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::{JobKind, Schedule, ToolCall};
+
+    fn tool_job() -> CronJob {
+        CronJob {
+            id: "job_1".into(),
+            name: "SOL \"price\"".into(),
+            schedule: Schedule::Cron {
+                expression: "0 0 9 * * *".into(),
+                timezone: Some("Europe/Berlin".into()),
+            },
+            message: "[tool] get_price".into(),
+            enabled: true,
+            created_at: "2026-01-01T00:00:00+00:00".into(),
+            last_run: Some("2026-01-02T09:00:00+00:00".into()),
+            next_run_ms: Some(1_767_430_800_000),
+            channel: "telegram".into(),
+            chat_id: "42".into(),
+            kind: JobKind::ToolCall(ToolCall {
+                name: "get_price".into(),
+                args: [
+                    ("symbol".to_string(), serde_json::json!("SOL")),
+                    ("window".to_string(), serde_json::json!({"days": 7, "bars": [1, 2.5]})),
+                    ("skip".to_string(), Value::Null),
+                ]
+                .into_iter()
+                .collect(),
+                format_template: Some("⏰ *{job}*\n\n{result}".into()),
+                template: None,
+                cooldown_secs: Some(600),
+                owner: "7".into(),
+            }),
+            critical: false,
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_and_edit() {
+        let job = tool_job();
+        let text = to_toml(&job).unwrap();
+        assert!(text.contains("# Cron job job_1"));
+        assert!(text.contains("[kind.args]"));
+        assert!(text.contains("'''\n⏰ *{job}*\n\n{result}'''"), "{}", text);
+
+        let same = from_toml(&text, &job).unwrap();
+        let JobKind::ToolCall(call) = &same.kind else {
+            panic!("not a tool job");
+        };
+        assert_eq!(same.name, job.name);
+        assert_eq!(call.args.get("window"), Some(&serde_json::json!({"days": 7, "bars": [1, 2.5]})));
+        assert!(!call.args.contains_key("skip"));
+        assert_eq!(same.last_run, job.last_run);
+
+        let edited = text
+            .replace("enabled = true", "enabled = false")
+            .replace("[schedule]\n", "[schedule]\ntype = \"interval\"\nseconds = 300\n# ")
+            .replace("expression =", "# expression =")
+            .replace("timezone =", "# timezone =");
+        let edited = from_toml(&edited, &job).unwrap();
+        assert!(!edited.enabled);
+        assert!(matches!(edited.schedule, Schedule::Interval { seconds: 300 }));
+        assert_eq!(edited.id, "job_1");
+    }
+
+    #[test]
+    fn test_rejects_bad_edits() {
+        let job = tool_job();
+        let text = to_toml(&job).unwrap();
+        let err = |text: &str| from_toml(text, &job).unwrap_err().to_string();
+
+        assert!(err(&format!("id = \"job_2\"\n{}", text)).contains("'id' can't be edited"));
+        assert!(err(&text.replace("0 0 9 * * *", "every morning")).contains("Invalid cron expression"));
+        assert!(err(&text.replace("name = ", "nme = ")).contains("'nme'"));
+        assert!(err("name = ").contains("Invalid job"));
+    }
+}
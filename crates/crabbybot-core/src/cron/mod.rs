@@ -2,24 +2,132 @@
 //!
 //! Supports both cron expressions (`0 9 * * *`) and interval-based
 //! scheduling (every N seconds).
+//!
+//! Jobs are stored as a snapshot (`cron.json`) plus an append-only change
+//! log (`cron.log`, one [`CronEvent`] per line), so several processes can
+//! share a workspace: `crabbybot cron add` while the bot runs appends its
+//! change instead of rewriting the file, and the bot picks it up on its next
+//! tick. Writers hold an advisory lock on `cron.lock`; the log is folded
+//! into the snapshot once it grows past [`COMPACT_AFTER`] entries.
 
-use chrono::Local;
+use chrono::{DateTime, FixedOffset, Local, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::agent::locale::parse_timezone;
+use crate::clock::{self, SharedClock};
+use crate::config::AnnouncementConfig;
+use crate::recovery;
+use crate::templates::{self, TemplateRegistry};
+
+pub mod edit;
+
+/// Errors from managing scheduled jobs.
+#[derive(Debug, thiserror::Error)]
+pub enum CronError {
+    #[error("Invalid cron expression '{expression}': {reason}")]
+    InvalidExpression { expression: String, reason: String },
+
+    #[error("Invalid timezone: {0}")]
+    InvalidTimezone(String),
+
+    #[error("Cron store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Cron store serialization error: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Invalid job: {0}")]
+    InvalidJob(String),
+}
 
 /// How a job is scheduled.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Schedule {
-    /// Cron expression (e.g., "0 9 * * *").
+    /// Cron expression (e.g., "0 9 * * *"), evaluated in `timezone` (an
+    /// IANA name) or the server's local time when unset.
     #[serde(rename = "cron")]
-    Cron { expression: String },
+    Cron {
+        expression: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timezone: Option<String>,
+    },
     /// Run every N seconds.
     #[serde(rename = "interval")]
     Interval { seconds: u64 },
 }
 
+impl Schedule {
+    /// Check the cron expression and timezone.
+    pub fn validate(&self) -> Result<(), CronError> {
+        if let Schedule::Cron { expression, timezone } = self {
+            use std::str::FromStr;
+            cron::Schedule::from_str(expression).map_err(|e| CronError::InvalidExpression {
+                expression: expression.clone(),
+                reason: e.to_string(),
+            })?;
+            if let Some(tz) = timezone {
+                parse_timezone(tz).map_err(CronError::InvalidTimezone)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `now` as wall-clock time in the schedule's timezone.
+    fn local_time(&self, now: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Schedule::Cron { timezone: Some(tz), .. } => match parse_timezone(tz) {
+                Ok(tz) => now.with_timezone(&tz).fixed_offset(),
+                Err(_) => now.with_timezone(&Local).fixed_offset(),
+            },
+            _ => now.with_timezone(&Local).fixed_offset(),
+        }
+    }
+}
+
+/// What a job does when it fires.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Send `message` to the agent as a system prompt.
+    #[default]
+    Agent,
+    /// Run a tool directly, without the LLM, and post its output.
+    ToolCall(ToolCall),
+}
+
+/// A tool invocation run by a `tool_call` job.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: HashMap<String, serde_json::Value>,
+    /// Outbound message template. `{result}` is replaced by the tool
+    /// output, `{job}` by the job name and `{time}` by the local time.
+    #[serde(default)]
+    pub format_template: Option<String>,
+    /// Name of a workspace template (see [`crate::templates`]) to render
+    /// instead. It gets `job`, `time`, `result` and, when the tool output is
+    /// JSON, the parsed value as `data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Seconds before identical output is posted again, overriding
+    /// `alerts.cooldownSecs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    /// User who scheduled the job. The tool runs as them, so guardrails,
+    /// approvals and recipient checks apply as to their own calls; empty
+    /// for jobs added from the CLI.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub owner: String,
+}
+
 /// A scheduled job.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CronJob {
@@ -39,29 +147,264 @@ pub struct CronJob {
     /// Chat ID to route responses to.
     #[serde(default)]
     pub chat_id: String,
+    #[serde(default)]
+    pub kind: JobKind,
+    /// Run even when the daily token budget is nearly used up. Only agent
+    /// jobs are ever skipped; `tool_call` jobs spend no tokens.
+    #[serde(default)]
+    pub critical: bool,
+}
+
+impl CronJob {
+    /// Render the outbound message for a `tool_call` job from the tool
+    /// output, with `{time}` taken from `now`.
+    pub fn format_tool_output(&self, result: &str, now: DateTime<Utc>) -> String {
+        let template = match &self.kind {
+            JobKind::ToolCall(ToolCall {
+                format_template: Some(t),
+                ..
+            }) => t.as_str(),
+            _ => "⏰ *{job}*\n\n{result}",
+        };
+        template
+            .replace("{job}", &self.name)
+            .replace("{time}", &self.schedule.local_time(now).format("%Y-%m-%d %H:%M").to_string())
+            .replace("{result}", result)
+    }
+
+    /// Like [`format_tool_output`](Self::format_tool_output), but renders the
+    /// job's named template when it has one. A missing or broken template
+    /// falls back to the plain format.
+    pub fn render_tool_output(&self, result: &str, templates: &TemplateRegistry, now: DateTime<Utc>) -> String {
+        let name = match &self.kind {
+            JobKind::ToolCall(ToolCall {
+                template: Some(name),
+                ..
+            }) => name,
+            _ => return self.format_tool_output(result, now),
+        };
+        let context = serde_json::json!({
+            "job": self.name,
+            "time": self.schedule.local_time(now).format("%Y-%m-%d %H:%M").to_string(),
+            "result": result,
+            "data": serde_json::from_str::<serde_json::Value>(result).unwrap_or_default(),
+        });
+        templates
+            .render(name, &self.channel, &context)
+            .unwrap_or_else(|e| {
+                warn!(job = %self.name, "{}", e);
+                self.format_tool_output(result, now)
+            })
+    }
+}
+
+/// A ready-made agent job, added with `crabbybot cron template <name>`.
+#[derive(Debug, Clone, Copy)]
+pub struct JobTemplate {
+    pub name: &'static str,
+    pub summary: &'static str,
+    /// Cron expression with seconds.
+    pub schedule: &'static str,
+    pub message: &'static str,
+}
+
+/// Built-in job templates.
+pub const JOB_TEMPLATES: &[JobTemplate] = &[JobTemplate {
+    name: "journal-review",
+    summary: "Weekly review of trades against the decision journal",
+    schedule: "0 0 9 * * Mon",
+    message: "Weekly trading review. Call journal_decision with list_days=7 to read last week's \
+              decisions. For each executed one, check where the market stands now (current price, \
+              open positions, closed positions) and compare it with the expected outcome and stop \
+              condition: was the thesis right, was the stop hit, should the position be closed? \
+              Sum up hits, misses and realized/unrealized PnL, and end with one or two lessons for \
+              next week's trades.",
+}];
+
+/// The built-in template called `name`.
+pub fn job_template(name: &str) -> Option<&'static JobTemplate> {
+    JOB_TEMPLATES.iter().find(|t| t.name == name)
+}
+
+/// A fixed message from the `announcements` config section.
+///
+/// The cron ticker renders and posts it itself, so no tokens are spent.
+/// Like heartbeats, announcements are rebuilt from config on every start and
+/// never written to `cron.json`; the first one goes out at the next
+/// scheduled time, not at startup.
+#[derive(Debug, Clone)]
+pub struct Announcement {
+    pub name: String,
+    pub schedule: Schedule,
+    /// Template text, used when `template` is unset.
+    pub message: String,
+    /// Workspace template to render instead of `message`.
+    pub template: Option<String>,
+    pub channel: String,
+    pub chat_id: String,
+    /// Unset until the service first sees it.
+    next_run_ms: Option<i64>,
+}
+
+impl Announcement {
+    /// Build from config. An empty `chat_id` or `timezone` falls back to
+    /// the given defaults.
+    pub fn from_config(
+        config: &AnnouncementConfig,
+        default_chat_id: &str,
+        default_timezone: Option<&str>,
+    ) -> Result<Self, CronError> {
+        let schedule = Schedule::Cron {
+            expression: config.schedule.clone(),
+            timezone: config.timezone.clone().or_else(|| default_timezone.map(String::from)),
+        };
+        schedule.validate()?;
+        let chat_id = if config.chat_id.is_empty() { default_chat_id } else { &config.chat_id };
+        Ok(Self {
+            name: config.name.clone(),
+            schedule,
+            message: config.message.clone(),
+            template: config.template.clone(),
+            channel: config.channel.clone(),
+            chat_id: chat_id.to_string(),
+            next_run_ms: None,
+        })
+    }
+
+    /// The outbound text as of `now`. A template that fails to render is
+    /// logged and the raw message sent instead.
+    pub fn render(&self, registry: &TemplateRegistry, now: DateTime<Utc>) -> String {
+        let now = self.schedule.local_time(now);
+        let context = serde_json::json!({
+            "name": self.name,
+            "date": now.format("%Y-%m-%d").to_string(),
+            "time": now.format("%H:%M").to_string(),
+            "weekday": now.format("%A").to_string(),
+        });
+        let rendered = match &self.template {
+            Some(name) => registry.render(name, &self.channel, &context),
+            None => templates::render_str(&self.name, &self.message, &context),
+        };
+        rendered.unwrap_or_else(|e| {
+            warn!(announcement = %self.name, "{}", e);
+            self.message.clone()
+        })
+    }
 }
 
 fn default_channel() -> String {
     "cli".to_string()
 }
 
+/// Change log entries kept before they are folded into `cron.json`.
+pub const COMPACT_AFTER: usize = 100;
+
 /// Persistent store for cron jobs.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct CronStore {
     jobs: Vec<CronJob>,
 }
 
+/// One change to the job store, as written to `cron.log`.
+///
+/// Replaying an event twice leaves the same state, so a crash between
+/// writing the snapshot and truncating the log loses nothing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum CronEvent {
+    Add { job: Box<CronJob> },
+    Remove { id: String },
+    Enable { id: String, enabled: bool },
+    Critical { id: String, critical: bool },
+    /// The job fired; `last_run` and `next_run_ms` move on.
+    Ran { id: String, last_run: String, next_run_ms: i64 },
+}
+
+impl CronStore {
+    fn apply(&mut self, event: &CronEvent) {
+        match event {
+            // Adding a job that exists replaces it where it is.
+            CronEvent::Add { job } => match self.job_mut(&job.id) {
+                Some(existing) => *existing = job.as_ref().clone(),
+                None => self.jobs.push(job.as_ref().clone()),
+            },
+            CronEvent::Remove { id } => self.jobs.retain(|j| j.id != *id),
+            CronEvent::Enable { id, enabled } => {
+                if let Some(job) = self.job_mut(id) {
+                    job.enabled = *enabled;
+                }
+            }
+            CronEvent::Critical { id, critical } => {
+                if let Some(job) = self.job_mut(id) {
+                    job.critical = *critical;
+                }
+            }
+            CronEvent::Ran { id, last_run, next_run_ms } => {
+                if let Some(job) = self.job_mut(id) {
+                    job.last_run = Some(last_run.clone());
+                    job.next_run_ms = Some(*next_run_ms);
+                }
+            }
+        }
+    }
+
+    fn job_mut(&mut self, id: &str) -> Option<&mut CronJob> {
+        self.jobs.iter_mut().find(|j| j.id == id)
+    }
+}
+
 pub struct CronService {
     store_path: PathBuf,
+    log_path: PathBuf,
+    lock_path: PathBuf,
     store: CronStore,
+    /// Entries in `cron.log` since the last compaction.
+    log_entries: usize,
+    /// Snapshot mtime and log length when last read, to notice writes by
+    /// other processes.
+    seen: (Option<SystemTime>, u64),
+    announcements: Vec<Announcement>,
+    clock: SharedClock,
+    /// Where a corrupt `cron.json` was moved on load.
+    quarantined: Option<PathBuf>,
 }
 
 impl CronService {
+    /// Load the jobs in `workspace`. A corrupt `cron.json` is moved aside
+    /// (see [`crate::recovery`]) together with the change log, which only
+    /// makes sense on top of it, and the service starts empty.
     pub fn new(workspace: &Path) -> Self {
-        let store_path = workspace.join("cron.json");
-        let store = Self::load_store(&store_path);
+        let mut service = Self {
+            store_path: workspace.join("cron.json"),
+            log_path: workspace.join("cron.log"),
+            lock_path: workspace.join("cron.lock"),
+            store: CronStore::default(),
+            log_entries: 0,
+            seen: (None, 0),
+            announcements: Vec::new(),
+            clock: clock::system(),
+            quarantined: None,
+        };
+        match service.lock() {
+            Ok(_lock) => service.load(),
+            Err(e) => {
+                warn!("Could not lock the cron store, reading it unlocked: {}", e);
+                service.load();
+            }
+        }
+        service
+    }
 
-        Self { store_path, store }
+    /// Read the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Also fire these config-driven announcements.
+    pub fn with_announcements(mut self, announcements: Vec<Announcement>) -> Self {
+        self.announcements = announcements;
+        self
     }
 
     /// Add a new cron job.
@@ -72,15 +415,36 @@ impl CronService {
         message: &str,
         channel: &str,
         chat_id: &str,
-    ) -> anyhow::Result<String> {
+    ) -> Result<String, CronError> {
+        self.add_job_of_kind(name, schedule, message, channel, chat_id, JobKind::Agent)
+    }
+
+    /// Add a job that runs a tool directly and posts the formatted result.
+    pub fn add_tool_job(
+        &mut self,
+        name: &str,
+        schedule: Schedule,
+        call: ToolCall,
+        channel: &str,
+        chat_id: &str,
+    ) -> Result<String, CronError> {
+        let message = format!("[tool] {}", call.name);
+        let kind = JobKind::ToolCall(call);
+        self.add_job_of_kind(name, schedule, &message, channel, chat_id, kind)
+    }
+
+    fn add_job_of_kind(
+        &mut self,
+        name: &str,
+        schedule: Schedule,
+        message: &str,
+        channel: &str,
+        chat_id: &str,
+        kind: JobKind,
+    ) -> Result<String, CronError> {
         let id = format!("job_{}", uuid_simple());
 
-        // Validate cron expression if applicable
-        if let Schedule::Cron { ref expression } = schedule {
-            use std::str::FromStr;
-            cron::Schedule::from_str(expression)
-                .map_err(|e| anyhow::anyhow!("Invalid cron expression '{}': {}", expression, e))?;
-        }
+        schedule.validate()?;
 
         let job = CronJob {
             id: id.clone(),
@@ -88,43 +452,141 @@ impl CronService {
             schedule,
             message: message.to_string(),
             enabled: true,
-            created_at: Local::now().to_rfc3339(),
+            created_at: self.clock.now().with_timezone(&Local).to_rfc3339(),
             last_run: None,
             next_run_ms: None,
             channel: channel.to_string(),
             chat_id: chat_id.to_string(),
+            kind,
+            critical: false,
         };
 
+        self.record(&[CronEvent::Add { job: Box::new(job) }])?;
         info!(id = %id, name = name, channel = channel, "Added cron job");
-        self.store.jobs.push(job);
-        self.save_store()?;
 
         Ok(id)
     }
 
+    /// Add the job a built-in template describes.
+    pub fn add_from_template(
+        &mut self,
+        template: &JobTemplate,
+        timezone: Option<String>,
+        channel: &str,
+        chat_id: &str,
+    ) -> Result<String, CronError> {
+        let schedule = Schedule::Cron {
+            expression: template.schedule.to_string(),
+            timezone,
+        };
+        self.add_job(template.name, schedule, template.message, channel, chat_id)
+    }
+
     /// Remove a job by ID.
-    pub fn remove_job(&mut self, job_id: &str) -> anyhow::Result<bool> {
-        let before = self.store.jobs.len();
-        self.store.jobs.retain(|j| j.id != job_id);
-        let removed = self.store.jobs.len() < before;
+    pub fn remove_job(&mut self, job_id: &str) -> Result<bool, CronError> {
+        if !self.has_job(job_id) {
+            return Ok(false);
+        }
+        self.record(&[CronEvent::Remove { id: job_id.to_string() }])?;
+        info!(id = job_id, "Removed cron job");
+        Ok(true)
+    }
 
-        if removed {
-            self.save_store()?;
-            info!(id = job_id, "Removed cron job");
+    /// Enable or disable a job.
+    pub fn enable_job(&mut self, job_id: &str, enabled: bool) -> Result<bool, CronError> {
+        if !self.has_job(job_id) {
+            return Ok(false);
         }
+        self.record(&[CronEvent::Enable {
+            id: job_id.to_string(),
+            enabled,
+        }])?;
+        Ok(true)
+    }
 
-        Ok(removed)
+    /// Apply the user-editable fields of `job` (see [`edit`]) to the stored
+    /// job with `job.id`, as `crabbybot cron edit` does. Run bookkeeping is
+    /// taken from the store, not from `job`, so runs made while the editor
+    /// was open are kept. A changed schedule runs next from now on rather
+    /// than from the old one.
+    pub fn update_job(&mut self, job: CronJob) -> Result<bool, CronError> {
+        job.schedule.validate()?;
+        if !self.has_job(&job.id) {
+            return Ok(false);
+        }
+        let Some(current) = self.store.jobs.iter().find(|j| j.id == job.id) else {
+            return Ok(false);
+        };
+        let mut updated = current.clone();
+        if updated.schedule != job.schedule {
+            updated.next_run_ms = Some(compute_next_run(&job.schedule, self.clock.now().timestamp_millis()));
+        }
+        updated.name = job.name;
+        updated.message = job.message;
+        updated.enabled = job.enabled;
+        updated.critical = job.critical;
+        updated.channel = job.channel;
+        updated.chat_id = job.chat_id;
+        updated.schedule = job.schedule;
+        updated.kind = job.kind;
+        let id = updated.id.clone();
+        self.record(&[CronEvent::Add { job: Box::new(updated) }])?;
+        info!(id = %id, "Updated cron job");
+        Ok(true)
     }
 
-    /// Enable or disable a job.
-    pub fn enable_job(&mut self, job_id: &str, enabled: bool) -> anyhow::Result<bool> {
-        if let Some(job) = self.store.jobs.iter_mut().find(|j| j.id == job_id) {
-            job.enabled = enabled;
-            self.save_store()?;
-            Ok(true)
-        } else {
-            Ok(false)
+    /// Add exported jobs, keeping their IDs. Jobs whose ID is taken are
+    /// skipped, and the rest first run at their next scheduled time rather
+    /// than right away. `tool_call` jobs arrive disabled: they call tools
+    /// without the agent in between, so the user enables each after reading
+    /// it. Nothing is added if any schedule is invalid. Returns the IDs
+    /// added.
+    pub fn import_jobs(&mut self, jobs: Vec<CronJob>) -> Result<Vec<String>, CronError> {
+        for job in &jobs {
+            job.schedule
+                .validate()
+                .map_err(|e| CronError::InvalidJob(format!("{} ({}): {}", job.name, job.id, e)))?;
+        }
+        self.refresh();
+        let now_ms = self.clock.now().timestamp_millis();
+        let mut seen = std::collections::HashSet::new();
+        let events: Vec<CronEvent> = jobs
+            .into_iter()
+            .filter(|job| !self.store.jobs.iter().any(|j| j.id == job.id) && seen.insert(job.id.clone()))
+            .map(|mut job| {
+                job.last_run = None;
+                job.next_run_ms = Some(compute_next_run(&job.schedule, now_ms));
+                if matches!(job.kind, JobKind::ToolCall(_)) {
+                    job.enabled = false;
+                }
+                CronEvent::Add { job: Box::new(job) }
+            })
+            .collect();
+        if !events.is_empty() {
+            self.record(&events)?;
         }
+        let added: Vec<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                CronEvent::Add { job } => Some(job.id.clone()),
+                _ => None,
+            })
+            .collect();
+        info!(count = added.len(), "Imported cron jobs");
+        Ok(added)
+    }
+
+    /// Mark a job as critical so it keeps running when the token budget is
+    /// nearly used up.
+    pub fn set_critical(&mut self, job_id: &str, critical: bool) -> Result<bool, CronError> {
+        if !self.has_job(job_id) {
+            return Ok(false);
+        }
+        self.record(&[CronEvent::Critical {
+            id: job_id.to_string(),
+            critical,
+        }])?;
+        Ok(true)
     }
 
     /// List all jobs.
@@ -136,76 +598,248 @@ impl CronService {
             .collect()
     }
 
+    /// Parse the job store in `workspace` strictly, returning the job count.
+    ///
+    /// [`CronService::new`] moves a corrupt `cron.json` aside and starts
+    /// empty, and skips unreadable `cron.log` entries; this surfaces the
+    /// error instead. Cron expressions are validated too.
+    pub fn validate_store(workspace: &Path) -> Result<usize, CronError> {
+        let path = workspace.join("cron.json");
+        let mut store: CronStore = match path.exists() {
+            true => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            false => CronStore::default(),
+        };
+        let log_path = workspace.join("cron.log");
+        if log_path.exists() {
+            for line in std::fs::read_to_string(log_path)?.lines().filter(|l| !l.trim().is_empty()) {
+                store.apply(&serde_json::from_str(line)?);
+            }
+        }
+        for job in &store.jobs {
+            job.schedule.validate()?;
+        }
+        Ok(store.jobs.len())
+    }
+
     /// Get a formatted status string.
     pub fn status(&self) -> String {
         let total = self.store.jobs.len();
         let enabled = self.store.jobs.iter().filter(|j| j.enabled).count();
-        format!("{} jobs ({} enabled)", total, enabled)
+        match self.announcements.len() {
+            0 => format!("{} jobs ({} enabled)", total, enabled),
+            n => format!("{} jobs ({} enabled), {} announcements", total, enabled, n),
+        }
+    }
+
+    /// Enabled jobs whose scheduled run has already passed, e.g. while the
+    /// bot was down. The next [`get_due_jobs`](Self::get_due_jobs) runs
+    /// each once.
+    pub fn missed_runs(&self) -> usize {
+        let now_ms = self.clock.now().timestamp_millis();
+        self.store
+            .jobs
+            .iter()
+            .filter(|j| j.enabled && j.next_run_ms.is_some_and(|next| next < now_ms))
+            .count()
+    }
+
+    /// Where a corrupt `cron.json` was moved when this service loaded.
+    pub fn quarantined(&self) -> Option<&Path> {
+        self.quarantined.as_deref()
     }
 
-    /// Get all due jobs (jobs whose next_run_ms <= now).
+    /// Get all due jobs (jobs whose next_run_ms <= now), after picking up
+    /// changes made by other processes.
     pub fn get_due_jobs(&mut self) -> Vec<CronJob> {
-        let now_ms = Local::now().timestamp_millis();
-        let mut due = Vec::new();
+        self.refresh();
+        let now = self.clock.now();
+        let now_ms = now.timestamp_millis();
+        let last_run = now.with_timezone(&Local).to_rfc3339();
 
-        for job in &mut self.store.jobs {
-            if !job.enabled {
-                continue;
+        let runs: Vec<CronEvent> = self
+            .store
+            .jobs
+            .iter()
+            // A job that never ran is due right away.
+            .filter(|job| job.enabled && job.next_run_ms.is_none_or(|next| now_ms >= next))
+            .map(|job| CronEvent::Ran {
+                id: job.id.clone(),
+                last_run: last_run.clone(),
+                next_run_ms: compute_next_run(&job.schedule, now_ms),
+            })
+            .collect();
+        if runs.is_empty() {
+            return Vec::new();
+        }
+        if let Err(e) = self.record(&runs) {
+            // Move the jobs on anyway, or they would fire on every tick.
+            warn!("Could not record cron runs: {}", e);
+            for run in &runs {
+                self.store.apply(run);
             }
+        }
 
-            let is_due = match job.next_run_ms {
-                Some(next) => now_ms >= next,
-                None => true, // Never run before
-            };
+        // Jobs another process removed meanwhile are gone from the store.
+        runs.iter()
+            .filter_map(|run| match run {
+                CronEvent::Ran { id, .. } => self.store.jobs.iter().find(|j| j.id == *id).cloned(),
+                _ => None,
+            })
+            .collect()
+    }
 
-            if is_due {
-                job.last_run = Some(Local::now().to_rfc3339());
-                job.next_run_ms = Some(compute_next_run(&job.schedule, now_ms));
-                due.push(job.clone());
+    /// Announcements whose time has come; each is moved on to its next run.
+    /// One seen for the first time is only scheduled.
+    pub fn due_announcements(&mut self) -> Vec<Announcement> {
+        let now_ms = self.clock.now().timestamp_millis();
+        let mut due = Vec::new();
+        for announcement in &mut self.announcements {
+            match announcement.next_run_ms {
+                Some(next) if now_ms < next => {}
+                None => announcement.next_run_ms = Some(compute_next_run(&announcement.schedule, now_ms)),
+                Some(_) => {
+                    announcement.next_run_ms = Some(compute_next_run(&announcement.schedule, now_ms));
+                    due.push(announcement.clone());
+                }
             }
         }
+        due
+    }
 
-        if !due.is_empty() {
-            let _ = self.save_store();
-        }
+    /// The clock this service reads.
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
 
-        due
+    /// Pick up jobs other processes added, changed or removed since this
+    /// service last read the store.
+    pub fn refresh(&mut self) {
+        if self.fingerprint() == self.seen {
+            return;
+        }
+        match self.lock() {
+            Ok(_lock) => self.load(),
+            Err(e) => warn!("Could not lock the cron store: {}", e),
+        }
     }
 
     // ── Private helpers ─────────────────────────────────────────────
 
-    fn load_store(path: &Path) -> CronStore {
-        if path.exists() {
-            std::fs::read_to_string(path)
-                .ok()
-                .and_then(|c| serde_json::from_str(&c).ok())
-                .unwrap_or_default()
-        } else {
-            CronStore::default()
+    fn has_job(&mut self, job_id: &str) -> bool {
+        self.refresh();
+        self.store.jobs.iter().any(|j| j.id == job_id)
+    }
+
+    /// Append `events` to the log and apply them, after catching up with
+    /// what other processes wrote.
+    fn record(&mut self, events: &[CronEvent]) -> Result<(), CronError> {
+        let _lock = self.lock()?;
+        if self.fingerprint() != self.seen {
+            self.load();
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
         }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)?
+            .write_all(lines.as_bytes())?;
+        for event in events {
+            self.store.apply(event);
+        }
+        self.log_entries += events.len();
+
+        if self.log_entries >= COMPACT_AFTER {
+            if let Err(e) = self.compact() {
+                warn!("Could not compact the cron log: {}", e);
+            }
+        }
+        self.seen = self.fingerprint();
+        Ok(())
     }
 
-    fn save_store(&self) -> anyhow::Result<()> {
-        let json = serde_json::to_string_pretty(&self.store)?;
-        std::fs::write(&self.store_path, json)?;
+    /// Read the snapshot and replay the log. Call with the lock held.
+    fn load(&mut self) {
+        let (store, quarantined) = recovery::load_or_quarantine::<CronStore>(&self.store_path, Utc::now());
+        if quarantined.is_some() {
+            if self.log_path.exists() {
+                if let Err(e) = recovery::quarantine(&self.log_path, Utc::now()) {
+                    warn!("Could not move the cron log aside: {}", e);
+                }
+            }
+            self.quarantined = quarantined;
+        }
+
+        let mut store = store.unwrap_or_default();
+        let mut entries = 0;
+        let log = std::fs::read_to_string(&self.log_path).unwrap_or_default();
+        for line in log.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str::<CronEvent>(line) {
+                Ok(event) => {
+                    store.apply(&event);
+                    entries += 1;
+                }
+                // A write cut short by a crash; everything before it stands.
+                Err(e) => warn!("Skipping unreadable cron.log entry: {}", e),
+            }
+        }
+        self.store = store;
+        self.log_entries = entries;
+        self.seen = self.fingerprint();
+    }
+
+    /// Write the jobs as the new snapshot and empty the log. Call with the
+    /// lock held.
+    fn compact(&mut self) -> Result<(), CronError> {
+        let tmp = self.store_path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.store)?)?;
+        std::fs::rename(&tmp, &self.store_path)?;
+        File::create(&self.log_path)?;
+        self.log_entries = 0;
         Ok(())
     }
+
+    /// Snapshot mtime and log length.
+    fn fingerprint(&self) -> (Option<SystemTime>, u64) {
+        let modified = std::fs::metadata(&self.store_path).and_then(|m| m.modified()).ok();
+        let log_len = std::fs::metadata(&self.log_path).map(|m| m.len()).unwrap_or(0);
+        (modified, log_len)
+    }
+
+    /// Block until this process holds the store's advisory lock, released
+    /// when the returned file is dropped.
+    fn lock(&self) -> Result<File, CronError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)?;
+        file.lock()?;
+        Ok(file)
+    }
 }
 
-/// Compute the next run time in milliseconds.
+/// Compute the next run time after `now_ms`, in milliseconds.
 fn compute_next_run(schedule: &Schedule, now_ms: i64) -> i64 {
     match schedule {
         Schedule::Interval { seconds } => now_ms + (*seconds as i64 * 1000),
-        Schedule::Cron { expression } => {
+        Schedule::Cron { expression, timezone } => {
             use std::str::FromStr;
-            match cron::Schedule::from_str(expression) {
-                Ok(sched) => sched
-                    .upcoming(Local)
-                    .next()
-                    .map(|dt| dt.timestamp_millis())
-                    .unwrap_or(now_ms + 60_000),
-                Err(_) => now_ms + 60_000,
-            }
+            let Ok(sched) = cron::Schedule::from_str(expression) else {
+                return now_ms + 60_000;
+            };
+            let Some(now) = DateTime::from_timestamp_millis(now_ms) else {
+                return now_ms + 60_000;
+            };
+            let next = match timezone.as_deref().and_then(|tz| parse_timezone(tz).ok()) {
+                Some(tz) => sched.after(&now.with_timezone(&tz)).next().map(|dt| dt.timestamp_millis()),
+                None => sched.after(&now.with_timezone(&Local)).next().map(|dt| dt.timestamp_millis()),
+            };
+            next.unwrap_or(now_ms + 60_000)
         }
     }
 }
@@ -248,4 +882,279 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&tmp);
     }
+
+    #[test]
+    fn test_job_templates_add_valid_jobs() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_cron_templates");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+
+        let mut service = CronService::new(&tmp);
+        for template in JOB_TEMPLATES {
+            service
+                .add_from_template(template, Some("UTC".into()), "telegram", "42")
+                .unwrap_or_else(|e| panic!("{}: {}", template.name, e));
+        }
+        assert_eq!(service.list_jobs(false).len(), JOB_TEMPLATES.len());
+        assert!(job_template("journal-review").unwrap().message.contains("journal_decision"));
+        assert!(job_template("nope").is_none());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_tool_job_roundtrip_and_format() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_cron_tool");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+
+        let mut service = CronService::new(&tmp);
+        let args = HashMap::from([("address".to_string(), serde_json::json!("abc"))]);
+        service
+            .add_tool_job(
+                "sol-balance",
+                Schedule::Interval { seconds: 60 },
+                ToolCall {
+                    name: "solana_balance".into(),
+                    args,
+                    format_template: Some("Balance at {time}: {result}".into()),
+                    template: None,
+                    cooldown_secs: None,
+                    owner: "7".into(),
+                },
+                "telegram",
+                "1",
+            )
+            .unwrap();
+
+        // Reload from disk to check the kind is persisted.
+        let service = CronService::new(&tmp);
+        let job = service.list_jobs(false)[0].clone();
+        assert!(matches!(job.kind, JobKind::ToolCall(ref c) if c.name == "solana_balance"));
+        let text = job.format_tool_output("1.5 SOL", Utc::now());
+        assert!(text.starts_with("Balance at ") && text.ends_with(": 1.5 SOL"));
+
+        // Jobs saved before `kind` existed still load as agent jobs.
+        let legacy: CronJob = serde_json::from_str(
+            r#"{"id":"j","name":"n","schedule":{"type":"interval","seconds":5},
+                "message":"m","enabled":true,"created_at":"now"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.kind, JobKind::Agent);
+        assert!(!legacy.critical);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_announcement_waits_for_its_time_and_renders() {
+        let config = AnnouncementConfig {
+            name: "standup".into(),
+            schedule: "0 0 9 * * *".into(),
+            message: "☕ {{ name }} on {{ weekday }}".into(),
+            ..Default::default()
+        };
+        let mut announcement = Announcement::from_config(&config, "42", Some("Asia/Tokyo")).unwrap();
+        assert_eq!(announcement.chat_id, "42");
+        assert!(matches!(announcement.schedule, Schedule::Cron { timezone: Some(ref tz), .. } if tz == "Asia/Tokyo"));
+
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_cron_announce");
+        let _ = std::fs::create_dir_all(&tmp);
+        let registry = TemplateRegistry::new(&tmp);
+        let now = Utc::now();
+        let weekday = announcement.schedule.local_time(now).format("%A").to_string();
+        assert_eq!(announcement.render(&registry, now), format!("☕ standup on {}", weekday));
+
+        // Not sent at startup, only once the next run is reached.
+        let mut service = CronService::new(&tmp).with_announcements(vec![announcement.clone()]);
+        assert!(service.due_announcements().is_empty());
+        announcement.next_run_ms = Some(0);
+        let mut service = CronService::new(&tmp).with_announcements(vec![announcement]);
+        assert_eq!(service.due_announcements().len(), 1);
+        assert!(service.due_announcements().is_empty());
+
+        // A broken template falls back to the raw message.
+        let broken = AnnouncementConfig {
+            message: "{% if %}".into(),
+            ..config.clone()
+        };
+        let broken = Announcement::from_config(&broken, "42", None).unwrap();
+        assert_eq!(broken.render(&registry, now), "{% if %}");
+
+        let bad = AnnouncementConfig {
+            schedule: "every day".into(),
+            ..config
+        };
+        assert!(matches!(
+            Announcement::from_config(&bad, "42", None),
+            Err(CronError::InvalidExpression { .. })
+        ));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_cron_schedule_in_timezone() {
+        // 09:00 in Tokyo is always 00:00 UTC (no DST).
+        let tokyo = Schedule::Cron {
+            expression: "0 0 9 * * *".into(),
+            timezone: Some("Asia/Tokyo".into()),
+        };
+        let next = compute_next_run(&tokyo, 0);
+        let next = chrono::DateTime::from_timestamp_millis(next).unwrap();
+        assert_eq!(next.format("%H:%M").to_string(), "00:00");
+
+        // Jobs saved without a timezone still load.
+        let legacy: Schedule = serde_json::from_str(r#"{"type":"cron","expression":"0 0 9 * * *"}"#).unwrap();
+        assert!(matches!(legacy, Schedule::Cron { timezone: None, .. }));
+
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_cron_tz");
+        let _ = std::fs::create_dir_all(&tmp);
+        let mut service = CronService::new(&tmp);
+        let bad = Schedule::Cron {
+            expression: "0 0 9 * * *".into(),
+            timezone: Some("Mars/Olympus".into()),
+        };
+        assert!(matches!(
+            service.add_job("bad-tz", bad, "hi", "cli", "test"),
+            Err(CronError::InvalidTimezone(_))
+        ));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+    #[test]
+    fn test_due_jobs_follow_the_injected_clock() {
+        use crate::clock::MockClock;
+        use chrono::{Duration, TimeZone};
+        use std::sync::Arc;
+
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_cron_clock_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+
+        // Monday 2026-01-05, 08:00 UTC.
+        let clock = Arc::new(MockClock::new(Utc.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap()));
+        let mut service = CronService::new(&tmp).with_clock(clock.clone());
+        let daily = Schedule::Cron {
+            expression: "0 0 9 * * *".into(),
+            timezone: Some("UTC".into()),
+        };
+        service.add_job("hourly", Schedule::Interval { seconds: 3600 }, "tick", "cli", "t").unwrap();
+        service.add_job("daily", daily, "brief", "cli", "t").unwrap();
+
+        // Jobs that never ran are due at once, then wait for their next run.
+        assert_eq!(service.get_due_jobs().len(), 2);
+        assert!(service.get_due_jobs().is_empty());
+
+        clock.advance(Duration::minutes(59));
+        assert!(service.get_due_jobs().is_empty());
+        clock.advance(Duration::minutes(1));
+        let due: Vec<_> = service.get_due_jobs().into_iter().map(|j| j.name).collect();
+        assert_eq!(due, ["hourly", "daily"]);
+        assert!(service.list_jobs(false)[1].last_run.as_deref().unwrap().starts_with("2026-01-05"));
+
+        // A week later each job has caught up once, not 168 times.
+        clock.advance(Duration::days(7));
+        assert_eq!(service.get_due_jobs().len(), 2);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_changes_from_another_process_are_picked_up() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_cron_shared_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+
+        // The running bot, and `cron add` / `cron remove` from the CLI.
+        let mut bot = CronService::new(&tmp);
+        let mut cli = CronService::new(&tmp);
+        let kept = cli.add_job("kept", Schedule::Interval { seconds: 60 }, "a", "cli", "t").unwrap();
+        let gone = cli.add_job("gone", Schedule::Interval { seconds: 60 }, "b", "cli", "t").unwrap();
+        assert_eq!(bot.get_due_jobs().len(), 2);
+
+        // Neither process overwrites the other's changes.
+        assert!(cli.remove_job(&gone).unwrap());
+        bot.add_job("from bot", Schedule::Interval { seconds: 60 }, "c", "cli", "t").unwrap();
+        bot.refresh();
+        let names: Vec<_> = bot.list_jobs(true).iter().map(|j| j.name.clone()).collect();
+        assert_eq!(names, ["kept", "from bot"]);
+        assert!(!CronService::new(&tmp).list_jobs(true).iter().any(|j| j.id == gone));
+
+        // Compaction folds the log into cron.json without losing anything.
+        for _ in 0..COMPACT_AFTER {
+            cli.enable_job(&kept, false).unwrap();
+        }
+        assert!(std::fs::metadata(tmp.join("cron.log")).unwrap().len() < 1_000);
+        bot.refresh();
+        assert_eq!(bot.list_jobs(false).len(), 1);
+        assert_eq!(CronService::validate_store(&tmp).unwrap(), 2);
+
+        // A line torn by a crash is skipped.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(tmp.join("cron.log"))
+            .unwrap()
+            .write_all(b"{\"op\":\"remo")
+            .unwrap();
+        assert_eq!(CronService::new(&tmp).list_jobs(true).len(), 2);
+        assert!(CronService::validate_store(&tmp).is_err());
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_update_and_import_jobs() {
+        use crate::clock::MockClock;
+        use chrono::TimeZone;
+        use std::sync::Arc;
+
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_cron_import_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
+        let clock = Arc::new(MockClock::new(now));
+        let mut service = CronService::new(&tmp).with_clock(clock.clone());
+        let first = service.add_job("first", Schedule::Interval { seconds: 60 }, "a", "cli", "t").unwrap();
+        let second = service.add_job("second", Schedule::Interval { seconds: 60 }, "b", "cli", "t").unwrap();
+
+        // An edit keeps the job's place; a new schedule starts from now.
+        let mut job = service.list_jobs(true)[0].clone();
+        job.name = "renamed".into();
+        job.schedule = Schedule::Interval { seconds: 600 };
+        // A run while the editor is open isn't undone by the edit.
+        clock.advance(chrono::Duration::seconds(61));
+        assert_eq!(service.get_due_jobs().len(), 2);
+        assert!(service.update_job(job.clone()).unwrap());
+        let names: Vec<_> = service.list_jobs(true).iter().map(|j| j.name.clone()).collect();
+        assert_eq!(names, ["renamed", "second"]);
+        let edited = service.list_jobs(true)[0];
+        assert_eq!(edited.next_run_ms, Some(now.timestamp_millis() + 661_000));
+        assert!(edited.last_run.is_some());
+        job.schedule = Schedule::Cron { expression: "nope".into(), timezone: None };
+        assert!(service.update_job(job).is_err());
+
+        // Importing an export into another workspace keeps the IDs and skips
+        // jobs that are already there.
+        let exported: Vec<CronJob> = service.list_jobs(true).into_iter().cloned().collect();
+        let other = tmp.join("other");
+        let _ = std::fs::create_dir_all(&other);
+        let mut target = CronService::new(&other).with_clock(clock);
+        assert_eq!(target.import_jobs(exported.clone()).unwrap(), [first, second]);
+        assert_eq!(target.import_jobs(exported).unwrap(), Vec::<String>::new());
+        assert!(target.get_due_jobs().is_empty(), "imported jobs wait for their schedule");
+
+        // Jobs that call tools directly arrive disabled.
+        let call = ToolCall {
+            name: "shell_exec".into(),
+            ..Default::default()
+        };
+        let tool = service.add_tool_job("tool", Schedule::Interval { seconds: 60 }, call, "cli", "t").unwrap();
+        let exported: Vec<CronJob> = service.list_jobs(true).into_iter().cloned().collect();
+        assert_eq!(target.import_jobs(exported).unwrap(), std::slice::from_ref(&tool));
+        assert!(target.list_jobs(true).iter().any(|j| j.id == tool && !j.enabled));
+
+        let mut bad = service.list_jobs(true)[0].clone();
+        bad.id = "job_new".into();
+        bad.schedule = Schedule::Cron { expression: "nope".into(), timezone: None };
+        assert!(target.import_jobs(vec![bad]).unwrap_err().to_string().contains("job_new"));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
 }
@@ -0,0 +1,235 @@
+//! Who may talk to the bot: the `allowFrom` rules shared by the chat
+//! transports.
+//!
+//! Each `allowFrom` entry is one rule, `kind:pattern`:
+//!
+//! | Rule             | Allows                                              |
+//! |------------------|-----------------------------------------------------|
+//! | `12345`          | user 12345 (a bare id is a user, as it always was)  |
+//! | `user:12345`     | user 12345                                          |
+//! | `chat:-100777`   | everyone in a chat (Telegram group, Discord channel)|
+//! | `guild:987`      | everyone in a Discord server                        |
+//! | `role:555`       | Discord members with that role                      |
+//!
+//! Patterns may contain `*` wildcards (`user:*`, `chat:-100*`). A message is
+//! let through when any rule matches; an empty list lets everyone through.
+
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::warn;
+
+/// Errors from parsing an `allowFrom` rule.
+#[derive(Debug, thiserror::Error)]
+pub enum AclError {
+    #[error("empty allowFrom rule")]
+    Empty,
+
+    #[error("unknown allowFrom rule kind '{0}' (use user, chat, guild or role)")]
+    UnknownKind(String),
+}
+
+/// What a rule is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    User,
+    Chat,
+    Guild,
+    Role,
+}
+
+impl RuleKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RuleKind::User => "user",
+            RuleKind::Chat => "chat",
+            RuleKind::Guild => "guild",
+            RuleKind::Role => "role",
+        }
+    }
+}
+
+/// One parsed `allowFrom` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub kind: RuleKind,
+    pub pattern: String,
+}
+
+impl Rule {
+    fn matches(&self, who: &Origin<'_>) -> bool {
+        match self.kind {
+            RuleKind::User => wildcard_match(&self.pattern, who.user_id),
+            RuleKind::Chat => wildcard_match(&self.pattern, who.chat_id),
+            RuleKind::Guild => who.guild_id.is_some_and(|g| wildcard_match(&self.pattern, g)),
+            RuleKind::Role => who.roles.iter().any(|r| wildcard_match(&self.pattern, r)),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = AclError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // Telegram chat ids are negative numbers, so only a word before the
+        // first `:` names a kind.
+        let (kind, pattern) = match s.split_once(':') {
+            Some((kind, pattern)) if kind.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let kind = match kind.to_ascii_lowercase().as_str() {
+                    "user" => RuleKind::User,
+                    "chat" => RuleKind::Chat,
+                    "guild" => RuleKind::Guild,
+                    "role" => RuleKind::Role,
+                    _ => return Err(AclError::UnknownKind(kind.to_string())),
+                };
+                (kind, pattern.trim())
+            }
+            _ => (RuleKind::User, s),
+        };
+        if pattern.is_empty() {
+            return Err(AclError::Empty);
+        }
+        Ok(Rule {
+            kind,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind.as_str(), self.pattern)
+    }
+}
+
+/// The sender of a message, as far as the transport knows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Origin<'a> {
+    pub user_id: &'a str,
+    pub chat_id: &'a str,
+    /// Discord server the message was sent in.
+    pub guild_id: Option<&'a str>,
+    /// Discord role ids of the sender in that server.
+    pub roles: &'a [String],
+}
+
+/// A transport's parsed `allowFrom` list.
+#[derive(Debug, Clone)]
+pub struct Acl {
+    rules: Vec<Rule>,
+    open: bool,
+}
+
+impl Acl {
+    /// Parse every entry, failing on the first bad one.
+    pub fn parse(entries: &[String]) -> Result<Self, AclError> {
+        let rules = entries.iter().map(|e| e.parse()).collect::<Result<_, _>>()?;
+        Ok(Self {
+            rules,
+            open: entries.is_empty(),
+        })
+    }
+
+    /// Parse `entries`, logging and dropping bad ones. The list stays closed
+    /// even if every entry was bad, so a typo never opens the bot to
+    /// everyone.
+    pub fn from_config(channel: &str, entries: &[String]) -> Self {
+        let rules = entries
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .parse()
+                    .inspect_err(|e| warn!(channel, entry, "Ignoring allowFrom rule: {}", e))
+                    .ok()
+            })
+            .collect();
+        Self {
+            rules,
+            open: entries.is_empty(),
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Whether `who` may use the bot.
+    pub fn allows(&self, who: &Origin<'_>) -> bool {
+        self.open || self.rules.iter().any(|rule| rule.matches(who))
+    }
+}
+
+/// `*` matches any run of characters, everything else itself.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all: exact match.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acl(entries: &[&str]) -> Acl {
+        Acl::parse(&entries.iter().map(|e| e.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_rules_parse_with_and_without_kind() {
+        assert_eq!("123".parse::<Rule>().unwrap().to_string(), "user:123");
+        assert_eq!("chat:-100777".parse::<Rule>().unwrap().kind, RuleKind::Chat);
+        assert_eq!("Role:5".parse::<Rule>().unwrap().kind, RuleKind::Role);
+        // A negative Telegram chat id is not mistaken for a kind.
+        assert_eq!("-100:7".parse::<Rule>().unwrap().kind, RuleKind::User);
+        assert!(matches!("team:1".parse::<Rule>(), Err(AclError::UnknownKind(_))));
+        assert!(matches!("user:".parse::<Rule>(), Err(AclError::Empty)));
+    }
+
+    #[test]
+    fn test_users_chats_guilds_and_roles() {
+        let acl = acl(&["42", "chat:-100777", "guild:9", "role:mods"]);
+        let roles = ["mods".to_string()];
+        let who = |user_id, chat_id| Origin { user_id, chat_id, ..Default::default() };
+
+        assert!(acl.allows(&who("42", "42")));
+        assert!(acl.allows(&who("7", "-100777")));
+        assert!(!acl.allows(&who("7", "7")));
+        assert!(acl.allows(&Origin { guild_id: Some("9"), ..who("7", "1") }));
+        assert!(!acl.allows(&Origin { guild_id: Some("8"), ..who("7", "1") }));
+        assert!(acl.allows(&Origin { roles: &roles, ..who("7", "1") }));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("-100*", "-100123"));
+        assert!(!wildcard_match("-100*", "100123"));
+        assert!(wildcard_match("1*3*5", "12345"));
+        assert!(!wildcard_match("1*3*5", "1235 4"));
+        assert!(!wildcard_match("12*2", "12"));
+        assert!(acl(&["user:*"]).allows(&Origin { user_id: "anyone", ..Default::default() }));
+    }
+
+    #[test]
+    fn test_empty_list_is_open_but_bad_rules_stay_closed() {
+        let anyone = Origin { user_id: "1", chat_id: "1", ..Default::default() };
+        assert!(Acl::parse(&[]).unwrap().allows(&anyone));
+        assert!(!Acl::from_config("telegram", &["team:1".into()]).allows(&anyone));
+        assert!(Acl::parse(&["team:1".into()]).is_err());
+    }
+}
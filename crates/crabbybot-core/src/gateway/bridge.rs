@@ -1,14 +1,28 @@
 use anyhow::Result;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Mutex};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::agent::{AgentError, AgentLoop};
-use crate::bus::events::{InboundMessage, OutboundMessage};
+use crate::agent::contacts::ContactBook;
+use crate::agent::locale::ChatLocale;
+use crate::agent::{AgentError, AgentLoop, AgentResult};
+use crate::alerts::AlertManager;
+use crate::bus::events::{new_request_id, InboundMessage, OutboundMessage};
 use crate::bus::MessageBus;
+use crate::commands::{CommandContext, CommandOutput, CommandRouter, CommandSpec, MenuEntry};
 use crate::cron::CronService;
+use crate::config::{RepliesConfig, SessionScope};
+use crate::scheduler::{Lane, LlmScheduler};
+
+use super::coalesce::Coalescer;
+use super::digest::{self, GroupLog};
+use super::errors;
+use super::hooks::{Hooks, PreMessage};
+use super::postprocess::{self, PostProcessor};
 
 /// Bridges the asynchronous [`MessageBus`] with the [`AgentLoop`].
 ///
@@ -25,18 +39,60 @@ use crate::cron::CronService;
 /// upgraded to a per-session pool later.
 ///
 /// ## What the bridge handles
-/// - **Command routing**: `/help`, `/status`, `/clear` are handled directly.
+/// - **Message stitching**: rapid messages from one user are merged into a
+///   single turn (see [`coalesce_window`](Self::coalesce_window)).
+/// - **Command routing**: the [standard commands](CommandRouter::standard)
+///   plus `/digest` and `/restart` are answered without the LLM, or
+///   rewritten into a prompt for it.
+/// - **Listen-only chats**: messages are recorded for `/digest` instead of
+///   answered (see [`listen_only`](Self::listen_only)).
+/// - **Session scopes**: a chat's messages go to its own session, one per
+///   user, or one shared by the channel (see
+///   [`session_scopes`](Self::session_scopes)).
+/// - **Forks**: after `/fork`, a conversation talks to the fork's session
+///   until `/unfork`.
 /// - **Agent passthrough**: all other messages go to the LLM.
+/// - **Reply post-processing**: disclaimers, signatures and paging of long
+///   replies (see [`replies`](Self::replies)); "more" sends the next page.
+/// - **Alert gating**: replies to scheduled agent jobs and webhook events
+///   are deduplicated and held back in muted chats (see
+///   [`alerts`](Self::alerts)).
 /// - **Streaming events**: `Typing` and `Progress` are forwarded to the bus
 ///   by the agent loop itself.
+/// - **Session tags**: sessions are tagged with topics and sentiment in
+///   the background (see [`tag_sessions_every`](Self::tag_sessions_every)).
 /// - **Graceful shutdown** via a [`CancellationToken`].
 pub struct AgentBridge {
     bus: Arc<MessageBus>,
     agent: Arc<Mutex<AgentLoop>>,
     cancel: CancellationToken,
-    cron: Arc<Mutex<CronService>>,
-    workspace: PathBuf,
-    start_time: std::time::Instant,
+    coalesce_window: Duration,
+    tag_interval: Duration,
+    state: BridgeState,
+}
+
+/// State shared by every turn.
+struct BridgeState {
+    commands: CommandRouter,
+    cx: CommandContext,
+    /// Cancelled by `/restart` and `/config set`.
+    cancel: CancellationToken,
+    /// Chats whose messages are recorded instead of answered.
+    listen_only: HashSet<String>,
+    /// Channel → how its messages are grouped into sessions; per chat
+    /// when missing.
+    session_scopes: HashMap<String, SessionScope>,
+    group_log: GroupLog,
+    hooks: Hooks,
+    replies: PostProcessor,
+    /// Watchdog limit for one agent turn; zero disables it.
+    turn_timeout: Duration,
+    /// Whether `/restart` and `/config set` restart the process.
+    restarts: bool,
+    /// The agent's LLM scheduler, waited on before the agent is locked.
+    scheduler: Option<Arc<LlmScheduler>>,
+    /// Gate for replies to unattended turns; `None` sends them all.
+    alerts: Option<AlertManager>,
 }
 
 impl AgentBridge {
@@ -47,16 +103,95 @@ impl AgentBridge {
         cron: Arc<Mutex<CronService>>,
         workspace: PathBuf,
     ) -> Self {
+        let scheduler = agent.scheduler();
+        let agent = Arc::new(Mutex::new(agent));
+        let group_log = GroupLog::new(&workspace);
         Self {
             bus,
-            agent: Arc::new(Mutex::new(agent)),
-            cancel,
-            cron,
-            workspace,
-            start_time: std::time::Instant::now(),
+            cancel: cancel.clone(),
+            coalesce_window: Duration::ZERO,
+            tag_interval: Duration::ZERO,
+            state: BridgeState {
+                commands: bot_commands(&group_log),
+                cx: CommandContext::new(Arc::clone(&agent), workspace.clone()).with_cron(cron),
+                cancel,
+                group_log,
+                hooks: Hooks::new(&workspace),
+                replies: PostProcessor::default(),
+                listen_only: HashSet::new(),
+                session_scopes: HashMap::new(),
+                turn_timeout: Duration::ZERO,
+                restarts: true,
+                scheduler,
+                alerts: None,
+            },
+            agent,
         }
     }
 
+    /// Hold each user message this long and append further messages from
+    /// the same user that arrive meanwhile, so text split across several
+    /// messages is answered once. Zero (the default) disables this.
+    pub fn coalesce_window(mut self, window: Duration) -> Self {
+        self.coalesce_window = window;
+        self
+    }
+
+    /// Tag the sessions that changed with topics and sentiment this often,
+    /// see [`crate::session::tags`]. Zero (the default) disables it.
+    pub fn tag_sessions_every(mut self, interval: Duration) -> Self {
+        self.tag_interval = interval;
+        self
+    }
+
+    /// Cancel agent turns that run longer than `limit` and tell the user.
+    /// Zero (the default) lets turns run indefinitely.
+    pub fn turn_timeout(mut self, limit: Duration) -> Self {
+        self.state.turn_timeout = limit;
+        self
+    }
+
+    /// Record messages in these chats (`channel:chat_id`) for `/digest`
+    /// instead of answering them. Commands are still handled.
+    pub fn listen_only(mut self, chat_keys: impl IntoIterator<Item = String>) -> Self {
+        self.state.listen_only = chat_keys.into_iter().collect();
+        self
+    }
+
+    /// Treat replies to scheduled agent jobs and webhook events as alerts:
+    /// repeats within the cooldown are held back, and muted chats skip the
+    /// turn altogether.
+    pub fn alerts(mut self, alerts: AlertManager) -> Self {
+        self.state.alerts = Some(alerts);
+        self
+    }
+
+    /// Which messages of each channel share a session, e.g. one per user
+    /// in group chats. Channels not listed get one session per chat.
+    pub fn session_scopes(mut self, scopes: HashMap<String, SessionScope>) -> Self {
+        self.state.session_scopes = scopes;
+        self
+    }
+
+    /// Rewrite agent replies before they are sent, see [`postprocess`].
+    pub fn replies(mut self, config: RepliesConfig) -> Self {
+        self.state.replies = PostProcessor::new(config);
+        self
+    }
+
+    /// Whether `/restart` and `/config set` restart the process (the
+    /// default). When off, as in the terminal chat, they only say that a
+    /// restart is needed.
+    pub fn restarts(mut self, enabled: bool) -> Self {
+        self.state.restarts = enabled;
+        self
+    }
+
+    /// The commands this bridge answers, for the chat apps' command menus.
+    pub fn menu(&self) -> Vec<MenuEntry> {
+        self.state.commands.menu()
+    }
+
     /// Run the bridge loop until the bus is closed or cancellation is requested.
     pub async fn run(self, mut inbound_rx: mpsc::Receiver<InboundMessage>) -> Result<()> {
         info!("Agent bridge started, waiting for inbound messages…");
@@ -65,21 +200,44 @@ impl AgentBridge {
             bus,
             agent,
             cancel,
-            cron,
-            workspace,
-            start_time,
+            coalesce_window,
+            tag_interval,
+            state,
         } = self;
+        let state = Arc::new(state);
 
+        let mut coalescer = Coalescer::new(coalesce_window);
+        let mut tagging = (!tag_interval.is_zero()).then(|| tokio::time::interval(tag_interval));
         loop {
+            let next_due = coalescer.next_deadline();
             tokio::select! {
                 _ = cancel.cancelled() => {
                     info!("Agent bridge received shutdown signal");
                     break;
                 }
+                _ = tick(&mut tagging) => {
+                    let agent = Arc::clone(&agent);
+                    tokio::spawn(async move {
+                        match agent.lock().await.tag_sessions() {
+                            Ok(0) => {}
+                            Ok(tagged) => debug!(sessions = tagged, "Tagged sessions"),
+                            Err(e) => warn!("Session tagging failed: {}", e),
+                        }
+                    });
+                }
+                _ = sleep_until(next_due) => {
+                    for msg in coalescer.take_due(Instant::now()) {
+                        spawn_turn(msg, &bus, &agent, &state);
+                    }
+                }
                 msg = inbound_rx.recv() => {
                     match msg {
                         None => {
-                            // All inbound_tx senders dropped — shut down.
+                            // All inbound_tx senders dropped — process what is
+                            // still held, then shut down.
+                            for msg in coalescer.drain() {
+                                spawn_turn(msg, &bus, &agent, &state);
+                            }
                             break;
                         }
                         Some(msg) => {
@@ -88,97 +246,9 @@ impl AgentBridge {
                                 chat_id = msg.chat_id,
                                 "Bridge received message"
                             );
-
-                            // Clone the cheap Arcs to move into the spawned task.
-                            let bus_t      = Arc::clone(&bus);
-                            let agent_t    = Arc::clone(&agent);
-                            let cron_t     = Arc::clone(&cron);
-                            let workspace_t = workspace.clone();
-                            let channel    = msg.channel.clone();
-                            let chat_id    = msg.chat_id.clone();
-                            let session_key = format!("{}:{}", channel, chat_id);
-                            let content    = msg.content.clone();
-                            let is_system  = msg.is_system;
-
-                            tokio::spawn(async move {
-                                // ── Command routing (non-system messages only) ──────
-                                if !is_system {
-                                    match handle_command(
-                                        &content,
-                                        &session_key,
-                                        &cron_t,
-                                        &workspace_t,
-                                        start_time,
-                                        &agent_t,
-                                    )
-                                    .await
-                                    {
-                                        Some(CommandResult::Reply(response)) => {
-                                            bus_t
-                                                .publish_outbound(OutboundMessage::reply(
-                                                    &channel, &chat_id, response,
-                                                ))
-                                                .await;
-                                            return;
-                                        }
-                                        Some(CommandResult::AgentPassthrough(prompt)) => {
-                                            // Rewrite the command into a natural language prompt
-                                            // and fall through to agent processing below.
-                                            let result = {
-                                                let mut lock = agent_t.lock().await;
-                                                lock.process(&prompt, &session_key, Some(&bus_t)).await
-                                            };
-                                            match result {
-                                                Ok(res) => {
-                                                    let outbound = if let Some(btns) = res.buttons {
-                                                        OutboundMessage::reply_with_buttons(&channel, &chat_id, res.content, btns)
-                                                    } else {
-                                                        OutboundMessage::reply(&channel, &chat_id, res.content)
-                                                    };
-                                                    bus_t.publish_outbound(outbound).await;
-                                                }
-                                                Err(e) => {
-                                                    error!("Error processing command passthrough: {}", e);
-                                                    let error_msg = format_agent_error(&e);
-                                                    bus_t
-                                                        .publish_outbound(OutboundMessage::reply(
-                                                            &channel, &chat_id, error_msg,
-                                                        ))
-                                                        .await;
-                                                }
-                                            }
-                                            return;
-                                        }
-                                        None => {} // Not a command, fall through to agent
-                                    }
-                                }
-
-                                // ── Agent processing ───────────────────────────────
-                                let result = {
-                                    let mut lock = agent_t.lock().await;
-                                    lock.process(&content, &session_key, Some(&bus_t)).await
-                                };
-
-                                match result {
-                                    Ok(res) => {
-                                        let outbound = if let Some(btns) = res.buttons {
-                                            OutboundMessage::reply_with_buttons(&channel, &chat_id, res.content, btns)
-                                        } else {
-                                            OutboundMessage::reply(&channel, &chat_id, res.content)
-                                        };
-                                        bus_t.publish_outbound(outbound).await;
-                                    }
-                                    Err(e) => {
-                                        error!("Error processing message: {}", e);
-                                        let error_msg = format_agent_error(&e);
-                                        bus_t
-                                            .publish_outbound(OutboundMessage::reply(
-                                                &channel, &chat_id, error_msg,
-                                            ))
-                                            .await;
-                                    }
-                                }
-                            });
+                            for msg in coalescer.push(msg, Instant::now()) {
+                                spawn_turn(msg, &bus, &agent, &state);
+                            }
                         }
                     }
                 }
@@ -190,145 +260,523 @@ impl AgentBridge {
     }
 }
 
-/// Result of command routing — either a direct reply or a prompt to pipe
-/// through the agent loop.
-enum CommandResult {
-    /// Send this text directly to the user.
-    Reply(String),
-    /// Rewrite the command into this prompt and process via `AgentLoop`.
-    AgentPassthrough(String),
+/// Sleep until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(d) => tokio::time::sleep_until(d.into()).await,
+        None => std::future::pending().await,
+    }
 }
 
-/// Handle slash commands. Returns `Some(CommandResult)` if the message was a
-/// recognised command, `None` if the message should pass to the agent as-is.
-async fn handle_command(
-    content: &str,
-    session_key: &str,
-    cron: &Arc<Mutex<CronService>>,
-    workspace: &Path,
-    start_time: std::time::Instant,
-    agent: &Arc<Mutex<AgentLoop>>,
-) -> Option<CommandResult> {
-    let trimmed = content.trim();
-    if !trimmed.starts_with('/') {
-        return None;
+/// The next tick of `interval`, or never without one.
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
     }
+}
+
+/// Handle one (possibly coalesced) inbound message in its own task.
+fn spawn_turn(
+    msg: InboundMessage,
+    bus: &Arc<MessageBus>,
+    agent: &Arc<Mutex<AgentLoop>>,
+    state: &Arc<BridgeState>,
+) {
+    // Clone the cheap Arcs to move into the spawned task.
+    let bus_t      = Arc::clone(bus);
+    let agent_t    = Arc::clone(agent);
+    let state_t    = Arc::clone(state);
+    let channel    = msg.channel.clone();
+    let chat_id    = msg.chat_id.clone();
+    let chat_key   = format!("{}:{}", channel, chat_id);
+    let content    = msg.content.clone();
+    let user_id    = msg.user_id.clone();
+    let sender     = msg.sender_name.clone().unwrap_or_else(|| msg.user_id.clone());
+    let is_system  = msg.is_system;
+    let reply_to   = msg.message_id.clone();
+    let span = info_span!(
+        "turn",
+        request_id = %new_request_id(),
+        channel = %channel,
+        chat_id = %chat_id
+    );
+
+    let turn = async move {
+        let scope = state_t.session_scopes.get(&channel).copied().unwrap_or_default();
+        // Per-user sessions follow linked accounts.
+        let member = if scope == SessionScope::User && !is_system {
+            ContactBook::new(&state_t.cx.workspace).member(&channel, &user_id)
+        } else {
+            user_id.clone()
+        };
+        let session_key = state_t.cx.session_for(&scope.session_key(&channel, &chat_id, &member)).await;
+
+        // Scheduled jobs may ask for a digest too.
+        if is_system && content.trim() == "/digest" {
+            let reply = cmd_digest(&chat_key, &agent_t, &state_t.group_log).await;
+            bus_t
+                .publish_outbound(OutboundMessage::reply(&channel, &chat_id, reply))
+                .await;
+            return;
+        }
+
+        // ── Command routing (non-system messages only) ──────
+        if !is_system {
+            let output = state_t
+                .commands
+                .dispatch(&state_t.cx, &content, &channel, &chat_id, &user_id, &session_key)
+                .await;
+            match output {
+                Some(CommandOutput::Reply(response)) => {
+                    bus_t
+                        .publish_outbound(
+                            OutboundMessage::reply(&channel, &chat_id, response).in_reply_to(reply_to),
+                        )
+                        .await;
+                    return;
+                }
+                Some(CommandOutput::Restart(response)) => {
+                    let response = if state_t.restarts { response } else { without_restart(&response) };
+                    bus_t
+                        .publish_outbound(
+                            OutboundMessage::reply(&channel, &chat_id, response).in_reply_to(reply_to),
+                        )
+                        .await;
+                    if state_t.restarts {
+                        crate::request_restart();
+                        state_t.cancel.cancel();
+                    }
+                    return;
+                }
+                Some(CommandOutput::Quit) => return,
+                Some(CommandOutput::Prompt(prompt)) => {
+                    // Rewrite the command into a natural language prompt
+                    // and fall through to agent processing below.
+                    let rewritten = state_t.replies.rewrites();
+                    let result =
+                        process_guarded(&prompt, &session_key, &msg, &agent_t, &bus_t, &state_t, rewritten).await;
+                    match result {
+                        Ok(res) => {
+                            let reply = finish_reply(&prompt, res.content, &session_key, &msg, &agent_t, &state_t).await;
+                            let outbound = if let Some(btns) = res.buttons {
+                                OutboundMessage::reply_with_buttons(&channel, &chat_id, reply, btns)
+                            } else {
+                                OutboundMessage::reply(&channel, &chat_id, reply)
+                            };
+                            bus_t.publish_outbound(outbound.in_reply_to(reply_to)).await;
+                        }
+                        Err(e) => {
+                            error!(error = ?e, "Error processing command passthrough: {}", e);
+                            let error_msg = errors::for_agent(&e).render();
+                            bus_t
+                                .publish_outbound(
+                                    OutboundMessage::reply(&channel, &chat_id, error_msg).in_reply_to(reply_to),
+                                )
+                                .await;
+                        }
+                    }
+                    return;
+                }
+                None => {} // Not a command, fall through to agent
+            }
+        }
 
-    let (cmd, args) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
-    let args = args.trim();
+        // ── Listen-only chats: record, don't reply ──────────
+        if !is_system && state_t.listen_only.contains(&chat_key) {
+            state_t.group_log.record(&chat_key, &sender, &content);
+            return;
+        }
 
-    match cmd {
-        "/help" | "/start" => Some(CommandResult::Reply(cmd_help())),
-        "/status" => Some(CommandResult::Reply(
-            cmd_status(cron, workspace, start_time).await,
-        )),
-        "/clear" | "/reset" | "/forget" => {
-            Some(CommandResult::Reply(cmd_clear(session_key, agent).await))
+        // ── "more": the next page of a long reply ──────────
+        if !is_system && state_t.replies.paginates() && postprocess::is_more(&content) {
+            let mut agent = agent_t.lock().await;
+            if let Some(rest) = agent.take_pending_reply(&session_key) {
+                let page = state_t.replies.page(rest);
+                agent.set_pending_reply(&session_key, page.rest);
+                drop(agent);
+                bus_t
+                    .publish_outbound(OutboundMessage::reply(&channel, &chat_id, page.text).in_reply_to(reply_to))
+                    .await;
+                return;
+            }
         }
-        // Crypto shortcuts — rewrite into agent prompts
-        "/portfolio" => Some(CommandResult::AgentPassthrough(
-            "Show my Solana wallet portfolio: SOL balance and all token balances.".into(),
-        )),
-        "/alpha" if !args.is_empty() => Some(CommandResult::AgentPassthrough(format!(
-            "Give me a full alpha summary for token {}",
-            args
-        ))),
-        "/buy" if !args.is_empty() => {
-            let parts: Vec<&str> = args.splitn(2, ' ').collect();
-            let mint = parts[0];
-            let amount = parts.get(1).unwrap_or(&"0.1");
-            Some(CommandResult::AgentPassthrough(format!(
-                "Buy {} SOL of token {}",
-                amount, mint
-            )))
+
+        // ── User hooks (hooks/pre_message, hooks/post_reply) ──
+        let (content, metadata) = if is_system {
+            (content, Default::default())
+        } else {
+            match state_t.hooks.pre_message(&msg).await {
+                PreMessage::Continue { content, metadata } => (content, metadata),
+                PreMessage::Reply(reply) => {
+                    bus_t
+                        .publish_outbound(OutboundMessage::reply(&channel, &chat_id, reply).in_reply_to(reply_to))
+                        .await;
+                    return;
+                }
+            }
+        };
+
+        // ── Alerts: unattended turns skip muted chats ───────
+        let alert_source = alert_source(&msg);
+        let alerts = state_t.alerts.as_ref().filter(|_| alert_source.is_some());
+        if alerts.is_some_and(|a| a.muted_until(&chat_key).is_some()) {
+            debug!(user_id = %user_id, "Chat is muted; skipping alert turn");
+            return;
+        }
+
+        // ── Agent processing ───────────────────────────────
+        let rewritten = state_t.replies.rewrites() || (!is_system && state_t.hooks.has_post_reply());
+        let result =
+            process_guarded(&content, &session_key, &msg, &agent_t, &bus_t, &state_t, rewritten).await;
+
+        match result {
+            Ok(res) => {
+                let reply = if is_system {
+                    res.content
+                } else {
+                    state_t.hooks.post_reply(&msg, &content, &metadata, res.content).await
+                };
+                let mut reply = finish_reply(&content, reply, &session_key, &msg, &agent_t, &state_t).await;
+                if let (Some(alerts), Some(source)) = (alerts, &alert_source) {
+                    match alerts.admit(&chat_key, source, &reply, None) {
+                        Some(admitted) => reply = admitted.decorate(reply),
+                        None => {
+                            debug!(source = %source, "Holding back repeated or muted alert reply");
+                            return;
+                        }
+                    }
+                }
+                let outbound = if let Some(btns) = res.buttons {
+                    OutboundMessage::reply_with_buttons(&channel, &chat_id, reply, btns)
+                } else {
+                    OutboundMessage::reply(&channel, &chat_id, reply)
+                };
+                bus_t.publish_outbound(outbound.in_reply_to(reply_to)).await;
+            }
+            Err(e) => {
+                error!(error = ?e, "Error processing message: {}", e);
+                let error_msg = errors::for_agent(&e).render();
+                bus_t
+                    .publish_outbound(
+                        OutboundMessage::reply(&channel, &chat_id, error_msg).in_reply_to(reply_to),
+                    )
+                    .await;
+            }
         }
-        _ => None,
+    };
+    tokio::spawn(turn.instrument(span));
+}
+
+/// What an unattended turn's reply counts as an alert of: the job's
+/// message for scheduled agent jobs, the hook for webhook events. `None`
+/// for everything else, which is never held back.
+fn alert_source(msg: &InboundMessage) -> Option<String> {
+    if !msg.is_system {
+        return None;
+    }
+    if msg.user_id == "cron" {
+        Some(format!("cron:{}", msg.content))
+    } else {
+        msg.user_id.starts_with("webhook:").then(|| msg.user_id.clone())
     }
 }
 
-fn cmd_help() -> String {
-    "🦀 **CrabbyBot Commands**\n\n\
-     🛠️ **General:**\n\
-     `/help` — Show this help message\n\
-     `/status` — Bot status (providers, model, uptime)\n\
-     `/clear` (or `/reset`, `/forget`) — Clear conversation history\n\n\
-     💰 **Crypto Shortcuts:**\n\
-     `/portfolio` — Your wallet’s SOL + token balances\n\
-     `/alpha <mint>` — Full safety + sentiment report\n\
-     `/buy <mint> [amount]` — Buy token (default: 0.1 SOL)\n\n\
-     ⏰ **Scheduling:**\n\
-     Just ask! e.g. *\"Remind me to check SOL price every hour\"*\n\n\
-     Any other message is processed by the AI assistant."
-        .to_string()
+/// How long a turn cancelled by the watchdog gets to wind down before it
+/// is dropped.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// Run one agent turn under the watchdog. A turn still running after
+/// `state.turn_timeout` is cancelled, which stops its in-flight LLM request
+/// and tool calls (shell commands are killed) and rolls its session back
+/// to the last save; one that ignores that for [`CANCEL_GRACE`] is dropped
+/// and cleaned up with [`AgentLoop::abort_turn`]. Timeouts are recorded in
+/// the chat's activity log and `traces/watchdog.jsonl`. The time spent
+/// waiting for the scheduler and the agent lock doesn't count. A reply the
+/// bridge will `rewrite` isn't streamed, since its drafts would show text
+/// that is never sent.
+async fn process_guarded(
+    content: &str,
+    session_key: &str,
+    msg: &InboundMessage,
+    agent: &Arc<Mutex<AgentLoop>>,
+    bus: &Arc<MessageBus>,
+    state: &BridgeState,
+    rewritten: bool,
+) -> Result<AgentResult, AgentError> {
+    let user_id = msg.user_id.as_str();
+    // Queue for the LLM before taking the agent, so a cron turn waiting
+    // behind users doesn't keep them from the agent meanwhile.
+    if let Some(ref scheduler) = state.scheduler {
+        scheduler.acquire(Lane::of(user_id)).await;
+    }
+    let mut lock = agent.lock().await;
+    if state.scheduler.is_some() {
+        lock.admit();
+    }
+    if rewritten {
+        lock.hold_partials();
+    }
+    if state.turn_timeout.is_zero() {
+        return lock
+            .process_in(content, session_key, &msg.channel, &msg.chat_id, user_id, Some(bus))
+            .await;
+    }
+    let cancel = CancellationToken::new();
+    lock.cancel_with(cancel.clone());
+    let wound_down = {
+        let turn = lock.process_in(content, session_key, &msg.channel, &msg.chat_id, user_id, Some(bus));
+        tokio::pin!(turn);
+        if let Ok(result) = tokio::time::timeout(state.turn_timeout, &mut turn).await {
+            return result;
+        }
+        cancel.cancel();
+        tokio::time::timeout(CANCEL_GRACE, turn).await.is_ok()
+    };
+    let err = AgentError::TimedOut(state.turn_timeout);
+    warn!(session = session_key, limit = ?state.turn_timeout, "Watchdog cancelled a stuck turn");
+    if !wound_down {
+        warn!(session = session_key, "Stuck turn ignored its cancellation and was dropped");
+        lock.abort_turn(session_key, &err);
+    }
+    let workspace = lock.workspace(&msg.channel, &msg.chat_id);
+    record_watchdog_trace(workspace, session_key, user_id, content, state.turn_timeout);
+    Err(err)
 }
 
-async fn cmd_status(
-    cron: &Arc<Mutex<CronService>>,
-    workspace: &Path,
-    start_time: std::time::Instant,
+/// Apply the reply post-processors and hold back what doesn't fit on the
+/// first page.
+async fn finish_reply(
+    prompt: &str,
+    reply: String,
+    session_key: &str,
+    msg: &InboundMessage,
+    agent: &Arc<Mutex<AgentLoop>>,
+    state: &BridgeState,
 ) -> String {
-    let uptime = start_time.elapsed();
-    let hours = uptime.as_secs() / 3600;
-    let mins = (uptime.as_secs() % 3600) / 60;
-    let secs = uptime.as_secs() % 60;
-
-    let cron = cron.lock().await;
-    let cron_status = cron.status();
-
-    format!(
-        "🤖 **CrabbyBot Status**\n\n\
-         ⏱ Uptime: {}h {}m {}s\n\
-         📋 Cron: {}\n\
-         📂 Workspace: `{}`",
-        hours,
-        mins,
-        secs,
-        cron_status,
-        workspace.display(),
-    )
+    let locale = if state.replies.localizes() {
+        agent.lock().await.chat_locale(&msg.channel, &msg.chat_id)
+    } else {
+        ChatLocale::default()
+    };
+    let page = state.replies.process(prompt, reply, &locale);
+    if state.replies.paginates() {
+        agent.lock().await.set_pending_reply(session_key, page.rest);
+    }
+    page.text
 }
 
-async fn cmd_clear(session_key: &str, agent: &Arc<Mutex<AgentLoop>>) -> String {
-    let mut lock = agent.lock().await;
-    if lock.clear_session(session_key) {
-        "✅ Conversation history cleared. I have forgotten our past messages.".to_string()
+/// Append a cancelled turn to `traces/watchdog.jsonl`.
+fn record_watchdog_trace(workspace: &Path, session_key: &str, user_id: &str, content: &str, limit: Duration) {
+    let entry = serde_json::json!({
+        "time": chrono::Utc::now().to_rfc3339(),
+        "session": session_key,
+        "user_id": user_id,
+        "limit_secs": limit.as_secs(),
+        "content": content.chars().take(200).collect::<String>(),
+    });
+    let dir = workspace.join("traces");
+    let result = std::fs::create_dir_all(&dir).and_then(|_| {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("watchdog.jsonl"))?;
+        writeln!(file, "{}", entry)
+    });
+    if let Err(e) = result {
+        debug!("Failed to write watchdog trace: {}", e);
+    }
+}
+
+/// A restart notice for a chat that keeps running: the "Restarting…" line
+/// is replaced by a hint to restart by hand.
+fn without_restart(text: &str) -> String {
+    let kept: Vec<&str> = text.lines().filter(|l| !l.contains("Restarting")).collect();
+    let hint = "Restart the chat to apply the change.";
+    if kept.is_empty() {
+        hint.to_string()
     } else {
-        "ℹ️ No conversation history to clear.".to_string()
+        format!("{}\n{}", kept.join("\n"), hint)
     }
 }
 
-// ── Error formatting ──────────────────────────────────────────────────────────
-
-/// Convert an [`AgentError`] into a user-facing Markdown string.
-fn format_agent_error(e: &AgentError) -> String {
-    match e {
-        AgentError::MaxIterationsExceeded(n) => {
-            format!(
-                "⚠️ **Max iterations reached** ({n})\n\n\
-                 The agent completed {n} tool-call rounds without a final answer. \
-                 Try a simpler request or increase `max_tool_iterations` in your config."
-            )
+/// The standard commands plus those that only make sense in bot mode.
+fn bot_commands(group_log: &GroupLog) -> CommandRouter {
+    let mut router = CommandRouter::standard();
+    let log = group_log.clone();
+    router
+        .register(
+            CommandSpec::new("digest", "Summarize what was said in a listen-only group"),
+            move |cx, inv| {
+                let log = log.clone();
+                Box::pin(async move { CommandOutput::Reply(cmd_digest(&inv.chat_key(), &cx.agent, &log).await) })
+            },
+        )
+        .register(
+            CommandSpec::new("restart", "Restart the bot").bare(),
+            |_, _| {
+                Box::pin(async {
+                    CommandOutput::Restart("🔄 Restarting CrabbyBot… please wait a few seconds.".into())
+                })
+            },
+        );
+    router
+}
+
+/// Summarize the messages recorded in a listen-only chat since the last
+/// digest. The log is only cleared once the summary succeeded.
+async fn cmd_digest(chat_key: &str, agent: &Arc<Mutex<AgentLoop>>, log: &GroupLog) -> String {
+    let entries = log.unread(chat_key);
+    if entries.is_empty() {
+        return "ℹ️ Nothing new since the last digest.".into();
+    }
+    let transcript = digest::transcript(&entries);
+    let result = agent
+        .lock()
+        .await
+        .summarize(digest::DIGEST_PROMPT, &transcript)
+        .await;
+    match result {
+        Ok(summary) => {
+            log.mark_read(chat_key, entries.len());
+            format!("📰 **Digest** ({} messages)\n\n{}", entries.len(), summary.trim())
         }
-        AgentError::Provider(inner) => {
-            let msg = inner.to_string();
-            if msg.contains("429")
-                || msg.contains("quota")
-                || msg.contains("exhausted")
-                || msg.contains("rate_limit")
-            {
-                "⚠️ **LLM Quota / Rate-limit**\n\n\
-                 All configured providers have hit their limits.\n\n\
-                 **Options:**\n\
-                 1. Wait a few minutes for rate limits to reset.\n\
-                 2. Add a **Groq** API key for a generous free tier.\n\
-                 3. Check your billing details."
-                    .into()
-            } else {
-                format!("⚠️ **Provider error**: {}", inner)
-            }
+        Err(e) => {
+            error!(chat = chat_key, error = ?e, "Digest failed: {}", e);
+            errors::for_agent(&e).render()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentConfig;
+    use crate::provider::types::*;
+    use crate::provider::{LlmProvider, ProviderError};
+    use crate::tools::{IntentCategory, Tool, ToolContext, ToolRegistry};
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Calls a tool, then hangs on the follow-up call; answers every call
+    /// after that with the history it was sent.
+    struct HangingProvider {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl LlmProvider for HangingProvider {
+        fn default_model(&self) -> &str {
+            "fake-model"
+        }
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: &[ToolDefinition],
+            _model: Option<&str>,
+            _max_tokens: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, ProviderError> {
+            let (content, tool_calls) = match self.calls.fetch_add(1, Ordering::SeqCst) {
+                0 => {
+                    let call = ToolCallRequest {
+                        id: "call_1".into(),
+                        name: "noop".into(),
+                        arguments: serde_json::Map::new(),
+                        parse_error: None,
+                    };
+                    (None, vec![call])
+                }
+                1 => std::future::pending().await,
+                _ => {
+                    let history: Vec<String> = messages
+                        .iter()
+                        .filter(|m| m.role != "system")
+                        .map(|m| format!("{}:{}", m.role, m.content_as_str().unwrap_or("")))
+                        .collect();
+                    (Some(history.join("|")), vec![])
+                }
+            };
+            Ok(LlmResponse {
+                finish_reason: if tool_calls.is_empty() { "stop" } else { "tool_calls" }.into(),
+                content,
+                tool_calls,
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    struct NoopTool;
+
+    #[async_trait]
+    impl Tool for NoopTool {
+        fn name(&self) -> &str {
+            "noop"
+        }
+        fn description(&self) -> &str {
+            "noop"
+        }
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+            "done".into()
         }
-        AgentError::Session(inner) => {
-            format!("⚠️ **Session error**: {}", inner)
+    }
+
+    async fn next_reply(outbound_rx: &mut mpsc::Receiver<OutboundMessage>) -> String {
+        loop {
+            if let Some(OutboundMessage::Reply { content, .. }) = outbound_rx.recv().await {
+                return content;
+            }
         }
     }
+
+    #[tokio::test]
+    async fn test_stuck_turn_is_rolled_back() {
+        let workspace = std::env::temp_dir().join(format!("CrabbyBot_test_bridge_watchdog_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&workspace);
+        // The chat's own workspace keeps its session and logs out of `~`.
+        let chat_workspace = workspace.join("chat");
+        std::fs::create_dir_all(&chat_workspace).unwrap();
+
+        let provider: Box<dyn LlmProvider> = Box::new(HangingProvider { calls: AtomicU32::new(0) });
+        let tools = ToolRegistry::new();
+        tools.register(Box::new(NoopTool), IntentCategory::General);
+        let config = AgentConfig {
+            workspace: workspace.clone(),
+            chat_workspaces: [("cli:direct".to_string(), chat_workspace.clone())].into(),
+            ..AgentConfig::default()
+        };
+        let agent = AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::new(tools), config);
+        let (bus, receivers) = MessageBus::new(64);
+        let (bus, mut outbound_rx) = (Arc::new(bus), receivers.outbound_rx);
+        let cron = Arc::new(Mutex::new(CronService::new(&workspace)));
+        let cancel = CancellationToken::new();
+        let bridge = AgentBridge::new(Arc::clone(&bus), agent, cancel.clone(), cron, workspace.clone())
+            .turn_timeout(Duration::from_millis(200));
+        let inbound = bus.inbound_sender();
+        let run = tokio::spawn(bridge.run(receivers.inbound_rx));
+
+        inbound.send(InboundMessage::cli("first")).await.unwrap();
+        let notice = next_reply(&mut outbound_rx).await;
+        assert!(notice.contains("Request timed out"), "{}", notice);
+
+        inbound.send(InboundMessage::cli("second")).await.unwrap();
+        let reply = next_reply(&mut outbound_rx).await;
+        assert!(reply.ends_with("user:second"), "{}", reply);
+        assert!(!reply.contains("first"), "stuck turn left history behind: {}", reply);
+
+        let log = std::fs::read_to_string(chat_workspace.join("logs").join("cli_direct.md")).unwrap();
+        assert!(log.contains("Turn cancelled"), "{}", log);
+        assert!(chat_workspace.join("traces").join("watchdog.jsonl").exists());
+
+        cancel.cancel();
+        let _ = run.await;
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
 }
@@ -0,0 +1,218 @@
+//! CLI transport: the interactive terminal chat of `crabbybot chat`.
+//!
+//! Lines typed on stdin become inbound messages on the `cli` channel, so
+//! they go through the same [`AgentBridge`](crate::gateway::AgentBridge) as
+//! every other chat: commands, hooks, the watchdog. Progress updates are
+//! printed inline while the agent works, and replies as they are written;
+//! the next prompt appears once the reply has been printed.
+//!
+//! `/quit` (or `/exit`, `/q`) and end of input close the chat.
+
+use crate::bus::events::{Button, InboundMessage, OutboundMessage};
+use crate::bus::MessageBus;
+use anyhow::Result;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+const PROMPT: &str = "  \x1b[36m>\x1b[0m ";
+
+pub struct CliTransport {
+    bus: Arc<MessageBus>,
+    chat_id: String,
+    cancel: CancellationToken,
+}
+
+impl CliTransport {
+    /// A chat whose messages are sent as `cli:<chat_id>`.
+    pub fn new(bus: Arc<MessageBus>, chat_id: impl Into<String>, cancel: CancellationToken) -> Self {
+        Self {
+            bus,
+            chat_id: chat_id.into(),
+            cancel,
+        }
+    }
+
+    /// Read stdin until `/quit`, end of input, or cancellation.
+    pub async fn run(self) -> Result<()> {
+        // Signalled once per printed reply, so the prompt doesn't interleave
+        // with progress lines.
+        let (replied_tx, mut replied_rx) = mpsc::unbounded_channel();
+        let streamed = Arc::new(Mutex::new(String::new()));
+        self.bus
+            .subscribe_outbound("cli", move |msg| {
+                let replied_tx = replied_tx.clone();
+                let streamed = Arc::clone(&streamed);
+                async move {
+                    let rendered = render(&msg, &mut streamed.lock().unwrap_or_else(|e| e.into_inner()));
+                    let Some((text, is_reply)) = rendered else { return };
+                    let mut stdout = std::io::stdout();
+                    let _ = stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush());
+                    if is_reply {
+                        let _ = replied_tx.send(());
+                    }
+                }
+            })
+            .await;
+
+        let inbound = self.bus.inbound_sender();
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            print_flush(PROMPT);
+            let line = tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                line = lines.next_line() => line?,
+            };
+            let Some(line) = line else { break };
+            let input = line.trim();
+            if input.is_empty() {
+                continue;
+            }
+            if is_quit(input) {
+                print_flush("  Goodbye! 👋\n");
+                break;
+            }
+
+            print_flush("\n");
+            inbound
+                .send(InboundMessage {
+                    channel: "cli".into(),
+                    chat_id: self.chat_id.clone(),
+                    user_id: "user".into(),
+                    sender_name: None,
+                    content: input.to_string(),
+                    message_id: None,
+                    media: Vec::new(),
+                    is_system: false,
+                })
+                .await?;
+            tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                _ = replied_rx.recv() => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_flush(text: &str) {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush());
+}
+
+fn is_quit(input: &str) -> bool {
+    matches!(input, "/quit" | "/exit" | "/q")
+}
+
+/// How an outbound message is printed, and whether it is the final reply.
+///
+/// `streamed` is the partial reply printed so far. Each `Partial` prints
+/// only what is new; a reply that continues the streamed text only prints
+/// the rest of it.
+fn render(msg: &OutboundMessage, streamed: &mut String) -> Option<(String, bool)> {
+    // Whatever follows a streamed reply starts on a fresh line.
+    let lead = if streamed.is_empty() { "" } else { "\n\n" };
+    match msg {
+        OutboundMessage::Reply { content, buttons, .. } => {
+            let mut text = String::new();
+            match content.strip_prefix(streamed.as_str()).filter(|_| !streamed.is_empty()) {
+                Some(rest) => {
+                    text.push_str(&reply_text(rest));
+                    text.push('\n');
+                }
+                None => {
+                    text.push_str(lead);
+                    for line in content.lines() {
+                        text.push_str(&format!("  \x1b[32m{}\x1b[0m\n", line));
+                    }
+                }
+            }
+            streamed.clear();
+            for button in buttons.iter().flatten() {
+                text.push_str(&format!("  [{}]{}\n", button.text, button_hint(button)));
+            }
+            text.push('\n');
+            Some((text, true))
+        }
+        OutboundMessage::Partial { content, .. } => {
+            let text = match content.strip_prefix(streamed.as_str()).filter(|_| !streamed.is_empty()) {
+                Some(rest) => reply_text(rest),
+                // A new model call starts over.
+                None => format!("{}  {}", lead, reply_text(content)),
+            };
+            *streamed = content.clone();
+            Some((text, false))
+        }
+        OutboundMessage::Progress { content, .. } => {
+            streamed.clear();
+            Some((format!("{}  \x1b[2m⋯ {}\x1b[0m\n", lead, content), false))
+        }
+        OutboundMessage::Attachment { path, caption, .. } => {
+            let caption = if caption.is_empty() { String::new() } else { format!("{} ", caption) };
+            Some((format!("  📎 {}({})\n", caption, path.display()), false))
+        }
+        OutboundMessage::Typing { .. } => None,
+    }
+}
+
+/// Reply text in the reply colour, indented like whole replies.
+fn reply_text(text: &str) -> String {
+    format!("\x1b[32m{}\x1b[0m", text.replace('\n', "\n  "))
+}
+
+/// What to type (or open) to press a button, since a terminal can't click.
+fn button_hint(button: &Button) -> String {
+    match (&button.url, &button.data) {
+        (Some(url), _) => format!(" {}", url),
+        (None, Some(data)) => format!(" type: {}", data),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_inline_and_reply_releases_the_prompt() {
+        let streamed = &mut String::new();
+        let (progress, done) = render(&OutboundMessage::progress("cli", "default", "Running tool: shell…"), streamed).unwrap();
+        assert!(progress.contains("Running tool: shell…"));
+        assert!(!done);
+
+        let (reply, done) = render(&OutboundMessage::reply("cli", "default", "one\ntwo"), streamed).unwrap();
+        assert_eq!(reply.matches("\x1b[32m").count(), 2);
+        assert!(done);
+
+        assert!(render(&OutboundMessage::typing("cli", "default"), streamed).is_none());
+    }
+
+    #[test]
+    fn test_partial_replies_print_only_what_is_new() {
+        let streamed = &mut String::new();
+        let partial = |text: &str| OutboundMessage::partial("cli", "default", text);
+        assert_eq!(render(&partial("Let me"), streamed).unwrap().0, "  \x1b[32mLet me\x1b[0m");
+        assert_eq!(render(&partial("Let me check."), streamed).unwrap().0, "\x1b[32m check.\x1b[0m");
+        let (progress, _) = render(&OutboundMessage::progress("cli", "default", "Running tool: web"), streamed).unwrap();
+        assert!(progress.starts_with("\n\n  "));
+
+        render(&partial("SOL is up\n3%"), streamed);
+        let (reply, done) = render(&OutboundMessage::reply("cli", "default", "SOL is up\n3%\n\n— CrabbyBot"), streamed).unwrap();
+        assert_eq!(reply, "\x1b[32m\n  \n  — CrabbyBot\x1b[0m\n\n");
+        assert!(done && streamed.is_empty());
+    }
+
+    #[test]
+    fn test_buttons_show_what_to_type() {
+        let buttons = vec![Button {
+            text: "Refresh".into(),
+            data: Some("/alerts".into()),
+            url: None,
+        }];
+        let msg = OutboundMessage::reply_with_buttons("cli", "default", "Alerts", buttons);
+        assert!(render(&msg, &mut String::new()).unwrap().0.contains("[Refresh] type: /alerts"));
+        assert!(is_quit("/q") && !is_quit("/quiet"));
+    }
+}
@@ -1,11 +1,19 @@
-use crate::bus::events::{InboundMessage, OutboundMessage};
+use crate::bus::events::{DeliveryError, DeliveryResult, InboundMessage, OutboundMessage};
 use crate::bus::MessageBus;
+use crate::commands::MenuEntry;
+use crate::gateway::acl::{Acl, Origin};
+use crate::gateway::uploads::{self, UploadStore};
 use crate::gateway::utils::chunk_message;
 use anyhow::Result;
 use serenity::async_trait;
-use serenity::model::channel::Message;
+use serenity::builder::{
+    CreateAttachment, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage,
+};
+use serenity::model::application::{Command, CommandOptionType, Interaction};
+use serenity::model::channel::{Message, MessageReference};
 use serenity::model::gateway::Ready;
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
 use serenity::prelude::*;
 use std::sync::Arc;
 use tracing::{error, info, warn};
@@ -15,12 +23,73 @@ const DISCORD_MAX_LEN: usize = 2000;
 
 struct Handler {
     bus: Arc<MessageBus>,
-    allow_from: Vec<String>,
+    acl: Acl,
+    uploads: Option<UploadStore>,
+    commands: Vec<MenuEntry>,
+}
+
+impl Handler {
+    /// Whether `allowFrom` admits this user in this channel; rejections
+    /// are logged.
+    fn admits(&self, user_id: &str, chat_id: &str, guild_id: Option<GuildId>, roles: &[RoleId]) -> bool {
+        let guild_id = guild_id.map(|g| g.to_string());
+        let roles: Vec<String> = roles.iter().map(|r| r.to_string()).collect();
+        let origin = Origin {
+            user_id,
+            chat_id,
+            guild_id: guild_id.as_deref(),
+            roles: &roles,
+        };
+        let allowed = self.acl.allows(&origin);
+        if !allowed {
+            warn!(
+                user_id = user_id,
+                channel_id = chat_id,
+                "Rejected Discord message not matching allowFrom"
+            );
+        }
+        allowed
+    }
+
+    /// Save the message's attachments and return a note for each. Failures
+    /// are reported in the channel.
+    async fn save_attachments(&self, ctx: &Context, msg: &Message) -> (Vec<String>, Vec<String>) {
+        let (mut notes, mut media) = (Vec::new(), Vec::new());
+        let Some(ref store) = self.uploads else {
+            return (notes, media);
+        };
+        let chat_id = msg.channel_id.to_string();
+        for attachment in &msg.attachments {
+            let saved = if store.accepts(attachment.size.into()) {
+                match attachment.download().await {
+                    Ok(bytes) => store
+                        .save("discord", &chat_id, &attachment.filename, &bytes)
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            } else {
+                Err(format!("file is larger than the {} MB upload limit", store.max_mb()))
+            };
+            match saved {
+                Ok(path) => {
+                    info!(chat_id, path = %path.display(), "Saved upload");
+                    notes.push(uploads::upload_note(&attachment.filename, &path, attachment.size.into()));
+                    media.push(path.to_string_lossy().into_owned());
+                }
+                Err(e) => {
+                    warn!(chat_id, file = attachment.filename, "Upload rejected: {}", e);
+                    let reply = format!("❌ Couldn't save {}: {}", attachment.filename, e);
+                    let _ = msg.channel_id.say(&ctx.http, reply).await;
+                }
+            }
+        }
+        (notes, media)
+    }
 }
 
 #[async_trait]
 impl EventHandler for Handler {
-    async fn message(&self, _ctx: Context, msg: Message) {
+    async fn message(&self, ctx: Context, msg: Message) {
         if msg.author.bot {
             return;
         }
@@ -28,21 +97,24 @@ impl EventHandler for Handler {
         let user_id = msg.author.id.to_string();
 
         // Enforce allowFrom ACL
-        if !self.allow_from.is_empty() && !self.allow_from.contains(&user_id) {
-            warn!(
-                user_id = user_id,
-                channel_id = msg.channel_id.to_string(),
-                "Rejected Discord message from user not in allowFrom list"
-            );
+        let chat_id = msg.channel_id.to_string();
+        let roles = msg.member.as_ref().map(|m| m.roles.as_slice()).unwrap_or_default();
+        if !self.admits(&user_id, &chat_id, msg.guild_id, roles) {
             return;
         }
 
+        let (notes, media) = self.save_attachments(&ctx, &msg).await;
+        if msg.content.trim().is_empty() && notes.is_empty() {
+            return;
+        }
         let inbound = InboundMessage {
             channel: "discord".to_owned(),
             chat_id: msg.channel_id.to_string(),
             user_id,
-            content: msg.content.clone(),
-            media: Vec::new(),
+            sender_name: Some(msg.author.name.clone()),
+            content: uploads::with_notes(&msg.content, &notes),
+            message_id: Some(msg.id.to_string()),
+            media,
             is_system: false,
         };
 
@@ -51,26 +123,141 @@ impl EventHandler for Handler {
         }
     }
 
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         info!("Discord transport ready: {}", ready.user.name);
+        if self.commands.is_empty() {
+            return;
+        }
+        let commands = self.commands.iter().map(application_command).collect();
+        match Command::set_global_commands(&ctx.http, commands).await {
+            Ok(registered) => info!(count = registered.len(), "Registered Discord application commands"),
+            Err(e) => warn!("Failed to register Discord application commands: {}", e),
+        }
+    }
+
+    /// Slash commands picked from the menu arrive as interactions; they are
+    /// acknowledged and passed on as the equivalent `/name args` message.
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Command(command) = interaction else {
+            return;
+        };
+        let user_id = command.user.id.to_string();
+        let chat_id = command.channel_id.to_string();
+        let roles = command.member.as_ref().map(|m| m.roles.as_slice()).unwrap_or_default();
+        if !self.admits(&user_id, &chat_id, command.guild_id, roles) {
+            return;
+        }
+        let args = command
+            .data
+            .options
+            .iter()
+            .find(|o| o.name == "args")
+            .and_then(|o| o.value.as_str())
+            .unwrap_or_default();
+        let content = format!("/{} {}", command.data.name, args).trim_end().to_string();
+
+        let ack = CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(format!("`{}`", content)));
+        if let Err(e) = command.create_response(&ctx.http, ack).await {
+            warn!("Failed to acknowledge Discord command: {}", e);
+        }
+        let inbound = InboundMessage {
+            channel: "discord".to_owned(),
+            chat_id,
+            user_id,
+            sender_name: Some(command.user.name.clone()),
+            content,
+            message_id: None,
+            media: Vec::new(),
+            is_system: false,
+        };
+        if let Err(e) = self.bus.inbound_sender().send(inbound).await {
+            error!("Failed to send inbound message to bus: {}", e);
+        }
+    }
+}
+
+/// A menu entry as a Discord application command; arguments go in one
+/// free-text `args` option.
+fn application_command(entry: &MenuEntry) -> CreateCommand {
+    let command = CreateCommand::new(entry.name).description(&entry.description);
+    if !entry.takes_args {
+        return command;
     }
+    command.add_option(
+        CreateCommandOption::new(CommandOptionType::String, "args", "Arguments").required(entry.requires_args),
+    )
+}
+
+/// Send `content` to channel `chat_id` in chunks, leaving out the first
+/// `skip` ones an earlier attempt delivered. The first chunk references
+/// `reply_to`, if given, so Discord shows it as a reply.
+async fn send_text(
+    http: &serenity::http::Http,
+    chat_id: &str,
+    content: &str,
+    reply_to: Option<&str>,
+    skip: usize,
+) -> DeliveryResult {
+    let Ok(channel_id) = chat_id.parse::<u64>() else {
+        return Err(format!("invalid channel id '{}'", chat_id).into());
+    };
+    let channel_id = ChannelId::new(channel_id);
+    let mut reference = reply_to
+        .and_then(|m| m.parse::<u64>().ok())
+        .map(|m| MessageReference::from((channel_id, MessageId::new(m))).fail_if_not_exists(false));
+    if skip > 0 {
+        reference = None;
+    }
+    let mut delivered = None;
+    for (i, chunk) in chunk_message(content, DISCORD_MAX_LEN).into_iter().enumerate().skip(skip) {
+        let mut message = CreateMessage::new().content(chunk);
+        if let Some(reference) = reference.take() {
+            message = message.reference_message(reference);
+        }
+        match channel_id.send_message(http, message).await {
+            Ok(sent) => delivered = Some(sent.id.to_string()),
+            Err(e) => {
+                error!("Failed to send Discord message: {}", e);
+                return Err(DeliveryError::after(i, e.to_string()));
+            }
+        }
+    }
+    Ok(delivered)
 }
 
 pub struct DiscordTransport {
     token: String,
     bus: Arc<MessageBus>,
-    allow_from: Vec<String>,
+    acl: Acl,
+    uploads: Option<UploadStore>,
+    commands: Vec<MenuEntry>,
 }
 
 impl DiscordTransport {
-    pub fn new(token: String, bus: Arc<MessageBus>, allow_from: Vec<String>) -> Self {
+    pub fn new(token: String, bus: Arc<MessageBus>, acl: Acl) -> Self {
         Self {
             token,
             bus,
-            allow_from,
+            acl,
+            uploads: None,
+            commands: Vec::new(),
         }
     }
 
+    /// Register these commands as application commands once connected,
+    /// so they autocomplete in the slash menu.
+    pub fn with_commands(mut self, commands: Vec<MenuEntry>) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Save message attachments into `store`. Without a store they are
+    /// ignored.
+    pub fn with_uploads(mut self, store: UploadStore) -> Self {
+        self.uploads = Some(store);
+        self
+    }
+
     pub async fn run(self) -> Result<()> {
         let mut client = Client::builder(
             &self.token,
@@ -80,7 +267,9 @@ impl DiscordTransport {
         )
         .event_handler(Handler {
             bus: Arc::clone(&self.bus),
-            allow_from: self.allow_from,
+            acl: self.acl,
+            uploads: self.uploads,
+            commands: self.commands,
         })
         .await?;
 
@@ -88,29 +277,43 @@ impl DiscordTransport {
         {
             let http = Arc::clone(&client.http);
             self.bus
-                .subscribe_outbound("discord", move |msg| {
+                .subscribe_outbound_tracked("discord", move |msg| {
                     let http = Arc::clone(&http);
                     async move {
                         match msg {
                             OutboundMessage::Reply {
-                                chat_id, content, ..
+                                chat_id,
+                                content,
+                                reply_to_message_id,
+                                sent_chunks,
+                                ..
+                            } => {
+                                let reply_to = reply_to_message_id.as_deref();
+                                send_text(&http, &chat_id, &content, reply_to, sent_chunks).await
                             }
-                            | OutboundMessage::Progress {
+                            OutboundMessage::Progress {
                                 chat_id, content, ..
+                            } => send_text(&http, &chat_id, &content, None, 0).await,
+                            // Discord doesn't expose a simple typing indicator via this API path
+                            OutboundMessage::Typing { .. } => Ok(None),
+                            // Replies are only sent once complete.
+                            OutboundMessage::Partial { .. } => Ok(None),
+                            OutboundMessage::Attachment {
+                                chat_id, path, caption, ..
                             } => {
-                                if let Ok(channel_id) = chat_id.parse::<u64>() {
-                                    let chunks = chunk_message(&content, DISCORD_MAX_LEN);
-                                    for chunk in chunks {
-                                        if let Err(e) =
-                                            ChannelId::new(channel_id).say(&http, chunk).await
-                                        {
-                                            error!("Failed to send Discord message: {}", e);
-                                        }
+                                let Ok(channel_id) = chat_id.parse::<u64>() else {
+                                    return Err(format!("invalid channel id '{}'", chat_id).into());
+                                };
+                                let file = CreateAttachment::path(&path).await.map_err(|e| e.to_string())?;
+                                let message = CreateMessage::new().content(caption);
+                                match ChannelId::new(channel_id).send_files(&http, [file], message).await {
+                                    Ok(sent) => Ok(Some(sent.id.to_string())),
+                                    Err(e) => {
+                                        error!("Failed to send Discord attachment: {}", e);
+                                        Err(e.to_string().into())
                                     }
                                 }
                             }
-                            // Discord doesn't expose a simple typing indicator via this API path
-                            OutboundMessage::Typing { .. } => {}
                         }
                     }
                 })
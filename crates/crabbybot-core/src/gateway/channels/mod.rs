@@ -1,4 +1,11 @@
+pub mod cli;
 #[cfg(feature = "discord")]
 pub mod discord;
+#[cfg(feature = "attach")]
+pub mod remote;
 #[cfg(feature = "telegram")]
 pub mod telegram;
+#[cfg(feature = "webchat")]
+pub mod webchat;
+#[cfg(feature = "websocket")]
+pub mod websocket;
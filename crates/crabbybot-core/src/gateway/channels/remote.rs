@@ -0,0 +1,263 @@
+//! Remote transport: the CLI chat of `crabbybot attach`, talking to a bot
+//! that runs elsewhere.
+//!
+//! Instead of an agent of its own, the chat is relayed over the running
+//! bot's WebChat WebSocket (`/ws?token=…&session=…`, see
+//! [`webchat`](super::webchat) for the protocol). Lines typed into the
+//! [`CliTransport`](super::cli::CliTransport) are sent as `message` frames,
+//! and the frames that come back are published as `cli` outbound messages,
+//! so progress, streamed replies and buttons print exactly as in a local
+//! chat. Sessions, memory and tools are the remote bot's: the conversation
+//! is its session `webchat:<session>`.
+
+use crate::bus::events::{Button, InboundMessage, OutboundMessage};
+use crate::bus::MessageBus;
+use anyhow::{bail, Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// A frame sent by the WebChat endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerFrame {
+    Hello {
+        chat_id: String,
+    },
+    Typing,
+    Progress {
+        content: String,
+    },
+    Partial {
+        content: String,
+    },
+    Reply {
+        content: String,
+        #[serde(default)]
+        buttons: Vec<ButtonFrame>,
+    },
+    Attachment {
+        name: String,
+        #[serde(default)]
+        caption: String,
+        image: Option<String>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+struct ButtonFrame {
+    text: String,
+    data: Option<String>,
+    url: Option<String>,
+}
+
+/// The WebSocket URL for `base` (`ws://`, `wss://`, `http://` or `https://`,
+/// with or without `/ws`). A bare host gets `wss://`, since the token travels
+/// in the URL, except on loopback where there is no TLS to talk to.
+pub fn socket_url(base: &str, token: &str, session: &str) -> Result<String> {
+    let base = base.trim().trim_end_matches('/');
+    let base = base.strip_suffix("/ws").unwrap_or(base);
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else if base.starts_with("ws://") || base.starts_with("wss://") {
+        base.to_string()
+    } else if is_loopback(base) {
+        format!("ws://{}", base)
+    } else {
+        format!("wss://{}", base)
+    };
+    let mut url = reqwest::Url::parse(&format!("{}/ws", base)).with_context(|| format!("invalid bot address '{}'", base))?;
+    url.query_pairs_mut().append_pair("token", token).append_pair("session", session);
+    Ok(url.to_string())
+}
+
+/// Whether the host of `address` (`host[:port][/path]`) is this machine.
+fn is_loopback(address: &str) -> bool {
+    let authority = address.split('/').next().unwrap_or_default();
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+pub struct RemoteTransport {
+    url: String,
+    bus: Arc<MessageBus>,
+    chat_id: String,
+    cancel: CancellationToken,
+}
+
+impl RemoteTransport {
+    /// Relay the `cli:<chat_id>` chat on `bus` to the WebSocket at `url`.
+    pub fn new(url: String, bus: Arc<MessageBus>, chat_id: impl Into<String>, cancel: CancellationToken) -> Self {
+        Self {
+            url,
+            bus,
+            chat_id: chat_id.into(),
+            cancel,
+        }
+    }
+
+    /// Connect, then relay until the connection closes or `cancel` fires.
+    /// The typed messages arrive on `inbound`, the bus's inbound receiver.
+    pub async fn run(self, mut inbound: mpsc::Receiver<InboundMessage>) -> Result<()> {
+        let (socket, _) = match tokio_tungstenite::connect_async(self.url.as_str()).await {
+            Ok(connected) => connected,
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) if response.status() == 401 => {
+                bail!("the bot rejected the token")
+            }
+            Err(e) => return Err(e).context("could not connect to the bot"),
+        };
+        info!("Attached to remote bot");
+        let (mut sink, mut stream) = socket.split();
+
+        loop {
+            tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                msg = inbound.recv() => {
+                    let Some(msg) = msg else { break };
+                    let frame = serde_json::json!({"type": "message", "content": msg.content});
+                    sink.send(Message::Text(frame.to_string().into())).await.context("connection to the bot lost")?;
+                }
+                frame = stream.next() => {
+                    let text = match frame {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | None => bail!("the bot closed the connection"),
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => return Err(e).context("connection to the bot lost"),
+                    };
+                    match serde_json::from_str::<ServerFrame>(&text) {
+                        Ok(frame) => {
+                            if let Some(msg) = self.outbound(frame) {
+                                self.bus.publish_outbound(msg).await;
+                            }
+                        }
+                        Err(e) => debug!("Ignoring malformed frame from the bot: {}", e),
+                    }
+                }
+            }
+        }
+        let _ = sink.send(Message::Close(None)).await;
+        Ok(())
+    }
+
+    /// The outbound message a frame shows up as in the terminal.
+    fn outbound(&self, frame: ServerFrame) -> Option<OutboundMessage> {
+        let chat_id = self.chat_id.as_str();
+        Some(match frame {
+            ServerFrame::Hello { chat_id: remote } => {
+                debug!(remote_chat = %remote, "Remote bot said hello");
+                return None;
+            }
+            ServerFrame::Typing | ServerFrame::Unknown => return None,
+            ServerFrame::Progress { content } => OutboundMessage::progress("cli", chat_id, content),
+            ServerFrame::Partial { content } => OutboundMessage::partial("cli", chat_id, content),
+            ServerFrame::Reply { content, buttons } if buttons.is_empty() => OutboundMessage::reply("cli", chat_id, content),
+            ServerFrame::Reply { content, buttons } => {
+                let buttons = buttons
+                    .into_iter()
+                    .map(|b| Button {
+                        text: b.text,
+                        data: b.data,
+                        url: b.url,
+                    })
+                    .collect();
+                OutboundMessage::reply_with_buttons("cli", chat_id, content, buttons)
+            }
+            ServerFrame::Attachment { name, caption, image } => {
+                OutboundMessage::attachment("cli", chat_id, save_image(&name, image.as_deref()), caption)
+            }
+        })
+    }
+}
+
+/// Write an inlined image to the temp dir so it can be opened; files the
+/// bot only announced are shown by name.
+fn save_image(name: &str, data_url: Option<&str>) -> PathBuf {
+    use base64::Engine;
+
+    let name = PathBuf::from(name).file_name().map(PathBuf::from).unwrap_or_else(|| "attachment".into());
+    let Some(data) = data_url.and_then(|url| url.split_once(";base64,")).map(|(_, data)| data) else {
+        return name;
+    };
+    let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) else {
+        return name;
+    };
+    let path = std::env::temp_dir().join("crabbybot-attach").join(&name);
+    let written = std::fs::create_dir_all(path.parent().unwrap_or(&path)).and_then(|_| std::fs::write(&path, bytes));
+    match written {
+        Ok(()) => path,
+        Err(e) => {
+            warn!(file = %name.display(), "Could not save attachment: {}", e);
+            name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_url() {
+        assert_eq!(
+            socket_url("https://bot.example.com/", "s3cret", "cli-main").unwrap(),
+            "wss://bot.example.com/ws?token=s3cret&session=cli-main"
+        );
+        assert_eq!(
+            socket_url("127.0.0.1:18790/ws", "a b", "x").unwrap(),
+            "ws://127.0.0.1:18790/ws?token=a+b&session=x"
+        );
+        assert_eq!(
+            socket_url("localhost:18790", "t", "x").unwrap(),
+            "ws://localhost:18790/ws?token=t&session=x"
+        );
+        assert!(socket_url("[::1]:18790", "t", "x").unwrap().starts_with("ws://[::1]:18790/ws"));
+        assert_eq!(
+            socket_url("bot.example.com:8443", "t", "x").unwrap(),
+            "wss://bot.example.com:8443/ws?token=t&session=x"
+        );
+    }
+
+    #[test]
+    fn test_frames_become_terminal_output() {
+        let (bus, _receivers) = MessageBus::new(1);
+        let remote = RemoteTransport::new(String::new(), Arc::new(bus), "main", CancellationToken::new());
+        let frame = |json: &str| remote.outbound(serde_json::from_str(json).unwrap());
+
+        assert!(frame(r#"{"type":"hello","chat_id":"cli-main"}"#).is_none());
+        assert!(frame(r#"{"type":"typing"}"#).is_none());
+        assert!(frame(r#"{"type":"something_new"}"#).is_none());
+        let Some(OutboundMessage::Progress { chat_id, content, .. }) =
+            frame(r#"{"type":"progress","content":"Running tool: web"}"#)
+        else {
+            panic!("expected progress");
+        };
+        assert_eq!((chat_id.as_str(), content.as_str()), ("main", "Running tool: web"));
+        let Some(OutboundMessage::Reply { content, buttons, .. }) =
+            frame(r#"{"type":"reply","content":"Done","buttons":[{"text":"More","data":"more","url":null}]}"#)
+        else {
+            panic!("expected a reply");
+        };
+        assert_eq!(content, "Done");
+        assert_eq!(buttons.unwrap()[0].data.as_deref(), Some("more"));
+
+        let Some(OutboundMessage::Attachment { path, .. }) =
+            frame(r#"{"type":"attachment","name":"../chart.png","caption":"SOL","image":"data:image/png;base64,iVBORw=="}"#)
+        else {
+            panic!("expected an attachment");
+        };
+        assert_eq!(std::fs::read(&path).unwrap(), b"\x89PNG");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,11 +1,17 @@
-use crate::bus::events::InboundMessage;
+use crate::bus::events::{DeliveryError, InboundMessage};
 use crate::bus::MessageBus;
-use crate::gateway::utils::chunk_message;
+use crate::commands::MenuEntry;
+use crate::gateway::acl::{Acl, Origin};
+use crate::gateway::pairing::{self, PairedUser, Pairing};
+use crate::gateway::uploads::{self, UploadStore};
+use crate::gateway::utils::{chunk_message, is_image};
 use anyhow::Result;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use teloxide::net::Download;
 use teloxide::prelude::*;
-use teloxide::types::MessageId;
+use teloxide::types::{BotCommand, FileMeta, MessageId, ReplyParameters};
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
@@ -25,6 +31,9 @@ struct ProgressState {
     message_id: Option<MessageId>,
     /// Accumulated status lines (one per tool-call batch).
     lines: Vec<String>,
+    /// The message showing the reply while it is written, edited as it
+    /// grows and deleted once the final reply is sent.
+    draft: Option<MessageId>,
 }
 
 /// Per-chat progress tracker, shared between the outbound callback closure
@@ -34,20 +43,48 @@ type ProgressTracker = Arc<Mutex<HashMap<String, ProgressState>>>;
 pub struct TelegramTransport {
     token: String,
     bus: Arc<MessageBus>,
-    allow_from: Vec<String>,
+    acl: Arc<Acl>,
     cancel: CancellationToken,
+    uploads: Option<Arc<UploadStore>>,
+    commands: Vec<MenuEntry>,
+    pairing: Option<Arc<Pairing>>,
 }
 
 impl TelegramTransport {
-    pub fn new(token: String, bus: Arc<MessageBus>, allow_from: Vec<String>, cancel: CancellationToken) -> Self {
+    pub fn new(token: String, bus: Arc<MessageBus>, acl: Acl, cancel: CancellationToken) -> Self {
         Self {
             token,
             bus,
-            allow_from,
+            acl: Arc::new(acl),
             cancel,
+            uploads: None,
+            commands: Vec::new(),
+            pairing: None,
         }
     }
 
+    /// Confirm WebChat sign-ins: `/start pair_<code>` from an allowed user
+    /// asks them to confirm, which pairs the browser waiting on that code
+    /// with them. `/signout` signs all their browsers out.
+    pub fn with_pairing(mut self, pairing: Arc<Pairing>) -> Self {
+        self.pairing = Some(pairing);
+        self
+    }
+
+    /// Offer these commands in Telegram's command menu. Without any, the
+    /// menu is left as it is.
+    pub fn with_commands(mut self, commands: Vec<MenuEntry>) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Save documents and photos users send into `store`. Without a store
+    /// they are ignored.
+    pub fn with_uploads(mut self, store: UploadStore) -> Self {
+        self.uploads = Some(Arc::new(store));
+        self
+    }
+
     pub async fn run(self) -> Result<()> {
         let bot = Bot::new(&self.token);
         let progress: ProgressTracker = Arc::new(Mutex::new(HashMap::new()));
@@ -61,13 +98,31 @@ impl TelegramTransport {
             warn!("Failed to delete webhook (normal on first startup): {}", e);
         }
 
+        if !self.commands.is_empty() {
+            let commands = self.commands.iter().map(|c| BotCommand::new(c.name, &c.description));
+            match bot.set_my_commands(commands).await {
+                Ok(_) => info!(count = self.commands.len(), "Registered Telegram command menu"),
+                Err(e) => warn!("Failed to register the Telegram command menu: {}", e),
+            }
+        }
+
+        if let Some(ref pairing) = self.pairing {
+            match bot.get_me().await {
+                Ok(me) => match me.username.as_deref() {
+                    Some(username) => pairing.set_bot_username(username),
+                    None => warn!("The bot has no username; WebChat can't offer Telegram sign-in"),
+                },
+                Err(e) => warn!("Failed to look up the bot's username for WebChat sign-in: {}", e),
+            }
+        }
+
         // Subscribe to outbound messages FIRST (before dispatcher starts)
         {
             let bot_out = bot.clone();
             let progress_out = Arc::clone(&progress);
 
             self.bus
-                .subscribe_outbound("telegram", move |msg| {
+                .subscribe_outbound_tracked("telegram", move |msg| {
                     use crate::bus::events::OutboundMessage;
                     let bot_out = bot_out.clone();
                     let progress_out = Arc::clone(&progress_out);
@@ -78,16 +133,44 @@ impl TelegramTransport {
                                 chat_id,
                                 content,
                                 buttons,
+                                reply_to_message_id,
+                                sent_chunks,
                                 ..
                             } => {
                                 // ── Final reply: send as new message(s) and clear progress ──
+                                let mut delivery = Err(format!("invalid chat id '{}'", chat_id).into());
                                 if let Ok(id) = chat_id.parse::<i64>() {
+                                    delivery = Ok(None);
+                                    let draft = progress_out
+                                        .lock()
+                                        .await
+                                        .get_mut(&chat_id)
+                                        .and_then(|state| state.draft.take());
+                                    if let Some(draft) = draft {
+                                        if let Err(e) = bot_out.delete_message(ChatId(id), draft).await {
+                                            debug!("Failed to delete the draft reply: {}", e);
+                                        }
+                                    }
                                     let chunks = chunk_message(&content, TELEGRAM_MAX_LEN);
                                     let num_chunks = chunks.len();
 
-                                    for (i, chunk) in chunks.into_iter().enumerate() {
+                                    // A retry sends only what the last attempt didn't.
+                                    for (i, chunk) in chunks.into_iter().enumerate().skip(sent_chunks) {
                                         let mut send = bot_out.send_message(ChatId(id), chunk);
 
+                                        // In groups (negative chat IDs), quote the message being
+                                        // answered with the first chunk.
+                                        if let Some(reply_to) = reply_to_message_id
+                                            .as_deref()
+                                            .and_then(|m| m.parse::<i32>().ok())
+                                            .filter(|_| i == 0 && id < 0)
+                                        {
+                                            send = send.reply_parameters(
+                                                ReplyParameters::new(MessageId(reply_to))
+                                                    .allow_sending_without_reply(),
+                                            );
+                                        }
+
                                         // Attach buttons only to the LAST chunk
                                         if i == num_chunks - 1 {
                                             if let Some(ref btns) = buttons {
@@ -121,13 +204,19 @@ impl TelegramTransport {
                                             }
                                         }
 
-                                        if let Err(e) = send.await {
-                                            error!("Failed to send Telegram message: {}", e);
+                                        match send.await {
+                                            Ok(sent) => delivery = Ok(Some(sent.id.0.to_string())),
+                                            Err(e) => {
+                                                error!("Failed to send Telegram message: {}", e);
+                                                delivery = Err(DeliveryError::after(i, e.to_string()));
+                                                break;
+                                            }
                                         }
                                     }
                                 }
                                 // Clear any accumulated progress for this chat
                                 progress_out.lock().await.remove(&chat_id);
+                                delivery
                             }
 
                             OutboundMessage::Progress {
@@ -195,6 +284,30 @@ impl TelegramTransport {
                                         }
                                     }
                                 }
+                                Ok(None)
+                            }
+
+                            OutboundMessage::Partial {
+                                chat_id, content, ..
+                            } => {
+                                // ── Partial reply: one draft message, edited as it grows ──
+                                if let Ok(id) = chat_id.parse::<i64>() {
+                                    let text = draft_text(&content);
+                                    let mut tracker = progress_out.lock().await;
+                                    let state = tracker.entry(chat_id.clone()).or_default();
+                                    match state.draft {
+                                        Some(msg_id) => {
+                                            if let Err(e) = bot_out.edit_message_text(ChatId(id), msg_id, &text).await {
+                                                debug!("Failed to update the draft reply: {}", e);
+                                            }
+                                        }
+                                        None => match bot_out.send_message(ChatId(id), &text).await {
+                                            Ok(sent) => state.draft = Some(sent.id),
+                                            Err(e) => debug!("Failed to send the draft reply: {}", e),
+                                        },
+                                    }
+                                }
+                                Ok(None)
                             }
 
                             OutboundMessage::Typing { chat_id, .. } => {
@@ -204,6 +317,40 @@ impl TelegramTransport {
                                         .send_chat_action(ChatId(id), ChatAction::Typing)
                                         .await;
                                 }
+                                Ok(None)
+                            }
+
+                            OutboundMessage::Attachment {
+                                chat_id,
+                                path,
+                                caption,
+                                ..
+                            } => {
+                                use teloxide::types::InputFile;
+                                let Ok(id) = chat_id.parse::<i64>() else {
+                                    return Err(format!("invalid chat id '{}'", chat_id).into());
+                                };
+                                let file = InputFile::file(&path);
+                                let sent = if is_image(&path) {
+                                    let mut send = bot_out.send_photo(ChatId(id), file);
+                                    if !caption.is_empty() {
+                                        send = send.caption(caption);
+                                    }
+                                    send.await
+                                } else {
+                                    let mut send = bot_out.send_document(ChatId(id), file);
+                                    if !caption.is_empty() {
+                                        send = send.caption(caption);
+                                    }
+                                    send.await
+                                };
+                                match sent {
+                                    Ok(sent) => Ok(Some(sent.id.0.to_string())),
+                                    Err(e) => {
+                                        error!("Failed to send Telegram attachment: {}", e);
+                                        Err(e.to_string().into())
+                                    }
+                                }
                             }
                         }
                     }
@@ -213,18 +360,25 @@ impl TelegramTransport {
 
         // Set up inbound update handler
         let bus = Arc::clone(&self.bus);
-        let allow_from = self.allow_from.clone();
+        let acl = Arc::clone(&self.acl);
 
         let message_handler = Update::filter_message().endpoint(
-            move |_bot: Bot, msg: Message, bus: Arc<MessageBus>, allow_from: Vec<String>, cancel: CancellationToken| async move {
+            move |bot: Bot,
+                  msg: Message,
+                  bus: Arc<MessageBus>,
+                  acl: Arc<Acl>,
+                  uploads: Option<Arc<UploadStore>>,
+                  pairing: Option<Arc<Pairing>>| async move {
                 let user_id = msg.from.as_ref().map(|u| u.id.to_string()).unwrap_or_else(|| "unknown".to_owned());
+                let sender_name = msg.from.as_ref().map(|u| u.full_name());
 
                 // Enforce allowFrom ACL
-                if !allow_from.is_empty() && !allow_from.contains(&user_id) {
+                let chat_id = msg.chat.id.to_string();
+                if !acl.allows(&Origin { user_id: &user_id, chat_id: &chat_id, ..Default::default() }) {
                     warn!(
                         user_id = user_id,
-                        chat_id = msg.chat.id.to_string(),
-                        "Rejected message from user not in allowFrom list"
+                        chat_id = chat_id,
+                        "Rejected message not matching allowFrom"
                     );
                     return respond(());
                 }
@@ -233,486 +387,90 @@ impl TelegramTransport {
                     let normalized = text.trim();
                     let lower = normalized.to_lowercase();
 
-                    // ── FAST PATH: /restart command ──
-                    if lower == "/restart" || lower == "restart" {
-                        let _ = _bot.send_message(msg.chat.id, "🔄 Restarting CrabbyBot… please wait a few seconds.").await;
-                        crate::request_restart();
-                        cancel.cancel();
-                        return respond(());
-                    }
-
-                    // ── FAST PATH: /config command (bypass LLM) ──
-                    if lower == "/config" || lower == "config"
-                        || lower.starts_with("/config ") || lower.starts_with("config ")
+                    // A WebChat sign-in link. Handled here, the agent never
+                    // sees the code; the sign-in waits for a button press.
+                    if let Some(code) = normalized
+                        .strip_prefix("/start ")
+                        .and_then(|arg| arg.trim().strip_prefix(pairing::START_PREFIX))
                     {
-                        let args_str = if lower == "/config" || lower == "config" {
-                            ""
-                        } else if normalized.starts_with('/') {
-                            normalized[8..].trim() // skip "/config "
-                        } else {
-                            normalized[7..].trim() // skip "config "
-                        };
-                        let args_lower = args_str.to_lowercase();
-
-                        use crate::config::Config;
-                        let mut config = Config::load().unwrap_or_default();
-
-                        // ── Helper: detect which provider the current model belongs to ──
-                        let detect_model_provider = |model: &str| -> &'static str {
-                            let m = model.to_lowercase();
-                            if m.starts_with("gemini") { "gemini" }
-                            else if m.starts_with("claude") || m.starts_with("anthropic/") { "anthropic" }
-                            else if m.starts_with("gpt") || m.starts_with("o1") || m.starts_with("o3") || m.starts_with("openai/") { "openai" }
-                            else if m.starts_with("deepseek") { "deepseek" }
-                            else if m.starts_with("llama") || m.starts_with("mixtral") || m.starts_with("groq/") { "groq" }
-                            else if m.contains('/') { "openrouter" }
-                            else { "unknown" }
-                        };
-
-                        let provider_display_name = |id: &str| -> &'static str {
-                            match id {
-                                "groq" => "Groq", "openai" => "OpenAI", "anthropic" => "Anthropic",
-                                "deepseek" => "DeepSeek", "gemini" => "Gemini", "openrouter" => "OpenRouter",
-                                _ => "Unknown",
-                            }
-                        };
-
-                        // ── /config model — quick check ──
-                        if args_lower == "model" {
-                            let model_str = &config.agents.defaults.model;
-                            let prov_id = detect_model_provider(model_str);
-                            let prov_name = provider_display_name(prov_id);
-                            let reply = format!(
-                                "🤖 Current Model\n\nModel: {}\nProvider: {}\nMax Tokens: {}",
-                                model_str, prov_name, config.agents.defaults.max_tokens
-                            );
-                            let _ = _bot.send_message(msg.chat.id, reply).await;
-                            return respond(());
-                        }
-
-                        if args_str.is_empty() || args_lower == "help" || args_lower == "--help" {
-                            // Show current config summary with masked keys
-                            let mask = |s: &str| -> String {
-                                if s.is_empty() || s.contains("YOUR_") { return "❌ not set".into(); }
-                                if s.len() <= 8 { return "••••••••".into(); }
-                                format!("{}••••{}", &s[..4], &s[s.len()-4..])
-                            };
-
-                            let groq_key = config.providers.groq.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let openai_key = config.providers.openai.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let anthropic_key = config.providers.anthropic.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let deepseek_key = config.providers.deepseek.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let gemini_key = config.providers.gemini.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let openrouter_key = config.providers.openrouter.as_ref().map(|p| mask(&p.api_key)).unwrap_or("❌ not set".into());
-                            let poly_key = config.tools.polymarket.private_key.as_deref().map(|k| mask(k)).unwrap_or("❌ not set".into());
-                            let solana_key = config.tools.solana_private_key.as_deref().map(|k| mask(k)).unwrap_or("❌ not set".into());
-
-                            // Mark the provider that matches the MODEL as active (not just the first valid key)
-                            let model_prov_id = detect_model_provider(&config.agents.defaults.model);
-                            let p_label = |name: &str, label: &str| -> String {
-                                if name == model_prov_id {
-                                    format!("{} 🟢 (Active)", label)
-                                } else {
-                                    label.to_string()
-                                }
-                            };
-
-                            // Check if the active model's provider has a valid key
-                            let model_str = &config.agents.defaults.model;
-                            let prov_name = provider_display_name(model_prov_id);
-                            let provider_has_key = match model_prov_id {
-                                "gemini" => gemini_key != "❌ not set",
-                                "anthropic" => anthropic_key != "❌ not set",
-                                "openai" => openai_key != "❌ not set",
-                                "deepseek" => deepseek_key != "❌ not set",
-                                "groq" => groq_key != "❌ not set",
-                                "openrouter" => openrouter_key != "❌ not set",
-                                _ => false,
-                            };
-
-                            let model_status = if provider_has_key {
-                                format!("{} → {} ✅", model_str, prov_name)
-                            } else {
-                                format!("{} → {} ⚠️ (no API key!)", model_str, prov_name)
-                            };
-
-                            let summary = format!(
-"⚙️ CrabbyBot Configuration
-
-━━━ 🔑 LLM Providers ━━━
-{}: {}
-{}: {}
-{}: {}
-{}: {}
-{}: {}
-{}: {}
-
-━━━ 🤖 Agent ━━━
-Model: {}
-Max Tokens: {}
-Temperature: {}
-
-━━━ 🔐 Wallet Keys ━━━
-Polymarket: {}
-Solana: {}
-
-━━━ 🎰 Betting ━━━
-Enabled: {}
-Max Bet: ${}
-Daily Loss Limit: ${}
-Strategy: {}
-Scan Interval: {} min
-
-━━━ ✏️ Set a value ━━━
-/config set model <MODEL>
-/config set max_tokens <NUMBER>
-/config set temperature <0.0-2.0>
-/config set groq_key <KEY>
-/config set openai_key <KEY>
-/config set anthropic_key <KEY>
-/config set deepseek_key <KEY>
-/config set gemini_key <KEY>
-/config set openrouter_key <KEY>
-/config set polymarket_key <KEY>
-/config set solana_key <KEY>
-/config set betting_enabled <true|false>
-/config set max_bet <AMOUNT>
-/config set daily_limit <AMOUNT>
-/config set strategy <value|momentum|contrarian>
-/config set scan_interval <MINUTES>
-
-━━━ 🔍 Quick check ━━━
-/config model
-
-━━━ 🔄 Reset a value ━━━
-/config reset <SETTING_NAME>
-/config reset all",
-                                p_label("groq", "Groq"), groq_key,
-                                p_label("openai", "OpenAI"), openai_key,
-                                p_label("anthropic", "Anthropic"), anthropic_key,
-                                p_label("deepseek", "DeepSeek"), deepseek_key,
-                                p_label("gemini", "Gemini"), gemini_key,
-                                p_label("openrouter", "OpenRouter"), openrouter_key,
-                                model_status,
-                                config.agents.defaults.max_tokens,
-                                config.agents.defaults.temperature,
-                                poly_key, solana_key,
-                                if config.tools.betting.enabled { "🟢" } else { "🔴" },
-                                config.tools.betting.max_bet_size_usdc,
-                                config.tools.betting.daily_loss_limit_usdc,
-                                config.tools.betting.strategy,
-                                config.tools.betting.scan_interval_minutes,
-                            );
-                            let _ = _bot.send_message(msg.chat.id, summary).await;
-                            return respond(());
-                        }
-
-                        // Handle "set <key> <value>"
-                        if args_lower.starts_with("set ") {
-                            // ── SECURITY: Delete the user's message immediately ──
-                            // API keys should never persist in Telegram chat history.
-                            let _ = _bot.delete_message(msg.chat.id, msg.id).await;
-
-                            let set_args = args_str[4..].trim();
-                            let parts: Vec<&str> = set_args.splitn(2, ' ').collect();
-                            if parts.len() < 2 {
-                                let _ = _bot.send_message(msg.chat.id, "❌ Usage: /config set <setting_name> <value>\n🔒 Your message was auto-deleted for security.").await;
-                                return respond(());
-                            }
-                            let key = parts[0].to_lowercase();
-                            let value = parts[1].trim().to_string();
-
-                            // Determine if this key holds a sensitive secret
-                            let is_secret = matches!(key.as_str(),
-                                "groq_key" | "openai_key" | "anthropic_key"
-                                | "deepseek_key" | "gemini_key" | "openrouter_key"
-                                | "polymarket_key" | "solana_key"
-                            );
-
-                            // Encrypt secrets before storing
-                            let store_value = if is_secret {
-                                match crate::vault::encrypt(&value) {
-                                    Ok(encrypted) => encrypted,
-                                    Err(e) => {
-                                        let _ = _bot.send_message(msg.chat.id, format!("❌ Encryption failed: {}\n🔒 Your message was auto-deleted.", e)).await;
-                                        return respond(());
-                                    }
-                                }
-                            } else {
-                                value.clone()
-                            };
-
-                            let preview = if value.len() > 4 {
-                                format!("{}••••", &value[..4])
-                            } else {
-                                "••••••••".to_string()
-                            };
-
-                            let result = match key.as_str() {
-                                "groq_key" => {
-                                    let entry = config.providers.groq.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value;
-                                    Ok(format!("Groq API key set ({})", preview))
-                                }
-                                "openai_key" => {
-                                    let entry = config.providers.openai.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value;
-                                    Ok(format!("OpenAI API key set ({})", preview))
-                                }
-                                "anthropic_key" => {
-                                    let entry = config.providers.anthropic.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value;
-                                    Ok(format!("Anthropic API key set ({})", preview))
-                                }
-                                "gemini_key" => {
-                                    let entry = config.providers.gemini.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value;
-                                    Ok(format!("Gemini API key set ({})", preview))
-                                }
-                                "deepseek_key" => {
-                                    let entry = config.providers.deepseek.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value.clone();
-                                    // Default DeepSeek to their official API base
-                                    if entry.api_base.is_none() {
-                                        entry.api_base = Some("https://api.deepseek.com/v1".into());
-                                    }
-                                    Ok(format!("DeepSeek API key set ({})", preview))
-                                }
-                                "openrouter_key" => {
-                                    let entry = config.providers.openrouter.get_or_insert_with(Default::default);
-                                    entry.api_key = store_value;
-                                    Ok(format!("OpenRouter API key set ({})", preview))
-                                }
-                                "polymarket_key" => {
-                                    config.tools.polymarket.private_key = Some(store_value);
-                                    Ok(format!("Polymarket private key set ({})", preview))
-                                }
-                                "solana_key" => {
-                                    config.tools.solana_private_key = Some(store_value);
-                                    Ok(format!("Solana private key set ({})", preview))
-                                }
-                                "model" => {
-                                    config.agents.defaults.model = value.clone();
-                                    Ok(format!("Model set to: {}", value))
-                                }
-                                "max_tokens" => {
-                                    match value.parse::<u32>() {
-                                        Ok(v) => { config.agents.defaults.max_tokens = v; Ok(format!("Max tokens set to {}", v)) }
-                                        Err(_) => Err("Invalid number".to_string())
-                                    }
-                                }
-                                "temperature" => {
-                                    match value.parse::<f32>() {
-                                        Ok(v) if (0.0..=2.0).contains(&v) => { config.agents.defaults.temperature = v; Ok(format!("Temperature set to {}", v)) }
-                                        Ok(_) => Err("Temperature must be between 0.0 and 2.0".to_string()),
-                                        Err(_) => Err("Invalid number".to_string())
-                                    }
-                                }
-                                "betting_enabled" => {
-                                    match value.to_lowercase().as_str() {
-                                        "true" | "1" | "yes" | "on" => { config.tools.betting.enabled = true; Ok("Betting enabled 🟢".to_string()) }
-                                        "false" | "0" | "no" | "off" => { config.tools.betting.enabled = false; Ok("Betting disabled 🔴".to_string()) }
-                                        _ => Err("Use true/false, on/off, or yes/no".to_string())
-                                    }
-                                }
-                                "max_bet" => {
-                                    match value.parse::<f64>() {
-                                        Ok(v) => { config.tools.betting.max_bet_size_usdc = v; Ok(format!("Max bet set to ${}", v)) }
-                                        Err(_) => Err("Invalid number".to_string())
-                                    }
-                                }
-                                "daily_limit" => {
-                                    match value.parse::<f64>() {
-                                        Ok(v) => { config.tools.betting.daily_loss_limit_usdc = v; Ok(format!("Daily loss limit set to ${}", v)) }
-                                        Err(_) => Err("Invalid number".to_string())
-                                    }
-                                }
-                                "strategy" => {
-                                    match value.to_lowercase().as_str() {
-                                        "value" | "momentum" | "contrarian" => {
-                                            config.tools.betting.strategy = value.to_lowercase();
-                                            Ok(format!("Strategy set to: {}", value.to_lowercase()))
-                                        }
-                                        _ => Err("Strategy must be: value, momentum, or contrarian".to_string())
-                                    }
-                                }
-                                "scan_interval" => {
-                                    match value.parse::<u64>() {
-                                        Ok(v) if v >= 1 => { config.tools.betting.scan_interval_minutes = v; Ok(format!("Scan interval set to {} min", v)) }
-                                        Ok(_) => Err("Scan interval must be at least 1 minute".to_string()),
-                                        Err(_) => Err("Invalid number".to_string())
-                                    }
-                                }
-                                _ => Err(format!("Unknown key: `{}`. Use /config to see available keys.", key))
-                            };
-
-                            let security_note = if is_secret {
-                                "\n🔒 Message auto-deleted · 🔐 Value encrypted (AES-256-GCM)"
-                            } else {
-                                ""
-                            };
-
-                            match result {
-                                Ok(success_msg) => {
-                                    match config.save() {
-                                        Ok(()) => {
-                                            let _ = _bot.send_message(msg.chat.id, format!("✅ {} — saved to config.json{}\n🔄 Restarting to apply changes…", success_msg, security_note)).await;
-                                            // Trigger automatic restart so config takes effect
-                                            crate::request_restart();
-                                            cancel.cancel();
-                                        }
-                                        Err(e) => {
-                                            let _ = _bot.send_message(msg.chat.id, format!("⚠️ {} — but failed to save: {}", success_msg, e)).await;
-                                        }
-                                    }
-                                }
-                                Err(err_msg) => {
-                                    let _ = _bot.send_message(msg.chat.id, format!("❌ {}", err_msg)).await;
-                                }
-                            }
-                            return respond(());
-                        }
-
-                        // Handle "reset <key>"
-                        if args_lower.starts_with("reset ") {
-                            let reset_args = args_str[6..].trim();
-                            let key = reset_args.to_lowercase();
-                            
-                            if key.is_empty() {
-                                let _ = _bot.send_message(msg.chat.id, "❌ Usage: /config reset <setting_name> | /config reset all").await;
+                        let reply = match pairing {
+                            Some(pairing) if msg.chat.is_private() && pairing.is_waiting(code) => {
+                                use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+                                let buttons = InlineKeyboardMarkup::new(vec![vec![
+                                    InlineKeyboardButton::callback(
+                                        "✅ Sign in",
+                                        format!("{}{}", pairing::CONFIRM_PREFIX, code),
+                                    ),
+                                    InlineKeyboardButton::callback(
+                                        "Cancel",
+                                        format!("{}{}", pairing::CANCEL_PREFIX, code),
+                                    ),
+                                ]]);
+                                let ask = "A browser wants to sign in to WebChat as you. Only confirm if you \
+                                           just opened this link from your own WebChat page.";
+                                let _ = bot.send_message(msg.chat.id, ask).reply_markup(buttons).await;
                                 return respond(());
                             }
-
-                            let mut modified = false;
-
-                            if key == "all" {
-                                if let Some(p) = config.providers.groq.as_mut() { p.api_key.clear(); modified = true; }
-                                if let Some(p) = config.providers.openai.as_mut() { p.api_key.clear(); modified = true; }
-                                if let Some(p) = config.providers.anthropic.as_mut() { p.api_key.clear(); modified = true; }
-                                if let Some(p) = config.providers.deepseek.as_mut() { p.api_key.clear(); modified = true; }
-                                if let Some(p) = config.providers.gemini.as_mut() { p.api_key.clear(); modified = true; }
-                                if let Some(p) = config.providers.openrouter.as_mut() { p.api_key.clear(); modified = true; }
-                                config.agents.defaults.model = crate::config::Config::default().agents.defaults.model;
-                                if config.tools.polymarket.private_key.is_some() { config.tools.polymarket.private_key = None; modified = true; }
-                                if config.tools.solana_private_key.is_some() { config.tools.solana_private_key = None; modified = true; }
-                            } else {
-                                match key.as_str() {
-                                    "groq_key" => if let Some(p) = config.providers.groq.as_mut() { p.api_key.clear(); modified = true; },
-                                    "openai_key" => if let Some(p) = config.providers.openai.as_mut() { p.api_key.clear(); modified = true; },
-                                    "anthropic_key" => if let Some(p) = config.providers.anthropic.as_mut() { p.api_key.clear(); modified = true; },
-                                    "deepseek_key" => if let Some(p) = config.providers.deepseek.as_mut() { p.api_key.clear(); modified = true; },
-                                    "gemini_key" => if let Some(p) = config.providers.gemini.as_mut() { p.api_key.clear(); modified = true; },
-                                    "openrouter_key" => if let Some(p) = config.providers.openrouter.as_mut() { p.api_key.clear(); modified = true; },
-                                    "model" => { config.agents.defaults.model = crate::config::Config::default().agents.defaults.model; modified = true; },
-                                    "max_tokens" => { config.agents.defaults.max_tokens = crate::config::AgentDefaults::default().max_tokens; modified = true; },
-                                    "temperature" => { config.agents.defaults.temperature = crate::config::AgentDefaults::default().temperature; modified = true; },
-                                    "betting_enabled" => { config.tools.betting.enabled = false; modified = true; },
-                                    "max_bet" => { config.tools.betting.max_bet_size_usdc = 5.0; modified = true; },
-                                    "daily_limit" => { config.tools.betting.daily_loss_limit_usdc = 20.0; modified = true; },
-                                    "strategy" => { config.tools.betting.strategy = "value".to_string(); modified = true; },
-                                    "scan_interval" => { config.tools.betting.scan_interval_minutes = 15; modified = true; },
-                                    "polymarket_key" => { config.tools.polymarket.private_key = None; modified = true; },
-                                    "solana_key" => { config.tools.solana_private_key = None; modified = true; },
-                                    _ => {
-                                        let _ = _bot.send_message(msg.chat.id, format!("❌ Unknown model: `{}`. Cannot reset.", key)).await;
-                                        return respond(());
-                                    }
-                                }
+                            Some(_) if msg.chat.is_private() => {
+                                "❌ This sign-in link has expired. Reload the WebChat page for a new one."
                             }
-
-                            if modified {
-                                match config.save() {
-                                    Ok(()) => {
-                                        let msg_text = if key == "all" {
-                                            "✅ All keys have been reset to empty.\n⚠️ Restart the bot to apply changes."
-                                        } else {
-                                            "✅ Key has been reset to empty.\n⚠️ Restart the bot to apply changes."
-                                        };
-                                        let _ = _bot.send_message(msg.chat.id, msg_text).await;
-                                    }
-                                    Err(e) => {
-                                        let _ = _bot.send_message(msg.chat.id, format!("⚠️ Failed to save config: {}", e)).await;
-                                    }
-                                }
-                            } else {
-                                let _ = _bot.send_message(msg.chat.id, "✅ Key was already unset or empty.").await;
-                            }
-                            return respond(());
-                        }
-
-                        let _ = _bot.send_message(msg.chat.id, "❌ Unknown config command. Use /config for help.").await;
+                            Some(_) => "❌ Open the sign-in link in a private chat with me.",
+                            None => "❌ WebChat sign-in with Telegram is off.",
+                        };
+                        let _ = bot.send_message(msg.chat.id, reply).await;
                         return respond(());
                     }
-                    // ── FAST PATH: /polymarket CLI commands (bypass LLM) ──
-                    if lower == "polymarket" || lower == "/polymarket"
-                        || lower.starts_with("polymarket ") || lower.starts_with("/polymarket ")
-                    {
-                        // Strip the prefix to get the raw arguments
-                        let args_str = if lower == "polymarket" || lower == "/polymarket" {
-                            ""
-                        } else if normalized.starts_with('/') {
-                            normalized[12..].trim() // skip "/polymarket "
-                        } else {
-                            normalized[11..].trim() // skip "polymarket "
-                        };
-                        let args_lower = args_str.to_lowercase();
-
-                        // Handle --help / help / bare command
-                        if args_str.is_empty() || args_lower == "--help" || args_lower == "help" {
-                            use crate::tools::polymarket_help::POLYMARKET_HELP;
-                            use crate::gateway::utils::chunk_message;
-
-                            let chunks = chunk_message(POLYMARKET_HELP, TELEGRAM_MAX_LEN);
-                            for chunk in chunks {
-                                let _ = _bot.send_message(msg.chat.id, chunk).await;
-                            }
-                            return respond(());
-                        }
-
-                        // Parse and execute the CLI command
-                        if let Some(parsed_args) = shlex::split(args_str) {
-                            use crate::config::Config;
-                            let config = Config::load().unwrap_or_default();
-
-                            let progress_msg = format!("⚙️ `polymarket {}`…", parsed_args.join(" "));
-                            let _ = _bot.send_message(msg.chat.id, &progress_msg).await;
 
-                            let str_args: Vec<&str> = parsed_args.iter().map(|s| s.as_str()).collect();
+                    if let Some(pairing) = pairing.as_ref().filter(|_| lower == "/signout") {
+                        let revoked = pairing.revoke(&user_id);
+                        info!(user_id, revoked, "Signed WebChat browsers out");
+                        let reply = format!("✅ Signed out of WebChat in {} browser(s).", revoked);
+                        let _ = bot.send_message(msg.chat.id, reply).await;
+                        return respond(());
+                    }
 
-                            match crate::tools::polymarket_common::run_polymarket_cli(&config.tools.polymarket, &str_args).await {
-                                Ok(output) => {
-                                    let content = if output.trim().is_empty() {
-                                        "✅ Command completed (no output)".to_string()
-                                    } else {
-                                        output
-                                    };
-                                    use crate::gateway::utils::chunk_message;
-                                    let chunks = chunk_message(&content, TELEGRAM_MAX_LEN);
-                                    for chunk in chunks {
-                                        let _ = _bot.send_message(msg.chat.id, chunk).await;
-                                    }
-                                }
-                                Err(e) => {
-                                    let err_msg = format!("❌ CLI Error:\n{}", e);
-                                    let _ = _bot.send_message(msg.chat.id, err_msg).await;
-                                }
-                            }
-                            return respond(());
-                        } else {
-                            let _ = _bot.send_message(msg.chat.id, "❌ Could not parse command arguments. Check your quoting.").await;
-                            return respond(());
-                        }
+                    // `/config set` may carry an API key; don't leave it in the chat
+                    // history. The command itself runs in the bridge.
+                    if lower.starts_with("/config set ") || lower.starts_with("config set ") {
+                        let _ = bot.delete_message(msg.chat.id, msg.id).await;
                     }
 
                     let inbound = InboundMessage {
                         channel: "telegram".to_owned(),
                         chat_id: msg.chat.id.to_string(),
                         user_id,
+                        sender_name,
                         content: text.to_owned(),
+                        message_id: Some(msg.id.0.to_string()),
                         media: Vec::new(),
                         is_system: false,
                     };
 
+                    if let Err(e) = bus.inbound_sender().send(inbound).await {
+                        error!("Failed to send inbound message to bus: {}", e);
+                    }
+                } else if let (Some(uploads), Some((file, name))) = (uploads, attached_file(&msg)) {
+                    let path = match save_upload(&bot, &uploads, &chat_id, file, &name).await {
+                        Ok(path) => path,
+                        Err(e) => {
+                            warn!(chat_id, file = name, "Upload rejected: {}", e);
+                            let _ = bot.send_message(msg.chat.id, format!("❌ Couldn't save {}: {}", name, e)).await;
+                            return respond(());
+                        }
+                    };
+                    info!(chat_id, path = %path.display(), "Saved upload");
+
+                    let note = uploads::upload_note(&name, &path, file.size.into());
+                    let inbound = InboundMessage {
+                        channel: "telegram".to_owned(),
+                        chat_id,
+                        user_id,
+                        sender_name,
+                        content: uploads::with_notes(msg.caption().unwrap_or_default(), &[note]),
+                        message_id: Some(msg.id.0.to_string()),
+                        media: vec![path.to_string_lossy().into_owned()],
+                        is_system: false,
+                    };
                     if let Err(e) = bus.inbound_sender().send(inbound).await {
                         error!("Failed to send inbound message to bus: {}", e);
                     }
@@ -722,15 +480,46 @@ Scan Interval: {} min
         );
 
         let callback_handler = Update::filter_callback_query().endpoint(
-            move |bot: Bot, q: CallbackQuery, bus: Arc<MessageBus>, allow_from: Vec<String>| async move {
+            move |bot: Bot,
+                  q: CallbackQuery,
+                  bus: Arc<MessageBus>,
+                  acl: Arc<Acl>,
+                  pairing: Option<Arc<Pairing>>| async move {
                 let user_id = q.from.id.to_string();
 
                 // Enforce allowFrom ACL
-                if !allow_from.is_empty() && !allow_from.contains(&user_id) {
+                let chat_id = q.message.as_ref().map(|m| m.chat().id.to_string()).unwrap_or_default();
+                if !acl.allows(&Origin { user_id: &user_id, chat_id: &chat_id, ..Default::default() }) {
                     warn!(user_id, "Rejected callback query from unauthorized user");
                     return respond(());
                 }
 
+                // The answer to a WebChat sign-in prompt.
+                if let (Some(pairing), Some(data), Some(msg)) = (pairing, q.data.as_deref(), q.message.as_ref()) {
+                    let answer = if let Some(code) = data.strip_prefix(pairing::CONFIRM_PREFIX) {
+                        let user = PairedUser {
+                            user_id: user_id.clone(),
+                            name: q.from.full_name(),
+                        };
+                        if msg.chat().is_private() && pairing.confirm(code, user) {
+                            info!(user_id, "Confirmed a WebChat sign-in");
+                            Some("✅ You're signed in to WebChat. Head back to your browser.")
+                        } else {
+                            Some("❌ This sign-in link has expired. Reload the WebChat page for a new one.")
+                        }
+                    } else if let Some(code) = data.strip_prefix(pairing::CANCEL_PREFIX) {
+                        pairing.cancel(code);
+                        Some("Sign-in cancelled.")
+                    } else {
+                        None
+                    };
+                    if let Some(answer) = answer {
+                        let _ = bot.edit_message_text(msg.chat().id, msg.id(), answer).await;
+                        let _ = bot.answer_callback_query(q.id).await;
+                        return respond(());
+                    }
+                }
+
                 if let (Some(data), Some(msg)) = (q.data, q.message) {
                     info!(user_id, data, "Received callback query");
                     
@@ -739,7 +528,9 @@ Scan Interval: {} min
                         channel: "telegram".to_owned(),
                         chat_id: msg.chat().id.to_string(),
                         user_id: user_id.clone(),
+                        sender_name: Some(q.from.full_name()),
                         content: data,
+                        message_id: None,
                         media: Vec::new(),
                         is_system: false,
                     };
@@ -759,9 +550,8 @@ Scan Interval: {} min
             .branch(message_handler)
             .branch(callback_handler);
 
-        let cancel = self.cancel.clone();
         let mut dispatcher = Dispatcher::builder(bot, handler)
-            .dependencies(dptree::deps![bus, allow_from, cancel])
+            .dependencies(dptree::deps![bus, acl, self.uploads, self.pairing])
             .build();
 
         // Grab the shutdown token so we can stop the dispatcher programmatically
@@ -781,6 +571,54 @@ Scan Interval: {} min
     }
 }
 
+/// The file attached to a message and a name for it: documents keep the
+/// sender's file name, photos are saved in their largest size.
+fn attached_file(msg: &Message) -> Option<(&FileMeta, String)> {
+    if let Some(doc) = msg.document() {
+        let name = doc
+            .file_name
+            .clone()
+            .unwrap_or_else(|| format!("document_{}", doc.file.unique_id));
+        return Some((&doc.file, name));
+    }
+    msg.photo()?
+        .iter()
+        .max_by_key(|p| p.width * p.height)
+        .map(|p| (&p.file, format!("photo_{}.jpg", p.file.unique_id)))
+}
+
+async fn save_upload(
+    bot: &Bot,
+    uploads: &UploadStore,
+    chat_id: &str,
+    file: &FileMeta,
+    name: &str,
+) -> Result<PathBuf, String> {
+    // Check the advertised size first to avoid downloading oversized files.
+    if !uploads.accepts(file.size.into()) {
+        return Err(format!("file is larger than the {} MB upload limit", uploads.max_mb()));
+    }
+    let file = bot.get_file(file.id.clone()).await.map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    bot.download_file(&file.path, &mut bytes)
+        .await
+        .map_err(|e| e.to_string())?;
+    uploads
+        .save("telegram", chat_id, name, &bytes)
+        .map_err(|e| e.to_string())
+}
+
+/// A partial reply cut to fit one message: the end is kept, since that is
+/// where the text grows.
+fn draft_text(content: &str) -> String {
+    let count = content.chars().count();
+    if count <= TELEGRAM_MAX_LEN {
+        return content.to_string();
+    }
+    let tail: String = content.chars().skip(count - (TELEGRAM_MAX_LEN - 1)).collect();
+    format!("…{}", tail)
+}
+
 /// Formats accumulated progress lines into a clean tree-style view.
 ///
 /// ```text
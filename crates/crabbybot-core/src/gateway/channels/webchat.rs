@@ -0,0 +1,435 @@
+//! WebChat transport: a browser chat UI served by the gateway.
+//!
+//! Serves the embedded single-page UI at `/` and accepts chat connections at
+//! `/ws?token=…&session=…` on `gateway.host:gateway.port`. The session ID is
+//! generated and remembered by the browser, so each browser is its own chat
+//! and keeps its history across reloads.
+//!
+//! Wire protocol (JSON text frames):
+//! - client → server: `{"type":"message","content":"…"}`
+//! - server → client: `{"type":"hello","chat_id":"…"}`, `{"type":"typing"}`,
+//!   `{"type":"progress","content":"…"}`, `{"type":"partial","content":"…"}`
+//!   (the reply so far while it is written; the `reply` frame replaces it),
+//!   `{"type":"reply","content":"…","buttons":[{"text":"…","data":"…","url":null}]}`,
+//!   `{"type":"attachment","name":"…","caption":"…","image":"data:image/png;base64,…"}`
+//!   (`image` is null for non-image files, which are only announced by name)
+//!
+//! Button presses are sent back as plain messages carrying the button data,
+//! the same way the Telegram transport treats callback queries.
+//!
+//! With Telegram sign-in (see [`crate::gateway::pairing`]) the page may use
+//! a paired token instead of the shared one:
+//! - `POST /pair` → `{"code":"…","link":"https://t.me/…"}`; `429` when the
+//!   caller's address already has codes waiting
+//! - `GET /pair/{code}` → `{"status":"pending"}`, then once confirmed in
+//!   Telegram `{"status":"paired","token":"…","name":"…"}`; `404` when the
+//!   code expired
+
+use crate::bus::events::{Button, InboundMessage, OutboundMessage};
+use crate::bus::MessageBus;
+use crate::gateway::pairing::{PairStatus, Pairing, StartError};
+use crate::gateway::utils::token_matches;
+use crate::provider::types::image_data_url;
+use anyhow::Result;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{header, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::{SinkExt, StreamExt};
+use rust_embed::RustEmbed;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+#[derive(RustEmbed)]
+#[folder = "assets/webchat/"]
+struct Assets;
+
+/// Open connections, keyed by chat ID.
+type Connections = Arc<RwLock<HashMap<String, mpsc::UnboundedSender<ServerFrame>>>>;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerFrame {
+    Hello { chat_id: String },
+    Typing,
+    Progress { content: String },
+    Partial { content: String },
+    Reply { content: String, buttons: Vec<ButtonFrame> },
+    Attachment { name: String, caption: String, image: Option<String> },
+}
+
+/// Largest image inlined into an `attachment` frame.
+const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Read a small image into a `data:` URL the page can display directly.
+fn inline_image(path: &std::path::Path) -> Option<String> {
+    image_data_url(path, MAX_INLINE_IMAGE_BYTES)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+struct ButtonFrame {
+    text: String,
+    data: Option<String>,
+    url: Option<String>,
+}
+
+impl From<Button> for ButtonFrame {
+    fn from(b: Button) -> Self {
+        Self {
+            text: b.text,
+            data: b.data,
+            url: b.url,
+        }
+    }
+}
+
+impl From<OutboundMessage> for ServerFrame {
+    fn from(msg: OutboundMessage) -> Self {
+        match msg {
+            OutboundMessage::Reply {
+                content, buttons, ..
+            } => Self::Reply {
+                content,
+                buttons: buttons
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(ButtonFrame::from)
+                    .collect(),
+            },
+            OutboundMessage::Typing { .. } => Self::Typing,
+            OutboundMessage::Progress { content, .. } => Self::Progress { content },
+            OutboundMessage::Partial { content, .. } => Self::Partial { content },
+            OutboundMessage::Attachment { path, caption, .. } => Self::Attachment {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                image: inline_image(&path),
+                caption,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    Message { content: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectParams {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    session: String,
+}
+
+#[derive(Clone)]
+struct AppState {
+    token: Arc<str>,
+    bus: Arc<MessageBus>,
+    connections: Connections,
+    pairing: Option<Arc<Pairing>>,
+}
+
+pub struct WebChatTransport {
+    host: String,
+    port: u16,
+    token: String,
+    bus: Arc<MessageBus>,
+    cancel: CancellationToken,
+    pairing: Option<Arc<Pairing>>,
+}
+
+impl WebChatTransport {
+    pub fn new(
+        host: String,
+        port: u16,
+        token: String,
+        bus: Arc<MessageBus>,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            host,
+            port,
+            token,
+            bus,
+            cancel,
+            pairing: None,
+        }
+    }
+
+    /// Let browsers sign in through the Telegram bot besides the shared
+    /// token.
+    pub fn with_pairing(mut self, pairing: Arc<Pairing>) -> Self {
+        self.pairing = Some(pairing);
+        self
+    }
+
+    /// Serve the chat on its own listener.
+    pub async fn run(self) -> Result<()> {
+        let (host, port, cancel) = (self.host.clone(), self.port, self.cancel.clone());
+        let app = self.router().await;
+        crate::gateway::http::serve(&host, port, app, cancel).await
+    }
+
+    /// Register for outbound messages and return the chat's routes, for
+    /// serving next to other gateway endpoints. `host`, `port` and the
+    /// cancellation token are left to whoever serves them.
+    pub async fn router(self) -> Router {
+        let connections: Connections = Arc::new(RwLock::new(HashMap::new()));
+
+        // Subscribe to outbound messages FIRST (before dispatcher starts)
+        {
+            let connections = Arc::clone(&connections);
+            self.bus
+                .subscribe_outbound_tracked("webchat", move |msg| {
+                    let connections = Arc::clone(&connections);
+                    async move {
+                        let chat_id = msg.chat_id().to_owned();
+                        let conns = connections.read().await;
+                        let Some(tx) = conns.get(&chat_id) else {
+                            return Err(format!("webchat client '{}' is not connected", chat_id).into());
+                        };
+                        tx.send(ServerFrame::from(msg))
+                            .map(|_| None)
+                            .map_err(|_| format!("webchat client '{}' disconnected", chat_id).into())
+                    }
+                })
+                .await;
+        }
+
+        let state = AppState {
+            token: self.token.into(),
+            bus: self.bus,
+            connections,
+            pairing: self.pairing,
+        };
+        info!("WebChat transport started");
+        Router::new()
+            .route("/ws", get(ws_handler))
+            .route("/pair", post(start_pairing))
+            .route("/pair/{code}", get(poll_pairing))
+            .fallback(get(static_handler))
+            .with_state(state)
+    }
+}
+
+/// Who is chatting: the user ID and name messages are sent under.
+struct Identity {
+    user_id: Option<String>,
+    name: Option<String>,
+}
+
+/// Accept a WebSocket connection if the token matches the shared one or
+/// was issued by Telegram sign-in.
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Query(params): Query<ConnectParams>,
+    State(state): State<AppState>,
+) -> Response {
+    let identity = if token_matches(&state.token, &params.token) {
+        Identity { user_id: None, name: None }
+    } else if let Some(user) = state.pairing.as_ref().and_then(|p| p.user(&params.token)) {
+        Identity {
+            user_id: Some(user.user_id),
+            name: Some(user.name),
+        }
+    } else {
+        warn!("Rejected WebChat connection with invalid token");
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let chat_id = sanitize_session(&params.session)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    ws.on_upgrade(move |socket| handle_socket(socket, chat_id, identity, state))
+}
+
+/// Issue a pairing code and its Telegram deep link.
+async fn start_pairing(State(state): State<AppState>, ConnectInfo(peer): ConnectInfo<SocketAddr>) -> Response {
+    let Some(pairing) = state.pairing else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match pairing.start(peer.ip()) {
+        Ok((code, link)) => Json(json!({ "code": code, "link": link })).into_response(),
+        Err(StartError::TooManyFromAddress) => {
+            warn!(address = %peer.ip(), "Refused a WebChat pairing code: too many waiting");
+            StatusCode::TOO_MANY_REQUESTS.into_response()
+        }
+        Err(StartError::NotReady | StartError::Full) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+/// Report whether a code was confirmed, handing out the token once it is.
+async fn poll_pairing(State(state): State<AppState>, Path(code): Path<String>) -> Response {
+    let Some(pairing) = state.pairing else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match pairing.poll(&code) {
+        PairStatus::Pending => Json(json!({ "status": "pending" })).into_response(),
+        PairStatus::Paired { token, user } => {
+            info!(user_id = %user.user_id, "WebChat browser signed in with Telegram");
+            Json(json!({ "status": "paired", "token": token, "name": user.name })).into_response()
+        }
+        PairStatus::Expired => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn handle_socket(socket: WebSocket, chat_id: String, identity: Identity, state: AppState) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<ServerFrame>();
+    let _ = tx.send(ServerFrame::Hello {
+        chat_id: chat_id.clone(),
+    });
+    // A newer tab for the same session takes over delivery.
+    state
+        .connections
+        .write()
+        .await
+        .insert(chat_id.clone(), tx.clone());
+    info!(chat_id = %chat_id, "WebChat client connected");
+
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            let Ok(text) = serde_json::to_string(&frame) else {
+                continue;
+            };
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(msg)) = stream.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let content = match serde_json::from_str::<ClientFrame>(&text) {
+            Ok(ClientFrame::Message { content }) => content,
+            Err(e) => {
+                debug!("Ignoring malformed WebChat frame: {}", e);
+                continue;
+            }
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let inbound = InboundMessage {
+            channel: "webchat".to_owned(),
+            chat_id: chat_id.clone(),
+            user_id: identity.user_id.clone().unwrap_or_else(|| chat_id.clone()),
+            sender_name: identity.name.clone(),
+            content,
+            message_id: None,
+            media: Vec::new(),
+            is_system: false,
+        };
+        if let Err(e) = state.bus.inbound_sender().send(inbound).await {
+            error!("Failed to send inbound message to bus: {}", e);
+        }
+    }
+
+    {
+        let mut conns = state.connections.write().await;
+        if conns.get(&chat_id).is_some_and(|c| c.same_channel(&tx)) {
+            conns.remove(&chat_id);
+        }
+    }
+    writer.abort();
+    info!(chat_id = %chat_id, "WebChat client disconnected");
+}
+
+/// Serve the embedded UI.
+async fn static_handler(uri: Uri) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    let path = if path.is_empty() { "index.html" } else { path };
+    match Assets::get(path) {
+        Some(file) => ([(header::CONTENT_TYPE, content_type(path))], file.data).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Accept browser-generated session IDs that are safe to use as chat IDs.
+fn sanitize_session(session: &str) -> Option<String> {
+    let valid = !session.is_empty()
+        && session.len() <= 64
+        && session
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| session.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_and_session_validation() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secret", "secreT"));
+        assert!(!token_matches("", ""));
+        assert_eq!(sanitize_session("abc-123_X").as_deref(), Some("abc-123_X"));
+        assert!(sanitize_session("../etc").is_none());
+        assert!(sanitize_session("").is_none());
+    }
+
+    #[test]
+    fn test_frames_serialize_to_protocol() {
+        let reply = ServerFrame::from(OutboundMessage::reply_with_buttons(
+            "webchat",
+            "c1",
+            "hi",
+            vec![Button {
+                text: "Yes".into(),
+                data: Some("yes".into()),
+                url: None,
+            }],
+        ));
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["type"], "reply");
+        assert_eq!(json["buttons"][0]["data"], "yes");
+
+        let typing = serde_json::to_value(ServerFrame::from(OutboundMessage::typing("webchat", "c1"))).unwrap();
+        assert_eq!(typing["type"], "typing");
+
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_webchat_attachment.png");
+        std::fs::write(&tmp, b"\x89PNG").unwrap();
+        let attachment =
+            serde_json::to_value(ServerFrame::from(OutboundMessage::attachment("webchat", "c1", &tmp, "SOL 7d"))).unwrap();
+        assert_eq!(attachment["type"], "attachment");
+        assert_eq!(attachment["name"], "CrabbyBot_test_webchat_attachment.png");
+        assert_eq!(attachment["image"], "data:image/png;base64,iVBORw==");
+        let _ = std::fs::remove_file(&tmp);
+
+        let frame: ClientFrame = serde_json::from_str(r#"{"type":"message","content":"gm"}"#).unwrap();
+        assert!(matches!(frame, ClientFrame::Message { content } if content == "gm"));
+    }
+
+    #[test]
+    fn test_ui_is_embedded() {
+        assert!(Assets::get("index.html").is_some());
+        assert_eq!(content_type("app.js"), "text/javascript; charset=utf-8");
+    }
+}
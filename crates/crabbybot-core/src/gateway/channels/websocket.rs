@@ -0,0 +1,395 @@
+//! WebSocket transport for custom frontends.
+//!
+//! Accepts connections at `/socket` on `gateway.host:gateway.port`. Each
+//! client configured under `channels.websocket.clients` has a name and a
+//! token, passed as `?token=` or `Authorization: Bearer <token>`. A client
+//! may hold several connections at once, and one connection may carry many
+//! chats: chat IDs are chosen by the client, and replies for a chat go to
+//! every open connection of the client that started it. Clients can't see
+//! each other's chats; internally a chat is `<client>.<chat_id>`.
+//!
+//! Wire protocol (JSON text frames, mirroring [`crate::bus::events`]):
+//! - client → server:
+//!   `{"type":"message","chat_id":"…","content":"…","user_id":"…","sender_name":"…","message_id":"…"}`
+//!   (only `chat_id` and `content` are required; `user_id` defaults to the chat)
+//! - server → client: `{"type":"hello","client":"…"}`,
+//!   `{"type":"typing","chat_id":"…"}`, `{"type":"progress","chat_id":"…","content":"…"}`,
+//!   `{"type":"partial","chat_id":"…","content":"…"}`,
+//!   `{"type":"reply","chat_id":"…","content":"…","buttons":[…],"in_reply_to":"…"}`,
+//!   `{"type":"attachment","chat_id":"…","name":"…","caption":"…","image":"data:…"}`
+//!   and `{"type":"error","message":"…"}` for frames that were not accepted.
+//!
+//! IDs are 1–64 letters, digits, `-` or `_`. Button presses are sent back as
+//! plain messages carrying the button data.
+
+use crate::bus::events::{Button, InboundMessage, OutboundMessage};
+use crate::bus::MessageBus;
+use crate::gateway::utils::{token_matches, valid_id};
+use crate::provider::types::image_data_url;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, error, info, warn};
+
+/// Channel name on the bus.
+const CHANNEL: &str = "websocket";
+
+/// Largest image inlined into an `attachment` frame.
+const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Open connections: client name → connection number → sender.
+type Connections = Arc<RwLock<HashMap<String, HashMap<u64, mpsc::UnboundedSender<ServerFrame>>>>>;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerFrame {
+    Hello {
+        client: String,
+    },
+    Typing {
+        chat_id: String,
+    },
+    Progress {
+        chat_id: String,
+        content: String,
+    },
+    Partial {
+        chat_id: String,
+        content: String,
+    },
+    Reply {
+        chat_id: String,
+        content: String,
+        buttons: Vec<ButtonFrame>,
+        in_reply_to: Option<String>,
+    },
+    Attachment {
+        chat_id: String,
+        name: String,
+        caption: String,
+        image: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+struct ButtonFrame {
+    text: String,
+    data: Option<String>,
+    url: Option<String>,
+}
+
+impl From<Button> for ButtonFrame {
+    fn from(b: Button) -> Self {
+        Self {
+            text: b.text,
+            data: b.data,
+            url: b.url,
+        }
+    }
+}
+
+impl ServerFrame {
+    /// The frame for `msg`, addressed to the client's own `chat_id`.
+    fn outbound(msg: OutboundMessage, chat_id: String) -> Self {
+        match msg {
+            OutboundMessage::Reply {
+                content,
+                buttons,
+                reply_to_message_id,
+                ..
+            } => Self::Reply {
+                chat_id,
+                content,
+                buttons: buttons
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(ButtonFrame::from)
+                    .collect(),
+                in_reply_to: reply_to_message_id,
+            },
+            OutboundMessage::Typing { .. } => Self::Typing { chat_id },
+            OutboundMessage::Progress { content, .. } => Self::Progress { chat_id, content },
+            OutboundMessage::Partial { content, .. } => Self::Partial { chat_id, content },
+            OutboundMessage::Attachment { path, caption, .. } => Self::Attachment {
+                chat_id,
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                image: image_data_url(&path, MAX_INLINE_IMAGE_BYTES),
+                caption,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    Message {
+        chat_id: String,
+        content: String,
+        #[serde(default)]
+        user_id: String,
+        #[serde(default)]
+        sender_name: Option<String>,
+        #[serde(default)]
+        message_id: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectParams {
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(Clone)]
+struct AppState {
+    /// Client name → token.
+    clients: Arc<BTreeMap<String, String>>,
+    bus: Arc<MessageBus>,
+    connections: Connections,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Name of the client `token` belongs to. Every token is compared, so the
+/// time taken doesn't tell which one nearly matched.
+fn client_for(clients: &BTreeMap<String, String>, token: &str) -> Option<String> {
+    clients.iter().fold(None, |found, (name, expected)| {
+        if token_matches(expected, token) {
+            Some(name.clone())
+        } else {
+            found
+        }
+    })
+}
+
+pub struct WebSocketTransport {
+    clients: BTreeMap<String, String>,
+    bus: Arc<MessageBus>,
+}
+
+impl WebSocketTransport {
+    /// `clients` maps each client's name to its token; clients with an
+    /// empty token can't connect.
+    pub fn new(clients: BTreeMap<String, String>, bus: Arc<MessageBus>) -> Self {
+        Self { clients, bus }
+    }
+
+    /// Register for outbound messages and return the `/socket` route, for
+    /// serving on the gateway's HTTP listener.
+    pub async fn router(self) -> Router {
+        let connections: Connections = Arc::new(RwLock::new(HashMap::new()));
+
+        // Subscribe to outbound messages FIRST (before dispatcher starts)
+        {
+            let connections = Arc::clone(&connections);
+            self.bus
+                .subscribe_outbound_tracked(CHANNEL, move |msg| {
+                    let connections = Arc::clone(&connections);
+                    async move {
+                        let Some((client, chat_id)) = msg.chat_id().split_once('.') else {
+                            return Err(format!("'{}' is not a websocket chat", msg.chat_id()).into());
+                        };
+                        let (client, chat_id) = (client.to_owned(), chat_id.to_owned());
+                        let conns = connections.read().await;
+                        let senders = conns.get(&client).filter(|s| !s.is_empty());
+                        let Some(senders) = senders else {
+                            return Err(format!("websocket client '{}' is not connected", client).into());
+                        };
+                        let frame = ServerFrame::outbound(msg, chat_id);
+                        let delivered = senders.values().filter(|tx| tx.send(frame.clone()).is_ok()).count();
+                        if delivered == 0 {
+                            return Err(format!("websocket client '{}' disconnected", client).into());
+                        }
+                        Ok(None)
+                    }
+                })
+                .await;
+        }
+
+        let state = AppState {
+            clients: Arc::new(self.clients),
+            bus: self.bus,
+            connections,
+            next_id: Arc::default(),
+        };
+        info!(clients = state.clients.len(), "WebSocket transport started");
+        Router::new().route("/socket", get(ws_handler)).with_state(state)
+    }
+}
+
+/// Accept a WebSocket connection from a configured client.
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(params): Query<ConnectParams>,
+    State(state): State<AppState>,
+) -> Response {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let Some(client) = client_for(&state.clients, bearer.unwrap_or(&params.token)) else {
+        warn!("Rejected WebSocket connection with invalid token");
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, client, state))
+}
+
+async fn handle_socket(socket: WebSocket, client: String, state: AppState) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<ServerFrame>();
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let _ = tx.send(ServerFrame::Hello { client: client.clone() });
+    state
+        .connections
+        .write()
+        .await
+        .entry(client.clone())
+        .or_default()
+        .insert(id, tx.clone());
+    info!(client = %client, connection = id, "WebSocket client connected");
+
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            let Ok(text) = serde_json::to_string(&frame) else {
+                continue;
+            };
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(msg)) = stream.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        match inbound(&client, &text) {
+            Ok(Some(inbound)) => {
+                if let Err(e) = state.bus.inbound_sender().send(inbound).await {
+                    error!("Failed to send inbound message to bus: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(message) => {
+                debug!(client = %client, "Rejected WebSocket frame: {}", message);
+                let _ = tx.send(ServerFrame::Error { message });
+            }
+        }
+    }
+
+    {
+        let mut conns = state.connections.write().await;
+        if let Some(senders) = conns.get_mut(&client) {
+            senders.remove(&id);
+            if senders.is_empty() {
+                conns.remove(&client);
+            }
+        }
+    }
+    writer.abort();
+    info!(client = %client, connection = id, "WebSocket client disconnected");
+}
+
+/// The bus message for a client frame; `None` for an empty message.
+fn inbound(client: &str, text: &str) -> Result<Option<InboundMessage>, String> {
+    let frame = serde_json::from_str::<ClientFrame>(text).map_err(|e| format!("malformed frame: {}", e))?;
+    let ClientFrame::Message {
+        chat_id,
+        content,
+        user_id,
+        sender_name,
+        message_id,
+    } = frame;
+    if !valid_id(&chat_id) {
+        return Err(format!("invalid chat_id '{}'", chat_id));
+    }
+    let user_id = if user_id.is_empty() { chat_id.clone() } else { user_id };
+    if !valid_id(&user_id) {
+        return Err(format!("invalid user_id '{}'", user_id));
+    }
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(InboundMessage {
+        channel: CHANNEL.to_owned(),
+        chat_id: format!("{}.{}", client, chat_id),
+        user_id: format!("{}.{}", client, user_id),
+        sender_name,
+        content,
+        message_id,
+        media: Vec::new(),
+        is_system: false,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_frames_become_namespaced_messages() {
+        let msg = inbound("webui", r#"{"type":"message","chat_id":"room-1","content":"gm","message_id":"m1"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!((msg.channel.as_str(), msg.chat_id.as_str()), ("websocket", "webui.room-1"));
+        assert_eq!(msg.user_id, "webui.room-1");
+        assert_eq!(msg.message_id.as_deref(), Some("m1"));
+
+        let msg = inbound("webui", r#"{"type":"message","chat_id":"r","user_id":"ann","content":"hi"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.user_id, "webui.ann");
+
+        assert!(inbound("webui", r#"{"type":"message","chat_id":"r","content":"  "}"#).unwrap().is_none());
+        assert!(inbound("webui", r#"{"type":"message","chat_id":"../x","content":"hi"}"#)
+            .unwrap_err()
+            .contains("invalid chat_id"));
+        assert!(inbound("webui", r#"{"type":"ping"}"#).unwrap_err().starts_with("malformed frame"));
+    }
+
+    #[test]
+    fn test_server_frames_and_tokens() {
+        let reply = OutboundMessage::reply_with_buttons(
+            "websocket",
+            "webui.room-1",
+            "done",
+            vec![Button {
+                text: "Again".into(),
+                data: Some("again".into()),
+                url: None,
+            }],
+        );
+        let json = serde_json::to_value(ServerFrame::outbound(reply, "room-1".into())).unwrap();
+        assert_eq!(json["type"], "reply");
+        assert_eq!(json["chat_id"], "room-1");
+        assert_eq!(json["buttons"][0]["data"], "again");
+        let typing = ServerFrame::outbound(OutboundMessage::typing("websocket", "webui.r"), "r".into());
+        assert_eq!(serde_json::to_value(typing).unwrap(), serde_json::json!({"type": "typing", "chat_id": "r"}));
+
+        let clients = BTreeMap::from([
+            ("webui".to_string(), "t0ken".to_string()),
+            ("kiosk".to_string(), String::new()),
+        ]);
+        assert_eq!(client_for(&clients, "t0ken").as_deref(), Some("webui"));
+        assert!(client_for(&clients, "").is_none());
+        assert!(client_for(&clients, "t0kem").is_none());
+    }
+}
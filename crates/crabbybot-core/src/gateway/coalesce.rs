@@ -0,0 +1,164 @@
+//! Stitching of multi-part user messages.
+//!
+//! Telegram splits long pasted text into several messages that arrive within
+//! a second or so of each other. Without help the agent answers every
+//! fragment on its own. The [`Coalescer`] holds a user's message for a short
+//! window and appends any further messages from the same user in the same
+//! chat that arrive before it expires, so the agent sees one message.
+//!
+//! System messages and slash commands are never held. They first release
+//! anything pending from the same sender so ordering is kept.
+
+use std::time::{Duration, Instant};
+
+use crate::bus::events::InboundMessage;
+
+/// A held message never waits longer than this many windows in total, so a
+/// user who keeps typing still gets an answer.
+const MAX_HOLD_WINDOWS: u32 = 4;
+
+struct Pending {
+    msg: InboundMessage,
+    first: Instant,
+    deadline: Instant,
+}
+
+impl Pending {
+    fn same_sender(&self, msg: &InboundMessage) -> bool {
+        self.msg.channel == msg.channel
+            && self.msg.chat_id == msg.chat_id
+            && self.msg.user_id == msg.user_id
+    }
+}
+
+/// Buffers rapid sequential messages per sender.
+pub(crate) struct Coalescer {
+    window: Duration,
+    /// Held messages in arrival order of their first fragment.
+    pending: Vec<Pending>,
+}
+
+impl Coalescer {
+    /// A zero `window` disables coalescing.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Accept a message and return what is ready to process now, in order.
+    /// A stitched message keeps the first fragment's `message_id`, so the
+    /// reply quotes where it began.
+    pub fn push(&mut self, msg: InboundMessage, now: Instant) -> Vec<InboundMessage> {
+        let idx = self.pending.iter().position(|p| p.same_sender(&msg));
+        let holdable = !self.window.is_zero() && !msg.is_system && !msg.content.trim_start().starts_with('/');
+
+        if !holdable {
+            let mut ready: Vec<_> = idx.map(|i| self.pending.remove(i).msg).into_iter().collect();
+            ready.push(msg);
+            return ready;
+        }
+
+        match idx {
+            Some(i) => {
+                let p = &mut self.pending[i];
+                if !msg.content.is_empty() {
+                    if !p.msg.content.is_empty() {
+                        p.msg.content.push('\n');
+                    }
+                    p.msg.content.push_str(&msg.content);
+                }
+                p.msg.media.extend(msg.media);
+                p.deadline = (now + self.window).min(p.first + self.window * MAX_HOLD_WINDOWS);
+            }
+            None => self.pending.push(Pending {
+                msg,
+                first: now,
+                deadline: now + self.window,
+            }),
+        }
+        Vec::new()
+    }
+
+    /// When the earliest held message is due.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|p| p.deadline).min()
+    }
+
+    /// Release every held message whose window has expired.
+    pub fn take_due(&mut self, now: Instant) -> Vec<InboundMessage> {
+        let (due, held) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|p| p.deadline <= now);
+        self.pending = held;
+        due.into_iter().map(|p| p.msg).collect()
+    }
+
+    /// Release everything, e.g. when the inbound channel closes.
+    pub fn drain(&mut self) -> Vec<InboundMessage> {
+        self.pending.drain(..).map(|p| p.msg).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(chat_id: &str, content: &str) -> InboundMessage {
+        InboundMessage {
+            channel: "telegram".into(),
+            chat_id: chat_id.into(),
+            user_id: "7".into(),
+            sender_name: None,
+            content: content.into(),
+            message_id: None,
+            media: Vec::new(),
+            is_system: false,
+        }
+    }
+
+    #[test]
+    fn test_fragments_are_stitched_per_sender() {
+        let window = Duration::from_millis(1000);
+        let mut c = Coalescer::new(window);
+        let t0 = Instant::now();
+
+        assert!(c.push(msg("1", "part one"), t0).is_empty());
+        assert!(c.push(msg("2", "other chat"), t0 + Duration::from_millis(100)).is_empty());
+        assert!(c.push(msg("1", "part two"), t0 + Duration::from_millis(600)).is_empty());
+        assert_eq!(c.next_deadline(), Some(t0 + Duration::from_millis(1100)));
+
+        let due = c.take_due(t0 + Duration::from_millis(1100));
+        let contents: Vec<_> = due.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["other chat"]);
+        let due = c.take_due(t0 + Duration::from_millis(1600));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].content, "part one\npart two");
+        assert!(c.next_deadline().is_none());
+
+        // A sender who keeps typing is answered after the maximum hold.
+        for i in 0..10 {
+            c.push(msg("1", "more"), t0 + Duration::from_millis(500 * i));
+        }
+        assert_eq!(c.next_deadline(), Some(t0 + window * MAX_HOLD_WINDOWS));
+    }
+
+    #[test]
+    fn test_commands_and_system_messages_are_not_held() {
+        let mut c = Coalescer::new(Duration::from_millis(1000));
+        let t0 = Instant::now();
+
+        assert!(c.push(msg("1", "hello"), t0).is_empty());
+        let ready = c.push(msg("1", "/status"), t0);
+        let contents: Vec<_> = ready.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["hello", "/status"]);
+
+        let mut system = msg("1", "heartbeat");
+        system.is_system = true;
+        assert_eq!(c.push(system, t0).len(), 1);
+
+        let mut off = Coalescer::new(Duration::ZERO);
+        assert_eq!(off.push(msg("1", "hi"), t0).len(), 1);
+    }
+}
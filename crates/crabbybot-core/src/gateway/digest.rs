@@ -0,0 +1,227 @@
+//! Passive recording of listen-only group chats for `/digest`.
+//!
+//! Messages in chats marked `listen_only` are appended to
+//! `group_logs/<channel>_<chat_id>.jsonl` in the workspace instead of being
+//! answered. `/digest` summarizes everything recorded since the previous
+//! digest and then drops what it summarized, so each digest covers only
+//! what is new; messages that arrive while it runs wait for the next one.
+//! A log that grows past [`MAX_LOG_BYTES`] loses its oldest messages.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Most transcript text handed to the model, in chars. Older messages are
+/// dropped first.
+const MAX_TRANSCRIPT_CHARS: usize = 60_000;
+
+/// Largest a chat's log may grow; past it the oldest messages are dropped
+/// until it is back to three quarters of this.
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Instructions for the digest completion.
+pub const DIGEST_PROMPT: &str = "You summarize group chat activity for someone who \
+     was away. Group the discussion by topic, name who said what when it matters, and \
+     call out decisions, questions addressed to the reader, links, tickers and \
+     addresses. Skip greetings and small talk. Be brief: a few bullets per topic.";
+
+/// One recorded group message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupEntry {
+    pub time: DateTime<Utc>,
+    pub sender: String,
+    pub text: String,
+}
+
+/// Per-chat logs of unread group messages.
+#[derive(Debug, Clone)]
+pub struct GroupLog {
+    dir: PathBuf,
+    /// Held while a log is written, so trimming and marking messages read
+    /// don't lose ones appended meanwhile.
+    lock: Arc<Mutex<()>>,
+}
+
+impl GroupLog {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join("group_logs"),
+            lock: Arc::default(),
+        }
+    }
+
+    fn path(&self, chat_key: &str) -> PathBuf {
+        let name: String = chat_key
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.jsonl", name))
+    }
+
+    /// Append a message, dropping the oldest ones once the log outgrows
+    /// [`MAX_LOG_BYTES`]. Failures are logged and otherwise ignored.
+    pub fn record(&self, chat_key: &str, sender: &str, text: &str) {
+        let entry = GroupEntry {
+            time: Utc::now(),
+            sender: sender.to_string(),
+            text: text.to_string(),
+        };
+        let path = self.path(chat_key);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let result = serde_json::to_string(&entry)
+            .map_err(std::io::Error::other)
+            .and_then(|line| {
+                std::fs::create_dir_all(&self.dir)?;
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+                file.write_all(format!("{}\n", line).as_bytes())?;
+                if file.metadata()?.len() > MAX_LOG_BYTES {
+                    let content = std::fs::read_to_string(&path)?;
+                    let keep = (MAX_LOG_BYTES * 3 / 4) as usize;
+                    let cut = content.len().saturating_sub(keep);
+                    let start = content.as_bytes()[cut..]
+                        .iter()
+                        .position(|&b| b == b'\n')
+                        .map_or(content.len(), |i| cut + i + 1);
+                    std::fs::write(&path, &content[start..])?;
+                }
+                Ok(())
+            });
+        if let Err(e) = result {
+            warn!(chat = chat_key, "Failed to record group message: {}", e);
+        }
+    }
+
+    /// Messages recorded and not yet marked read.
+    pub fn unread(&self, chat_key: &str) -> Vec<GroupEntry> {
+        let Ok(content) = std::fs::read_to_string(self.path(chat_key)) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| {
+                serde_json::from_str(l)
+                    .map_err(|e| debug!(chat = chat_key, "Skipping bad group log line: {}", e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Mark the first `count` messages [`unread`](Self::unread) returned as
+    /// read, keeping any recorded since.
+    pub fn mark_read(&self, chat_key: &str, count: usize) {
+        let path = self.path(chat_key);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        let mut seen = 0;
+        let rest: String = content
+            .lines()
+            .skip_while(|l| {
+                let read = seen < count;
+                if serde_json::from_str::<GroupEntry>(l).is_ok() {
+                    seen += 1;
+                }
+                read
+            })
+            .map(|l| format!("{}\n", l))
+            .collect();
+        let result = if rest.trim().is_empty() {
+            std::fs::remove_file(&path)
+        } else {
+            std::fs::write(&path, rest)
+        };
+        if let Err(e) = result {
+            warn!(chat = chat_key, "Failed to mark group messages read: {}", e);
+        }
+    }
+}
+
+/// `[14:05] alice: text` lines, newest last, cut from the front to fit
+/// [`MAX_TRANSCRIPT_CHARS`].
+pub fn transcript(entries: &[GroupEntry]) -> String {
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for entry in entries.iter().rev() {
+        let line = format!(
+            "[{}] {}: {}",
+            entry.time.with_timezone(&Local).format("%a %H:%M"),
+            entry.sender,
+            entry.text
+        );
+        total += line.len() + 1;
+        if total > MAX_TRANSCRIPT_CHARS && !lines.is_empty() {
+            lines.push(format!("({} earlier messages omitted)", entries.len() - lines.len()));
+            break;
+        }
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_unread_clear() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_digest_{}", std::process::id()));
+        let log = GroupLog::new(&dir);
+
+        log.record("telegram:-100", "alice", "SOL breakout?");
+        log.record("telegram:-100", "bob", "needs a close above 150");
+        log.record("telegram:-200", "carol", "other group");
+
+        let unread = log.unread("telegram:-100");
+        assert_eq!(unread.len(), 2);
+        assert_eq!(unread[1].sender, "bob");
+        let text = transcript(&unread);
+        assert!(text.lines().next().unwrap().ends_with("alice: SOL breakout?"));
+
+        // A message recorded while the digest ran stays unread.
+        log.record("telegram:-100", "carol", "closed at 152");
+        log.mark_read("telegram:-100", unread.len());
+        let left = log.unread("telegram:-100");
+        assert_eq!(left.iter().map(|e| e.text.as_str()).collect::<Vec<_>>(), ["closed at 152"]);
+        log.mark_read("telegram:-100", 1);
+        assert!(log.unread("telegram:-100").is_empty());
+        assert_eq!(log.unread("telegram:-200").len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_log_is_capped() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_digest_cap_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let log = GroupLog::new(&dir);
+        let text = "ÿ".repeat(5_001);
+        for i in 0..120 {
+            log.record("telegram:-100", &i.to_string(), &text);
+        }
+        let size = std::fs::metadata(log.path("telegram:-100")).unwrap().len();
+        assert!(size <= MAX_LOG_BYTES, "{}", size);
+        let unread = log.unread("telegram:-100");
+        assert_eq!(unread.last().unwrap().sender, "119");
+        assert!(unread[0].sender != "0");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_transcript_drops_oldest() {
+        let entry = |text: String| GroupEntry {
+            time: Utc::now(),
+            sender: "x".into(),
+            text,
+        };
+        let entries: Vec<_> = (0..3).map(|i| entry(format!("{}{}", i, "y".repeat(40_000)))).collect();
+        let text = transcript(&entries);
+        assert!(text.starts_with("(2 earlier messages omitted)"));
+        assert!(text.contains("] x: 2y"));
+    }
+}
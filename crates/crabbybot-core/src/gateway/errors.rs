@@ -0,0 +1,261 @@
+//! User-facing error messages.
+//!
+//! Failures reach the chat as a short explanation instead of the raw error
+//! string: what went wrong, whether anything will be retried, and what the
+//! user can do about it. The full error still goes to the logs and traces
+//! where it happened; only the chat gets the friendly version.
+
+use crate::agent::AgentError;
+use crate::provider::ProviderError;
+use crate::tools::ToolError;
+
+/// What happens next on our side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    /// Retried a few times already; nothing more happens until the user
+    /// asks again.
+    Exhausted,
+    /// Not retried: trying again unchanged would fail the same way.
+    Never,
+    /// Nothing failed for good; it waits on someone.
+    Pending,
+}
+
+/// A failure explained for the chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserError {
+    pub title: String,
+    /// What happened, in plain words.
+    pub what: String,
+    pub retry: Retry,
+    /// What the user can do.
+    pub hint: Option<String>,
+}
+
+impl UserError {
+    fn new(title: &str, what: impl Into<String>, retry: Retry) -> Self {
+        Self {
+            title: title.into(),
+            what: what.into(),
+            retry,
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// The message as Markdown.
+    pub fn render(&self) -> String {
+        let icon = if self.retry == Retry::Pending { "⏳" } else { "⚠️" };
+        let mut text = format!("{} **{}**\n\n{}", icon, self.title, self.what);
+        let retry = match self.retry {
+            Retry::Exhausted => Some("It was retried automatically and still failed."),
+            Retry::Never => Some("It won't be retried automatically."),
+            Retry::Pending => None,
+        };
+        if let Some(retry) = retry {
+            text.push(' ');
+            text.push_str(retry);
+        }
+        if let Some(hint) = &self.hint {
+            text.push_str("\n\n**What you can do:** ");
+            text.push_str(hint);
+        }
+        text
+    }
+}
+
+/// Explain a failed agent turn.
+pub fn for_agent(e: &AgentError) -> UserError {
+    match e {
+        AgentError::MaxIterationsExceeded(n) => UserError::new(
+            "Max iterations reached",
+            format!("The agent made {n} rounds of tool calls without reaching an answer."),
+            Retry::Never,
+        )
+        .hint("Ask for something smaller, or raise `agents.defaults.max_tool_iterations`."),
+        AgentError::Provider(inner) => for_provider(inner),
+        AgentError::Session(_) => UserError::new(
+            "Couldn't save the conversation",
+            "The reply was lost because the session file couldn't be written.",
+            Retry::Never,
+        )
+        .hint("Check free disk space and permissions of `~/.CrabbyBot/sessions`, then send the message again."),
+        AgentError::TimedOut(limit) => UserError::new(
+            "Request timed out",
+            format!(
+                "This took longer than {} seconds, so it was stopped. A provider or tool may be unresponsive.",
+                limit.as_secs()
+            ),
+            Retry::Never,
+        )
+        .hint("Try again, or ask for less at once."),
+        AgentError::Cancelled => UserError::new(
+            "Request cancelled",
+            "The request was stopped before it finished; nothing from it was kept.",
+            Retry::Never,
+        ),
+        AgentError::ContextTooLong(_) => UserError::new(
+            "Conversation too long",
+            "This request doesn't fit the model's context window, even after dropping older messages.",
+            Retry::Exhausted,
+        )
+        .hint("Send /clear to start fresh, or ask for less at once (fewer items, a shorter time range)."),
+    }
+}
+
+/// Explain an LLM provider failure.
+pub fn for_provider(e: &ProviderError) -> UserError {
+    match e {
+        ProviderError::Exhausted => quota(),
+        _ if e.is_rate_limited() => quota(),
+        _ if e.is_auth() => {
+            UserError::new("API key rejected", "The LLM provider refused the configured API key.", Retry::Never)
+                .hint("Check the key, or set a new one with `/config set <provider>_key <KEY>`.")
+        }
+        ProviderError::Cancelled => UserError::new(
+            "Request cancelled",
+            "The request was stopped before the LLM provider answered.",
+            Retry::Never,
+        ),
+        ProviderError::NotConfigured => {
+            UserError::new("No LLM provider", "No provider has an API key configured.", Retry::Never)
+                .hint("Set one with `/config set groq_key <KEY>`.")
+        }
+        ProviderError::Network(inner) if inner.is_decode() || inner.is_body() => UserError::new(
+            "Unreadable provider reply",
+            "The LLM provider sent a reply that couldn't be read, usually a sign of an outage or a proxy in the way.",
+            Retry::Exhausted,
+        )
+        .hint("Try again in a minute, or switch to another model in `agents.defaults.model`."),
+        ProviderError::Network(inner) if inner.is_timeout() => UserError::new(
+            "Provider timed out",
+            "The LLM provider didn't answer in time.",
+            Retry::Exhausted,
+        )
+        .hint("Try again in a minute."),
+        ProviderError::Network(_) => UserError::new(
+            "Provider unreachable",
+            "The LLM provider couldn't be reached.",
+            Retry::Exhausted,
+        )
+        .hint("Check the server's internet connection and the provider's `apiBase`, then try again."),
+        ProviderError::InvalidResponse(_) => UserError::new(
+            "Unreadable provider reply",
+            "The LLM provider's reply didn't have the expected shape.",
+            Retry::Never,
+        )
+        .hint("Try again, or switch to another model in `agents.defaults.model`."),
+        ProviderError::Api { status: 404, .. } => UserError::new(
+            "Model not found",
+            "The provider doesn't know the configured model.",
+            Retry::Never,
+        )
+        .hint("Check the model name in `agents.defaults.model`."),
+        ProviderError::Api { status, .. } if *status >= 500 => UserError::new(
+            "Provider outage",
+            format!("The LLM provider is having problems (HTTP {}).", status),
+            Retry::Exhausted,
+        )
+        .hint("Try again in a few minutes."),
+        ProviderError::Api { status, .. } => UserError::new(
+            "Provider error",
+            format!("The LLM provider rejected the request (HTTP {}).", status),
+            Retry::Never,
+        )
+        .hint("Try rephrasing, or send /clear if it keeps happening in this conversation."),
+    }
+}
+
+fn quota() -> UserError {
+    UserError::new(
+        "LLM Quota / Rate-limit",
+        "All configured providers have hit their limits.",
+        Retry::Exhausted,
+    )
+    .hint(
+        "Wait a few minutes for rate limits to reset, add a **Groq** API key for a generous \
+         free tier, or check your billing details.",
+    )
+}
+
+/// Explain a tool call the registry refused.
+pub fn for_tool(e: &ToolError) -> UserError {
+    match e {
+        ToolError::NotFound(name) => {
+            UserError::new("Tool unavailable", format!("There is no tool called `{}`.", name), Retry::Never)
+                .hint("It may be disabled in the `tools` section of the config, or the job refers to an old name.")
+        }
+        ToolError::RecipientRejected(reason) => UserError::new("Transfer blocked", reason.clone(), Retry::Never)
+            .hint("Double-check the address against your saved wallets."),
+        ToolError::ReadOnly(name) => UserError::new(
+            "Read-only mode",
+            format!("`{}` would change something, and the bot runs read-only.", name),
+            Retry::Never,
+        )
+        .hint("Turn off `tools.readOnly` (or unset `CRABBYBOT_READ_ONLY`) to allow it."),
+        ToolError::NotAllowed(name) => UserError::new(
+            "Not allowed for this event",
+            format!("`{}` would change something, and this webhook may only read.", name),
+            Retry::Never,
+        )
+        .hint("Add the tool to the hook's `tools` in `gateway.webhooks` to allow it."),
+        ToolError::ApprovalRequired { tool, id, required } => UserError::new(
+            "Waiting for approval",
+            format!("`{}` runs once it has {} approval(s).", tool, required),
+            Retry::Pending,
+        )
+        .hint(format!("Approve with `/approve {}`, or cancel with `/reject {}`.", id, id)),
+        ToolError::DecisionNotJournaled(name) => UserError::new(
+            "Decision not recorded",
+            format!("`{}` places orders only after the decision behind them is journaled.", name),
+            Retry::Never,
+        )
+        .hint("Ask the agent to record the decision first."),
+        ToolError::Blocked { tool, rule, reason } => UserError::new(
+            "Blocked by a guardrail",
+            format!("`{}` was stopped by rule `{}`: {}", tool, rule, reason),
+            Retry::Never,
+        )
+        .hint("Change `guardrails.rules` if this should be allowed."),
+        ToolError::ApprovalUnavailable { tool, reason } => UserError::new(
+            "Approval unavailable",
+            format!("`{}` needs approval, but the request couldn't be filed: {}", tool, reason),
+            Retry::Never,
+        )
+        .hint("Check the `approvals` section of the config."),
+        ToolError::Cancelled(tool) => UserError::new(
+            "Tool cancelled",
+            format!("`{}` was stopped before it finished because the request was cancelled.", tool),
+            Retry::Never,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_say_what_happened_and_what_to_do() {
+        let quota = for_agent(&AgentError::Provider(ProviderError::Api {
+            status: 429,
+            message: "rate limit".into(),
+        }));
+        assert_eq!(quota.retry, Retry::Exhausted);
+        let text = quota.render();
+        assert!(text.starts_with("⚠️ **LLM Quota / Rate-limit**"), "{text}");
+        assert!(text.contains("retried automatically") && text.contains("**What you can do:**"), "{text}");
+
+        let garbled = for_provider(&ProviderError::InvalidResponse("error decoding response body".into())).render();
+        assert!(!garbled.contains("error decoding"), "{garbled}");
+        assert_eq!(for_provider(&ProviderError::Api { status: 503, message: String::new() }).title, "Provider outage");
+
+        let held = for_tool(&ToolError::ApprovalRequired { tool: "send".into(), id: "a1".into(), required: 2 });
+        assert_eq!(held.retry, Retry::Pending);
+        assert!(held.render().starts_with("⏳") && held.render().contains("/approve a1"));
+    }
+}
@@ -0,0 +1,321 @@
+//! User scripts run before and after each agent turn.
+//!
+//! `hooks/pre_message.rhai` and `hooks/post_reply.rhai` in the workspace are
+//! [Rhai](https://rhai.rs) scripts, read on every message so edits apply
+//! without a restart. They run in an embedded engine that can't reach files,
+//! processes, the network or the environment; all a script sees is the
+//! constant `message`:
+//!
+//! ```text
+//! #{channel: "telegram", chat_id: "42", user_id: "7", sender: "alice",
+//!   content: "gm", metadata: #{}, reply: ()}
+//! ```
+//!
+//! (`reply` is the agent's reply in `post_reply`), and all it can do is call:
+//!
+//! - `set_content(text)` — replace the message, or the reply in `post_reply`;
+//! - `set_metadata(key, value)` — add to the metadata handed to `post_reply`;
+//! - `reply(text)` — `pre_message` only: answer with `text` and skip the
+//!   agent.
+//!
+//! `print` and `debug` go to the log. Scripts are stopped after
+//! [`MAX_OPERATIONS`] steps or [`HOOK_TIMEOUT`]; a hook that fails is logged
+//! and ignored so a broken script never drops a message.
+
+use rhai::{Dynamic, Engine, Scope};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::bus::events::InboundMessage;
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Steps a script may take before it is stopped.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What a hook sees.
+#[derive(Debug, Clone, Serialize)]
+struct HookInput<'a> {
+    channel: &'a str,
+    chat_id: &'a str,
+    user_id: &'a str,
+    sender: &'a str,
+    content: &'a str,
+    metadata: &'a Map<String, Value>,
+    reply: Option<&'a str>,
+}
+
+/// What a hook changed.
+#[derive(Debug, Default)]
+struct HookOutput {
+    content: Option<String>,
+    metadata: Map<String, Value>,
+    reply: Option<String>,
+}
+
+/// Result of the `pre_message` hook.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreMessage {
+    /// Hand this (possibly rewritten) content to the agent.
+    Continue {
+        content: String,
+        metadata: Map<String, Value>,
+    },
+    /// Send this canned reply and skip the agent.
+    Reply(String),
+}
+
+/// The workspace's `hooks/` directory.
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    dir: PathBuf,
+}
+
+impl Hooks {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join("hooks"),
+        }
+    }
+
+    async fn run(&self, name: &'static str, input: &HookInput<'_>) -> Option<HookOutput> {
+        let path = self.dir.join(format!("{}.rhai", name));
+        let script = std::fs::read_to_string(&path).ok()?;
+        let message = rhai::serde::to_dynamic(input)
+            .map_err(|e| warn!(hook = name, "Can't hand the message to the hook: {}", e))
+            .ok()?;
+        let run = tokio::task::spawn_blocking(move || run_script(name, &script, message));
+        match run.await {
+            Ok(Ok(output)) => Some(output),
+            Ok(Err(e)) => {
+                warn!(hook = name, "Hook failed: {}", e);
+                None
+            }
+            Err(e) => {
+                warn!(hook = name, "Hook panicked: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Whether a `post_reply` script is in place to rewrite replies.
+    pub fn has_post_reply(&self) -> bool {
+        self.dir.join("post_reply.rhai").is_file()
+    }
+
+    /// Run `pre_message` for an incoming user message.
+    pub async fn pre_message(&self, msg: &InboundMessage) -> PreMessage {
+        let mut metadata = Map::new();
+        let sender = msg.sender_name.as_deref().unwrap_or(&msg.user_id);
+        let input = HookInput {
+            channel: &msg.channel,
+            chat_id: &msg.chat_id,
+            user_id: &msg.user_id,
+            sender,
+            content: &msg.content,
+            metadata: &metadata,
+            reply: None,
+        };
+        let Some(out) = self.run("pre_message", &input).await else {
+            return PreMessage::Continue {
+                content: msg.content.clone(),
+                metadata,
+            };
+        };
+        if let Some(reply) = out.reply {
+            debug!(chat = %msg.chat_id, "pre_message hook answered directly");
+            return PreMessage::Reply(reply);
+        }
+        metadata.extend(out.metadata);
+        PreMessage::Continue {
+            content: out.content.unwrap_or_else(|| msg.content.clone()),
+            metadata,
+        }
+    }
+
+    /// Run `post_reply` on the agent's reply to `msg`, returning the text to
+    /// send.
+    pub async fn post_reply(
+        &self,
+        msg: &InboundMessage,
+        content: &str,
+        metadata: &Map<String, Value>,
+        reply: String,
+    ) -> String {
+        let sender = msg.sender_name.as_deref().unwrap_or(&msg.user_id);
+        let input = HookInput {
+            channel: &msg.channel,
+            chat_id: &msg.chat_id,
+            user_id: &msg.user_id,
+            sender,
+            content,
+            metadata,
+            reply: Some(&reply),
+        };
+        match self.run("post_reply", &input).await {
+            Some(HookOutput {
+                content: Some(text), ..
+            }) => text,
+            _ => reply,
+        }
+    }
+}
+
+/// An engine with nothing but the hook API and Rhai's own language
+/// packages, limited in steps, time, depth and data size.
+fn sandbox(name: &'static str, output: &Arc<Mutex<HookOutput>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(1_000)
+        .disable_symbol("eval");
+
+    let started = Instant::now();
+    engine.on_progress(move |_| (started.elapsed() > HOOK_TIMEOUT).then(|| "timed out".into()));
+    engine.on_print(move |text| debug!(hook = name, "{}", text));
+    engine.on_debug(move |text, _, pos| debug!(hook = name, %pos, "{}", text));
+
+    let out = Arc::clone(output);
+    engine.register_fn("set_content", move |text: &str| {
+        if let Ok(mut out) = out.lock() {
+            out.content = Some(text.to_string());
+        }
+    });
+    let out = Arc::clone(output);
+    engine.register_fn("set_metadata", move |key: &str, value: Dynamic| -> Result<(), Box<rhai::EvalAltResult>> {
+        let value: Value = rhai::serde::from_dynamic(&value)?;
+        if let Ok(mut out) = out.lock() {
+            out.metadata.insert(key.to_string(), value);
+        }
+        Ok(())
+    });
+    if name == "pre_message" {
+        let out = Arc::clone(output);
+        engine.register_fn("reply", move |text: &str| {
+            if let Ok(mut out) = out.lock() {
+                out.reply = Some(text.to_string());
+            }
+        });
+    }
+    engine
+}
+
+/// Run the hook `name` with `message` in scope.
+fn run_script(name: &'static str, script: &str, message: Dynamic) -> Result<HookOutput, String> {
+    let output = Arc::new(Mutex::new(HookOutput::default()));
+    let engine = sandbox(name, &output);
+    let mut scope = Scope::new();
+    scope.push_constant("message", message);
+    engine.run_with_scope(&mut scope, script).map_err(|e| e.to_string())?;
+    drop(engine);
+    let output = Arc::try_unwrap(output).map_err(|_| "hook output still in use".to_string())?;
+    output.into_inner().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_hook(dir: &Path, name: &str, body: &str) {
+        let path = dir.join("hooks").join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, body).unwrap();
+    }
+
+    fn message(content: &str) -> InboundMessage {
+        InboundMessage {
+            channel: "telegram".into(),
+            chat_id: "42".into(),
+            user_id: "7".into(),
+            sender_name: Some("alice".into()),
+            content: content.into(),
+            message_id: None,
+            media: Vec::new(),
+            is_system: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_hooks_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let hooks = Hooks::new(&dir);
+        let msg = message("gm");
+
+        // No scripts: everything passes through.
+        assert_eq!(
+            hooks.pre_message(&msg).await,
+            PreMessage::Continue {
+                content: "gm".into(),
+                metadata: Map::new()
+            }
+        );
+        assert_eq!(hooks.post_reply(&msg, "gm", &Map::new(), "hi".into()).await, "hi");
+
+        write_hook(
+            &dir,
+            "pre_message.rhai",
+            r#"
+            if message.content == "gm" {
+                reply("gm ser");
+            } else {
+                set_content("rewritten");
+                set_metadata("lang", "en");
+                set_metadata("sender", message.sender);
+            }
+            "#,
+        );
+        write_hook(&dir, "post_reply.rhai", r#"set_content(message.reply + " — " + message.metadata.lang);"#);
+        assert_eq!(hooks.pre_message(&msg).await, PreMessage::Reply("gm ser".into()));
+
+        let other = message("price of SOL?");
+        let PreMessage::Continue { content, metadata } = hooks.pre_message(&other).await else {
+            panic!("expected the message to continue");
+        };
+        assert_eq!(content, "rewritten");
+        assert_eq!(metadata["lang"], "en");
+        assert_eq!(metadata["sender"], "alice");
+        assert_eq!(hooks.post_reply(&other, &content, &metadata, "hi".into()).await, "hi — en");
+
+        // A failing hook is ignored, and so is one calling outside the API.
+        write_hook(&dir, "post_reply.rhai", "throw \"broken\";");
+        assert_eq!(hooks.post_reply(&other, &content, &metadata, "hi".into()).await, "hi");
+        write_hook(&dir, "post_reply.rhai", "reply(\"only before the agent\");");
+        assert_eq!(hooks.post_reply(&other, &content, &metadata, "hi".into()).await, "hi");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_hooks_are_sandboxed() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_hooks_sandbox_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let hooks = Hooks::new(&dir);
+        let msg = message("gm");
+        let unchanged = PreMessage::Continue {
+            content: "gm".into(),
+            metadata: Map::new(),
+        };
+
+        for script in [
+            "loop { }",
+            "import \"secrets\" as s; set_content(s::key);",
+            "eval(\"set_content(1)\");",
+            "message.content = \"changed\"; set_content(message.content);",
+        ] {
+            write_hook(&dir, "pre_message.rhai", script);
+            assert_eq!(hooks.pre_message(&msg).await, unchanged, "{}", script);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
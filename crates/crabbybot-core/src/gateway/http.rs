@@ -0,0 +1,427 @@
+//! The gateway's HTTP listener on `gateway.host:gateway.port`, shared by
+//! WebChat, the WebSocket transport, webhooks and the REST API.
+//!
+//! The REST API (`api` feature, `gateway.api`) lets other services drive
+//! the agent without a chat app:
+//! - `GET /v1/health`: `{"status":"ok","version":"…","uptime_secs":…}`, no token needed
+//! - `POST /v1/messages` with `{"chat_id":"…","content":"…","user_id":"…","wait":true}`:
+//!   the message goes to the agent as chat `api:<chat_id>` from user
+//!   `api.<user_id>` (plain `api` without one), so a client can't pass as a
+//!   user of another channel, such as an approver. The answer is
+//!   `{"chat_id":"…","reply":"…","buttons":[…]}` once the agent replied, or
+//!   `202` right away with `"wait": false`; `504` when no reply came within
+//!   `gateway.api.replyTimeoutSecs`.
+//! - `GET /v1/sessions`: `[{"key":"…","updated":"…"}]`, the API's own
+//!   sessions (`api:…`) only
+//! - `GET /v1/sessions/{key}`: the session's metadata and messages
+//!   (URL-encode `#` in fork keys), `404` if there is none or it isn't an
+//!   API session
+//!
+//! All but the health check need `Authorization: Bearer <gateway.api.token>`.
+
+use anyhow::Result;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::bus::events::{new_request_id, InboundMessage, OutboundMessage};
+use crate::bus::MessageBus;
+use crate::gateway::utils::{token_matches, valid_id};
+use crate::session::SessionManager;
+
+/// Channel name of messages sent through the API.
+const CHANNEL: &str = "api";
+
+/// Whether `key` names a session of an API chat.
+fn is_api_session(key: &str) -> bool {
+    key.strip_prefix(CHANNEL).is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// Serve `app` until `cancel` is triggered. Handlers can see the peer's
+/// address through `ConnectInfo<SocketAddr>`.
+pub async fn serve(host: &str, port: u16, app: Router, cancel: CancellationToken) -> Result<()> {
+    let addr = format!("{}:{}", host, port);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    info!(addr = %addr, "Gateway HTTP server started");
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(cancel.cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+/// Requests waiting for their reply, per chat, oldest first.
+type Waiters = Arc<Mutex<HashMap<String, VecDeque<(String, oneshot::Sender<OutboundMessage>)>>>>;
+
+/// The `/v1` REST endpoints.
+pub struct RestApi {
+    token: String,
+    bus: Arc<MessageBus>,
+    workspace: PathBuf,
+    chat_workspaces: BTreeMap<String, PathBuf>,
+    reply_timeout: Duration,
+}
+
+#[derive(Clone)]
+struct ApiState {
+    token: Arc<str>,
+    bus: Arc<MessageBus>,
+    workspace: PathBuf,
+    chat_workspaces: Arc<BTreeMap<String, PathBuf>>,
+    reply_timeout: Duration,
+    waiters: Waiters,
+    started: Instant,
+}
+
+impl RestApi {
+    /// `token` is the bearer token clients must send; sessions are read
+    /// from `workspace`.
+    pub fn new(token: String, bus: Arc<MessageBus>, workspace: PathBuf, reply_timeout: Duration) -> Self {
+        Self {
+            token,
+            bus,
+            workspace,
+            chat_workspaces: BTreeMap::new(),
+            reply_timeout,
+        }
+    }
+
+    /// Also read the sessions of chats with a workspace of their own.
+    pub fn with_chat_workspaces(mut self, workspaces: BTreeMap<String, PathBuf>) -> Self {
+        self.chat_workspaces = workspaces;
+        self
+    }
+
+    /// Register for the agent's replies and return the `/v1` routes.
+    pub async fn router(self) -> Router {
+        let waiters: Waiters = Arc::default();
+        {
+            let waiters = Arc::clone(&waiters);
+            self.bus
+                .subscribe_outbound_tracked(CHANNEL, move |msg| {
+                    let waiters = Arc::clone(&waiters);
+                    async move {
+                        if matches!(msg, OutboundMessage::Reply { .. }) {
+                            hand_over(&waiters, msg).await;
+                        }
+                        Ok(None)
+                    }
+                })
+                .await;
+        }
+        let state = ApiState {
+            token: self.token.into(),
+            bus: self.bus,
+            workspace: self.workspace,
+            chat_workspaces: Arc::new(self.chat_workspaces),
+            reply_timeout: self.reply_timeout,
+            waiters,
+            started: Instant::now(),
+        };
+        info!("REST API enabled at /v1");
+        Router::new()
+            .route("/v1/health", get(health))
+            .route("/v1/messages", post(send_message))
+            .route("/v1/sessions", get(list_sessions))
+            .route("/v1/sessions/{key}", get(get_session))
+            .with_state(state)
+    }
+}
+
+/// Give a reply to the request it answers. Replies that answer no waiting
+/// request (progress notes, approval prompts, replies after a timeout)
+/// stay in the session only.
+async fn hand_over(waiters: &Waiters, msg: OutboundMessage) {
+    let OutboundMessage::Reply {
+        chat_id,
+        reply_to_message_id,
+        ..
+    } = &msg
+    else {
+        return;
+    };
+    let mut waiters = waiters.lock().await;
+    let Some(queue) = waiters.get_mut(chat_id) else {
+        debug!(chat_id = %chat_id, "API reply with no request waiting");
+        return;
+    };
+    let Some(at) = queue
+        .iter()
+        .position(|(id, _)| Some(id) == reply_to_message_id.as_ref())
+    else {
+        debug!(chat_id = %chat_id, "API reply answers no waiting request");
+        return;
+    };
+    let waiter = queue.remove(at);
+    if queue.is_empty() {
+        let chat_id = chat_id.clone();
+        waiters.remove(&chat_id);
+    }
+    if let Some((_, tx)) = waiter {
+        let _ = tx.send(msg);
+    }
+}
+
+fn authorized(state: &ApiState, headers: &HeaderMap) -> bool {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    token_matches(&state.token, bearer)
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+async fn health(State(state): State<ApiState>) -> Json<Value> {
+    Json(json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": state.started.elapsed().as_secs(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageRequest {
+    chat_id: String,
+    content: String,
+    #[serde(default)]
+    user_id: String,
+    #[serde(default = "default_wait")]
+    wait: bool,
+}
+
+fn default_wait() -> bool {
+    true
+}
+
+async fn send_message(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<MessageRequest>,
+) -> Response {
+    if !authorized(&state, &headers) {
+        return error(StatusCode::UNAUTHORIZED, "missing or wrong bearer token");
+    }
+    if !valid_id(&request.chat_id) || !(request.user_id.is_empty() || valid_id(&request.user_id)) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "chat_id and user_id must be 1-64 letters, digits, '-' or '_'",
+        );
+    }
+    if request.content.trim().is_empty() {
+        return error(StatusCode::BAD_REQUEST, "content is empty");
+    }
+    let user_id = if request.user_id.is_empty() {
+        CHANNEL.to_string()
+    } else {
+        format!("{}.{}", CHANNEL, request.user_id)
+    };
+
+    let id = new_request_id();
+    let reply = if request.wait {
+        let (tx, rx) = oneshot::channel();
+        state
+            .waiters
+            .lock()
+            .await
+            .entry(request.chat_id.clone())
+            .or_default()
+            .push_back((id.clone(), tx));
+        Some(rx)
+    } else {
+        None
+    };
+    let msg = InboundMessage {
+        channel: CHANNEL.to_owned(),
+        chat_id: request.chat_id.clone(),
+        user_id,
+        sender_name: None,
+        content: request.content,
+        message_id: Some(id.clone()),
+        media: Vec::new(),
+        is_system: false,
+    };
+    if state.bus.inbound_sender().send(msg).await.is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "the bot is shutting down");
+    }
+    let Some(reply) = reply else {
+        return (StatusCode::ACCEPTED, Json(json!({ "chat_id": request.chat_id, "queued": true }))).into_response();
+    };
+
+    match tokio::time::timeout(state.reply_timeout, reply).await {
+        Ok(Ok(OutboundMessage::Reply { content, buttons, .. })) => {
+            let buttons: Vec<Value> = buttons
+                .unwrap_or_default()
+                .into_iter()
+                .map(|b| json!({ "text": b.text, "data": b.data, "url": b.url }))
+                .collect();
+            Json(json!({ "chat_id": request.chat_id, "reply": content, "buttons": buttons })).into_response()
+        }
+        Ok(_) => error(StatusCode::SERVICE_UNAVAILABLE, "the bot is shutting down"),
+        Err(_) => {
+            if let Some(queue) = state.waiters.lock().await.get_mut(&request.chat_id) {
+                queue.retain(|(waiting, _)| *waiting != id);
+            }
+            warn!(chat_id = %request.chat_id, "API request timed out waiting for the reply");
+            error(
+                StatusCode::GATEWAY_TIMEOUT,
+                "no reply in time; it will be in the session once the agent is done",
+            )
+        }
+    }
+}
+
+fn sessions(state: &ApiState) -> SessionManager {
+    SessionManager::new(&state.workspace).with_chat_workspaces(state.chat_workspaces.as_ref().clone())
+}
+
+async fn list_sessions(State(state): State<ApiState>, headers: HeaderMap) -> Response {
+    if !authorized(&state, &headers) {
+        return error(StatusCode::UNAUTHORIZED, "missing or wrong bearer token");
+    }
+    let list: Vec<Value> = sessions(&state)
+        .list_sessions()
+        .into_iter()
+        .filter(|(key, _)| is_api_session(key))
+        .map(|(key, updated)| json!({ "key": key, "updated": updated }))
+        .collect();
+    Json(list).into_response()
+}
+
+async fn get_session(State(state): State<ApiState>, Path(key): Path<String>, headers: HeaderMap) -> Response {
+    if !authorized(&state, &headers) {
+        return error(StatusCode::UNAUTHORIZED, "missing or wrong bearer token");
+    }
+    let mut manager = sessions(&state);
+    if !is_api_session(&key) || !manager.exists(&key) {
+        return error(StatusCode::NOT_FOUND, "no such session");
+    }
+    let session = manager.get_or_create(&key);
+    Json(json!({
+        "key": session.key,
+        "created_at": session.created_at,
+        "updated_at": session.updated_at,
+        "parent": session.parent,
+        "topics": session.topics,
+        "sentiment": session.sentiment,
+        "summary": session.summary,
+        "messages": session.messages,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    fn state(workspace: PathBuf) -> (ApiState, crate::bus::MessageBusReceivers) {
+        let (bus, receivers) = MessageBus::new(4);
+        let state = ApiState {
+            token: "t0ken".into(),
+            bus: Arc::new(bus),
+            workspace,
+            chat_workspaces: Arc::default(),
+            reply_timeout: Duration::from_secs(5),
+            waiters: Arc::default(),
+            started: Instant::now(),
+        };
+        (state, receivers)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    async fn body(response: Response) -> (StatusCode, Value) {
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_message_waits_for_its_reply() {
+        let (state, mut receivers) = state(std::env::temp_dir());
+        let request = |chat_id: &str, wait: bool| {
+            Json(MessageRequest {
+                chat_id: chat_id.into(),
+                content: "gm".into(),
+                user_id: "7".into(),
+                wait,
+            })
+        };
+
+        let (status, _) = body(send_message(State(state.clone()), bearer("nope"), request("ops", true)).await).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = body(send_message(State(state.clone()), bearer("t0ken"), request("../x", true)).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let call = tokio::spawn(send_message(State(state.clone()), bearer("t0ken"), request("ops", true)));
+        let inbound = receivers.inbound_rx.recv().await.unwrap();
+        assert_eq!((inbound.channel.as_str(), inbound.chat_id.as_str()), ("api", "ops"));
+        assert_eq!(inbound.user_id, "api.7");
+        // Replies for another chat, or answering no request, don't answer it.
+        hand_over(&state.waiters, OutboundMessage::reply("api", "other", "no")).await;
+        hand_over(&state.waiters, OutboundMessage::reply("api", "ops", "approve?")).await;
+        let stray = OutboundMessage::reply("api", "ops", "late").in_reply_to(Some("old".into()));
+        hand_over(&state.waiters, stray).await;
+        let reply = OutboundMessage::reply("api", "ops", "gm!").in_reply_to(inbound.message_id);
+        hand_over(&state.waiters, reply).await;
+        let (status, json) = body(call.await.unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["reply"], "gm!");
+        assert!(state.waiters.lock().await.is_empty());
+
+        let (status, json) = body(send_message(State(state.clone()), bearer("t0ken"), request("ops", false)).await).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(json["queued"], true);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_listed_and_read() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_http_api_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let workspaces = BTreeMap::from([("api:ops".to_string(), tmp.clone())]);
+        let mut manager = SessionManager::new(&tmp).with_chat_workspaces(workspaces.clone());
+        manager.get_or_create("api:ops").add_message("user", "gm");
+        manager.save("api:ops").unwrap();
+        manager.get_or_create("telegram:42").add_message("user", "private");
+        manager.save("telegram:42").unwrap();
+        let (mut state, _receivers) = state(tmp.clone());
+        state.chat_workspaces = Arc::new(workspaces);
+
+        let (status, json) = body(list_sessions(State(state.clone()), bearer("t0ken")).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json.as_array().unwrap().iter().any(|s| s["key"] == "api:ops"));
+        assert!(json.as_array().unwrap().iter().all(|s| s["key"] != "telegram:42"));
+        let (status, _) = body(get_session(State(state.clone()), Path("telegram:42".into()), bearer("t0ken")).await).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, json) = body(get_session(State(state.clone()), Path("api:ops".into()), bearer("t0ken")).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["messages"][0]["content"], "gm");
+
+        let missing = format!("api:none-{}", std::process::id());
+        let (status, _) = body(get_session(State(state.clone()), Path(missing), bearer("t0ken")).await).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = body(list_sessions(State(state), HeaderMap::new()).await).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,6 +1,18 @@
+pub mod acl;
 pub mod bridge;
 pub mod channels;
+mod coalesce;
+pub mod digest;
+pub mod errors;
+pub mod hooks;
+#[cfg(any(feature = "webchat", feature = "websocket", feature = "webhooks", feature = "api"))]
+pub mod http;
+pub mod pairing;
+pub mod postprocess;
+pub mod uploads;
 pub mod utils;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
 
 pub use bridge::AgentBridge;
 pub use utils::chunk_message;
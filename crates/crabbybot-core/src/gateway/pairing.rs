@@ -0,0 +1,277 @@
+//! Signing in to WebChat with Telegram instead of a shared token.
+//!
+//! The page asks for a pairing code and shows a deep link,
+//! `https://t.me/<bot>?start=pair_<code>`. Opening it sends `/start
+//! pair_<code>` to the bot; the Telegram transport checks the sender
+//! against its `allowFrom` rules and asks them to confirm with a button, so
+//! a link someone else sent can't sign a stranger's browser in by a tap.
+//! Confirming pairs the code with their user ID. The page, polling
+//! meanwhile, then gets a token of its own for `/ws`, and its messages carry
+//! the Telegram user's ID, so approvals and per-user settings treat them as
+//! the same person.
+//!
+//! Codes expire after [`CODE_TTL`] and are handed out once; one address
+//! can't hold more than [`MAX_PENDING_PER_ADDRESS`] at a time. Tokens expire
+//! after [`TOKEN_TTL`], and `/signout` in Telegram revokes all of a user's.
+//! They live in memory: after a restart, browsers sign in again.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a pairing code can be confirmed and picked up.
+pub const CODE_TTL: Duration = Duration::from_secs(600);
+
+/// How long a browser stays signed in.
+pub const TOKEN_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Prefix of the `/start` parameter carrying a code.
+pub const START_PREFIX: &str = "pair_";
+
+/// Callback data of the button confirming a sign-in, before the code.
+pub const CONFIRM_PREFIX: &str = "pair_ok:";
+
+/// Callback data of the button turning a sign-in down, before the code.
+pub const CANCEL_PREFIX: &str = "pair_no:";
+
+/// Codes waiting at once, so an unauthenticated page can't fill memory.
+const MAX_PENDING: usize = 256;
+
+/// Codes one address may have waiting at once, so a single client can't
+/// use up [`MAX_PENDING`] and lock everyone else out.
+pub const MAX_PENDING_PER_ADDRESS: usize = 4;
+
+/// The Telegram user a browser signed in as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairedUser {
+    pub user_id: String,
+    pub name: String,
+}
+
+/// Where a pairing code stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairStatus {
+    /// Not confirmed in Telegram yet.
+    Pending,
+    /// Confirmed: the browser's token, given out once.
+    Paired { token: String, user: PairedUser },
+    /// Unknown, expired or already picked up.
+    Expired,
+}
+
+/// Why [`Pairing::start`] handed out no code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartError {
+    /// The bot's username isn't known yet.
+    NotReady,
+    /// The address already has [`MAX_PENDING_PER_ADDRESS`] codes waiting.
+    TooManyFromAddress,
+    /// Too many codes are waiting overall.
+    Full,
+}
+
+/// A code waiting to be confirmed and picked up.
+struct Pending {
+    issued: Instant,
+    address: IpAddr,
+    confirmed: Option<PairedUser>,
+}
+
+#[derive(Default)]
+struct State {
+    bot_username: Option<String>,
+    pending: HashMap<String, Pending>,
+    /// Browser token → when it was issued and the user it signs in as.
+    tokens: HashMap<String, (Instant, PairedUser)>,
+}
+
+/// Pairing codes and browser tokens, shared by the WebChat and Telegram
+/// transports.
+#[derive(Default)]
+pub struct Pairing {
+    state: Mutex<State>,
+}
+
+impl Pairing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bot's `@username`, known once the Telegram transport is up.
+    pub fn set_bot_username(&self, username: &str) {
+        self.lock().bot_username = Some(username.trim_start_matches('@').to_string());
+    }
+
+    /// A new code and its deep link for a page loaded from `address`.
+    pub fn start(&self, address: IpAddr) -> Result<(String, String), StartError> {
+        let mut state = self.lock();
+        state.pending.retain(|_, p| p.issued.elapsed() < CODE_TTL);
+        let username = state.bot_username.clone().ok_or(StartError::NotReady)?;
+        let from_address = state.pending.values().filter(|p| p.address == address).count();
+        if from_address >= MAX_PENDING_PER_ADDRESS {
+            return Err(StartError::TooManyFromAddress);
+        }
+        if state.pending.len() >= MAX_PENDING {
+            return Err(StartError::Full);
+        }
+        let code = uuid::Uuid::new_v4().simple().to_string();
+        let pending = Pending {
+            issued: Instant::now(),
+            address,
+            confirmed: None,
+        };
+        state.pending.insert(code.clone(), pending);
+        let link = format!("https://t.me/{}?start={}{}", username, START_PREFIX, code);
+        Ok((code, link))
+    }
+
+    /// Whether `code` is waiting for someone to confirm it.
+    pub fn is_waiting(&self, code: &str) -> bool {
+        self.lock()
+            .pending
+            .get(code)
+            .is_some_and(|p| p.confirmed.is_none() && p.issued.elapsed() < CODE_TTL)
+    }
+
+    /// Sign the browser waiting on `code` in as `user`. False when the code
+    /// is unknown, expired or already confirmed.
+    pub fn confirm(&self, code: &str, user: PairedUser) -> bool {
+        let mut state = self.lock();
+        match state.pending.get_mut(code) {
+            Some(p) if p.confirmed.is_none() && p.issued.elapsed() < CODE_TTL => {
+                p.confirmed = Some(user);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Turn down `code`, so it can't be confirmed any more. False when it
+    /// wasn't waiting.
+    pub fn cancel(&self, code: &str) -> bool {
+        let mut state = self.lock();
+        match state.pending.get(code) {
+            Some(p) if p.confirmed.is_none() => state.pending.remove(code).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Check on `code`; once confirmed, this issues the browser's token.
+    pub fn poll(&self, code: &str) -> PairStatus {
+        let mut state = self.lock();
+        match state.pending.get(code) {
+            Some(p) if p.issued.elapsed() >= CODE_TTL => {
+                state.pending.remove(code);
+                PairStatus::Expired
+            }
+            Some(Pending { confirmed: None, .. }) => PairStatus::Pending,
+            Some(_) => {
+                let Some(Pending {
+                    confirmed: Some(user), ..
+                }) = state.pending.remove(code)
+                else {
+                    return PairStatus::Expired;
+                };
+                let token = format!(
+                    "{}{}",
+                    uuid::Uuid::new_v4().simple(),
+                    uuid::Uuid::new_v4().simple()
+                );
+                state.tokens.retain(|_, (issued, _)| issued.elapsed() < TOKEN_TTL);
+                state.tokens.insert(token.clone(), (Instant::now(), user.clone()));
+                PairStatus::Paired { token, user }
+            }
+            None => PairStatus::Expired,
+        }
+    }
+
+    /// The user a browser token signs in as, while it hasn't expired.
+    pub fn user(&self, token: &str) -> Option<PairedUser> {
+        let mut state = self.lock();
+        match state.tokens.get(token) {
+            Some((issued, user)) if issued.elapsed() < TOKEN_TTL => Some(user.clone()),
+            Some(_) => {
+                state.tokens.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Sign out every browser signed in as `user_id`. Returns how many were.
+    pub fn revoke(&self, user_id: &str) -> usize {
+        let mut state = self.lock();
+        let before = state.tokens.len();
+        state.tokens.retain(|_, (_, user)| user.user_id != user_id);
+        before - state.tokens.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> PairedUser {
+        PairedUser {
+            user_id: "42".into(),
+            name: "Alice".into(),
+        }
+    }
+
+    fn address(last: u8) -> IpAddr {
+        IpAddr::from([192, 0, 2, last])
+    }
+
+    #[test]
+    fn test_code_pairs_once() {
+        let pairing = Pairing::new();
+        assert_eq!(pairing.start(address(1)), Err(StartError::NotReady));
+        pairing.set_bot_username("@crabby_bot");
+        let (code, link) = pairing.start(address(1)).unwrap();
+        assert_eq!(link, format!("https://t.me/crabby_bot?start=pair_{}", code));
+
+        assert_eq!(pairing.poll(&code), PairStatus::Pending);
+        assert!(pairing.is_waiting(&code));
+        assert!(!pairing.confirm("nope", alice()));
+        assert!(pairing.confirm(&code, alice()));
+        assert!(!pairing.confirm(&code, PairedUser { user_id: "7".into(), name: "Mallory".into() }));
+
+        let PairStatus::Paired { token, user } = pairing.poll(&code) else {
+            panic!("not paired");
+        };
+        assert_eq!(user, alice());
+        assert_eq!(pairing.user(&token), Some(alice()));
+        assert_eq!(pairing.poll(&code), PairStatus::Expired);
+        assert_eq!(pairing.user("guess"), None);
+
+        assert_eq!(pairing.revoke("42"), 1);
+        assert_eq!(pairing.user(&token), None);
+    }
+
+    #[test]
+    fn test_cancelled_code_cannot_be_confirmed() {
+        let pairing = Pairing::new();
+        pairing.set_bot_username("crabby_bot");
+        let (code, _) = pairing.start(address(1)).unwrap();
+        assert!(pairing.cancel(&code));
+        assert!(!pairing.is_waiting(&code));
+        assert!(!pairing.confirm(&code, alice()));
+        assert_eq!(pairing.poll(&code), PairStatus::Expired);
+    }
+
+    #[test]
+    fn test_one_address_cannot_take_every_code() {
+        let pairing = Pairing::new();
+        pairing.set_bot_username("crabby_bot");
+        for _ in 0..MAX_PENDING_PER_ADDRESS {
+            pairing.start(address(1)).unwrap();
+        }
+        assert_eq!(pairing.start(address(1)), Err(StartError::TooManyFromAddress));
+        assert!(pairing.start(address(2)).is_ok());
+    }
+}
@@ -0,0 +1,283 @@
+//! Post-processing of agent replies before the bridge publishes them.
+//!
+//! Configured under `gateway.replies`, applied in this order:
+//!
+//! 1. model self-references ("As an AI language model, …") are stripped;
+//! 2. with `localize`, dollar amounts, percentages and 12-hour times are
+//!    rewritten for the chat's locale (`$1,234.56` → `1.234,56 $`,
+//!    `6:30 PM` → `18:30` for `de-DE`); code spans are left alone;
+//! 3. replies about trading or markets get the `disclaimer`, every reply
+//!    the `signature`;
+//! 4. replies longer than `maxChars` are cut at a paragraph or line break.
+//!    The rest is kept in the session and sent a page at a time when the
+//!    user says "more".
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::agent::locale::ChatLocale;
+use crate::agent::router::IntentRouter;
+use crate::config::RepliesConfig;
+use crate::tools::IntentCategory;
+
+/// Appended to a page that has more after it.
+pub const MORE_HINT: &str = "_… say \"more\" to continue._";
+
+/// A reply ready to send, and what is held back for "more".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub text: String,
+    pub rest: Option<String>,
+}
+
+/// Applies the `gateway.replies` settings to outgoing replies.
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessor {
+    config: RepliesConfig,
+}
+
+impl PostProcessor {
+    pub fn new(config: RepliesConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether replies may be split into pages.
+    pub fn paginates(&self) -> bool {
+        self.config.max_chars > 0
+    }
+
+    /// Whether any setting changes the model's reply before it is sent.
+    pub fn rewrites(&self) -> bool {
+        let c = &self.config;
+        c.strip_self_references || c.localize || c.max_chars > 0 || !c.disclaimer.is_empty() || !c.signature.is_empty()
+    }
+
+    /// Whether replies are rewritten for the chat's locale.
+    pub fn localizes(&self) -> bool {
+        self.config.localize
+    }
+
+    /// Process the reply to `prompt` in a chat with `locale` and return
+    /// its first page.
+    pub fn process(&self, prompt: &str, reply: String, locale: &ChatLocale) -> Page {
+        let mut text = if self.config.strip_self_references {
+            strip_self_references(&reply)
+        } else {
+            reply
+        };
+        if self.config.localize {
+            text = localize(&text, locale);
+        }
+        if !self.config.disclaimer.is_empty() && (is_trading(prompt) || is_trading(&text)) {
+            text = format!("{}\n\n{}", text.trim_end(), self.config.disclaimer);
+        }
+        if !self.config.signature.is_empty() {
+            text = format!("{}\n\n{}", text.trim_end(), self.config.signature);
+        }
+        self.page(text)
+    }
+
+    /// Cut `text` to `maxChars`, keeping the remainder for the next page.
+    pub fn page(&self, text: String) -> Page {
+        let max = self.config.max_chars;
+        if max == 0 || text.chars().count() <= max {
+            return Page { text, rest: None };
+        }
+        // Leave room for the hint so a page never exceeds the limit.
+        let budget = max.saturating_sub(MORE_HINT.chars().count() + 2).max(1);
+        let cut = break_point(&text, budget);
+        let rest = text[cut..].trim_start().to_string();
+        Page {
+            text: format!("{}\n\n{}", text[..cut].trim_end(), MORE_HINT),
+            rest: (!rest.is_empty()).then_some(rest),
+        }
+    }
+}
+
+/// Whether a message asks for the next page of a long reply.
+pub fn is_more(text: &str) -> bool {
+    let text = text.trim().trim_end_matches(['.', '!']).to_lowercase();
+    matches!(text.as_str(), "more" | "continue" | "go on")
+}
+
+/// Byte index to cut `text` at so the first part has at most `budget`
+/// characters: the last paragraph break, else line break, else space in
+/// the second half of the budget, else the budget itself.
+fn break_point(text: &str, budget: usize) -> usize {
+    let limit = text.char_indices().nth(budget).map_or(text.len(), |(i, _)| i);
+    let head = &text[..limit];
+    let floor = limit / 2;
+    ["\n\n", "\n", " "]
+        .iter()
+        .find_map(|sep| head.rfind(sep).filter(|&i| i > floor))
+        .unwrap_or(limit)
+}
+
+fn is_trading(text: &str) -> bool {
+    matches!(
+        IntentRouter::classify(text),
+        IntentCategory::PolymarketTrade | IntentCategory::PolymarketRead | IntentCategory::CryptoTokens
+    )
+}
+
+/// Rewrite US-style dollar amounts (`$1,234.56`, `$2.4B`), decimal
+/// percentages and 12-hour times the way `locale` writes them. Text in
+/// backticks is kept as is, and so is everything without a locale.
+pub fn localize(text: &str, locale: &ChatLocale) -> String {
+    if locale.locale.is_none() {
+        return text.to_string();
+    }
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| if i % 2 == 1 { part.to_string() } else { localize_prose(part, locale) })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+fn localize_prose(text: &str, locale: &ChatLocale) -> String {
+    static PATTERNS: OnceLock<[Regex; 3]> = OnceLock::new();
+    let [money, percent, time] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"\$(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d+))?([KMBT]\b)?").expect("money pattern"),
+            Regex::new(r"\b(\d{1,3}(?:,\d{3})+|\d+)\.(\d+)%").expect("percent pattern"),
+            Regex::new(r"\b(1[0-2]|0?[1-9]):([0-5]\d) ?([AaPp])\.?[Mm]\b\.?").expect("time pattern"),
+        ]
+    });
+    // `1,234` and `56` as written in English → the value and its decimals.
+    let parse = |whole: &str, fraction: &str| {
+        let value = format!("{}.{}", whole.replace(',', ""), fraction).parse::<f64>().ok()?;
+        Some((value, fraction.len()))
+    };
+    let text = money.replace_all(text, |c: &regex::Captures| {
+        let fraction = c.get(2).map_or("", |m| m.as_str());
+        match parse(&c[1], fraction) {
+            Some((value, decimals)) => {
+                let suffix = c.get(3).map_or("", |m| m.as_str());
+                locale.with_currency(&format!("{}{}", locale.number(value, decimals), suffix), "USD")
+            }
+            None => c[0].to_string(),
+        }
+    });
+    let text = percent.replace_all(&text, |c: &regex::Captures| match parse(&c[1], &c[2]) {
+        Some((value, decimals)) => format!("{}%", locale.number(value, decimals)),
+        None => c[0].to_string(),
+    });
+    if locale.uses_12h() {
+        return text.into_owned();
+    }
+    time.replace_all(&text, |c: &regex::Captures| {
+        let hour = c[1].parse::<u32>().unwrap_or(0) % 12;
+        let pm = c[3].eq_ignore_ascii_case("p");
+        format!("{:02}:{}", if pm { hour + 12 } else { hour }, &c[2])
+    })
+    .into_owned()
+}
+
+/// Remove phrases where the model talks about being a model.
+fn strip_self_references(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:as an ai(?: language model| assistant)?|as a (?:large )?language model)(?: (?:developed|created|trained) by [^,.]+)?,\s*|\bi(?:'m| am) (?:just |only )?an ai(?: language model| assistant)?(?:,| and| so)[^.!?\n]*[.!?]\s*",
+        )
+        .expect("self-reference pattern")
+    });
+    let mut out = String::with_capacity(text.len());
+    // Where a sentence now starts with what followed a removed phrase.
+    let mut starts = Vec::new();
+    let mut last = 0;
+    for m in pattern.find_iter(text) {
+        out.push_str(&text[last..m.start()]);
+        let before = out.trim_end();
+        if before.is_empty() || before.ends_with(['.', '!', '?']) {
+            starts.push(out.len());
+        }
+        last = m.end();
+    }
+    out.push_str(&text[last..]);
+    for at in starts.into_iter().rev() {
+        if let Some(c) = out[at..].chars().next().filter(|c| c.is_lowercase()) {
+            out.replace_range(at..at + c.len_utf8(), &c.to_uppercase().to_string());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_references_are_stripped() {
+        assert_eq!(
+            strip_self_references("As an AI language model, I can't predict prices. But SOL looks strong."),
+            "I can't predict prices. But SOL looks strong."
+        );
+        assert_eq!(
+            strip_self_references("Sure. I'm just an AI, so take this with salt. Odds are 62%."),
+            "Sure. Odds are 62%."
+        );
+        assert_eq!(strip_self_references("The AI sector rallied."), "The AI sector rallied.");
+    }
+
+    #[test]
+    fn test_disclaimer_only_on_trading_replies() {
+        let post = PostProcessor::new(RepliesConfig {
+            disclaimer: "Not financial advice.".into(),
+            signature: "— CrabbyBot".into(),
+            ..Default::default()
+        });
+        let none = ChatLocale::default();
+        let trade = post.process("buy 10 USDC of YES", "Order placed.".into(), &none);
+        assert_eq!(trade.text, "Order placed.\n\nNot financial advice.\n\n— CrabbyBot");
+        let chat = post.process("tell me a joke", "Why did the crab…".into(), &none);
+        assert_eq!(chat.text, "Why did the crab…\n\n— CrabbyBot");
+    }
+
+    #[test]
+    fn test_replies_are_localized() {
+        let post = PostProcessor::new(RepliesConfig {
+            localize: true,
+            ..Default::default()
+        });
+        let german = ChatLocale {
+            locale: Some("de-DE".into()),
+            ..Default::default()
+        };
+        let reply = "SOL is at $142.37 (+3.25%), market cap $2.41T. Next update at 6:30 PM, see `$1,000.50`.";
+        assert_eq!(
+            post.process("price?", reply.into(), &german).text,
+            "SOL is at 142,37 $ (+3,25%), market cap 2,41T $. Next update at 18:30, see `$1,000.50`."
+        );
+        assert_eq!(
+            localize("Balance: $1,234,567.8 at 12:05 am", &german),
+            "Balance: 1.234.567,8 $ at 00:05"
+        );
+
+        let american = ChatLocale {
+            locale: Some("en-US".into()),
+            ..Default::default()
+        };
+        assert_eq!(localize("$1,234.56 at 6:30 PM", &american), "$1,234.56 at 6:30 PM");
+        assert_eq!(post.process("hi", reply.into(), &ChatLocale::default()).text, reply);
+    }
+
+    #[test]
+    fn test_long_replies_are_paged() {
+        let post = PostProcessor::new(RepliesConfig {
+            max_chars: 60,
+            ..Default::default()
+        });
+        let reply = format!("{}\n{}\n\n{}", "a".repeat(30), "b".repeat(30), "c".repeat(30));
+        let first = post.process("hi", reply, &ChatLocale::default());
+        assert_eq!(first.text, format!("{}\n\n{}", "a".repeat(30), MORE_HINT));
+        assert!(first.text.chars().count() <= 60);
+
+        let second = post.page(first.rest.unwrap());
+        assert_eq!(second.text, format!("{}\n\n{}", "b".repeat(30), MORE_HINT));
+        let last = post.page(second.rest.unwrap());
+        assert_eq!(last, Page { text: "c".repeat(30), rest: None });
+
+        assert!(is_more(" More! ") && is_more("continue") && !is_more("more please, about SOL"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Files users send through chat channels.
+//!
+//! Transports download attachments into `uploads/<channel>_<chat>/` in the
+//! workspace and append an [`upload_note`] to the inbound message, so the
+//! agent knows the file exists and can open it with its filesystem tools.
+
+use std::path::{Path, PathBuf};
+
+/// Saves uploaded files into per-chat directories.
+#[derive(Debug, Clone)]
+pub struct UploadStore {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl UploadStore {
+    /// Store under `workspace/uploads`, rejecting files over `max_mb`
+    /// megabytes.
+    pub fn new(workspace: &Path, max_mb: u64) -> Self {
+        Self {
+            dir: workspace.join("uploads"),
+            max_bytes: max_mb * 1024 * 1024,
+        }
+    }
+
+    /// Whether a file of `size` bytes may be saved.
+    pub fn accepts(&self, size: u64) -> bool {
+        size <= self.max_bytes
+    }
+
+    /// The size limit, for error messages.
+    pub fn max_mb(&self) -> u64 {
+        self.max_bytes / (1024 * 1024)
+    }
+
+    /// Directory for one chat's uploads.
+    pub fn chat_dir(&self, channel: &str, chat_id: &str) -> PathBuf {
+        self.dir.join(sanitize(&format!("{}_{}", channel, chat_id)))
+    }
+
+    /// Write `bytes` under the sender-supplied `file_name` and return the
+    /// path. The name is reduced to a safe base name, and a numeric suffix
+    /// is added instead of overwriting an earlier upload.
+    pub fn save(&self, channel: &str, chat_id: &str, file_name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+        if !self.accepts(bytes.len() as u64) {
+            return Err(std::io::Error::other(format!(
+                "file is larger than the {} MB upload limit",
+                self.max_mb()
+            )));
+        }
+        let dir = self.chat_dir(channel, chat_id);
+        std::fs::create_dir_all(&dir)?;
+        let path = unused_path(&dir, &sanitize(file_name));
+        std::fs::write(&path, bytes)?;
+        Ok(path)
+    }
+}
+
+/// Keep only the final path component and replace anything outside
+/// `[A-Za-z0-9._ -]`, so a file name can't escape the uploads directory.
+fn sanitize(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let clean: String = base
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | ' ') { c } else { '_' })
+        .collect();
+    let clean = clean.trim().trim_start_matches('.');
+    if clean.is_empty() {
+        "upload".into()
+    } else {
+        clean.to_string()
+    }
+}
+
+fn unused_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{}-{}{}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .expect("unbounded suffix search")
+}
+
+/// The note appended to a message carrying an upload.
+pub fn upload_note(file_name: &str, path: &Path, size: u64) -> String {
+    format!(
+        "[System note: the user uploaded \"{}\" ({}), saved at {}. Use your file tools to read it if needed.]",
+        file_name,
+        human_size(size),
+        path.display()
+    )
+}
+
+/// Combine a caption (possibly empty) with upload notes.
+pub fn with_notes(caption: &str, notes: &[String]) -> String {
+    let caption = caption.trim();
+    let notes = notes.join("\n");
+    if caption.is_empty() {
+        notes
+    } else if notes.is_empty() {
+        caption.to_string()
+    } else {
+        format!("{}\n\n{}", caption, notes)
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uploads_are_saved_per_chat_without_overwriting() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_uploads");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let store = UploadStore::new(&tmp, 1);
+
+        let first = store.save("telegram", "-100", "report.pdf", b"one").unwrap();
+        let second = store.save("telegram", "-100", "report.pdf", b"two").unwrap();
+        assert_eq!(first, tmp.join("uploads/telegram_-100/report.pdf"));
+        assert_eq!(second.file_name().unwrap(), "report-1.pdf");
+        assert_eq!(std::fs::read(&first).unwrap(), b"one");
+
+        let sneaky = store.save("discord", "9", "../../etc/passwd", b"x").unwrap();
+        assert_eq!(sneaky, tmp.join("uploads/discord_9/passwd"));
+        assert_eq!(sanitize("..."), "upload");
+        assert_eq!(sanitize("my résumé.docx"), "my r_sum_.docx");
+
+        assert!(store.save("telegram", "1", "big.bin", &vec![0; 2 * 1024 * 1024]).is_err());
+
+        let note = upload_note("report.pdf", &first, 2048);
+        assert!(note.contains("\"report.pdf\" (2.0 KB)"), "{note}");
+        let notes = [note.clone()];
+        assert_eq!(with_notes("  ", &notes), note);
+        assert_eq!(with_notes("summarize this", &notes), format!("summarize this\n\n{note}"));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
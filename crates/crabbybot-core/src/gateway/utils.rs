@@ -1,34 +1,229 @@
 //! Shared gateway utilities.
 
-/// Split a message into chunks of at most `max_len` characters,
-/// preferring to break at newlines when possible.
+/// Split a message into chunks of at most `max_len` bytes for platforms
+/// with a message length limit (Telegram, Discord).
 ///
-/// Used by both the Telegram and Discord transports to respect
-/// platform-specific message length limits.
+/// Breaks go between paragraphs where possible, then between lines, then
+/// at a space outside inline markup (`` `code` ``, `**bold**`, links), and
+/// only as a last resort mid-word, never inside a UTF-8 character or an
+/// HTML entity. A fenced code block split across chunks is closed at the
+/// end of one and reopened, with its language, at the start of the next.
 pub fn chunk_message(text: &str, max_len: usize) -> Vec<String> {
     if text.len() <= max_len {
         return vec![text.to_owned()];
     }
+    let mut chunker = Chunker {
+        max_len,
+        chunks: Vec::new(),
+        current: String::new(),
+    };
+    for block in blocks(text) {
+        chunker.push_block(&block);
+    }
+    chunker.flush();
+    chunker.chunks
+}
 
-    let mut chunks = Vec::new();
-    let mut remaining = text;
+/// A paragraph, or a fenced code block with its fence lines.
+struct Block<'a> {
+    lines: Vec<&'a str>,
+    /// The opening fence line (e.g. "```rust") of a code block.
+    fence: Option<&'a str>,
+}
 
-    while !remaining.is_empty() {
-        if remaining.len() <= max_len {
-            chunks.push(remaining.to_owned());
-            break;
+/// Split `text` into blocks at blank lines outside code fences.
+fn blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        match fence {
+            Some(open) => {
+                lines.push(line);
+                if fence_marker(open).is_some_and(|m| line.trim() == m) {
+                    blocks.push(Block { lines: std::mem::take(&mut lines), fence: Some(open) });
+                    fence = None;
+                }
+            }
+            None if fence_marker(line).is_some() => {
+                if !lines.is_empty() {
+                    blocks.push(Block { lines: std::mem::take(&mut lines), fence: None });
+                }
+                lines.push(line);
+                fence = Some(line);
+            }
+            None if line.trim().is_empty() => {
+                if !lines.is_empty() {
+                    blocks.push(Block { lines: std::mem::take(&mut lines), fence: None });
+                }
+            }
+            None => lines.push(line),
         }
+    }
+    if !lines.is_empty() {
+        blocks.push(Block { lines, fence });
+    }
+    blocks
+}
 
-        // Try to find a newline to break at
-        let slice = &remaining[..max_len];
-        let break_at = slice.rfind('\n').unwrap_or(max_len);
-        let break_at = if break_at == 0 { max_len } else { break_at };
+/// "```" or "~~~" when `line` opens or closes a code fence.
+fn fence_marker(line: &str) -> Option<&'static str> {
+    let line = line.trim_start();
+    ["```", "~~~"].into_iter().find(|m| line.starts_with(m))
+}
+
+struct Chunker {
+    max_len: usize,
+    chunks: Vec<String>,
+    current: String,
+}
+
+impl Chunker {
+    fn flush(&mut self) {
+        if !self.current.trim().is_empty() {
+            self.chunks.push(std::mem::take(&mut self.current));
+        }
+        self.current.clear();
+    }
+
+    /// Append `text` to the current chunk after `sep`, starting a new chunk
+    /// if it doesn't fit. `text` must fit in an empty chunk.
+    fn append(&mut self, sep: &str, text: &str) {
+        if !self.current.is_empty() && self.current.len() + sep.len() + text.len() > self.max_len {
+            self.flush();
+        }
+        if !self.current.is_empty() {
+            self.current.push_str(sep);
+        }
+        self.current.push_str(text);
+    }
+
+    fn push_block(&mut self, block: &Block<'_>) {
+        let whole = block.lines.join("\n");
+        if whole.len() <= self.max_len {
+            self.append("\n\n", &whole);
+            return;
+        }
+        self.flush();
+        if let Some(open) = block.fence {
+            self.push_code(block, open);
+            return;
+        }
+        for line in &block.lines {
+            for piece in split_line(line, self.max_len) {
+                self.append("\n", piece);
+            }
+        }
+    }
+
+    /// Spread a code block over several chunks, each closed and reopened.
+    fn push_code(&mut self, block: &Block<'_>, open: &str) {
+        let marker = fence_marker(open).unwrap_or("```");
+        let body = match block.lines.last() {
+            Some(last) if block.lines.len() > 1 && fence_marker(last).is_some() => &block.lines[1..block.lines.len() - 1],
+            _ => &block.lines[1..],
+        };
+        // Room for the reopened fence, a newline, and "\n```" to close.
+        let budget = self.max_len.saturating_sub(open.len() + 1 + marker.len() + 1).max(1);
+        let mut part = String::new();
+        for line in body {
+            for piece in split_line(line, budget) {
+                if !part.is_empty() && part.len() + 1 + piece.len() > budget {
+                    self.current = format!("{}\n{}\n{}", open, part, marker);
+                    self.flush();
+                    part.clear();
+                }
+                if !part.is_empty() {
+                    part.push('\n');
+                }
+                part.push_str(piece);
+            }
+        }
+        self.current = format!("{}\n{}\n{}", open, part, marker);
+    }
+}
+
+/// Cut `line` into pieces of at most `max_len` bytes.
+fn split_line(line: &str, max_len: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.len() > max_len {
+        let cut = break_at(rest, max_len);
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() || pieces.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
 
-        chunks.push(remaining[..break_at].to_owned());
-        remaining = remaining[break_at..].trim_start_matches('\n');
+/// Where to cut `text` so the head is at most `max_len` bytes: the last
+/// space outside inline markup, else the last character boundary that
+/// isn't inside an HTML entity like `&amp;`. Always at least one character.
+fn break_at(text: &str, max_len: usize) -> usize {
+    let mut in_code = false;
+    let mut depth = 0i32;
+    let mut bold = false;
+    let mut space = None;
+    let mut prev = '\0';
+    for (i, c) in text.char_indices() {
+        if i > max_len {
+            break;
+        }
+        match c {
+            '`' => in_code = !in_code,
+            '[' | '(' if !in_code => depth += 1,
+            ']' | ')' if !in_code => depth = (depth - 1).max(0),
+            '*' if !in_code && prev == '*' => bold = !bold,
+            ' ' if i > 0 && !in_code && depth == 0 && !bold => space = Some(i),
+            _ => {}
+        }
+        prev = c;
+    }
+    if let Some(i) = space {
+        return i;
+    }
+    let mut cut = max_len.min(text.len());
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    // Step back out of an entity such as `&amp;` or `&#128512;`.
+    if let Some(amp) = text[..cut].rfind('&') {
+        let entity = &text[amp + 1..cut];
+        if amp > 0 && cut - amp <= 10 && entity.chars().all(|c| c.is_ascii_alphanumeric() || c == '#') {
+            cut = amp;
+        }
     }
+    if cut == 0 {
+        cut = text.chars().next().map_or(0, char::len_utf8);
+    }
+    cut
+}
+
+/// Whether a file should be sent as a photo rather than a document.
+pub fn is_image(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp"))
+}
 
-    chunks
+/// Whether `id` is safe to use in chat IDs, session keys and file names.
+pub fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Compare secrets without short-circuiting on the first differing byte.
+/// An empty `expected` never matches.
+pub fn token_matches(expected: &str, provided: &str) -> bool {
+    if expected.is_empty() || expected.len() != provided.len() {
+        return false;
+    }
+    expected
+        .bytes()
+        .zip(provided.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
 }
 
 #[cfg(test)]
@@ -67,4 +262,48 @@ mod tests {
         assert_eq!(chunks[0].len(), 2000);
         assert_eq!(chunks[1].len(), 1000);
     }
+
+    #[test]
+    fn test_chunk_reopens_code_fences() {
+        let code: Vec<String> = (0..30).map(|i| format!("let x{i} = {i};")).collect();
+        let text = format!("Here you go:\n\n```rust\n{}\n```\n\nDone.", code.join("\n"));
+        let chunks = chunk_message(&text, 200);
+        assert!(chunks.len() > 2);
+        for chunk in &chunks {
+            assert!(chunk.len() <= 200, "{chunk}");
+            assert_eq!(chunk.matches("```").count() % 2, 0, "unbalanced fence in {chunk:?}");
+        }
+        assert!(chunks[1].starts_with("```rust\nlet x"), "{:?}", chunks[1]);
+        let rejoined: String = chunks.concat();
+        for line in &code {
+            assert!(rejoined.contains(line.as_str()), "{line} lost");
+        }
+    }
+
+    #[test]
+    fn test_chunk_prefers_paragraphs_and_keeps_entities() {
+        let text = format!("{}\n\n{}", "a ".repeat(40).trim_end(), "b ".repeat(40).trim_end());
+        assert_eq!(chunk_message(&text, 100), ["a ".repeat(40).trim_end(), "b ".repeat(40).trim_end()]);
+
+        let emoji = "🦀".repeat(30);
+        let chunks = chunk_message(&emoji, 50);
+        assert!(chunks.iter().all(|c| c.len() <= 50 && c.chars().all(|ch| ch == '🦀')));
+        assert_eq!(chunks.concat(), emoji);
+
+        let markup = format!("{} `keep this together` and [a link](https://example.com/x)", "w".repeat(30));
+        let chunks = chunk_message(&markup, 45);
+        assert!(chunks.iter().any(|c| c.contains("`keep this together`")), "{chunks:?}");
+        assert!(chunks.iter().any(|c| c.contains("[a link](https://example.com/x)")), "{chunks:?}");
+
+        let entities = format!("{}&amp;&amp;", "x".repeat(20));
+        let chunks = chunk_message(&entities, 23);
+        assert_eq!(chunks, ["x".repeat(20), "&amp;&amp;".to_string()]);
+    }
+
+    #[test]
+    fn test_is_image() {
+        assert!(is_image(std::path::Path::new("charts/sol.PNG")));
+        assert!(!is_image(std::path::Path::new("report.pdf")));
+        assert!(!is_image(std::path::Path::new("png")));
+    }
 }
@@ -0,0 +1,357 @@
+//! Declarative guardrails: config rules that block, hold or annotate tool
+//! calls.
+//!
+//! Each rule in `guardrails.rules` names conditions on the call (tool name,
+//! substrings of its arguments, the user making it, the time of day, the
+//! USD amount the tool reports through
+//! [`Tool::trade_usd`](crate::tools::Tool::trade_usd)) and what happens when
+//! all of them hold:
+//!
+//! - `block` refuses the call, even one signed off through an approval;
+//! - `requireApproval` files an approval request (see [`crate::approvals`])
+//!   with the rule's `required` and `approvers`;
+//! - `annotate` lets the call run and appends the rule's message to the
+//!   result the model sees.
+//!
+//! ```json
+//! "guardrails": {"rules": [
+//!   {"name": "no-rm", "tool": "shell_exec", "args": {"command": "rm -rf"}, "action": "block",
+//!    "message": "Recursive deletes are off limits"},
+//!   {"name": "night-orders", "tool": "polymarket_*", "hours": "00:00-07:00",
+//!    "action": "requireApproval", "required": 1, "approvers": ["111"]}
+//! ]}
+//! ```
+
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::agent::locale::parse_timezone;
+use crate::config::{ApprovalRule, GuardrailAction, GuardrailRule, GuardrailsConfig};
+
+/// A time-of-day window such as `22:00-06:00`; it may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Hours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for Hours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| format!("'{}' is not a HH:MM time", t.trim()))
+        };
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("'{}' is not a range like 22:00-06:00", s))?;
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+/// A tool call as the rules see it.
+#[derive(Debug, Clone, Copy)]
+pub struct Call<'a> {
+    pub tool: &'a str,
+    pub args: &'a HashMap<String, Value>,
+    /// USD the call commits, when the tool reports it.
+    pub usd: Option<f64>,
+    pub user_id: &'a str,
+}
+
+/// What the rules decided for one call.
+#[derive(Debug, Default)]
+pub struct Verdict<'a> {
+    /// The first matching `block` rule.
+    pub blocked: Option<&'a GuardrailRule>,
+    /// The first matching `requireApproval` rule.
+    pub approval: Option<&'a GuardrailRule>,
+    /// Messages of the matching `annotate` rules.
+    pub notes: Vec<String>,
+}
+
+/// A rule with its window and timezone parsed.
+struct Compiled {
+    rule: GuardrailRule,
+    hours: Option<Hours>,
+    timezone: Option<Tz>,
+}
+
+/// A rule whose hours or timezone don't parse.
+#[derive(Debug, thiserror::Error)]
+#[error("guardrails.rules[{index}] ({label}): {reason}")]
+pub struct GuardrailError {
+    pub index: usize,
+    pub label: String,
+    pub reason: String,
+}
+
+/// The configured rules, ready to check calls against.
+pub struct Guardrails {
+    rules: Vec<Compiled>,
+}
+
+impl Guardrails {
+    /// Compile the rules, failing on the first one whose hours or timezone
+    /// don't parse. `default_timezone` applies to rules that set none.
+    pub fn new(config: &GuardrailsConfig, default_timezone: Option<&str>) -> Result<Self, GuardrailError> {
+        let default_tz = default_timezone.and_then(|tz| parse_timezone(tz).ok());
+        let rules = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let compiled = || -> Result<Compiled, String> {
+                    Ok(Compiled {
+                        hours: rule.hours.as_deref().map(str::parse).transpose()?,
+                        timezone: match rule.timezone.as_deref() {
+                            Some(tz) => Some(parse_timezone(tz)?),
+                            None => default_tz,
+                        },
+                        rule: rule.clone(),
+                    })
+                };
+                compiled().map_err(|reason| GuardrailError {
+                    index,
+                    label: rule.label().to_string(),
+                    reason,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// A single rule blocking every call, for when the configured rules
+    /// can't be compiled: better to refuse everything than to run calls a
+    /// rule was meant to stop.
+    pub fn deny_all(reason: impl Into<String>) -> Self {
+        Self {
+            rules: vec![Compiled {
+                rule: GuardrailRule {
+                    name: "invalid-guardrails".into(),
+                    action: GuardrailAction::Block,
+                    message: reason.into(),
+                    ..Default::default()
+                },
+                hours: None,
+                timezone: None,
+            }],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule may hold a call for approval.
+    pub fn requires_approvals(&self) -> bool {
+        self.rules.iter().any(|c| c.rule.action == GuardrailAction::RequireApproval)
+    }
+
+    /// Check `call` against every rule at `now`.
+    pub fn check(&self, call: &Call<'_>, now: DateTime<Utc>) -> Verdict<'_> {
+        let mut verdict = Verdict::default();
+        for compiled in self.rules.iter().filter(|c| c.matches(call, now)) {
+            let rule = &compiled.rule;
+            match rule.action {
+                GuardrailAction::Block => {
+                    verdict.blocked.get_or_insert(rule);
+                }
+                GuardrailAction::RequireApproval => {
+                    verdict.approval.get_or_insert(rule);
+                }
+                GuardrailAction::Annotate => verdict.notes.push(format!("⚠️ {}: {}", rule.label(), rule.message)),
+            }
+        }
+        verdict
+    }
+}
+
+impl Compiled {
+    fn matches(&self, call: &Call<'_>, now: DateTime<Utc>) -> bool {
+        let rule = &self.rule;
+        let tool_matches = match rule.tool.strip_suffix('*') {
+            _ if rule.tool.is_empty() => true,
+            Some(prefix) => call.tool.starts_with(prefix),
+            None => call.tool == rule.tool,
+        };
+        if !tool_matches {
+            return false;
+        }
+        if !rule.users.is_empty() && !rule.users.iter().any(|u| u == call.user_id) {
+            return false;
+        }
+        if rule.min_usd > 0.0 && !call.usd.is_some_and(|usd| usd >= rule.min_usd) {
+            return false;
+        }
+        if let Some(hours) = self.hours {
+            let local = match self.timezone {
+                Some(tz) => now.with_timezone(&tz).time(),
+                None => now.with_timezone(&chrono::Local).time(),
+            };
+            if !hours.contains(local) {
+                return false;
+            }
+        }
+        rule.args.iter().all(|(name, needle)| {
+            let needle = needle.to_lowercase();
+            let contains = |v: &Value| arg_text(v).to_lowercase().contains(&needle);
+            if name == "*" {
+                call.args.values().any(contains)
+            } else {
+                call.args.get(name).is_some_and(contains)
+            }
+        })
+    }
+}
+
+/// An argument as text: strings as they are, anything else as JSON.
+fn arg_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl GuardrailRule {
+    /// The rule's name, or its tool when unnamed.
+    pub fn label(&self) -> &str {
+        match (self.name.as_str(), self.tool.as_str()) {
+            ("", "") => "guardrail",
+            ("", tool) => tool,
+            (name, _) => name,
+        }
+    }
+
+    /// Why a blocked call was refused.
+    pub fn reason(&self) -> &str {
+        if self.message.is_empty() {
+            "not allowed by the configured guardrails"
+        } else {
+            &self.message
+        }
+    }
+
+    /// The approval rule a `requireApproval` match files its request under.
+    pub fn approval_rule(&self) -> ApprovalRule {
+        ApprovalRule {
+            tool: self.tool.clone(),
+            min_usd: self.min_usd,
+            required: self.required.max(1),
+            approvers: self.approvers.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    fn rule(name: &str, tool: &str, action: GuardrailAction) -> GuardrailRule {
+        GuardrailRule {
+            name: name.into(),
+            tool: tool.into(),
+            action,
+            message: format!("{} matched", name),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hours_wrap_past_midnight() {
+        let night: Hours = "22:00-06:00".parse().unwrap();
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert!(night.contains(at(23, 30)) && night.contains(at(5, 59)));
+        assert!(!night.contains(at(6, 0)) && !night.contains(at(12, 0)));
+        let day: Hours = "09:00-17:00".parse().unwrap();
+        assert!(day.contains(at(9, 0)) && !day.contains(at(17, 0)));
+        assert!("9-5".parse::<Hours>().is_err());
+        assert!("nightly".parse::<Hours>().is_err());
+    }
+
+    #[test]
+    fn test_rules_match_tool_args_user_time_and_amount() {
+        let config = GuardrailsConfig {
+            rules: vec![
+                GuardrailRule {
+                    args: BTreeMap::from([("command".into(), "RM -RF".into())]),
+                    ..rule("no-rm", "shell_exec", GuardrailAction::Block)
+                },
+                GuardrailRule {
+                    hours: Some("00:00-07:00".into()),
+                    required: 2,
+                    approvers: vec!["1".into(), "2".into()],
+                    ..rule("night-orders", "polymarket_*", GuardrailAction::RequireApproval)
+                },
+                GuardrailRule {
+                    min_usd: 100.0,
+                    users: vec!["7".into()],
+                    ..rule("big-trade", "polymarket_*", GuardrailAction::Annotate)
+                },
+            ],
+        };
+        let guardrails = Guardrails::new(&config, Some("Europe/Berlin")).unwrap();
+        assert!(guardrails.requires_approvals());
+        let args = |k: &str, v: &str| HashMap::from([(k.to_string(), Value::from(v))]);
+        let call = |tool, args, usd, user_id| Call { tool, args, usd, user_id };
+        // 23:30 UTC is 00:30 in Berlin in winter.
+        let night = Utc.with_ymd_and_hms(2026, 1, 5, 23, 30, 0).unwrap();
+        let noon = Utc.with_ymd_and_hms(2026, 1, 5, 11, 0, 0).unwrap();
+
+        let rm = args("command", "cd /tmp && rm -rf build");
+        assert_eq!(guardrails.check(&call("shell_exec", &rm, None, "7"), noon).blocked.unwrap().name, "no-rm");
+        let ls = args("command", "ls -la");
+        assert!(guardrails.check(&call("shell_exec", &ls, None, "7"), noon).blocked.is_none());
+
+        let order = args("price", "0.42");
+        let verdict = guardrails.check(&call("polymarket_create_order", &order, Some(250.0), "7"), night);
+        let approval = verdict.approval.unwrap().approval_rule();
+        assert_eq!((approval.required, approval.approvers.len()), (2, 2));
+        assert_eq!(verdict.notes, ["⚠️ big-trade: big-trade matched"]);
+
+        let verdict = guardrails.check(&call("polymarket_create_order", &order, Some(50.0), "8"), noon);
+        assert!(verdict.approval.is_none() && verdict.notes.is_empty());
+    }
+
+    #[test]
+    fn test_rules_with_bad_hours_are_rejected() {
+        let mut config = GuardrailsConfig {
+            rules: vec![
+                rule("fine", "shell_exec", GuardrailAction::Annotate),
+                GuardrailRule {
+                    hours: Some("late".into()),
+                    ..rule("broken", "shell_exec", GuardrailAction::Block)
+                },
+            ],
+        };
+        let err = Guardrails::new(&config, None).err().unwrap();
+        assert_eq!((err.index, err.label.as_str()), (1, "broken"));
+        config.rules[1].hours = None;
+        config.rules[1].timezone = Some("Mars/Olympus".into());
+        assert!(Guardrails::new(&config, None).is_err());
+
+        let empty = HashMap::new();
+        let call = Call { tool: "read_file", args: &empty, usd: None, user_id: "7" };
+        let deny = Guardrails::deny_all("bad config");
+        let verdict = deny.check(&call, Utc::now());
+        assert_eq!(verdict.blocked.unwrap().reason(), "bad config");
+    }
+}
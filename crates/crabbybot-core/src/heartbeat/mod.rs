@@ -30,6 +30,7 @@
 //! # }
 //! ```
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
@@ -37,6 +38,8 @@ use tokio_util::sync::CancellationToken;
 use tracing::info;
 
 use crate::bus::events::InboundMessage;
+use crate::clock::{self, SharedClock};
+use crate::usage::UsageTracker;
 
 /// A proactive wake-up trigger.
 ///
@@ -47,6 +50,9 @@ pub struct Heartbeat {
     message: String,
     channel: String,
     chat_id: String,
+    /// Skip beats while the daily token budget is nearly used up.
+    usage: Option<Arc<UsageTracker>>,
+    clock: SharedClock,
 }
 
 impl Heartbeat {
@@ -72,12 +78,19 @@ impl Heartbeat {
                     info!("Heartbeat cancelled");
                     return;
                 }
-                _ = tokio::time::sleep(self.interval) => {
+                _ = self.clock.sleep(self.interval) => {
+                    if let Some(ref usage) = self.usage {
+                        if !usage.admit_background("heartbeat").await {
+                            continue;
+                        }
+                    }
                     let msg = InboundMessage {
                         channel: self.channel.clone(),
                         chat_id: self.chat_id.clone(),
                         user_id: "heartbeat".into(),
+                        sender_name: None,
                         content: self.message.clone(),
+                        message_id: None,
                         media: Vec::new(),
                         is_system: true,
                     };
@@ -103,6 +116,8 @@ pub struct HeartbeatBuilder {
     message: Option<String>,
     channel: Option<String>,
     chat_id: Option<String>,
+    usage: Option<Arc<UsageTracker>>,
+    clock: Option<SharedClock>,
 }
 
 impl HeartbeatBuilder {
@@ -130,6 +145,19 @@ impl HeartbeatBuilder {
         self
     }
 
+    /// Skip beats once the tracker's daily token budget is nearly used up.
+    /// Leave unset for critical heartbeats.
+    pub fn usage_budget(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Wait on `clock` instead of the system clock.
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Build the [`Heartbeat`].
     ///
     /// # Panics
@@ -144,6 +172,8 @@ impl HeartbeatBuilder {
                 .expect("Heartbeat::builder: message is required"),
             channel: self.channel.unwrap_or_else(|| "cli".into()),
             chat_id: self.chat_id.unwrap_or_else(|| "direct".into()),
+            usage: self.usage,
+            clock: self.clock.unwrap_or_else(clock::system),
         }
     }
 }
@@ -183,6 +213,37 @@ mod tests {
         cancel.cancel();
     }
 
+    /// Verify that beats follow an injected clock, not the wall clock.
+    #[tokio::test]
+    async fn test_heartbeat_follows_mock_clock() {
+        use crate::clock::MockClock;
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let cancel = CancellationToken::new();
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+
+        let hb = Heartbeat::builder()
+            .interval(Duration::from_secs(3600))
+            .message("hourly")
+            .clock(clock.clone())
+            .build();
+        tokio::spawn(hb.run(tx, cancel.clone()));
+        tokio::task::yield_now().await;
+
+        clock.advance(chrono::Duration::minutes(30));
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err(), "heartbeat fired before its interval");
+
+        clock.advance(chrono::Duration::minutes(30));
+        let msg = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("heartbeat did not fire once the clock reached its interval")
+            .expect("channel closed");
+        assert_eq!(msg.content, "hourly");
+
+        cancel.cancel();
+    }
+
     /// Verify that cancelling stops the heartbeat.
     #[tokio::test]
     async fn test_heartbeat_cancels() {
@@ -0,0 +1,239 @@
+//! Decision journal: why the agent traded, written down before it did.
+//!
+//! Before a call that places an order (a tool reporting
+//! [`Tool::trade_usd`](crate::tools::Tool::trade_usd)) runs, the registry
+//! wants a decision recorded in the same chat within the last
+//! [`OPEN_MINUTES`]: the thesis, the inputs it rests on, the expected
+//! outcome and the condition that would prove it wrong. The agent records
+//! one with the `journal_decision` tool; the autonomous betting engine
+//! writes its own. The trade that follows is linked to the entry with the
+//! tool's result, so a later review can hold outcomes against intentions.
+//!
+//! Entries are appended to `journal/decisions.jsonl` in the workspace.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// How long a recorded decision stays open for the trade it announces.
+pub const OPEN_MINUTES: i64 = 30;
+
+/// Characters of a trade's result kept on its entry.
+const RESULT_CHARS: usize = 500;
+
+/// What the agent writes down before trading.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecisionDraft {
+    /// The trade, e.g. "buy 50 YES on 'Fed cuts in March' at 0.42".
+    pub trade: String,
+    pub thesis: String,
+    /// Data the decision rests on: prices, news, model outputs.
+    pub inputs: Vec<String>,
+    pub expected_outcome: String,
+    /// When the position should be closed or the thesis dropped.
+    pub stop_condition: String,
+    pub size_usd: Option<f64>,
+}
+
+impl DecisionDraft {
+    /// Names of the required fields left empty.
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            ("trade", &self.trade),
+            ("thesis", &self.thesis),
+            ("expected_outcome", &self.expected_outcome),
+            ("stop_condition", &self.stop_condition),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// A recorded decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub id: String,
+    pub recorded_at: DateTime<Utc>,
+    pub channel: String,
+    pub chat_id: String,
+    #[serde(flatten)]
+    pub draft: DecisionDraft,
+    /// The trade made on this decision, once there is one.
+    #[serde(default)]
+    pub execution: Option<Execution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub tool: String,
+    pub at: DateTime<Utc>,
+    pub result: String,
+}
+
+impl Decision {
+    /// The entry as a short Markdown block, for reviews.
+    pub fn render(&self) -> String {
+        let d = &self.draft;
+        let size = d.size_usd.map(|u| format!(" (${:.2})", u)).unwrap_or_default();
+        let mut out = format!(
+            "**#{}** {} UTC — {}{}\n- Thesis: {}\n",
+            self.id,
+            self.recorded_at.format("%Y-%m-%d %H:%M"),
+            d.trade,
+            size,
+            d.thesis
+        );
+        if !d.inputs.is_empty() {
+            out.push_str(&format!("- Inputs: {}\n", d.inputs.join("; ")));
+        }
+        out.push_str(&format!(
+            "- Expected: {}\n- Stop: {}\n",
+            d.expected_outcome, d.stop_condition
+        ));
+        match &self.execution {
+            Some(e) => out.push_str(&format!(
+                "- Executed via `{}` at {} UTC: {}\n",
+                e.tool,
+                e.at.format("%Y-%m-%d %H:%M"),
+                e.result.lines().next().unwrap_or_default()
+            )),
+            None => out.push_str("- Not executed\n"),
+        }
+        out
+    }
+}
+
+/// Shared, file-backed decision journal.
+pub struct DecisionJournal {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl DecisionJournal {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            path: workspace.join("journal").join("decisions.jsonl"),
+            lock: Mutex::default(),
+        }
+    }
+
+    /// Append a decision made in `channel`/`chat_id`.
+    pub fn record(&self, draft: DecisionDraft, channel: &str, chat_id: &str) -> std::io::Result<Decision> {
+        let mut id = uuid::Uuid::new_v4().simple().to_string();
+        id.truncate(6);
+        let decision = Decision {
+            id,
+            recorded_at: Utc::now(),
+            channel: channel.to_string(),
+            chat_id: chat_id.to_string(),
+            draft,
+            execution: None,
+        };
+        let line = serde_json::to_string(&decision).map_err(std::io::Error::other)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(decision)
+    }
+
+    /// The latest decision in the chat that no trade has used yet and that
+    /// was recorded within [`OPEN_MINUTES`] of `now`.
+    pub fn open_for(&self, channel: &str, chat_id: &str, now: DateTime<Utc>) -> Option<Decision> {
+        let cutoff = now - Duration::minutes(OPEN_MINUTES);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.load().into_iter().rev().find(|d| {
+            d.channel == channel && d.chat_id == chat_id && d.execution.is_none() && d.recorded_at > cutoff
+        })
+    }
+
+    /// Attach the trade made on decision `id`.
+    pub fn link(&self, id: &str, tool: &str, result: &str) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.load();
+        let Some(decision) = all.iter_mut().find(|d| d.id == id) else {
+            return Ok(());
+        };
+        decision.execution = Some(Execution {
+            tool: tool.to_string(),
+            at: Utc::now(),
+            result: result.chars().take(RESULT_CHARS).collect(),
+        });
+        let mut out = String::new();
+        for d in &all {
+            out.push_str(&serde_json::to_string(d).map_err(std::io::Error::other)?);
+            out.push('\n');
+        }
+        std::fs::write(&self.path, out)
+    }
+
+    /// Decisions recorded after `cutoff`, oldest first.
+    pub fn since(&self, cutoff: DateTime<Utc>) -> Vec<Decision> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.load().into_iter().filter(|d| d.recorded_at > cutoff).collect()
+    }
+
+    /// Every readable entry; a damaged line is skipped.
+    fn load(&self) -> Vec<Decision> {
+        std::fs::read_to_string(&self.path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(trade: &str) -> DecisionDraft {
+        DecisionDraft {
+            trade: trade.into(),
+            thesis: "Market underprices a March cut".into(),
+            inputs: vec!["CME FedWatch 61%".into()],
+            expected_outcome: "YES reaches 0.60 by March".into(),
+            stop_condition: "Exit below 0.30".into(),
+            size_usd: Some(21.0),
+        }
+    }
+
+    #[test]
+    fn test_decision_opens_until_a_trade_uses_it() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_journal_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let journal = DecisionJournal::new(&dir);
+        let now = Utc::now();
+        assert!(journal.open_for("telegram", "42", now).is_none());
+
+        let decision = journal.record(draft("buy 50 YES at 0.42"), "telegram", "42").unwrap();
+        assert_eq!(journal.open_for("telegram", "42", now).unwrap().id, decision.id);
+        assert!(journal.open_for("telegram", "7", now).is_none());
+        assert!(journal.open_for("telegram", "42", now + Duration::minutes(OPEN_MINUTES + 1)).is_none());
+
+        journal.link(&decision.id, "polymarket_create_order", "✅ Order placed").unwrap();
+        assert!(journal.open_for("telegram", "42", now).is_none());
+        let all = journal.since(now - Duration::days(7));
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].execution.as_ref().unwrap().tool, "polymarket_create_order");
+        assert!(all[0].render().contains("Executed via `polymarket_create_order`"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let mut d = draft("buy");
+        assert!(d.missing().is_empty());
+        d.stop_condition = " ".into();
+        d.thesis.clear();
+        assert_eq!(d.missing(), ["thesis", "stop_condition"]);
+    }
+}
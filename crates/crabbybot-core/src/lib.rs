@@ -5,17 +5,37 @@
 //! - [`config`] — Typed configuration loading from JSON
 //! - [`provider`] — LLM provider trait and OpenAI-compatible implementation
 //! - [`bus`] — Async message bus for channel-agent decoupling
+//! - [`commands`] — Slash commands shared by the chat channels and the CLI chat
 //! - [`tools`] — Tool trait, registry, and built-in filesystem/shell/web tools
 //! - [`agent`] — Agent loop, memory, skills, and context building
 //! - [`session`] — Conversation session persistence (JSONL)
 //! - [`cron`] — Scheduled task management
+//! - [`consolidate`] — Nightly summaries of the day's sessions into memory
+//! - [`clock`] — Injectable time source, with a mock clock for tests
+//! - [`usage`] — Daily token accounting and background-work budget
+//! - [`recovery`] — Corrupt state files and the restart report
+//! - [`telemetry`] — Opt-in daily report of noised usage counts
+//! - [`runtime`] — `AgentBuilder` / `Runtime::from_config` bootstrap
+//!
+//! Optional pieces sit behind cargo features: `gateway` (agent bridge,
+//! [`run_bot`] and [`run_repl`]), `telegram`, `discord`, `webchat` (browser
+//! chat UI), `websocket` (JSON socket for custom frontends), `webhooks`
+//! (external events as agent messages), `api` (`/v1` REST API),
+//! `crypto-tools` (Solana and token analysis), `polymarket` (Polymarket
+//! tools and betting engine), `data-tools` (table analysis), `charts` (PNG
+//! plots), `sync` (encrypted workspace sync to S3 or WebDAV), and `attach`
+//! ([`run_attach`], the CLI chat of a running bot).
 //!
 //! # Quick Start
 //!
 //! ```no_run
+//! use std::sync::Arc;
+//! use tokio::sync::Mutex;
 //! use crabbybot_core::config::Config;
-//! use crabbybot_core::provider::openai::OpenAiProvider;
+//! use crabbybot_core::provider::{openai::OpenAiProvider, LlmProvider};
 //! use crabbybot_core::agent::{AgentLoop, AgentConfig};
+//! use crabbybot_core::agent::locale::LocaleSettings;
+//! use crabbybot_core::agent::persona::PersonaSettings;
 //! use crabbybot_core::tools::ToolRegistry;
 //!
 //! // Load configuration
@@ -32,28 +52,68 @@
 //! let tools = ToolRegistry::new();
 //! let agent_config = AgentConfig {
 //!     model: Some(config.agents.defaults.model.clone()),
+//!     summary_model: config.agents.defaults.summary_model.clone(),
+//!     models: config.agents.defaults.models.clone(),
 //!     max_tokens: config.agents.defaults.max_tokens,
 //!     max_context_tokens: 30_000,
 //!     temperature: config.agents.defaults.temperature,
 //!     max_iterations: config.agents.defaults.max_tool_iterations,
+//!     max_continuations: config.agents.defaults.max_continuations,
+//!     stream: config.agents.defaults.stream,
 //!     workspace: config.workspace_path(),
+//!     chat_workspaces: config.chat_workspaces(),
+//!     locale: LocaleSettings::from_config(&config.agents),
+//!     persona: PersonaSettings::from_config(&config.agents),
+//!     webhook_tools: Default::default(),
 //! };
 //!
+//! let provider: Box<dyn LlmProvider> = Box::new(provider);
 //! let mut agent = AgentLoop::new(Arc::new(Mutex::new(provider)), Arc::new(tools), agent_config);
 //! ```
 
 pub mod agent;
+pub mod alerts;
+pub mod approvals;
+pub mod backup;
 pub mod bus;
+pub mod clock;
+pub mod commands;
 pub mod config;
+pub mod consolidate;
 pub mod cron;
+#[cfg(feature = "gateway")]
 pub mod gateway;
+pub mod guardrails;
 pub mod heartbeat;
+pub mod journal;
 pub mod provider;
+pub mod recovery;
+pub mod runtime;
+pub mod scheduler;
+pub mod selftest;
 pub mod service;
 pub mod session;
+pub mod support;
+#[cfg(feature = "sync")]
+pub mod sync;
+pub mod telemetry;
+pub mod templates;
 pub mod tools;
+pub mod update;
+pub mod usage;
 pub mod vault;
 
+pub use config::ConfigError;
+pub use cron::CronError;
+pub use provider::ProviderError;
+pub use session::SessionError;
+pub use tools::ToolError;
+
+#[cfg(feature = "gateway")]
+pub use runtime::{run_bot, run_repl};
+#[cfg(feature = "attach")]
+pub use runtime::run_attach;
+
 // ── Process-wide restart signal ──────────────────────────────────────────────
 
 use std::sync::atomic::{AtomicBool, Ordering};
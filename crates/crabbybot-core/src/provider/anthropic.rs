@@ -0,0 +1,789 @@
+//! Native Anthropic provider.
+//!
+//! Talks to the Messages API (`POST {base}/messages`) directly instead of
+//! Anthropic's OpenAI-compatible endpoint, which gives access to what the
+//! shim leaves out:
+//!
+//! - **Extended thinking** (`thinkingBudget`): the model thinks before it
+//!   answers. Thinking never reaches the session or the user, but the
+//!   signed thinking blocks of a tool-use turn are kept in memory and sent
+//!   back with that turn, as the API requires.
+//! - **Prompt caching** (`promptCaching`): the tool definitions, the system
+//!   prompt and the conversation so far are marked as cache breakpoints, so
+//!   follow-up requests re-read them at a fraction of the input price.
+//!
+//! Messages are converted from the OpenAI shape the agent uses: system
+//! messages become the `system` parameter, tool calls `tool_use` blocks and
+//! tool results `tool_result` blocks in a user turn.
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+use super::endpoints::{EndpointPool, EndpointStrategy};
+use super::types::{ChatMessage, LlmResponse, ToolCallRequest, ToolDefinition, Usage};
+use super::{repair, LlmProvider, OnDelta, ProviderError};
+
+/// Default API base URL.
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+/// Value of the `anthropic-version` header.
+const API_VERSION: &str = "2023-06-01";
+
+/// Smallest thinking budget the API accepts.
+pub const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Tool-use turns whose thinking blocks are kept for replay.
+const THINKING_CACHE_SIZE: usize = 64;
+
+/// Provider for the native Anthropic Messages API.
+///
+/// Retries transient HTTP errors (429, 5xx and 529 "overloaded") and
+/// network failures with exponential backoff, moving on to the next
+/// endpoint first when several are configured.
+pub struct AnthropicProvider {
+    client: Client,
+    api_key: String,
+    endpoints: EndpointPool,
+    default_model: String,
+    sequential_tool_models: Vec<String>,
+    thinking_budget: u32,
+    prompt_caching: bool,
+    /// Thinking blocks of recent tool-use turns, keyed by the turn's first
+    /// tool call ID.
+    thinking: Mutex<ThinkingCache>,
+}
+
+#[derive(Default)]
+struct ThinkingCache {
+    blocks: HashMap<String, Vec<Value>>,
+    order: VecDeque<String>,
+}
+
+impl ThinkingCache {
+    fn insert(&mut self, id: String, blocks: Vec<Value>) {
+        if self.blocks.insert(id.clone(), blocks).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > THINKING_CACHE_SIZE {
+            if let Some(old) = self.order.pop_front() {
+                self.blocks.remove(&old);
+            }
+        }
+    }
+}
+
+impl AnthropicProvider {
+    /// Create a provider for `api_base` (default [`DEFAULT_BASE_URL`]).
+    pub fn new(api_key: &str, api_base: Option<&str>, default_model: &str, client: Client) -> Self {
+        let base_url = api_base.unwrap_or(DEFAULT_BASE_URL).trim_end_matches('/').to_string();
+        debug!(base_url = %base_url, "Initialized native Anthropic provider");
+        Self {
+            client,
+            api_key: api_key.to_string(),
+            endpoints: EndpointPool::new(vec![base_url], EndpointStrategy::default()),
+            default_model: default_model.to_string(),
+            sequential_tool_models: Vec::new(),
+            thinking_budget: 0,
+            prompt_caching: false,
+            thinking: Mutex::new(ThinkingCache::default()),
+        }
+    }
+
+    /// Let the model think for up to `budget` tokens before answering.
+    /// 0 turns extended thinking off.
+    pub fn thinking_budget(mut self, budget: u32) -> Self {
+        self.thinking_budget = budget;
+        self
+    }
+
+    /// Mark tools, system prompt and history as prompt cache breakpoints.
+    pub fn prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
+    /// Extra base URLs for the same deployment, tried after the primary one
+    /// in the order `strategy` picks.
+    pub fn endpoints(mut self, extra: Vec<String>, strategy: EndpointStrategy) -> Self {
+        let mut urls = self.endpoints.urls().to_vec();
+        for url in extra {
+            let url = url.trim().trim_end_matches('/').to_string();
+            if !url.is_empty() && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        self.endpoints = EndpointPool::new(urls, strategy);
+        self
+    }
+
+    /// Models whose requests set `disable_parallel_tool_use`; a trailing
+    /// `*` matches by prefix.
+    pub fn sequential_tool_models(mut self, models: Vec<String>) -> Self {
+        self.sequential_tool_models = models;
+        self
+    }
+
+    fn is_sequential(&self, model: &str) -> bool {
+        super::matches_model(&self.sequential_tool_models, model)
+    }
+
+    /// The primary API base URL.
+    pub fn base_url(&self) -> &str {
+        &self.endpoints.urls()[0]
+    }
+
+    fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+        matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504 | 529)
+    }
+
+    fn request(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> MessagesRequest {
+        let model = model.unwrap_or(&self.default_model);
+        // OpenRouter-style IDs ("anthropic/claude-…") name the same models.
+        let model = model.strip_prefix("anthropic/").unwrap_or(model).to_string();
+        let (system, messages) = {
+            let cache = self.thinking.lock().unwrap_or_else(|e| e.into_inner());
+            convert_messages(messages, &cache)
+        };
+        let mut request = MessagesRequest {
+            tool_choice: (!tools.is_empty() && self.is_sequential(&model))
+                .then(|| json!({"type": "auto", "disable_parallel_tool_use": true})),
+            model,
+            // The thinking budget counts against `max_tokens`; the answer
+            // still gets the configured limit on top.
+            max_tokens: max_tokens + self.thinking_budget,
+            system,
+            messages,
+            tools: tools
+                .iter()
+                .map(|t| {
+                    json!({
+                        "name": t.function.name,
+                        "description": t.function.description,
+                        "input_schema": t.function.parameters,
+                    })
+                })
+                .collect(),
+            // Thinking only runs at the default temperature.
+            temperature: (self.thinking_budget == 0).then_some(temperature),
+            thinking: (self.thinking_budget > 0)
+                .then(|| json!({"type": "enabled", "budget_tokens": self.thinking_budget})),
+            stream: false,
+        };
+        if self.prompt_caching {
+            request.mark_cache_breakpoints();
+        }
+        request
+    }
+
+    /// POST `request` to `{base}/messages`, see [`EndpointPool::send`].
+    async fn send(&self, request: &MessagesRequest) -> Result<reqwest::Response, ProviderError> {
+        debug!(
+            model = request.model,
+            msg_count = request.messages.len(),
+            stream = request.stream,
+            "Sending Anthropic messages request"
+        );
+        let post = |base: &str| {
+            self.client
+                .post(format!("{}/messages", base))
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", API_VERSION)
+                .json(request)
+        };
+        let api_error = |status: reqwest::StatusCode, body: String| ProviderError::Api {
+            status: status.as_u16(),
+            message: serde_json::from_str::<ErrorBody>(&body)
+                .map(|e| e.error.message)
+                .unwrap_or(body),
+        };
+        self.endpoints.send(post, Self::is_retryable_status, api_error).await
+    }
+
+    /// Turn the response's content blocks into an [`LlmResponse`], keeping
+    /// the thinking of a tool-use turn for when it is sent back.
+    fn to_response(&self, blocks: Vec<Value>, stop_reason: Option<String>, usage: ApiUsage) -> LlmResponse {
+        let mut text = String::new();
+        let mut thinking = Vec::new();
+        let mut tool_calls = Vec::new();
+        for block in blocks {
+            match block["type"].as_str().unwrap_or_default() {
+                "text" => text.push_str(block["text"].as_str().unwrap_or_default()),
+                "thinking" | "redacted_thinking" => thinking.push(block),
+                "tool_use" => tool_calls.push(tool_call(&block)),
+                other => debug!(block = other, "Ignoring unknown content block"),
+            }
+        }
+        if !thinking.is_empty() {
+            debug!(blocks = thinking.len(), "Stripped model reasoning from reply");
+            if let Some(first) = tool_calls.first() {
+                self.thinking
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(first.id.clone(), thinking);
+            }
+        }
+
+        let usage = Usage {
+            prompt_tokens: usage.prompt_tokens(),
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.prompt_tokens() + usage.output_tokens,
+        };
+        let finish_reason = match stop_reason.as_deref() {
+            None | Some("end_turn" | "stop_sequence") => "stop".to_string(),
+            Some("tool_use") => "tool_calls".to_string(),
+            Some(other) => other.to_string(),
+        };
+        debug!(
+            finish_reason,
+            tool_calls = tool_calls.len(),
+            tokens = usage.total_tokens,
+            "Received LLM response"
+        );
+
+        let text = text.trim().to_string();
+        LlmResponse {
+            content: (!text.is_empty()).then_some(text),
+            tool_calls,
+            finish_reason,
+            usage,
+        }
+    }
+}
+
+/// A `tool_use` block as a tool call. Streamed blocks carry their input
+/// as the raw JSON text, which may need repair.
+fn tool_call(block: &Value) -> ToolCallRequest {
+    let id = block["id"].as_str().unwrap_or_default().to_string();
+    let name = block["name"].as_str().unwrap_or_default().to_string();
+    let parsed = match &block["input"] {
+        Value::Object(input) => Ok(input.clone()),
+        Value::String(raw) => repair::parse_arguments(raw).map(|(arguments, repaired)| {
+            if repaired {
+                debug!(tool = name, raw, "Repaired malformed tool arguments");
+            }
+            arguments
+        }),
+        _ => Ok(Map::new()),
+    };
+    match parsed {
+        Ok(arguments) => ToolCallRequest {
+            id,
+            name,
+            arguments,
+            parse_error: None,
+        },
+        Err(e) => {
+            warn!(tool = name, error = %e, "Failed to parse tool arguments");
+            ToolCallRequest {
+                id,
+                name,
+                arguments: Map::new(),
+                parse_error: Some(e),
+            }
+        }
+    }
+}
+
+/// Split the agent's messages into the `system` blocks and the turns of
+/// the Messages API. Consecutive turns of the same role are merged, since
+/// tool results come back as one user turn.
+fn convert_messages(messages: &[ChatMessage], thinking: &ThinkingCache) -> (Vec<Value>, Vec<Value>) {
+    let mut system = Vec::new();
+    let mut turns: Vec<Value> = Vec::new();
+    for message in messages {
+        let (role, blocks) = match message.role.as_str() {
+            "system" => {
+                system.extend(content_blocks(message.content.as_ref()));
+                continue;
+            }
+            "assistant" => {
+                let mut blocks = Vec::new();
+                if let Some(calls) = &message.tool_calls {
+                    if let Some(replay) = calls.first().and_then(|c| thinking.blocks.get(&c.id)) {
+                        blocks.extend(replay.iter().cloned());
+                    }
+                }
+                blocks.extend(content_blocks(message.content.as_ref()));
+                for call in message.tool_calls.iter().flatten() {
+                    let input = serde_json::from_str::<Value>(&call.function.arguments)
+                        .ok()
+                        .filter(Value::is_object)
+                        .unwrap_or_else(|| json!({}));
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": input,
+                    }));
+                }
+                ("assistant", blocks)
+            }
+            "tool" => {
+                // Images a tool returns go inside its result, as blocks.
+                let content = match &message.content {
+                    Some(Value::Array(_)) => json!(content_blocks(message.content.as_ref())),
+                    _ => json!(message.content_as_str().unwrap_or_default()),
+                };
+                (
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": message.tool_call_id.as_deref().unwrap_or_default(),
+                        "content": content,
+                    })],
+                )
+            }
+            _ => ("user", content_blocks(message.content.as_ref())),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+        match turns.last_mut().filter(|t| t["role"] == role) {
+            Some(last) => {
+                if let Some(content) = last["content"].as_array_mut() {
+                    content.extend(blocks);
+                }
+            }
+            None => turns.push(json!({"role": role, "content": blocks})),
+        }
+    }
+    (system, turns)
+}
+
+/// Message content as Anthropic content blocks. OpenAI-style `image_url`
+/// parts become `image` blocks; empty text is dropped, since the API
+/// rejects it.
+fn content_blocks(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(text)) if !text.trim().is_empty() => vec![json!({"type": "text", "text": text})],
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| match part["type"].as_str() {
+                Some("text") if part["text"].as_str().is_some_and(|t| !t.trim().is_empty()) => Some(part.clone()),
+                Some("text") => None,
+                Some("image_url") => image_block(part["image_url"]["url"].as_str()?),
+                _ => Some(part.clone()),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// An `image` block for a `data:` URL or a web URL.
+fn image_block(url: &str) -> Option<Value> {
+    let source = match url.strip_prefix("data:") {
+        Some(data) => {
+            let (media_type, data) = data.split_once(";base64,")?;
+            json!({"type": "base64", "media_type": media_type, "data": data})
+        }
+        None => json!({"type": "url", "url": url}),
+    };
+    Some(json!({"type": "image", "source": source}))
+}
+
+// ── Messages API request/response types ─────────────────────────────
+
+#[derive(Serialize)]
+struct MessagesRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    system: Vec<Value>,
+    messages: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+impl MessagesRequest {
+    /// Cache everything up to the last tool, the end of the system prompt
+    /// and the end of the conversation (three of the four breakpoints the
+    /// API allows).
+    fn mark_cache_breakpoints(&mut self) {
+        let last_turn = self
+            .messages
+            .last_mut()
+            .and_then(|turn| turn["content"].as_array_mut())
+            .and_then(|content| content.last_mut());
+        for block in [self.tools.last_mut(), self.system.last_mut(), last_turn].into_iter().flatten() {
+            block["cache_control"] = json!({"type": "ephemeral"});
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    #[serde(default)]
+    content: Vec<Value>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: ApiUsage,
+}
+
+#[derive(Deserialize, Default)]
+struct ApiUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
+}
+
+impl ApiUsage {
+    /// All input tokens, whether read from the cache, written to it or
+    /// neither.
+    fn prompt_tokens(&self) -> u32 {
+        self.input_tokens
+            + self.cache_creation_input_tokens.unwrap_or(0)
+            + self.cache_read_input_tokens.unwrap_or(0)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+/// One `data:` event of a streamed message.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: MessagesResponse,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: Value,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: Value,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        #[serde(default)]
+        delta: Value,
+        #[serde(default)]
+        usage: Option<ApiUsage>,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: ErrorDetail,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// Builds a message from server-sent events, passing text deltas on as
+/// they arrive.
+#[derive(Default)]
+struct StreamAssembler {
+    blocks: Vec<Value>,
+    /// Raw JSON of each `tool_use` block's input, as it streams in.
+    inputs: Vec<String>,
+    stop_reason: Option<String>,
+    usage: ApiUsage,
+    received: bool,
+}
+
+impl StreamAssembler {
+    /// Take in one line of the event stream. Returns `true` at
+    /// `message_stop`.
+    fn push_line(&mut self, line: &str, on_delta: OnDelta<'_>) -> Result<bool, ProviderError> {
+        let Some(data) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") else {
+            return Ok(false);
+        };
+        let event: StreamEvent =
+            serde_json::from_str(data.trim()).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.received = true;
+                self.usage = message.usage;
+            }
+            StreamEvent::ContentBlockStart { index, content_block } => {
+                if self.blocks.len() <= index {
+                    self.blocks.resize(index + 1, Value::Null);
+                    self.inputs.resize(index + 1, String::new());
+                }
+                self.blocks[index] = content_block;
+            }
+            StreamEvent::ContentBlockDelta { index, delta } => {
+                let (Some(block), Some(input)) = (self.blocks.get_mut(index), self.inputs.get_mut(index)) else {
+                    return Err(ProviderError::InvalidResponse(format!("delta for unknown block {}", index)));
+                };
+                let append = |block: &mut Value, field: &str, piece: &str| {
+                    let text = format!("{}{}", block[field].as_str().unwrap_or_default(), piece);
+                    block[field] = Value::String(text);
+                };
+                let piece = |field: &str| delta[field].as_str().unwrap_or_default();
+                match delta["type"].as_str().unwrap_or_default() {
+                    "text_delta" => {
+                        append(block, "text", piece("text"));
+                        if !piece("text").is_empty() {
+                            on_delta(piece("text"));
+                        }
+                    }
+                    "input_json_delta" => input.push_str(piece("partial_json")),
+                    "thinking_delta" => append(block, "thinking", piece("thinking")),
+                    "signature_delta" => append(block, "signature", piece("signature")),
+                    other => debug!(delta = other, "Ignoring unknown stream delta"),
+                }
+            }
+            StreamEvent::ContentBlockStop { index } => {
+                if let (Some(block), Some(input)) = (self.blocks.get_mut(index), self.inputs.get(index)) {
+                    if block["type"] == "tool_use" && !input.is_empty() {
+                        block["input"] = Value::String(input.clone());
+                    }
+                }
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                if let Some(reason) = delta["stop_reason"].as_str() {
+                    self.stop_reason = Some(reason.to_string());
+                }
+                if let Some(usage) = usage {
+                    self.usage.output_tokens = usage.output_tokens;
+                }
+            }
+            StreamEvent::MessageStop => return Ok(true),
+            StreamEvent::Error { error } => return Err(ProviderError::InvalidResponse(error.message)),
+            StreamEvent::Ping | StreamEvent::Unknown => {}
+        }
+        Ok(false)
+    }
+}
+
+// ── LlmProvider implementation ──────────────────────────────────────
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, ProviderError> {
+        let request = self.request(messages, tools, model, max_tokens, temperature);
+        let body = self.send(&request).await?.text().await?;
+        let message: MessagesResponse =
+            serde_json::from_str(&body).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+        Ok(self.to_response(message.content, message.stop_reason, message.usage))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+        on_delta: OnDelta<'_>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let request = MessagesRequest {
+            stream: true,
+            ..self.request(messages, tools, model, max_tokens, temperature)
+        };
+        let mut body = self.send(&request).await?.bytes_stream();
+
+        let mut stream = StreamAssembler::default();
+        let mut pending = Vec::new();
+        'read: while let Some(bytes) = body.next().await {
+            pending.extend_from_slice(&bytes?);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                if stream.push_line(&String::from_utf8_lossy(&line), on_delta)? {
+                    break 'read;
+                }
+            }
+        }
+        if !stream.received {
+            return Err(ProviderError::InvalidResponse("stream ended without a message".into()));
+        }
+        let blocks = stream.blocks.into_iter().filter(|b| !b.is_null()).collect();
+        Ok(self.to_response(blocks, stream.stop_reason, stream.usage))
+    }
+
+    fn default_model(&self) -> &str {
+        &self.default_model
+    }
+
+    fn supports_parallel_tool_calls(&self, model: Option<&str>) -> bool {
+        !self.is_sequential(model.unwrap_or(&self.default_model))
+    }
+
+    /// Every current Claude model takes images.
+    fn supports_images(&self, _model: Option<&str>) -> bool {
+        true
+    }
+
+    fn models_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(
+            self.client
+                .get(format!("{}/models", self.base_url()))
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", API_VERSION),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::types::{FunctionCall, ToolCallMessage, ToolFunctionDef};
+
+    fn provider() -> AnthropicProvider {
+        AnthropicProvider::new("k", None, "anthropic/claude-sonnet-4-5", Client::new())
+    }
+
+    #[test]
+    fn test_messages_are_converted() {
+        let call = ToolCallMessage {
+            id: "toolu_1".into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "price".into(),
+                arguments: r#"{"token":"SOL"}"#.into(),
+            },
+        };
+        let messages = [
+            ChatMessage::system("You are CrabbyBot."),
+            ChatMessage::user("SOL price?"),
+            ChatMessage::assistant_with_tool_calls(Some(""), vec![call]),
+            ChatMessage::tool_result("toolu_1", "price", "$142"),
+            ChatMessage::user("And in EUR?"),
+        ];
+        let tools = [ToolDefinition {
+            def_type: "function".into(),
+            function: ToolFunctionDef {
+                name: "price".into(),
+                description: "Token price".into(),
+                parameters: json!({"type": "object"}),
+            },
+        }];
+        let provider = provider().thinking_budget(2048).prompt_caching(true);
+        let thinking = json!({"type": "thinking", "thinking": "look it up", "signature": "sig"});
+        provider.thinking.lock().unwrap().insert("toolu_1".into(), vec![thinking.clone()]);
+
+        let request = serde_json::to_value(provider.request(&messages, &tools, None, 1000, 0.7)).unwrap();
+        assert_eq!(request["model"], "claude-sonnet-4-5");
+        assert_eq!(request["max_tokens"], 3048);
+        assert!(request.get("temperature").is_none());
+        assert_eq!(request["thinking"]["budget_tokens"], 2048);
+        assert_eq!(request["system"][0]["text"], "You are CrabbyBot.");
+        assert_eq!(request["tools"][0]["input_schema"]["type"], "object");
+
+        let turns = request["messages"].as_array().unwrap();
+        let roles: Vec<&str> = turns.iter().map(|t| t["role"].as_str().unwrap()).collect();
+        assert_eq!(roles, ["user", "assistant", "user"]);
+        // The thinking is replayed ahead of the tool call; the empty text is dropped.
+        assert_eq!(turns[1]["content"][0], thinking);
+        assert_eq!(turns[1]["content"][1]["input"]["token"], "SOL");
+        assert_eq!(turns[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(turns[2]["content"][1]["text"], "And in EUR?");
+
+        for cached in [&request["tools"][0], &request["system"][0], &turns[2]["content"][1]] {
+            assert_eq!(cached["cache_control"]["type"], "ephemeral");
+        }
+        assert!(turns[2]["content"][0].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_stream_assembly() {
+        let shown = Mutex::new(String::new());
+        let on_delta = |d: &str| shown.lock().unwrap().push_str(d);
+        let lines = [
+            "event: message_start",
+            r#"data: {"type":"message_start","message":{"content":[],"usage":{"input_tokens":20,"cache_read_input_tokens":100,"output_tokens":1}}}"#,
+            r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Need the price."}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig"}}"#,
+            r#"data: {"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Checking "}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"now."}}"#,
+            r#"data: {"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_9","name":"price","input":{}}}"#,
+            r#"data: {"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"token\": "}}"#,
+            r#"data: {"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"\"SOL\"}"}}"#,
+            r#"data: {"type":"content_block_stop","index":2}"#,
+            r#"data: {"type":"ping"}"#,
+            r#"data: {"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":30}}"#,
+            r#"data: {"type":"message_stop"}"#,
+        ];
+        let mut stream = StreamAssembler::default();
+        let done: Vec<bool> = lines.iter().map(|l| stream.push_line(l, &on_delta).unwrap()).collect();
+        assert_eq!(done.last(), Some(&true));
+        assert_eq!(*shown.lock().unwrap(), "Checking now.");
+
+        let provider = provider();
+        let response = provider.to_response(stream.blocks, stream.stop_reason, stream.usage);
+        assert_eq!(response.content.as_deref(), Some("Checking now."));
+        assert_eq!(response.finish_reason, "tool_calls");
+        assert_eq!(response.tool_calls[0].arguments["token"], "SOL");
+        assert_eq!((response.usage.prompt_tokens, response.usage.total_tokens), (120, 150));
+        // Kept to be sent back with the tool call.
+        let cache = provider.thinking.lock().unwrap();
+        assert_eq!(cache.blocks["toolu_9"][0]["signature"], "sig");
+
+        let mut failed = StreamAssembler::default();
+        let error = r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(failed.push_line(error, &on_delta).is_err());
+    }
+
+    #[test]
+    fn test_images_become_image_blocks() {
+        let content = json!([
+            {"type": "text", "text": "What is this?"},
+            {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0"}},
+            {"type": "image_url", "image_url": {"url": "https://example.com/chart.png"}},
+        ]);
+        let blocks = content_blocks(Some(&content));
+        assert_eq!(blocks[1]["source"]["media_type"], "image/png");
+        assert_eq!(blocks[1]["source"]["data"], "iVBORw0");
+        assert_eq!(blocks[2]["source"]["type"], "url");
+        assert!(content_blocks(Some(&json!("  "))).is_empty());
+
+        let result = ChatMessage::tool_result_with_images(
+            "toolu_1",
+            "plot",
+            "Chart rendered.",
+            &["data:image/png;base64,iVBORw0".to_string()],
+        );
+        let (_, turns) = convert_messages(&[result], &ThinkingCache::default());
+        let content = &turns[0]["content"][0]["content"];
+        assert_eq!(content[0]["text"], "Chart rendered.");
+        assert_eq!(content[1]["type"], "image");
+        assert_eq!(content[1]["source"]["data"], "iVBORw0");
+    }
+}
@@ -0,0 +1,278 @@
+//! Regional endpoints of a single provider.
+//!
+//! A provider entry may list several `apiBases` (Azure OpenAI regions, the
+//! nodes of a vLLM cluster). [`EndpointPool`] decides which one a request
+//! goes to first and which to try next when it fails. This is separate from
+//! [`FallbackProvider`](super::FallbackProvider), which moves between
+//! different providers and models; here every endpoint serves the same
+//! deployment.
+//!
+//! An endpoint that fails with a network error or a transient status is
+//! parked for [`ENDPOINT_COOLDOWN`] and only used again once every healthy
+//! endpoint has been tried. [`EndpointPool::send`] runs that retry loop for
+//! every provider; they only build the request.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::{debug, warn};
+
+use super::ProviderError;
+
+/// How long a failing endpoint is skipped.
+const ENDPOINT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Maximum number of attempts for transient errors, unless there are more
+/// endpoints to try once each.
+const MAX_RETRIES: u32 = 3;
+
+/// Base delay for exponential backoff (milliseconds).
+const BASE_DELAY_MS: u64 = 500;
+
+/// Weight of the newest sample in the latency average.
+const LATENCY_WEIGHT: f64 = 0.3;
+
+/// How the first endpoint for a request is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EndpointStrategy {
+    /// Fastest recent response first; endpoints without a measurement yet
+    /// are tried before measured ones so every region gets probed.
+    #[default]
+    Latency,
+    /// Rotate through the endpoints request by request.
+    RoundRobin,
+}
+
+impl FromStr for EndpointStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "" | "latency" => Ok(Self::Latency),
+            "round-robin" | "roundrobin" => Ok(Self::RoundRobin),
+            other => Err(format!("unknown endpoint strategy '{}'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Health {
+    latency_ms: Option<f64>,
+    down_until: Option<Instant>,
+}
+
+/// The base URLs of one provider with their recent health.
+#[derive(Debug)]
+pub struct EndpointPool {
+    urls: Vec<String>,
+    strategy: EndpointStrategy,
+    health: Mutex<Vec<Health>>,
+    next: AtomicUsize,
+}
+
+impl EndpointPool {
+    /// `urls` must not be empty.
+    pub fn new(urls: Vec<String>, strategy: EndpointStrategy) -> Self {
+        let health = Mutex::new(vec![Health::default(); urls.len()]);
+        Self {
+            urls,
+            strategy,
+            health,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Indices of the endpoints in the order a request should try them.
+    pub fn order(&self) -> Vec<usize> {
+        self.order_at(Instant::now())
+    }
+
+    fn order_at(&self, now: Instant) -> Vec<usize> {
+        let n = self.urls.len();
+        let start = match self.strategy {
+            EndpointStrategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % n,
+            EndpointStrategy::Latency => 0,
+        };
+        let mut order: Vec<usize> = (0..n).map(|i| (start + i) % n).collect();
+        let Ok(health) = self.health.lock() else {
+            return order;
+        };
+        let down = |i: &usize| health[*i].down_until.is_some_and(|t| t > now);
+        if self.strategy == EndpointStrategy::Latency {
+            // Stable sort: unmeasured endpoints keep config order, ahead of measured ones.
+            order.sort_by(|a, b| {
+                let latency = |i: &usize| health[*i].latency_ms.unwrap_or(-1.0);
+                latency(a).total_cmp(&latency(b))
+            });
+        }
+        order.sort_by_key(down);
+        order
+    }
+
+    /// Record a completed request to endpoint `i`.
+    pub fn record_success(&self, i: usize, elapsed: Duration) {
+        if let Ok(mut health) = self.health.lock() {
+            let h = &mut health[i];
+            let sample = elapsed.as_secs_f64() * 1000.0;
+            h.latency_ms = Some(match h.latency_ms {
+                Some(avg) => avg + LATENCY_WEIGHT * (sample - avg),
+                None => sample,
+            });
+            h.down_until = None;
+        }
+    }
+
+    /// Park endpoint `i` after a network error or transient status.
+    pub fn record_failure(&self, i: usize) {
+        if let Ok(mut health) = self.health.lock() {
+            health[i].down_until = Some(Instant::now() + ENDPOINT_COOLDOWN);
+        }
+    }
+
+    /// Send the request `request` builds for an endpoint's base URL and
+    /// return the first successful response, before its body is read.
+    ///
+    /// Network errors and statuses `retryable` accepts move on to the next
+    /// endpoint; the exponential backoff only applies once every endpoint
+    /// has been tried. Any other status fails at once, its body turned into
+    /// an error by `api_error`.
+    pub async fn send(
+        &self,
+        request: impl Fn(&str) -> RequestBuilder,
+        retryable: impl Fn(StatusCode) -> bool,
+        api_error: impl Fn(StatusCode, String) -> ProviderError,
+    ) -> Result<Response, ProviderError> {
+        let order = self.order();
+        let rounds = order.len() as u32;
+        let mut last_error: Option<ProviderError> = None;
+
+        for attempt in 0..MAX_RETRIES.max(rounds) {
+            if attempt > 0 && attempt % rounds == 0 {
+                let delay = BASE_DELAY_MS * 2u64.pow(attempt / rounds - 1);
+                warn!(attempt, delay_ms = delay, "Retrying LLM API request");
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+
+            let endpoint = order[(attempt % rounds) as usize];
+            let url = &self.urls[endpoint];
+            debug!(attempt, url = %url, "Sending LLM API request");
+            let started = Instant::now();
+
+            let response = match request(url).send().await {
+                Ok(r) => r,
+                Err(e) => {
+                    // Network-level errors are always retryable.
+                    warn!(attempt, url = %url, error = %e, "Network error calling LLM API");
+                    self.record_failure(endpoint);
+                    last_error = Some(e.into());
+                    continue;
+                }
+            };
+
+            let status = response.status();
+            if status.is_success() {
+                self.record_success(endpoint, started.elapsed());
+                return Ok(response);
+            }
+
+            let error = api_error(status, response.text().await?);
+            if !retryable(status) {
+                return Err(error);
+            }
+            warn!(attempt, url = %url, status = %status, "Transient LLM API error, will retry");
+            self.record_failure(endpoint);
+            last_error = Some(error);
+        }
+
+        Err(last_error.unwrap_or(ProviderError::Exhausted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(strategy: EndpointStrategy) -> EndpointPool {
+        let urls = ["https://eu", "https://us", "https://asia"].map(String::from).to_vec();
+        EndpointPool::new(urls, strategy)
+    }
+
+    #[test]
+    fn test_latency_order() {
+        let pool = pool(EndpointStrategy::Latency);
+        assert_eq!(pool.order(), [0, 1, 2]);
+
+        pool.record_success(0, Duration::from_millis(300));
+        pool.record_success(1, Duration::from_millis(80));
+        // Unmeasured `asia` is probed first, then the fastest.
+        assert_eq!(pool.order(), [2, 1, 0]);
+        pool.record_success(2, Duration::from_millis(150));
+        assert_eq!(pool.order(), [1, 2, 0]);
+
+        pool.record_failure(1);
+        assert_eq!(pool.order(), [2, 0, 1]);
+        assert_eq!(pool.order_at(Instant::now() + ENDPOINT_COOLDOWN * 2), [1, 2, 0]);
+    }
+
+    #[test]
+    fn test_round_robin_skips_parked() {
+        let pool = pool(EndpointStrategy::RoundRobin);
+        assert_eq!(pool.order()[0], 0);
+        assert_eq!(pool.order()[0], 1);
+        pool.record_failure(2);
+        assert_eq!(pool.order(), [0, 1, 2]);
+        assert_eq!(pool.order(), [0, 1, 2]);
+
+        assert_eq!("round_robin".parse(), Ok(EndpointStrategy::RoundRobin));
+        assert!("random".parse::<EndpointStrategy>().is_err());
+    }
+
+    /// A server answering every request with `status` and `body`.
+    async fn serve(status: &'static str, body: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_over_and_parks() {
+        let down = serve("503 Service Unavailable", "overloaded").await;
+        let up = serve("200 OK", "{}").await;
+        let pool = EndpointPool::new(vec![down, up], EndpointStrategy::Latency);
+        let client = reqwest::Client::new();
+        let api_error = |status: StatusCode, message: String| ProviderError::Api { status: status.as_u16(), message };
+
+        let response = pool
+            .send(|base| client.post(format!("{}/chat", base)), |s| s.is_server_error(), api_error)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(pool.order(), [1, 0]);
+
+        let refused = serve("400 Bad Request", "no such model").await;
+        let pool = EndpointPool::new(vec![refused], EndpointStrategy::Latency);
+        let result = pool.send(|base| client.post(base), |s| s.is_server_error(), api_error).await;
+        assert!(matches!(result, Err(ProviderError::Api { status: 400, message }) if message == "no such model"));
+    }
+}
@@ -2,18 +2,148 @@
 //!
 //! Defines the `LlmProvider` trait that all backends must implement.
 //! The `openai` module provides an OpenAI-compatible implementation
-//! that covers most providers (OpenRouter, Anthropic, DeepSeek, Groq, vLLM, etc.).
+//! that covers most providers (OpenRouter, DeepSeek, Groq, vLLM, etc.);
+//! `anthropic` speaks Anthropic's native Messages API and `ollama` a local
+//! Ollama server's own API.
 
+pub mod anthropic;
+pub mod endpoints;
+pub mod ollama;
 pub mod openai;
+pub mod repair;
 pub mod types;
 
 use async_trait::async_trait;
+use futures::future::BoxFuture;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 use types::{ChatMessage, LlmResponse, ToolDefinition};
 
+// ── Errors ──────────────────────────────────────────────────────────
+
+/// Errors returned by [`LlmProvider::chat`].
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderError {
+    /// The provider API answered with a non-success HTTP status.
+    #[error("LLM API error ({status}): {message}")]
+    Api { status: u16, message: String },
+
+    /// The request never got a response (DNS, TLS, timeout, connection reset…).
+    #[error("LLM API request failed: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// The response body could not be understood.
+    #[error("Invalid LLM API response: {0}")]
+    InvalidResponse(String),
+
+    /// No provider has an API key configured.
+    #[error("No LLM provider configured. Use `/config set groq_key <KEY>` to enable the bot.")]
+    NotConfigured,
+
+    /// Every provider in a fallback chain failed or is quarantined.
+    #[error("All providers are exhausted or in quarantine")]
+    Exhausted,
+
+    /// The caller stopped waiting for the call, see
+    /// [`AgentLoop::cancel_with`](crate::agent::AgentLoop::cancel_with).
+    #[error("LLM call cancelled")]
+    Cancelled,
+}
+
+impl ProviderError {
+    /// HTTP status of an [`ProviderError::Api`] error.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Api { status, .. } => Some(*status),
+            Self::Network(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    fn message_contains(&self, needles: &[&str]) -> bool {
+        match self {
+            Self::Api { message, .. } => needles.iter().any(|n| message.contains(n)),
+            _ => false,
+        }
+    }
+
+    /// Rate limit or exhausted quota.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(429) || self.message_contains(&["quota", "rate limit", "rate_limit"])
+    }
+
+    /// Invalid, expired, or unauthorised API key.
+    pub fn is_auth(&self) -> bool {
+        matches!(self.status(), Some(401 | 403))
+            || self.message_contains(&["Unauthorized", "User not found"])
+    }
+
+    /// The request exceeded the provider's payload or context limit.
+    pub fn is_payload_too_large(&self) -> bool {
+        self.status() == Some(413) || self.message_contains(&["Payload Too Large"])
+    }
+
+    /// The prompt does not fit the model's context window. Providers report
+    /// this as a 400 with their own wording rather than a distinct status.
+    pub fn is_context_length_exceeded(&self) -> bool {
+        let Self::Api { status, message } = self else {
+            return false;
+        };
+        let message = message.to_lowercase();
+        matches!(status, 400 | 413)
+            && [
+                "context_length_exceeded",
+                "context length",
+                "context window",
+                "prompt is too long",
+                "too many tokens",
+                "reduce the length",
+                "input is too long",
+            ]
+            .iter()
+            .any(|n| message.contains(n))
+    }
+
+    /// Whether a fallback chain should move on to the next provider.
+    pub fn is_failover(&self) -> bool {
+        self.is_rate_limited()
+            || self.is_auth()
+            || self.is_payload_too_large()
+            || self.status() == Some(404)
+            || self.message_contains(&["tool call validation"])
+    }
+}
+
+/// Model families that accept images, matched against the model name
+/// without its `provider/` prefix.
+const VISION_FAMILIES: &[&str] = &[
+    "gpt-4o", "gpt-4.1", "gpt-4-turbo", "gpt-5", "claude", "gemini", "grok-4", "pixtral", "llava",
+    "vision", "-vl", "qwen2.5vl", "gemma3", "minicpm-v", "llama4", "llama-4",
+];
+
+/// Whether `model` can look at images, going by its name.
+pub fn is_vision_model(model: &str) -> bool {
+    let model = model.to_ascii_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    let reasoning = ["o1", "o3", "o4"].iter().any(|p| name.starts_with(p)) && !matches!(name, "o1-mini" | "o3-mini");
+    reasoning || VISION_FAMILIES.iter().any(|family| name.contains(family))
+}
+
+/// Whether `model` is one of `patterns`, ignoring case. Patterns ending in
+/// `*` match by prefix, so `"*"` covers every model.
+fn matches_model(patterns: &[String], model: &str) -> bool {
+    patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => model.to_ascii_lowercase().starts_with(&prefix.to_ascii_lowercase()),
+        None => pattern.eq_ignore_ascii_case(model),
+    })
+}
+
+/// Receives each piece of reply text as a streaming provider produces it.
+pub type OnDelta<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
 /// Trait for LLM providers.
 ///
 /// Any backend that can handle chat completions with tool calling
@@ -35,10 +165,51 @@ pub trait LlmProvider: Send + Sync {
         model: Option<&str>,
         max_tokens: u32,
         temperature: f32,
-    ) -> anyhow::Result<LlmResponse>;
+    ) -> Result<LlmResponse, ProviderError>;
+
+    /// Like [`chat`](Self::chat), but passes the reply text to `on_delta`
+    /// piece by piece as it is generated. The full response is still
+    /// returned at the end.
+    ///
+    /// The default waits for `chat` and reports its content in one piece.
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+        on_delta: OnDelta<'_>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let response = self.chat(messages, tools, model, max_tokens, temperature).await?;
+        if let Some(content) = response.content.as_deref().filter(|c| !c.is_empty()) {
+            on_delta(content);
+        }
+        Ok(response)
+    }
 
     /// Get the default model identifier.
     fn default_model(&self) -> &str;
+
+    /// Whether `model` (None = default) accepts several tool calls in one
+    /// turn. When `false` the agent runs calls one at a time and records
+    /// each as its own assistant turn.
+    fn supports_parallel_tool_calls(&self, _model: Option<&str>) -> bool {
+        true
+    }
+
+    /// Whether `model` (None = default) can look at images, so tools may
+    /// hand it the images they produce along with their result.
+    fn supports_images(&self, _model: Option<&str>) -> bool {
+        false
+    }
+
+    /// A request for the provider's model list, which checks that it is
+    /// reachable and accepts the key without spending tokens. `None` when
+    /// there is nothing to probe.
+    fn models_request(&self) -> Option<reqwest::RequestBuilder> {
+        None
+    }
 }
 /// A provider that wraps multiple other providers and implements failover logic.
 ///
@@ -63,16 +234,20 @@ impl FallbackProvider {
     }
 }
 
-#[async_trait]
-impl LlmProvider for FallbackProvider {
-    async fn chat(
-        &self,
-        messages: &[ChatMessage],
-        tools: &[ToolDefinition],
-        model: Option<&str>,
-        max_tokens: u32,
-        temperature: f32,
-    ) -> anyhow::Result<LlmResponse> {
+impl FallbackProvider {
+    /// Run `call` against each healthy provider in turn until one succeeds
+    /// or fails with an error that shouldn't fail over. `call` gets the
+    /// provider and the model it should use. Once `committed` returns true
+    /// no further provider is tried.
+    async fn try_each<'a, F>(
+        &'a self,
+        model: Option<&'a str>,
+        committed: impl Fn() -> bool,
+        call: F,
+    ) -> Result<LlmResponse, ProviderError>
+    where
+        F: Fn(&'a dyn LlmProvider, Option<&'a str>) -> BoxFuture<'a, Result<LlmResponse, ProviderError>>,
+    {
         let mut last_error = None;
         let now = Instant::now();
 
@@ -92,37 +267,22 @@ impl LlmProvider for FallbackProvider {
 
             let effective_model = if i == 0 { model } else { None };
 
-            match provider
-                .chat(messages, tools, effective_model, max_tokens, temperature)
-                .await
-            {
+            match call(provider.as_ref(), effective_model).await {
                 Ok(res) => return Ok(res),
                 Err(e) => {
-                    let err_str = e.to_string();
-                    let is_failover = err_str.contains("429")
-                        || err_str.contains("quota")
-                        || err_str.contains("rate limit")
-                        || err_str.contains("404")
-                        || err_str.contains("tool call validation")
-                        // Auth errors: the key is invalid/expired — skip to next provider
-                        || err_str.contains("401")
-                        || err_str.contains("403")
-                        || err_str.contains("Unauthorized")
-                        || err_str.contains("User not found")
-                        // Payload too large — next provider may have higher context limit
-                        || err_str.contains("413")
-                        || err_str.contains("Payload Too Large");
-
-                    if is_failover {
+                    if e.is_failover() {
                         warn!(
                             provider = %name,
-                            error = %err_str,
+                            error = %e,
                             "Provider failed with failover-eligible error, entering quarantine"
                         );
                         {
                             let mut health = self.health.lock().unwrap();
                             health.insert(name.clone(), Instant::now());
                         }
+                        if committed() {
+                            return Err(e);
+                        }
                         last_error = Some(e);
                         continue;
                     }
@@ -135,8 +295,65 @@ impl LlmProvider for FallbackProvider {
         // or just return the last error. For now, we've tried all available "healthy" ones.
         // If we reach here, it means no healthy provider succeeded.
 
-        Err(last_error
-            .unwrap_or_else(|| anyhow::anyhow!("All providers are exhausted or in quarantine")))
+        Err(last_error.unwrap_or(ProviderError::Exhausted))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FallbackProvider {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, ProviderError> {
+        self.try_each(model, || false, |provider, model| {
+            provider.chat(messages, tools, model, max_tokens, temperature)
+        })
+        .await
+    }
+
+    /// Once a provider has streamed part of its reply the chain stops
+    /// there: failing over would repeat the text already shown.
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+        on_delta: OnDelta<'_>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let streamed = AtomicBool::new(false);
+        let forward = |delta: &str| {
+            streamed.store(true, Ordering::Relaxed);
+            on_delta(delta);
+        };
+        self.try_each(
+            model,
+            || streamed.load(Ordering::Relaxed),
+            |provider, model| provider.chat_stream(messages, tools, model, max_tokens, temperature, &forward),
+        )
+        .await
+    }
+
+    /// Any provider in the chain may end up answering, so parallel calls
+    /// are only used when every one of them supports them.
+    fn supports_parallel_tool_calls(&self, model: Option<&str>) -> bool {
+        self.providers
+            .iter()
+            .enumerate()
+            .all(|(i, (_, p))| p.supports_parallel_tool_calls(if i == 0 { model } else { None }))
+    }
+
+    /// Likewise, images are only sent when every provider can take them.
+    fn supports_images(&self, model: Option<&str>) -> bool {
+        self.providers
+            .iter()
+            .enumerate()
+            .all(|(i, (_, p))| p.supports_images(if i == 0 { model } else { None }))
     }
 
     fn default_model(&self) -> &str {
@@ -165,11 +382,132 @@ impl LlmProvider for NoopProvider {
         _model: Option<&str>,
         _max_tokens: u32,
         _temperature: f32,
-    ) -> anyhow::Result<LlmResponse> {
-        anyhow::bail!("No LLM provider configured. Use `/config set groq_key <KEY>` to enable the bot.")
+    ) -> Result<LlmResponse, ProviderError> {
+        Err(ProviderError::NotConfigured)
     }
 
     fn default_model(&self) -> &str {
         &self.model
     }
 }
+
+// ── Construction from config ────────────────────────────────────────
+
+/// Build the provider chain described by `config`.
+///
+/// Every active provider is wrapped in a [`FallbackProvider`] in config
+/// order. With no active providers a [`NoopProvider`] is returned so the bot
+/// can still start in setup mode.
+pub fn from_config(
+    config: &crate::config::Config,
+    model_override: Option<&str>,
+    client: reqwest::Client,
+) -> Box<dyn LlmProvider> {
+    let model = model_override.unwrap_or(&config.agents.defaults.model);
+    let inner_providers: Vec<_> = active_providers(config, model, client)
+        .into_iter()
+        .map(|(name, p)| (name.to_string(), p))
+        .collect();
+
+    if inner_providers.is_empty() {
+        warn!("No active LLM providers. Bot will start in limited setup mode.");
+        return Box::new(NoopProvider {
+            model: model.to_string(),
+        });
+    }
+    Box::new(FallbackProvider::new(inner_providers))
+}
+
+/// Instantiate every active provider in config order, decrypting API keys.
+///
+/// The `anthropic` entry uses the native Messages API unless its `api` is
+/// `openai`, and `ollama` the native Ollama API unless its `api` is
+/// `openai`; every other entry is OpenAI-compatible.
+pub(crate) fn active_providers(
+    config: &crate::config::Config,
+    model: &str,
+    client: reqwest::Client,
+) -> Vec<(&'static str, Box<dyn LlmProvider>)> {
+    config
+        .providers
+        .find_all_active()
+        .into_iter()
+        .map(|(name, entry)| {
+            let p_model = entry.model.as_deref().unwrap_or(model);
+            let api_key = crate::vault::decrypt(&entry.api_key).unwrap_or_else(|e| {
+                warn!("Failed to decrypt API key for provider {}: {}", name, e);
+                entry.api_key.clone()
+            });
+            let strategy = entry.endpoint_strategy.parse().unwrap_or_default();
+            let p: Box<dyn LlmProvider> = if name == "anthropic" && entry.api != "openai" {
+                Box::new(
+                    anthropic::AnthropicProvider::new(&api_key, entry.api_base.as_deref(), p_model, client.clone())
+                        .sequential_tool_models(entry.sequential_tool_models.clone())
+                        .endpoints(entry.api_bases.clone(), strategy)
+                        .thinking_budget(entry.thinking_budget)
+                        .prompt_caching(entry.prompt_caching),
+                )
+            } else if name == "ollama" && entry.api != "openai" {
+                Box::new(
+                    ollama::OllamaProvider::new(entry.api_base.as_deref(), p_model, client.clone())
+                        .sequential_tool_models(entry.sequential_tool_models.clone())
+                        .endpoints(entry.api_bases.clone(), strategy)
+                        .keep_alive(entry.keep_alive.as_deref()),
+                )
+            } else {
+                Box::new(
+                    openai::OpenAiProvider::new(
+                        name,
+                        &api_key,
+                        entry.api_base.as_deref(),
+                        p_model,
+                        client.clone(),
+                    )
+                    .sequential_tool_models(entry.sequential_tool_models.clone())
+                    .endpoints(entry.api_bases.clone(), strategy)
+                    .reasoning_effort(config.agents.defaults.reasoning_effort.clone()),
+                )
+            };
+            (name, p)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(status: u16, message: &str) -> ProviderError {
+        ProviderError::Api {
+            status,
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn test_provider_error_classification() {
+        assert!(api(429, "slow down").is_rate_limited());
+        assert!(api(402, "monthly quota exceeded").is_rate_limited());
+        assert!(api(401, "bad key").is_auth());
+        assert!(api(413, "").is_payload_too_large());
+        assert!(api(400, "This model's maximum context length is 128000 tokens (context_length_exceeded)")
+            .is_context_length_exceeded());
+        assert!(api(400, "prompt is too long: 210000 tokens > 200000 maximum").is_context_length_exceeded());
+        assert!(!api(400, "invalid tool schema").is_context_length_exceeded());
+        assert!(!api(500, "context length").is_context_length_exceeded());
+
+        assert!(api(404, "model not found").is_failover());
+        assert!(!api(500, "internal error").is_failover());
+        assert!(!ProviderError::NotConfigured.is_failover());
+    }
+
+    #[test]
+    fn test_vision_models() {
+        for model in ["openai/gpt-4o-mini", "o3", "anthropic/claude-sonnet-4-5", "llava:13b", "qwen2.5vl:7b"] {
+            assert!(is_vision_model(model), "{}", model);
+        }
+        for model in ["gpt-3.5-turbo", "o3-mini", "deepseek/deepseek-r1", "llama3.1:8b", "mixtral-8x7b"] {
+            assert!(!is_vision_model(model), "{}", model);
+        }
+    }
+}
@@ -0,0 +1,579 @@
+//! Native Ollama provider for models running on a local server.
+//!
+//! Talks to Ollama's own API (`POST {base}/api/chat`) rather than its
+//! OpenAI-compatible shim, which gives access to:
+//!
+//! - **Keep-alive** (`keepAlive`): how long the model stays loaded after a
+//!   request, so the next message doesn't wait for it to load again.
+//! - **Model listing** (`GET {base}/api/tags`): the models pulled on the
+//!   server, see [`OllamaProvider::list_models`].
+//!
+//! No API key is needed; the server runs on this machine or the local
+//! network, so the bot keeps working without internet access. Messages are
+//! converted from the OpenAI shape the agent uses: images travel as bare
+//! base64 in `images`, tool call arguments as JSON objects and streamed
+//! replies as one JSON object per line.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{debug, warn};
+
+use super::endpoints::{EndpointPool, EndpointStrategy};
+use super::openai::{strip_thinking, visible_answer};
+use super::types::{ChatMessage, LlmResponse, ToolCallRequest, ToolDefinition, Usage};
+use super::{repair, LlmProvider, OnDelta, ProviderError};
+
+/// Default server address.
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Provider for a local Ollama server.
+///
+/// Retries network failures and 5xx responses with exponential backoff,
+/// moving on to the next endpoint first when several are configured.
+pub struct OllamaProvider {
+    client: Client,
+    endpoints: EndpointPool,
+    default_model: String,
+    sequential_tool_models: Vec<String>,
+    keep_alive: Option<Value>,
+}
+
+/// A model pulled on the server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub details: ModelDetails,
+}
+
+/// What `/api/tags` reports about a model's build.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub family: String,
+    #[serde(default)]
+    pub parameter_size: String,
+    #[serde(default)]
+    pub quantization_level: String,
+}
+
+impl OllamaProvider {
+    /// Create a provider for `api_base` (default [`DEFAULT_BASE_URL`]).
+    pub fn new(api_base: Option<&str>, default_model: &str, client: Client) -> Self {
+        let base_url = base_url(api_base.unwrap_or(DEFAULT_BASE_URL));
+        debug!(base_url = %base_url, "Initialized Ollama provider");
+        Self {
+            client,
+            endpoints: EndpointPool::new(vec![base_url], EndpointStrategy::default()),
+            default_model: default_model.to_string(),
+            sequential_tool_models: Vec::new(),
+            keep_alive: None,
+        }
+    }
+
+    /// How long the model stays loaded after a request: a duration such as
+    /// `10m` or `1h`, `0` to unload it at once, or `-1` to keep it loaded.
+    /// `None` leaves the server default (5 minutes).
+    pub fn keep_alive(mut self, keep_alive: Option<&str>) -> Self {
+        self.keep_alive = keep_alive.map(str::trim).filter(|k| !k.is_empty()).map(|k| match k.parse::<i64>() {
+            // Bare numbers are seconds; Ollama only takes them as numbers.
+            Ok(seconds) => json!(seconds),
+            Err(_) => json!(k),
+        });
+        self
+    }
+
+    /// Extra base URLs for the same models, tried after the primary one in
+    /// the order `strategy` picks.
+    pub fn endpoints(mut self, extra: Vec<String>, strategy: EndpointStrategy) -> Self {
+        let mut urls = self.endpoints.urls().to_vec();
+        for url in extra {
+            let url = base_url(&url);
+            if !url.is_empty() && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        self.endpoints = EndpointPool::new(urls, strategy);
+        self
+    }
+
+    /// Models whose tool calls the agent runs one per turn; a trailing `*`
+    /// matches by prefix.
+    pub fn sequential_tool_models(mut self, models: Vec<String>) -> Self {
+        self.sequential_tool_models = models;
+        self
+    }
+
+    fn is_sequential(&self, model: &str) -> bool {
+        super::matches_model(&self.sequential_tool_models, model)
+    }
+
+    /// The primary server address.
+    pub fn base_url(&self) -> &str {
+        &self.endpoints.urls()[0]
+    }
+
+    /// The models pulled on the primary server, newest first.
+    pub async fn list_models(&self) -> Result<Vec<LocalModel>, ProviderError> {
+        let response = self.client.get(format!("{}/api/tags", self.base_url())).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(api_error(status, body));
+        }
+        let mut models = serde_json::from_str::<TagsResponse>(&body)
+            .map_err(|e| ProviderError::InvalidResponse(e.to_string()))?
+            .models;
+        models.sort_by_key(|m| std::cmp::Reverse(m.modified_at));
+        Ok(models)
+    }
+
+    fn request(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> ChatRequest<'_> {
+        let model = model.unwrap_or(&self.default_model);
+        // Like OpenRouter IDs, `ollama/llama3.2` names the provider first.
+        let model = model.strip_prefix("ollama/").unwrap_or(model).to_string();
+        ChatRequest {
+            model,
+            messages: messages.iter().map(convert_message).collect(),
+            tools: tools.to_vec(),
+            stream: false,
+            options: json!({"temperature": temperature, "num_predict": max_tokens}),
+            keep_alive: self.keep_alive.as_ref(),
+        }
+    }
+
+    /// POST `request` to `{base}/api/chat`, see [`EndpointPool::send`].
+    /// Only 5xx responses are retried.
+    async fn send(&self, request: &ChatRequest<'_>) -> Result<reqwest::Response, ProviderError> {
+        debug!(
+            model = request.model,
+            msg_count = request.messages.len(),
+            stream = request.stream,
+            "Sending Ollama chat request"
+        );
+        let post = |base: &str| self.client.post(format!("{}/api/chat", base)).json(request);
+        self.endpoints.send(post, |status| status.is_server_error(), api_error).await
+    }
+}
+
+/// A configured server address without a trailing slash or the `/api` or
+/// OpenAI-style `/v1` suffix people copy from other setups.
+fn base_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix("/v1").or_else(|| url.strip_suffix("/api")).unwrap_or(url);
+    url.to_string()
+}
+
+fn api_error(status: reqwest::StatusCode, body: String) -> ProviderError {
+    ProviderError::Api {
+        status: status.as_u16(),
+        message: serde_json::from_str::<ErrorBody>(&body).map(|e| e.error).unwrap_or(body),
+    }
+}
+
+/// One agent message in Ollama's shape.
+fn convert_message(message: &ChatMessage) -> Value {
+    let mut text = String::new();
+    let mut images = Vec::new();
+    match &message.content {
+        Some(Value::String(s)) => text.push_str(s),
+        Some(Value::Array(parts)) => {
+            for part in parts {
+                match part["type"].as_str() {
+                    Some("text") => text.push_str(part["text"].as_str().unwrap_or_default()),
+                    Some("image_url") => {
+                        let url = part["image_url"]["url"].as_str().unwrap_or_default();
+                        // Ollama only takes inline images, not links.
+                        match url.split_once(";base64,") {
+                            Some((_, data)) if url.starts_with("data:") => images.push(data.to_string()),
+                            _ => debug!(url, "Dropping image link Ollama cannot fetch"),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut converted = json!({"role": message.role, "content": text});
+    if !images.is_empty() {
+        converted["images"] = json!(images);
+    }
+    if let Some(calls) = &message.tool_calls {
+        converted["tool_calls"] = calls
+            .iter()
+            .map(|call| {
+                let arguments = serde_json::from_str::<Value>(&call.function.arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                json!({"function": {"name": call.function.name, "arguments": arguments}})
+            })
+            .collect();
+    }
+    if message.role == "tool" {
+        if let Some(name) = &message.name {
+            converted["tool_name"] = json!(name);
+        }
+    }
+    converted
+}
+
+/// A tool call from a reply. Ollama sends no IDs, so each call gets a
+/// fresh one for its result to refer to.
+fn tool_call(call: &Value) -> ToolCallRequest {
+    let id = call["id"]
+        .as_str()
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+    let name = call["function"]["name"].as_str().unwrap_or_default().to_string();
+    let parsed = match &call["function"]["arguments"] {
+        Value::Object(arguments) => Ok(arguments.clone()),
+        Value::String(raw) => repair::parse_arguments(raw).map(|(arguments, repaired)| {
+            if repaired {
+                debug!(tool = name, raw, "Repaired malformed tool arguments");
+            }
+            arguments
+        }),
+        _ => Ok(Map::new()),
+    };
+    match parsed {
+        Ok(arguments) => ToolCallRequest {
+            id,
+            name,
+            arguments,
+            parse_error: None,
+        },
+        Err(e) => {
+            warn!(tool = name, error = %e, "Failed to parse tool arguments");
+            ToolCallRequest {
+                id,
+                name,
+                arguments: Map::new(),
+                parse_error: Some(e),
+            }
+        }
+    }
+}
+
+/// Build the [`LlmResponse`] from the reply text, its tool calls and the
+/// final chunk's statistics.
+fn to_response(content: &str, calls: &[Value], done: &ChatChunk) -> LlmResponse {
+    let (text, thinking) = strip_thinking(content);
+    if let Some(thinking) = thinking {
+        debug!(chars = thinking.len(), "Stripped model reasoning from reply");
+    }
+    let tool_calls: Vec<ToolCallRequest> = calls.iter().map(tool_call).collect();
+    let finish_reason = match done.done_reason.as_deref() {
+        _ if !tool_calls.is_empty() => "tool_calls".to_string(),
+        None | Some("stop") => "stop".to_string(),
+        Some(other) => other.to_string(),
+    };
+    let usage = Usage {
+        prompt_tokens: done.prompt_eval_count,
+        completion_tokens: done.eval_count,
+        total_tokens: done.prompt_eval_count + done.eval_count,
+    };
+    debug!(
+        finish_reason,
+        tool_calls = tool_calls.len(),
+        tokens = usage.total_tokens,
+        "Received LLM response"
+    );
+    LlmResponse {
+        content: (!text.is_empty()).then_some(text),
+        tool_calls,
+        finish_reason,
+        usage,
+    }
+}
+
+// ── Ollama API request/response types ───────────────────────────────
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: String,
+    messages: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolDefinition>,
+    stream: bool,
+    options: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a Value>,
+}
+
+/// A whole reply, or one line of a streamed one.
+#[derive(Deserialize, Default)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChunkMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ChunkMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<LocalModel>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Builds a reply from streamed lines, passing on the part of the text
+/// that is answer rather than thinking as it grows.
+#[derive(Default)]
+struct StreamAssembler {
+    content: String,
+    tool_calls: Vec<Value>,
+    /// Answer text already passed on.
+    shown: String,
+    done: Option<ChatChunk>,
+}
+
+impl StreamAssembler {
+    /// Take in one line of the stream. Returns `true` at the final chunk.
+    fn push_line(&mut self, line: &str, on_delta: OnDelta<'_>) -> Result<bool, ProviderError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(false);
+        }
+        let mut chunk: ChatChunk =
+            serde_json::from_str(line).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+        if let Some(error) = chunk.error.take() {
+            return Err(ProviderError::InvalidResponse(error));
+        }
+        if let Some(message) = chunk.message.take() {
+            self.tool_calls.extend(message.tool_calls);
+            if !message.content.is_empty() {
+                self.content.push_str(&message.content);
+                if let Some(new) = visible_answer(&self.content, false)
+                    .and_then(|answer| answer.strip_prefix(self.shown.as_str()))
+                    .filter(|new| !new.is_empty())
+                {
+                    on_delta(new);
+                    self.shown.push_str(new);
+                }
+            }
+        }
+        if chunk.done {
+            self.done = Some(chunk);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// The completed response. Held-back text nothing was shown of yet is
+    /// passed on in one piece.
+    fn finish(self, on_delta: OnDelta<'_>) -> Result<LlmResponse, ProviderError> {
+        let Some(done) = self.done else {
+            return Err(ProviderError::InvalidResponse("stream ended before the reply was done".into()));
+        };
+        let response = to_response(&self.content, &self.tool_calls, &done);
+        if let Some(content) = response.content.as_deref().filter(|_| self.shown.is_empty()) {
+            on_delta(content);
+        }
+        Ok(response)
+    }
+}
+
+// ── LlmProvider implementation ──────────────────────────────────────
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, ProviderError> {
+        let request = self.request(messages, tools, model, max_tokens, temperature);
+        let body = self.send(&request).await?.text().await?;
+        let mut reply: ChatChunk =
+            serde_json::from_str(&body).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+        let message = reply.message.take().unwrap_or_default();
+        Ok(to_response(&message.content, &message.tool_calls, &reply))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+        on_delta: OnDelta<'_>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let request = ChatRequest {
+            stream: true,
+            ..self.request(messages, tools, model, max_tokens, temperature)
+        };
+        let mut body = self.send(&request).await?.bytes_stream();
+
+        let mut stream = StreamAssembler::default();
+        let mut pending = Vec::new();
+        'read: while let Some(bytes) = body.next().await {
+            pending.extend_from_slice(&bytes?);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                if stream.push_line(&String::from_utf8_lossy(&line), on_delta)? {
+                    break 'read;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            stream.push_line(&String::from_utf8_lossy(&pending), on_delta)?;
+        }
+        stream.finish(on_delta)
+    }
+
+    fn default_model(&self) -> &str {
+        &self.default_model
+    }
+
+    fn supports_parallel_tool_calls(&self, model: Option<&str>) -> bool {
+        !self.is_sequential(model.unwrap_or(&self.default_model))
+    }
+
+    fn supports_images(&self, model: Option<&str>) -> bool {
+        super::is_vision_model(model.unwrap_or(&self.default_model))
+    }
+
+    fn models_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(self.client.get(format!("{}/api/tags", self.base_url())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::types::{FunctionCall, ToolCallMessage, ToolFunctionDef};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_request_shape() {
+        let call = ToolCallMessage {
+            id: "call_1".into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "price".into(),
+                arguments: r#"{"token":"SOL"}"#.into(),
+            },
+        };
+        let mut photo = ChatMessage::user("");
+        photo.content = Some(json!([
+            {"type": "text", "text": "What is this?"},
+            {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0"}},
+            {"type": "image_url", "image_url": {"url": "https://example.com/chart.png"}},
+        ]));
+        let messages = [
+            ChatMessage::system("You are CrabbyBot."),
+            photo,
+            ChatMessage::assistant_with_tool_calls(None, vec![call]),
+            ChatMessage::tool_result("call_1", "price", "$142"),
+        ];
+        let tools = [ToolDefinition {
+            def_type: "function".into(),
+            function: ToolFunctionDef {
+                name: "price".into(),
+                description: "Token price".into(),
+                parameters: json!({"type": "object"}),
+            },
+        }];
+        let provider = OllamaProvider::new(Some("http://gpu-box:11434/v1/"), "ollama/llama3.2", Client::new())
+            .keep_alive(Some("-1"));
+        assert_eq!(provider.base_url(), "http://gpu-box:11434");
+
+        let request = serde_json::to_value(provider.request(&messages, &tools, None, 500, 0.2)).unwrap();
+        assert_eq!(request["model"], "llama3.2");
+        assert_eq!(request["keep_alive"], -1);
+        assert_eq!(request["options"]["num_predict"], 500);
+        assert_eq!(request["tools"][0]["function"]["name"], "price");
+
+        let turns = request["messages"].as_array().unwrap();
+        assert_eq!(turns[1]["content"], "What is this?");
+        assert_eq!(turns[1]["images"], json!(["iVBORw0"]));
+        assert_eq!(turns[2]["tool_calls"][0]["function"]["arguments"]["token"], "SOL");
+        assert_eq!(turns[3]["tool_name"], "price");
+
+        let provider = provider.keep_alive(Some("30m"));
+        assert_eq!(provider.request(&[], &[], None, 1, 0.0).keep_alive, Some(&json!("30m")));
+    }
+
+    #[test]
+    fn test_stream_assembly() {
+        let shown = Mutex::new(String::new());
+        let on_delta = |d: &str| shown.lock().unwrap().push_str(d);
+        let lines = [
+            r#"{"message":{"role":"assistant","content":"<think>price?"},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"</think>Checking "},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"now."},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"price","arguments":{"token":"SOL"}}}]},"done":false}"#,
+            "",
+            r#"{"message":{"role":"assistant","content":""},"done":true,"done_reason":"stop","prompt_eval_count":40,"eval_count":12}"#,
+        ];
+        let mut stream = StreamAssembler::default();
+        let done: Vec<bool> = lines.iter().map(|l| stream.push_line(l, &on_delta).unwrap()).collect();
+        assert_eq!(done.last(), Some(&true));
+
+        let response = stream.finish(&on_delta).unwrap();
+        assert_eq!(*shown.lock().unwrap(), "Checking now.");
+        assert_eq!(response.content.as_deref(), Some("Checking now."));
+        assert_eq!(response.finish_reason, "tool_calls");
+        assert_eq!(response.tool_calls[0].arguments["token"], "SOL");
+        assert!(response.tool_calls[0].id.starts_with("call_"));
+        assert_eq!(response.usage.total_tokens, 52);
+
+        let mut failed = StreamAssembler::default();
+        assert!(failed.push_line(r#"{"error":"model 'llama9' not found"}"#, &on_delta).is_err());
+        assert!(StreamAssembler::default().finish(&on_delta).is_err());
+    }
+
+    #[test]
+    fn test_model_list_parses() {
+        let body = r#"{"models":[
+            {"name":"llama3.2:latest","size":2019393189,"modified_at":"2025-01-10T12:00:00.5+01:00","details":{"family":"llama","parameter_size":"3.2B","quantization_level":"Q4_K_M"}},
+            {"name":"qwen3:8b","size":5225388164,"modified_at":"2025-05-01T08:00:00Z","details":{}}
+        ]}"#;
+        let models = serde_json::from_str::<TagsResponse>(body).unwrap().models;
+        assert_eq!(models[0].details.parameter_size, "3.2B");
+        assert!(models[1].modified_at > models[0].modified_at);
+    }
+}
@@ -5,7 +5,8 @@
 //!
 //! - OpenAI (`https://api.openai.com/v1`)
 //! - OpenRouter (`https://openrouter.ai/api/v1`)
-//! - Anthropic via OpenRouter
+//! - Anthropic via OpenRouter, or its own compatibility endpoint
+//!   (see [`anthropic`](super::anthropic) for the native API)
 //! - DeepSeek (`https://api.deepseek.com/v1`)
 //! - Groq (`https://api.groq.com/openai/v1`)
 //! - Gemini (`https://generativelanguage.googleapis.com/v1beta/openai`)
@@ -13,14 +14,17 @@
 //!
 //! No LiteLLM dependency — just direct HTTP via `reqwest`.
 
-use anyhow::{Context, Result};
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
 use tracing::{debug, warn};
 
+use super::endpoints::{EndpointPool, EndpointStrategy};
 use super::types::{ChatMessage, LlmResponse, ToolCallRequest, ToolDefinition, Usage};
-use super::LlmProvider;
+use super::{repair, LlmProvider, OnDelta, ProviderError};
 
 /// Known provider base URLs.
 const PROVIDER_URLS: &[(&str, &str)] = &[
@@ -33,24 +37,47 @@ const PROVIDER_URLS: &[(&str, &str)] = &[
         "gemini",
         "https://generativelanguage.googleapis.com/v1beta/openai",
     ),
+    ("ollama", "http://localhost:11434/v1"),
 ];
 
-/// Maximum number of retry attempts for transient errors.
-const MAX_RETRIES: u32 = 3;
-
-/// Base delay for exponential backoff (milliseconds).
-const BASE_DELAY_MS: u64 = 500;
-
 /// OpenAI-compatible provider that works with any provider exposing the
 /// `/chat/completions` endpoint.
 ///
 /// Includes automatic retry with exponential backoff for transient HTTP
-/// errors (429, 500, 502, 503, 504) and network failures.
+/// errors (429, 500, 502, 503, 504) and network failures. With several
+/// endpoints configured, retries move on to the next endpoint first.
 pub struct OpenAiProvider {
     client: Client,
     api_key: String,
-    base_url: String,
+    endpoints: EndpointPool,
     default_model: String,
+    sequential_tool_models: Vec<String>,
+    reasoning_effort: Option<String>,
+}
+
+/// How a model family deviates from the standard chat-completions parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReasoningKind {
+    /// OpenAI o-series / GPT-5: `max_completion_tokens` instead of
+    /// `max_tokens`, optional `reasoning_effort`, no custom temperature.
+    OpenAi,
+    /// DeepSeek-R1 and derivatives: no custom temperature; thinking comes
+    /// back in `reasoning_content` or inline `<think>` blocks.
+    DeepSeek,
+}
+
+impl ReasoningKind {
+    fn detect(model: &str) -> Option<Self> {
+        let id = model.rsplit('/').next().unwrap_or(model).to_ascii_lowercase();
+        let bytes = id.as_bytes();
+        if (bytes.len() >= 2 && bytes[0] == b'o' && bytes[1].is_ascii_digit()) || id.starts_with("gpt-5") {
+            Some(Self::OpenAi)
+        } else if id.contains("deepseek-reasoner") || id.contains("deepseek-r1") {
+            Some(Self::DeepSeek)
+        } else {
+            None
+        }
+    }
 }
 
 impl OpenAiProvider {
@@ -85,11 +112,107 @@ impl OpenAiProvider {
         Self {
             client,
             api_key: api_key.to_string(),
-            base_url,
+            endpoints: EndpointPool::new(vec![base_url], EndpointStrategy::default()),
             default_model: default_model.to_string(),
+            sequential_tool_models: Vec::new(),
+            reasoning_effort: None,
+        }
+    }
+
+    /// `reasoning_effort` for OpenAI-style reasoning models; ignored by others.
+    pub fn reasoning_effort(mut self, effort: Option<String>) -> Self {
+        self.reasoning_effort = effort;
+        self
+    }
+
+    /// Extra base URLs for the same deployment, tried after the primary one
+    /// in the order `strategy` picks.
+    pub fn endpoints(mut self, extra: Vec<String>, strategy: EndpointStrategy) -> Self {
+        let mut urls = self.endpoints.urls().to_vec();
+        for url in extra {
+            let url = url.trim().trim_end_matches('/').to_string();
+            if !url.is_empty() && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        self.endpoints = EndpointPool::new(urls, strategy);
+        self
+    }
+
+    /// Models that only handle one tool call per turn. Entries ending in `*`
+    /// match by prefix, so `"*"` covers every model on this provider.
+    pub fn sequential_tool_models(mut self, models: Vec<String>) -> Self {
+        self.sequential_tool_models = models;
+        self
+    }
+
+    fn is_sequential(&self, model: &str) -> bool {
+        super::matches_model(&self.sequential_tool_models, model)
+    }
+
+    /// The primary API base URL.
+    pub fn base_url(&self) -> &str {
+        &self.endpoints.urls()[0]
+    }
+
+    fn request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: &'a [ToolDefinition],
+        model: &'a str,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> CompletionRequest<'a> {
+        let tools_opt = if tools.is_empty() { None } else { Some(tools) };
+        let reasoning = ReasoningKind::detect(model);
+        let openai_reasoning = reasoning == Some(ReasoningKind::OpenAi);
+
+        CompletionRequest {
+            model,
+            messages: move_tool_images(messages),
+            max_tokens: (!openai_reasoning).then_some(max_tokens),
+            max_completion_tokens: openai_reasoning.then_some(max_tokens),
+            temperature: reasoning.is_none().then_some(temperature),
+            reasoning_effort: self
+                .reasoning_effort
+                .as_deref()
+                .filter(|_| openai_reasoning),
+            tools: tools_opt,
+            tool_choice: if tools_opt.is_some() {
+                Some("auto")
+            } else {
+                None
+            },
+            parallel_tool_calls: (tools_opt.is_some() && self.is_sequential(model)).then_some(false),
+            stream: false,
+            stream_options: None,
         }
     }
 
+    /// POST `request` to `{base}/chat/completions`, see [`EndpointPool::send`].
+    async fn send(&self, request: &CompletionRequest<'_>) -> Result<reqwest::Response, ProviderError> {
+        debug!(
+            model = request.model,
+            msg_count = request.messages.len(),
+            stream = request.stream,
+            "Sending chat completion request"
+        );
+        let post = |base: &str| {
+            self.client
+                .post(format!("{}/chat/completions", base))
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(request)
+        };
+        let api_error = |status: reqwest::StatusCode, body: String| ProviderError::Api {
+            status: status.as_u16(),
+            message: serde_json::from_str::<ErrorResponse>(&body)
+                .map(|e| e.message())
+                .unwrap_or(body),
+        };
+        self.endpoints.send(post, Self::is_retryable_status, api_error).await
+    }
+
     /// Returns `true` if the HTTP status code is transient and should be retried.
     fn is_retryable_status(status: reqwest::StatusCode) -> bool {
         matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
@@ -101,13 +224,31 @@ impl OpenAiProvider {
 #[derive(Serialize)]
 struct CompletionRequest<'a> {
     model: &'a str,
-    messages: &'a [ChatMessage],
-    max_tokens: u32,
-    temperature: f32,
+    messages: Cow<'a, [ChatMessage]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<&'a [ToolDefinition]>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    /// Ask for a final chunk carrying token usage.
+    include_usage: bool,
 }
 
 #[derive(Deserialize)]
@@ -126,17 +267,23 @@ struct Choice {
 #[derive(Deserialize)]
 struct MessageResponse {
     content: Option<String>,
+    /// DeepSeek's name for the model's thinking.
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    /// OpenRouter's name for the same.
+    #[serde(default)]
+    reasoning: Option<String>,
     #[serde(default)]
     tool_calls: Option<Vec<ToolCallResponse>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct ToolCallResponse {
     id: String,
     function: FunctionCallResponse,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct FunctionCallResponse {
     name: String,
     arguments: String,
@@ -149,6 +296,56 @@ struct UsageResponse {
     total_tokens: Option<u32>,
 }
 
+/// One `data:` event of a streamed completion.
+#[derive(Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<UsageResponse>,
+    /// Some providers report failures mid-stream this way.
+    #[serde(default)]
+    error: Option<ErrorDetail>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Option<Delta>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    reasoning: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+#[derive(Deserialize)]
+struct ToolCallDelta {
+    #[serde(default)]
+    index: Option<usize>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<FunctionDelta>,
+}
+
+#[derive(Deserialize)]
+struct FunctionDelta {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
 enum ErrorResponse {
@@ -189,145 +386,346 @@ impl LlmProvider for OpenAiProvider {
         model: Option<&str>,
         max_tokens: u32,
         temperature: f32,
-    ) -> Result<LlmResponse> {
+    ) -> Result<LlmResponse, ProviderError> {
         let model = model.unwrap_or(&self.default_model);
-        let url = format!("{}/chat/completions", self.base_url);
-
-        let tools_opt = if tools.is_empty() { None } else { Some(tools) };
+        let request = self.request(messages, tools, model, max_tokens, temperature);
+        let body = self.send(&request).await?.text().await?;
+        let completion: CompletionResponse =
+            serde_json::from_str(&body).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+
+        let choice = completion
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::InvalidResponse("no choices returned".into()))?;
+        Ok(into_response(choice.message, choice.finish_reason, completion.usage))
+    }
 
-        let request_body = CompletionRequest {
-            model,
-            messages,
-            max_tokens,
-            temperature,
-            tools: tools_opt,
-            tool_choice: if tools_opt.is_some() {
-                Some("auto")
-            } else {
-                None
-            },
+    /// Requests a server-sent event stream and passes content deltas on as
+    /// they arrive. Thinking, whether in `reasoning_content` or in inline
+    /// `<think>` blocks, is never passed on.
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        max_tokens: u32,
+        temperature: f32,
+        on_delta: OnDelta<'_>,
+    ) -> Result<LlmResponse, ProviderError> {
+        let model = model.unwrap_or(&self.default_model);
+        let request = CompletionRequest {
+            stream: true,
+            stream_options: Some(StreamOptions { include_usage: true }),
+            ..self.request(messages, tools, model, max_tokens, temperature)
         };
+        let mut body = self.send(&request).await?.bytes_stream();
+
+        // R1-style models may open the thinking block in the prompt
+        // template, so their text is held back until it is closed.
+        let mut stream = StreamAssembler::new(ReasoningKind::detect(model) == Some(ReasoningKind::DeepSeek));
+        let mut pending = Vec::new();
+        'read: while let Some(bytes) = body.next().await {
+            pending.extend_from_slice(&bytes?);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                if stream.push_line(&String::from_utf8_lossy(&line), on_delta)? {
+                    break 'read;
+                }
+            }
+        }
+        stream.finish(on_delta)
+    }
 
-        debug!(model, url = %url, msg_count = messages.len(), "Sending chat completion request");
+    fn default_model(&self) -> &str {
+        &self.default_model
+    }
 
-        // ── Retry loop with exponential backoff ────────────────────
-        let mut last_error: Option<anyhow::Error> = None;
+    fn supports_parallel_tool_calls(&self, model: Option<&str>) -> bool {
+        !self.is_sequential(model.unwrap_or(&self.default_model))
+    }
 
-        for attempt in 0..MAX_RETRIES {
-            if attempt > 0 {
-                let delay = BASE_DELAY_MS * 2u64.pow(attempt - 1);
-                warn!(attempt, delay_ms = delay, "Retrying LLM API request");
-                tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
-            }
+    fn supports_images(&self, model: Option<&str>) -> bool {
+        super::is_vision_model(model.unwrap_or(&self.default_model))
+    }
 
-            let result = self
-                .client
-                .post(&url)
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-                .await;
+    fn models_request(&self) -> Option<reqwest::RequestBuilder> {
+        Some(self.client.get(format!("{}/models", self.base_url())).bearer_auth(&self.api_key))
+    }
+}
 
-            let response = match result {
-                Ok(r) => r,
+/// Chat Completions only takes images in user messages, so images returned
+/// by tools move to a user message after the results of that round of
+/// calls; the tool messages keep their text.
+fn move_tool_images(messages: &[ChatMessage]) -> Cow<'_, [ChatMessage]> {
+    if !messages.iter().any(|m| m.role == "tool" && m.image_count() > 0) {
+        return Cow::Borrowed(messages);
+    }
+    let mut out = Vec::with_capacity(messages.len() + 1);
+    let mut images = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        let mut message = message.clone();
+        if message.role == "tool" && message.image_count() > 0 {
+            let name = message.name.as_deref().unwrap_or("a tool");
+            images.push(serde_json::json!({"type": "text", "text": format!("Image from {}:", name)}));
+            if let Some(Value::Array(parts)) = &message.content {
+                images.extend(parts.iter().filter(|p| p["type"] == "image_url").cloned());
+            }
+            message.content = message.text().map(Value::String);
+        }
+        out.push(message);
+        let round_ends = messages.get(i + 1).is_none_or(|next| next.role != "tool");
+        if round_ends && !images.is_empty() {
+            let mut user = ChatMessage::user("");
+            user.content = Some(Value::Array(std::mem::take(&mut images)));
+            out.push(user);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Turn the model's message into an [`LlmResponse`]: tool arguments are
+/// parsed (and repaired) and thinking is stripped from the content.
+fn into_response(
+    message: MessageResponse,
+    finish_reason: Option<String>,
+    usage: Option<UsageResponse>,
+) -> LlmResponse {
+    // Parse tool calls
+    let tool_calls = match message.tool_calls {
+        Some(tcs) => tcs
+            .into_iter()
+            .map(|tc| match repair::parse_arguments(&tc.function.arguments) {
+                Ok((arguments, repaired)) => {
+                    if repaired {
+                        debug!(
+                            tool = tc.function.name,
+                            raw = tc.function.arguments,
+                            "Repaired malformed tool arguments"
+                        );
+                    }
+                    ToolCallRequest {
+                        id: tc.id,
+                        name: tc.function.name,
+                        arguments,
+                        parse_error: None,
+                    }
+                }
                 Err(e) => {
-                    // Network-level errors are always retryable.
-                    warn!(attempt, error = %e, "Network error calling LLM API");
-                    last_error = Some(e.into());
-                    continue;
+                    warn!(
+                        tool = tc.function.name,
+                        error = %e,
+                        raw = tc.function.arguments,
+                        "Failed to parse tool arguments"
+                    );
+                    ToolCallRequest {
+                        id: tc.id,
+                        name: tc.function.name,
+                        arguments: serde_json::Map::new(),
+                        parse_error: Some(e),
+                    }
                 }
-            };
-
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .context("Failed to read LLM API response body")?;
-
-            if !status.is_success() {
-                let err_msg = serde_json::from_str::<ErrorResponse>(&body)
-                    .map(|e| e.message())
-                    .unwrap_or_else(|_| body.clone());
-
-                if Self::is_retryable_status(status) {
-                    warn!(attempt, status = %status, "Transient LLM API error, will retry");
-                    last_error = Some(anyhow::anyhow!("LLM API error ({}): {}", status, err_msg));
-                    continue;
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let usage = usage.map_or(Usage::default(), |u| Usage {
+        prompt_tokens: u.prompt_tokens.unwrap_or(0),
+        completion_tokens: u.completion_tokens.unwrap_or(0),
+        total_tokens: u.total_tokens.unwrap_or(0),
+    });
+
+    debug!(
+        finish_reason = finish_reason.as_deref().unwrap_or("unknown"),
+        tool_calls = tool_calls.len(),
+        tokens = usage.total_tokens,
+        "Received LLM response"
+    );
+
+    // Thinking never reaches the session or the user.
+    let thinking = message.reasoning_content.or(message.reasoning);
+    let content = message.content.and_then(|c| {
+        let (answer, inline) = strip_thinking(&c);
+        if let Some(t) = thinking.as_deref().or(inline.as_deref()) {
+            debug!(thinking_chars = t.len(), "Stripped model reasoning from reply");
+        }
+        (!answer.is_empty()).then_some(answer)
+    });
+
+    LlmResponse {
+        content,
+        tool_calls,
+        finish_reason: finish_reason.unwrap_or_else(|| "stop".into()),
+        usage,
+    }
+}
+
+/// Builds a completion from server-sent event lines, passing on the part
+/// of the content that is answer rather than thinking as it grows.
+#[derive(Default)]
+struct StreamAssembler {
+    /// Show nothing until `</think>` (or the end of the stream).
+    hold: bool,
+    content: String,
+    thinking: String,
+    tool_calls: Vec<ToolCallResponse>,
+    finish_reason: Option<String>,
+    usage: Option<UsageResponse>,
+    /// Answer text already passed on.
+    shown: String,
+    received: bool,
+}
+
+impl StreamAssembler {
+    fn new(hold: bool) -> Self {
+        Self {
+            hold,
+            ..Default::default()
+        }
+    }
+
+    /// Take in one line of the event stream. Returns `true` at `[DONE]`.
+    fn push_line(&mut self, line: &str, on_delta: OnDelta<'_>) -> Result<bool, ProviderError> {
+        // Blank lines separate events; `:` lines are keep-alive comments
+        // and `event:`/`id:` lines carry nothing we need.
+        let Some(data) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") else {
+            return Ok(false);
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            return Ok(true);
+        }
+        let chunk: StreamChunk =
+            serde_json::from_str(data).map_err(|e| ProviderError::InvalidResponse(e.to_string()))?;
+        if let Some(error) = chunk.error {
+            return Err(ProviderError::InvalidResponse(error.message));
+        }
+        self.received = true;
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+        for choice in chunk.choices {
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+            let Some(delta) = choice.delta else { continue };
+            if let Some(thinking) = delta.reasoning_content.or(delta.reasoning) {
+                self.thinking.push_str(&thinking);
+            }
+            for call in delta.tool_calls.unwrap_or_default() {
+                self.push_tool_call(call);
+            }
+            if let Some(content) = delta.content.filter(|c| !c.is_empty()) {
+                self.content.push_str(&content);
+                if let Some(new) = visible_answer(&self.content, self.hold)
+                    .and_then(|answer| answer.strip_prefix(self.shown.as_str()))
+                    .filter(|new| !new.is_empty())
+                {
+                    on_delta(new);
+                    self.shown.push_str(new);
                 }
+            }
+        }
+        Ok(false)
+    }
 
-                // Non-retryable error — fail immediately.
-                anyhow::bail!("LLM API error ({}): {}", status, err_msg);
+    /// Tool calls arrive in pieces keyed by `index`; the arguments are
+    /// concatenated. Providers that leave out `index` start a new call
+    /// with each new `id`.
+    fn push_tool_call(&mut self, delta: ToolCallDelta) {
+        let index = delta.index.unwrap_or_else(|| match &delta.id {
+            Some(id) if !self.tool_calls.iter().any(|c| &c.id == id) => self.tool_calls.len(),
+            _ => self.tool_calls.len().saturating_sub(1),
+        });
+        if self.tool_calls.len() <= index {
+            self.tool_calls.resize_with(index + 1, Default::default);
+        }
+        let call = &mut self.tool_calls[index];
+        if let Some(id) = delta.id.filter(|id| !id.is_empty()) {
+            call.id = id;
+        }
+        if let Some(function) = delta.function {
+            if let Some(name) = function.name.filter(|_| call.function.name.is_empty()) {
+                call.function.name = name;
+            }
+            if let Some(arguments) = function.arguments {
+                call.function.arguments.push_str(&arguments);
             }
+        }
+    }
 
-            // ── Success path — parse the response ──────────────────
-            let completion: CompletionResponse =
-                serde_json::from_str(&body).context("Failed to parse LLM API response")?;
-
-            let choice = completion
-                .choices
-                .into_iter()
-                .next()
-                .context("LLM API returned no choices")?;
-
-            // Parse tool calls
-            let tool_calls = match choice.message.tool_calls {
-                Some(tcs) => tcs
-                    .into_iter()
-                    .filter_map(|tc| {
-                        match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
-                            &tc.function.arguments,
-                        ) {
-                            Ok(args) => Some(ToolCallRequest {
-                                id: tc.id,
-                                name: tc.function.name,
-                                arguments: args,
-                            }),
-                            Err(e) => {
-                                warn!(
-                                    tool = tc.function.name,
-                                    error = %e,
-                                    raw = tc.function.arguments,
-                                    "Failed to parse tool arguments, skipping"
-                                );
-                                None
-                            }
-                        }
-                    })
-                    .collect(),
-                None => Vec::new(),
-            };
-
-            let usage = completion.usage.map_or(Usage::default(), |u| Usage {
-                prompt_tokens: u.prompt_tokens.unwrap_or(0),
-                completion_tokens: u.completion_tokens.unwrap_or(0),
-                total_tokens: u.total_tokens.unwrap_or(0),
-            });
-
-            debug!(
-                finish_reason = choice.finish_reason.as_deref().unwrap_or("unknown"),
-                tool_calls = tool_calls.len(),
-                tokens = usage.total_tokens,
-                "Received LLM response"
-            );
-
-            return Ok(LlmResponse {
-                content: choice.message.content,
-                tool_calls,
-                finish_reason: choice.finish_reason.unwrap_or_else(|| "stop".into()),
-                usage,
-            });
+    /// The completed response. Held-back text nothing was shown of yet is
+    /// passed on in one piece.
+    fn finish(self, on_delta: OnDelta<'_>) -> Result<LlmResponse, ProviderError> {
+        if !self.received {
+            return Err(ProviderError::InvalidResponse("stream ended without a completion".into()));
+        }
+        let nothing_shown = self.shown.is_empty();
+        let message = MessageResponse {
+            content: (!self.content.is_empty()).then_some(self.content),
+            reasoning_content: (!self.thinking.is_empty()).then_some(self.thinking),
+            reasoning: None,
+            tool_calls: (!self.tool_calls.is_empty()).then_some(self.tool_calls),
+        };
+        let response = into_response(message, self.finish_reason, self.usage);
+        if let Some(content) = response.content.as_deref().filter(|_| nothing_shown) {
+            on_delta(content);
         }
+        Ok(response)
+    }
+}
 
-        // All retries exhausted.
-        Err(last_error.unwrap_or_else(|| {
-            anyhow::anyhow!("LLM API request failed after {} retries", MAX_RETRIES)
-        }))
+/// The answer part of partially streamed `content`, or `None` while it may
+/// still be thinking.
+pub(super) fn visible_answer(content: &str, hold: bool) -> Option<&str> {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+    if let Some(end) = content.rfind(CLOSE) {
+        return Some(content[end + CLOSE.len()..].trim_start());
     }
+    let head = content.trim_start();
+    if hold || head.starts_with(OPEN) || OPEN.starts_with(head) {
+        None
+    } else {
+        Some(head)
+    }
+}
 
-    fn default_model(&self) -> &str {
-        &self.default_model
+/// Split `<think>…</think>` blocks out of a reply, returning the answer and
+/// the thinking text. An unclosed `<think>` swallows the rest of the reply,
+/// since it means the model ran out of tokens mid-thought.
+pub(super) fn strip_thinking(content: &str) -> (String, Option<String>) {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+    let mut answer = String::new();
+    let mut thinking = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(OPEN) {
+        answer.push_str(&rest[..start]);
+        let after = &rest[start + OPEN.len()..];
+        match after.find(CLOSE) {
+            Some(end) => {
+                thinking.push(after[..end].trim());
+                rest = &after[end + CLOSE.len()..];
+            }
+            None => {
+                thinking.push(after.trim());
+                rest = "";
+            }
+        }
+    }
+    // Some R1 deployments put `<think>` in the prompt template, so the reply
+    // only carries the closing tag.
+    if thinking.is_empty() {
+        if let Some(end) = rest.find(CLOSE) {
+            thinking.push(rest[..end].trim());
+            rest = &rest[end + CLOSE.len()..];
+        }
     }
+    answer.push_str(rest);
+    let thinking = (!thinking.is_empty()).then(|| thinking.join("\n\n"));
+    (answer.trim().to_string(), thinking)
 }
 
 #[cfg(test)]
@@ -338,10 +736,10 @@ mod tests {
     fn test_provider_url_lookup() {
         let client = Client::new();
         let p = OpenAiProvider::new("openrouter", "test-key", None, "test-model", client.clone());
-        assert_eq!(p.base_url, "https://openrouter.ai/api/v1");
+        assert_eq!(p.base_url(), "https://openrouter.ai/api/v1");
 
         let p = OpenAiProvider::new("deepseek", "test-key", None, "test-model", client);
-        assert_eq!(p.base_url, "https://api.deepseek.com/v1");
+        assert_eq!(p.base_url(), "https://api.deepseek.com/v1");
     }
 
     #[test]
@@ -353,7 +751,134 @@ mod tests {
             "llama-3",
             Client::new(),
         );
-        assert_eq!(p.base_url, "http://localhost:8000/v1");
+        assert_eq!(p.base_url(), "http://localhost:8000/v1");
+    }
+
+    #[test]
+    fn test_extra_endpoints() {
+        let p = OpenAiProvider::new("vllm", "k", Some("http://a:8000/v1/"), "llama-3", Client::new()).endpoints(
+            vec!["http://b:8000/v1/".into(), "http://a:8000/v1".into(), " ".into()],
+            EndpointStrategy::RoundRobin,
+        );
+        assert_eq!(p.base_url(), "http://a:8000/v1");
+        assert_eq!(p.endpoints.urls(), ["http://a:8000/v1", "http://b:8000/v1"]);
+    }
+
+    #[test]
+    fn test_sequential_tool_models() {
+        let p = OpenAiProvider::new("openrouter", "k", None, "meta-llama/llama-3-8b", Client::new())
+            .sequential_tool_models(vec!["meta-llama/*".into(), "qwen-7b".into()]);
+        assert!(!p.supports_parallel_tool_calls(None));
+        assert!(!p.supports_parallel_tool_calls(Some("Qwen-7B")));
+        assert!(p.supports_parallel_tool_calls(Some("openai/gpt-4o")));
+
+        let all = OpenAiProvider::new("vllm", "k", None, "any", Client::new())
+            .sequential_tool_models(vec!["*".into()]);
+        assert!(!all.supports_parallel_tool_calls(Some("other")));
+    }
+
+    #[test]
+    fn test_tool_images_move_to_a_user_message() {
+        let text_only = [ChatMessage::user("hi")];
+        assert!(matches!(move_tool_images(&text_only), Cow::Borrowed(_)));
+
+        let image = "data:image/png;base64,iVBORw0".to_string();
+        let messages = [
+            ChatMessage::user("chart SOL"),
+            ChatMessage::assistant_with_tool_calls(None, vec![]),
+            ChatMessage::tool_result_with_images("c1", "plot", "Chart rendered.", &[image]),
+            ChatMessage::tool_result("c2", "price", "SOL 150"),
+            ChatMessage::assistant("Here it is."),
+        ];
+        let moved = move_tool_images(&messages);
+        let roles: Vec<_> = moved.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "tool", "tool", "user", "assistant"]);
+        assert_eq!(moved[2].content_as_str(), Some("Chart rendered."));
+        let parts = moved[4].content.as_ref().unwrap();
+        assert_eq!(parts[0]["text"], "Image from plot:");
+        assert_eq!(parts[1]["image_url"]["url"], "data:image/png;base64,iVBORw0");
+    }
+
+    #[test]
+    fn test_reasoning_model_parameters() {
+        assert_eq!(ReasoningKind::detect("o3-mini"), Some(ReasoningKind::OpenAi));
+        assert_eq!(ReasoningKind::detect("openai/o1"), Some(ReasoningKind::OpenAi));
+        assert_eq!(ReasoningKind::detect("deepseek/deepseek-r1"), Some(ReasoningKind::DeepSeek));
+        assert_eq!(ReasoningKind::detect("deepseek-reasoner"), Some(ReasoningKind::DeepSeek));
+        assert_eq!(ReasoningKind::detect("openai/gpt-4o"), None);
+        assert_eq!(ReasoningKind::detect("ollama"), None);
+
+        let request = CompletionRequest {
+            model: "o3-mini",
+            messages: Cow::Borrowed(&[]),
+            max_tokens: None,
+            max_completion_tokens: Some(100),
+            temperature: None,
+            reasoning_effort: Some("low"),
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            stream: false,
+            stream_options: None,
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["max_completion_tokens"], 100);
+        assert_eq!(json["reasoning_effort"], "low");
+        assert!(json.get("temperature").is_none());
+        assert!(json.get("max_tokens").is_none());
+    }
+
+    #[test]
+    fn test_strip_thinking() {
+        let (answer, thinking) = strip_thinking("<think>\nhmm, 2+2\n</think>\n\nIt's 4.");
+        assert_eq!(answer, "It's 4.");
+        assert_eq!(thinking.as_deref(), Some("hmm, 2+2"));
+
+        let (answer, thinking) = strip_thinking("the user wants X</think>Here is X.");
+        assert_eq!(answer, "Here is X.");
+        assert_eq!(thinking.as_deref(), Some("the user wants X"));
+
+        assert_eq!(strip_thinking("<think>never finished").0, "");
+        assert_eq!(strip_thinking("plain reply").0, "plain reply");
+        assert!(strip_thinking("plain reply").1.is_none());
+    }
+
+    #[test]
+    fn test_stream_assembly() {
+        use std::sync::Mutex;
+        let shown = Mutex::new(Vec::<String>::new());
+        let on_delta = |d: &str| shown.lock().unwrap().push(d.to_string());
+        let lines = [
+            r#"data: {"choices":[{"delta":{"content":"<think>hmm"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"</think>\n\nIt's "}}]}"#,
+            ": keep-alive",
+            r#"data: {"choices":[{"delta":{"content":"4."}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"c1","function":{"name":"calc","arguments":"{\"x\":"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"2}"}}]},"finish_reason":"tool_calls"}]}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":7,"total_tokens":12}}"#,
+            "data: [DONE]",
+        ];
+        let mut stream = StreamAssembler::new(false);
+        let done: Vec<bool> = lines.iter().map(|l| stream.push_line(l, &on_delta).unwrap()).collect();
+        assert_eq!(done.iter().filter(|d| **d).count(), 1);
+        let response = stream.finish(&on_delta).unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["It's ", "4."]);
+        assert_eq!(response.content.as_deref(), Some("It's 4."));
+        assert_eq!(response.tool_calls[0].name, "calc");
+        assert_eq!(response.tool_calls[0].arguments["x"], 2);
+        assert_eq!((response.finish_reason.as_str(), response.usage.total_tokens), ("tool_calls", 12));
+
+        // Held-back text arrives in one piece at the end.
+        shown.lock().unwrap().clear();
+        let mut held = StreamAssembler::new(true);
+        held.push_line(r#"data: {"choices":[{"delta":{"content":"Plain answer"}}]}"#, &on_delta).unwrap();
+        assert!(shown.lock().unwrap().is_empty());
+        held.finish(&on_delta).unwrap();
+        assert_eq!(*shown.lock().unwrap(), ["Plain answer"]);
+
+        let mut failed = StreamAssembler::new(false);
+        assert!(failed.push_line(r#"data: {"error":{"message":"overloaded"}}"#, &on_delta).is_err());
+        assert!(StreamAssembler::new(false).finish(&on_delta).is_err());
     }
 
     #[test]
@@ -0,0 +1,251 @@
+//! Repair for malformed tool calls.
+//!
+//! Smaller and open-weight models regularly emit tool arguments that are
+//! almost JSON (single quotes, trailing commas, Python literals, markdown
+//! fences, a missing closing brace) or call a tool by a slightly wrong name
+//! (`ReadFile`, `functions.read_file`). These helpers recover what can be
+//! recovered unambiguously; anything else is reported back to the model so
+//! it can retry.
+
+use serde_json::{Map, Value};
+
+/// Parse tool-call arguments, repairing near-JSON if strict parsing fails.
+///
+/// Returns the arguments and whether a repair was needed. An empty string is
+/// accepted as `{}` since several providers send it for argument-less calls.
+pub fn parse_arguments(raw: &str) -> Result<(Map<String, Value>, bool), String> {
+    if raw.trim().is_empty() {
+        return Ok((Map::new(), false));
+    }
+    let strict_err = match serde_json::from_str::<Value>(raw) {
+        Ok(value) => match as_object(value) {
+            Some(map) => return Ok((map, false)),
+            None => "arguments must be a JSON object".to_string(),
+        },
+        Err(e) => e.to_string(),
+    };
+    serde_json::from_str::<Value>(&repair_json(raw))
+        .ok()
+        .and_then(as_object)
+        .map(|map| (map, true))
+        .ok_or(strict_err)
+}
+
+/// Accept an object, or a string that itself contains a JSON object
+/// (double-encoded arguments).
+fn as_object(value: Value) -> Option<Map<String, Value>> {
+    match value {
+        Value::Object(map) => Some(map),
+        Value::String(s) => match serde_json::from_str(&s) {
+            Ok(Value::Object(map)) => Some(map),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Best-effort rewrite of near-JSON into JSON.
+///
+/// Handles markdown code fences, single-quoted strings, trailing commas,
+/// `True`/`False`/`None`, and unclosed strings, objects, and arrays.
+pub fn repair_json(raw: &str) -> String {
+    let text = strip_fences(raw.trim());
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + 8);
+    let mut closers: Vec<char> = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(q) = quote {
+            match c {
+                '\\' if i + 1 < chars.len() => {
+                    let next = chars[i + 1];
+                    // `\'` is not a valid JSON escape.
+                    if next == '\'' {
+                        out.push('\'');
+                    } else {
+                        out.push(c);
+                        out.push(next);
+                    }
+                    i += 1;
+                }
+                '"' if q == '\'' => out.push_str("\\\""),
+                _ if c == q => {
+                    out.push('"');
+                    quote = None;
+                }
+                '\n' => out.push_str("\\n"),
+                _ => out.push(c),
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                out.push('"');
+            }
+            '{' => {
+                closers.push('}');
+                out.push(c);
+            }
+            '[' => {
+                closers.push(']');
+                out.push(c);
+            }
+            '}' | ']' => {
+                if closers.last() == Some(&c) {
+                    closers.pop();
+                }
+                out.push(c);
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']') | None) {
+                    out.push(c);
+                }
+            }
+            c if c.is_ascii_alphabetic() => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric() && *c != '_')
+                    .map_or(chars.len(), |p| i + p);
+                let word: String = chars[i..end].iter().collect();
+                out.push_str(match word.as_str() {
+                    "True" => "true",
+                    "False" => "false",
+                    "None" => "null",
+                    w => w,
+                });
+                i = end;
+                continue;
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+
+    if quote.is_some() {
+        out.push('"');
+    }
+    let trimmed = out.trim_end().trim_end_matches(',').len();
+    out.truncate(trimmed);
+    while let Some(closer) = closers.pop() {
+        out.push(closer);
+    }
+    out
+}
+
+fn strip_fences(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    rest.strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// Lowercase and drop separators and any `functions.`-style namespace, so
+/// `ReadFile`, `read-file` and `functions.read_file` compare equal.
+fn normalize(name: &str) -> String {
+    let base = name.rsplit(['.', ':', '/']).next().unwrap_or(name);
+    base.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Find the registered tool a misspelled name unambiguously refers to.
+///
+/// Matches on the normalized name first, then on a small edit distance
+/// (one edit per five characters). Returns `None` when no candidate is close
+/// enough or two candidates are equally close.
+pub fn closest_tool_name<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let wanted = normalize(name);
+    if wanted.is_empty() {
+        return None;
+    }
+    let exact: Vec<_> = candidates.iter().filter(|c| normalize(c) == wanted).collect();
+    if let [only] = exact.as_slice() {
+        return Some(only);
+    }
+
+    let max_distance = (wanted.len() / 5).max(1);
+    let mut best: Option<(&'a str, usize)> = None;
+    let mut tied = false;
+    for &candidate in candidates {
+        let d = strsim::levenshtein(&wanted, &normalize(candidate));
+        if d > max_distance {
+            continue;
+        }
+        match best {
+            Some((_, bd)) if d == bd => tied = true,
+            Some((_, bd)) if d > bd => {}
+            _ => {
+                best = Some((candidate, d));
+                tied = false;
+            }
+        }
+    }
+    if tied {
+        None
+    } else {
+        best.map(|(c, _)| c)
+    }
+}
+
+/// Up to `limit` candidate names ranked by similarity, for "did you mean"
+/// feedback.
+pub fn suggest_tool_names<'a>(name: &str, candidates: &[&'a str], limit: usize) -> Vec<&'a str> {
+    let wanted = normalize(name);
+    let mut scored: Vec<_> = candidates
+        .iter()
+        .map(|&c| (strsim::sorensen_dice(&wanted, &normalize(c)), c))
+        .filter(|(score, _)| *score >= 0.4)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored.into_iter().take(limit).map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_arguments_repairs_near_json() {
+        let cases = [
+            (r#"{"path": "a.txt",}"#, json!({"path": "a.txt"})),
+            (r#"{'path': 'it\'s "here"'}"#, json!({"path": "it's \"here\""})),
+            ("```json\n{\"n\": 1, \"x\": [1, 2,]}\n```", json!({"n": 1, "x": [1, 2]})),
+            (r#"{"ok": True, "v": None"#, json!({"ok": true, "v": null})),
+            (r#""{\"a\": 1}""#, json!({"a": 1})),
+        ];
+        for (raw, expected) in cases {
+            let (args, repaired) = parse_arguments(raw).unwrap_or_else(|e| panic!("{raw}: {e}"));
+            assert_eq!(Value::Object(args), expected, "{raw}");
+            assert!(repaired || raw.starts_with('"'), "{raw}");
+        }
+
+        assert!(!parse_arguments(r#"{"a": 1}"#).unwrap().1);
+        assert!(parse_arguments("").unwrap().0.is_empty());
+        assert!(parse_arguments("[1, 2]").is_err());
+        assert!(parse_arguments("not json at all").is_err());
+    }
+
+    #[test]
+    fn test_tool_name_matching() {
+        let tools = ["read_file", "write_file", "web_fetch", "web_search"];
+        assert_eq!(closest_tool_name("ReadFile", &tools), Some("read_file"));
+        assert_eq!(closest_tool_name("functions.web_fetch", &tools), Some("web_fetch"));
+        assert_eq!(closest_tool_name("web_serch", &tools), Some("web_search"));
+        assert_eq!(closest_tool_name("delete_file", &tools), None);
+        assert_eq!(closest_tool_name("", &tools), None);
+
+        let suggestions = suggest_tool_names("fetch_web_page", &tools, 2);
+        assert_eq!(suggestions.first(), Some(&"web_fetch"), "{suggestions:?}");
+        assert!(suggest_tool_names("xyz", &tools, 3).is_empty());
+    }
+}
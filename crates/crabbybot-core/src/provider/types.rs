@@ -4,6 +4,7 @@
 //! Every provider must produce `LlmResponse` from a list of `ChatMessage`s.
 
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// A single message in a conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,10 +73,73 @@ impl ChatMessage {
         }
     }
 
+    /// A tool result with images for the model to look at. The content
+    /// becomes OpenAI-style parts: the text, then one `image_url` part per
+    /// URL. Each provider converts them to its own format.
+    pub fn tool_result_with_images(tool_call_id: &str, name: &str, result: &str, image_urls: &[String]) -> Self {
+        let mut msg = Self::tool_result(tool_call_id, name, result);
+        if !image_urls.is_empty() {
+            let mut parts = vec![serde_json::json!({"type": "text", "text": result})];
+            parts.extend(
+                image_urls
+                    .iter()
+                    .map(|url| serde_json::json!({"type": "image_url", "image_url": {"url": url}})),
+            );
+            msg.content = Some(serde_json::Value::Array(parts));
+        }
+        msg
+    }
+
     /// Get the content as a string, if it is one.
     pub fn content_as_str(&self) -> Option<&str> {
         self.content.as_ref().and_then(|v| v.as_str())
     }
+
+    /// The text of the message: the content string, or the text parts of
+    /// multi-part content joined, without its images.
+    pub fn text(&self) -> Option<String> {
+        match self.content.as_ref()? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Array(parts) => Some(
+                parts
+                    .iter()
+                    .filter_map(|p| p["text"].as_str().filter(|_| p["type"] == "text"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Number of images in multi-part content.
+    pub fn image_count(&self) -> usize {
+        match &self.content {
+            Some(serde_json::Value::Array(parts)) => parts.iter().filter(|p| p["type"] == "image_url").count(),
+            _ => 0,
+        }
+    }
+}
+
+/// A PNG, JPEG, GIF or WebP file as a `data:` URL; `None` for other files,
+/// unreadable ones and those over `max_bytes`.
+pub fn image_data_url(path: &Path, max_bytes: u64) -> Option<String> {
+    use base64::Engine;
+
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "png" | "gif" | "webp" => ext.as_str(),
+        "jpg" | "jpeg" => "jpeg",
+        _ => return None,
+    };
+    if std::fs::metadata(path).ok()?.len() > max_bytes {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!(
+        "data:image/{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
 }
 
 /// A tool call embedded in an assistant message (OpenAI format).
@@ -100,6 +164,9 @@ pub struct ToolCallRequest {
     pub id: String,
     pub name: String,
     pub arguments: serde_json::Map<String, serde_json::Value>,
+    /// Set when the raw arguments could not be parsed even after repair;
+    /// `arguments` is then empty and the call should not be executed.
+    pub parse_error: Option<String>,
 }
 
 /// Response from an LLM provider.
@@ -111,6 +178,14 @@ pub struct LlmResponse {
     pub usage: Usage,
 }
 
+impl LlmResponse {
+    /// Whether the reply stopped at the `max_tokens` limit rather than
+    /// where the model meant to end it.
+    pub fn is_truncated(&self) -> bool {
+        matches!(self.finish_reason.as_str(), "length" | "max_tokens")
+    }
+}
+
 /// Token usage statistics.
 #[derive(Debug, Clone, Default)]
 pub struct Usage {
@@ -159,4 +234,24 @@ mod tests {
         assert_eq!(msg.tool_call_id.as_deref(), Some("call_123"));
         assert_eq!(msg.name.as_deref(), Some("read_file"));
     }
+
+    #[test]
+    fn test_tool_result_with_images() {
+        let plain = ChatMessage::tool_result_with_images("c1", "plot", "Chart saved.", &[]);
+        assert_eq!(plain.content_as_str(), Some("Chart saved."));
+
+        let path = std::env::temp_dir().join(format!("CrabbyBot_test_image_{}.png", std::process::id()));
+        std::fs::write(&path, b"\x89PNG").unwrap();
+        let url = image_data_url(&path, 1024).unwrap();
+        assert_eq!(url, "data:image/png;base64,iVBORw==");
+        assert!(image_data_url(&path, 2).is_none());
+        assert!(image_data_url(Path::new("notes.txt"), 1024).is_none());
+        let _ = std::fs::remove_file(&path);
+
+        let msg = ChatMessage::tool_result_with_images("c1", "plot", "Chart saved.", &[url]);
+        assert_eq!(msg.content_as_str(), None);
+        assert_eq!(msg.text().as_deref(), Some("Chart saved."));
+        assert_eq!(msg.image_count(), 1);
+        assert_eq!(msg.content.unwrap()[1]["image_url"]["url"], "data:image/png;base64,iVBORw==");
+    }
 }
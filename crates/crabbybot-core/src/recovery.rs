@@ -0,0 +1,143 @@
+//! What survived a restart.
+//!
+//! State lives in workspace files: `cron.json` and `cron.log` for scheduled jobs,
+//! `alert_mutes.json` for muted chats, and one JSONL file per session. A
+//! file that no longer parses is renamed to `<name>.corrupt-<timestamp>`
+//! instead of being overwritten, so its contents can still be recovered by
+//! hand. After startup, `run_bot` posts a [`RecoveryReport`] to the admin
+//! chat (`gateway.restartReport`).
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use tracing::{error, warn};
+
+use crate::cron::{CronService, JobKind};
+
+/// Rename `path` to `<name>.corrupt-<timestamp>` next to it.
+pub fn quarantine(path: &Path, now: DateTime<Utc>) -> std::io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now.format("%Y%m%dT%H%M%SZ")));
+    let target = path.with_file_name(name);
+    std::fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Parse the JSON file at `path`, moving it aside when it doesn't parse.
+///
+/// Returns the parsed value (`None` when the file is missing or corrupt)
+/// and where a corrupt file was moved.
+pub fn load_or_quarantine<T: DeserializeOwned>(path: &Path, now: DateTime<Utc>) -> (Option<T>, Option<PathBuf>) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return (None, None);
+    };
+    match serde_json::from_str(&content) {
+        Ok(value) => (Some(value), None),
+        Err(e) => match quarantine(path, now) {
+            Ok(target) => {
+                warn!(path = %path.display(), moved_to = %target.display(), "Corrupt state file moved aside: {}", e);
+                (None, Some(target))
+            }
+            Err(io) => {
+                error!(path = %path.display(), "Corrupt state file could not be moved aside: {}", io);
+                (None, None)
+            }
+        },
+    }
+}
+
+/// State found on startup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Scheduled jobs loaded from `cron.json`.
+    pub cron_jobs: usize,
+    /// Enabled jobs whose run time passed while the bot was down. Each runs
+    /// once on the first tick.
+    pub missed_runs: usize,
+    /// Enabled `tool_call` jobs, which post alerts again from now on.
+    pub monitors: usize,
+    /// Conversation sessions on disk.
+    pub sessions: usize,
+    /// Corrupt files moved aside, by their new path.
+    pub quarantined: Vec<PathBuf>,
+}
+
+impl RecoveryReport {
+    /// Collect the report from the loaded cron store and the workspace.
+    /// A corrupt `alert_mutes.json` is moved aside here.
+    pub fn collect(workspace: &Path, cron: &CronService, sessions: usize) -> Self {
+        let now = cron.clock().now();
+        let jobs = cron.list_jobs(true);
+        let (_, mutes) = load_or_quarantine::<serde_json::Value>(&workspace.join("alert_mutes.json"), now);
+        Self {
+            cron_jobs: jobs.len(),
+            missed_runs: cron.missed_runs(),
+            monitors: jobs
+                .iter()
+                .filter(|j| j.enabled && matches!(j.kind, JobKind::ToolCall(_)))
+                .count(),
+            sessions,
+            quarantined: cron.quarantined().map(Path::to_path_buf).into_iter().chain(mutes).collect(),
+        }
+    }
+
+    /// The chat message.
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "🔄 *Restarted*\n\n\
+             • Scheduled jobs loaded: {}\n\
+             • Missed runs (catching up now): {}\n\
+             • Monitors re-armed: {}\n\
+             • Sessions on disk: {}",
+            self.cron_jobs, self.missed_runs, self.monitors, self.sessions,
+        );
+        if self.quarantined.is_empty() {
+            text.push_str("\n• Corrupted files: none");
+        } else {
+            text.push_str("\n\n⚠️ Corrupted files were moved aside and started fresh:");
+            for path in &self.quarantined {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                text.push_str(&format!("\n• `{}`", name));
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::cron::Schedule;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_report_counts_and_quarantines() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_recovery");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let _ = std::fs::create_dir_all(&tmp);
+        std::fs::write(tmp.join("alert_mutes.json"), "{not json").unwrap();
+
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut cron = CronService::new(&tmp).with_clock(clock.clone());
+        cron.add_job("brief", Schedule::Interval { seconds: 60 }, "Brief", "telegram", "1")
+            .unwrap();
+        cron.get_due_jobs();
+        clock.advance(chrono::Duration::minutes(5));
+
+        let report = RecoveryReport::collect(&tmp, &cron, 3);
+        assert_eq!((report.cron_jobs, report.missed_runs, report.monitors, report.sessions), (1, 1, 0, 3));
+        assert_eq!(report.quarantined.len(), 1);
+        assert!(!tmp.join("alert_mutes.json").exists());
+        assert!(report.quarantined[0].exists());
+        assert!(report.render().contains("alert_mutes.json.corrupt-"));
+
+        std::fs::write(tmp.join("cron.json"), "[[[").unwrap();
+        let cron = CronService::new(&tmp);
+        assert_eq!(cron.list_jobs(true).len(), 0);
+        assert!(cron.quarantined().is_some_and(|p| p.exists()));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
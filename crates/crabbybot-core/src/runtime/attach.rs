@@ -0,0 +1,47 @@
+//! Interactive chat with a bot running elsewhere.
+
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::bus::{self, MessageBus};
+use crate::gateway::channels::cli::CliTransport;
+use crate::gateway::channels::remote::{socket_url, RemoteTransport};
+
+/// Run an interactive chat on stdin/stdout against the bot serving WebChat
+/// at `url`, until `/quit`, EOF, `cancel`, or the connection drops.
+///
+/// The terminal is the same [`CliTransport`] as [`run_repl`](super::run_repl),
+/// but nothing runs locally: each message goes to the remote bot, whose
+/// progress and replies are shown as they arrive. The conversation is the
+/// bot's session `webchat:<session>`.
+pub fn run_attach(url: &str, token: &str, session: &str, cancel: CancellationToken) -> JoinHandle<anyhow::Result<()>> {
+    let url = socket_url(url, token, session);
+    let session = session.to_string();
+
+    tokio::spawn(async move {
+        let url = url?;
+        // Quitting stops the relay without cancelling the caller's token.
+        let cancel = cancel.child_token();
+        let (bus, receivers) = MessageBus::new(10);
+        let bus = Arc::new(bus);
+
+        let transport = CliTransport::new(Arc::clone(&bus), session.clone(), cancel.clone());
+        let remote = RemoteTransport::new(url, Arc::clone(&bus), session, cancel.clone());
+        tokio::spawn(bus::dispatch_outbound(bus.subscribers(), receivers.outbound_rx));
+        let mut relay = tokio::spawn(remote.run(receivers.inbound_rx));
+
+        let result = tokio::select! {
+            result = transport.run() => result,
+            // The relay only ends on its own when the connection does.
+            relayed = &mut relay => {
+                cancel.cancel();
+                return relayed?;
+            }
+        };
+        cancel.cancel();
+        relay.await??;
+        result
+    })
+}
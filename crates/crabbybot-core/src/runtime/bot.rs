@@ -0,0 +1,670 @@
+//! Bot mode: transports, dispatcher, agent bridge, and background services.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::{JoinError, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+use super::{Runtime, SharedProvider};
+use crate::agent::activity::{Activity, ActivityLog};
+use crate::alerts::AlertManager;
+use crate::approvals::Notice;
+use crate::bus::events::{new_request_id, InboundMessage, OutboundMessage};
+use crate::bus::{DeliveryPolicy, MessageBus, MessageBusReceivers};
+use crate::clock::SharedClock;
+use crate::config::Config;
+use crate::cron::{CronService, JobKind};
+#[cfg(any(feature = "telegram", feature = "discord"))]
+use crate::gateway::{acl::Acl, uploads::UploadStore};
+use crate::gateway::{errors, AgentBridge};
+use crate::heartbeat::Heartbeat;
+use crate::recovery::RecoveryReport;
+use crate::scheduler::LlmScheduler;
+use crate::selftest::{self, SelfTestMode, SelfTestReport};
+#[cfg(feature = "polymarket")]
+use crate::service::betting::BettingService;
+use crate::service::betting::BettingState;
+use crate::session::SessionManager;
+use crate::templates::TemplateRegistry;
+use crate::tools::{ToolContext, ToolRegistry};
+use crate::usage::UsageTracker;
+
+impl Runtime {
+    /// Wrap the agent in an [`AgentBridge`] bound to this runtime's bus.
+    ///
+    /// Returns the bridge together with the receivers it does not consume.
+    pub fn into_bridge(self, cancel: CancellationToken) -> (AgentBridge, RuntimeParts) {
+        let bridge = AgentBridge::new(
+            Arc::clone(&self.bus),
+            self.agent,
+            cancel,
+            Arc::clone(&self.cron),
+            self.workspace.clone(),
+        )
+        .coalesce_window(Duration::from_millis(self.config.gateway.coalesce_window_ms))
+        .turn_timeout(Duration::from_secs(self.config.gateway.turn_timeout_secs))
+        .tag_sessions_every(Duration::from_secs(self.config.gateway.tag_sessions_minutes * 60))
+        .replies(self.config.gateway.replies.clone())
+        .session_scopes(self.config.channels.session_scopes())
+        .alerts(AlertManager::new(&self.workspace, &self.config.alerts).with_clock(Arc::clone(&self.clock)))
+        .listen_only(
+            self.config
+                .agents
+                .chats
+                .iter()
+                .filter(|(_, chat)| chat.listen_only)
+                .map(|(key, _)| key.clone()),
+        );
+        let parts = RuntimeParts {
+            config: self.config,
+            workspace: self.workspace,
+            client: self.client,
+            bus: self.bus,
+            receivers: self.receivers,
+            provider: self.provider,
+            tools: self.tools,
+            cron: self.cron,
+            betting_state: self.betting_state,
+            heartbeats: self.heartbeats,
+            usage: self.usage,
+            scheduler: self.scheduler,
+            default_target: self.default_target,
+            clock: self.clock,
+        };
+        (bridge, parts)
+    }
+}
+
+/// What remains of a [`Runtime`] once the agent has moved into a bridge.
+pub struct RuntimeParts {
+    pub config: Config,
+    pub workspace: PathBuf,
+    pub client: reqwest::Client,
+    pub bus: Arc<MessageBus>,
+    pub receivers: MessageBusReceivers,
+    pub provider: SharedProvider,
+    pub tools: Arc<ToolRegistry>,
+    pub cron: Arc<Mutex<CronService>>,
+    pub betting_state: Arc<Mutex<BettingState>>,
+    pub heartbeats: Vec<Heartbeat>,
+    pub usage: Arc<UsageTracker>,
+    pub scheduler: Option<Arc<LlmScheduler>>,
+    pub default_target: (String, String),
+    pub clock: SharedClock,
+}
+
+/// Handle to the services started by [`run_bot`].
+pub struct BotHandle {
+    /// Names of the chat transports that were started.
+    pub transports: Vec<&'static str>,
+    /// Outcome of the light self-test run before transports started.
+    pub self_test: SelfTestReport,
+    /// One task per service (transports, dispatcher, bridge, cron, ...).
+    pub tasks: JoinSet<()>,
+    cancel: CancellationToken,
+}
+
+impl BotHandle {
+    /// Wait for the next service task to exit. Services only exit on
+    /// cancellation or failure, so this doubles as a health watch.
+    /// Returns `None` when no tasks are left.
+    pub async fn join_next(&mut self) -> Option<Result<(), JoinError>> {
+        self.tasks.join_next().await
+    }
+
+    /// Cancel all services and wait up to `grace` for them to finish.
+    pub async fn shutdown(mut self, grace: Duration) {
+        self.cancel.cancel();
+        if tokio::time::timeout(grace, async { while self.tasks.join_next().await.is_some() {} })
+            .await
+            .is_err()
+        {
+            warn!("Services did not stop within {:?}; aborting", grace);
+            self.tasks.shutdown().await;
+        }
+    }
+}
+
+/// Start the bot: chat transports, outbound dispatcher, agent bridge,
+/// betting engine, heartbeats, and the cron ticker.
+///
+/// Everything stops when `cancel` is triggered. If no transport is enabled
+/// nothing is started and the returned handle has no tasks.
+#[cfg_attr(
+    not(any(
+        feature = "telegram",
+        feature = "discord",
+        feature = "webchat",
+        feature = "websocket",
+        feature = "api"
+    )),
+    allow(unused_mut, unused_variables)
+)]
+pub async fn run_bot(config: Config, cancel: CancellationToken) -> anyhow::Result<BotHandle> {
+    let runtime = Runtime::from_config(config);
+    let (bridge, parts) = runtime.into_bridge(cancel.clone());
+    #[cfg(any(feature = "telegram", feature = "discord"))]
+    let menu = bridge.menu();
+    let (admin_channel, admin_chat_id) = parts.default_target.clone();
+    #[cfg(feature = "webhooks")]
+    let default_chat_id = admin_chat_id.clone();
+    let RuntimeParts {
+        config,
+        workspace,
+        client,
+        bus,
+        receivers,
+        provider,
+        tools,
+        cron,
+        betting_state,
+        heartbeats,
+        usage,
+        scheduler,
+        clock,
+        ..
+    } = parts;
+
+    // 0. Warm provider connections and sanity-check tools and cron before
+    //    accepting traffic. Failures are reported, not fatal.
+    let self_test = selftest::run(&config, &client, &tools, SelfTestMode::Light).await;
+    if self_test.passed() {
+        info!("Self-test passed");
+    } else {
+        for check in self_test.failures() {
+            warn!(check = %check.name, "Self-test failed: {}", check.detail);
+        }
+    }
+
+    let mut tasks = JoinSet::new();
+    let mut transports = Vec::new();
+    // WebChat, the WebSocket transport, webhooks and the REST API share one
+    // listener on gateway.host:port.
+    #[cfg(any(feature = "webchat", feature = "websocket", feature = "webhooks", feature = "api"))]
+    let mut http_routes: Option<axum::Router> = None;
+
+    // WebChat sign-in through the Telegram bot, shared by both transports.
+    #[cfg(feature = "webchat")]
+    let pairing = {
+        let web = config.channels.webchat.as_ref().filter(|w| w.enabled && w.telegram_login);
+        let tel = config
+            .channels
+            .telegram
+            .as_ref()
+            .filter(|t| cfg!(feature = "telegram") && t.enabled && !t.token.is_empty());
+        if web.is_some() && tel.is_none() {
+            warn!("channels.webchat.telegramLogin needs the Telegram channel; sign-in with Telegram is off");
+        }
+        web.and(tel).map(|_| Arc::new(crate::gateway::pairing::Pairing::new()))
+    };
+
+    // 1. Start transports FIRST so they register their outbound subscribers
+    //    before the dispatch loop begins processing messages.
+    #[cfg(feature = "telegram")]
+    if let Some(ref tel) = config.channels.telegram {
+        if tel.enabled && !tel.token.is_empty() {
+            let transport = crate::gateway::channels::telegram::TelegramTransport::new(
+                tel.token.clone(),
+                Arc::clone(&bus),
+                Acl::from_config("telegram", &tel.allow_from),
+                cancel.clone(),
+            )
+            .with_uploads(UploadStore::new(&workspace, config.gateway.max_upload_mb))
+            .with_commands(menu.clone());
+            #[cfg(feature = "webchat")]
+            let transport = match pairing {
+                Some(ref pairing) => transport.with_pairing(Arc::clone(pairing)),
+                None => transport,
+            };
+            tasks.spawn(async move {
+                if let Err(e) = transport.run().await {
+                    error!("Telegram transport failed: {}", e);
+                }
+            });
+            transports.push("telegram");
+        }
+    }
+
+    #[cfg(feature = "discord")]
+    if let Some(ref disc) = config.channels.discord {
+        if disc.enabled && !disc.token.is_empty() {
+            let transport = crate::gateway::channels::discord::DiscordTransport::new(
+                disc.token.clone(),
+                Arc::clone(&bus),
+                Acl::from_config("discord", &disc.allow_from),
+            )
+            .with_uploads(UploadStore::new(&workspace, config.gateway.max_upload_mb))
+            .with_commands(menu.clone());
+            tasks.spawn(async move {
+                if let Err(e) = transport.run().await {
+                    error!("Discord transport failed: {}", e);
+                }
+            });
+            transports.push("discord");
+        }
+    }
+
+    #[cfg(feature = "webchat")]
+    if let Some(ref web) = config.channels.webchat {
+        if web.enabled && (!web.token.is_empty() || pairing.is_some()) {
+            let transport = crate::gateway::channels::webchat::WebChatTransport::new(
+                config.gateway.host.clone(),
+                config.gateway.port,
+                web.token.clone(),
+                Arc::clone(&bus),
+                cancel.clone(),
+            );
+            let transport = match pairing {
+                Some(pairing) => transport.with_pairing(pairing),
+                None => transport,
+            };
+            http_routes = Some(transport.router().await);
+            transports.push("webchat");
+        } else if web.enabled {
+            warn!("WebChat is enabled but has neither channels.webchat.token nor Telegram sign-in; not starting it");
+        }
+    }
+
+    #[cfg(feature = "websocket")]
+    if let Some(ref socket) = config.channels.websocket {
+        let clients: BTreeMap<String, String> = socket
+            .clients
+            .iter()
+            .filter(|(_, token)| !token.is_empty())
+            .map(|(name, token)| (name.clone(), token.clone()))
+            .collect();
+        if socket.enabled && !clients.is_empty() {
+            let transport = crate::gateway::channels::websocket::WebSocketTransport::new(clients, Arc::clone(&bus));
+            let routes = transport.router().await;
+            http_routes = Some(match http_routes.take() {
+                Some(web) => web.merge(routes),
+                None => routes,
+            });
+            transports.push("websocket");
+        } else if socket.enabled {
+            warn!("WebSocket is enabled but channels.websocket.clients has no tokens; not starting it");
+        }
+    }
+
+    #[cfg(feature = "api")]
+    if config.gateway.api.enabled {
+        let token = crate::vault::decrypt(&config.gateway.api.token).unwrap_or_else(|e| {
+            warn!("Failed to decrypt gateway.api.token: {}", e);
+            String::new()
+        });
+        if !token.is_empty() {
+            let api = crate::gateway::http::RestApi::new(
+                token,
+                Arc::clone(&bus),
+                workspace.clone(),
+                Duration::from_secs(config.gateway.api.reply_timeout_secs.max(1)),
+            )
+            .with_chat_workspaces(config.chat_workspaces());
+            let routes = api.router().await;
+            http_routes = Some(match http_routes.take() {
+                Some(web) => web.merge(routes),
+                None => routes,
+            });
+            transports.push("api");
+        } else {
+            warn!("The REST API is enabled but gateway.api.token is empty; not starting it");
+        }
+    }
+
+    if transports.is_empty() {
+        warn!("No bot channels enabled");
+        return Ok(BotHandle {
+            transports,
+            self_test,
+            tasks,
+            cancel,
+        });
+    }
+
+    #[cfg(feature = "webhooks")]
+    {
+        let hooks = config
+            .gateway
+            .webhooks
+            .iter()
+            .filter(|h| h.enabled)
+            .filter(|h| {
+                if h.secret.is_empty() {
+                    warn!(hook = %h.name, "Webhook has no secret; not serving it");
+                }
+                !h.secret.is_empty()
+            })
+            .map(|h| crate::gateway::webhooks::Webhook::from_config(h, &default_chat_id));
+        let webhooks = crate::gateway::webhooks::Webhooks::new(hooks, Arc::clone(&bus), TemplateRegistry::new(&workspace))
+            .usage_budget(Arc::clone(&usage));
+        if !webhooks.is_empty() {
+            let routes = webhooks.router();
+            http_routes = Some(match http_routes.take() {
+                Some(web) => web.merge(routes),
+                None => routes,
+            });
+        }
+    }
+
+    #[cfg(any(feature = "webchat", feature = "websocket", feature = "webhooks", feature = "api"))]
+    if let Some(app) = http_routes {
+        let (host, port, cancel) = (config.gateway.host.clone(), config.gateway.port, cancel.clone());
+        tasks.spawn(async move {
+            if let Err(e) = crate::gateway::http::serve(&host, port, app, cancel).await {
+                error!("Gateway HTTP server failed: {}", e);
+            }
+        });
+    }
+
+    // 2. Outbound dispatcher — uses the shared subscriber map, no bus lock needed
+    let subs = bus.subscribers();
+    let delivery = &config.gateway.delivery;
+    if delivery.track {
+        let policy = DeliveryPolicy {
+            max_attempts: delivery.max_attempts.max(1),
+            backoff: Duration::from_millis(delivery.retry_backoff_ms),
+        };
+        let log_path = workspace.join("traces").join("delivery.jsonl");
+        let recorder = crate::bus::record_deliveries(bus.subscribe_delivery(), log_path);
+        let cancel_log = cancel.clone();
+        tasks.spawn(async move {
+            tokio::select! {
+                _ = cancel_log.cancelled() => {}
+                _ = recorder => {}
+            }
+        });
+        tasks.spawn(crate::bus::dispatch_outbound_tracked(
+            subs,
+            receivers.outbound_rx,
+            Some(bus.delivery_sender()),
+            policy,
+        ));
+    } else {
+        tasks.spawn(crate::bus::dispatch_outbound(subs, receivers.outbound_rx));
+    }
+
+    // 3. Agent bridge
+    let inbound_rx = receivers.inbound_rx;
+    tasks.spawn(async move {
+        if let Err(e) = bridge.run(inbound_rx).await {
+            error!("Agent bridge failed: {}", e);
+        }
+    });
+
+    // 4. Betting engine
+    #[cfg(feature = "polymarket")]
+    spawn_betting_engine(&mut tasks, betting_state, Arc::clone(&tools), cancel.clone());
+    #[cfg(not(feature = "polymarket"))]
+    let _ = betting_state;
+
+    // 5. Heartbeats from config
+    for hb in heartbeats {
+        tasks.spawn(hb.run(bus.inbound_sender(), cancel.clone()));
+    }
+
+    // 6. Restart report, before the first tick catches up on missed runs
+    if config.gateway.restart_report && !admin_chat_id.is_empty() {
+        let sessions = SessionManager::new(&workspace)
+            .with_chat_workspaces(config.chat_workspaces())
+            .list_sessions()
+            .len();
+        let report = RecoveryReport::collect(&workspace, &*cron.lock().await, sessions);
+        info!(?report, "Recovered state");
+        bus.publish_outbound(OutboundMessage::reply(&admin_channel, &admin_chat_id, report.render()))
+            .await;
+    }
+
+    // 7. Cron ticker
+    let output = JobOutput {
+        workspace: workspace.clone(),
+        chat_workspaces: config.chat_workspaces(),
+        activity: ActivityLog::new(&workspace).with_chat_workspaces(config.chat_workspaces()),
+        templates: TemplateRegistry::new(&workspace),
+        alerts: Arc::new(AlertManager::new(&workspace, &config.alerts).with_clock(Arc::clone(&clock))),
+    };
+    tasks.spawn(cron_ticker(
+        cron,
+        tools,
+        Arc::clone(&bus),
+        output,
+        Arc::clone(&usage),
+        Arc::clone(&clock),
+        cancel.clone(),
+    ));
+
+    // 8. Nightly memory consolidation
+    if config.consolidation.enabled {
+        let mut consolidator = crate::consolidate::Consolidator::from_config(&config, provider)
+            .with_usage(usage)
+            .with_clock(clock);
+        if let Some(scheduler) = scheduler {
+            consolidator = consolidator.with_scheduler(scheduler);
+        }
+        tasks.spawn(crate::consolidate::run_periodic(consolidator, cancel.clone()));
+    }
+
+    // 9. Workspace sync
+    #[cfg(feature = "sync")]
+    if config.sync.enabled {
+        match crate::sync::SyncEngine::from_config(&config.sync, &workspace, client.clone()) {
+            Ok(engine) => {
+                let interval = Duration::from_secs(config.sync.interval_minutes.max(1) * 60);
+                let admin = (admin_channel.clone(), admin_chat_id.clone());
+                tasks.spawn(crate::sync::run_periodic(engine, interval, Arc::clone(&bus), admin, cancel.clone()));
+            }
+            Err(e) => warn!("Workspace sync disabled: {}", e),
+        }
+    }
+
+    // 10. Opt-in telemetry
+    let telemetry = crate::telemetry::Telemetry::new(&workspace, config.telemetry.clone());
+    if telemetry.is_enabled() {
+        tasks.spawn(crate::telemetry::run_periodic(telemetry, client.clone(), cancel.clone()));
+    }
+
+    info!(transports = ?transports, "Bot services started");
+    Ok(BotHandle {
+        transports,
+        self_test,
+        tasks,
+        cancel,
+    })
+}
+
+/// The betting loop has no cancel hook, so abort it on shutdown.
+#[cfg(feature = "polymarket")]
+fn spawn_betting_engine(
+    tasks: &mut JoinSet<()>,
+    state: Arc<Mutex<BettingState>>,
+    tools: Arc<ToolRegistry>,
+    cancel: CancellationToken,
+) {
+    let betting = BettingService::spawn(state, tools);
+    let abort = betting.abort_handle();
+    tasks.spawn(async move {
+        tokio::select! {
+            _ = cancel.cancelled() => abort.abort(),
+            _ = betting => {}
+        }
+    });
+}
+
+/// Post what the approvals sweep turned up: a note to the requesting chat
+/// for each expired request, and a repost of each one due for escalation.
+/// `approval_expired` and `approval_escalated` workspace templates, given the
+/// request as `approval` plus its `summary` and `request` text, replace the
+/// built-in wording.
+async fn sweep_approvals(
+    tools: &ToolRegistry,
+    bus: &MessageBus,
+    templates: &TemplateRegistry,
+    now: chrono::DateTime<chrono::Utc>,
+) {
+    let Some(approvals) = tools.approvals() else {
+        return;
+    };
+    let notices = match approvals.sweep(now) {
+        Ok(notices) => notices,
+        Err(e) => {
+            warn!("Approvals sweep failed: {}", e);
+            return;
+        }
+    };
+    let context = |a: &crate::approvals::Approval| {
+        serde_json::json!({"approval": a, "summary": a.summary(), "request": a.request_text()})
+    };
+    for notice in notices {
+        let msg = match notice {
+            Notice::Expired(a) => {
+                info!(approval = %a.id, tool = %a.tool, "Approval request expired");
+                let text = templates.render_or("approval_expired", &a.channel, &context(&a), || {
+                    format!("⌛ Approval #{} expired; {} was cancelled.", a.id, a.summary())
+                });
+                OutboundMessage::reply(&a.channel, &a.chat_id, text)
+            }
+            Notice::Escalate(a) => {
+                let Some((channel, chat_id)) = approvals.escalation_target() else {
+                    continue;
+                };
+                info!(approval = %a.id, channel, chat_id, "Escalating approval request");
+                let text = templates.render_or("approval_escalated", channel, &context(&a), || {
+                    format!("⏫ Still unanswered in {}:{}\n\n{}", a.channel, a.chat_id, a.request_text())
+                });
+                OutboundMessage::reply_with_buttons(channel, chat_id, text, a.buttons())
+            }
+        };
+        bus.publish_outbound(msg).await;
+    }
+}
+
+/// Where `tool_call` job results are logged, rendered and deduplicated.
+#[derive(Clone)]
+struct JobOutput {
+    /// Workspace the tool calls run in, unless their chat has its own.
+    workspace: PathBuf,
+    chat_workspaces: BTreeMap<String, PathBuf>,
+    activity: ActivityLog,
+    templates: TemplateRegistry,
+    alerts: Arc<AlertManager>,
+}
+
+/// Check for due jobs every 30 seconds. Agent jobs are pushed to the bus as
+/// system messages; `tool_call` jobs run their tool directly and post the
+/// formatted result, recording the run in the chat's activity log. Repeats
+/// of the same result within the alert cooldown, and results for muted
+/// chats, are held back. Non-critical agent jobs are skipped while the daily
+/// token budget is nearly used up. Announcements are rendered and posted
+/// as they are, except to muted chats. Approval requests that ran out of
+/// time are expired or escalated.
+async fn cron_ticker(
+    cron: Arc<Mutex<CronService>>,
+    tools: Arc<ToolRegistry>,
+    bus: Arc<MessageBus>,
+    output: JobOutput,
+    usage: Arc<UsageTracker>,
+    clock: SharedClock,
+    cancel: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(30));
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = interval.tick() => {
+                let (due_jobs, announcements) = {
+                    let mut cron = cron.lock().await;
+                    (cron.get_due_jobs(), cron.due_announcements())
+                };
+                for announcement in announcements {
+                    let chat_key = format!("{}:{}", announcement.channel, announcement.chat_id);
+                    if output.alerts.muted_until(&chat_key).is_some() {
+                        debug!(announcement = %announcement.name, "Chat is muted; skipping announcement");
+                        continue;
+                    }
+                    info!(announcement = %announcement.name, "Announcement fired");
+                    let text = announcement.render(&output.templates, clock.now());
+                    bus.publish_outbound(OutboundMessage::reply(&announcement.channel, &announcement.chat_id, text))
+                        .await;
+                }
+                sweep_approvals(&tools, &bus, &output.templates, clock.now()).await;
+                for job in due_jobs {
+                    info!(job_id = %job.id, job_name = %job.name, "Cron job fired");
+                    if let JobKind::ToolCall(ref call) = job.kind {
+                        let call = call.clone();
+                        let tools = Arc::clone(&tools);
+                        let bus = Arc::clone(&bus);
+                        let JobOutput {
+                            workspace,
+                            chat_workspaces,
+                            activity,
+                            templates,
+                            alerts,
+                        } = output.clone();
+                        let clock = Arc::clone(&clock);
+                        let span = info_span!("cron_job", request_id = %new_request_id(), job_id = %job.id);
+                        let run = async move {
+                            let chat_key = format!("{}:{}", job.channel, job.chat_id);
+                            let ctx = ToolContext::new(&job.channel, &job.chat_id)
+                                .with_user(&call.owner)
+                                .with_workspace(chat_workspaces.get(&chat_key).unwrap_or(&workspace))
+                                .with_bus(Some(Arc::clone(&bus)))
+                                .with_activity(activity.clone())
+                                .with_tools(Arc::clone(&tools));
+                            let key = ctx.session_key.clone();
+                            let content = format!("Scheduled job \"{}\"", job.name);
+                            activity.record(&key, Activity::TurnStarted { user_id: "cron", content: &content });
+                            let args: serde_json::Map<_, _> = call.args.clone().into_iter().collect();
+                            activity.record(&key, Activity::ToolCall { name: &call.name, args: &args });
+
+                            let started = std::time::Instant::now();
+                            let result = match tools.try_execute(&call.name, call.args, &ctx).await {
+                                Ok(result) => result,
+                                Err(e) => {
+                                    error!(tool = %call.name, "Scheduled tool call failed: {}", e);
+                                    errors::for_tool(&e).render()
+                                }
+                            };
+                            activity.record(
+                                &key,
+                                Activity::ToolResult { name: &call.name, result: &result, elapsed: started.elapsed() },
+                            );
+                            let cooldown = call.cooldown_secs.map(|s| chrono::Duration::seconds(s as i64));
+                            let Some(admitted) = alerts.admit(&chat_key, &job.id, &result, cooldown) else {
+                                debug!(job_id = %job.id, "Holding back repeated or muted job output");
+                                return;
+                            };
+                            let text = admitted.decorate(job.render_tool_output(&result, &templates, clock.now()));
+                            activity.record(&key, Activity::Reply(&text));
+                            bus.publish_outbound(OutboundMessage::reply(&job.channel, &job.chat_id, text))
+                                .await;
+                        };
+                        tokio::spawn(run.instrument(span));
+                        continue;
+                    }
+                    if !job.critical
+                        && !usage.admit_background(&format!("cron job \"{}\"", job.name)).await
+                    {
+                        continue;
+                    }
+                    let msg = InboundMessage {
+                        channel: job.channel.clone(),
+                        chat_id: job.chat_id.clone(),
+                        user_id: "cron".to_string(),
+                        sender_name: None,
+                        content: job.message.clone(),
+                        message_id: None,
+                        media: Vec::new(),
+                        is_system: true,
+                    };
+                    if let Err(e) = bus.inbound_sender().send(msg).await {
+                        error!("Failed to send cron job to bus: {}", e);
+                    }
+                }
+            }
+        }
+    }
+    info!("Cron ticker stopped");
+}
@@ -0,0 +1,358 @@
+//! Runtime bootstrap: wires providers, tools, bus, cron, and heartbeats from
+//! a [`Config`].
+//!
+//! Binaries used to assemble these pieces by hand. [`AgentBuilder`] does it
+//! once, so a downstream `main` only decides what to run:
+//!
+//! ```no_run
+//! use crabbybot_core::config::Config;
+//! use crabbybot_core::runtime::Runtime;
+//!
+//! let runtime = Runtime::from_config(Config::load().unwrap());
+//! println!("{} tools registered", runtime.tools.len());
+//! ```
+//!
+//! To embed the whole assistant, use [`run_bot`] (transports + agent +
+//! background services) or [`run_repl`] (stdin chat); [`run_attach`] is the
+//! stdin chat of a bot running elsewhere. All take an external
+//! [`CancellationToken`] and hand back join handles:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use crabbybot_core::config::Config;
+//! use tokio_util::sync::CancellationToken;
+//!
+//! let cancel = CancellationToken::new();
+//! let mut bot = crabbybot_core::run_bot(Config::load()?, cancel.clone()).await?;
+//! // ... run your own services, then:
+//! cancel.cancel();
+//! bot.shutdown(std::time::Duration::from_secs(2)).await;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "attach")]
+mod attach;
+#[cfg(feature = "gateway")]
+mod bot;
+#[cfg(feature = "gateway")]
+mod repl;
+
+#[cfg(feature = "attach")]
+pub use attach::run_attach;
+#[cfg(feature = "gateway")]
+pub use bot::{run_bot, BotHandle, RuntimeParts};
+#[cfg(feature = "gateway")]
+pub use repl::run_repl;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::agent::locale::LocaleSettings;
+use crate::agent::persona::PersonaSettings;
+use crate::agent::{AgentConfig, AgentLoop};
+use crate::clock::{self, SharedClock};
+use crate::bus::{MessageBus, MessageBusReceivers};
+use crate::config::Config;
+use crate::cron::{Announcement, CronService};
+use crate::heartbeat::Heartbeat;
+use crate::provider::{self, LlmProvider};
+use crate::scheduler::LlmScheduler;
+use crate::service::betting::BettingState;
+use crate::tools::{ToolRegistry, ToolSetBuilder};
+use crate::usage::UsageTracker;
+
+/// Provider handle shared between the agent loop and provider-backed tools.
+pub type SharedProvider = Arc<Mutex<Box<dyn LlmProvider>>>;
+
+/// Everything a binary needs to run the assistant, built from config.
+pub struct Runtime {
+    pub config: Config,
+    pub workspace: PathBuf,
+    /// HTTP client shared by the provider and tools.
+    pub client: reqwest::Client,
+    pub bus: Arc<MessageBus>,
+    pub receivers: MessageBusReceivers,
+    pub provider: SharedProvider,
+    pub tools: Arc<ToolRegistry>,
+    pub cron: Arc<Mutex<CronService>>,
+    pub betting_state: Arc<Mutex<BettingState>>,
+    pub heartbeats: Vec<Heartbeat>,
+    /// Daily token totals, shared by the agent and background services.
+    pub usage: Arc<UsageTracker>,
+    /// Paces LLM calls when `usage.callsPerMinute` is set; shared by the
+    /// agent and background services.
+    pub scheduler: Option<Arc<LlmScheduler>>,
+    /// Where scheduled jobs and heartbeats deliver unless told otherwise
+    /// (see [`AgentBuilder::default_target`]).
+    pub default_target: (String, String),
+    pub agent: AgentLoop,
+    /// Time source for cron, heartbeats and alerts.
+    pub clock: SharedClock,
+}
+
+impl Runtime {
+    /// Build a runtime with bot-mode defaults.
+    pub fn from_config(config: Config) -> Self {
+        AgentBuilder::new(config).build()
+    }
+}
+
+// ── Builder ─────────────────────────────────────────────────────────
+
+/// Builder for [`Runtime`].
+pub struct AgentBuilder {
+    config: Config,
+    model: Option<String>,
+    default_channel: Option<String>,
+    default_chat_id: Option<String>,
+    bus_capacity: usize,
+    schedule_tools: bool,
+    betting_tools: bool,
+    clock: Option<SharedClock>,
+}
+
+impl AgentBuilder {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            model: None,
+            default_channel: None,
+            default_chat_id: None,
+            bus_capacity: 100,
+            schedule_tools: true,
+            betting_tools: true,
+            clock: None,
+        }
+    }
+
+    /// Override the configured default model.
+    pub fn model(mut self, model: Option<impl Into<String>>) -> Self {
+        self.model = model.map(Into::into);
+        self
+    }
+
+    /// Where scheduled jobs and heartbeats deliver by default.
+    ///
+    /// Defaults to Telegram and the first allowed Telegram user (in private
+    /// chats the chat id equals the user id).
+    pub fn default_target(mut self, channel: impl Into<String>, chat_id: impl Into<String>) -> Self {
+        self.default_channel = Some(channel.into());
+        self.default_chat_id = Some(chat_id.into());
+        self
+    }
+
+    /// Capacity of the inbound and outbound bus queues.
+    pub fn bus_capacity(mut self, capacity: usize) -> Self {
+        self.bus_capacity = capacity;
+        self
+    }
+
+    /// Register the schedule_task / list_schedules / cancel_schedule tools.
+    pub fn schedule_tools(mut self, enabled: bool) -> Self {
+        self.schedule_tools = enabled;
+        self
+    }
+
+    /// Register the betting_control tool.
+    pub fn betting_tools(mut self, enabled: bool) -> Self {
+        self.betting_tools = enabled;
+        self
+    }
+
+    /// Run cron, heartbeats and alerts on `clock` instead of the system
+    /// clock, e.g. a [`MockClock`](crate::clock::MockClock) in tests.
+    pub fn clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    pub fn build(self) -> Runtime {
+        let config = self.config;
+        let clock = self.clock.unwrap_or_else(clock::system);
+        let workspace = config.workspace_path();
+        let client = reqwest::Client::new();
+
+        let default_channel = self.default_channel.unwrap_or_else(|| "telegram".into());
+        let default_chat_id = self.default_chat_id.unwrap_or_else(|| {
+            config
+                .channels
+                .telegram
+                .as_ref()
+                .and_then(|t| {
+                    // The first plain user id; group, role and wildcard rules
+                    // name no single chat.
+                    t.allow_from
+                        .iter()
+                        .map(|rule| rule.strip_prefix("user:").unwrap_or(rule))
+                        .find(|id| !id.is_empty() && id.trim_start_matches('-').bytes().all(|b| b.is_ascii_digit()))
+                })
+                .map(str::to_string)
+                .unwrap_or_default()
+        });
+
+        let provider: SharedProvider = Arc::new(Mutex::new(provider::from_config(
+            &config,
+            self.model.as_deref(),
+            client.clone(),
+        )));
+
+        let announcements = config
+            .announcements
+            .iter()
+            .filter(|a| a.enabled)
+            .filter_map(|a| {
+                let timezone = config.agents.defaults.timezone.as_deref();
+                Announcement::from_config(a, &default_chat_id, timezone)
+                    .inspect_err(|e| tracing::warn!(announcement = %a.name, "Skipping announcement: {}", e))
+                    .ok()
+            })
+            .collect();
+        let cron = Arc::new(Mutex::new(
+            CronService::new(&workspace)
+                .with_announcements(announcements)
+                .with_clock(Arc::clone(&clock)),
+        ));
+        let betting_state = Arc::new(Mutex::new(BettingState::new(config.tools.betting.clone())));
+
+        let mut tools = ToolSetBuilder::new(&config)
+            .client(client.clone())
+            .provider(Arc::clone(&provider));
+        if self.schedule_tools {
+            tools = tools.cron(Arc::clone(&cron), default_channel.clone(), default_chat_id.clone());
+        }
+        if self.betting_tools {
+            tools = tools.betting_state(Arc::clone(&betting_state));
+        }
+        let tools = Arc::new(tools.build());
+
+        let (bus, receivers) = MessageBus::new(self.bus_capacity);
+        let bus = Arc::new(bus);
+        let usage = Arc::new(
+            UsageTracker::new(&workspace, config.usage.clone()).notify(
+                Arc::clone(&bus),
+                default_channel.clone(),
+                default_chat_id.clone(),
+            ),
+        );
+
+        let heartbeats = config
+            .heartbeats
+            .iter()
+            .filter(|h| h.enabled && !h.message.is_empty() && h.interval_minutes > 0)
+            .map(|h| {
+                let chat_id = if h.chat_id.is_empty() { &default_chat_id } else { &h.chat_id };
+                let builder = Heartbeat::builder()
+                    .interval(Duration::from_secs(h.interval_minutes * 60))
+                    .message(h.message.clone())
+                    .channel(h.channel.clone())
+                    .chat_id(chat_id.clone())
+                    .clock(Arc::clone(&clock));
+                if h.critical {
+                    builder.build()
+                } else {
+                    builder.usage_budget(Arc::clone(&usage)).build()
+                }
+            })
+            .collect();
+
+        let agent_config = AgentConfig {
+            model: self.model,
+            summary_model: config.agents.defaults.summary_model.clone(),
+            models: config.agents.defaults.models.clone(),
+            max_tokens: config.agents.defaults.max_tokens,
+            temperature: config.agents.defaults.temperature,
+            max_iterations: config.agents.defaults.max_tool_iterations,
+            max_continuations: config.agents.defaults.max_continuations,
+            stream: config.agents.defaults.stream,
+            workspace: workspace.clone(),
+            chat_workspaces: config.chat_workspaces(),
+            max_context_tokens: 4_000,
+            locale: LocaleSettings::from_config(&config.agents),
+            persona: PersonaSettings::from_config(&config.agents),
+            webhook_tools: config
+                .gateway
+                .webhooks
+                .iter()
+                .map(|hook| (hook.name.clone(), hook.tools.clone()))
+                .collect(),
+        };
+        let mut agent = AgentLoop::new(Arc::clone(&provider), Arc::clone(&tools), agent_config)
+            .with_usage(Arc::clone(&usage));
+        let scheduler = LlmScheduler::from_config(&config.usage).map(Arc::new);
+        if let Some(ref scheduler) = scheduler {
+            agent = agent.with_scheduler(Arc::clone(scheduler));
+        }
+
+        Runtime {
+            config,
+            workspace,
+            client,
+            bus,
+            receivers,
+            provider,
+            tools,
+            cron,
+            betting_state,
+            heartbeats,
+            usage,
+            scheduler,
+            default_target: (default_channel, default_chat_id),
+            agent,
+            clock,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HeartbeatConfig;
+
+    fn test_config(name: &str) -> Config {
+        let dir = std::env::temp_dir().join(format!("crabbybot_runtime_{}", name));
+        let _ = std::fs::create_dir_all(&dir);
+        let mut config = Config::default();
+        config.agents.defaults.workspace = dir.to_string_lossy().into_owned();
+        config
+    }
+
+    #[tokio::test]
+    async fn test_runtime_from_config() {
+        let mut config = test_config("from_config");
+        config.heartbeats.push(HeartbeatConfig {
+            message: "daily summary".into(),
+            ..Default::default()
+        });
+        config.heartbeats.push(HeartbeatConfig::default()); // no message — skipped
+
+        let runtime = Runtime::from_config(config);
+        assert!(runtime.tools.has("read_file"));
+        assert!(runtime.tools.has("schedule_task"));
+        assert_eq!(runtime.tools.has("betting_control"), cfg!(feature = "polymarket"));
+        assert_eq!(runtime.heartbeats.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_builder_can_skip_schedule_and_betting_tools() {
+        let runtime = AgentBuilder::new(test_config("chat_mode"))
+            .default_target("cli", "direct")
+            .schedule_tools(false)
+            .betting_tools(false)
+            .build();
+        assert!(!runtime.tools.has("schedule_task"));
+        assert!(!runtime.tools.has("betting_control"));
+    }
+
+    #[cfg(feature = "gateway")]
+    #[tokio::test]
+    async fn test_run_bot_without_transports_starts_nothing() {
+        let cancel = tokio_util::sync::CancellationToken::new();
+        let mut bot = run_bot(test_config("no_transports"), cancel).await.unwrap();
+        assert!(bot.transports.is_empty());
+        assert!(bot.join_next().await.is_none());
+        bot.shutdown(Duration::from_millis(10)).await;
+    }
+}
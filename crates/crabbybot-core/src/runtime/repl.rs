@@ -0,0 +1,61 @@
+//! Interactive stdin/stdout chat.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::{AgentBuilder, RuntimeParts};
+use crate::bus;
+use crate::config::Config;
+use crate::gateway::channels::cli::CliTransport;
+
+/// Run an interactive chat on stdin/stdout until `/quit`, EOF, or `cancel`.
+///
+/// The chat is the `cli` channel of a bus and [`AgentBridge`], the same
+/// pipeline bot mode uses, so it understands the same commands and shows
+/// the agent's progress as it works. The conversation is saved as session
+/// `cli:<session_key>`.
+///
+/// [`AgentBridge`]: crate::gateway::AgentBridge
+pub fn run_repl(
+    config: Config,
+    session_key: &str,
+    model_override: Option<&str>,
+    cancel: CancellationToken,
+) -> JoinHandle<anyhow::Result<()>> {
+    // `sessions list` shows the full key; accept it back.
+    let chat_id = session_key.strip_prefix("cli:").unwrap_or(session_key).to_string();
+    let mut runtime = AgentBuilder::new(config)
+        .model(model_override)
+        .default_target("cli", chat_id.clone())
+        .bus_capacity(10)
+        .schedule_tools(false)
+        .betting_tools(false)
+        .build();
+    // Chats used to be saved under the bare name; carry that history over
+    // the first time. Fails harmlessly when there is none or it was done.
+    let _ = runtime.agent.fork_session(&chat_id, &format!("cli:{}", chat_id));
+
+    tokio::spawn(async move {
+        // Quitting stops the bridge without cancelling the caller's token.
+        let cancel = cancel.child_token();
+        let (bridge, parts) = runtime.into_bridge(cancel.clone());
+        let RuntimeParts { bus, receivers, .. } = parts;
+
+        let transport = CliTransport::new(Arc::clone(&bus), chat_id, cancel.clone());
+        let bridge = tokio::spawn(
+            bridge
+                .coalesce_window(Duration::ZERO)
+                .restarts(false)
+                .run(receivers.inbound_rx),
+        );
+        tokio::spawn(bus::dispatch_outbound(bus.subscribers(), receivers.outbound_rx));
+
+        let result = transport.run().await;
+        cancel.cancel();
+        let _ = bridge.await;
+        result
+    })
+}
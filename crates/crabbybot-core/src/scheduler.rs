@@ -0,0 +1,332 @@
+//! Sharing the LLM provider between chat, scheduled jobs and heartbeats.
+//!
+//! When `usage.callsPerMinute` is set, every LLM call the agent makes first
+//! takes a token from one bucket that refills at that rate and holds up to
+//! `usage.burst` tokens. Callers are sorted into [`Lane`]s:
+//!
+//! | Lane          | Who                          | May take a token while          |
+//! |---------------|------------------------------|---------------------------------|
+//! | `interactive` | users                        | one is left                     |
+//! | `cron`        | scheduled jobs and webhooks  | more than a quarter of the bucket is left |
+//! | `heartbeat`   | heartbeats                   | more than half of the bucket is left |
+//!
+//! so background work never drains what a user's next message needs. Calls
+//! waiting at the same time are served by lane, then first come first
+//! served. A call that has waited `usage.starvationSecs` goes ahead of
+//! everything else and ignores the reserve, so a busy chat can slow
+//! heartbeats down but never stop them.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+use tracing::debug;
+
+use crate::config::UsageConfig;
+
+/// Who an LLM call is made for, highest priority first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lane {
+    #[default]
+    Interactive,
+    Cron,
+    Heartbeat,
+}
+
+impl Lane {
+    /// The lane of a turn started by `user_id`: the senders the cron
+    /// ticker, webhooks and heartbeats use, anyone else is a user.
+    pub fn of(user_id: &str) -> Self {
+        match user_id {
+            "heartbeat" => Lane::Heartbeat,
+            "cron" => Lane::Cron,
+            _ if user_id.starts_with("webhook:") => Lane::Cron,
+            _ => Lane::Interactive,
+        }
+    }
+
+    /// Tokens this lane must leave in a bucket of `capacity`.
+    fn reserve(self, capacity: f64) -> f64 {
+        match self {
+            Lane::Interactive => 0.0,
+            Lane::Cron => capacity / 4.0,
+            Lane::Heartbeat => capacity / 2.0,
+        }
+    }
+}
+
+impl fmt::Display for Lane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Lane::Interactive => "interactive",
+            Lane::Cron => "cron",
+            Lane::Heartbeat => "heartbeat",
+        })
+    }
+}
+
+struct Waiter {
+    id: u64,
+    lane: Lane,
+    since: Instant,
+}
+
+/// The token bucket and who is waiting on it.
+struct Bucket {
+    capacity: f64,
+    /// Tokens per second.
+    rate: f64,
+    starvation: Duration,
+    tokens: f64,
+    refilled: Instant,
+    waiters: Vec<Waiter>,
+    next_id: u64,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled = now;
+    }
+
+    /// Tokens `waiter` needs in the bucket before it may take one.
+    fn needs(&self, waiter: &Waiter, now: Instant) -> f64 {
+        if now.saturating_duration_since(waiter.since) >= self.starvation {
+            1.0
+        } else {
+            1.0 + waiter.lane.reserve(self.capacity)
+        }
+    }
+
+    /// The waiter served next: the longest starving one, else the first of
+    /// the highest lane.
+    fn next(&self, now: Instant) -> Option<&Waiter> {
+        let starving = self
+            .waiters
+            .iter()
+            .filter(|w| now.saturating_duration_since(w.since) >= self.starvation)
+            .min_by_key(|w| w.since);
+        starving.or_else(|| self.waiters.iter().min_by_key(|w| (w.lane, w.since, w.id)))
+    }
+
+    /// Hand a token to `id` if it is next in line and enough are left.
+    fn try_take(&mut self, id: u64, now: Instant) -> bool {
+        self.refill(now);
+        let Some(next) = self.next(now).filter(|w| w.id == id) else {
+            return false;
+        };
+        if self.tokens < self.needs(next, now) {
+            return false;
+        }
+        self.tokens -= 1.0;
+        self.waiters.retain(|w| w.id != id);
+        true
+    }
+
+    /// How long until `id` may be served, assuming no one else is.
+    fn wait_for(&self, id: u64, now: Instant) -> Duration {
+        let Some(waiter) = self.waiters.iter().find(|w| w.id == id) else {
+            return Duration::ZERO;
+        };
+        let missing = (self.needs(waiter, now) - self.tokens).max(0.0);
+        let refill = Duration::from_secs_f64(missing / self.rate);
+        let starves = self.starvation.saturating_sub(now.saturating_duration_since(waiter.since));
+        if starves.is_zero() {
+            refill
+        } else {
+            refill.min(starves)
+        }
+    }
+}
+
+/// Hands out LLM calls to the lanes, see the [module docs](self).
+pub struct LlmScheduler {
+    bucket: Mutex<Bucket>,
+    changed: Notify,
+}
+
+impl LlmScheduler {
+    /// A bucket of `burst` tokens refilled with `calls_per_minute`, starting
+    /// full.
+    pub fn new(calls_per_minute: u32, burst: u32, starvation: Duration) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            bucket: Mutex::new(Bucket {
+                capacity,
+                rate: f64::from(calls_per_minute.max(1)) / 60.0,
+                starvation,
+                tokens: capacity,
+                refilled: Instant::now(),
+                waiters: Vec::new(),
+                next_id: 0,
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    /// The scheduler `usage` asks for; `None` when calls aren't paced.
+    pub fn from_config(usage: &UsageConfig) -> Option<Self> {
+        (usage.calls_per_minute > 0).then(|| {
+            Self::new(
+                usage.calls_per_minute,
+                usage.burst,
+                Duration::from_secs(usage.starvation_secs),
+            )
+        })
+    }
+
+    /// Wait for a token in `lane`. Dropping the future gives up the place
+    /// in line.
+    pub async fn acquire(&self, lane: Lane) {
+        let started = Instant::now();
+        let id = {
+            let mut bucket = self.lock();
+            let id = bucket.next_id;
+            bucket.next_id += 1;
+            bucket.waiters.push(Waiter {
+                id,
+                lane,
+                since: started,
+            });
+            id
+        };
+        let mut place = Place {
+            scheduler: self,
+            id,
+            served: false,
+        };
+
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            let wait = {
+                let mut bucket = self.lock();
+                let now = Instant::now();
+                if bucket.try_take(id, now) {
+                    None
+                } else {
+                    Some(bucket.wait_for(id, now))
+                }
+            };
+            let Some(wait) = wait else {
+                place.served = true;
+                // Whoever is next may be able to go too.
+                self.changed.notify_waiters();
+                break;
+            };
+            tokio::select! {
+                _ = changed => {}
+                _ = tokio::time::sleep(wait.max(Duration::from_millis(10))) => {}
+            }
+        }
+
+        let waited = started.elapsed();
+        if waited >= Duration::from_secs(1) {
+            debug!(lane = %lane, waited_ms = waited.as_millis() as u64, "LLM call waited for its turn");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A caller's place in line, given up when it is dropped unserved.
+struct Place<'a> {
+    scheduler: &'a LlmScheduler,
+    id: u64,
+    served: bool,
+}
+
+impl Drop for Place<'_> {
+    fn drop(&mut self) {
+        if !self.served {
+            self.scheduler.lock().waiters.retain(|w| w.id != self.id);
+            self.scheduler.changed.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(tokens: f64, now: Instant) -> Bucket {
+        Bucket {
+            capacity: 8.0,
+            rate: 1.0,
+            starvation: Duration::from_secs(60),
+            tokens,
+            refilled: now,
+            waiters: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    fn wait(bucket: &mut Bucket, lane: Lane, since: Instant) -> u64 {
+        let id = bucket.next_id;
+        bucket.next_id += 1;
+        bucket.waiters.push(Waiter { id, lane, since });
+        id
+    }
+
+    #[test]
+    fn test_lanes_by_sender() {
+        assert_eq!(Lane::of("123456"), Lane::Interactive);
+        assert_eq!(Lane::of("cron"), Lane::Cron);
+        assert_eq!(Lane::of("webhook:github"), Lane::Cron);
+        assert_eq!(Lane::of("heartbeat"), Lane::Heartbeat);
+    }
+
+    #[test]
+    fn test_priority_reserve_and_starvation() {
+        let t0 = Instant::now();
+        let mut b = bucket(5.0, t0);
+        let heartbeat = wait(&mut b, Lane::Heartbeat, t0);
+        let cron = wait(&mut b, Lane::Cron, t0);
+        let user = wait(&mut b, Lane::Interactive, t0 + Duration::from_secs(1));
+
+        // The user goes first even though they came last.
+        assert!(!b.try_take(heartbeat, t0 + Duration::from_secs(1)));
+        assert!(b.try_take(user, t0 + Duration::from_secs(1)));
+        // Cron must leave 2 of the 8 tokens, the heartbeat 4.
+        assert!(b.try_take(cron, t0 + Duration::from_secs(1)));
+        assert!(!b.try_take(heartbeat, t0 + Duration::from_secs(1)));
+        assert_eq!(b.wait_for(heartbeat, t0 + Duration::from_secs(1)).as_secs(), 1);
+
+        // Busy users keep the bucket low; the heartbeat waits for the
+        // reserve until it starves, then takes the last token.
+        b.waiters.clear();
+        b.rate = 0.0;
+        b.tokens = 4.0;
+        let heartbeat = wait(&mut b, Lane::Heartbeat, t0);
+        for s in [2, 3, 4] {
+            let user = wait(&mut b, Lane::Interactive, t0 + Duration::from_secs(s));
+            assert!(b.try_take(user, t0 + Duration::from_secs(s)));
+            assert!(!b.try_take(heartbeat, t0 + Duration::from_secs(s)));
+        }
+        let later = t0 + Duration::from_secs(60);
+        let user = wait(&mut b, Lane::Interactive, later);
+        assert!(!b.try_take(user, later), "the starving heartbeat is next");
+        assert!(b.try_take(heartbeat, later));
+        assert!(!b.try_take(user, later));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_refill() {
+        let scheduler = LlmScheduler::new(600, 1, Duration::from_secs(60));
+        let started = Instant::now();
+        scheduler.acquire(Lane::Interactive).await;
+        scheduler.acquire(Lane::Interactive).await;
+        // 600 a minute is one every 100ms.
+        assert!(started.elapsed() >= Duration::from_millis(80));
+
+        // A caller that gives up leaves the line.
+        let gave_up = tokio::time::timeout(Duration::from_millis(5), scheduler.acquire(Lane::Cron)).await;
+        assert!(gave_up.is_err());
+        assert!(scheduler.lock().waiters.is_empty());
+    }
+}
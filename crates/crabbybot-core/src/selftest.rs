@@ -0,0 +1,227 @@
+//! Startup self-test and connection warm-up.
+//!
+//! Checks each active LLM provider, the tool registry's parameter schemas,
+//! and the cron store, and renders the outcome as a pass/fail table.
+//!
+//! [`SelfTestMode::Light`] only probes each provider's `/models` endpoint,
+//! which resolves DNS and completes the TLS handshake on the shared client so
+//! the first real request doesn't pay for it. It runs automatically in bot
+//! mode. [`SelfTestMode::Full`] sends a tiny completion to every provider
+//! instead and backs `crabbybot --self-test`.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::cron::CronService;
+use crate::provider::types::ChatMessage;
+use crate::provider::LlmProvider;
+use crate::tools::ToolRegistry;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestMode {
+    /// Reachability probes only; no tokens are spent.
+    Light,
+    /// A one-token completion against each provider.
+    Full,
+}
+
+/// Outcome of a single check.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub elapsed: Duration,
+}
+
+/// All check outcomes, in the order they ran.
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+
+    /// Render the report as an aligned plain-text table.
+    pub fn render(&self) -> String {
+        let width = self
+            .checks
+            .iter()
+            .map(|c| c.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Check".len());
+        let mut out = format!("  {:<width$}  Result   Time     Detail\n", "Check");
+        for c in &self.checks {
+            out.push_str(&format!(
+                "  {:<width$}  {}  {:>6}ms  {}\n",
+                c.name,
+                if c.passed { "✅ PASS" } else { "❌ FAIL" },
+                c.elapsed.as_millis(),
+                c.detail
+            ));
+        }
+        out
+    }
+
+    fn push(&mut self, name: impl Into<String>, started: Instant, result: Result<String, String>) {
+        let (passed, detail) = match result {
+            Ok(d) => (true, d),
+            Err(d) => (false, d),
+        };
+        self.checks.push(CheckResult {
+            name: name.into(),
+            passed,
+            detail,
+            elapsed: started.elapsed(),
+        });
+    }
+}
+
+/// Run every check. Provider checks run concurrently.
+pub async fn run(
+    config: &Config,
+    client: &reqwest::Client,
+    tools: &ToolRegistry,
+    mode: SelfTestMode,
+) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    let providers = crate::provider::active_providers(
+        config,
+        &config.agents.defaults.model,
+        client.clone(),
+    );
+    if providers.is_empty() {
+        report.push("providers", Instant::now(), Err("no active LLM provider configured".into()));
+    }
+    let probes = providers.iter().map(|(name, provider)| async move {
+        let started = Instant::now();
+        let result = match mode {
+            SelfTestMode::Light => probe(provider.as_ref()).await,
+            SelfTestMode::Full => complete(provider.as_ref()).await,
+        };
+        (format!("provider:{}", name), started, result)
+    });
+    for (name, started, result) in futures::future::join_all(probes).await {
+        report.push(name, started, result);
+    }
+
+    let started = Instant::now();
+    report.push("tools", started, check_tools(tools));
+
+    let started = Instant::now();
+    report.push("cron", started, check_cron(&config.workspace_path()));
+
+    report
+}
+
+/// Hit `GET {base}/models`. Any response other than an auth rejection means
+/// DNS, TLS and routing work.
+async fn probe(provider: &dyn LlmProvider) -> Result<String, String> {
+    let Some(request) = provider.models_request() else {
+        return Ok("no probe available".into());
+    };
+    let resp = request
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("unreachable: {}", e))?;
+    match resp.status().as_u16() {
+        401 | 403 => Err(format!("API key rejected (HTTP {})", resp.status().as_u16())),
+        status => Ok(format!("reachable (HTTP {})", status)),
+    }
+}
+
+async fn complete(provider: &dyn LlmProvider) -> Result<String, String> {
+    let messages = [ChatMessage::user("Reply with OK.")];
+    match tokio::time::timeout(COMPLETION_TIMEOUT, provider.chat(&messages, &[], None, 5, 0.0)).await {
+        Ok(Ok(_)) => Ok(format!("completion ok ({})", provider.default_model())),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {}s", COMPLETION_TIMEOUT.as_secs())),
+    }
+}
+
+fn check_tools(tools: &ToolRegistry) -> Result<String, String> {
+    let problems = tools.validate_schemas();
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    match tools.conflicts().as_slice() {
+        [] => Ok(format!("{} tool schemas valid", tools.len())),
+        conflicts => Ok(format!(
+            "{} tool schemas valid; name conflicts: {}",
+            tools.len(),
+            conflicts
+                .iter()
+                .map(|c| format!("{} from {} as {}", c.name, c.moved, c.registered_as))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn check_cron(workspace: &Path) -> Result<String, String> {
+    CronService::validate_store(workspace)
+        .map(|n| format!("{} job(s) parsed", n))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{IntentCategory, Tool, ToolContext};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    struct BadSchemaTool;
+
+    #[async_trait]
+    impl Tool for BadSchemaTool {
+        fn name(&self) -> &str {
+            "bad"
+        }
+        fn description(&self) -> &str {
+            "schema requires an undeclared field"
+        }
+        fn parameters(&self) -> Value {
+            json!({ "type": "object", "properties": {}, "required": ["missing"] })
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+            String::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_self_test_reports_bad_schema_and_cron() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_selftest");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("cron.json"), "{ not json").unwrap();
+
+        let mut config = Config::default();
+        config.agents.defaults.workspace = tmp.to_string_lossy().into_owned();
+        let tools = ToolRegistry::new();
+        tools.register(Box::new(BadSchemaTool), IntentCategory::General);
+
+        let report = run(&config, &reqwest::Client::new(), &tools, SelfTestMode::Light).await;
+        assert!(!report.passed());
+        let failed: Vec<_> = report.failures().map(|c| c.name.as_str()).collect();
+        assert_eq!(failed, vec!["providers", "tools", "cron"]);
+        assert!(report.render().contains("required `missing`"));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -11,7 +11,8 @@ use tokio::time::{self, Duration};
 use tracing::{debug, info, warn};
 
 use crate::config::BettingConfig;
-use crate::tools::ToolRegistry;
+use crate::journal::DecisionDraft;
+use crate::tools::{ToolContext, ToolRegistry};
 
 // ── Types ──────────────────────────────────────────────────────────
 
@@ -221,6 +222,7 @@ impl BettingService {
                         &tools,
                         &candidate,
                         &config,
+                        score,
                     )
                     .await;
 
@@ -268,7 +270,7 @@ impl BettingService {
         let trending_output = tools
             .execute("polymarket_trending", HashMap::from([
                 ("limit".into(), serde_json::json!("5")),
-            ]))
+            ]), &ToolContext::system())
             .await;
 
         debug!(output_len = trending_output.len(), "Trending markets fetched");
@@ -351,7 +353,7 @@ impl BettingService {
             tools
                 .execute("polymarket_price", HashMap::from([
                     ("token_id".into(), serde_json::json!(candidate.token_id)),
-                ]))
+                ]), &ToolContext::system())
                 .await
         } else {
             String::new()
@@ -405,6 +407,7 @@ impl BettingService {
         tools: &ToolRegistry,
         candidate: &MarketCandidate,
         config: &BettingConfig,
+        score: u32,
     ) -> Result<String, String> {
         if candidate.token_id.is_empty() {
             return Err("No token ID available for this market".into());
@@ -433,6 +436,31 @@ impl BettingService {
             "Placing limit order"
         );
 
+        // Journal the decision; the registry links the order to it.
+        let ctx = ToolContext::system();
+        if let Some(journal) = tools.journal() {
+            let draft = DecisionDraft {
+                trade: format!(
+                    "{} {:.0} shares of \"{}\" at {:.2}",
+                    candidate.suggested_side, shares, candidate.question, order_price
+                ),
+                thesis: format!(
+                    "Trending market scored {}/10 by the heuristic (threshold {})",
+                    score, config.min_llm_score
+                ),
+                inputs: vec![
+                    format!("price {:.2}", candidate.current_price),
+                    format!("volume {}", candidate.volume_str),
+                ],
+                expected_outcome: format!("Position gains {:.0}% (take profit)", config.take_profit_percent),
+                stop_condition: format!("Position loses {:.0}% (stop loss)", config.stop_loss_percent),
+                size_usd: Some(shares * order_price),
+            };
+            if let Err(e) = journal.record(draft, &ctx.channel, &ctx.chat_id) {
+                warn!("Failed to journal betting decision: {}", e);
+            }
+        }
+
         let result = tools
             .execute("polymarket_create_order", HashMap::from([
                 ("token_id".into(), serde_json::json!(candidate.token_id)),
@@ -440,7 +468,7 @@ impl BettingService {
                 ("price".into(), serde_json::json!(format!("{:.2}", order_price))),
                 ("size".into(), serde_json::json!(format!("{:.0}", shares))),
                 ("order_type".into(), serde_json::json!("GTC")),
-            ]))
+            ]), &ctx)
             .await;
 
         if result.contains("❌") || result.contains("Error") || result.contains("error") {
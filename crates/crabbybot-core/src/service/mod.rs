@@ -1 +1,3 @@
 pub mod betting;
+#[cfg(feature = "crypto-tools")]
+pub mod solana_ws;
@@ -0,0 +1,424 @@
+//! Solana websocket subscriptions (`accountSubscribe` / `logsSubscribe`).
+//!
+//! Polling `getBalance` burns RPC quota and notices changes late. A
+//! [`SolanaWs`] keeps one websocket to the RPC node (the `wss://` twin of
+//! `tools.solanaRpcUrl`) and fans notifications out to per-subscription
+//! channels. When the connection drops it reconnects with exponential
+//! backoff and re-sends every live subscription, so consumers only see a
+//! gap in updates. A subscription ends when its receiver is dropped; the
+//! node is told to stop sending it right away.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::{SinkExt as _, StreamExt as _};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// RPC nodes drop idle sockets; a ping keeps the connection alive.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What to subscribe to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subscription {
+    /// Lamports, owner and data of one account.
+    Account(String),
+    /// Logs of every transaction that mentions this address.
+    Logs(String),
+}
+
+impl Subscription {
+    fn request(&self, id: u64) -> Value {
+        let (method, params) = match self {
+            Self::Account(address) => (
+                "accountSubscribe",
+                json!([address, {"encoding": "jsonParsed", "commitment": "confirmed"}]),
+            ),
+            Self::Logs(address) => (
+                "logsSubscribe",
+                json!([{"mentions": [address]}, {"commitment": "confirmed"}]),
+            ),
+        };
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn unsubscribe(&self, server_id: u64) -> Value {
+        let method = match self {
+            Self::Account(_) => "accountUnsubscribe",
+            Self::Logs(_) => "logsUnsubscribe",
+        };
+        json!({"jsonrpc": "2.0", "id": 0, "method": method, "params": [server_id]})
+    }
+}
+
+/// One notification: the slot it was observed at and the RPC `value`
+/// (an account object for accounts, `{signature, err, logs}` for logs).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    pub slot: u64,
+    pub value: Value,
+}
+
+/// websocket URL for an HTTP(S) RPC URL.
+pub fn ws_url(rpc_url: &str) -> String {
+    if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    }
+}
+
+/// A parsed inbound frame.
+#[derive(Debug, PartialEq)]
+enum Incoming {
+    /// Reply to a subscribe request: request id → server subscription id.
+    Subscribed { request: u64, server: u64 },
+    Notification { server: u64, update: Update },
+    Other,
+}
+
+fn parse_incoming(text: &str) -> Incoming {
+    let Ok(msg) = serde_json::from_str::<Value>(text) else {
+        return Incoming::Other;
+    };
+    if let (Some(request), Some(server)) = (msg["id"].as_u64(), msg["result"].as_u64()) {
+        return Incoming::Subscribed { request, server };
+    }
+    let params = &msg["params"];
+    match params["subscription"].as_u64() {
+        Some(server) if msg["method"].as_str().is_some_and(|m| m.ends_with("Notification")) => {
+            Incoming::Notification {
+                server,
+                update: Update {
+                    slot: params["result"]["context"]["slot"].as_u64().unwrap_or_default(),
+                    value: params["result"]["value"].clone(),
+                },
+            }
+        }
+        _ => Incoming::Other,
+    }
+}
+
+struct Request {
+    subscription: Subscription,
+    updates: mpsc::UnboundedSender<Update>,
+}
+
+/// Handle to the background websocket task. Cheap to share behind an
+/// `Arc`; the task exits once the handle and all receivers are dropped.
+pub struct SolanaWs {
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+impl SolanaWs {
+    /// Start the connection task for the RPC at `rpc_url` (HTTP or WS form).
+    /// Must be called inside a Tokio runtime.
+    pub fn connect(rpc_url: &str) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(ws_url(rpc_url), rx));
+        Self { requests }
+    }
+
+    /// Subscribe and receive updates until the receiver is dropped.
+    pub fn subscribe(&self, subscription: Subscription) -> mpsc::UnboundedReceiver<Update> {
+        let (updates, rx) = mpsc::unbounded_channel();
+        let _ = self.requests.send(Request { subscription, updates });
+        rx
+    }
+
+    pub fn subscribe_account(&self, address: &str) -> mpsc::UnboundedReceiver<Update> {
+        self.subscribe(Subscription::Account(address.to_string()))
+    }
+
+    pub fn subscribe_logs(&self, address: &str) -> mpsc::UnboundedReceiver<Update> {
+        self.subscribe(Subscription::Logs(address.to_string()))
+    }
+}
+
+/// Live subscriptions, keyed by a local id that doubles as the request id.
+type Subs = HashMap<u64, Request>;
+
+/// Send `id` on `closed` once the receiver of `req` is dropped.
+fn watch(id: u64, req: &Request, closed: &mpsc::UnboundedSender<u64>) {
+    let updates = req.updates.clone();
+    let closed = closed.clone();
+    tokio::spawn(async move {
+        updates.closed().await;
+        let _ = closed.send(id);
+    });
+}
+
+async fn run(url: String, mut requests: mpsc::UnboundedReceiver<Request>) {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let mut subs = Subs::new();
+    let mut next_id = 1u64;
+    let mut backoff = Duration::from_secs(1);
+    let (closed_tx, mut closed) = mpsc::unbounded_channel();
+    let mut handle_open = true;
+
+    loop {
+        match connect_async(url.as_str()).await {
+            Ok((ws, _)) => {
+                if !subs.is_empty() {
+                    info!(url = %url, subscriptions = subs.len(), "Solana websocket connected, resubscribing");
+                }
+                backoff = Duration::from_secs(1);
+                let conn = Conn {
+                    subs: &mut subs,
+                    next_id: &mut next_id,
+                    requests: &mut requests,
+                    handle_open: &mut handle_open,
+                    closed_tx: &closed_tx,
+                    closed: &mut closed,
+                };
+                if serve(ws, conn).await {
+                    return;
+                }
+                warn!(url = %url, "Solana websocket dropped, reconnecting");
+            }
+            Err(e) => warn!(url = %url, "Solana websocket connect failed: {}", e),
+        }
+
+        // Keep accepting subscriptions while waiting to reconnect.
+        let sleep = tokio::time::sleep(backoff);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                req = requests.recv(), if handle_open => match req {
+                    Some(req) => {
+                        watch(next_id, &req, &closed_tx);
+                        subs.insert(next_id, req);
+                        next_id += 1;
+                    }
+                    None => handle_open = false,
+                },
+                Some(id) = closed.recv() => {
+                    subs.remove(&id);
+                }
+            }
+            if !handle_open && subs.is_empty() {
+                return;
+            }
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// State of the connection task that outlives one socket.
+struct Conn<'a> {
+    subs: &'a mut Subs,
+    next_id: &'a mut u64,
+    requests: &'a mut mpsc::UnboundedReceiver<Request>,
+    handle_open: &'a mut bool,
+    /// Local ids of subscriptions whose receiver was dropped.
+    closed_tx: &'a mpsc::UnboundedSender<u64>,
+    closed: &'a mut mpsc::UnboundedReceiver<u64>,
+}
+
+/// Drive one connection. Returns `true` when the task should stop (the
+/// handle and every receiver are gone), `false` when the socket dropped.
+async fn serve<S>(ws: tokio_tungstenite::WebSocketStream<S>, conn: Conn<'_>) -> bool
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let Conn { subs, next_id, requests, handle_open, closed_tx, closed } = conn;
+    let (mut sink, mut stream) = ws.split();
+    // Server subscription id → local id, rebuilt on every connection.
+    let mut servers: HashMap<u64, u64> = HashMap::new();
+
+    subs.retain(|_, r| !r.updates.is_closed());
+    for (id, req) in subs.iter() {
+        let frame = req.subscription.request(*id).to_string();
+        if sink.send(Message::Text(frame.into())).await.is_err() {
+            return false;
+        }
+    }
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+    loop {
+        if !*handle_open && subs.is_empty() {
+            let _ = sink.close().await;
+            return true;
+        }
+        tokio::select! {
+            req = requests.recv(), if *handle_open => match req {
+                Some(req) => {
+                    let frame = req.subscription.request(*next_id).to_string();
+                    watch(*next_id, &req, closed_tx);
+                    subs.insert(*next_id, req);
+                    *next_id += 1;
+                    if sink.send(Message::Text(frame.into())).await.is_err() {
+                        return false;
+                    }
+                }
+                None => *handle_open = false,
+            },
+            Some(id) = closed.recv() => {
+                // Not confirmed yet: dropped when the confirmation comes in.
+                let Some(server) = servers.iter().find(|(_, local)| **local == id).map(|(s, _)| *s) else {
+                    continue;
+                };
+                servers.remove(&server);
+                if let Some(req) = subs.remove(&id) {
+                    let frame = req.subscription.unsubscribe(server).to_string();
+                    if sink.send(Message::Text(frame.into())).await.is_err() {
+                        return false;
+                    }
+                }
+            }
+            _ = ping.tick() => {
+                if sink.send(Message::Ping(Vec::new().into())).await.is_err() {
+                    return false;
+                }
+            }
+            frame = stream.next() => {
+                let text = match frame {
+                    Some(Ok(Message::Text(t))) => t.to_string(),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return false,
+                    Some(Ok(_)) => continue,
+                };
+                match parse_incoming(&text) {
+                    Incoming::Subscribed { request, server } => match subs.get(&request) {
+                        // Its receiver was dropped while the request was in flight.
+                        Some(req) if req.updates.is_closed() => {
+                            let frame = req.subscription.unsubscribe(server).to_string();
+                            subs.remove(&request);
+                            if sink.send(Message::Text(frame.into())).await.is_err() {
+                                return false;
+                            }
+                        }
+                        Some(_) => {
+                            debug!(request, server, "Solana subscription confirmed");
+                            servers.insert(server, request);
+                        }
+                        None => {}
+                    },
+                    Incoming::Notification { server, update } => {
+                        let Some(id) = servers.get(&server).copied() else { continue };
+                        let delivered = subs.get(&id).is_some_and(|r| r.updates.send(update).is_ok());
+                        if !delivered {
+                            // Receiver dropped: stop the subscription upstream too.
+                            if let Some(req) = subs.remove(&id) {
+                                servers.remove(&server);
+                                let frame = req.subscription.unsubscribe(server).to_string();
+                                if sink.send(Message::Text(frame.into())).await.is_err() {
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_ws_url_and_parsing() {
+        assert_eq!(ws_url("https://api.mainnet-beta.solana.com"), "wss://api.mainnet-beta.solana.com");
+        assert_eq!(ws_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8899");
+
+        assert_eq!(
+            parse_incoming(r#"{"jsonrpc":"2.0","result":23784,"id":3}"#),
+            Incoming::Subscribed { request: 3, server: 23784 }
+        );
+        let note = r#"{"jsonrpc":"2.0","method":"accountNotification","params":{
+            "result":{"context":{"slot":5199307},"value":{"lamports":33594}},"subscription":23784}}"#;
+        assert_eq!(
+            parse_incoming(note),
+            Incoming::Notification {
+                server: 23784,
+                update: Update { slot: 5199307, value: json!({"lamports": 33594}) },
+            }
+        );
+        assert_eq!(parse_incoming("not json"), Incoming::Other);
+    }
+
+    /// The next JSON request the client sends.
+    async fn next_request(ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>) -> Value {
+        loop {
+            if let Some(Ok(Message::Text(t))) = ws.next().await {
+                return serde_json::from_str(&t).unwrap();
+            }
+        }
+    }
+
+    /// Accept one connection, confirm the first subscribe request and send
+    /// one notification carrying `lamports`, then drop the socket.
+    async fn serve_once(listener: &TcpListener, lamports: u64) -> Value {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let request = next_request(&mut ws).await;
+        let reply = json!({"jsonrpc": "2.0", "result": 7, "id": request["id"]});
+        ws.send(Message::Text(reply.to_string().into())).await.unwrap();
+        let note = json!({"jsonrpc": "2.0", "method": "accountNotification", "params": {
+            "result": {"context": {"slot": lamports}, "value": {"lamports": lamports}},
+            "subscription": 7}});
+        ws.send(Message::Text(note.to_string().into())).await.unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn test_resubscribes_after_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let ws = SolanaWs::connect(&url);
+        let mut updates = ws.subscribe_account("Wallet111");
+
+        let first = serve_once(&listener, 100).await;
+        assert_eq!(first["method"], "accountSubscribe");
+        assert_eq!(first["params"][0], "Wallet111");
+        assert_eq!(updates.recv().await.unwrap().value["lamports"], 100);
+
+        // The server dropped the socket; the client reconnects and
+        // re-sends the same subscription.
+        let second = serve_once(&listener, 200).await;
+        assert_eq!(second["params"][0], "Wallet111");
+        let update = tokio::time::timeout(Duration::from_secs(10), updates.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update, Update { slot: 200, value: json!({"lamports": 200}) });
+    }
+
+    #[tokio::test]
+    async fn test_dropped_receiver_unsubscribes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let ws = SolanaWs::connect(&url);
+        let mut updates = ws.subscribe_logs("Wallet111");
+
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut server = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let request = next_request(&mut server).await;
+        assert_eq!(request["method"], "logsSubscribe");
+        let reply = json!({"jsonrpc": "2.0", "result": 7, "id": request["id"]});
+        server.send(Message::Text(reply.to_string().into())).await.unwrap();
+        let note = json!({"jsonrpc": "2.0", "method": "logsNotification", "params": {
+            "result": {"context": {"slot": 1}, "value": {"signature": "sig"}}, "subscription": 7}});
+        server.send(Message::Text(note.to_string().into())).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().slot, 1);
+
+        // No further notification is needed for the node to hear about it.
+        drop(updates);
+        let request = tokio::time::timeout(Duration::from_secs(10), next_request(&mut server))
+            .await
+            .unwrap();
+        assert_eq!(request["method"], "logsUnsubscribe");
+        assert_eq!(request["params"], json!([7]));
+    }
+}
@@ -3,12 +3,30 @@
 //! Sessions are stored as JSONL files for easy persistence and reading.
 //! Each line in the file is a JSON object representing a message.
 
+pub mod tags;
+
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing::warn;
 
+/// Errors from persisting sessions.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Session I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Session serialization error: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Session not found: {0}")]
+    NotFound(String),
+
+    #[error("Session already exists: {0}")]
+    AlreadyExists(String),
+}
+
 /// A conversation session with message history.
 #[derive(Debug, Clone)]
 pub struct Session {
@@ -16,6 +34,60 @@ pub struct Session {
     pub messages: Vec<SessionMessage>,
     pub created_at: String,
     pub updated_at: String,
+    /// Key of the session this one was forked from.
+    pub parent: Option<String>,
+    /// The part of a long reply not sent yet, waiting for "more".
+    pub pending_reply: Option<String>,
+    /// Model picked with `/model` for this conversation only.
+    pub model: Option<String>,
+    /// When the middle of the context was last replaced by a summary
+    /// (RFC 3339).
+    pub compressed_at: Option<String>,
+    /// Append how full the context is to every reply (`/context footer on`).
+    pub context_footer: bool,
+    /// Running summary of the first `summarized_through` messages, written
+    /// once they no longer fit the context budget.
+    pub summary: Option<String>,
+    /// Number of leading messages `summary` covers.
+    pub summarized_through: usize,
+    /// Topics found by [`tags::tag`], strongest first.
+    pub topics: Vec<String>,
+    /// How the user came across: `positive`, `negative` or `neutral`.
+    pub sentiment: Option<String>,
+    /// Number of messages the tags were worked out from.
+    pub tagged_through: usize,
+}
+
+/// A message found by [`SessionManager::search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub key: String,
+    pub role: String,
+    pub timestamp: String,
+    /// The text around the match, on one line.
+    pub snippet: String,
+}
+
+/// Characters of context shown on each side of a match.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// The part of `text` around the first case-insensitive occurrence of
+/// `needle` (already lowercased), or `None` without one.
+fn snippet(text: &str, needle: &[char]) -> Option<String> {
+    let chars: Vec<char> = text.chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let at = lower.windows(needle.len()).position(|w| w == needle)?;
+    let start = at.saturating_sub(SNIPPET_CONTEXT);
+    let end = (at + needle.len() + SNIPPET_CONTEXT).min(chars.len());
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.extend(&chars[start..end]);
+    if end < chars.len() {
+        out.push('…');
+    }
+    Some(out.split_whitespace().collect::<Vec<_>>().join(" "))
 }
 
 /// A single message in a session.
@@ -32,6 +104,30 @@ pub struct SessionMessage {
     pub name: Option<String>,
 }
 
+impl SessionMessage {
+    /// When the message was recorded, if the timestamp parses.
+    pub fn time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&chrono::Utc))
+    }
+
+    /// The message as the provider sees it: content and tool fields as
+    /// stored, without the timestamp.
+    pub fn to_chat_message(&self) -> crate::provider::types::ChatMessage {
+        crate::provider::types::ChatMessage {
+            role: self.role.clone(),
+            content: self
+                .content
+                .as_ref()
+                .map(|s| serde_json::Value::String(s.clone())),
+            tool_calls: self.tool_calls.clone(),
+            tool_call_id: self.tool_call_id.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
 impl Session {
     pub fn new(key: &str) -> Self {
         let now = chrono::Local::now().to_rfc3339();
@@ -40,6 +136,16 @@ impl Session {
             messages: Vec::new(),
             created_at: now.clone(),
             updated_at: now,
+            parent: None,
+            pending_reply: None,
+            model: None,
+            compressed_at: None,
+            context_footer: false,
+            summary: None,
+            summarized_through: 0,
+            topics: Vec::new(),
+            sentiment: None,
+            tagged_through: 0,
         }
     }
 
@@ -60,7 +166,8 @@ impl Session {
     pub fn add_chat_message(&mut self, msg: &crate::provider::types::ChatMessage) {
         self.messages.push(SessionMessage {
             role: msg.role.clone(),
-            content: msg.content_as_str().map(|s| s.to_string()),
+            // Images a tool showed the model are only kept for the turn.
+            content: msg.text(),
             timestamp: chrono::Local::now().to_rfc3339(),
             tool_calls: msg.tool_calls.clone(),
             tool_call_id: msg.tool_call_id.clone(),
@@ -79,16 +186,7 @@ impl Session {
 
         self.messages[start..]
             .iter()
-            .map(|m| crate::provider::types::ChatMessage {
-                role: m.role.clone(),
-                content: m
-                    .content
-                    .as_ref()
-                    .map(|s| serde_json::Value::String(s.clone())),
-                tool_calls: m.tool_calls.clone(),
-                tool_call_id: m.tool_call_id.clone(),
-                name: m.name.clone(),
-            })
+            .map(SessionMessage::to_chat_message)
             .collect()
     }
 
@@ -104,8 +202,17 @@ impl Session {
         &self,
         max_tokens: usize,
     ) -> Vec<crate::provider::types::ChatMessage> {
+        self.messages_within_budget(max_tokens)
+            .iter()
+            .map(SessionMessage::to_chat_message)
+            .collect()
+    }
+
+    /// The messages [`get_history_within_budget`](Self::get_history_within_budget)
+    /// converts, with their timestamps intact.
+    pub fn messages_within_budget(&self, max_tokens: usize) -> &[SessionMessage] {
         if self.messages.is_empty() {
-            return vec![];
+            return &[];
         }
 
         let mut budget = max_tokens;
@@ -129,31 +236,79 @@ impl Session {
             budget = budget.saturating_sub(estimated_tokens);
         }
 
-        self.messages[start..]
-            .iter()
-            .map(|m| crate::provider::types::ChatMessage {
-                role: m.role.clone(),
-                content: m
-                    .content
-                    .as_ref()
-                    .map(|s| serde_json::Value::String(s.clone())),
-                tool_calls: m.tool_calls.clone(),
-                tool_call_id: m.tool_call_id.clone(),
-                name: m.name.clone(),
-            })
-            .collect()
+        &self.messages[start..]
+    }
+
+    /// Work out the tags again if messages were added since the last
+    /// time. Returns whether anything was tagged.
+    pub fn retag(&mut self) -> bool {
+        if self.messages.len() == self.tagged_through {
+            return false;
+        }
+        let tags = tags::tag(&self.messages);
+        self.topics = tags.topics;
+        self.sentiment = tags.sentiment;
+        self.tagged_through = self.messages.len();
+        true
+    }
+
+    /// Whether the session was tagged with `topic` (case-insensitive).
+    pub fn has_topic(&self, topic: &str) -> bool {
+        self.topics.iter().any(|t| t.eq_ignore_ascii_case(topic.trim()))
     }
 
     /// Clear all messages.
     pub fn clear(&mut self) {
         self.messages.clear();
+        self.summary = None;
+        self.summarized_through = 0;
+        self.topics.clear();
+        self.sentiment = None;
+        self.tagged_through = 0;
         self.updated_at = chrono::Local::now().to_rfc3339();
     }
 }
 
+/// Channel and chat a session key belongs to: `telegram:42`, its forks
+/// (`telegram:42#idea`) and per-user sessions (`telegram:42:7`) all give
+/// `("telegram", "42")`. A channel's shared session has no chat and gives
+/// `"direct"`.
+pub fn chat_of(key: &str) -> (&str, &str) {
+    let base = key.split('#').next().unwrap_or(key);
+    match base.split_once(':') {
+        Some((channel, rest)) => (channel, rest.split(':').next().unwrap_or(rest)),
+        None => (base, "direct"),
+    }
+}
+
+/// Key for a fork called `name` of the session `current`: the conversation's
+/// base key plus `#name`, so forks of forks stay grouped under one chat.
+/// `None` unless `name` is 1–40 letters, digits or dashes.
+pub fn fork_key(current: &str, name: &str) -> Option<String> {
+    let valid = !name.is_empty()
+        && name.len() <= 40
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let base = current.split('#').next().unwrap_or(current);
+    valid.then(|| format!("{}#{}", base, name))
+}
+
+/// The earlier (or, with `later`, the later) of two RFC 3339 timestamps,
+/// compared as instants so differing offsets don't matter. A readable
+/// timestamp wins over an unreadable one.
+fn pick_time(a: String, b: String, later: bool) -> String {
+    let parse = |t: &str| chrono::DateTime::parse_from_rfc3339(t).ok();
+    match (parse(&a), parse(&b)) {
+        (Some(x), Some(y)) if (y > x) == later => b,
+        (None, Some(_)) => b,
+        _ => a,
+    }
+}
+
 /// Manages conversation sessions with file-based persistence.
 pub struct SessionManager {
     sessions_dir: PathBuf,
+    /// Directories of chats with their own workspace, by `channel:chat_id`.
+    chat_dirs: HashMap<String, PathBuf>,
     cache: HashMap<String, Session>,
 }
 
@@ -167,10 +322,25 @@ impl SessionManager {
 
         Self {
             sessions_dir,
+            chat_dirs: HashMap::new(),
             cache: HashMap::new(),
         }
     }
 
+    /// Keep the sessions of these chats (`channel:chat_id`, forks included)
+    /// in `sessions/` of their own workspace.
+    pub fn with_chat_workspaces(mut self, workspaces: impl IntoIterator<Item = (String, PathBuf)>) -> Self {
+        self.chat_dirs
+            .extend(workspaces.into_iter().map(|(chat, workspace)| (chat, workspace.join("sessions"))));
+        self
+    }
+
+    /// Drop changes to `key` that weren't saved, so the next access reads
+    /// the session as it was last written.
+    pub fn discard_unsaved(&mut self, key: &str) {
+        self.cache.remove(key);
+    }
+
     /// Get an existing session or create a new one.
     pub fn get_or_create(&mut self, key: &str) -> &mut Session {
         if !self.cache.contains_key(key) {
@@ -181,7 +351,7 @@ impl SessionManager {
     }
 
     /// Save a session to disk.
-    pub fn save(&self, key: &str) -> anyhow::Result<()> {
+    pub fn save(&self, key: &str) -> Result<(), SessionError> {
         let session = match self.cache.get(key) {
             Some(s) => s,
             None => return Ok(()),
@@ -191,11 +361,37 @@ impl SessionManager {
         let mut lines = Vec::new();
 
         // Metadata line
-        let metadata = serde_json::json!({
+        let mut metadata = serde_json::json!({
             "_type": "metadata",
             "created_at": session.created_at,
             "updated_at": session.updated_at,
         });
+        if let Some(parent) = &session.parent {
+            metadata["parent"] = parent.as_str().into();
+        }
+        if let Some(pending) = &session.pending_reply {
+            metadata["pending_reply"] = pending.as_str().into();
+        }
+        if let Some(model) = &session.model {
+            metadata["model"] = model.as_str().into();
+        }
+        if let Some(compressed_at) = &session.compressed_at {
+            metadata["compressed_at"] = compressed_at.as_str().into();
+        }
+        if session.context_footer {
+            metadata["context_footer"] = true.into();
+        }
+        if let Some(summary) = &session.summary {
+            metadata["summary"] = summary.as_str().into();
+            metadata["summarized_through"] = session.summarized_through.into();
+        }
+        if session.tagged_through > 0 {
+            metadata["topics"] = session.topics.clone().into();
+            if let Some(sentiment) = &session.sentiment {
+                metadata["sentiment"] = sentiment.as_str().into();
+            }
+            metadata["tagged_through"] = session.tagged_through.into();
+        }
         lines.push(serde_json::to_string(&metadata)?);
 
         // Message lines
@@ -203,11 +399,168 @@ impl SessionManager {
             lines.push(serde_json::to_string(msg)?);
         }
 
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
         std::fs::write(path, lines.join("\n") + "\n")?;
         Ok(())
     }
 
-    /// Delete a session.
+    /// Whether `key` has messages in memory or a file on disk.
+    pub fn exists(&self, key: &str) -> bool {
+        self.cache.contains_key(key) || self.session_path(key).exists()
+    }
+
+    /// Copy the history of `source` into a new session `new_key` whose
+    /// metadata points back at `source`. Later messages in either session
+    /// leave the other untouched.
+    pub fn fork(&mut self, source: &str, new_key: &str) -> Result<&Session, SessionError> {
+        if !self.exists(source) {
+            return Err(SessionError::NotFound(source.to_string()));
+        }
+        if self.exists(new_key) {
+            return Err(SessionError::AlreadyExists(new_key.to_string()));
+        }
+        let source_session = self.get_or_create(source);
+        let mut fork = Session::new(new_key);
+        fork.messages = source_session.messages.clone();
+        fork.model = source_session.model.clone();
+        fork.summary = source_session.summary.clone();
+        fork.summarized_through = source_session.summarized_through;
+        fork.topics = source_session.topics.clone();
+        fork.sentiment = source_session.sentiment.clone();
+        fork.tagged_through = source_session.tagged_through;
+        fork.parent = Some(source.to_string());
+        self.cache.insert(new_key.to_string(), fork);
+        self.save(new_key)?;
+        Ok(&self.cache[new_key])
+    }
+
+    /// Move the session `old` to `new_key`. Forks of `old` are pointed at
+    /// the new key.
+    pub fn rename(&mut self, old: &str, new_key: &str) -> Result<&Session, SessionError> {
+        if !self.exists(old) {
+            return Err(SessionError::NotFound(old.to_string()));
+        }
+        if self.exists(new_key) {
+            return Err(SessionError::AlreadyExists(new_key.to_string()));
+        }
+        let mut session = self.get_or_create(old).clone();
+        session.key = new_key.to_string();
+        self.cache.insert(new_key.to_string(), session);
+        self.save(new_key)?;
+        self.delete(old);
+        self.relink(old, Some(new_key))?;
+        Ok(&self.cache[new_key])
+    }
+
+    /// Fold the history of `from` into `into` and delete `from`. The two
+    /// histories are joined whole, the one that started first going first,
+    /// so no tool call is separated from its results. Forks of `from` are
+    /// pointed at `into`.
+    pub fn merge(&mut self, into: &str, from: &str) -> Result<&Session, SessionError> {
+        for key in [into, from] {
+            if !self.exists(key) {
+                return Err(SessionError::NotFound(key.to_string()));
+            }
+        }
+        let other = self.get_or_create(from).clone();
+        let session = self.get_or_create(into);
+        let start = |s: &Session| s.messages.first().and_then(SessionMessage::time);
+        if start(&other) < start(session) {
+            let later = std::mem::replace(&mut session.messages, other.messages);
+            session.messages.extend(later);
+        } else {
+            session.messages.extend(other.messages);
+        }
+        session.created_at = pick_time(session.created_at.clone(), other.created_at, false);
+        session.updated_at = pick_time(session.updated_at.clone(), other.updated_at, true);
+        // The running summary covers a prefix that may have just changed.
+        session.summary = None;
+        session.summarized_through = 0;
+        session.tagged_through = 0;
+        self.save(into)?;
+        self.delete(from);
+        self.relink(from, Some(into))?;
+        Ok(&self.cache[into])
+    }
+
+    /// Sessions not updated for longer than `age`, as `(key, updated_at)`,
+    /// oldest first. Sessions without a readable timestamp are left out.
+    pub fn not_updated_for(&self, age: chrono::Duration) -> Vec<(String, String)> {
+        let cutoff = chrono::Utc::now() - age;
+        let mut stale: Vec<_> = self
+            .list_sessions()
+            .into_iter()
+            .filter(|(_, updated)| {
+                chrono::DateTime::parse_from_rfc3339(updated).is_ok_and(|t| t < cutoff)
+            })
+            .collect();
+        stale.reverse();
+        stale
+    }
+
+    /// User and assistant messages containing `query` (case-insensitive)
+    /// in every stored session, or only in those tagged with `topic`,
+    /// newest sessions and messages first, at most `limit` of them.
+    pub fn search(&self, query: &str, topic: Option<&str>, limit: usize) -> Vec<SearchHit> {
+        let needle: Vec<char> = query.trim().chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let mut hits = Vec::new();
+        for (key, _) in self.list_sessions() {
+            let Some(session) = self.cache.get(&key).cloned().or_else(|| self.load(&key)) else {
+                continue;
+            };
+            if topic.is_some_and(|topic| !session.has_topic(topic)) {
+                continue;
+            }
+            let messages = session.messages.iter().rev().filter(|m| m.role == "user" || m.role == "assistant");
+            for msg in messages {
+                let Some(snippet) = msg.content.as_deref().and_then(|text| snippet(text, &needle)) else {
+                    continue;
+                };
+                hits.push(SearchHit {
+                    key: key.clone(),
+                    role: msg.role.clone(),
+                    timestamp: msg.timestamp.clone(),
+                    snippet,
+                });
+                if hits.len() >= limit {
+                    return hits;
+                }
+            }
+        }
+        hits
+    }
+
+    /// Tag every stored session that gained messages since it was last
+    /// tagged. Sessions not in memory are loaded for it and dropped again.
+    /// Returns how many were tagged.
+    pub fn tag_sessions(&mut self) -> Result<usize, SessionError> {
+        let mut tagged = 0;
+        for (key, _) in self.list_sessions() {
+            let cached = self.cache.contains_key(&key);
+            if !cached {
+                let Some(session) = self.load(&key) else {
+                    continue;
+                };
+                self.cache.insert(key.clone(), session);
+            }
+            if self.get_or_create(&key).retag() {
+                self.save(&key)?;
+                tagged += 1;
+            }
+            if !cached {
+                self.cache.remove(&key);
+            }
+        }
+        Ok(tagged)
+    }
+
+    /// Delete a session. Its forks still name it as their parent, so
+    /// going back from one starts it afresh; see [`purge`](Self::purge).
     pub fn delete(&mut self, key: &str) -> bool {
         self.cache.remove(key);
         let path = self.session_path(key);
@@ -218,11 +571,22 @@ impl SessionManager {
         }
     }
 
-    /// List all sessions.
+    /// Delete a session for good: its forks no longer have a parent.
+    /// Returns whether it existed.
+    pub fn purge(&mut self, key: &str) -> Result<bool, SessionError> {
+        let deleted = self.delete(key);
+        self.relink(key, None)?;
+        Ok(deleted)
+    }
+
+    /// List all sessions, including those in chat workspaces.
     pub fn list_sessions(&self) -> Vec<(String, String)> {
         let mut sessions = Vec::new();
 
-        if let Ok(entries) = std::fs::read_dir(&self.sessions_dir) {
+        let mut dirs: Vec<&PathBuf> = std::iter::once(&self.sessions_dir).chain(self.chat_dirs.values()).collect();
+        dirs.sort();
+        dirs.dedup();
+        for entries in dirs.into_iter().filter_map(|dir| std::fs::read_dir(dir).ok()) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if path.extension().is_some_and(|e| e == "jsonl") {
@@ -251,9 +615,26 @@ impl SessionManager {
 
     // ── Private helpers ─────────────────────────────────────────────
 
+    /// Point forks whose parent is `old` at `new_key`, or unlink them.
+    fn relink(&mut self, old: &str, new_key: Option<&str>) -> Result<(), SessionError> {
+        for (key, _) in self.list_sessions() {
+            let session = self.get_or_create(&key);
+            if session.parent.as_deref() == Some(old) {
+                session.parent = new_key.map(String::from);
+                self.save(&key)?;
+            }
+        }
+        Ok(())
+    }
+
     fn session_path(&self, key: &str) -> PathBuf {
         let safe_name = key.replace([':', '/'], "_");
-        self.sessions_dir.join(format!("{}.jsonl", safe_name))
+        let (channel, chat_id) = chat_of(key);
+        let dir = self
+            .chat_dirs
+            .get(&format!("{}:{}", channel, chat_id))
+            .unwrap_or(&self.sessions_dir);
+        dir.join(format!("{}.jsonl", safe_name))
     }
 
     fn load(&self, key: &str) -> Option<Session> {
@@ -266,6 +647,16 @@ impl SessionManager {
         let mut messages = Vec::new();
         let mut created_at = String::new();
         let mut updated_at = String::new();
+        let mut parent = None;
+        let mut pending_reply = None;
+        let mut model = None;
+        let mut compressed_at = None;
+        let mut context_footer = false;
+        let mut summary = None;
+        let mut summarized_through = 0;
+        let mut topics = Vec::new();
+        let mut sentiment = None;
+        let mut tagged_through = 0;
 
         for line in content.lines() {
             let line = line.trim();
@@ -277,6 +668,19 @@ impl SessionManager {
                 if value.get("_type").and_then(|v| v.as_str()) == Some("metadata") {
                     created_at = value["created_at"].as_str().unwrap_or_default().to_string();
                     updated_at = value["updated_at"].as_str().unwrap_or_default().to_string();
+                    parent = value["parent"].as_str().map(String::from);
+                    pending_reply = value["pending_reply"].as_str().map(String::from);
+                    model = value["model"].as_str().map(String::from);
+                    compressed_at = value["compressed_at"].as_str().map(String::from);
+                    context_footer = value["context_footer"].as_bool().unwrap_or(false);
+                    summary = value["summary"].as_str().map(String::from);
+                    summarized_through = value["summarized_through"].as_u64().unwrap_or(0) as usize;
+                    topics = value["topics"]
+                        .as_array()
+                        .map(|t| t.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                        .unwrap_or_default();
+                    sentiment = value["sentiment"].as_str().map(String::from);
+                    tagged_through = value["tagged_through"].as_u64().unwrap_or(0) as usize;
                 } else if let Ok(msg) = serde_json::from_value::<SessionMessage>(value) {
                     messages.push(msg);
                 }
@@ -290,6 +694,16 @@ impl SessionManager {
             messages,
             created_at,
             updated_at,
+            parent,
+            pending_reply,
+            model,
+            compressed_at,
+            context_footer,
+            summary,
+            summarized_through,
+            topics,
+            sentiment,
+            tagged_through,
         })
     }
 }
@@ -320,4 +734,200 @@ mod tests {
         assert_eq!(history.len(), 5);
         assert_eq!(history[0].content_as_str().unwrap(), "Message 5");
     }
+
+    #[test]
+    fn test_fork_key() {
+        assert_eq!(fork_key("telegram:42", "bear-case").as_deref(), Some("telegram:42#bear-case"));
+        assert_eq!(fork_key("telegram:42#bear-case", "b2").as_deref(), Some("telegram:42#b2"));
+        assert!(fork_key("telegram:42", "").is_none());
+        assert!(fork_key("telegram:42", "../etc").is_none());
+    }
+
+    #[test]
+    fn test_chat_of() {
+        assert_eq!(chat_of("telegram:42"), ("telegram", "42"));
+        assert_eq!(chat_of("telegram:42#bear-case"), ("telegram", "42"));
+        assert_eq!(chat_of("telegram:42:7"), ("telegram", "42"));
+        assert_eq!(chat_of("telegram:42:7#b2"), ("telegram", "42"));
+        assert_eq!(chat_of("discord"), ("discord", "direct"));
+    }
+
+    #[test]
+    fn test_fork_copies_history_and_links_parent() {
+        let mut mgr = SessionManager {
+            sessions_dir: std::env::temp_dir().join(format!("CrabbyBot_test_fork_{}", std::process::id())),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        };
+        std::fs::create_dir_all(&mgr.sessions_dir).unwrap();
+
+        mgr.get_or_create("cli:main").add_message("user", "Should I buy?");
+        mgr.get_or_create("cli:main").model = Some("gpt-4o".into());
+        mgr.save("cli:main").unwrap();
+        assert!(matches!(mgr.fork("cli:nope", "cli:x"), Err(SessionError::NotFound(_))));
+
+        mgr.fork("cli:main", "cli:main#bear").unwrap();
+        mgr.get_or_create("cli:main#bear").add_message("user", "What if it dumps?");
+        assert_eq!(mgr.get_or_create("cli:main").messages.len(), 1);
+        assert!(matches!(
+            mgr.fork("cli:main", "cli:main#bear"),
+            Err(SessionError::AlreadyExists(_))
+        ));
+
+        // The link survives a reload from disk.
+        let reloaded = mgr.load("cli:main#bear").unwrap();
+        assert_eq!(reloaded.parent.as_deref(), Some("cli:main"));
+        assert_eq!(reloaded.messages.len(), 1);
+        assert_eq!(reloaded.model.as_deref(), Some("gpt-4o"));
+
+        let _ = std::fs::remove_dir_all(&mgr.sessions_dir);
+    }
+
+    #[test]
+    fn test_rename_merge_and_purge() {
+        let mut mgr = SessionManager {
+            sessions_dir: std::env::temp_dir().join(format!("CrabbyBot_test_bulk_{}", std::process::id())),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        };
+        std::fs::create_dir_all(&mgr.sessions_dir).unwrap();
+
+        mgr.get_or_create("cli:old").add_message("user", "first");
+        mgr.save("cli:old").unwrap();
+        mgr.fork("cli:old", "cli:old#what-if").unwrap();
+        mgr.get_or_create("cli:later").add_message("user", "second");
+        mgr.get_or_create("cli:later").summary = Some("stale".into());
+        mgr.save("cli:later").unwrap();
+
+        mgr.rename("cli:old", "cli:trading").unwrap();
+        assert!(!mgr.exists("cli:old"));
+        assert!(matches!(mgr.rename("cli:old", "cli:x"), Err(SessionError::NotFound(_))));
+        assert!(matches!(mgr.rename("cli:later", "cli:trading"), Err(SessionError::AlreadyExists(_))));
+        assert_eq!(mgr.load("cli:old#what-if").unwrap().parent.as_deref(), Some("cli:trading"));
+
+        // Merged into the later session, the earlier history still comes first.
+        let merged = mgr.merge("cli:later", "cli:trading").unwrap();
+        let texts: Vec<_> = merged.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(texts, ["first", "second"]);
+        assert!(merged.summary.is_none());
+        assert!(!mgr.exists("cli:trading"));
+        assert_eq!(mgr.load("cli:later").unwrap().messages.len(), 2);
+        assert_eq!(mgr.load("cli:old#what-if").unwrap().parent.as_deref(), Some("cli:later"));
+
+        assert!(mgr.not_updated_for(chrono::Duration::days(1)).is_empty());
+        assert_eq!(mgr.not_updated_for(chrono::Duration::zero()).len(), 2);
+
+        // Purging the parent leaves the fork without one.
+        assert!(mgr.purge("cli:later").unwrap());
+        assert_eq!(mgr.load("cli:old#what-if").unwrap().parent, None);
+
+        let _ = std::fs::remove_dir_all(&mgr.sessions_dir);
+    }
+
+    #[test]
+    fn test_merge_orders_by_instant_not_text() {
+        let mut mgr = SessionManager {
+            sessions_dir: std::env::temp_dir().join(format!("CrabbyBot_test_merge_tz_{}", std::process::id())),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        };
+        std::fs::create_dir_all(&mgr.sessions_dir).unwrap();
+        for (key, text, at) in [
+            ("cli:utc", "later", "2026-01-01T09:00:00+00:00"),
+            ("cli:berlin", "earlier", "2026-01-01T10:00:00+02:00"),
+        ] {
+            let session = mgr.get_or_create(key);
+            session.add_message("user", text);
+            session.messages[0].timestamp = at.into();
+            session.created_at = at.into();
+            session.updated_at = at.into();
+            mgr.save(key).unwrap();
+        }
+
+        let merged = mgr.merge("cli:utc", "cli:berlin").unwrap();
+        let texts: Vec<_> = merged.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(texts, ["earlier", "later"]);
+        assert_eq!(merged.created_at, "2026-01-01T10:00:00+02:00");
+        assert_eq!(merged.updated_at, "2026-01-01T09:00:00+00:00");
+        assert_eq!(pick_time("garbage".into(), "2026-01-01T09:00:00Z".into(), false), "2026-01-01T09:00:00Z");
+
+        let _ = std::fs::remove_dir_all(&mgr.sessions_dir);
+    }
+
+    #[test]
+    fn test_search_finds_snippets_across_sessions() {
+        let mut mgr = SessionManager {
+            sessions_dir: std::env::temp_dir().join(format!("CrabbyBot_test_search_{}", std::process::id())),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        };
+        std::fs::create_dir_all(&mgr.sessions_dir).unwrap();
+
+        let long = format!("{} Then we discussed the BONK airdrop\nand its vesting. {}", "x".repeat(100), "y".repeat(100));
+        mgr.get_or_create("cli:a").add_message("user", &long);
+        mgr.get_or_create("cli:a").add_message("tool", "bonk price: 0.00002");
+        mgr.save("cli:a").unwrap();
+        mgr.get_or_create("telegram:7").add_message("assistant", "Bonk is up 4% today.");
+        mgr.save("telegram:7").unwrap();
+
+        // Sessions on disk are searched too, not only the cached ones.
+        let fresh = SessionManager {
+            sessions_dir: mgr.sessions_dir.clone(),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        };
+        let hits = fresh.search("bonk", None, 10);
+        assert_eq!(hits.len(), 2);
+        let a = hits.iter().find(|h| h.key == "cli:a").unwrap();
+        assert_eq!(a.role, "user");
+        assert!(a.snippet.starts_with('…') && a.snippet.ends_with('…'), "{}", a.snippet);
+        assert!(a.snippet.contains("the BONK airdrop and its vesting."), "{}", a.snippet);
+        let tg = hits.iter().find(|h| h.key == "telegram:7").unwrap();
+        assert_eq!(tg.snippet, "Bonk is up 4% today.");
+        assert!(tg.timestamp.parse::<chrono::DateTime<chrono::FixedOffset>>().is_ok());
+
+        assert_eq!(fresh.search("BONK", None, 1).len(), 1);
+        assert!(fresh.search("  ", None, 10).is_empty());
+        assert!(fresh.search("solana", None, 10).is_empty());
+
+        // Tagging is stored with the session and narrows the search.
+        let mut fresh = fresh;
+        fresh.get_or_create("cli:a").add_message("user", "Which token and wallet got the airdrop?");
+        fresh.save("cli:a").unwrap();
+        assert_eq!(fresh.tag_sessions().unwrap(), 2);
+        assert_eq!(fresh.tag_sessions().unwrap(), 0);
+        let reloaded = fresh.load("cli:a").unwrap();
+        assert_eq!(reloaded.topics, ["crypto"]);
+        assert_eq!(reloaded.sentiment.as_deref(), Some("neutral"));
+        assert_eq!(reloaded.tagged_through, 3);
+        let hits = fresh.search("bonk", Some("Crypto"), 10);
+        assert_eq!(hits.iter().map(|h| h.key.as_str()).collect::<Vec<_>>(), ["cli:a"]);
+        assert!(!fresh.cache.contains_key("telegram:7"));
+
+        let _ = std::fs::remove_dir_all(&mgr.sessions_dir);
+    }
+
+    #[test]
+    fn test_chat_dirs_keep_sessions_apart() {
+        let root = std::env::temp_dir().join(format!("CrabbyBot_test_chat_dirs_{}", std::process::id()));
+        let work = root.join("work").join("sessions");
+        let mut mgr = SessionManager {
+            sessions_dir: root.join("default"),
+            chat_dirs: HashMap::new(),
+            cache: HashMap::new(),
+        }
+        .with_chat_workspaces([("telegram:7".to_string(), root.join("work"))]);
+
+        for key in ["telegram:7", "telegram:7#plan", "telegram:7:99", "telegram:8"] {
+            mgr.get_or_create(key).add_message("user", "hi");
+            mgr.save(key).unwrap();
+        }
+        assert!(work.join("telegram_7.jsonl").exists());
+        assert!(work.join("telegram_7#plan.jsonl").exists());
+        assert!(work.join("telegram_7_99.jsonl").exists());
+        assert!(root.join("default").join("telegram_8.jsonl").exists());
+        assert_eq!(mgr.list_sessions().len(), 4);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
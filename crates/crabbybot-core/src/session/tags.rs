@@ -0,0 +1,138 @@
+//! Keyword tagging of sessions with topics and sentiment.
+//!
+//! Cheap enough to run over every stored session: no model is involved,
+//! only word lists. A topic is given when at least [`MIN_HITS`] of its
+//! keywords occur in the user and assistant messages; the three strongest
+//! are kept. The sentiment is read from the user's messages only, so the
+//! assistant's politeness doesn't count.
+
+use std::collections::HashMap;
+
+use super::SessionMessage;
+
+/// Keyword occurrences a topic needs.
+const MIN_HITS: usize = 2;
+
+/// Topics kept per session.
+const MAX_TOPICS: usize = 3;
+
+const TOPICS: &[(&str, &[&str])] = &[
+    (
+        "trading",
+        &[
+            "buy", "sell", "trade", "trading", "order", "orders", "position", "positions", "long", "short",
+            "entry", "exit", "leverage", "swap", "profit", "loss", "pnl", "portfolio", "stop-loss",
+        ],
+    ),
+    (
+        "crypto",
+        &[
+            "solana", "sol", "token", "tokens", "coin", "coins", "mint", "wallet", "airdrop", "rug",
+            "rugcheck", "memecoin", "pump", "jupiter", "defi", "nft", "nfts", "ethereum", "eth", "btc",
+            "bitcoin", "usdc",
+        ],
+    ),
+    (
+        "polymarket",
+        &["polymarket", "prediction", "predictions", "odds", "outcome", "bet", "bets", "betting", "election"],
+    ),
+    (
+        "sports",
+        &["match", "game", "score", "team", "league", "football", "soccer", "nba", "nfl", "goal", "season"],
+    ),
+    ("news", &["news", "headline", "headlines", "article", "articles", "announced", "announcement"]),
+    (
+        "coding",
+        &["code", "rust", "python", "bug", "compile", "function", "script", "api", "deploy", "repo", "git"],
+    ),
+    (
+        "research",
+        &["research", "analysis", "analyze", "compare", "comparison", "summary", "summarize", "explain"],
+    ),
+    ("planning", &["remind", "reminder", "schedule", "meeting", "todo", "plan", "deadline", "tomorrow"]),
+];
+
+const POSITIVE: &[&str] = &[
+    "thanks", "thank", "great", "awesome", "nice", "perfect", "love", "good", "excellent", "cool",
+    "helpful", "amazing", "brilliant",
+];
+
+const NEGATIVE: &[&str] = &[
+    "wrong", "bad", "useless", "broken", "terrible", "hate", "annoying", "confused", "frustrated",
+    "frustrating", "stupid", "wtf", "awful", "worse", "worst",
+];
+
+/// Topics and sentiment of a conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    /// Strongest first.
+    pub topics: Vec<String>,
+    /// `positive`, `negative` or `neutral`; `None` without user messages.
+    pub sentiment: Option<String>,
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Tag a conversation from its messages.
+pub fn tag(messages: &[SessionMessage]) -> Tags {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut mood = 0i64;
+    let mut from_user = false;
+    for msg in messages.iter().filter(|m| m.role == "user" || m.role == "assistant") {
+        let Some(text) = msg.content.as_deref() else {
+            continue;
+        };
+        let user = msg.role == "user";
+        from_user |= user;
+        for word in words(text) {
+            if user {
+                mood += i64::from(POSITIVE.contains(&word.as_str())) - i64::from(NEGATIVE.contains(&word.as_str()));
+            }
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<(&str, usize)> = TOPICS
+        .iter()
+        .map(|(topic, keywords)| (*topic, keywords.iter().filter_map(|k| counts.get(*k)).sum()))
+        .filter(|(_, hits)| *hits >= MIN_HITS)
+        .collect();
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let sentiment = from_user.then_some(match mood.signum() {
+        1 => "positive",
+        -1 => "negative",
+        _ => "neutral",
+    });
+    Tags {
+        topics: scored.into_iter().take(MAX_TOPICS).map(|(t, _)| t.to_string()).collect(),
+        sentiment: sentiment.map(String::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+
+    #[test]
+    fn test_tag_topics_and_sentiment() {
+        let mut session = Session::new("cli:t");
+        session.add_message("user", "Should I buy SOL or wait for the airdrop?");
+        session.add_message("assistant", "Your wallet holds 3 SOL. A limit order to buy below 140 keeps the position small.");
+        session.add_message("user", "Great, thanks!");
+        let tags = tag(&session.messages);
+        assert_eq!(tags.topics, ["crypto", "trading"]);
+        assert_eq!(tags.sentiment.as_deref(), Some("positive"));
+
+        session.add_message("user", "That price is wrong, this is useless and broken");
+        assert_eq!(tag(&session.messages).sentiment.as_deref(), Some("negative"));
+
+        let mut quiet = Session::new("cli:q");
+        quiet.add_message("assistant", "Hello!");
+        assert_eq!(tag(&quiet.messages), Tags::default());
+    }
+}
@@ -0,0 +1,559 @@
+//! Encrypted sync of workspace state with remote storage.
+//!
+//! The files under `sync.paths` (memory, skills, the decision journal and
+//! the knowledge graph by default) are mirrored to an S3-compatible bucket
+//! or a WebDAV folder, so the desktop and the VPS running bot mode share one
+//! assistant. Everything is encrypted on this machine with AES-256-GCM under
+//! a key derived from `sync.encryptionPassword` (Argon2id); the storage only
+//! ever sees random object names and ciphertext.
+//!
+//! Remote layout under `sync.prefix`:
+//!
+//! - `salt`: the Argon2id salt, the only plaintext object
+//! - `manifest`: encrypted list of synced files with their content hashes
+//! - `objects/<id>`: one encrypted object per file version
+//!
+//! Each run compares the local files, the manifest, and the hashes both
+//! sides agreed on last time (`.sync/state.json` in the workspace). A file
+//! changed on one side only is copied to the other, deletions included.
+//! When both sides changed it, the local copy wins and the remote one is
+//! kept next to it as `<name>.conflict-<timestamp>`. The manifest is written
+//! with `If-Match`, so two machines syncing at once can't overwrite each
+//! other's changes: the loser retries on its next run.
+
+pub mod s3;
+pub mod webdav;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::bus::events::OutboundMessage;
+use crate::bus::MessageBus;
+use crate::config::SyncConfig;
+
+const SALT: &str = "salt";
+const MANIFEST: &str = "manifest";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Marks copies of the remote side of a conflict; never synced themselves.
+const CONFLICT_MARK: &str = ".conflict-";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("remote storage error: {0}")]
+    Remote(String),
+
+    #[error("wrong encryption password, or the remote data is corrupted")]
+    Decrypt,
+
+    #[error("the remote changed during the sync; retrying on the next run")]
+    Conflict,
+
+    #[error("sync is misconfigured: {0}")]
+    Config(String),
+}
+
+/// Condition a write must meet on the remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition<'a> {
+    None,
+    /// The object must not exist yet.
+    Absent,
+    /// The object must still have this ETag.
+    Matches(&'a str),
+}
+
+/// Object storage the sync writes to. Keys are relative to the configured
+/// prefix and use `/` as separator.
+#[async_trait]
+pub trait Remote: Send + Sync {
+    /// The object and its ETag, or `None` when it doesn't exist.
+    async fn get(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>, SyncError>;
+
+    /// Store `data` under `key`. A failed precondition is
+    /// [`SyncError::Conflict`].
+    async fn put(&self, key: &str, data: Vec<u8>, condition: Precondition<'_>) -> Result<(), SyncError>;
+
+    /// Remove `key`; a missing object is not an error.
+    async fn delete(&self, key: &str) -> Result<(), SyncError>;
+}
+
+/// Build the remote `config.backend` names.
+pub fn remote_from_config(config: &SyncConfig, client: reqwest::Client) -> Result<Box<dyn Remote>, SyncError> {
+    let secret = |value: &str| {
+        crate::vault::decrypt(value).map_err(|e| SyncError::Config(format!("cannot decrypt a sync secret: {}", e)))
+    };
+    match config.backend.as_str() {
+        "s3" => Ok(Box::new(s3::S3Remote::new(
+            client,
+            &config.url,
+            &config.bucket,
+            &config.region,
+            &config.prefix,
+            &config.access_key_id,
+            &secret(&config.secret)?,
+        )?)),
+        "webdav" => Ok(Box::new(webdav::WebDavRemote::new(
+            client,
+            &config.url,
+            &config.prefix,
+            &config.username,
+            &secret(&config.password)?,
+        )?)),
+        other => Err(SyncError::Config(format!("unknown backend '{}'", other))),
+    }
+}
+
+/// What one run changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    /// Deleted here because they were deleted on the remote.
+    pub deleted_local: Vec<String>,
+    /// Deleted on the remote because they were deleted here.
+    pub deleted_remote: Vec<String>,
+    /// Files both sides changed, by the path of the saved remote copy.
+    pub conflicts: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// One line for logs and the CLI.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} uploaded, {} downloaded, {} deleted here, {} deleted remotely, {} conflicts",
+            self.uploaded.len(),
+            self.downloaded.len(),
+            self.deleted_local.len(),
+            self.deleted_remote.len(),
+            self.conflicts.len()
+        )
+    }
+}
+
+/// The remote's list of files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Manifest {
+    revision: u64,
+    files: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// SHA-256 of the plaintext.
+    hash: String,
+    object: String,
+    updated_by: String,
+    updated_at: DateTime<Utc>,
+}
+
+/// Hashes both sides agreed on after the last successful run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    synced: BTreeMap<String, String>,
+}
+
+/// Syncs one workspace with one remote.
+pub struct SyncEngine {
+    remote: Box<dyn Remote>,
+    workspace: PathBuf,
+    paths: Vec<String>,
+    password: String,
+    device: String,
+    key: Option<[u8; 32]>,
+}
+
+impl SyncEngine {
+    pub fn new(remote: Box<dyn Remote>, workspace: &Path, paths: Vec<String>, password: impl Into<String>) -> Self {
+        let device = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_else(|_| "unknown".into());
+        Self {
+            remote,
+            workspace: workspace.to_path_buf(),
+            paths,
+            password: password.into(),
+            device,
+            key: None,
+        }
+    }
+
+    /// Build the engine `config` describes.
+    pub fn from_config(config: &SyncConfig, workspace: &Path, client: reqwest::Client) -> Result<Self, SyncError> {
+        let password = crate::vault::decrypt(&config.encryption_password)
+            .map_err(|e| SyncError::Config(format!("cannot decrypt sync.encryptionPassword: {}", e)))?;
+        if password.is_empty() {
+            return Err(SyncError::Config("sync.encryptionPassword is empty".into()));
+        }
+        let mut engine = Self::new(remote_from_config(config, client)?, workspace, config.paths.clone(), password);
+        if !config.device_name.is_empty() {
+            engine.device = config.device_name.clone();
+        }
+        Ok(engine)
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.workspace.join(".sync").join("state.json")
+    }
+
+    /// The encryption key, creating the remote salt on the first run.
+    async fn key(&mut self) -> Result<[u8; 32], SyncError> {
+        if let Some(key) = self.key {
+            return Ok(key);
+        }
+        let salt = match self.remote.get(SALT).await? {
+            Some((salt, _)) if salt.len() == SALT_LEN => salt,
+            Some(_) => return Err(SyncError::Decrypt),
+            None => {
+                let mut salt = vec![0u8; SALT_LEN];
+                rand::thread_rng().fill_bytes(&mut salt);
+                match self.remote.put(SALT, salt.clone(), Precondition::Absent).await {
+                    Ok(()) => salt,
+                    // Another machine created it first.
+                    Err(SyncError::Conflict) => self.remote.get(SALT).await?.ok_or(SyncError::Conflict)?.0,
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        let key = crate::backup::derive_key(&self.password, &salt).map_err(|_| SyncError::Decrypt)?;
+        self.key = Some(key);
+        Ok(key)
+    }
+
+    /// Every synced file under the workspace, by relative path, with its hash.
+    fn scan(&self) -> BTreeMap<String, String> {
+        let mut files = BTreeMap::new();
+        for root in &self.paths {
+            for (rel, path) in crate::backup::walk(&self.workspace.join(root)) {
+                let name = if rel.is_empty() { root.clone() } else { format!("{}/{}", root.trim_end_matches('/'), rel) };
+                if name.contains(CONFLICT_MARK) {
+                    continue;
+                }
+                if let Ok(data) = std::fs::read(&path) {
+                    files.insert(name, hash(&data));
+                }
+            }
+        }
+        files
+    }
+
+    /// Bring the workspace and the remote in line.
+    pub async fn run(&mut self) -> Result<SyncReport, SyncError> {
+        let key = self.key().await?;
+        let (mut manifest, etag) = match self.remote.get(MANIFEST).await? {
+            Some((data, etag)) => {
+                let plain = decrypt(&key, &data)?;
+                (serde_json::from_slice::<Manifest>(&plain).map_err(|_| SyncError::Decrypt)?, etag)
+            }
+            None => (Manifest::default(), None),
+        };
+        let state: State = std::fs::read(self.state_path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        let local = self.scan();
+
+        let names: BTreeSet<String> = local.keys().chain(manifest.files.keys()).chain(state.synced.keys()).cloned().collect();
+        let mut report = SyncReport::default();
+        let mut synced = BTreeMap::new();
+        let mut uploads = Vec::new();
+        let mut replaced = Vec::new();
+        let now = Utc::now();
+
+        for name in &names {
+            let here = local.get(name);
+            let there = manifest.files.get(name).map(|e| &e.hash);
+            let base = state.synced.get(name);
+            let path = self.workspace.join(name);
+            let push = match (here, there) {
+                _ if here == there => {
+                    if let Some(h) = here {
+                        synced.insert(name.clone(), h.clone());
+                    }
+                    continue;
+                }
+                _ if there == base => true,
+                _ if here == base => false,
+                // Changed on both sides: keep this side, save the other.
+                (Some(_), Some(_)) => {
+                    let copy = format!("{}{}{}", name, CONFLICT_MARK, now.format("%Y%m%dT%H%M%SZ"));
+                    let data = self.fetch(&key, &manifest.files[name].object).await?;
+                    write_file(&self.workspace.join(&copy), &data)?;
+                    warn!(file = %name, remote_copy = %copy, "Sync conflict: both sides changed the file");
+                    report.conflicts.push(copy);
+                    true
+                }
+                // Edited on one side, deleted on the other: the edit survives.
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+
+            match (push, here, manifest.files.get(name)) {
+                (true, Some(h), _) => {
+                    let object = format!("objects/{}", uuid::Uuid::new_v4().simple());
+                    let data = std::fs::read(&path)?;
+                    self.remote.put(&object, encrypt(&key, &data)?, Precondition::None).await?;
+                    uploads.push(object.clone());
+                    let entry = Entry {
+                        hash: h.clone(),
+                        object,
+                        updated_by: self.device.clone(),
+                        updated_at: now,
+                    };
+                    if let Some(old) = manifest.files.insert(name.clone(), entry) {
+                        replaced.push(old.object);
+                    }
+                    synced.insert(name.clone(), h.clone());
+                    report.uploaded.push(name.clone());
+                }
+                (true, None, _) => {
+                    if let Some(old) = manifest.files.remove(name) {
+                        replaced.push(old.object);
+                    }
+                    report.deleted_remote.push(name.clone());
+                }
+                (false, _, Some(entry)) => {
+                    let data = self.fetch(&key, &entry.object).await?;
+                    write_file(&path, &data)?;
+                    synced.insert(name.clone(), entry.hash.clone());
+                    report.downloaded.push(name.clone());
+                }
+                (false, _, None) => {
+                    if path.exists() {
+                        std::fs::remove_file(&path)?;
+                    }
+                    report.deleted_local.push(name.clone());
+                }
+            }
+        }
+
+        if !report.uploaded.is_empty() || !report.deleted_remote.is_empty() {
+            manifest.revision += 1;
+            let data = encrypt(&key, &serde_json::to_vec(&manifest).unwrap_or_default())?;
+            let condition = match etag.as_deref() {
+                Some(tag) => Precondition::Matches(tag),
+                None => Precondition::Absent,
+            };
+            if let Err(e) = self.remote.put(MANIFEST, data, condition).await {
+                for object in &uploads {
+                    let _ = self.remote.delete(object).await;
+                }
+                return Err(e);
+            }
+            for object in &replaced {
+                if let Err(e) = self.remote.delete(object).await {
+                    warn!(object = %object, "Could not remove a replaced sync object: {}", e);
+                }
+            }
+        }
+
+        write_file(
+            &self.state_path(),
+            &serde_json::to_vec_pretty(&State { synced }).unwrap_or_default(),
+        )?;
+        Ok(report)
+    }
+
+    async fn fetch(&self, key: &[u8; 32], object: &str) -> Result<Vec<u8>, SyncError> {
+        let (data, _) = self
+            .remote
+            .get(object)
+            .await?
+            .ok_or_else(|| SyncError::Remote(format!("object {} is missing", object)))?;
+        decrypt(key, &data)
+    }
+}
+
+/// Sync every `interval` until cancelled, telling the admin chat about
+/// conflicts.
+pub async fn run_periodic(
+    mut engine: SyncEngine,
+    interval: Duration,
+    bus: Arc<MessageBus>,
+    admin: (String, String),
+    cancel: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+        match engine.run().await {
+            Ok(report) if report.is_empty() => {}
+            Ok(report) => {
+                info!("Workspace sync: {}", report.summary());
+                if !report.conflicts.is_empty() && !admin.1.is_empty() {
+                    let text = format!(
+                        "🔀 *Sync conflict*\n\nThese files changed here and on another machine. \
+                         This machine's version was kept; the other one was saved next to it:\n{}",
+                        report.conflicts.iter().map(|c| format!("• `{}`", c)).collect::<Vec<_>>().join("\n")
+                    );
+                    bus.publish_outbound(OutboundMessage::reply(&admin.0, &admin.1, text)).await;
+                }
+            }
+            Err(SyncError::Conflict) => info!("Workspace sync raced another machine; retrying on the next run"),
+            Err(e) => warn!("Workspace sync failed: {}", e),
+        }
+    }
+}
+
+fn hash(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, SyncError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| SyncError::Decrypt)?;
+    let ciphertext = cipher.encrypt(&Nonce::from(nonce), plaintext).map_err(|_| SyncError::Decrypt)?;
+    Ok([&nonce[..], &ciphertext].concat())
+}
+
+fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, SyncError> {
+    if data.len() < NONCE_LEN {
+        return Err(SyncError::Decrypt);
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| SyncError::Decrypt)?;
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| SyncError::Decrypt)?;
+    cipher.decrypt(&Nonce::from(nonce), ciphertext).map_err(|_| SyncError::Decrypt)
+}
+
+fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Object data and its ETag counter, by key.
+    type Objects = Arc<Mutex<HashMap<String, (Vec<u8>, u64)>>>;
+
+    /// Storage in memory, with a counter as ETag. When the flag is set, the
+    /// next manifest write finds the manifest changed by someone else.
+    #[derive(Clone, Default)]
+    struct MemoryRemote(Objects, Arc<std::sync::atomic::AtomicBool>);
+
+    #[async_trait]
+    impl Remote for MemoryRemote {
+        async fn get(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>, SyncError> {
+            let objects = self.0.lock().unwrap();
+            Ok(objects.get(key).map(|(data, tag)| (data.clone(), Some(tag.to_string()))))
+        }
+
+        async fn put(&self, key: &str, data: Vec<u8>, condition: Precondition<'_>) -> Result<(), SyncError> {
+            let mut objects = self.0.lock().unwrap();
+            if key == MANIFEST && self.1.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                if let Some((_, tag)) = objects.get_mut(key) {
+                    *tag += 1;
+                }
+            }
+            let current = objects.get(key).map(|(_, tag)| tag.to_string());
+            match (condition, current.as_deref()) {
+                (Precondition::Absent, Some(_)) => return Err(SyncError::Conflict),
+                (Precondition::Matches(want), have) if have != Some(want) => return Err(SyncError::Conflict),
+                _ => {}
+            }
+            let tag = objects.get(key).map_or(1, |(_, tag)| tag + 1);
+            objects.insert(key.to_string(), (data, tag));
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), SyncError> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    fn machine(remote: &MemoryRemote, name: &str) -> (SyncEngine, PathBuf) {
+        let ws = std::env::temp_dir().join(format!("CrabbyBot_test_sync_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&ws);
+        std::fs::create_dir_all(ws.join("memory")).unwrap();
+        let engine = SyncEngine::new(Box::new(remote.clone()), &ws, vec!["memory".into()], "correct horse");
+        (engine, ws)
+    }
+
+    #[tokio::test]
+    async fn test_sync_between_machines() {
+        let remote = MemoryRemote::default();
+        let (mut desktop, desk) = machine(&remote, "desktop");
+        let (mut vps, server) = machine(&remote, "vps");
+
+        std::fs::write(desk.join("memory/MEMORY.md"), "Prefers concise answers").unwrap();
+        let report = desktop.run().await.unwrap();
+        assert_eq!(report.uploaded, ["memory/MEMORY.md"]);
+        // Nothing readable reaches the storage.
+        for (data, _) in remote.0.lock().unwrap().values() {
+            assert!(!String::from_utf8_lossy(data).contains("concise"));
+        }
+
+        let report = vps.run().await.unwrap();
+        assert_eq!(report.downloaded, ["memory/MEMORY.md"]);
+        assert_eq!(std::fs::read_to_string(server.join("memory/MEMORY.md")).unwrap(), "Prefers concise answers");
+        assert!(vps.run().await.unwrap().is_empty());
+
+        // Both change the file: the local side wins, the remote copy is kept.
+        std::fs::write(desk.join("memory/MEMORY.md"), "desktop edit").unwrap();
+        desktop.run().await.unwrap();
+        std::fs::write(server.join("memory/MEMORY.md"), "vps edit").unwrap();
+        let report = vps.run().await.unwrap();
+        assert_eq!(report.uploaded, ["memory/MEMORY.md"]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(std::fs::read_to_string(server.join(&report.conflicts[0])).unwrap(), "desktop edit");
+
+        // Deletions travel too.
+        std::fs::remove_file(server.join("memory/MEMORY.md")).unwrap();
+        assert_eq!(vps.run().await.unwrap().deleted_remote, ["memory/MEMORY.md"]);
+        assert_eq!(desktop.run().await.unwrap().deleted_local, ["memory/MEMORY.md"]);
+        assert!(!desk.join("memory/MEMORY.md").exists());
+
+        let mut wrong = SyncEngine::new(Box::new(remote.clone()), &desk, vec!["memory".into()], "wrong");
+        assert!(matches!(wrong.run().await, Err(SyncError::Decrypt)));
+
+        let _ = std::fs::remove_dir_all(&desk);
+        let _ = std::fs::remove_dir_all(&server);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_race_is_detected() {
+        let remote = MemoryRemote::default();
+        let (mut engine, ws) = machine(&remote, "race");
+        std::fs::write(ws.join("memory/MEMORY.md"), "first").unwrap();
+        engine.run().await.unwrap();
+
+        // Another machine commits between this run's read and write.
+        std::fs::write(ws.join("memory/a.md"), "a").unwrap();
+        remote.1.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert!(matches!(engine.run().await, Err(SyncError::Conflict)));
+        assert_eq!(remote.0.lock().unwrap().keys().filter(|k| k.starts_with("objects/")).count(), 1);
+
+        assert_eq!(engine.run().await.unwrap().uploaded, ["memory/a.md"]);
+        let _ = std::fs::remove_dir_all(&ws);
+    }
+}
@@ -0,0 +1,182 @@
+//! S3-compatible storage (AWS S3, MinIO, Cloudflare R2, Backblaze B2, …).
+//!
+//! Requests use path-style URLs (`<url>/<bucket>/<prefix>/<key>`) and are
+//! signed with AWS Signature Version 4. Conditional writes rely on
+//! `If-Match` / `If-None-Match`, which S3 and most compatible stores honour.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use super::{Precondition, Remote, SyncError};
+
+pub struct S3Remote {
+    client: reqwest::Client,
+    base: Url,
+    region: String,
+    access_key_id: String,
+    secret: String,
+}
+
+impl S3Remote {
+    pub fn new(
+        client: reqwest::Client,
+        url: &str,
+        bucket: &str,
+        region: &str,
+        prefix: &str,
+        access_key_id: &str,
+        secret: &str,
+    ) -> Result<Self, SyncError> {
+        let root = format!(
+            "{}/{}/{}",
+            url.trim_end_matches('/'),
+            bucket.trim_matches('/'),
+            prefix.trim_matches('/')
+        );
+        let base = Url::parse(&format!("{}/", root.trim_end_matches('/')))
+            .map_err(|e| SyncError::Config(format!("sync.url: {}", e)))?;
+        Ok(Self {
+            client,
+            base,
+            region: if region.is_empty() { "us-east-1".into() } else { region.to_string() },
+            access_key_id: access_key_id.to_string(),
+            secret: secret.to_string(),
+        })
+    }
+
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>, condition: Precondition<'_>) -> Result<reqwest::Response, SyncError> {
+        let url = self.base.join(key).map_err(|e| SyncError::Remote(e.to_string()))?;
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+        let authorization = self.authorization(method.as_str(), &url, &amz_date, &payload_hash);
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("authorization", authorization);
+        request = match condition {
+            Precondition::None => request,
+            Precondition::Absent => request.header("if-none-match", "*"),
+            Precondition::Matches(tag) => request.header("if-match", tag),
+        };
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+        request.send().await.map_err(|e| SyncError::Remote(e.to_string()))
+    }
+
+    /// The SigV4 `Authorization` header for a request without a query string.
+    fn authorization(&self, method: &str, url: &Url, amz_date: &str, payload_hash: &str) -> String {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret, date, &self.region, "s3");
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            scope,
+            signed_headers,
+            hex(&hmac(&key, string_to_sign.as_bytes()))
+        )
+    }
+}
+
+#[async_trait]
+impl Remote for S3Remote {
+    async fn get(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>, SyncError> {
+        let response = self.send(Method::GET, key, Vec::new(), Precondition::None).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let etag = response
+                    .headers()
+                    .get("etag")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                let data = response.bytes().await.map_err(|e| SyncError::Remote(e.to_string()))?;
+                Ok(Some((data.to_vec(), etag)))
+            }
+            status => Err(SyncError::Remote(format!("GET {}: {}", key, status))),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, condition: Precondition<'_>) -> Result<(), SyncError> {
+        let response = self.send(Method::PUT, key, data, condition).await?;
+        match response.status() {
+            // 409: a concurrent conditional write to the same key.
+            StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT => Err(SyncError::Conflict),
+            status if status.is_success() => Ok(()),
+            status => Err(SyncError::Remote(format!("PUT {}: {}", key, status))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SyncError> {
+        let response = self.send(Method::DELETE, key, Vec::new(), Precondition::None).await?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(SyncError::Remote(format!("DELETE {}: {}", key, status))),
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The SigV4 key for one day, region and service.
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let k_region = hmac(&k_date, region.as_bytes());
+    let k_service = hmac(&k_region, service.as_bytes());
+    hmac(&k_service, b"aws4_request")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // From the AWS documentation on deriving a SigV4 signing key.
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+
+        let remote = S3Remote::new(reqwest::Client::new(), "https://s3.example.com", "bucket", "", "/crabbybot/", "AK", "SK")
+            .unwrap();
+        assert_eq!(remote.base.join("objects/abc").unwrap().path(), "/bucket/crabbybot/objects/abc");
+        let auth = remote.authorization("GET", &remote.base.join("salt").unwrap(), "20260101T000000Z", "x");
+        assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=AK/20260101/us-east-1/s3/aws4_request, "));
+    }
+}
@@ -0,0 +1,127 @@
+//! WebDAV storage (Nextcloud, ownCloud, Apache `mod_dav`, rclone serve, …).
+//!
+//! Objects are plain files under `<url>/<prefix>/`, written with HTTP basic
+//! auth. Missing folders are created with `MKCOL` the first time a write
+//! needs them.
+
+use async_trait::async_trait;
+use reqwest::{Method, StatusCode, Url};
+
+use super::{Precondition, Remote, SyncError};
+
+pub struct WebDavRemote {
+    client: reqwest::Client,
+    base: Url,
+    username: String,
+    password: String,
+}
+
+impl WebDavRemote {
+    pub fn new(client: reqwest::Client, url: &str, prefix: &str, username: &str, password: &str) -> Result<Self, SyncError> {
+        let root = format!("{}/{}", url.trim_end_matches('/'), prefix.trim_matches('/'));
+        let base = Url::parse(&format!("{}/", root.trim_end_matches('/')))
+            .map_err(|e| SyncError::Config(format!("sync.url: {}", e)))?;
+        Ok(Self {
+            client,
+            base,
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    fn url(&self, key: &str) -> Result<Url, SyncError> {
+        self.base.join(key).map_err(|e| SyncError::Remote(e.to_string()))
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        if self.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    /// Create the prefix folder and the folders leading to `key`.
+    async fn create_folders(&self, key: &str) -> Result<(), SyncError> {
+        let mkcol = Method::from_bytes(b"MKCOL").expect("valid method");
+        let mut folders = vec![String::new()];
+        let parts: Vec<&str> = key.split('/').collect();
+        for i in 1..parts.len() {
+            folders.push(format!("{}/", parts[..i].join("/")));
+        }
+        for folder in folders {
+            let response = self
+                .request(mkcol.clone(), self.url(&folder)?)
+                .send()
+                .await
+                .map_err(|e| SyncError::Remote(e.to_string()))?;
+            // 405: the folder already exists.
+            if !response.status().is_success() && response.status() != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(SyncError::Remote(format!("MKCOL {}: {}", folder, response.status())));
+            }
+        }
+        Ok(())
+    }
+
+    async fn put_once(&self, key: &str, data: Vec<u8>, condition: Precondition<'_>) -> Result<StatusCode, SyncError> {
+        let request = self.request(Method::PUT, self.url(key)?).body(data);
+        let request = match condition {
+            Precondition::None => request,
+            Precondition::Absent => request.header("if-none-match", "*"),
+            Precondition::Matches(tag) => request.header("if-match", tag),
+        };
+        let response = request.send().await.map_err(|e| SyncError::Remote(e.to_string()))?;
+        Ok(response.status())
+    }
+}
+
+#[async_trait]
+impl Remote for WebDavRemote {
+    async fn get(&self, key: &str) -> Result<Option<(Vec<u8>, Option<String>)>, SyncError> {
+        let response = self
+            .request(Method::GET, self.url(key)?)
+            .send()
+            .await
+            .map_err(|e| SyncError::Remote(e.to_string()))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let etag = response
+                    .headers()
+                    .get("etag")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                let data = response.bytes().await.map_err(|e| SyncError::Remote(e.to_string()))?;
+                Ok(Some((data.to_vec(), etag)))
+            }
+            status => Err(SyncError::Remote(format!("GET {}: {}", key, status))),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, condition: Precondition<'_>) -> Result<(), SyncError> {
+        let mut status = self.put_once(key, data.clone(), condition).await?;
+        // 409: a parent folder is missing.
+        if status == StatusCode::CONFLICT {
+            self.create_folders(key).await?;
+            status = self.put_once(key, data, condition).await?;
+        }
+        match status {
+            StatusCode::PRECONDITION_FAILED => Err(SyncError::Conflict),
+            status if status.is_success() => Ok(()),
+            status => Err(SyncError::Remote(format!("PUT {}: {}", key, status))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SyncError> {
+        let response = self
+            .request(Method::DELETE, self.url(key)?)
+            .send()
+            .await
+            .map_err(|e| SyncError::Remote(e.to_string()))?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(SyncError::Remote(format!("DELETE {}: {}", key, status))),
+        }
+    }
+}
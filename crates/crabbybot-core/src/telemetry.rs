@@ -0,0 +1,252 @@
+//! Opt-in telemetry: a daily report of noised usage counts.
+//!
+//! Nothing is collected or sent unless `telemetry.enabled` is set. The bot
+//! then POSTs the previous UTC day's report to `telemetry.endpoint` once a
+//! day, so operators running several bots can watch the fleet's health in
+//! aggregate. The report is built from the workspace's tool statistics and
+//! token usage and holds counts only: calls and failed calls per tool, and
+//! the number of LLM requests. Messages, tool arguments and output, chat and
+//! user IDs and host details never leave the machine.
+//!
+//! Every count gets Laplace noise of scale `1 / telemetry.epsilon`
+//! (differential privacy with a single call as the unit), is rounded, and is
+//! left out when it comes to zero or less. The noise is drawn from a random
+//! secret kept in `telemetry/seed` and the day, so `crabbybot telemetry
+//! preview` shows exactly the report that will be sent. Sent reports are
+//! kept in `telemetry/sent/YYYY-MM-DD.json`.
+
+use chrono::{NaiveDate, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::TelemetryConfig;
+use crate::tools::stats::ToolStats;
+use crate::usage::UsageTracker;
+
+/// Version of the report layout.
+const SCHEMA: u32 = 1;
+
+/// How often the bot checks whether yesterday's report went out.
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("telemetry is off; set telemetry.enabled and telemetry.endpoint to opt in")]
+    Disabled,
+
+    #[error("cannot decrypt telemetry.token: {0}")]
+    Token(String),
+
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("the endpoint answered {0}")]
+    Rejected(reqwest::StatusCode),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// One day's report, as sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub schema: u32,
+    pub version: String,
+    pub day: NaiveDate,
+    pub epsilon: f64,
+    /// Calls per tool.
+    pub features: BTreeMap<String, u64>,
+    /// Failed calls per tool.
+    pub errors: BTreeMap<String, u64>,
+    pub llm_requests: u64,
+}
+
+/// Builds, previews and sends the reports of a workspace.
+pub struct Telemetry {
+    workspace: PathBuf,
+    config: TelemetryConfig,
+}
+
+impl Telemetry {
+    pub fn new(workspace: &Path, config: TelemetryConfig) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            config,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled && !self.config.endpoint.is_empty()
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.config.endpoint
+    }
+
+    /// The last complete UTC day, the one reported next.
+    pub fn last_day() -> NaiveDate {
+        Utc::now().date_naive() - chrono::Duration::days(1)
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.workspace.join("telemetry")
+    }
+
+    fn sent_path(&self, day: NaiveDate) -> PathBuf {
+        self.dir().join("sent").join(format!("{}.json", day.format("%Y-%m-%d")))
+    }
+
+    /// The noise seed for `day`: the local secret, created on first use,
+    /// hashed with the day.
+    fn seed(&self, day: NaiveDate) -> [u8; 32] {
+        let path = self.dir().join("seed");
+        let secret = match std::fs::read_to_string(&path) {
+            Ok(secret) if !secret.trim().is_empty() => secret.trim().to_string(),
+            _ => {
+                let mut bytes = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                let written = std::fs::create_dir_all(self.dir()).and_then(|_| std::fs::write(&path, &secret));
+                if let Err(e) = written {
+                    warn!("Could not save the telemetry seed; previews won't match what is sent: {}", e);
+                }
+                secret
+            }
+        };
+        Sha256::new()
+            .chain_update(secret.as_bytes())
+            .chain_update(day.to_string().as_bytes())
+            .finalize()
+            .into()
+    }
+
+    /// The report for `day`, noised.
+    pub fn report(&self, day: NaiveDate) -> Report {
+        let stats = ToolStats::load_day(&self.workspace, day);
+        let usage = UsageTracker::load_day(&self.workspace, day);
+        let epsilon = self.config.epsilon;
+        let mut rng = StdRng::from_seed(self.seed(day));
+        let mut noised = |count: u64| noise(&mut rng, count, epsilon);
+        let features = stats.iter().filter_map(|(tool, agg)| Some((tool.clone(), noised(agg.calls)?))).collect();
+        let errors = stats.iter().filter_map(|(tool, agg)| Some((tool.clone(), noised(agg.errors)?))).collect();
+        Report {
+            schema: SCHEMA,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            day,
+            epsilon,
+            features,
+            errors,
+            llm_requests: noised(usage.requests).unwrap_or(0),
+        }
+    }
+
+    /// The report sent for `day`, if one was.
+    pub fn sent(&self, day: NaiveDate) -> Option<Report> {
+        let json = std::fs::read_to_string(self.sent_path(day)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// POST the report for `day` and keep a copy of it.
+    pub async fn send(&self, client: &reqwest::Client, day: NaiveDate) -> Result<Report, TelemetryError> {
+        if !self.is_enabled() {
+            return Err(TelemetryError::Disabled);
+        }
+        let report = self.report(day);
+        let mut request = client.post(&self.config.endpoint).json(&report);
+        if !self.config.token.is_empty() {
+            let token = crate::vault::decrypt(&self.config.token).map_err(|e| TelemetryError::Token(e.to_string()))?;
+            request = request.bearer_auth(token);
+        }
+        let response = request.timeout(Duration::from_secs(30)).send().await?;
+        if !response.status().is_success() {
+            return Err(TelemetryError::Rejected(response.status()));
+        }
+        let path = self.sent_path(day);
+        std::fs::create_dir_all(path.parent().unwrap_or(&path))?;
+        std::fs::write(&path, serde_json::to_string_pretty(&report).unwrap_or_default())?;
+        Ok(report)
+    }
+}
+
+/// `count` plus Laplace noise of scale `1 / epsilon`, rounded; `None` when
+/// that is zero or less.
+fn noise(rng: &mut impl Rng, count: u64, epsilon: f64) -> Option<u64> {
+    let u: f64 = rng.gen_range(-0.5..0.5);
+    let laplace = -u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln() / epsilon;
+    let noised = (count as f64 + laplace).round();
+    (noised >= 1.0).then_some(noised as u64)
+}
+
+/// Send the last day's report once it is complete, retrying every hour
+/// until it goes through.
+pub async fn run_periodic(telemetry: Telemetry, client: reqwest::Client, cancel: CancellationToken) {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+        let day = Telemetry::last_day();
+        if telemetry.sent(day).is_some() {
+            debug!(%day, "Telemetry report already sent");
+            continue;
+        }
+        match telemetry.send(&client, day).await {
+            Ok(_) => info!(%day, "Telemetry report sent"),
+            Err(e) => warn!(%day, "Could not send the telemetry report: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_is_noised_counts_only() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_telemetry_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        std::fs::create_dir_all(tmp.join("stats/tools")).unwrap();
+        std::fs::write(
+            tmp.join("stats/tools/2026-03-14.json"),
+            r#"{"rugcheck": {"calls": 40, "errors": 3}, "web_fetch": {"calls": 7, "errors": 0}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.join("usage")).unwrap();
+        std::fs::write(tmp.join("usage/2026-03-14.json"), r#"{"requests": 120, "total_tokens": 99000}"#).unwrap();
+
+        // With a huge budget the noise vanishes and zero counts are left out.
+        let exact = Telemetry::new(&tmp, TelemetryConfig { epsilon: 1e9, ..Default::default() }).report(day);
+        assert_eq!(exact.features, BTreeMap::from([("rugcheck".into(), 40), ("web_fetch".into(), 7)]));
+        assert_eq!(exact.errors, BTreeMap::from([("rugcheck".into(), 3)]));
+        assert_eq!(exact.llm_requests, 120);
+        let json = serde_json::to_value(&exact).unwrap();
+        assert_eq!(json["day"], "2026-03-14");
+        assert!(json.get("llmRequests").is_some());
+
+        // The preview is what gets sent: the same day gives the same noise.
+        let telemetry = Telemetry::new(&tmp, TelemetryConfig::default());
+        assert_eq!(telemetry.report(day), telemetry.report(day));
+        assert!(tmp.join("telemetry/seed").exists());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let draws: Vec<f64> = (0..2000).map(|_| noise(&mut rng, 1000, 1.0).unwrap() as f64).collect();
+        let mean = draws.iter().sum::<f64>() / draws.len() as f64;
+        assert!((mean - 1000.0).abs() < 0.2, "{}", mean);
+        assert!(draws.iter().any(|&d| d != 1000.0));
+
+        let off = telemetry.send(&reqwest::Client::new(), day).await;
+        assert!(matches!(off, Err(TelemetryError::Disabled)));
+        assert!(telemetry.sent(day).is_none());
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,241 @@
+//! Outbound message templates.
+//!
+//! Templates live in `workspace/templates/` and are rendered with
+//! [minijinja](https://docs.rs/minijinja): `{{ field }}`, `{{ field.nested }}`,
+//! `{{ price | usd }}`, `{% if ok %}…{% endif %}`, `{% for t in tokens %}…{% endfor %}`.
+//! A template named `price_alert` can have per-channel variants, tried in
+//! this order:
+//!
+//! 1. `price_alert.<channel>.tmpl` (e.g. `price_alert.discord.tmpl`)
+//! 2. `price_alert.plain.tmpl` for the CLI, `price_alert.markdown.tmpl` for
+//!    chat channels
+//! 3. `price_alert.tmpl`
+//!
+//! Files are re-read whenever their modification time changes, so edits
+//! apply to the next message without a restart.
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::warn;
+
+const EXTENSION: &str = "tmpl";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("no template named '{0}'")]
+    NotFound(String),
+
+    #[error("template '{name}' failed to render: {message}")]
+    Render { name: String, message: String },
+}
+
+/// Text style a channel renders: `plain` for the CLI, `markdown` elsewhere.
+pub fn channel_style(channel: &str) -> &'static str {
+    if channel == "cli" {
+        "plain"
+    } else {
+        "markdown"
+    }
+}
+
+/// `1234.5` → `$1,234.50`; small prices keep significant digits.
+fn format_usd(value: minijinja::Value) -> String {
+    let Ok(n) = f64::try_from(value.clone()) else {
+        return value.to_string();
+    };
+    if n.abs() < 1.0 && n != 0.0 {
+        return format!("${:.6}", n).trim_end_matches('0').to_string();
+    }
+    let cents = format!("{:.2}", n.abs());
+    let (int, frac) = cents.split_once('.').unwrap_or((&cents, "00"));
+    let mut grouped = String::new();
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}${}.{}", if n < 0.0 { "-" } else { "" }, grouped, frac)
+}
+
+/// `1.234` → `+1.23%`.
+fn format_pct(value: minijinja::Value) -> String {
+    match f64::try_from(value.clone()) {
+        Ok(n) => format!("{:+.2}%", n),
+        Err(_) => value.to_string(),
+    }
+}
+
+fn format_json(value: minijinja::Value) -> String {
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// Render template `text` against `context`. `name` is only used in errors.
+///
+/// Printing an undefined field is an error, but `{% if field %}` may test
+/// for one.
+pub fn render_str(name: &str, text: &str, context: &Value) -> Result<String, TemplateError> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+    env.set_keep_trailing_newline(true);
+    env.add_filter("usd", format_usd);
+    env.add_filter("pct", format_pct);
+    env.add_filter("json", format_json);
+    env.render_named_str(name, text, context)
+        .map_err(|e| TemplateError::Render {
+            name: name.to_string(),
+            message: e.to_string(),
+        })
+}
+
+/// Named templates from `workspace/templates/`.
+#[derive(Debug, Clone)]
+pub struct TemplateRegistry {
+    dir: PathBuf,
+    cache: Arc<Mutex<HashMap<PathBuf, (SystemTime, String)>>>,
+}
+
+impl TemplateRegistry {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            dir: workspace.join("templates"),
+            cache: Arc::default(),
+        }
+    }
+
+    /// The file used for `name` on `channel`, if any variant exists.
+    pub fn resolve(&self, name: &str, channel: &str) -> Option<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return None;
+        }
+        [
+            format!("{}.{}.{}", name, channel, EXTENSION),
+            format!("{}.{}.{}", name, channel_style(channel), EXTENSION),
+            format!("{}.{}", name, EXTENSION),
+        ]
+        .into_iter()
+        .map(|file| self.dir.join(file))
+        .find(|path| path.is_file())
+    }
+
+    /// File contents, re-read when the modification time changed.
+    fn source(&self, path: &Path) -> Option<String> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        let mut cache = self.cache.lock().ok()?;
+        if let Some((at, text)) = cache.get(path) {
+            if *at == modified {
+                return Some(text.clone());
+            }
+        }
+        let text = std::fs::read_to_string(path).ok()?;
+        cache.insert(path.to_path_buf(), (modified, text.clone()));
+        Some(text)
+    }
+
+    /// Render template `name` for `channel` with `context`.
+    pub fn render(&self, name: &str, channel: &str, context: &Value) -> Result<String, TemplateError> {
+        let text = self
+            .resolve(name, channel)
+            .and_then(|path| self.source(&path))
+            .ok_or_else(|| TemplateError::NotFound(name.to_string()))?;
+        render_str(name, &text, context)
+    }
+
+    /// Render template `name` if the workspace has one, else `fallback`.
+    /// A template that fails to render is logged and skipped.
+    pub fn render_or(&self, name: &str, channel: &str, context: &Value, fallback: impl FnOnce() -> String) -> String {
+        match self.render(name, channel, context) {
+            Ok(text) => text,
+            Err(TemplateError::NotFound(_)) => fallback(),
+            Err(e) => {
+                warn!("{}", e);
+                fallback()
+            }
+        }
+    }
+
+    /// Names of all templates, without variants.
+    pub fn names(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| {
+                let file = e.file_name().to_string_lossy().into_owned();
+                let stem = file.strip_suffix(&format!(".{}", EXTENSION))?;
+                Some(stem.split('.').next().unwrap_or(stem).to_string())
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_str_formatters() {
+        let ctx = json!({"token": "SOL", "price": 1234.5, "change": 3.456, "tiny": 0.000123});
+        let text = render_str("t", "{{ token }} {{ price | usd }} ({{ change | pct }}) {{ tiny | usd }}", &ctx).unwrap();
+        assert_eq!(text, "SOL $1,234.50 (+3.46%) $0.000123");
+        assert!(matches!(
+            render_str("t", "{{ missing }}", &ctx),
+            Err(TemplateError::Render { .. })
+        ));
+        let text = render_str("t", "{% if missing %}never{% else %}{{ token }}{% endif %}\n", &ctx).unwrap();
+        assert_eq!(text, "SOL\n");
+    }
+
+    #[test]
+    fn test_variants_and_reload() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_templates_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("templates")).unwrap();
+        let registry = TemplateRegistry::new(&dir);
+        let ctx = json!({"token": "SOL"});
+        let write = |file: &str, text: &str| std::fs::write(dir.join("templates").join(file), text).unwrap();
+
+        assert!(matches!(
+            registry.render("alert", "telegram", &ctx),
+            Err(TemplateError::NotFound(_))
+        ));
+        write("alert.tmpl", "{{ token }} moved");
+        write("alert.markdown.tmpl", "*{{ token }}* moved");
+        write("alert.discord.tmpl", "**{{ token }}** moved");
+        assert_eq!(registry.render("alert", "cli", &ctx).unwrap(), "SOL moved");
+        assert_eq!(registry.render("alert", "telegram", &ctx).unwrap(), "*SOL* moved");
+        assert_eq!(registry.render("alert", "discord", &ctx).unwrap(), "**SOL** moved");
+        assert_eq!(registry.names(), ["alert"]);
+        assert_eq!(registry.render_or("other", "cli", &ctx, || "built-in".into()), "built-in");
+        write("broken.tmpl", "{{ missing }}");
+        assert_eq!(registry.render_or("broken", "cli", &ctx, || "built-in".into()), "built-in");
+        assert!(registry.resolve("../alert", "cli").is_none());
+
+        // Edits are picked up once the modification time changes.
+        let path = dir.join("templates/alert.tmpl");
+        write("alert.tmpl", "{{ token }} moved again");
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(registry.render("alert", "cli", &ctx).unwrap(), "SOL moved again");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
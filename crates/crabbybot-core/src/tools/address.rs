@@ -0,0 +1,408 @@
+//! Address validation and recipient screening.
+//!
+//! [`check`] recognises Solana, EVM and Bitcoin addresses and verifies what
+//! each format allows: EIP-55 mixed-case checksums for EVM, Base58Check and
+//! bech32/bech32m checksums for Bitcoin. Solana addresses carry no checksum,
+//! so only their length and encoding can be checked.
+//!
+//! [`screen_recipient`] is the risk check the registry runs on the recipient
+//! arguments of transfer tools (see [`Tool::recipient_params`]). It rejects
+//! malformed addresses and flags lookalikes of the user's known addresses,
+//! the pattern used by address-poisoning scams: a fresh address sharing the
+//! first and last characters of one the victim has used before.
+//!
+//! [`Tool::recipient_params`]: super::Tool::recipient_params
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{Tool, ToolContext};
+use crate::agent::profile::ProfileStore;
+
+/// Leading and trailing characters an address must share with a known one,
+/// without being equal to it, to count as a lookalike.
+const LOOKALIKE_EDGE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Solana,
+    Evm,
+    Bitcoin,
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Solana => "Solana",
+            Self::Evm => "EVM",
+            Self::Bitcoin => "Bitcoin",
+        })
+    }
+}
+
+/// Result of validating one address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressCheck {
+    /// The chain the address belongs to, if recognised.
+    pub chain: Option<Chain>,
+    /// Whether the address is well-formed with a correct checksum.
+    pub valid: bool,
+    /// Details worth telling the user, e.g. why it is invalid.
+    pub notes: Vec<String>,
+}
+
+impl AddressCheck {
+    fn valid(chain: Chain, note: impl Into<String>) -> Self {
+        Self {
+            chain: Some(chain),
+            valid: true,
+            notes: vec![note.into()],
+        }
+    }
+
+    fn invalid(chain: Option<Chain>, note: impl Into<String>) -> Self {
+        Self {
+            chain,
+            valid: false,
+            notes: vec![note.into()],
+        }
+    }
+}
+
+/// Validate `address`, detecting its chain from the format.
+pub fn check(address: &str) -> AddressCheck {
+    let address = address.trim();
+    if let Some(hex) = address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")) {
+        return check_evm(hex);
+    }
+    let lower = address.to_ascii_lowercase();
+    if lower.starts_with("bc1") || lower.starts_with("tb1") {
+        return check_bech32(address);
+    }
+    if matches!(address.chars().next(), Some('1' | '3' | 'm' | 'n' | '2')) && (25..=35).contains(&address.len()) {
+        // Base58Check and Solana lengths overlap; prefer whichever format
+        // the address actually satisfies.
+        let btc = check_base58check(address);
+        if btc.valid || address.len() < 32 {
+            return btc;
+        }
+        let sol = check_solana(address);
+        return if sol.valid { sol } else { btc };
+    }
+    check_solana(address)
+}
+
+fn check_solana(address: &str) -> AddressCheck {
+    let Ok(bytes) = bs58::decode(address).into_vec() else {
+        return AddressCheck::invalid(None, "not a recognised address format");
+    };
+    if bytes.len() != 32 {
+        return AddressCheck::invalid(
+            Some(Chain::Solana),
+            format!("decodes to {} bytes; Solana addresses are 32", bytes.len()),
+        );
+    }
+    let mut check = AddressCheck::valid(
+        Chain::Solana,
+        "Solana addresses have no checksum, so a mistyped address can still look valid",
+    );
+    let key: [u8; 32] = bytes.try_into().unwrap_or_default();
+    if ed25519_dalek::VerifyingKey::from_bytes(&key).is_err() {
+        check
+            .notes
+            .push("off-curve (program-derived) address, not a regular wallet".into());
+    }
+    check
+}
+
+fn check_evm(hex: &str) -> AddressCheck {
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return AddressCheck::invalid(Some(Chain::Evm), "EVM addresses are 0x followed by 40 hex digits");
+    }
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    if !(has_upper && has_lower) {
+        return AddressCheck::valid(
+            Chain::Evm,
+            format!("no EIP-55 checksum (single case); checksummed form: 0x{}", eip55(hex)),
+        );
+    }
+    let expected = eip55(hex);
+    if hex == expected {
+        AddressCheck::valid(Chain::Evm, "EIP-55 checksum OK")
+    } else {
+        AddressCheck::invalid(
+            Some(Chain::Evm),
+            "EIP-55 checksum mismatch: the letter case doesn't match, so the address was probably mistyped",
+        )
+    }
+}
+
+/// The EIP-55 mixed-case form of a 40-digit hex address (without `0x`).
+fn eip55(hex: &str) -> String {
+    let lower = hex.to_ascii_lowercase();
+    let hash = Keccak256::digest(lower.as_bytes());
+    lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn check_base58check(address: &str) -> AddressCheck {
+    let Ok(bytes) = bs58::decode(address).into_vec() else {
+        return AddressCheck::invalid(Some(Chain::Bitcoin), "contains characters outside base58");
+    };
+    if bytes.len() != 25 {
+        return AddressCheck::invalid(Some(Chain::Bitcoin), "wrong length for a Base58Check address");
+    }
+    let (payload, checksum) = bytes.split_at(21);
+    if Sha256::digest(Sha256::digest(payload))[..4] != *checksum {
+        return AddressCheck::invalid(Some(Chain::Bitcoin), "Base58Check checksum mismatch");
+    }
+    let kind = match payload[0] {
+        0x00 => "P2PKH",
+        0x05 => "P2SH",
+        0x6f => "testnet P2PKH",
+        0xc4 => "testnet P2SH",
+        v => return AddressCheck::invalid(Some(Chain::Bitcoin), format!("unknown version byte {:#04x}", v)),
+    };
+    AddressCheck::valid(Chain::Bitcoin, format!("{} address, checksum OK", kind))
+}
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    values.iter().fold(1u32, |chk, &v| {
+        let top = chk >> 25;
+        let chk = ((chk & 0x1ffffff) << 5) ^ u32::from(v);
+        (0..5).fold(chk, |c, i| if (top >> i) & 1 == 1 { c ^ GEN[i] } else { c })
+    })
+}
+
+fn check_bech32(address: &str) -> AddressCheck {
+    let fail = |note: &str| AddressCheck::invalid(Some(Chain::Bitcoin), note);
+    if address.chars().any(|c| c.is_ascii_uppercase()) && address.chars().any(|c| c.is_ascii_lowercase()) {
+        return fail("bech32 addresses can't mix upper and lower case");
+    }
+    let lower = address.to_ascii_lowercase();
+    let Some((hrp, data)) = lower.rsplit_once('1') else {
+        return fail("missing bech32 separator");
+    };
+    if data.len() < 8 || lower.len() > 90 {
+        return fail("wrong length for a bech32 address");
+    }
+    let Some(values) = data
+        .bytes()
+        .map(|b| BECH32_CHARSET.iter().position(|&c| c == b).map(|p| p as u8))
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return fail("contains characters outside the bech32 alphabet");
+    };
+
+    let mut expanded: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|b| b & 31));
+    expanded.extend(&values);
+    let witness_version = values[0];
+    let expected = if witness_version == 0 { 1 } else { 0x2bc830a3 };
+    if bech32_polymod(&expanded) != expected {
+        return fail("bech32 checksum mismatch");
+    }
+
+    // Witness program length, from 5-bit groups to bytes.
+    let program_bytes = (values.len() - 7) * 5 / 8;
+    if witness_version > 16
+        || !(2..=40).contains(&program_bytes)
+        || (witness_version == 0 && program_bytes != 20 && program_bytes != 32)
+    {
+        return fail("invalid witness program");
+    }
+    let network = if hrp == "tb" { "testnet " } else { "" };
+    let kind = match (witness_version, program_bytes) {
+        (0, 20) => "P2WPKH",
+        (0, _) => "P2WSH",
+        (1, 32) => "Taproot",
+        _ => "SegWit",
+    };
+    AddressCheck::valid(Chain::Bitcoin, format!("{}{} address, checksum OK", network, kind))
+}
+
+/// Normalise for comparison: EVM addresses are case-insensitive.
+fn normalise(address: &str) -> String {
+    let a = address.trim();
+    if a.starts_with("0x") || a.starts_with("0X") {
+        a.to_ascii_lowercase()
+    } else {
+        a.to_string()
+    }
+}
+
+/// The known address `address` imitates, if any.
+pub fn lookalike<'a>(address: &str, known: &'a [String]) -> Option<&'a str> {
+    let candidate = normalise(address);
+    let skip = if candidate.starts_with("0x") { 2 } else { 0 };
+    let body = &candidate.as_bytes()[skip.min(candidate.len())..];
+    known.iter().map(String::as_str).find(|k| {
+        let k_norm = normalise(k);
+        if k_norm == candidate {
+            return false;
+        }
+        let k_body = &k_norm.as_bytes()[skip.min(k_norm.len())..];
+        let prefix = body.iter().zip(k_body).take_while(|(a, b)| a == b).count();
+        let suffix = body.iter().rev().zip(k_body.iter().rev()).take_while(|(a, b)| a == b).count();
+        prefix >= LOOKALIKE_EDGE && suffix >= LOOKALIKE_EDGE
+    })
+}
+
+/// Whether `address` is one of the `known` addresses.
+pub fn is_known(address: &str, known: &[String]) -> bool {
+    let candidate = normalise(address);
+    known.iter().any(|k| normalise(k) == candidate)
+}
+
+/// Addresses the user has saved, used to spot lookalikes.
+pub fn known_addresses(ctx: &ToolContext) -> Vec<String> {
+    if ctx.user_id.is_empty() {
+        return Vec::new();
+    }
+    ProfileStore::new(&ctx.workspace)
+        .get(&ctx.channel, &ctx.user_id)
+        .map(|p| p.wallets)
+        .unwrap_or_default()
+}
+
+/// Why a transfer to `address` must not go ahead, if anything.
+///
+/// Malformed addresses are always rejected. Lookalikes of a known address
+/// are rejected unless `approved`, i.e. the user explicitly confirmed.
+pub fn screen_recipient(address: &str, known: &[String], approved: bool) -> Result<(), String> {
+    let result = check(address);
+    if !result.valid {
+        return Err(format!("recipient {} is not a valid address: {}", address, result.notes.join("; ")));
+    }
+    if let Some(original) = lookalike(address, known) {
+        if !approved {
+            return Err(format!(
+                "recipient {} looks like your saved address {} but is different. This is a common \
+                 address-poisoning scam; ask the user to confirm the full address before sending",
+                address, original
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ── ValidateAddressTool ─────────────────────────────────────────────
+
+pub struct ValidateAddressTool;
+
+#[async_trait]
+impl Tool for ValidateAddressTool {
+    fn name(&self) -> &str {
+        "validate_address"
+    }
+
+    fn description(&self) -> &str {
+        "Check whether a Solana, EVM or Bitcoin address is well-formed and its checksum is \
+         correct, and whether it imitates one of the user's saved wallets (address poisoning). \
+         Use it whenever the user pastes an address to send funds to."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "The address to check"
+                }
+            },
+            "required": ["address"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(address) = args.get("address").and_then(|v| v.as_str()).map(str::trim) else {
+            return "Error: 'address' parameter is required".into();
+        };
+        let result = check(address);
+        let mut lines = vec![match (result.valid, result.chain) {
+            (true, Some(chain)) => format!("✅ Valid {} address", chain),
+            (false, Some(chain)) => format!("❌ Invalid {} address", chain),
+            _ => "❌ Not a recognised Solana, EVM or Bitcoin address".to_string(),
+        }];
+        lines.extend(result.notes.iter().map(|n| format!("- {}", n)));
+
+        let known = known_addresses(ctx);
+        if is_known(address, &known) {
+            lines.push("- Matches one of your saved wallets".into());
+        } else if let Some(original) = lookalike(address, &known) {
+            lines.push(format!(
+                "⚠️ Looks like your saved wallet {} but is a different address — possible address poisoning. \
+                 Compare every character before sending anything.",
+                original
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_per_chain() {
+        let sol = check("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM");
+        assert_eq!((sol.chain, sol.valid), (Some(Chain::Solana), true));
+        assert!(!check("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9z").valid);
+        assert!(!check("0OIl-not-base58").valid);
+
+        let evm = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert!(check(evm).valid);
+        assert!(check(&evm.to_ascii_lowercase()).notes[0].contains(evm));
+        let mistyped = check("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+        assert_eq!((mistyped.chain, mistyped.valid), (Some(Chain::Evm), false));
+        assert!(!check("0x1234").valid);
+
+        assert!(check("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").valid);
+        assert!(check("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").valid);
+        assert!(!check("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3").valid);
+        let segwit = check("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq");
+        assert!(segwit.valid && segwit.notes[0].starts_with("P2WPKH"), "{segwit:?}");
+        let taproot = check("bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297");
+        assert!(taproot.valid && taproot.notes[0].starts_with("Taproot"), "{taproot:?}");
+        assert!(!check("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdx").valid);
+    }
+
+    #[test]
+    fn test_lookalikes_are_screened() {
+        let known = vec!["0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()];
+        let poisoned = "0x5aa0000000000000000000000000000000000aed";
+        assert_eq!(lookalike(poisoned, &known), Some(known[0].as_str()));
+        assert_eq!(lookalike("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", &known), None);
+        assert!(is_known("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", &known));
+
+        assert!(screen_recipient(poisoned, &known, false).unwrap_err().contains("address-poisoning"));
+        assert!(screen_recipient(poisoned, &known, true).is_ok());
+        assert!(screen_recipient("0x1234", &known, true).is_err());
+        assert!(screen_recipient("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM", &known, false).is_ok());
+    }
+}
@@ -4,7 +4,7 @@
 
 use super::rugcheck::{RugCheckTool, RugcheckReport};
 use super::sentiment::SentimentTool;
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
@@ -31,6 +31,10 @@ impl Tool for AlphaSummaryTool {
         "alpha_summary"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 10)
+    }
+
     fn description(&self) -> &str {
         "Get a comprehensive 'Alpha Signal' for a token. Synthesizes RugCheck risk, \
          social sentiment, and community activity into a single decision-support report. \
@@ -50,7 +54,11 @@ impl Tool for AlphaSummaryTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(mint) = args.get("mint").and_then(|v| v.as_str()) else {
             return "❌ Error: 'mint' parameter is required".into();
         };
@@ -0,0 +1,629 @@
+//! Archive tools: archive_create, archive_extract.
+//!
+//! Zip and gzip-compressed tar archives, so the agent can bundle reports for
+//! sending or unpack uploaded bundles. Unlike the other filesystem tools
+//! these are always confined to the workspace, whatever
+//! `restrict_to_workspace` says: archive entries are untrusted input, and
+//! an entry named `../../.bashrc` must never leave the destination.
+//!
+//! Extraction skips links and special files, rejects absolute or `..`
+//! entry paths, never writes through a symlink already in the destination,
+//! and stops once the unpacked size or entry count exceeds the limits below
+//! (guarding against zip bombs).
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::{CostHint, CostTier, Tool, ToolContext};
+
+/// Largest total size an archive may contain or unpack to.
+const MAX_TOTAL_BYTES: u64 = 256 * 1024 * 1024;
+/// Most entries an archive may contain or unpack to.
+const MAX_ENTRIES: usize = 10_000;
+/// Extracted file names listed in the tool result.
+const LISTED_ENTRIES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    TarGz,
+    Tar,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// File name without the archive extension.
+    fn stem(path: &Path) -> String {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("archive");
+        let lower = name.to_lowercase();
+        [".tar.gz", ".tgz", ".tar", ".zip"]
+            .iter()
+            .find(|ext| lower.ends_with(*ext))
+            .map(|ext| name[..name.len() - ext.len()].to_string())
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────
+
+/// Resolve `raw` (relative paths are taken from the workspace) and require
+/// the result to stay inside the workspace, following symlinks for the part
+/// of the path that exists.
+fn resolve_in_workspace(raw: &str, workspace: &Path) -> Result<PathBuf, String> {
+    let ws = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    let joined = ws.join(raw);
+
+    let mut path = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            c => path.push(c),
+        }
+    }
+    // Canonicalize the deepest existing ancestor so symlinks can't escape.
+    let mut existing = path.as_path();
+    while !existing.exists() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break,
+        }
+    }
+    let real = match (existing.canonicalize(), path.strip_prefix(existing)) {
+        (Ok(real), Ok(rest)) if rest.as_os_str().is_empty() => real,
+        (Ok(real), Ok(rest)) => real.join(rest),
+        _ => path,
+    };
+    if real.starts_with(&ws) {
+        Ok(real)
+    } else {
+        Err(format!("Error: '{}' is outside the workspace", raw))
+    }
+}
+
+/// An archive entry's path as a relative path of normal components only,
+/// or `None` if it is absolute, climbs with `..`, or is empty.
+fn safe_entry_path(name: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+fn display(path: &Path, workspace: &Path) -> String {
+    let ws = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    path.strip_prefix(&ws).unwrap_or(path).display().to_string()
+}
+
+fn string_list(args: &HashMap<String, Value>, key: &str) -> Vec<String> {
+    match args.get(key) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect(),
+        Some(Value::String(s)) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+// ── Creating ────────────────────────────────────────────────────────
+
+/// Files to pack: (path on disk, name inside the archive).
+fn collect_files(inputs: &[PathBuf]) -> Result<Vec<(PathBuf, String)>, String> {
+    fn walk(path: &Path, name: &Path, out: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+        let meta = std::fs::symlink_metadata(path)?;
+        if meta.is_dir() {
+            let mut children: Vec<_> = std::fs::read_dir(path)?.collect::<io::Result<_>>()?;
+            children.sort_by_key(|e| e.file_name());
+            for child in children {
+                walk(&child.path(), &name.join(child.file_name()), out)?;
+            }
+        } else if meta.is_file() {
+            out.push((path.to_path_buf(), name.to_string_lossy().replace('\\', "/")));
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    for input in inputs {
+        let name = PathBuf::from(input.file_name().unwrap_or(input.as_os_str()));
+        walk(input, &name, &mut files).map_err(|e| format!("Error reading '{}': {}", input.display(), e))?;
+    }
+    if files.len() > MAX_ENTRIES {
+        return Err(format!("Error: too many files ({}, limit {})", files.len(), MAX_ENTRIES));
+    }
+    let total: u64 = files
+        .iter()
+        .filter_map(|(p, _)| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+    if total > MAX_TOTAL_BYTES {
+        return Err(format!(
+            "Error: inputs total {}, over the {} limit",
+            human_size(total),
+            human_size(MAX_TOTAL_BYTES)
+        ));
+    }
+    Ok(files)
+}
+
+fn write_archive(output: &Path, format: Format, files: &[(PathBuf, String)]) -> io::Result<()> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = File::create(output)?;
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(true);
+            for (path, name) in files {
+                zip.start_file(name.as_str(), options).map_err(io::Error::other)?;
+                io::copy(&mut File::open(path)?, &mut zip)?;
+            }
+            zip.finish().map_err(io::Error::other)?;
+        }
+        Format::TarGz => {
+            let gz = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut tar = tar::Builder::new(gz);
+            for (path, name) in files {
+                tar.append_path_with_name(path, name)?;
+            }
+            tar.into_inner()?.finish()?.flush()?;
+        }
+        Format::Tar => {
+            let mut tar = tar::Builder::new(file);
+            for (path, name) in files {
+                tar.append_path_with_name(path, name)?;
+            }
+            tar.into_inner()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+// ── Extracting ──────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct Extracted {
+    files: Vec<String>,
+    bytes: u64,
+    /// Entries left out: unsafe paths, links, paths through a symlink, or
+    /// existing files.
+    skipped: Vec<String>,
+}
+
+impl Extracted {
+    fn check_entries(&self) -> io::Result<()> {
+        if self.files.len() >= MAX_ENTRIES {
+            return Err(io::Error::other(format!("archive has more than {} entries", MAX_ENTRIES)));
+        }
+        Ok(())
+    }
+
+    /// Copy one entry to `dest/rel`, enforcing the total size limit on the
+    /// bytes actually written rather than the size the archive claims.
+    fn write_entry(&mut self, reader: &mut dyn Read, dest: &Path, rel: &Path, overwrite: bool) -> io::Result<()> {
+        self.check_entries()?;
+        let target = dest.join(rel);
+        let name = rel.to_string_lossy().replace('\\', "/");
+        if through_symlink(dest, rel) {
+            self.skipped.push(format!("{} (symlink in the way)", name));
+            return Ok(());
+        }
+        if target.exists() && !overwrite {
+            self.skipped.push(format!("{} (exists)", name));
+            return Ok(());
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let remaining = MAX_TOTAL_BYTES - self.bytes;
+        let written = io::copy(&mut reader.take(remaining + 1), &mut File::create(&target)?)?;
+        if written > remaining {
+            let _ = std::fs::remove_file(&target);
+            return Err(io::Error::other(format!(
+                "archive unpacks to more than {}",
+                human_size(MAX_TOTAL_BYTES)
+            )));
+        }
+        self.bytes += written;
+        self.files.push(name);
+        Ok(())
+    }
+}
+
+/// Whether `rel` under `dest` passes through or ends at an existing
+/// symlink, which would send the write somewhere outside `dest`.
+fn through_symlink(dest: &Path, rel: &Path) -> bool {
+    let mut path = dest.to_path_buf();
+    rel.components().any(|c| {
+        path.push(c);
+        std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink())
+    })
+}
+
+fn extract_archive(archive: &Path, format: Format, dest: &Path, overwrite: bool) -> io::Result<Extracted> {
+    let file = File::open(archive)?;
+    let mut out = Extracted::default();
+    std::fs::create_dir_all(dest)?;
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i).map_err(io::Error::other)?;
+                let raw = entry.name().to_string();
+                let Some(rel) = safe_entry_path(Path::new(&raw)) else {
+                    out.skipped.push(format!("{} (unsafe path)", raw));
+                    continue;
+                };
+                if entry.is_dir() && through_symlink(dest, &rel) {
+                    out.skipped.push(format!("{} (symlink in the way)", raw));
+                } else if entry.is_dir() {
+                    std::fs::create_dir_all(dest.join(rel))?;
+                } else if entry.is_symlink() {
+                    out.skipped.push(format!("{} (link)", raw));
+                } else {
+                    out.write_entry(&mut entry, dest, &rel, overwrite)?;
+                }
+            }
+        }
+        Format::TarGz => extract_tar(tar::Archive::new(flate2::read::GzDecoder::new(file)), dest, overwrite, &mut out)?,
+        Format::Tar => extract_tar(tar::Archive::new(file), dest, overwrite, &mut out)?,
+    }
+    Ok(out)
+}
+
+fn extract_tar<R: Read>(mut archive: tar::Archive<R>, dest: &Path, overwrite: bool, out: &mut Extracted) -> io::Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let raw = entry.path()?.to_string_lossy().into_owned();
+        let Some(rel) = safe_entry_path(Path::new(&raw)) else {
+            out.skipped.push(format!("{} (unsafe path)", raw));
+            continue;
+        };
+        match entry.header().entry_type() {
+            tar::EntryType::Directory if through_symlink(dest, &rel) => {
+                out.skipped.push(format!("{} (symlink in the way)", raw))
+            }
+            tar::EntryType::Directory => std::fs::create_dir_all(dest.join(rel))?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                out.write_entry(&mut entry, dest, &rel, overwrite)?
+            }
+            _ => out.skipped.push(format!("{} (link or special file)", raw)),
+        }
+    }
+    Ok(())
+}
+
+// ── ArchiveCreateTool ───────────────────────────────────────────────
+
+pub struct ArchiveCreateTool {
+    workspace: PathBuf,
+}
+
+impl ArchiveCreateTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ArchiveCreateTool {
+    fn name(&self) -> &str {
+        "archive_create"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
+    fn description(&self) -> &str {
+        "Pack workspace files and directories into a .zip, .tar.gz or .tar archive \
+         (format chosen by the output extension), e.g. to bundle reports for sending."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files or directories to include, relative to the workspace"
+                },
+                "output": {
+                    "type": "string",
+                    "description": "Archive to write, e.g. 'exports/report.zip'"
+                }
+            },
+            "required": ["paths", "output"]
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let paths = string_list(&args, "paths");
+        if paths.is_empty() {
+            return "Error: 'paths' must list at least one file or directory".into();
+        }
+        let Some(raw_output) = args.get("output").and_then(|v| v.as_str()) else {
+            return "Error: 'output' parameter is required".into();
+        };
+        let output = match resolve_in_workspace(raw_output, ctx.workspace_or(&self.workspace)) {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        let Some(format) = Format::from_path(&output) else {
+            return "Error: 'output' must end in .zip, .tar.gz, .tgz or .tar".into();
+        };
+        let mut inputs = Vec::new();
+        for raw in &paths {
+            match resolve_in_workspace(raw, ctx.workspace_or(&self.workspace)) {
+                Ok(p) if p.exists() => inputs.push(p),
+                Ok(_) => return format!("Error: '{}' does not exist", raw),
+                Err(e) => return e,
+            }
+        }
+
+        let workspace = ctx.workspace_or(&self.workspace).to_path_buf();
+        let result = tokio::task::spawn_blocking(move || {
+            let files = collect_files(&inputs)?;
+            if files.iter().any(|(p, _)| *p == output) {
+                return Err("Error: the output archive can't be one of its own inputs".to_string());
+            }
+            write_archive(&output, format, &files)
+                .map_err(|e| format!("Error writing '{}': {}", output.display(), e))?;
+            let size = std::fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+            Ok(format!(
+                "✅ Created {} ({} files, {})",
+                display(&output, &workspace),
+                files.len(),
+                human_size(size)
+            ))
+        })
+        .await;
+        match result {
+            Ok(Ok(msg)) | Ok(Err(msg)) => msg,
+            Err(e) => format!("Error: archive task failed: {}", e),
+        }
+    }
+}
+
+// ── ArchiveExtractTool ──────────────────────────────────────────────
+
+pub struct ArchiveExtractTool {
+    workspace: PathBuf,
+}
+
+impl ArchiveExtractTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ArchiveExtractTool {
+    fn name(&self) -> &str {
+        "archive_extract"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
+    fn description(&self) -> &str {
+        "Unpack a .zip, .tar.gz or .tar archive in the workspace (e.g. an uploaded bundle). \
+         Extracts next to the archive into a folder named after it unless `destination` is given."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "archive": {
+                    "type": "string",
+                    "description": "Archive to unpack, relative to the workspace"
+                },
+                "destination": {
+                    "type": "string",
+                    "description": "Directory to unpack into (optional)"
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace files that already exist (default false: they are skipped)"
+                }
+            },
+            "required": ["archive"]
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(raw_archive) = args.get("archive").and_then(|v| v.as_str()) else {
+            return "Error: 'archive' parameter is required".into();
+        };
+        let archive = match resolve_in_workspace(raw_archive, ctx.workspace_or(&self.workspace)) {
+            Ok(p) if p.is_file() => p,
+            Ok(_) => return format!("Error: '{}' is not a file", raw_archive),
+            Err(e) => return e,
+        };
+        let Some(format) = Format::from_path(&archive) else {
+            return "Error: only .zip, .tar.gz, .tgz and .tar archives are supported".into();
+        };
+        let dest = match args.get("destination").and_then(|v| v.as_str()) {
+            Some(raw) => match resolve_in_workspace(raw, ctx.workspace_or(&self.workspace)) {
+                Ok(p) => p,
+                Err(e) => return e,
+            },
+            None => archive.with_file_name(Format::stem(&archive)),
+        };
+        let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let workspace = ctx.workspace_or(&self.workspace).to_path_buf();
+        let result = tokio::task::spawn_blocking(move || {
+            let out = extract_archive(&archive, format, &dest, overwrite)
+                .map_err(|e| format!("Error extracting '{}': {}", archive.display(), e))?;
+            let mut msg = format!(
+                "✅ Extracted {} files ({}) to {}",
+                out.files.len(),
+                human_size(out.bytes),
+                display(&dest, &workspace)
+            );
+            for name in out.files.iter().take(LISTED_ENTRIES) {
+                msg.push_str(&format!("\n  {}", name));
+            }
+            if out.files.len() > LISTED_ENTRIES {
+                msg.push_str(&format!("\n  … and {} more", out.files.len() - LISTED_ENTRIES));
+            }
+            if !out.skipped.is_empty() {
+                msg.push_str(&format!("\nSkipped {}: {}", out.skipped.len(), out.skipped.join(", ")));
+            }
+            Ok::<_, String>(msg)
+        })
+        .await;
+        match result {
+            Ok(Ok(msg)) | Ok(Err(msg)) => msg,
+            Err(e) => format!("Error: archive task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(pairs: Value) -> HashMap<String, Value> {
+        serde_json::from_value(pairs).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_archive_roundtrip() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_archive");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(tmp.join("reports/q1")).unwrap();
+        std::fs::write(tmp.join("reports/summary.md"), "# Summary").unwrap();
+        std::fs::write(tmp.join("reports/q1/data.csv"), "a,b\n1,2\n").unwrap();
+
+        let create = ArchiveCreateTool::new(tmp.clone());
+        let extract = ArchiveExtractTool::new(tmp.clone());
+        let ctx = ToolContext::default();
+
+        for output in ["out/reports.zip", "out/bundle.tar.gz"] {
+            let res = create.execute(args(json!({"paths": ["reports"], "output": output})), &ctx).await;
+            assert!(res.starts_with(&format!("✅ Created {} (2 files", output)), "{res}");
+
+            let res = extract.execute(args(json!({"archive": output})), &ctx).await;
+            assert!(res.starts_with("✅ Extracted 2 files"), "{res}");
+            let dest = tmp.join("out").join(Format::stem(Path::new(output)));
+            let text = std::fs::read_to_string(dest.join("reports/q1/data.csv")).unwrap();
+            assert_eq!(text, "a,b\n1,2\n");
+
+            // A second run leaves existing files alone unless asked.
+            let res = extract.execute(args(json!({"archive": output})), &ctx).await;
+            assert!(res.contains("Extracted 0 files") && res.contains("Skipped 2"), "{res}");
+        }
+
+        let res = create.execute(args(json!({"paths": ["../etc"], "output": "x.zip"})), &ctx).await;
+        assert!(res.contains("outside the workspace"), "{res}");
+        let res = extract.execute(args(json!({"archive": "out/reports.zip", "destination": "/tmp/x"})), &ctx).await;
+        assert!(res.contains("outside the workspace"), "{res}");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn test_extract_rejects_path_traversal() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_archive_evil");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(tmp.join("evil.zip")).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for name in ["../escaped.txt", "/abs.txt", "ok/fine.txt"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+        zip.finish().unwrap();
+
+        let res = ArchiveExtractTool::new(tmp.clone())
+            .execute(args(json!({"archive": "evil.zip"})), &ToolContext::default())
+            .await;
+        assert!(res.starts_with("✅ Extracted 1 files"), "{res}");
+        assert!(res.contains("Skipped 2"), "{res}");
+        assert!(tmp.join("evil/ok/fine.txt").exists());
+        assert!(!tmp.join("escaped.txt").exists());
+        assert!(!std::env::temp_dir().join("escaped.txt").exists());
+
+        assert_eq!(safe_entry_path(Path::new("a/./b")), Some(PathBuf::from("a/b")));
+        assert_eq!(safe_entry_path(Path::new("a/../../b")), None);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_extract_does_not_write_through_symlinks() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_archive_links_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let outside = tmp.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join(".bashrc"), "untouched").unwrap();
+        let ws = tmp.join("ws");
+        std::fs::create_dir_all(ws.join("bundle")).unwrap();
+        std::os::unix::fs::symlink(outside.join(".bashrc"), ws.join("bundle/notes.txt")).unwrap();
+        std::os::unix::fs::symlink(&outside, ws.join("bundle/docs")).unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(ws.join("bundle.zip")).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for name in ["notes.txt", "docs/evil.txt", "fine.txt"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(b"pwned").unwrap();
+        }
+        zip.finish().unwrap();
+
+        let res = ArchiveExtractTool::new(ws.clone())
+            .execute(args(json!({"archive": "bundle.zip", "overwrite": true})), &ToolContext::default())
+            .await;
+        assert!(res.starts_with("✅ Extracted 1 files"), "{res}");
+        assert!(res.contains("symlink in the way"), "{res}");
+        assert_eq!(std::fs::read_to_string(outside.join(".bashrc")).unwrap(), "untouched");
+        assert!(!outside.join("evil.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::service::betting::BettingState;
 
 /// Control the autonomous Polymarket betting engine.
@@ -28,6 +28,10 @@ impl Tool for BettingControlTool {
         "betting_control"
     }
 
+    fn mutates(&self, args: &HashMap<String, Value>) -> bool {
+        matches!(args.get("action").and_then(|v| v.as_str()), Some("start") | Some("stop"))
+    }
+
     fn description(&self) -> &str {
         "Control the autonomous Polymarket betting engine. \
          Actions: 'start' (resume scanning/trading), 'stop' (pause), \
@@ -48,7 +52,7 @@ impl Tool for BettingControlTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let action = args
             .get("action")
             .and_then(|v| v.as_str())
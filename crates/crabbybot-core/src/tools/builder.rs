@@ -0,0 +1,491 @@
+//! `ToolSetBuilder`: constructs the tool registry from configuration.
+//!
+//! Every built-in tool is registered here, filtered through the
+//! `tools.enabled` / `tools.disabled` lists in `config.json`, so binaries
+//! no longer hand-roll the registration block.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::agent::locale::LocaleSettings;
+use crate::approvals::Approvals;
+use crate::config::{Config, NewsProvider};
+use crate::cron::CronService;
+use crate::guardrails::Guardrails;
+use crate::journal::DecisionJournal;
+use crate::provider::LlmProvider;
+use crate::service::betting::BettingState;
+
+use super::address::ValidateAddressTool;
+use super::archive::{ArchiveCreateTool, ArchiveExtractTool};
+use super::filesystem::{EditFileTool, ListDirTool, ReadFileTool, WriteFileTool};
+use super::journal::JournalDecisionTool;
+use super::prediction::tool_predict::PredictionState;
+use super::prediction::{GraphQueryTool, PredictTool, SimulateTool};
+use super::market::MarketOverviewTool;
+use super::news::NewsSearchTool;
+use super::pipeline::RunPipelineTool;
+use super::memory::{ForgetTool, RecallTool, RememberTool};
+use super::profile::UpdateProfileTool;
+use super::qr::MakeQrTool;
+use super::schedule::{CancelScheduleTool, ListSchedulesTool, ScheduleTaskTool};
+use super::shell::ExecTool;
+use super::sports::SportsScoresTool;
+use super::timeline::RecallEntityTool;
+use super::wallets::{ListWalletsTool, WalletBook};
+use super::web::{WebFetchTool, WebSearchTool};
+use super::{IntentCategory, Tool, ToolRegistry, ToolStats};
+
+/// Builds a [`ToolRegistry`] containing every built-in tool allowed by config.
+///
+/// Optional dependencies (cron service, betting state, LLM provider) gate the
+/// tools that need them: tools whose dependency was not supplied are skipped.
+pub struct ToolSetBuilder<'a> {
+    config: &'a Config,
+    client: reqwest::Client,
+    cron: Option<Arc<Mutex<CronService>>>,
+    default_channel: String,
+    default_chat_id: String,
+    #[cfg_attr(not(feature = "polymarket"), allow(dead_code))]
+    betting_state: Option<Arc<Mutex<BettingState>>>,
+    provider: Option<Arc<Mutex<Box<dyn LlmProvider>>>>,
+}
+
+impl<'a> ToolSetBuilder<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            cron: None,
+            default_channel: "cli".into(),
+            default_chat_id: "direct".into(),
+            betting_state: None,
+            provider: None,
+        }
+    }
+
+    /// Share an existing HTTP client instead of creating a new one.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Enable the schedule tools. Jobs created by the agent deliver to
+    /// `channel`/`chat_id` unless told otherwise.
+    pub fn cron(
+        mut self,
+        cron: Arc<Mutex<CronService>>,
+        channel: impl Into<String>,
+        chat_id: impl Into<String>,
+    ) -> Self {
+        self.cron = Some(cron);
+        self.default_channel = channel.into();
+        self.default_chat_id = chat_id.into();
+        self
+    }
+
+    /// Enable the betting control tool.
+    pub fn betting_state(mut self, state: Arc<Mutex<BettingState>>) -> Self {
+        self.betting_state = Some(state);
+        self
+    }
+
+    /// Enable the prediction engine tools, which share the agent's provider.
+    pub fn provider(mut self, provider: Arc<Mutex<Box<dyn LlmProvider>>>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Construct the registry.
+    pub fn build(self) -> ToolRegistry {
+        let mut set = FilteredRegistry {
+            config: self.config,
+            registry: ToolRegistry::new(),
+            skipped: Vec::new(),
+        };
+        let tc = &self.config.tools;
+        let workspace = self.config.workspace_path();
+        let restrict = tc.restrict_to_workspace;
+        let client = &self.client;
+        let stats = ToolStats::new(&workspace, tc.slow_tool_p95_ms);
+        let wallets = Arc::new(WalletBook::from_config(tc, &workspace));
+        let journal = Arc::new(DecisionJournal::new(&workspace));
+
+        // Filesystem + shell
+        set.add(ReadFileTool::new(workspace.clone(), restrict), IntentCategory::System);
+        set.add(WriteFileTool::new(workspace.clone(), restrict), IntentCategory::System);
+        set.add(EditFileTool::new(workspace.clone(), restrict), IntentCategory::System);
+        set.add(ListDirTool::new(workspace.clone(), restrict), IntentCategory::System);
+        set.add(ArchiveCreateTool::new(workspace.clone()), IntentCategory::System);
+        set.add(ArchiveExtractTool::new(workspace.clone()), IntentCategory::System);
+        #[cfg(feature = "data-tools")]
+        set.add(
+            super::table::TableAnalyzeTool::new(workspace.clone(), restrict),
+            IntentCategory::System,
+        );
+        set.add(
+            ExecTool::new(workspace.clone(), restrict, tc.exec.timeout_seconds),
+            IntentCategory::System,
+        );
+
+        // Per-user profiles and long-term memory (available regardless of intent)
+        set.add(UpdateProfileTool::new(workspace.clone()), IntentCategory::General);
+        set.add(RememberTool::new(workspace.clone()), IntentCategory::General);
+        set.add(RecallTool::new(workspace.clone()), IntentCategory::General);
+        set.add(ForgetTool::new(workspace.clone()), IntentCategory::General);
+        set.add(
+            RecallEntityTool::new(workspace.clone(), self.config.chat_workspaces(), Arc::clone(&journal)),
+            IntentCategory::General,
+        );
+
+        // Decision journal, written before every order
+        set.add(JournalDecisionTool::new(Arc::clone(&journal)), IntentCategory::General);
+
+        // Charts, QR codes, address checks and the market snapshot
+        // (available regardless of intent)
+        #[cfg(feature = "charts")]
+        set.add(super::plot::PlotTool::new(workspace.clone()), IntentCategory::General);
+        set.add(MakeQrTool::new(workspace.clone()), IntentCategory::General);
+        set.add(ValidateAddressTool, IntentCategory::General);
+        set.add(MarketOverviewTool::new(client.clone()), IntentCategory::General);
+        set.add(
+            ListWalletsTool::new(
+                client.clone(),
+                Arc::clone(&wallets),
+                &tc.solana_rpc_url,
+                &tc.polymarket.rpc_url,
+            ),
+            IntentCategory::General,
+        );
+
+        // Pipelines of the tools above, run without the model
+        if !tc.pipelines.is_empty() {
+            set.add(RunPipelineTool::new(tc.pipelines.clone()), IntentCategory::General);
+        }
+
+        // Web
+        set.add(WebFetchTool::new(client.clone()), IntentCategory::Research);
+        if !tc.web_search.api_key.is_empty() {
+            let ws_key = crate::vault::decrypt(&tc.web_search.api_key).unwrap_or_else(|e| {
+                warn!("Failed to decrypt WebSearch API key: {}", e);
+                tc.web_search.api_key.clone()
+            });
+            set.add(
+                WebSearchTool::new(client.clone(), &ws_key, tc.web_search.max_results),
+                IntentCategory::Research,
+            );
+        }
+        let news_key = match (&tc.news.provider, tc.news.api_key.is_empty()) {
+            (NewsProvider::Brave, true) => &tc.web_search.api_key,
+            _ => &tc.news.api_key,
+        };
+        if !news_key.is_empty() {
+            let news_key = crate::vault::decrypt(news_key).unwrap_or_else(|e| {
+                warn!("Failed to decrypt news API key: {}", e);
+                news_key.clone()
+            });
+            set.add(
+                NewsSearchTool::new(client.clone(), &tc.news, &news_key),
+                IntentCategory::Research,
+            );
+        }
+
+        // Sports scores, offered alongside the Polymarket sports markets
+        let football_key = crate::vault::decrypt(&tc.sports.api_football_key).unwrap_or_else(|e| {
+            warn!("Failed to decrypt API-Football key: {}", e);
+            tc.sports.api_football_key.clone()
+        });
+        set.add(
+            SportsScoresTool::new(client.clone(), &tc.sports, &football_key),
+            IntentCategory::PolymarketRead,
+        );
+
+        // Schedule tools (LLM-powered cron via natural language)
+        if let Some(ref cron) = self.cron {
+            set.add(
+                ScheduleTaskTool::new(
+                    Arc::clone(cron),
+                    self.default_channel.clone(),
+                    self.default_chat_id.clone(),
+                    LocaleSettings::from_config(&self.config.agents),
+                ),
+                IntentCategory::System,
+            );
+            set.add(ListSchedulesTool::new(Arc::clone(cron)), IntentCategory::System);
+            set.add(CancelScheduleTool::new(Arc::clone(cron)), IntentCategory::System);
+        }
+
+        #[cfg(feature = "crypto-tools")]
+        register_crypto_tools(&mut set, client, tc, &wallets);
+
+        #[cfg(feature = "polymarket")]
+        register_polymarket_tools(&mut set, self.config, self.betting_state.as_ref(), &wallets);
+
+        // Prediction engine tools (share LLM provider via Arc<Mutex<...>>)
+        if let Some(ref provider) = self.provider {
+            let state = Arc::new(PredictionState {
+                provider: Arc::clone(provider),
+                workspace: workspace.clone(),
+            });
+            set.add(PredictTool { state: Arc::clone(&state) }, IntentCategory::Prediction);
+            set.add(SimulateTool { state }, IntentCategory::Prediction);
+            set.add(GraphQueryTool { workspace: workspace.clone() }, IntentCategory::Prediction);
+        }
+
+        let mut registry = set.finish();
+        registry.set_stats(stats);
+        registry.set_read_only(tc.read_only);
+        registry.set_journal(journal, tc.require_decision_journal);
+        let guardrails = Guardrails::new(&self.config.guardrails, self.config.agents.defaults.timezone.as_deref())
+            .unwrap_or_else(|e| {
+                error!("{}; refusing every tool call", e);
+                Guardrails::deny_all(format!("Tool calls are off until the guardrails are fixed: {}", e))
+            });
+        if !self.config.approvals.rules.is_empty() || guardrails.requires_approvals() {
+            registry.set_approvals(Arc::new(Approvals::new(&workspace, self.config.approvals.clone())));
+        }
+        if !guardrails.is_empty() {
+            registry.set_guardrails(Arc::new(guardrails));
+        }
+        registry
+    }
+}
+
+#[cfg(feature = "crypto-tools")]
+fn register_crypto_tools(
+    set: &mut FilteredRegistry<'_>,
+    client: &reqwest::Client,
+    tc: &crate::config::ToolsConfig,
+    wallets: &Arc<WalletBook>,
+) {
+    use super::alpha_summary::AlphaSummaryTool;
+    use super::fees::FeeEstimateTool;
+    use super::jupiter::{JupPriceTool, JupQuoteTool};
+    use super::nft::{NftMetadataTool, SolanaNftsTool};
+    use super::rugcheck::RugCheckTool;
+    use super::sentiment::SentimentTool;
+    use super::solana::{SolanaBalanceTool, SolanaTokenBalancesTool, SolanaTransactionsTool};
+    use super::solana_stream::SolanaStreamTool;
+    use super::solana_tx::SolanaSimulateTool;
+
+    let rpc_url = tc.solana_rpc_url.as_str();
+
+    // Solana tools (crypto-native on-chain data)
+    set.add(
+        SolanaBalanceTool::new(client.clone(), rpc_url, Arc::clone(wallets)),
+        IntentCategory::CryptoTokens,
+    );
+    set.add(
+        SolanaTransactionsTool::new(client.clone(), rpc_url, Arc::clone(wallets)),
+        IntentCategory::CryptoTokens,
+    );
+    set.add(
+        SolanaTokenBalancesTool::new(client.clone(), rpc_url, Arc::clone(wallets)),
+        IntentCategory::CryptoTokens,
+    );
+    set.add(SolanaSimulateTool::new(client.clone(), rpc_url), IntentCategory::CryptoTokens);
+    set.add(SolanaStreamTool::new(rpc_url, Arc::clone(wallets)), IntentCategory::CryptoTokens);
+
+    set.add(
+        FeeEstimateTool::new(client.clone(), rpc_url, &tc.polymarket.rpc_url, &tc.ethereum_rpc_url),
+        IntentCategory::CryptoTokens,
+    );
+
+    // NFTs
+    set.add(
+        SolanaNftsTool::new(client.clone(), &tc.nft, rpc_url, Arc::clone(wallets)),
+        IntentCategory::CryptoTokens,
+    );
+    set.add(NftMetadataTool::new(client.clone(), &tc.nft, rpc_url), IntentCategory::CryptoTokens);
+
+    // Token analysis
+    set.add(RugCheckTool::new(client.clone()), IntentCategory::CryptoTokens);
+    set.add(SentimentTool::new(client.clone()), IntentCategory::CryptoTokens);
+    set.add(AlphaSummaryTool::new(client.clone()), IntentCategory::CryptoTokens);
+
+    // Prices and swap quotes
+    set.add(JupPriceTool::new(client.clone()), IntentCategory::CryptoTokens);
+    set.add(JupQuoteTool::new(client.clone()), IntentCategory::CryptoTokens);
+}
+
+#[cfg(feature = "polymarket")]
+fn register_polymarket_tools(
+    set: &mut FilteredRegistry<'_>,
+    config: &Config,
+    betting_state: Option<&Arc<Mutex<BettingState>>>,
+    wallets: &Arc<WalletBook>,
+) {
+    use super::betting_control::BettingControlTool;
+    use super::polymarket::{PolymarketMarketTool, PolymarketSearchTool, PolymarketTrendingTool};
+    use super::polymarket_approve::PolymarketApproveTool;
+    use super::polymarket_bridge::PolymarketBridgeTool;
+    use super::polymarket_comments::PolymarketCommentsTool;
+    use super::polymarket_ctf::{PolymarketCtfMergeTool, PolymarketCtfRedeemTool, PolymarketCtfSplitTool};
+    use super::polymarket_data::{
+        PolymarketActivityTool, PolymarketBuilderLeaderboardTool, PolymarketClosedPositionsTool,
+        PolymarketHoldersTool, PolymarketLeaderboardTool, PolymarketOpenInterestTool,
+        PolymarketPositionsTool, PolymarketTradesTool, PolymarketVolumeTool,
+    };
+    use super::polymarket_events::{PolymarketEventDetailTool, PolymarketEventsTool};
+    use super::polymarket_orderbook::{
+        PolymarketClobMarketTool, PolymarketDepthTool, PolymarketLastTradeTool, PolymarketOrderbookTool,
+        PolymarketTickSizeTool,
+    };
+    use super::polymarket_orders::{
+        PolymarketAccountStatusTool, PolymarketApiKeysTool, PolymarketBalanceTool,
+        PolymarketCancelOrderTool, PolymarketMyOrdersTool, PolymarketNotificationsTool,
+        PolymarketRewardsTool,
+    };
+    use super::polymarket_prices::{PolymarketPriceHistoryTool, PolymarketPriceTool};
+    use super::polymarket_profiles::PolymarketProfileTool;
+    use super::polymarket_series::PolymarketSeriesTool;
+    use super::polymarket_sports::PolymarketSportsTool;
+    use super::polymarket_status::PolymarketStatusTool;
+    use super::polymarket_stream::PolymarketStreamTool;
+    use super::polymarket_tags::PolymarketTagsTool;
+    use super::polymarket_trade::{PolymarketCreateOrderTool, PolymarketMarketOrderTool};
+    use super::polymarket_wallet::{
+        PolymarketWalletCreateTool, PolymarketWalletImportTool, PolymarketWalletTool,
+    };
+
+    // Polymarket read-only tools (markets, events, prices, data)
+    let mut pm = config.tools.polymarket.clone();
+    if let Some(ref pk) = pm.private_key {
+        pm.private_key = Some(crate::vault::decrypt(pk).unwrap_or_else(|e| {
+            warn!("Failed to decrypt Polymarket private key: {}", e);
+            pk.clone()
+        }));
+    }
+    let read = IntentCategory::PolymarketRead;
+    set.add(PolymarketTrendingTool::new(pm.clone()), read);
+    set.add(PolymarketSearchTool::new(pm.clone()), read);
+    set.add(PolymarketMarketTool::new(pm.clone()), read);
+    set.add(PolymarketEventsTool::new(pm.clone()), read);
+    set.add(PolymarketEventDetailTool::new(pm.clone()), read);
+    set.add(PolymarketPriceTool::new(pm.clone()), read);
+    set.add(PolymarketPriceHistoryTool::new(pm.clone()), read);
+    set.add(PolymarketOrderbookTool::new(pm.clone()), read);
+    set.add(PolymarketDepthTool::new(pm.clone()), read);
+    set.add(PolymarketLastTradeTool::new(pm.clone()), read);
+    set.add(PolymarketClobMarketTool::new(pm.clone()), read);
+    set.add(PolymarketTickSizeTool::new(pm.clone()), read);
+    set.add(PolymarketPositionsTool::new(), read);
+    set.add(PolymarketLeaderboardTool::new(), read);
+    set.add(PolymarketClosedPositionsTool::new(), read);
+    set.add(PolymarketTradesTool::new(), read);
+    set.add(PolymarketActivityTool::new(), read);
+    set.add(PolymarketHoldersTool::new(), read);
+    set.add(PolymarketOpenInterestTool::new(), read);
+    set.add(PolymarketVolumeTool::new(), read);
+    set.add(PolymarketBuilderLeaderboardTool::new(), read);
+    set.add(PolymarketBridgeTool::new(), read);
+    set.add(PolymarketStatusTool::new(), read);
+    set.add(PolymarketStreamTool::new(), read);
+
+    // Polymarket Gamma browsing (tags, series, comments, profiles, sports)
+    set.add(PolymarketTagsTool::new(), read);
+    set.add(PolymarketSeriesTool::new(), read);
+    set.add(PolymarketCommentsTool::new(), read);
+    set.add(PolymarketProfileTool::new(), read);
+    set.add(PolymarketSportsTool::new(), read);
+
+    // Polymarket authenticated trading tools (need POLYMARKET_PRIVATE_KEY)
+    let trade = IntentCategory::PolymarketTrade;
+    set.add(PolymarketCreateOrderTool::new(pm.clone(), Arc::clone(wallets)), trade);
+    set.add(PolymarketMarketOrderTool::new(pm.clone(), Arc::clone(wallets)), trade);
+    set.add(PolymarketMyOrdersTool::new(pm.clone()), trade);
+    set.add(PolymarketCancelOrderTool::new(pm.clone()), trade);
+    set.add(PolymarketBalanceTool::new(pm.clone()), trade);
+    set.add(PolymarketWalletTool::new(pm.clone()), trade);
+    set.add(PolymarketWalletCreateTool::new(), trade);
+    set.add(PolymarketWalletImportTool::new(), trade);
+    set.add(PolymarketRewardsTool::new(pm.clone()), trade);
+    set.add(PolymarketNotificationsTool::new(pm.clone()), trade);
+    set.add(PolymarketApiKeysTool::new(pm.clone()), trade);
+    set.add(PolymarketAccountStatusTool::new(pm.clone()), trade);
+
+    // Polymarket on-chain tools (need wallet + MATIC)
+    set.add(PolymarketCtfSplitTool::new(pm.clone()), trade);
+    set.add(PolymarketCtfMergeTool::new(pm.clone()), trade);
+    set.add(PolymarketCtfRedeemTool::new(pm.clone()), trade);
+    set.add(PolymarketApproveTool::new(pm), trade);
+
+    if let Some(bs) = betting_state {
+        set.add(BettingControlTool::new(Arc::clone(bs)), trade);
+    }
+}
+
+/// Registry wrapper that consults the config allow/deny lists.
+struct FilteredRegistry<'a> {
+    config: &'a Config,
+    registry: ToolRegistry,
+    skipped: Vec<String>,
+}
+
+impl FilteredRegistry<'_> {
+    fn add(&mut self, tool: impl Tool + 'static, category: IntentCategory) {
+        if self.config.tools.is_tool_enabled(tool.name()) {
+            self.registry.register(Box::new(tool), category);
+        } else {
+            self.skipped.push(tool.name().to_string());
+        }
+    }
+
+    fn finish(self) -> ToolRegistry {
+        let tc = &self.config.tools;
+        for name in tc.enabled.iter().chain(&tc.disabled) {
+            if !self.registry.has(name) && !self.skipped.contains(name) {
+                warn!(tool = %name, "tools.enabled/disabled references an unknown tool");
+            }
+        }
+        if !self.skipped.is_empty() {
+            info!(count = self.skipped.len(), "Tools disabled by config: {}", self.skipped.join(", "));
+        }
+        let registry = self.registry;
+        for (alias, target) in &tc.aliases {
+            if let Err(e) = registry.alias(alias, target) {
+                warn!(alias = %alias, "tools.aliases: {}", e);
+            }
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_tools_are_not_registered() {
+        let mut config = Config::default();
+        config.tools.disabled = vec!["exec".into(), "web_fetch".into()];
+        let tools = ToolSetBuilder::new(&config).build();
+
+        assert!(!tools.has("exec"));
+        assert!(!tools.has("web_fetch"));
+        assert!(tools.has("read_file"));
+        // Prediction tools need a provider.
+        assert!(!tools.has("predict"));
+    }
+
+    #[test]
+    fn test_enabled_list_restricts_registry() {
+        let mut config = Config::default();
+        config.tools.enabled = vec!["read_file".into(), "list_dir".into()];
+        let tools = ToolSetBuilder::new(&config).build();
+
+        assert_eq!(tools.len(), 2);
+        assert!(tools.has("read_file"));
+        assert!(tools.has("list_dir"));
+    }
+
+    #[test]
+    fn test_builtin_tool_schemas_are_valid() {
+        let config = Config::default();
+        let cron = Arc::new(Mutex::new(CronService::new(&std::env::temp_dir())));
+        let tools = ToolSetBuilder::new(&config).cron(cron, "cli", "direct").build();
+
+        assert_eq!(tools.validate_schemas(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,241 @@
+//! Per-call execution context handed to every tool.
+//!
+//! Carries who is asking and where the answer goes, so a single tool
+//! instance can serve every channel and chat.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::agent::activity::{Activity, ActivityLog};
+use crate::agent::locale::ChatLocale;
+use crate::bus::events::{Button, OutboundMessage};
+use crate::bus::MessageBus;
+
+use super::ToolRegistry;
+
+/// Context for a single tool invocation.
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    /// Channel the request came from (e.g. "telegram", "cli").
+    pub channel: String,
+    /// Chat the request came from.
+    pub chat_id: String,
+    /// User who sent the request; empty for system-triggered turns.
+    pub user_id: String,
+    /// Session key; `channel:chat_id` unless set with
+    /// [`with_session`](Self::with_session).
+    pub session_key: String,
+    /// Workspace of the chat the request came from; empty when the caller
+    /// set none (see [`workspace_or`](Self::workspace_or)).
+    pub workspace: PathBuf,
+    /// Whether the user explicitly confirmed this action (e.g. via an inline
+    /// button), letting guarded tools skip their own confirmation step.
+    pub approved: bool,
+    /// Whether the call is a `run_pipeline` step, whose output the next step
+    /// reads as data rather than the model as text.
+    pub in_pipeline: bool,
+    /// Timezone and locale of the chat, for writing numbers, amounts and
+    /// times the way the user reads them.
+    pub locale: ChatLocale,
+    /// The only mutating tools this turn may call; `None` allows all of
+    /// them. Webhook turns carry their hook's `tools` here.
+    pub mutating_tools: Option<Vec<String>>,
+    /// Cancelled when the turn is, so a call that outlives it is dropped.
+    pub cancel: CancellationToken,
+    bus: Option<Arc<MessageBus>>,
+    activity: Option<ActivityLog>,
+    /// Images passed to [`show_image`](Self::show_image); `None` when the
+    /// model can't look at them.
+    images: Option<Arc<Mutex<Vec<PathBuf>>>>,
+    /// The registry the call came through, for tools that call other tools.
+    tools: Option<Arc<ToolRegistry>>,
+}
+
+impl ToolContext {
+    pub fn new(channel: impl Into<String>, chat_id: impl Into<String>) -> Self {
+        let channel = channel.into();
+        let chat_id = chat_id.into();
+        Self {
+            session_key: format!("{}:{}", channel, chat_id),
+            channel,
+            chat_id,
+            ..Default::default()
+        }
+    }
+
+    /// Context for calls made by background services rather than a user.
+    pub fn system() -> Self {
+        Self::new("system", "internal")
+    }
+
+    pub fn with_user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = user_id.into();
+        self
+    }
+
+    /// Use `session_key` when the conversation isn't named after its chat,
+    /// as with per-user or shared session scopes.
+    pub fn with_session(mut self, session_key: impl Into<String>) -> Self {
+        self.session_key = session_key.into();
+        self
+    }
+
+    pub fn with_workspace(mut self, workspace: impl Into<PathBuf>) -> Self {
+        self.workspace = workspace.into();
+        self
+    }
+
+    pub fn with_locale(mut self, locale: ChatLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn with_bus(mut self, bus: Option<Arc<MessageBus>>) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Also record progress updates in the session's activity log.
+    pub fn with_activity(mut self, activity: ActivityLog) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Refuse mutating calls to any tool not in `tools`.
+    pub fn with_mutating_tools(mut self, tools: Option<Vec<String>>) -> Self {
+        self.mutating_tools = tools;
+        self
+    }
+
+    /// Whether this turn may make a mutating call to `tool`.
+    pub fn may_mutate(&self, tool: &str) -> bool {
+        self.mutating_tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == tool))
+    }
+
+    pub fn with_approval(mut self, approved: bool) -> Self {
+        self.approved = approved;
+        self
+    }
+
+    /// Mark the call as a pipeline step (see [`in_pipeline`](Self::in_pipeline)).
+    pub fn with_pipeline(mut self) -> Self {
+        self.in_pipeline = true;
+        self
+    }
+
+    /// Collect the images this call shows the model (see
+    /// [`show_image`](Self::show_image)).
+    pub fn with_images(mut self) -> Self {
+        self.images = Some(Arc::default());
+        self
+    }
+
+    /// Let the tool call other tools of `tools` (see [`tools`](Self::tools)).
+    pub fn with_tools(mut self, tools: Arc<ToolRegistry>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// The registry the call came through; `None` when the caller didn't
+    /// pass it on.
+    pub fn tools(&self) -> Option<&Arc<ToolRegistry>> {
+        self.tools.as_ref()
+    }
+
+    /// The chat's workspace, or `default` (the tool's own) when none was set.
+    pub fn workspace_or<'a>(&'a self, default: &'a Path) -> &'a Path {
+        if self.workspace.as_os_str().is_empty() {
+            default
+        } else {
+            &self.workspace
+        }
+    }
+
+    /// Whether this call originates from a chat (as opposed to a background service).
+    pub fn has_chat(&self) -> bool {
+        !self.chat_id.is_empty() && self.channel != "system"
+    }
+
+    /// Push a progress update to the originating chat, if a bus is attached.
+    pub async fn progress(&self, text: impl Into<String>) {
+        let text = text.into();
+        if let Some(activity) = &self.activity {
+            activity.record(&self.session_key, Activity::Progress(&text));
+        }
+        if let Some(bus) = &self.bus {
+            bus.publish_outbound(OutboundMessage::progress(&self.channel, &self.chat_id, text))
+                .await;
+        }
+    }
+
+    /// Send a message with buttons to the originating chat. Returns `false`
+    /// when there is no chat or bus to send it through.
+    pub async fn send_reply(&self, text: impl Into<String>, buttons: Vec<Button>) -> bool {
+        match &self.bus {
+            Some(bus) if self.has_chat() => {
+                bus.publish_outbound(OutboundMessage::reply_with_buttons(&self.channel, &self.chat_id, text, buttons))
+                    .await;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Show an image file to the model along with this call's result, so it
+    /// can check what it produced. Returns `false` when the model can't look
+    /// at images; the result text should then stand on its own.
+    pub fn show_image(&self, path: &Path) -> bool {
+        match &self.images {
+            Some(images) => {
+                images.lock().unwrap_or_else(|e| e.into_inner()).push(path.to_path_buf());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The images shown so far, removed from the context.
+    pub fn take_images(&self) -> Vec<PathBuf> {
+        self.images
+            .as_ref()
+            .map(|images| std::mem::take(&mut *images.lock().unwrap_or_else(|e| e.into_inner())))
+            .unwrap_or_default()
+    }
+
+    /// Send a file to the originating chat. Returns `false` when there is
+    /// no chat or bus to send it through, so the caller can point the user
+    /// at the file instead.
+    pub async fn send_attachment(&self, path: &Path, caption: impl Into<String>) -> bool {
+        match &self.bus {
+            Some(bus) if self.has_chat() => {
+                bus.publish_outbound(OutboundMessage::attachment(&self.channel, &self.chat_id, path, caption))
+                    .await;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolContext")
+            .field("channel", &self.channel)
+            .field("chat_id", &self.chat_id)
+            .field("user_id", &self.user_id)
+            .field("session_key", &self.session_key)
+            .field("workspace", &self.workspace)
+            .field("approved", &self.approved)
+            .field("in_pipeline", &self.in_pipeline)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,309 @@
+//! Network fee estimates: Solana priority fees and EVM gas prices.
+//!
+//! [`solana_priority_fees`] and [`evm_gas`] turn recent on-chain fee data
+//! into cheap / standard / fast levels, so tools that build transactions
+//! can pick a priority fee from current conditions instead of a constant.
+//! `fee_estimate` reports the same numbers to the user.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::solana::SolanaRpc;
+use super::{Tool, ToolContext};
+
+/// Base fee per Solana signature, in lamports.
+const SOLANA_BASE_FEE: u64 = 5_000;
+/// Compute units assumed for a typical swap when pricing a transaction.
+const TYPICAL_SWAP_CU: u64 = 200_000;
+/// Gas of a plain native-token transfer.
+const TRANSFER_GAS: f64 = 21_000.0;
+/// Blocks of EVM fee history to average over.
+const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// Fee levels from cheapest to fastest inclusion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeLevels<T> {
+    pub cheap: T,
+    pub standard: T,
+    pub fast: T,
+}
+
+/// Value at `pct` (0–100) of `sorted`, nearest-rank.
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+/// Fee levels from `getRecentPrioritizationFees` samples. Slots where
+/// nobody paid a priority fee are ignored; they say nothing about what it
+/// takes to land a transaction under load.
+fn priority_levels(samples: &Value) -> FeeLevels<u64> {
+    let mut fees: Vec<u64> = samples
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s["prioritizationFee"].as_u64())
+        .filter(|f| *f > 0)
+        .collect();
+    fees.sort_unstable();
+    FeeLevels {
+        cheap: percentile(&fees, 25),
+        standard: percentile(&fees, 50),
+        fast: percentile(&fees, 90),
+    }
+}
+
+/// Priority fee levels in micro-lamports per compute unit over the last
+/// ~150 slots. Pass the transaction's writable accounts to price contention
+/// on those accounts (e.g. a hot pool) rather than the whole network.
+pub(super) async fn solana_priority_fees(rpc: &SolanaRpc, accounts: &[String]) -> Result<FeeLevels<u64>, String> {
+    let params = if accounts.is_empty() { json!([]) } else { json!([accounts]) };
+    let data = rpc.call("getRecentPrioritizationFees", params).await?;
+    Ok(priority_levels(&data["result"]))
+}
+
+/// EVM fee suggestion (EIP-1559), in gwei.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvmGas {
+    /// Base fee of the next block.
+    pub base_fee: f64,
+    /// Priority tips.
+    pub tip: FeeLevels<f64>,
+}
+
+impl EvmGas {
+    /// `maxFeePerGas` for a tip level, leaving room for two full blocks of
+    /// base fee increases.
+    pub fn max_fee(&self, tip: f64) -> f64 {
+        2.0 * self.base_fee + tip
+    }
+}
+
+fn hex_u128(v: &Value) -> Option<u128> {
+    u128::from_str_radix(v.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+/// Gas levels from an `eth_feeHistory` result requested with reward
+/// percentiles 25, 50 and 90.
+fn evm_levels(history: &Value) -> Option<EvmGas> {
+    const GWEI: f64 = 1e9;
+    let base_fee = hex_u128(history["baseFeePerGas"].as_array()?.last()?)? as f64 / GWEI;
+    let rewards: Vec<&Vec<Value>> = history["reward"].as_array()?.iter().filter_map(|r| r.as_array()).collect();
+    let avg = |i: usize| {
+        let values: Vec<f64> = rewards.iter().filter_map(|r| hex_u128(r.get(i)?)).map(|v| v as f64 / GWEI).collect();
+        if values.is_empty() {
+            0.0
+        } else {
+            values.iter().sum::<f64>() / values.len() as f64
+        }
+    };
+    Some(EvmGas {
+        base_fee,
+        tip: FeeLevels {
+            cheap: avg(0),
+            standard: avg(1),
+            fast: avg(2),
+        },
+    })
+}
+
+/// Current gas levels of the EVM chain at `rpc_url`.
+pub async fn evm_gas(client: &Client, rpc_url: &str) -> Result<EvmGas, String> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_feeHistory",
+        "params": [format!("{:#x}", FEE_HISTORY_BLOCKS), "latest", [25, 50, 90]]
+    });
+    let resp: Value = client
+        .post(rpc_url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("network error: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("invalid response: {}", e))?;
+    if let Some(msg) = resp["error"]["message"].as_str() {
+        return Err(msg.to_string());
+    }
+    evm_levels(&resp["result"]).ok_or_else(|| "unexpected eth_feeHistory response".into())
+}
+
+fn render_solana(levels: &FeeLevels<u64>) -> String {
+    let cost = |price: u64| {
+        let lamports = SOLANA_BASE_FEE + price * TYPICAL_SWAP_CU / 1_000_000;
+        format!("{:.9} SOL", lamports as f64 / 1e9)
+    };
+    let row = |name: &str, price: u64| {
+        format!("- {}: {} µlamports/CU · swap ≈ {}", name, price, cost(price))
+    };
+    [
+        "◎ *Solana priority fees*".to_string(),
+        row("Cheap", levels.cheap),
+        row("Standard", levels.standard),
+        row("Fast", levels.fast),
+    ]
+    .join("\n")
+}
+
+fn render_evm(name: &str, symbol: &str, gas: &EvmGas) -> String {
+    let row = |level: &str, tip: f64| {
+        format!(
+            "- {}: tip {:.3} gwei, max fee {:.3} gwei · transfer ≈ {:.6} {}",
+            level,
+            tip,
+            gas.max_fee(tip),
+            (gas.base_fee + tip) * TRANSFER_GAS / 1e9,
+            symbol
+        )
+    };
+    [
+        format!("⛽ *{} gas* (base fee {:.3} gwei)", name, gas.base_fee),
+        row("Cheap", gas.tip.cheap),
+        row("Standard", gas.tip.standard),
+        row("Fast", gas.tip.fast),
+    ]
+    .join("\n")
+}
+
+// ── FeeEstimateTool ─────────────────────────────────────────────────
+
+pub struct FeeEstimateTool {
+    client: Client,
+    solana: SolanaRpc,
+    polygon_rpc_url: String,
+    ethereum_rpc_url: String,
+}
+
+impl FeeEstimateTool {
+    pub fn new(client: Client, solana_rpc_url: &str, polygon_rpc_url: &str, ethereum_rpc_url: &str) -> Self {
+        Self {
+            solana: SolanaRpc::new(client.clone(), solana_rpc_url),
+            client,
+            polygon_rpc_url: polygon_rpc_url.to_string(),
+            ethereum_rpc_url: ethereum_rpc_url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FeeEstimateTool {
+    fn name(&self) -> &str {
+        "fee_estimate"
+    }
+
+    fn description(&self) -> &str {
+        "Current network fees with cheap/standard/fast levels: Solana priority fees \
+         (micro-lamports per compute unit) and Polygon/Ethereum gas prices (gwei), each with \
+         the cost of a typical transaction."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "chain": {
+                    "type": "string",
+                    "enum": ["all", "solana", "polygon", "ethereum"],
+                    "description": "Chain to estimate (default: all)"
+                },
+                "accounts": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Solana accounts the transaction writes to, to price contention on them (optional)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let chain = args.get("chain").and_then(|v| v.as_str()).unwrap_or("all");
+        if !["all", "solana", "polygon", "ethereum"].contains(&chain) {
+            return format!("Error: unknown chain '{}'", chain);
+        }
+        let accounts: Vec<String> = args
+            .get("accounts")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|a| a.as_str().map(String::from))
+            .take(128)
+            .collect();
+        let wants = |c: &str| chain == "all" || chain == c;
+
+        let (solana, polygon, ethereum) = tokio::join!(
+            async {
+                if !wants("solana") {
+                    return None;
+                }
+                Some(match solana_priority_fees(&self.solana, &accounts).await {
+                    Ok(levels) => render_solana(&levels),
+                    Err(e) => format!("◎ Solana: ❌ {}", e),
+                })
+            },
+            async {
+                if !wants("polygon") {
+                    return None;
+                }
+                Some(match evm_gas(&self.client, &self.polygon_rpc_url).await {
+                    Ok(gas) => render_evm("Polygon", "POL", &gas),
+                    Err(e) => format!("⛽ Polygon: ❌ {}", e),
+                })
+            },
+            async {
+                if !wants("ethereum") {
+                    return None;
+                }
+                Some(match evm_gas(&self.client, &self.ethereum_rpc_url).await {
+                    Ok(gas) => render_evm("Ethereum", "ETH", &gas),
+                    Err(e) => format!("⛽ Ethereum: ❌ {}", e),
+                })
+            },
+        );
+        [solana, polygon, ethereum].into_iter().flatten().collect::<Vec<_>>().join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solana_priority_levels() {
+        let samples = json!([
+            {"slot": 1, "prioritizationFee": 0},
+            {"slot": 2, "prioritizationFee": 1000},
+            {"slot": 3, "prioritizationFee": 5000},
+            {"slot": 4, "prioritizationFee": 2000},
+            {"slot": 5, "prioritizationFee": 100000}
+        ]);
+        let levels = priority_levels(&samples);
+        assert_eq!(levels, FeeLevels { cheap: 1000, standard: 2000, fast: 100000 });
+        assert_eq!(priority_levels(&json!([])), FeeLevels { cheap: 0, standard: 0, fast: 0 });
+        assert!(render_solana(&levels).contains("- Standard: 2000 µlamports/CU · swap ≈ 0.000005400 SOL"));
+    }
+
+    #[test]
+    fn test_evm_levels_from_fee_history() {
+        let history = json!({
+            "baseFeePerGas": ["0x3b9aca00", "0x77359400"],
+            "reward": [["0x3b9aca00", "0x77359400", "0xb2d05e00"], ["0x3b9aca00", "0x77359400", "0xee6b2800"]]
+        });
+        let gas = evm_levels(&history).unwrap();
+        assert_eq!(gas.base_fee, 2.0);
+        assert_eq!(gas.tip, FeeLevels { cheap: 1.0, standard: 2.0, fast: 3.5 });
+        assert_eq!(gas.max_fee(gas.tip.fast), 7.5);
+        assert!(evm_levels(&json!({})).is_none());
+    }
+}
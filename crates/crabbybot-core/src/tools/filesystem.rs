@@ -1,25 +1,26 @@
 //! Filesystem tools: read_file, write_file, edit_file, list_dir.
 //!
 //! These tools give the agent the ability to interact with the local
-//! filesystem. When `restrict_to_workspace` is enabled, all paths are
-//! validated to be within the workspace directory.
+//! filesystem. Relative paths are taken from the workspace of the calling
+//! chat (see [`ToolContext::workspace_or`]). When `restrict_to_workspace` is
+//! enabled, all paths are validated to be within that workspace.
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use super::Tool;
+use super::{Tool, ToolContext};
 
 // ── Helpers ─────────────────────────────────────────────────────────
 
-fn resolve_path(raw: &str, workspace: &Path, restrict: bool) -> Result<PathBuf, String> {
+pub(super) fn resolve_path(raw: &str, workspace: &Path, restrict: bool) -> Result<PathBuf, String> {
     let path = if raw.starts_with("~/") || raw.starts_with("~\\") {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join(&raw[2..])
     } else {
-        PathBuf::from(raw)
+        workspace.join(raw)
     };
 
     // Canonicalize the resolved path (or at least normalize it)
@@ -102,12 +103,16 @@ impl Tool for ReadFileTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(raw_path) = get_string_arg(&args, "path") else {
             return "Error: 'path' parameter is required".into();
         };
 
-        let path = match resolve_path(&raw_path, &self.workspace, self.restrict) {
+        let path = match resolve_path(&raw_path, ctx.workspace_or(&self.workspace), self.restrict) {
             Ok(p) => p,
             Err(e) => return e,
         };
@@ -178,7 +183,7 @@ impl Tool for WriteFileTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(raw_path) = get_string_arg(&args, "path") else {
             return "Error: 'path' parameter is required".into();
         };
@@ -186,7 +191,7 @@ impl Tool for WriteFileTool {
             return "Error: 'content' parameter is required".into();
         };
 
-        let path = match resolve_path(&raw_path, &self.workspace, self.restrict) {
+        let path = match resolve_path(&raw_path, ctx.workspace_or(&self.workspace), self.restrict) {
             Ok(p) => p,
             Err(e) => return e,
         };
@@ -252,7 +257,7 @@ impl Tool for EditFileTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(raw_path) = get_string_arg(&args, "path") else {
             return "Error: 'path' parameter is required".into();
         };
@@ -263,7 +268,7 @@ impl Tool for EditFileTool {
             return "Error: 'new_text' parameter is required".into();
         };
 
-        let path = match resolve_path(&raw_path, &self.workspace, self.restrict) {
+        let path = match resolve_path(&raw_path, ctx.workspace_or(&self.workspace), self.restrict) {
             Ok(p) => p,
             Err(e) => return e,
         };
@@ -329,12 +334,16 @@ impl Tool for ListDirTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(raw_path) = get_string_arg(&args, "path") else {
             return "Error: 'path' parameter is required".into();
         };
 
-        let path = match resolve_path(&raw_path, &self.workspace, self.restrict) {
+        let path = match resolve_path(&raw_path, ctx.workspace_or(&self.workspace), self.restrict) {
             Ok(p) => p,
             Err(e) => return e,
         };
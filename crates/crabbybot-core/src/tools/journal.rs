@@ -0,0 +1,92 @@
+//! `journal_decision` tool: record why a trade is made before making it,
+//! and read the journal back for reviews. See [`crate::journal`].
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Tool, ToolContext};
+use crate::journal::{DecisionDraft, DecisionJournal, OPEN_MINUTES};
+
+pub struct JournalDecisionTool {
+    journal: Arc<DecisionJournal>,
+}
+
+impl JournalDecisionTool {
+    pub fn new(journal: Arc<DecisionJournal>) -> Self {
+        Self { journal }
+    }
+
+    fn list(&self, days: i64) -> String {
+        let decisions = self.journal.since(Utc::now() - Duration::days(days));
+        if decisions.is_empty() {
+            return format!("No decisions recorded in the last {} days.", days);
+        }
+        let executed = decisions.iter().filter(|d| d.execution.is_some()).count();
+        let mut out = format!(
+            "📓 {} decisions in the last {} days ({} executed):\n",
+            decisions.len(),
+            days,
+            executed
+        );
+        for d in &decisions {
+            out.push('\n');
+            out.push_str(&d.render());
+        }
+        out
+    }
+}
+
+#[async_trait]
+impl Tool for JournalDecisionTool {
+    fn name(&self) -> &str {
+        "journal_decision"
+    }
+
+    fn description(&self) -> &str {
+        "Record a trading decision before placing the order: the trade, your thesis, \
+         the inputs it rests on, the expected outcome and the stop condition. Order \
+         tools refuse to run without a decision recorded in this chat in the last 30 \
+         minutes, and the order is linked to it. Pass `list_days` instead to read the \
+         journal back, e.g. for a weekly review of outcomes against expectations."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "trade": { "type": "string", "description": "The order about to be placed, e.g. 'buy 50 YES on <market> at 0.42'" },
+                "thesis": { "type": "string", "description": "Why this trade has an edge" },
+                "inputs": { "type": "array", "items": { "type": "string" }, "description": "Data the decision uses: prices, news, model outputs" },
+                "expected_outcome": { "type": "string", "description": "What should happen, and by when" },
+                "stop_condition": { "type": "string", "description": "When to exit or drop the thesis" },
+                "size_usd": { "type": "number", "description": "USD committed" },
+                "list_days": { "type": "integer", "description": "List the decisions of the last N days instead of recording one" }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        if let Some(days) = args.get("list_days").and_then(|v| v.as_i64()) {
+            return self.list(days.clamp(1, 365));
+        }
+        let draft: DecisionDraft = match serde_json::from_value(Value::Object(args.into_iter().collect())) {
+            Ok(draft) => draft,
+            Err(e) => return format!("Error: invalid decision: {}", e),
+        };
+        let missing = draft.missing();
+        if !missing.is_empty() {
+            return format!("Error: a decision needs {}", missing.join(", "));
+        }
+        match self.journal.record(draft, &ctx.channel, &ctx.chat_id) {
+            Ok(decision) => format!(
+                "📓 Decision #{} recorded. Place the order within {} minutes and it will be linked to this entry.",
+                decision.id, OPEN_MINUTES
+            ),
+            Err(e) => format!("Error saving decision: {}", e),
+        }
+    }
+}
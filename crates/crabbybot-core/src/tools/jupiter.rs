@@ -0,0 +1,489 @@
+//! Jupiter prices and swap quotes.
+//!
+//! [`JupiterClient`] wraps the keyless Jupiter lite API: USD prices for any
+//! set of mints and best-route swap quotes. `jup_price` and `jup_quote`
+//! expose it to the agent; swap execution and price alerts are meant to
+//! reuse the same client (a [`Quote`] keeps the raw response, which is what
+//! Jupiter's `/swap` endpoint expects back).
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::solana::WELL_KNOWN_TOKENS;
+use super::{Tool, ToolContext};
+
+const JUPITER_API: &str = "https://lite-api.jup.ag";
+const WRAPPED_SOL: &str = "So11111111111111111111111111111111111111112";
+const DEFAULT_SLIPPAGE_BPS: u64 = 50;
+/// Price impact (percent) above which a quote is flagged.
+const HIGH_IMPACT_PCT: f64 = 1.0;
+
+/// Mint address for a symbol (`SOL`, `usdc`, …) or a mint given as is.
+pub fn resolve_mint(token: &str) -> Result<String, String> {
+    let token = token.trim();
+    if token.eq_ignore_ascii_case("SOL") || token.eq_ignore_ascii_case("WSOL") {
+        return Ok(WRAPPED_SOL.into());
+    }
+    if let Some((mint, _)) = WELL_KNOWN_TOKENS.iter().find(|(_, l)| l.eq_ignore_ascii_case(token)) {
+        return Ok(mint.to_string());
+    }
+    if bs58::decode(token).into_vec().is_ok_and(|b| b.len() == 32) {
+        return Ok(token.to_string());
+    }
+    Err(format!("Error: unknown token '{}'. Use a mint address for tokens outside the built-in list.", token))
+}
+
+/// Short label for a mint: its symbol when known, else an abbreviation.
+fn label(mint: &str) -> String {
+    if mint == WRAPPED_SOL {
+        return "SOL".into();
+    }
+    WELL_KNOWN_TOKENS
+        .iter()
+        .find(|(m, _)| *m == mint)
+        .map(|(_, l)| l.to_string())
+        .unwrap_or_else(|| format!("{}…{}", &mint[..4.min(mint.len())], &mint[mint.len().saturating_sub(4)..]))
+}
+
+fn raw_amount(v: &Value) -> u64 {
+    v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_u64()).unwrap_or_default()
+}
+
+fn ui_amount(raw: u64, decimals: u8) -> f64 {
+    raw as f64 / 10f64.powi(decimals.into())
+}
+
+/// USD price of a token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPrice {
+    pub usd: f64,
+    pub decimals: Option<u8>,
+    /// Percent change over 24 hours.
+    pub change_24h: Option<f64>,
+}
+
+/// One hop of a route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteLeg {
+    /// AMM name (e.g. "Raydium CLMM").
+    pub label: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    /// Share of the input routed through this leg.
+    pub percent: u64,
+}
+
+/// A best-route swap quote.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    /// Least output accepted after slippage.
+    pub min_out_amount: u64,
+    pub price_impact_pct: f64,
+    pub slippage_bps: u64,
+    pub route: Vec<RouteLeg>,
+    /// Untouched API response, for building the swap transaction.
+    pub raw: Value,
+}
+
+impl Quote {
+    fn from_json(v: Value) -> Result<Self, String> {
+        if let Some(err) = v["error"].as_str() {
+            return Err(format!("Error: Jupiter: {}", err));
+        }
+        let route = v["routePlan"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|leg| RouteLeg {
+                label: leg["swapInfo"]["label"].as_str().unwrap_or("?").to_string(),
+                input_mint: leg["swapInfo"]["inputMint"].as_str().unwrap_or_default().to_string(),
+                output_mint: leg["swapInfo"]["outputMint"].as_str().unwrap_or_default().to_string(),
+                percent: leg["percent"].as_u64().unwrap_or(100),
+            })
+            .collect::<Vec<_>>();
+        if route.is_empty() {
+            return Err("Error: Jupiter returned no route for this pair".into());
+        }
+        Ok(Self {
+            input_mint: v["inputMint"].as_str().unwrap_or_default().to_string(),
+            output_mint: v["outputMint"].as_str().unwrap_or_default().to_string(),
+            in_amount: raw_amount(&v["inAmount"]),
+            out_amount: raw_amount(&v["outAmount"]),
+            min_out_amount: raw_amount(&v["otherAmountThreshold"]),
+            price_impact_pct: v["priceImpactPct"]
+                .as_str()
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or_default()
+                * 100.0,
+            slippage_bps: v["slippageBps"].as_u64().unwrap_or(DEFAULT_SLIPPAGE_BPS),
+            route,
+            raw: v,
+        })
+    }
+
+    /// Human-readable summary with the route breakdown.
+    pub fn render(&self, in_decimals: u8, out_decimals: u8) -> String {
+        let (input, output) = (label(&self.input_mint), label(&self.output_mint));
+        let in_ui = ui_amount(self.in_amount, in_decimals);
+        let out_ui = ui_amount(self.out_amount, out_decimals);
+        let mut out = vec![
+            format!("🪐 *Jupiter quote*: {} {} → {:.6} {}", in_ui, input, out_ui, output),
+            format!("Rate: 1 {} = {:.6} {}", input, out_ui / in_ui.max(f64::MIN_POSITIVE), output),
+            format!(
+                "Minimum received: {:.6} {} ({}% slippage)",
+                ui_amount(self.min_out_amount, out_decimals),
+                output,
+                self.slippage_bps as f64 / 100.0
+            ),
+            format!("Price impact: {:.3}%", self.price_impact_pct),
+            String::new(),
+            "*Route*".into(),
+        ];
+        for (i, leg) in self.route.iter().enumerate() {
+            out.push(format!(
+                "{}. {} ({}%): {} → {}",
+                i + 1,
+                leg.label,
+                leg.percent,
+                label(&leg.input_mint),
+                label(&leg.output_mint)
+            ));
+        }
+        if self.price_impact_pct > HIGH_IMPACT_PCT {
+            out.push(String::new());
+            out.push(format!(
+                "⚠️ Price impact above {}%: liquidity is thin for this size.",
+                HIGH_IMPACT_PCT
+            ));
+        }
+        out.join("\n")
+    }
+}
+
+/// Client for the Jupiter price and quote APIs.
+#[derive(Clone)]
+pub struct JupiterClient {
+    client: Client,
+    base_url: String,
+}
+
+impl JupiterClient {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            base_url: JUPITER_API.into(),
+        }
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value, String> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| format!("Error: Jupiter request failed: {}", e))?;
+        let status = resp.status();
+        let body: Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("Error: invalid Jupiter response: {}", e))?;
+        if !status.is_success() {
+            let msg = body["error"].as_str().map(String::from).unwrap_or_else(|| body.to_string());
+            return Err(format!("Error: Jupiter returned HTTP {}: {}", status, msg));
+        }
+        Ok(body)
+    }
+
+    /// USD prices keyed by mint; mints Jupiter can't price are absent.
+    pub async fn prices(&self, mints: &[String]) -> Result<HashMap<String, TokenPrice>, String> {
+        let body = self.get("/price/v3", &[("ids", mints.join(","))]).await?;
+        Ok(parse_prices(&body))
+    }
+
+    /// Best route for swapping `amount` raw units of `input_mint`.
+    pub async fn quote(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<Quote, String> {
+        let body = self
+            .get(
+                "/swap/v1/quote",
+                &[
+                    ("inputMint", input_mint.to_string()),
+                    ("outputMint", output_mint.to_string()),
+                    ("amount", amount.to_string()),
+                    ("slippageBps", slippage_bps.to_string()),
+                ],
+            )
+            .await?;
+        Quote::from_json(body)
+    }
+}
+
+fn parse_prices(body: &Value) -> HashMap<String, TokenPrice> {
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(mint, p)| {
+            Some((
+                mint.clone(),
+                TokenPrice {
+                    usd: p["usdPrice"].as_f64()?,
+                    decimals: p["decimals"].as_u64().and_then(|d| u8::try_from(d).ok()),
+                    change_24h: p["priceChange24h"].as_f64(),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn format_usd(v: f64) -> String {
+    if v >= 1.0 {
+        format!("${:.2}", v)
+    } else {
+        format!("${:.8}", v).trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+// ── JupPriceTool ────────────────────────────────────────────────────
+
+pub struct JupPriceTool {
+    jupiter: JupiterClient,
+}
+
+impl JupPriceTool {
+    pub fn new(client: Client) -> Self {
+        Self {
+            jupiter: JupiterClient::new(client),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for JupPriceTool {
+    fn name(&self) -> &str {
+        "jup_price"
+    }
+
+    fn description(&self) -> &str {
+        "Get live USD prices and 24h change of Solana tokens from Jupiter. Accepts symbols of \
+         major tokens (SOL, USDC, JUP, BONK…) or mint addresses."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "tokens": {
+                    "type": "string",
+                    "description": "Comma-separated symbols or mint addresses (max 20)"
+                }
+            },
+            "required": ["tokens"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(tokens) = args.get("tokens").and_then(|v| v.as_str()) else {
+            return "Error: 'tokens' parameter is required".into();
+        };
+        let mut mints = Vec::new();
+        for token in tokens.split(',').map(str::trim).filter(|t| !t.is_empty()).take(20) {
+            match resolve_mint(token) {
+                Ok(m) => mints.push(m),
+                Err(e) => return e,
+            }
+        }
+        if mints.is_empty() {
+            return "Error: 'tokens' parameter is required".into();
+        }
+
+        let prices = match self.jupiter.prices(&mints).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        let mut out = vec!["🪐 *Jupiter prices*".to_string()];
+        for mint in &mints {
+            out.push(match prices.get(mint) {
+                Some(p) => format!(
+                    "- {}: {}{}",
+                    label(mint),
+                    format_usd(p.usd),
+                    p.change_24h
+                        .map(|c| format!(" ({:+.2}% 24h)", c))
+                        .unwrap_or_default()
+                ),
+                None => format!("- {}: no price available", label(mint)),
+            });
+        }
+        out.join("\n")
+    }
+}
+
+// ── JupQuoteTool ────────────────────────────────────────────────────
+
+pub struct JupQuoteTool {
+    jupiter: JupiterClient,
+}
+
+impl JupQuoteTool {
+    pub fn new(client: Client) -> Self {
+        Self {
+            jupiter: JupiterClient::new(client),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for JupQuoteTool {
+    fn name(&self) -> &str {
+        "jup_quote"
+    }
+
+    fn description(&self) -> &str {
+        "Get the best swap quote between two Solana tokens from Jupiter, with expected output, \
+         minimum received after slippage, price impact and the route through each AMM. \
+         Read-only: nothing is swapped."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "input": {
+                    "type": "string",
+                    "description": "Token to sell: symbol (SOL, USDC…) or mint address"
+                },
+                "output": {
+                    "type": "string",
+                    "description": "Token to buy: symbol or mint address"
+                },
+                "amount": {
+                    "type": "number",
+                    "description": "Amount of the input token to sell, in whole tokens (e.g. 1.5)"
+                },
+                "slippage_bps": {
+                    "type": "integer",
+                    "description": "Slippage tolerance in basis points (default: 50 = 0.5%)"
+                }
+            },
+            "required": ["input", "output", "amount"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let mint_arg = |key: &str| match args.get(key).and_then(|v| v.as_str()) {
+            Some(t) => resolve_mint(t),
+            None => Err(format!("Error: '{}' parameter is required", key)),
+        };
+        let (input, output) = match (mint_arg("input"), mint_arg("output")) {
+            (Ok(i), Ok(o)) => (i, o),
+            (Err(e), _) | (_, Err(e)) => return e,
+        };
+        if input == output {
+            return "Error: input and output tokens are the same".into();
+        }
+        let amount = match args.get("amount").and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok())) {
+            Some(a) if a > 0.0 && a.is_finite() => a,
+            _ => return "Error: 'amount' must be a positive number".into(),
+        };
+        let slippage_bps = args
+            .get("slippage_bps")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_SLIPPAGE_BPS)
+            .min(5_000);
+
+        // Decimals come from the price API so amounts can be given in whole tokens.
+        let prices = match self.jupiter.prices(&[input.clone(), output.clone()]).await {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+        let decimals = |mint: &str| prices.get(mint).and_then(|p| p.decimals);
+        let (Some(in_decimals), Some(out_decimals)) = (decimals(&input), decimals(&output)) else {
+            return "Error: Jupiter has no data for one of these tokens".into();
+        };
+        let raw = (amount * 10f64.powi(in_decimals.into())).round() as u64;
+
+        match self.jupiter.quote(&input, &output, raw, slippage_bps).await {
+            Ok(quote) => {
+                let mut text = quote.render(in_decimals, out_decimals);
+                if let Some(usd) = prices.get(&input).map(|p| p.usd * amount) {
+                    text.push_str(&format!("\nValue in: ≈{}", format_usd(usd)));
+                }
+                text
+            }
+            Err(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn test_resolve_mint() {
+        assert_eq!(resolve_mint("sol").unwrap(), WRAPPED_SOL);
+        assert_eq!(resolve_mint("USDC").unwrap(), USDC);
+        assert_eq!(resolve_mint(USDC).unwrap(), USDC);
+        assert!(resolve_mint("NOTATOKEN").is_err());
+    }
+
+    #[test]
+    fn test_parse_prices() {
+        let body = json!({
+            WRAPPED_SOL: {"usdPrice": 152.31, "decimals": 9, "priceChange24h": -1.25},
+            USDC: {"usdPrice": 0.9999, "decimals": 6}
+        });
+        let prices = parse_prices(&body);
+        assert_eq!(prices[WRAPPED_SOL], TokenPrice { usd: 152.31, decimals: Some(9), change_24h: Some(-1.25) });
+        assert_eq!(prices[USDC].decimals, Some(6));
+    }
+
+    #[test]
+    fn test_quote_render() {
+        let body = json!({
+            "inputMint": WRAPPED_SOL,
+            "outputMint": USDC,
+            "inAmount": "1000000000",
+            "outAmount": "152300000",
+            "otherAmountThreshold": "151538500",
+            "slippageBps": 50,
+            "priceImpactPct": "0.0125",
+            "routePlan": [
+                {"swapInfo": {"label": "Raydium CLMM", "inputMint": WRAPPED_SOL, "outputMint": USDC}, "percent": 70},
+                {"swapInfo": {"label": "Orca", "inputMint": WRAPPED_SOL, "outputMint": USDC}, "percent": 30}
+            ]
+        });
+        let quote = Quote::from_json(body).unwrap();
+        assert_eq!(quote.out_amount, 152_300_000);
+        let text = quote.render(9, 6);
+        assert!(text.contains("1 SOL → 152.300000 USDC"), "{text}");
+        assert!(text.contains("Minimum received: 151.538500 USDC (0.5% slippage)"), "{text}");
+        assert!(text.contains("1. Raydium CLMM (70%): SOL → USDC"), "{text}");
+        assert!(text.contains("Price impact: 1.250%"), "{text}");
+        assert!(text.contains("⚠️ Price impact above 1%"), "{text}");
+
+        assert!(Quote::from_json(json!({"error": "Could not find any route"})).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! `market_overview`: the crypto market at a glance.
+//!
+//! BTC/ETH/SOL prices, total market cap, dominance and the Fear & Greed
+//! index in one call, so a heartbeat summary costs one tool round trip
+//! instead of five. Each source is fetched concurrently; one failing only
+//! blanks its own section.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{Tool, ToolContext};
+use crate::agent::locale::ChatLocale;
+
+const COINGECKO_API: &str = "https://api.coingecko.com/api/v3";
+const FEAR_GREED_API: &str = "https://api.alternative.me/fng/?limit=2";
+
+/// CoinGecko id and ticker of each asset in the overview.
+const ASSETS: &[(&str, &str)] = &[("bitcoin", "BTC"), ("ethereum", "ETH"), ("solana", "SOL")];
+
+#[derive(Debug, Clone, PartialEq)]
+struct AssetPrice {
+    symbol: &'static str,
+    usd: f64,
+    change_24h: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GlobalStats {
+    market_cap_usd: f64,
+    market_cap_change_24h: f64,
+    btc_dominance: f64,
+    eth_dominance: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FearGreed {
+    value: u64,
+    label: String,
+    /// Yesterday's reading, when the API returned it.
+    previous: Option<u64>,
+}
+
+fn parse_prices(data: &Value) -> Vec<AssetPrice> {
+    ASSETS
+        .iter()
+        .filter_map(|(id, symbol)| {
+            Some(AssetPrice {
+                symbol,
+                usd: data[id]["usd"].as_f64()?,
+                change_24h: data[id]["usd_24h_change"].as_f64().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn parse_global(data: &Value) -> Option<GlobalStats> {
+    let d = &data["data"];
+    Some(GlobalStats {
+        market_cap_usd: d["total_market_cap"]["usd"].as_f64()?,
+        market_cap_change_24h: d["market_cap_change_percentage_24h_usd"].as_f64().unwrap_or_default(),
+        btc_dominance: d["market_cap_percentage"]["btc"].as_f64().unwrap_or_default(),
+        eth_dominance: d["market_cap_percentage"]["eth"].as_f64().unwrap_or_default(),
+    })
+}
+
+/// The index API returns numbers as strings.
+fn parse_fear_greed(data: &Value) -> Option<FearGreed> {
+    let value = |i: usize| data["data"][i]["value"].as_str()?.parse::<u64>().ok();
+    Some(FearGreed {
+        value: value(0)?,
+        label: data["data"][0]["value_classification"].as_str().unwrap_or_default().to_string(),
+        previous: value(1),
+    })
+}
+
+/// `$2.41T`, `$870.5B`, `$64,210`, `$142.37`, written the chat's way.
+fn format_usd(v: f64, locale: &ChatLocale) -> String {
+    if v >= 1e12 {
+        locale.with_currency(&format!("{}T", locale.number(v / 1e12, 2)), "USD")
+    } else if v >= 1e9 {
+        locale.with_currency(&format!("{}B", locale.number(v / 1e9, 1)), "USD")
+    } else if v >= 1_000.0 {
+        locale.money(v.round(), "USD", 0)
+    } else {
+        locale.money(v, "USD", 2)
+    }
+}
+
+/// `+1.23%`, written the chat's way.
+fn format_change(change: f64, locale: &ChatLocale) -> String {
+    let sign = if change < 0.0 { "-" } else { "+" };
+    format!("{}{}%", sign, locale.number(change.abs(), 2))
+}
+
+fn arrow(change: f64) -> &'static str {
+    if change >= 0.0 {
+        "🟢"
+    } else {
+        "🔴"
+    }
+}
+
+fn render(
+    prices: Result<Vec<AssetPrice>, String>,
+    global: Result<GlobalStats, String>,
+    fear_greed: Result<FearGreed, String>,
+    locale: &ChatLocale,
+) -> String {
+    let mut out = vec!["🌐 *Market Overview*".to_string(), String::new()];
+    match prices {
+        Ok(prices) => out.extend(prices.iter().map(|p| {
+            format!(
+                "{} {}: {} ({} 24h)",
+                arrow(p.change_24h),
+                p.symbol,
+                format_usd(p.usd, locale),
+                format_change(p.change_24h, locale)
+            )
+        })),
+        Err(e) => out.push(format!("Prices: ❌ {}", e)),
+    }
+    out.push(String::new());
+    match global {
+        Ok(g) => {
+            out.push(format!(
+                "💰 Total market cap: {} ({} 24h)",
+                format_usd(g.market_cap_usd, locale),
+                format_change(g.market_cap_change_24h, locale)
+            ));
+            out.push(format!(
+                "👑 Dominance: BTC {}% · ETH {}%",
+                locale.number(g.btc_dominance, 1),
+                locale.number(g.eth_dominance, 1)
+            ));
+        }
+        Err(e) => out.push(format!("Market cap: ❌ {}", e)),
+    }
+    match fear_greed {
+        Ok(fg) => {
+            let trend = fg
+                .previous
+                .map(|p| format!(" (yesterday {})", p))
+                .unwrap_or_default();
+            out.push(format!("😱 Fear & Greed: {} — {}{}", fg.value, fg.label, trend));
+        }
+        Err(e) => out.push(format!("Fear & Greed: ❌ {}", e)),
+    }
+    out.join("\n")
+}
+
+// ── MarketOverviewTool ──────────────────────────────────────────────
+
+pub struct MarketOverviewTool {
+    client: Client,
+}
+
+impl MarketOverviewTool {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    async fn get_json(&self, url: &str) -> Result<Value, String> {
+        let resp = self
+            .client
+            .get(url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("network error: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()));
+        }
+        resp.json().await.map_err(|e| format!("invalid response: {}", e))
+    }
+}
+
+#[async_trait]
+impl Tool for MarketOverviewTool {
+    fn name(&self) -> &str {
+        "market_overview"
+    }
+
+    fn description(&self) -> &str {
+        "Crypto market snapshot in one call: BTC/ETH/SOL prices with 24h change, total market \
+         cap, BTC/ETH dominance and the Fear & Greed index. Use for daily summaries."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let ids: Vec<&str> = ASSETS.iter().map(|(id, _)| *id).collect();
+        let price_url = format!(
+            "{}/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true",
+            COINGECKO_API,
+            ids.join(",")
+        );
+        let global_url = format!("{}/global", COINGECKO_API);
+        let (prices, global, fear_greed) = tokio::join!(
+            self.get_json(&price_url),
+            self.get_json(&global_url),
+            self.get_json(FEAR_GREED_API),
+        );
+        render(
+            prices.map(|d| parse_prices(&d)),
+            global.and_then(|d| parse_global(&d).ok_or_else(|| "unexpected response".into())),
+            fear_greed.and_then(|d| parse_fear_greed(&d).ok_or_else(|| "unexpected response".into())),
+            &ctx.locale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let prices = parse_prices(&json!({
+            "bitcoin": {"usd": 64210.4, "usd_24h_change": 1.234},
+            "ethereum": {"usd": 2410.0, "usd_24h_change": -0.5},
+            "solana": {"usd": 142.37, "usd_24h_change": 3.0}
+        }));
+        assert_eq!(prices.len(), 3);
+        let global = parse_global(&json!({"data": {
+            "total_market_cap": {"usd": 2.41e12},
+            "market_cap_change_percentage_24h_usd": -1.2,
+            "market_cap_percentage": {"btc": 54.32, "eth": 13.01}
+        }}))
+        .unwrap();
+        let fg = parse_fear_greed(&json!({"data": [
+            {"value": "72", "value_classification": "Greed"},
+            {"value": "65", "value_classification": "Greed"}
+        ]}))
+        .unwrap();
+        assert_eq!(fg.previous, Some(65));
+
+        let german = ChatLocale {
+            locale: Some("de-DE".into()),
+            ..Default::default()
+        };
+        let text = render(Ok(prices.clone()), Ok(global.clone()), Ok(fg.clone()), &german);
+        assert!(text.contains("🟢 BTC: 64.210 $ (+1,23% 24h)"));
+        assert!(text.contains("Total market cap: 2,41T $ (-1,20% 24h)"));
+
+        let text = render(Ok(prices), Ok(global), Ok(fg), &ChatLocale::default());
+        assert!(text.contains("🟢 BTC: $64,210 (+1.23% 24h)"));
+        assert!(text.contains("🔴 ETH: $2,410 (-0.50% 24h)"));
+        assert!(text.contains("SOL: $142.37"));
+        assert!(text.contains("Total market cap: $2.41T (-1.20% 24h)"));
+        assert!(text.contains("Dominance: BTC 54.3% · ETH 13.0%"));
+        assert!(text.contains("Fear & Greed: 72 — Greed (yesterday 65)"));
+    }
+
+    #[test]
+    fn test_partial_failure() {
+        let text = render(
+            Ok(Vec::new()),
+            Err("HTTP 429 Too Many Requests".into()),
+            Err("network error".into()),
+            &ChatLocale::default(),
+        );
+        assert!(text.contains("Market cap: ❌ HTTP 429"));
+        assert!(text.contains("Fear & Greed: ❌ network error"));
+        assert!(parse_fear_greed(&json!({"data": []})).is_none());
+    }
+}
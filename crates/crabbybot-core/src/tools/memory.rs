@@ -0,0 +1,216 @@
+//! `remember`, `recall` and `forget`: the agent's long-term memory.
+//!
+//! Backed by the chat workspace's [`MemoryStore`]: named facts in
+//! `memory/facts.json`, free-text notes in `memory/MEMORY.md`. Both are
+//! part of every system prompt, so what is remembered outlives trimmed
+//! history and carries over into new sessions.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::{Tool, ToolContext};
+use crate::agent::memory::MemoryStore;
+
+/// Days of daily notes `recall` searches.
+const RECALL_DAYS: u32 = 30;
+
+fn text<'a>(args: &'a HashMap<String, Value>, key: &str) -> Option<&'a str> {
+    args.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty())
+}
+
+pub struct RememberTool {
+    workspace: PathBuf,
+}
+
+impl RememberTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for RememberTool {
+    fn name(&self) -> &str {
+        "remember"
+    }
+
+    fn description(&self) -> &str {
+        "Save something to long-term memory so it is known in later conversations. \
+         Pass `key` for a fact that may change (e.g. key 'home_city', text 'Lisbon'); \
+         it replaces the previous value. Without `key` the text is added as a note. \
+         Use it for lasting facts, decisions and preferences, not for chit-chat."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "What to remember" },
+                "key": { "type": "string", "description": "Name of the fact, e.g. 'home_city'" }
+            },
+            "required": ["text"]
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(content) = text(&args, "text") else {
+            return "Error: 'text' is required".into();
+        };
+        let store = MemoryStore::new(ctx.workspace_or(&self.workspace));
+        match text(&args, "key") {
+            Some(key) => match store.set_fact(key, content) {
+                Ok(Some(previous)) => format!(
+                    "🧠 Updated {}: {} (was: {})",
+                    MemoryStore::fact_key(key),
+                    content,
+                    previous
+                ),
+                Ok(None) => format!("🧠 Remembered {}: {}", MemoryStore::fact_key(key), content),
+                Err(e) => format!("Error saving memory: {}", e),
+            },
+            None => match store.append_long_term(content) {
+                Ok(()) => format!("🧠 Noted: {}", content),
+                Err(e) => format!("Error saving memory: {}", e),
+            },
+        }
+    }
+}
+
+pub struct RecallTool {
+    workspace: PathBuf,
+}
+
+impl RecallTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for RecallTool {
+    fn name(&self) -> &str {
+        "recall"
+    }
+
+    fn description(&self) -> &str {
+        "Search long-term memory: named facts, notes, and the daily notes of the last \
+         30 days. Use it when the user refers to something from an earlier conversation \
+         that is not in the history. Without `query` it lists all facts and notes."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "description": "Word or phrase to look for" }
+            },
+            "required": []
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let query = text(&args, "query").unwrap_or_default();
+        let found = MemoryStore::new(ctx.workspace_or(&self.workspace)).recall(query, RECALL_DAYS);
+        if found.is_empty() {
+            return match query {
+                "" => "Memory is empty.".into(),
+                q => format!("Nothing in memory about '{}'.", q),
+            };
+        }
+        let mut out = String::from("🧠 From memory:\n");
+        for (key, fact) in &found.facts {
+            out.push_str(&format!("\n• {}: {} (since {})", key, fact.value, fact.updated));
+        }
+        for note in &found.notes {
+            out.push_str(&format!("\n• {}", note.trim_start_matches("- ")));
+        }
+        out
+    }
+}
+
+pub struct ForgetTool {
+    workspace: PathBuf,
+}
+
+impl ForgetTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for ForgetTool {
+    fn name(&self) -> &str {
+        "forget"
+    }
+
+    fn description(&self) -> &str {
+        "Remove something from long-term memory: the fact with this key, or else every \
+         note containing this text. Use it when the user asks you to forget something \
+         or a remembered fact turns out to be wrong."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "key_or_text": { "type": "string", "description": "Fact key, or text the notes to drop contain" }
+            },
+            "required": ["key_or_text"]
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(target) = text(&args, "key_or_text") else {
+            return "Error: 'key_or_text' is required".into();
+        };
+        match MemoryStore::new(ctx.workspace_or(&self.workspace)).forget(target) {
+            Ok(0) => format!("Nothing in memory matches '{}'.", target),
+            Ok(1) => "🧹 Forgotten.".into(),
+            Ok(n) => format!("🧹 Forgot {} notes.", n),
+            Err(e) => format!("Error updating memory: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_remember_recall_forget_in_the_chat_workspace() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_memory_tools_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let chat = tmp.join("chat");
+        let ctx = ToolContext::new("telegram", "7").with_workspace(&chat);
+        let args = |pairs: &[(&str, &str)]| -> HashMap<String, Value> {
+            pairs.iter().map(|(k, v)| (k.to_string(), json!(v))).collect()
+        };
+
+        let remember = RememberTool::new(tmp.clone());
+        let out = remember.execute(args(&[("key", "risk limit"), ("text", "50 USD")]), &ctx).await;
+        assert_eq!(out, "🧠 Remembered risk_limit: 50 USD");
+        let out = remember.execute(args(&[("key", "Risk Limit"), ("text", "80 USD")]), &ctx).await;
+        assert_eq!(out, "🧠 Updated risk_limit: 80 USD (was: 50 USD)");
+        remember.execute(args(&[("text", "Trades only on weekdays")]), &ctx).await;
+        assert!(chat.join("memory").join("facts.json").exists());
+        assert!(!tmp.join("memory").exists());
+
+        let recall = RecallTool::new(tmp.clone());
+        let out = recall.execute(args(&[("query", "usd")]), &ctx).await;
+        assert!(out.contains("• risk_limit: 80 USD (since "), "{}", out);
+        assert!(recall.execute(args(&[]), &ctx).await.contains("• Trades only on weekdays"));
+
+        let forget = ForgetTool::new(tmp.clone());
+        assert_eq!(forget.execute(args(&[("key_or_text", "risk_limit")]), &ctx).await, "🧹 Forgotten.");
+        assert_eq!(recall.execute(args(&[("query", "usd")]), &ctx).await, "Nothing in memory about 'usd'.");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -4,44 +4,209 @@
 //! `ToolRegistry`. The agent loop queries the registry for available
 //! tools and dispatches tool calls by name.
 
+pub mod address;
+#[cfg(feature = "crypto-tools")]
 pub mod alpha_summary;
+pub mod archive;
+pub mod builder;
+pub mod context;
+#[cfg(feature = "crypto-tools")]
+pub mod fees;
 pub mod filesystem;
+pub mod journal;
+#[cfg(feature = "crypto-tools")]
+pub mod jupiter;
+pub mod market;
+pub mod memory;
+pub mod news;
+#[cfg(feature = "crypto-tools")]
+pub mod nft;
+#[cfg(feature = "polymarket")]
 pub mod polymarket;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_approve;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_bridge;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_comments;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_common;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_ctf;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_data;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_events;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_orderbook;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_orders;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_prices;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_profiles;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_series;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_sports;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_status;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_stream;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_tags;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_trade;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_wallet;
+#[cfg(feature = "polymarket")]
 pub mod betting_control;
+#[cfg(feature = "polymarket")]
 pub mod polymarket_help;
+pub mod pipeline;
+#[cfg(feature = "charts")]
+pub mod plot;
+pub mod profile;
+pub mod qr;
+#[cfg(feature = "crypto-tools")]
 pub mod rugcheck;
 pub mod schedule;
+#[cfg(feature = "crypto-tools")]
 pub mod sentiment;
 pub mod shell;
+#[cfg(feature = "crypto-tools")]
 pub mod solana;
+#[cfg(feature = "crypto-tools")]
+pub mod solana_stream;
+#[cfg(feature = "crypto-tools")]
+pub mod solana_tx;
+pub mod sports;
+pub mod stats;
+#[cfg(feature = "data-tools")]
+pub mod table;
+pub mod timeline;
+pub mod wallets;
 pub mod web;
 pub mod prediction;
 
 use async_trait::async_trait;
+use chrono::Utc;
 use serde_json::Value;
 use std::collections::HashMap;
-use tracing::{debug, error};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Instant;
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
 
+use crate::approvals::Approvals;
+use crate::config::ApprovalRule;
+use crate::guardrails::{self, Guardrails};
+use crate::journal::DecisionJournal;
 use crate::provider::types::{ToolDefinition, ToolFunctionDef};
 
+pub use builder::ToolSetBuilder;
+pub use context::ToolContext;
+pub use stats::ToolStats;
+
+/// Errors surfaced by the tool registry.
+///
+/// Tools themselves report failures as text for the LLM; this type covers
+/// the cases a caller of the registry may want to match on.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ToolError {
+    /// No tool with this name is registered.
+    #[error("Tool '{0}' not found")]
+    NotFound(String),
+
+    /// A transfer was stopped by the recipient screen in [`address`].
+    #[error("Transfer blocked: {0}")]
+    RecipientRejected(String),
+
+    /// A mutating call was refused because the registry is read-only.
+    #[error("'{0}' is unavailable in read-only mode: nothing may be written, run, traded or scheduled")]
+    ReadOnly(String),
+
+    /// A mutating call was refused because this turn may only change state
+    /// through other tools (see [`ToolContext::mutating_tools`]).
+    #[error("'{0}' is not allowed here: this turn was started by an external event and may only read")]
+    NotAllowed(String),
+
+    /// The call waits for sign-off under an approval rule, see
+    /// [`crate::approvals`].
+    #[error(
+        "'{tool}' was not run: it needs {required} approval(s) first. Request #{id} was filed; \
+         tell the user it runs once approved with /approve {id}, and don't call it again"
+    )]
+    ApprovalRequired { tool: String, id: String, required: u32 },
+
+    /// An order was placed without a decision recorded first, see
+    /// [`crate::journal`].
+    #[error(
+        "'{0}' was not run: record the decision first with journal_decision (trade, thesis, inputs, \
+         expected_outcome, stop_condition), then place the order again"
+    )]
+    DecisionNotJournaled(String),
+
+    /// A guardrail rule refused the call, see [`crate::guardrails`].
+    #[error("'{tool}' was blocked by guardrail '{rule}': {reason}")]
+    Blocked { tool: String, rule: String, reason: String },
+
+    /// The call needs approval, but the request couldn't be filed.
+    #[error("'{tool}' needs approval, but the request couldn't be filed: {reason}")]
+    ApprovalUnavailable { tool: String, reason: String },
+
+    /// The turn was cancelled while the tool ran, see [`ToolContext::cancel`].
+    #[error("'{0}' was cancelled before it finished")]
+    Cancelled(String),
+}
+
+/// How heavy a tool call is, roughly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CostTier {
+    /// Local work or a single quick API lookup.
+    #[default]
+    Cheap,
+    /// Several requests, a slower API, or a local process.
+    Moderate,
+    /// Full page loads, live streams, or extra LLM calls.
+    Expensive,
+}
+
+impl CostTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cheap => "cheap",
+            Self::Moderate => "moderate",
+            Self::Expensive => "expensive",
+        }
+    }
+}
+
+/// Cost and typical latency of a tool, appended to its description so the
+/// model can prefer cheap tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostHint {
+    pub tier: CostTier,
+    pub typical_secs: u32,
+}
+
+impl CostHint {
+    pub const fn new(tier: CostTier, typical_secs: u32) -> Self {
+        Self { tier, typical_secs }
+    }
+
+    /// `description` followed by the hint, e.g. `… [cost: expensive, ~10s]`.
+    pub fn annotate(&self, description: &str) -> String {
+        format!("{} [cost: {}, ~{}s]", description.trim_end(), self.tier.as_str(), self.typical_secs)
+    }
+}
+
+impl Default for CostHint {
+    fn default() -> Self {
+        Self::new(CostTier::Cheap, 1)
+    }
+}
+
 /// Trait that all agent tools must implement.
 ///
 /// Tools are capabilities the agent can invoke (read files, run commands, etc.).
@@ -59,7 +224,39 @@ pub trait Tool: Send + Sync {
     fn parameters(&self) -> Value;
 
     /// Execute the tool with the given arguments.
-    async fn execute(&self, args: HashMap<String, Value>) -> String;
+    ///
+    /// `ctx` identifies the chat and user the call is made for.
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String;
+
+    /// Arguments naming an address that funds are sent to.
+    ///
+    /// Transfer tools list them here so the registry can screen each
+    /// recipient with [`address::screen_recipient`] before the tool runs.
+    fn recipient_params(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Rough cost of a call, shown to the model with the description.
+    /// Defaults to a cheap lookup of about a second.
+    fn cost(&self) -> CostHint {
+        CostHint::default()
+    }
+
+    /// Whether this call changes state outside the conversation: writes
+    /// files, runs commands, moves funds, places orders or edits schedules.
+    /// A read-only registry refuses such calls before they run, and
+    /// [`ToolContext::mutating_tools`] limits them. Every tool is assumed to
+    /// mutate unless it says otherwise, so read-only tools return `false`.
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        true
+    }
+
+    /// USD this call commits, for approval rules with a `minUsd`. Trading
+    /// tools report it; `None` when the call spends nothing or the amount
+    /// isn't known up front.
+    fn trade_usd(&self, _args: &HashMap<String, Value>) -> Option<f64> {
+        None
+    }
 }
 
 /// High-level categories representing user intent.
@@ -96,95 +293,555 @@ impl IntentCategory {
     }
 }
 
+/// Source of the tools registered with [`ToolRegistry::register`].
+pub const BUILTIN_SOURCE: &str = "builtin";
+
+/// A registered tool and where it came from.
+struct Registered {
+    tool: Arc<dyn Tool>,
+    category: IntentCategory,
+    source: String,
+}
+
+impl Registered {
+    /// `source.name`, e.g. `builtin.web_search`.
+    fn qualified_name(&self) -> String {
+        format!("{}.{}", self.source, self.tool.name())
+    }
+
+    /// The name the tool is moved to when another source holds its own:
+    /// `source_name`, with characters providers reject replaced.
+    fn namespaced_name(&self) -> String {
+        let source: String = self
+            .source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        format!("{}_{}", source, self.tool.name())
+    }
+}
+
+/// Two sources registered a tool under the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolConflict {
+    pub name: String,
+    /// Source that keeps the plain name.
+    pub kept: String,
+    /// Source whose tool was moved.
+    pub moved: String,
+    /// Name the moved tool is called by now.
+    pub registered_as: String,
+}
+
 /// Dynamic registry for agent tools.
 ///
-/// Allows runtime registration and lookup of tools by name.
-#[derive(Default)]
+/// Allows runtime registration and lookup of tools by name, and records
+/// per-tool usage statistics for every call.
+///
+/// Every tool comes from a source (`builtin` unless registered with
+/// [`register_from`](Self::register_from)) and can also be looked up as
+/// `source.name`. When two sources register the same name, the first keeps
+/// it and the later one is exposed as `source_name`; the clash is logged and
+/// listed by [`conflicts`](Self::conflicts). [`alias`](Self::alias) settles
+/// one explicitly.
+///
+/// Tools can be added and removed while the bot runs (MCP reconnects,
+/// plugin reloads): registration takes `&self`, and every change bumps the
+/// version published by [`subscribe`](Self::subscribe). Calls already
+/// running keep the tool they started with.
 pub struct ToolRegistry {
-    tools: HashMap<String, (Box<dyn Tool>, IntentCategory)>,
+    tools: RwLock<HashMap<String, Registered>>,
+    conflicts: RwLock<Vec<ToolConflict>>,
+    version: watch::Sender<u64>,
+    stats: ToolStats,
+    read_only: bool,
+    approvals: Option<Arc<Approvals>>,
+    journal: Option<Arc<DecisionJournal>>,
+    require_decisions: bool,
+    guardrails: Option<Arc<Guardrails>>,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            tools: RwLock::default(),
+            conflicts: RwLock::default(),
+            version: watch::Sender::new(0),
+            stats: ToolStats::default(),
+            read_only: false,
+            approvals: None,
+            journal: None,
+            require_decisions: false,
+            guardrails: None,
+        }
+    }
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
-        Self {
-            tools: HashMap::new(),
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Registered>> {
+        self.tools.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Registered>> {
+        self.tools.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn changed(&self) {
+        self.version.send_modify(|v| *v += 1);
+    }
+
+    /// Watch the tool set: the value is bumped on every registration,
+    /// removal or alias.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.version.subscribe()
+    }
+
+    /// Replace the default in-memory statistics recorder.
+    pub fn set_stats(&mut self, stats: ToolStats) {
+        self.stats = stats;
+    }
+
+    /// Refuse every call for which [`Tool::mutates`] is true.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Hold calls matching an approval rule until they are signed off.
+    pub fn set_approvals(&mut self, approvals: Arc<Approvals>) {
+        self.approvals = Some(approvals);
+    }
+
+    pub fn approvals(&self) -> Option<&Arc<Approvals>> {
+        self.approvals.as_ref()
+    }
+
+    /// Link orders to the decisions recorded before them; with `required`,
+    /// refuse orders that have none.
+    pub fn set_journal(&mut self, journal: Arc<DecisionJournal>, required: bool) {
+        self.journal = Some(journal);
+        self.require_decisions = required;
+    }
+
+    pub fn journal(&self) -> Option<&Arc<DecisionJournal>> {
+        self.journal.as_ref()
+    }
+
+    /// Check every call against these rules before it runs.
+    pub fn set_guardrails(&mut self, guardrails: Arc<Guardrails>) {
+        self.guardrails = Some(guardrails);
+    }
+
+    /// Usage statistics for calls made through this registry.
+    pub fn stats(&self) -> &ToolStats {
+        &self.stats
+    }
+
+    /// Register a built-in tool with a specific intent category.
+    pub fn register(&self, tool: Box<dyn Tool>, category: IntentCategory) {
+        self.register_from(BUILTIN_SOURCE, tool, category);
+    }
+
+    /// Register a tool from `source` (e.g. `mcp.github`, `plugin.charts`).
+    ///
+    /// A name another source already holds is not overwritten: the new tool
+    /// is exposed as `source_name` instead and the conflict recorded. A
+    /// source registering the same name twice keeps the first.
+    pub fn register_from(&self, source: &str, tool: Box<dyn Tool>, category: IntentCategory) {
+        let entry = Registered {
+            tool: Arc::from(tool),
+            category,
+            source: source.to_string(),
+        };
+        let name = entry.tool.name().to_string();
+        let mut tools = self.write();
+        let Some(existing) = tools.get(&name) else {
+            debug!(tool = %name, source, category = category.as_str(), "Registered tool");
+            tools.insert(name, entry);
+            drop(tools);
+            self.changed();
+            return;
+        };
+        if existing.source == source {
+            warn!(tool = %name, source, "Tool registered twice by the same source; keeping the first");
+            return;
+        }
+        let registered_as = entry.namespaced_name();
+        if tools.contains_key(&registered_as) {
+            warn!(tool = %name, source, "Tool name conflict with no free name left; skipping");
+            return;
+        }
+        warn!(
+            tool = %name,
+            kept = %existing.source,
+            moved = source,
+            "Tool name conflict; registered as '{}'. Set tools.aliases to choose.",
+            registered_as
+        );
+        self.conflicts.write().unwrap_or_else(|e| e.into_inner()).push(ToolConflict {
+            name,
+            kept: existing.source.clone(),
+            moved: source.to_string(),
+            registered_as: registered_as.clone(),
+        });
+        tools.insert(registered_as, entry);
+        drop(tools);
+        self.changed();
+    }
+
+    /// Remove the tool registered as `name` (or `source.name`). Returns
+    /// whether there was one.
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut tools = self.write();
+        let Some(key) = Self::key_in(&tools, name) else {
+            return false;
+        };
+        tools.remove(&key);
+        drop(tools);
+        self.forget_conflicts();
+        info!(tool = %key, "Unregistered tool");
+        self.changed();
+        true
+    }
+
+    /// Remove every tool from `source`, e.g. before an MCP server's tools
+    /// are registered again on reconnect. Returns how many went.
+    pub fn unregister_source(&self, source: &str) -> usize {
+        let mut tools = self.write();
+        let before = tools.len();
+        tools.retain(|_, entry| entry.source != source);
+        let removed = before - tools.len();
+        drop(tools);
+        if removed > 0 {
+            self.forget_conflicts();
+            info!(source, count = removed, "Unregistered tools");
+            self.changed();
         }
+        removed
     }
 
-    /// Register a tool with a specific intent category.
-    pub fn register(&mut self, tool: Box<dyn Tool>, category: IntentCategory) {
-        debug!(tool = tool.name(), category = category.as_str(), "Registered tool");
-        self.tools.insert(tool.name().to_string(), (tool, category));
+    /// Drop conflicts whose moved tool is gone.
+    fn forget_conflicts(&self) {
+        let tools = self.read();
+        self.conflicts
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|c| tools.contains_key(&c.registered_as));
+    }
+
+    /// Expose the tool `target` (a registered or `source.name` name) as
+    /// `alias` instead. A different tool holding `alias` moves to its
+    /// `source_name`.
+    pub fn alias(&self, alias: &str, target: &str) -> Result<(), ToolError> {
+        let mut tools = self.write();
+        let key = Self::key_in(&tools, target).ok_or_else(|| ToolError::NotFound(target.to_string()))?;
+        if key == alias {
+            return Ok(());
+        }
+        let entry = tools.remove(&key).expect("key_in returns registered keys");
+        if let Some(holder) = tools.remove(alias) {
+            let moved_to = holder.namespaced_name();
+            info!(alias, tool = %holder.qualified_name(), "Alias takes this name; moved to '{}'", moved_to);
+            tools.insert(moved_to, holder);
+        }
+        info!(alias, tool = %entry.qualified_name(), "Aliased tool");
+        tools.insert(alias.to_string(), entry);
+        drop(tools);
+        self.changed();
+        Ok(())
     }
 
-    /// Get a tool by name.
-    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
-        self.tools.get(name).map(|(t, _)| t.as_ref())
+    /// Name conflicts resolved at registration, in order.
+    pub fn conflicts(&self) -> Vec<ToolConflict> {
+        self.conflicts.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
 
-    /// Check if a tool is registered.
+    /// The key `name` is registered under: itself, or the current name of
+    /// the tool it qualifies (`source.name`).
+    fn key_in(tools: &HashMap<String, Registered>, name: &str) -> Option<String> {
+        if tools.contains_key(name) {
+            return Some(name.to_string());
+        }
+        tools
+            .iter()
+            .find(|(_, entry)| entry.qualified_name() == name)
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Get a tool by name or by `source.name`.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        let tools = self.read();
+        let key = Self::key_in(&tools, name)?;
+        tools.get(&key).map(|entry| Arc::clone(&entry.tool))
+    }
+
+    /// Check if a tool is registered, by name or by `source.name`.
     pub fn has(&self, name: &str) -> bool {
-        self.tools.contains_key(name)
+        Self::key_in(&self.read(), name).is_some()
     }
 
-    /// Execute a tool by name with the given arguments.
-    pub async fn execute(&self, name: &str, args: HashMap<String, Value>) -> String {
-        match self.tools.get(name) {
-            Some((tool, _)) => {
-                debug!(tool = name, "Executing tool");
-                tool.execute(args).await
+    /// Run the address checks on a transfer tool's recipients.
+    fn screen_recipients(tool: &dyn Tool, args: &HashMap<String, Value>, ctx: &ToolContext) -> Result<(), ToolError> {
+        let recipients: Vec<&str> = tool
+            .recipient_params()
+            .iter()
+            .filter_map(|p| args.get(*p).and_then(|v| v.as_str()))
+            .collect();
+        if recipients.is_empty() {
+            return Ok(());
+        }
+        let known = address::known_addresses(ctx);
+        for recipient in recipients {
+            address::screen_recipient(recipient, &known, ctx.approved).map_err(|reason| {
+                warn!(tool = tool.name(), recipient, "{}", reason);
+                ToolError::RecipientRejected(reason)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// File an approval request when a rule holds this call back: a
+    /// guardrail's `requireApproval` match (`guarded`), or an approval rule
+    /// for a mutating call. Calls made with the approvers' sign-off pass.
+    async fn hold_for_approval(
+        &self,
+        tool: &dyn Tool,
+        args: &HashMap<String, Value>,
+        ctx: &ToolContext,
+        guarded: Option<ApprovalRule>,
+    ) -> Result<(), ToolError> {
+        if ctx.approved {
+            return Ok(());
+        }
+        let Some(approvals) = self.approvals.as_ref() else {
+            return match guarded {
+                Some(_) => Err(ToolError::ApprovalUnavailable {
+                    tool: tool.name().to_string(),
+                    reason: "approvals are not set up".into(),
+                }),
+                None => Ok(()),
+            };
+        };
+        let usd = tool.trade_usd(args);
+        let rule = match guarded.as_ref() {
+            Some(rule) => rule,
+            None if tool.mutates(args) => match approvals.rule_for(tool.name(), usd) {
+                Some(rule) => rule,
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        let approval = approvals
+            .request(tool.name(), args, usd, ctx, rule)
+            .map_err(|e| ToolError::ApprovalUnavailable {
+                tool: tool.name().to_string(),
+                reason: e.to_string(),
+            })?;
+        info!(tool = tool.name(), id = %approval.id, "Call held for approval");
+        ctx.send_reply(approval.request_text(), approval.buttons()).await;
+        Err(ToolError::ApprovalRequired {
+            tool: tool.name().to_string(),
+            id: approval.id,
+            required: approval.required,
+        })
+    }
+
+    /// The open decision an order is made on. Orders signed off through
+    /// an approval were checked when the request was filed.
+    fn decision_for(
+        &self,
+        tool: &dyn Tool,
+        args: &HashMap<String, Value>,
+        ctx: &ToolContext,
+    ) -> Result<Option<String>, ToolError> {
+        let Some(journal) = self.journal.as_ref().filter(|_| tool.trade_usd(args).is_some()) else {
+            return Ok(None);
+        };
+        match journal.open_for(&ctx.channel, &ctx.chat_id, Utc::now()) {
+            Some(decision) => Ok(Some(decision.id)),
+            None if self.require_decisions && !ctx.approved => {
+                warn!(tool = tool.name(), "Refused order without a recorded decision");
+                Err(ToolError::DecisionNotJournaled(tool.name().to_string()))
             }
-            None => {
-                error!(tool = name, "Tool not found");
-                format!("Error: Tool '{}' not found", name)
+            None => Ok(None),
+        }
+    }
+
+    /// Execute a tool by name, failing with [`ToolError::NotFound`] for unknown tools,
+    /// [`ToolError::ReadOnly`] for mutating calls in read-only mode,
+    /// [`ToolError::NotAllowed`] for mutating calls the turn isn't allowed,
+    /// [`ToolError::Blocked`] for calls a guardrail refuses,
+    /// [`ToolError::RecipientRejected`] for transfers to suspicious addresses,
+    /// [`ToolError::DecisionNotJournaled`] for orders placed without a recorded
+    /// decision, [`ToolError::ApprovalRequired`] for calls that wait for sign-off,
+    /// and [`ToolError::Cancelled`] when the turn is cancelled mid-call.
+    pub async fn try_execute(
+        &self,
+        name: &str,
+        args: HashMap<String, Value>,
+        ctx: &ToolContext,
+    ) -> Result<String, ToolError> {
+        let tool = self.get(name).ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        if self.read_only && tool.mutates(&args) {
+            warn!(tool = name, "Refused mutating call in read-only mode");
+            return Err(ToolError::ReadOnly(name.to_string()));
+        }
+        if !ctx.may_mutate(tool.name()) && tool.mutates(&args) {
+            warn!(tool = name, "Refused mutating call outside the turn's allowed tools");
+            return Err(ToolError::NotAllowed(name.to_string()));
+        }
+        let (guarded, notes) = self.check_guardrails(tool.as_ref(), &args, ctx)?;
+        Self::screen_recipients(tool.as_ref(), &args, ctx)?;
+        let decision = self.decision_for(tool.as_ref(), &args, ctx)?;
+        self.hold_for_approval(tool.as_ref(), &args, ctx, guarded).await?;
+        debug!(tool = name, "Executing tool");
+        let started = Instant::now();
+        let mut output = tokio::select! {
+            output = tool.execute(args, ctx) => output,
+            _ = ctx.cancel.cancelled() => return Err(ToolError::Cancelled(name.to_string())),
+        };
+        let failed = stats::is_error_output(&output);
+        self.stats.record(name, started.elapsed(), failed);
+        if let (Some(id), Some(journal), false) = (decision, self.journal.as_ref(), failed) {
+            if let Err(e) = journal.link(&id, name, &output) {
+                warn!(tool = name, decision = %id, "Failed to link order to its decision: {}", e);
+            }
+        }
+        for note in notes {
+            output.push_str("\n\n");
+            output.push_str(&note);
+        }
+        Ok(output)
+    }
+
+    /// Run the guardrails: refuse a blocked call, or return the approval
+    /// rule a match requires and the notes to add to the result.
+    fn check_guardrails(
+        &self,
+        tool: &dyn Tool,
+        args: &HashMap<String, Value>,
+        ctx: &ToolContext,
+    ) -> Result<(Option<ApprovalRule>, Vec<String>), ToolError> {
+        let Some(rules) = self.guardrails.as_ref() else {
+            return Ok((None, Vec::new()));
+        };
+        let call = guardrails::Call {
+            tool: tool.name(),
+            args,
+            usd: tool.trade_usd(args),
+            user_id: &ctx.user_id,
+        };
+        let verdict = rules.check(&call, Utc::now());
+        if let Some(rule) = verdict.blocked {
+            warn!(tool = tool.name(), rule = rule.label(), "Call blocked by guardrail");
+            return Err(ToolError::Blocked {
+                tool: tool.name().to_string(),
+                rule: rule.label().to_string(),
+                reason: rule.reason().to_string(),
+            });
+        }
+        Ok((verdict.approval.map(|rule| rule.approval_rule()), verdict.notes))
+    }
+
+    /// Execute a tool by name with the given arguments.
+    ///
+    /// Errors are rendered as text so they can be fed back to the LLM.
+    pub async fn execute(
+        &self,
+        name: &str,
+        args: HashMap<String, Value>,
+        ctx: &ToolContext,
+    ) -> String {
+        match self.try_execute(name, args, ctx).await {
+            Ok(output) => output,
+            Err(e) => {
+                error!(tool = name, "{}", e);
+                format!("Error: {}", e)
             }
         }
     }
 
+    /// The definition sent to the model under the registered `name`, with
+    /// the cost hint appended to the description.
+    fn definition(name: &str, tool: &dyn Tool) -> ToolDefinition {
+        ToolDefinition {
+            def_type: "function".into(),
+            function: ToolFunctionDef {
+                name: name.into(),
+                description: tool.cost().annotate(tool.description()),
+                parameters: tool.parameters(),
+            },
+        }
+    }
+
     /// Get all tool definitions for a given category.
     pub fn definitions_for(&self, category: IntentCategory) -> Vec<ToolDefinition> {
-        self.tools
-            .values()
-            .filter(|(_, cat)| *cat == category || *cat == IntentCategory::General) // Always include general
-            .map(|(tool, _)| ToolDefinition {
-                def_type: "function".into(),
-                function: ToolFunctionDef {
-                    name: tool.name().into(),
-                    description: tool.description().into(),
-                    parameters: tool.parameters(),
-                },
-            })
+        self.read()
+            .iter()
+            .filter(|(_, e)| e.category == category || e.category == IntentCategory::General) // Always include general
+            .map(|(name, e)| Self::definition(name, e.tool.as_ref()))
             .collect()
     }
 
     /// Get all tool definitions (ignoring categories).
     pub fn definitions(&self) -> Vec<ToolDefinition> {
-        self.tools
-            .values()
-            .map(|(tool, _)| ToolDefinition {
-                def_type: "function".into(),
-                function: ToolFunctionDef {
-                    name: tool.name().into(),
-                    description: tool.description().into(),
-                    parameters: tool.parameters(),
-                },
-            })
+        self.read()
+            .iter()
+            .map(|(name, e)| Self::definition(name, e.tool.as_ref()))
             .collect()
     }
 
     /// Get the list of registered tool names.
-    pub fn names(&self) -> Vec<&str> {
-        self.tools.keys().map(|s| s.as_str()).collect()
+    pub fn names(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
     }
 
     /// Number of registered tools.
     pub fn len(&self) -> usize {
-        self.tools.len()
+        self.read().len()
     }
 
     /// Whether the registry is empty.
     pub fn is_empty(&self) -> bool {
-        self.tools.is_empty()
+        self.read().is_empty()
+    }
+
+    /// Check every tool's parameter schema for problems providers reject:
+    /// a non-object root, missing `properties`, or `required` names that
+    /// aren't declared. Returns one message per problem.
+    pub fn validate_schemas(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, entry) in self.read().iter() {
+            let schema = entry.tool.parameters();
+            if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+                problems.push(format!("{}: schema root is not an object", name));
+                continue;
+            }
+            let Some(props) = schema.get("properties").and_then(|p| p.as_object()) else {
+                problems.push(format!("{}: missing `properties`", name));
+                continue;
+            };
+            let required = schema.get("required").and_then(|r| r.as_array());
+            for req in required.into_iter().flatten() {
+                match req.as_str() {
+                    Some(r) if props.contains_key(r) => {}
+                    r => problems.push(format!(
+                        "{}: required `{}` is not a property",
+                        name,
+                        r.map(String::from).unwrap_or_else(|| req.to_string())
+                    )),
+                }
+            }
+        }
+        problems.sort();
+        problems
     }
 }
 
@@ -205,27 +862,297 @@ mod tests {
         fn parameters(&self) -> Value {
             serde_json::json!({"type": "object", "properties": {}})
         }
-        async fn execute(&self, _args: HashMap<String, Value>) -> String {
+        fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+            false
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
             "dummy result".into()
         }
     }
 
     #[tokio::test]
     async fn test_register_and_execute() {
-        let mut registry = ToolRegistry::new();
+        let registry = ToolRegistry::new();
         registry.register(Box::new(DummyTool), IntentCategory::General);
 
         assert!(registry.has("dummy"));
         assert_eq!(registry.len(), 1);
 
-        let result = registry.execute("dummy", HashMap::new()).await;
+        let result = registry.execute("dummy", HashMap::new(), &ToolContext::default()).await;
         assert_eq!(result, "dummy result");
+        assert_eq!(registry.stats().today()["dummy"].calls, 1);
+    }
+
+    /// A tool named `name` that answers `output`.
+    struct EchoTool(&'static str, &'static str);
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn description(&self) -> &str {
+            "Echo"
+        }
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+            self.1.into()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_name_conflicts_are_namespaced_and_aliased() {
+        let ctx = ToolContext::default();
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool("search", "builtin")), IntentCategory::General);
+        registry.register_from("mcp.brave", Box::new(EchoTool("search", "brave")), IntentCategory::General);
+        registry.register(Box::new(EchoTool("search", "again")), IntentCategory::General);
+
+        // The first registration keeps the name; the later one is moved.
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.execute("search", HashMap::new(), &ctx).await, "builtin");
+        assert_eq!(registry.execute("mcp_brave_search", HashMap::new(), &ctx).await, "brave");
+        assert_eq!(registry.execute("mcp.brave.search", HashMap::new(), &ctx).await, "brave");
+        assert_eq!(
+            registry.conflicts(),
+            [ToolConflict {
+                name: "search".into(),
+                kept: "builtin".into(),
+                moved: "mcp.brave".into(),
+                registered_as: "mcp_brave_search".into(),
+            }]
+        );
+
+        // An alias hands the plain name over; the holder moves aside.
+        registry.alias("search", "mcp.brave.search").unwrap();
+        registry.alias("web_lookup", "builtin.search").unwrap();
+        assert_eq!(registry.execute("search", HashMap::new(), &ctx).await, "brave");
+        assert_eq!(registry.execute("web_lookup", HashMap::new(), &ctx).await, "builtin");
+        let mut names: Vec<_> = registry.definitions().into_iter().map(|d| d.function.name).collect();
+        names.sort();
+        assert_eq!(names, ["search", "web_lookup"]);
+        assert_eq!(registry.alias("x", "nope"), Err(ToolError::NotFound("nope".into())));
+    }
+
+    #[tokio::test]
+    async fn test_unregister_at_runtime_notifies_subscribers() {
+        let registry = ToolRegistry::new();
+        let mut changes = registry.subscribe();
+        registry.register(Box::new(EchoTool("search", "builtin")), IntentCategory::General);
+        registry.register_from("mcp.brave", Box::new(EchoTool("search", "brave")), IntentCategory::General);
+        registry.register_from("mcp.brave", Box::new(EchoTool("news", "news")), IntentCategory::General);
+        assert!(changes.has_changed().unwrap());
+        changes.mark_unchanged();
+
+        // An MCP reconnect drops the server's tools and their conflicts.
+        assert_eq!(registry.unregister_source("mcp.brave"), 2);
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(registry.names(), ["search"]);
+        assert!(registry.conflicts().is_empty());
+
+        assert!(registry.unregister("builtin.search"));
+        assert!(!registry.unregister("search"));
+        assert!(registry.is_empty());
+        let result = registry.try_execute("search", HashMap::new(), &ToolContext::default()).await;
+        assert_eq!(result, Err(ToolError::NotFound("search".into())));
+    }
+
+    struct SendTool;
+
+    #[async_trait]
+    impl Tool for SendTool {
+        fn name(&self) -> &str {
+            "send"
+        }
+        fn description(&self) -> &str {
+            "Pretend transfer"
+        }
+        fn parameters(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {"to": {"type": "string"}}})
+        }
+        async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+            "sent".into()
+        }
+        fn recipient_params(&self) -> &[&'static str] {
+            &["to"]
+        }
+        fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+            true
+        }
+        fn cost(&self) -> CostHint {
+            CostHint::new(CostTier::Expensive, 20)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_recipients_are_screened() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_recipient_screen");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        crate::agent::profile::ProfileStore::new(&tmp)
+            .update("telegram", "7", |p| p.wallets = vec!["0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".into()])
+            .unwrap();
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(SendTool), IntentCategory::General);
+        let ctx = ToolContext::new("telegram", "7").with_user("7").with_workspace(&tmp);
+        let to = |addr: &str| HashMap::from([("to".to_string(), Value::from(addr))]);
+
+        let saved = registry.try_execute("send", to("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"), &ctx).await;
+        assert_eq!(saved.unwrap(), "sent");
+
+        let poisoned = "0x5aa0000000000000000000000000000000000aed";
+        let blocked = registry.execute("send", to(poisoned), &ctx).await;
+        assert!(blocked.starts_with("Error: Transfer blocked"), "{blocked}");
+        let confirmed = registry.execute("send", to(poisoned), &ctx.clone().with_approval(true)).await;
+        assert_eq!(confirmed, "sent");
+
+        let malformed = registry.try_execute("send", to("0xnope"), &ctx.clone().with_approval(true)).await;
+        assert!(matches!(malformed, Err(ToolError::RecipientRejected(_))));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn test_calls_matching_a_rule_wait_for_approval() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_registry_approvals");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let config = crate::config::ApprovalsConfig {
+            rules: vec![crate::config::ApprovalRule {
+                tool: "send".into(),
+                required: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(SendTool), IntentCategory::General);
+        registry.register(Box::new(DummyTool), IntentCategory::General);
+        registry.set_approvals(Arc::new(Approvals::new(&tmp, config)));
+        let ctx = ToolContext::new("telegram", "7").with_user("7").with_workspace(&tmp);
+        let to = HashMap::from([("to".to_string(), Value::from("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"))]);
+
+        let held = registry.try_execute("send", to.clone(), &ctx).await;
+        let Err(ToolError::ApprovalRequired { id, required: 1, .. }) = held else {
+            panic!("expected the call to be held, got {:?}", held);
+        };
+        assert_eq!(registry.approvals().unwrap().pending().unwrap()[0].id, id);
+        // Other tools and approved calls run as usual.
+        assert_eq!(registry.execute("dummy", HashMap::new(), &ctx).await, "dummy result");
+        let approved = registry.try_execute("send", to, &ctx.clone().with_approval(true)).await;
+        assert_eq!(approved.unwrap(), "sent");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn test_guardrails_block_hold_and_annotate_calls() {
+        use crate::config::{GuardrailAction, GuardrailRule, GuardrailsConfig};
+        let config = GuardrailsConfig {
+            rules: vec![
+                GuardrailRule {
+                    name: "no-burn".into(),
+                    tool: "send".into(),
+                    args: std::collections::BTreeMap::from([("to".into(), "0x000000000000".into())]),
+                    message: "Never send to the zero address".into(),
+                    ..Default::default()
+                },
+                GuardrailRule {
+                    tool: "send".into(),
+                    users: vec!["9".into()],
+                    action: GuardrailAction::RequireApproval,
+                    ..Default::default()
+                },
+                GuardrailRule {
+                    tool: "dummy".into(),
+                    action: GuardrailAction::Annotate,
+                    message: "Results are made up".into(),
+                    ..Default::default()
+                },
+            ],
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(SendTool), IntentCategory::General);
+        registry.register(Box::new(DummyTool), IntentCategory::General);
+        registry.set_guardrails(Arc::new(Guardrails::new(&config, None).unwrap()));
+        let ctx = ToolContext::new("telegram", "7").with_user("7").with_approval(true);
+        let to = |addr: &str| HashMap::from([("to".to_string(), Value::from(addr))]);
+
+        let burn = registry.try_execute("send", to("0x0000000000000000000000000000000000000000"), &ctx).await;
+        assert!(matches!(burn, Err(ToolError::Blocked { ref rule, .. }) if rule == "no-burn"), "{burn:?}");
+        let sent = registry.try_execute("send", to("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"), &ctx).await;
+        assert_eq!(sent.unwrap(), "sent");
+
+        // User 9 needs sign-off, and there's no approvals store to ask.
+        let other = ToolContext::new("telegram", "9").with_user("9");
+        let held = registry.try_execute("send", to("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"), &other).await;
+        assert!(matches!(held, Err(ToolError::ApprovalUnavailable { .. })), "{held:?}");
+
+        let noted = registry.execute("dummy", HashMap::new(), &ctx).await;
+        assert_eq!(noted, "dummy result\n\n⚠️ dummy: Results are made up");
+    }
+
+    #[test]
+    fn test_definitions_carry_cost_hints() {
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(DummyTool), IntentCategory::General);
+        registry.register(Box::new(SendTool), IntentCategory::General);
+        let mut descriptions: Vec<String> =
+            registry.definitions().into_iter().map(|d| d.function.description).collect();
+        descriptions.sort();
+        assert_eq!(
+            descriptions,
+            [
+                "A dummy tool for testing [cost: cheap, ~1s]",
+                "Pretend transfer [cost: expensive, ~20s]"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_refuses_mutating_calls() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(DummyTool), IntentCategory::General);
+        registry.register(Box::new(SendTool), IntentCategory::General);
+        registry.register(Box::new(EchoTool("echo", "builtin")), IntentCategory::General);
+        registry.set_read_only(true);
+        let ctx = ToolContext::default();
+
+        assert_eq!(registry.execute("dummy", HashMap::new(), &ctx).await, "dummy result");
+        let refused = registry.try_execute("send", HashMap::new(), &ctx).await;
+        assert_eq!(refused, Err(ToolError::ReadOnly("send".into())));
+        // Tools that don't say they only read count as mutating.
+        let refused = registry.try_execute("echo", HashMap::new(), &ctx).await;
+        assert_eq!(refused, Err(ToolError::ReadOnly("echo".into())));
+        assert!(!registry.stats().today().contains_key("send"));
+    }
+
+    #[tokio::test]
+    async fn test_turn_limited_to_its_mutating_tools() {
+        let registry = ToolRegistry::new();
+        registry.register(Box::new(DummyTool), IntentCategory::General);
+        registry.register(Box::new(SendTool), IntentCategory::General);
+
+        let read_only = ToolContext::default().with_mutating_tools(Some(Vec::new()));
+        assert_eq!(registry.execute("dummy", HashMap::new(), &read_only).await, "dummy result");
+        let refused = registry.try_execute("send", HashMap::new(), &read_only).await;
+        assert_eq!(refused, Err(ToolError::NotAllowed("send".into())));
+
+        let allowed = ToolContext::default().with_mutating_tools(Some(vec!["send".into()]));
+        assert!(registry.try_execute("send", HashMap::new(), &allowed).await.is_ok());
     }
 
     #[tokio::test]
     async fn test_missing_tool() {
         let registry = ToolRegistry::new();
-        let result = registry.execute("nonexistent", HashMap::new()).await;
+        let result = registry.execute("nonexistent", HashMap::new(), &ToolContext::default()).await;
         assert!(result.contains("not found"));
+
+        let err = registry
+            .try_execute("nonexistent", HashMap::new(), &ToolContext::default())
+            .await;
+        assert_eq!(err, Err(ToolError::NotFound("nonexistent".into())));
     }
 }
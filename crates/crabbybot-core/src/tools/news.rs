@@ -0,0 +1,423 @@
+//! `news_search`: recent headlines from a news API.
+//!
+//! `web_search` ranks by relevance, which buries breaking news under
+//! older, SEO-heavy pages. This tool asks a news index for articles in a
+//! date range, newest first, drops sources on the deny list (or outside
+//! the allow list) and collapses syndicated copies of the same story.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use super::{CostHint, CostTier, Tool, ToolContext};
+use crate::config::{NewsConfig, NewsProvider};
+
+/// One headline, normalised across providers.
+#[derive(Debug, Clone, PartialEq)]
+struct Article {
+    title: String,
+    url: String,
+    source: String,
+    published: Option<DateTime<Utc>>,
+}
+
+impl Article {
+    /// Host of the article URL without `www.`.
+    fn host(&self) -> String {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_lowercase()))
+            .unwrap_or_default()
+    }
+
+    /// Whether `entry` (a domain like `reuters.com` or a source name)
+    /// names this article's source. Subdomains match their parent.
+    fn is_from(&self, entry: &str) -> bool {
+        let entry = entry.trim().trim_start_matches("www.").to_lowercase();
+        let host = self.host();
+        host == entry || host.ends_with(&format!(".{}", entry)) || self.source.to_lowercase() == entry
+    }
+}
+
+fn parse_time(raw: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw?).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Articles from a NewsAPI or GNews response; both use this shape.
+fn parse_articles(data: &Value) -> Vec<Article> {
+    data["articles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| {
+            Some(Article {
+                title: a["title"].as_str()?.trim().to_string(),
+                url: a["url"].as_str()?.to_string(),
+                source: a["source"]["name"].as_str().unwrap_or_default().to_string(),
+                published: parse_time(a["publishedAt"].as_str()),
+            })
+        })
+        .collect()
+}
+
+/// Articles from a Brave news search response. `page_age` has no zone;
+/// it is UTC.
+fn parse_brave(data: &Value) -> Vec<Article> {
+    data["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|r| {
+            let published = r["page_age"].as_str().and_then(|t| {
+                chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S")
+                    .ok()
+                    .map(|t| t.and_utc())
+                    .or_else(|| parse_time(Some(t)))
+            });
+            Some(Article {
+                title: r["title"].as_str()?.trim().to_string(),
+                url: r["url"].as_str()?.to_string(),
+                source: r["meta_url"]["hostname"].as_str().unwrap_or_default().to_string(),
+                published,
+            })
+        })
+        .collect()
+}
+
+/// Title reduced to lowercase words, without a trailing " - Source" or
+/// " | Source" that syndicated copies append.
+fn title_key(title: &str) -> String {
+    let core = title
+        .rsplit_once(" - ")
+        .or_else(|| title.rsplit_once(" | "))
+        .map(|(head, _)| head)
+        .filter(|head| head.split_whitespace().count() >= 4)
+        .unwrap_or(title);
+    core.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// URL without query string or fragment, so tracking parameters do not
+/// make the same page look new.
+fn url_key(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut u) => {
+            u.set_query(None);
+            u.set_fragment(None);
+            u.as_str().trim_end_matches('/').to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Apply source filters, sort newest first and drop duplicate stories,
+/// keeping the newest copy.
+fn filter_articles(mut articles: Vec<Article>, allow: &[String], deny: &[String]) -> Vec<Article> {
+    articles.retain(|a| {
+        (allow.is_empty() || allow.iter().any(|s| a.is_from(s)))
+            && !deny.iter().any(|s| a.is_from(s))
+    });
+    articles.sort_by_key(|a| std::cmp::Reverse(a.published));
+    let mut seen = HashSet::new();
+    articles.retain(|a| {
+        let fresh_title = seen.insert(title_key(&a.title));
+        let fresh_url = seen.insert(url_key(&a.url));
+        fresh_title && fresh_url
+    });
+    articles
+}
+
+fn format_age(published: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let mins = (now - published).num_minutes().max(0);
+    match mins {
+        0..=59 => format!("{}m ago", mins),
+        60..=2879 => format!("{}h ago", mins / 60),
+        _ => format!("{}d ago", mins / 1440),
+    }
+}
+
+fn string_list(args: &HashMap<String, Value>, key: &str) -> Vec<String> {
+    match args.get(key) {
+        Some(Value::String(s)) => s.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// ── NewsSearchTool ──────────────────────────────────────────────────
+
+pub struct NewsSearchTool {
+    client: Client,
+    provider: NewsProvider,
+    api_key: String,
+    allow_sources: Vec<String>,
+    deny_sources: Vec<String>,
+    max_results: u32,
+}
+
+impl NewsSearchTool {
+    /// `api_key` must already be decrypted.
+    pub fn new(client: Client, config: &NewsConfig, api_key: &str) -> Self {
+        Self {
+            client,
+            provider: config.provider,
+            api_key: api_key.to_string(),
+            allow_sources: config.allow_sources.clone(),
+            deny_sources: config.deny_sources.clone(),
+            max_results: config.max_results,
+        }
+    }
+
+    async fn fetch(&self, query: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Article>, String> {
+        // Ask for extra results: filtering and dedup drop some.
+        let page = (self.max_results * 3).clamp(10, 100);
+        let iso = |t: DateTime<Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let req = match self.provider {
+            NewsProvider::NewsApi => self
+                .client
+                .get("https://newsapi.org/v2/everything")
+                .header("X-Api-Key", &self.api_key)
+                .query(&[
+                    ("q", query),
+                    ("from", &iso(from)),
+                    ("to", &iso(to)),
+                    ("sortBy", "publishedAt"),
+                    ("pageSize", &page.to_string()),
+                ]),
+            NewsProvider::GNews => self.client.get("https://gnews.io/api/v4/search").query(&[
+                ("q", query),
+                ("from", &iso(from)),
+                ("to", &iso(to)),
+                ("sortby", "publishedAt"),
+                ("max", &page.to_string()),
+                ("apikey", &self.api_key),
+            ]),
+            NewsProvider::Brave => {
+                let freshness = format!("{}to{}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+                self.client
+                    .get("https://api.search.brave.com/res/v1/news/search")
+                    .header("Accept", "application/json")
+                    .header("X-Subscription-Token", &self.api_key)
+                    .query(&[("q", query), ("freshness", &freshness), ("count", &page.min(50).to_string())])
+            }
+        };
+        let resp = req.send().await.map_err(|e| format!("request failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("news API error ({})", resp.status()));
+        }
+        let data: Value = resp.json().await.map_err(|e| format!("invalid response: {}", e))?;
+        Ok(match self.provider {
+            NewsProvider::Brave => parse_brave(&data),
+            _ => parse_articles(&data),
+        }
+        .into_iter()
+        // Brave filters by day only; trim to the exact window.
+        .filter(|a| a.published.is_none_or(|p| p >= from && p <= to))
+        .collect())
+    }
+}
+
+#[async_trait]
+impl Tool for NewsSearchTool {
+    fn name(&self) -> &str {
+        "news_search"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
+    fn description(&self) -> &str {
+        "Search recent news headlines, newest first, with timestamps and URLs. Use instead of \
+         web_search for breaking news. Filter by date range and by source domains; duplicate \
+         copies of the same story are collapsed."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Search terms"
+                },
+                "hours": {
+                    "type": "integer",
+                    "description": "Look back this many hours (default: 24). Ignored when 'from' is set."
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Start date, YYYY-MM-DD (optional)"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "End date, YYYY-MM-DD, inclusive (optional, default: now)"
+                },
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only these sources, as domains (e.g. reuters.com) or names"
+                },
+                "exclude_sources": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Drop these sources, as domains or names"
+                },
+                "count": {
+                    "type": "integer",
+                    "description": "Number of headlines (default from config, max 30)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(query) = args.get("query").and_then(|v| v.as_str()).filter(|q| !q.trim().is_empty()) else {
+            return "Error: 'query' parameter is required".into();
+        };
+        let now = Utc::now();
+        let date = |key: &str| -> Result<Option<NaiveDate>, String> {
+            match args.get(key).and_then(|v| v.as_str()) {
+                Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                    .map(Some)
+                    .map_err(|_| format!("Error: invalid '{}' date '{}', expected YYYY-MM-DD", key, d)),
+                None => Ok(None),
+            }
+        };
+        let (from, to) = match (date("from"), date("to")) {
+            (Err(e), _) | (_, Err(e)) => return e,
+            (Ok(from), Ok(to)) => (from, to),
+        };
+        let to = to
+            .and_then(|d| d.and_hms_opt(23, 59, 59))
+            .map(|t| t.and_utc().min(now))
+            .unwrap_or(now);
+        let from = match from.and_then(|d| d.and_hms_opt(0, 0, 0)) {
+            Some(t) => t.and_utc(),
+            None => to - Duration::hours(args.get("hours").and_then(|v| v.as_i64()).unwrap_or(24).clamp(1, 24 * 30)),
+        };
+        if from >= to {
+            return "Error: 'from' must be before 'to'".into();
+        }
+        let count = args
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(self.max_results as u64)
+            .clamp(1, 30) as usize;
+
+        let mut allow = string_list(&args, "sources");
+        if allow.is_empty() {
+            allow = self.allow_sources.clone();
+        }
+        let mut deny = self.deny_sources.clone();
+        deny.extend(string_list(&args, "exclude_sources"));
+
+        debug!(query, %from, %to, provider = ?self.provider, "Searching news");
+
+        let articles = match self.fetch(query, from, to).await {
+            Ok(a) => filter_articles(a, &allow, &deny),
+            Err(e) => return format!("Error: {}", e),
+        };
+        if articles.is_empty() {
+            return format!("No news found for '{}' since {}.", query, from.format("%Y-%m-%d %H:%M UTC"));
+        }
+
+        let lines: Vec<String> = articles
+            .iter()
+            .take(count)
+            .enumerate()
+            .map(|(i, a)| {
+                let when = a
+                    .published
+                    .map(|p| format!("{} ({})", p.format("%Y-%m-%d %H:%M UTC"), format_age(p, now)))
+                    .unwrap_or_else(|| "time unknown".into());
+                let source = if a.source.is_empty() { a.host() } else { a.source.clone() };
+                format!("{}. {}\n   {} · {}\n   {}", i + 1, a.title, source, when, a.url)
+            })
+            .collect();
+        format!("📰 *News: {}*\n\n{}", query, lines.join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(title: &str, url: &str, source: &str, hour: u32) -> Article {
+        Article {
+            title: title.into(),
+            url: url.into(),
+            source: source.into(),
+            published: NaiveDate::from_ymd_opt(2026, 10, 17)
+                .and_then(|d| d.and_hms_opt(hour, 0, 0))
+                .map(|t| t.and_utc()),
+        }
+    }
+
+    #[test]
+    fn test_filter_and_dedupe() {
+        let articles = vec![
+            article("Fed holds rates steady in October", "https://www.reuters.com/a?utm=x", "Reuters", 9),
+            article("Fed holds rates steady in October - Yahoo Finance", "https://finance.yahoo.com/b", "Yahoo", 10),
+            article("Solana ETF decision delayed", "https://spam.example/c", "Spam", 11),
+            article("Bitcoin tops $150k", "https://reuters.com/a", "Reuters", 12),
+            article("Markets open higher", "https://uk.reuters.com/d", "Reuters UK", 8),
+            article("BTC hits new high", "https://www.reuters.com/a", "Reuters", 7),
+        ];
+        let kept = filter_articles(articles.clone(), &[], &["spam.example".into()]);
+        let titles: Vec<&str> = kept.iter().map(|a| a.title.as_str()).collect();
+        // Newest first. The newer Yahoo copy of the Fed story replaces
+        // Reuters', and the last article is the Fed URL minus tracking params.
+        assert_eq!(
+            titles,
+            ["Bitcoin tops $150k", "Fed holds rates steady in October - Yahoo Finance", "Markets open higher"]
+        );
+
+        let reuters = filter_articles(articles, &["reuters.com".into()], &[]);
+        assert_eq!(reuters.len(), 3);
+        assert!(reuters.iter().all(|a| a.host().ends_with("reuters.com")));
+    }
+
+    #[test]
+    fn test_parse_providers() {
+        let newsapi = json!({"status": "ok", "articles": [
+            {"source": {"id": null, "name": "CoinDesk"}, "title": "SOL rallies",
+             "url": "https://www.coindesk.com/x", "publishedAt": "2026-10-17T08:30:00Z"},
+            {"source": {"name": "Broken"}, "title": null, "url": "https://x.test"}
+        ]});
+        let parsed = parse_articles(&newsapi);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].host(), "coindesk.com");
+        assert_eq!(parsed[0].published, parse_time(Some("2026-10-17T08:30:00Z")));
+
+        let brave = json!({"results": [
+            {"title": "ETH upgrade ships", "url": "https://decrypt.co/y",
+             "meta_url": {"hostname": "decrypt.co"}, "page_age": "2026-10-17T11:00:00"}
+        ]});
+        let parsed = parse_brave(&brave);
+        assert_eq!(parsed[0].source, "decrypt.co");
+        assert_eq!(parsed[0].published, article("", "", "", 11).published);
+    }
+
+    #[test]
+    fn test_title_key_and_age() {
+        assert_eq!(title_key("Fed Holds Rates Steady in October | CNBC"), "fed holds rates steady in october");
+        // Too short to be "headline - source": keep the dash.
+        assert_eq!(title_key("Q3 - results"), "q3 results");
+        let now = article("", "", "", 12).published.unwrap();
+        assert_eq!(format_age(now - Duration::minutes(5), now), "5m ago");
+        assert_eq!(format_age(now - Duration::hours(3), now), "3h ago");
+        assert_eq!(format_age(now - Duration::days(4), now), "4d ago");
+    }
+}
@@ -0,0 +1,462 @@
+//! Solana NFT tools on the Digital Asset Standard (DAS) API.
+//!
+//! `solana_nfts` lists a wallet's NFTs grouped by collection, with floor
+//! prices from Magic Eden when `tools.nft.marketplaceUrl` is set, and
+//! `nft_metadata` shows one asset in detail. Both need an RPC that serves
+//! DAS (`tools.nft.dasUrl`, e.g. Helius); the public mainnet RPC does not.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::solana::SolanaRpc;
+use super::wallets::WalletBook;
+use super::{CostHint, CostTier, Tool, ToolContext};
+use crate::config::{NftConfig, WalletChain};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Collections whose floor price is looked up, largest holdings first.
+const MAX_FLOOR_LOOKUPS: usize = 10;
+/// Asset interfaces that count as NFTs (fungible tokens are skipped).
+const NFT_INTERFACES: &[&str] = &["V1_NFT", "V2_NFT", "ProgrammableNFT", "MplCoreAsset", "LEGACY_NFT"];
+
+/// Where to reach the indexers.
+#[derive(Clone)]
+struct Indexers {
+    client: Client,
+    das_url: String,
+    marketplace_url: String,
+}
+
+impl Indexers {
+    fn new(client: Client, config: &NftConfig, rpc_url: &str) -> Self {
+        let das_url = if config.das_url.is_empty() { rpc_url } else { &config.das_url };
+        Self {
+            client,
+            das_url: das_url.to_string(),
+            marketplace_url: config.marketplace_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn das(&self, method: &str, params: Value) -> Result<Value, String> {
+        SolanaRpc::new(self.client.clone(), &self.das_url)
+            .call(method, params)
+            .await
+            .map(|v| v["result"].clone())
+            .map_err(|e| {
+                if e.contains("Method not found") {
+                    "Error: this RPC does not support the DAS API. Set `tools.nft.dasUrl` to a \
+                     DAS-capable endpoint (e.g. Helius)."
+                        .to_string()
+                } else {
+                    format!("Error: {}", e)
+                }
+            })
+    }
+
+    /// Magic Eden collection symbol and floor price (SOL) for the collection
+    /// containing `mint`.
+    async fn floor(&self, mint: &str) -> Option<(String, f64)> {
+        if self.marketplace_url.is_empty() {
+            return None;
+        }
+        let get = |url: String| async move {
+            self.client.get(url).send().await.ok()?.json::<Value>().await.ok()
+        };
+        let token = get(format!("{}/tokens/{}", self.marketplace_url, mint)).await?;
+        let symbol = token["collection"].as_str()?.to_string();
+        let stats = get(format!("{}/collections/{}/stats", self.marketplace_url, symbol)).await?;
+        let floor = stats["floorPrice"].as_f64()? / LAMPORTS_PER_SOL;
+        Some((symbol, floor))
+    }
+}
+
+/// NFTs of one collection held by a wallet.
+#[derive(Debug, Clone, PartialEq)]
+struct Holding {
+    /// Collection address; empty for NFTs without a verified collection.
+    collection: String,
+    name: String,
+    count: usize,
+    /// One mint of the collection, used for the floor lookup.
+    sample_mint: String,
+    /// Item names, for NFTs without a collection.
+    items: Vec<String>,
+}
+
+fn asset_name(asset: &Value) -> String {
+    asset["content"]["metadata"]["name"]
+        .as_str()
+        .filter(|n| !n.is_empty())
+        .unwrap_or("Unnamed")
+        .to_string()
+}
+
+/// Group DAS assets into collections, largest first, skipping fungibles.
+fn group_holdings(items: &[Value]) -> Vec<Holding> {
+    let mut by_collection: HashMap<String, Holding> = HashMap::new();
+    for asset in items {
+        let interface = asset["interface"].as_str().unwrap_or_default();
+        if !NFT_INTERFACES.contains(&interface) || asset["burnt"].as_bool() == Some(true) {
+            continue;
+        }
+        let group = asset["grouping"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|g| g["group_key"] == "collection");
+        let collection = group.and_then(|g| g["group_value"].as_str()).unwrap_or_default();
+        let holding = by_collection.entry(collection.to_string()).or_insert_with(|| Holding {
+            collection: collection.to_string(),
+            name: group
+                .and_then(|g| g["collection_metadata"]["name"].as_str())
+                .filter(|n| !n.is_empty())
+                .map(String::from)
+                .unwrap_or_else(|| {
+                    if collection.is_empty() {
+                        "No collection".into()
+                    } else {
+                        asset_name(asset).trim_end_matches(|c: char| c.is_ascii_digit() || c == '#' || c == ' ').to_string()
+                    }
+                }),
+            count: 0,
+            sample_mint: asset["id"].as_str().unwrap_or_default().to_string(),
+            items: Vec::new(),
+        });
+        holding.count += 1;
+        if collection.is_empty() {
+            holding.items.push(asset_name(asset));
+        }
+    }
+    let mut holdings: Vec<Holding> = by_collection.into_values().collect();
+    holdings.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    holdings
+}
+
+/// Detail view of a DAS asset.
+fn render_asset(asset: &Value) -> String {
+    let meta = &asset["content"]["metadata"];
+    let id = asset["id"].as_str().unwrap_or("?");
+    let mut out = vec![format!("🖼️ *{}*", asset_name(asset))];
+    if let Some(symbol) = meta["symbol"].as_str().filter(|s| !s.is_empty()) {
+        out[0].push_str(&format!(" ({})", symbol));
+    }
+    out.push(format!("Mint: `{}`", id));
+    out.push(format!("Standard: {}", asset["interface"].as_str().unwrap_or("?")));
+    if let Some(collection) = asset["grouping"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|g| g["group_key"] == "collection")
+    {
+        let name = collection["collection_metadata"]["name"].as_str().unwrap_or("");
+        out.push(format!(
+            "Collection: {} `{}`",
+            name,
+            collection["group_value"].as_str().unwrap_or("?")
+        ));
+    }
+    if let Some(owner) = asset["ownership"]["owner"].as_str() {
+        out.push(format!("Owner: `{}`", owner));
+    }
+    if let Some(description) = meta["description"].as_str().filter(|d| !d.is_empty()) {
+        out.push(format!("Description: {}", description));
+    }
+
+    let mut flags = Vec::new();
+    if asset["compression"]["compressed"].as_bool() == Some(true) {
+        flags.push("compressed");
+    }
+    if asset["mutable"].as_bool() == Some(true) {
+        flags.push("mutable metadata");
+    }
+    if asset["ownership"]["frozen"].as_bool() == Some(true) {
+        flags.push("frozen");
+    }
+    if !flags.is_empty() {
+        out.push(format!("Flags: {}", flags.join(", ")));
+    }
+    if let Some(bp) = asset["royalty"]["basis_points"].as_u64() {
+        out.push(format!("Royalty: {}%", bp as f64 / 100.0));
+    }
+
+    let attributes: Vec<String> = meta["attributes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| {
+            let value = match &a["value"] {
+                Value::String(s) => s.clone(),
+                Value::Null => return None,
+                v => v.to_string(),
+            };
+            Some(format!("{}: {}", a["trait_type"].as_str().unwrap_or("?"), value))
+        })
+        .collect();
+    if !attributes.is_empty() {
+        out.push(format!("Traits: {}", attributes.join(" · ")));
+    }
+    if let Some(image) = asset["content"]["links"]["image"].as_str() {
+        out.push(format!("Image: {}", image));
+    }
+    out.push(format!("🔗 [View on Solscan](https://solscan.io/token/{})", id));
+    out.join("\n")
+}
+
+// ── SolanaNftsTool ──────────────────────────────────────────────────
+
+pub struct SolanaNftsTool {
+    indexers: Indexers,
+    wallets: Arc<WalletBook>,
+}
+
+impl SolanaNftsTool {
+    pub fn new(client: Client, config: &NftConfig, rpc_url: &str, wallets: Arc<WalletBook>) -> Self {
+        Self {
+            indexers: Indexers::new(client, config, rpc_url),
+            wallets,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SolanaNftsTool {
+    fn name(&self) -> &str {
+        "solana_nfts"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 4)
+    }
+
+    fn description(&self) -> &str {
+        "List the NFTs held by a Solana wallet, grouped by collection, with floor prices and \
+         an estimated total value. Use alongside the token balance tools to answer \
+         \"what's in this wallet\"."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Solana wallet address; defaults to the configured wallet"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of a configured wallet to use instead of an address"
+                }
+            },
+            "required": []
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let address = match args.get("address").and_then(|v| v.as_str()).filter(|a| !a.is_empty()) {
+            Some(a) => a.to_string(),
+            None => match self
+                .wallets
+                .resolve(args.get("wallet").and_then(|v| v.as_str()), WalletChain::Solana)
+                .and_then(|w| w.require_address().map(String::from))
+            {
+                Ok(a) => a,
+                Err(e) => return e,
+            },
+        };
+
+        let result = match self
+            .indexers
+            .das(
+                "getAssetsByOwner",
+                json!({
+                    "ownerAddress": address,
+                    "page": 1,
+                    "limit": 1000,
+                    "displayOptions": {"showCollectionMetadata": true}
+                }),
+            )
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => return e,
+        };
+        let items = result["items"].as_array().cloned().unwrap_or_default();
+        let holdings = group_holdings(&items);
+        if holdings.is_empty() {
+            return format!("No NFTs found for `{}`.", address);
+        }
+
+        let floors = join_all(
+            holdings
+                .iter()
+                .take(MAX_FLOOR_LOOKUPS)
+                .map(|h| async move {
+                    if h.collection.is_empty() {
+                        None
+                    } else {
+                        self.indexers.floor(&h.sample_mint).await
+                    }
+                }),
+        )
+        .await;
+
+        let total: usize = holdings.iter().map(|h| h.count).sum();
+        let mut out = vec![format!(
+            "🖼️ *NFTs of* `{}`: {} in {} collection(s)",
+            address,
+            total,
+            holdings.len()
+        )];
+        let mut value = 0.0;
+        for (i, h) in holdings.iter().enumerate() {
+            let floor = floors.get(i).cloned().flatten();
+            let line = match floor {
+                Some((symbol, floor)) => {
+                    value += floor * h.count as f64;
+                    format!(
+                        "- *{}* × {} · floor {:.3} SOL ≈ {:.3} SOL ([{}](https://magiceden.io/marketplace/{}))",
+                        h.name,
+                        h.count,
+                        floor,
+                        floor * h.count as f64,
+                        symbol,
+                        symbol
+                    )
+                }
+                None => format!("- *{}* × {}", h.name, h.count),
+            };
+            out.push(line);
+            if h.collection.is_empty() {
+                let shown: Vec<&str> = h.items.iter().take(10).map(String::as_str).collect();
+                out.push(format!("  {}", shown.join(", ")));
+            }
+        }
+        if value > 0.0 {
+            out.push(String::new());
+            out.push(format!("Estimated floor value: *{:.3} SOL*", value));
+        }
+        out.join("\n")
+    }
+}
+
+// ── NftMetadataTool ─────────────────────────────────────────────────
+
+pub struct NftMetadataTool {
+    indexers: Indexers,
+}
+
+impl NftMetadataTool {
+    pub fn new(client: Client, config: &NftConfig, rpc_url: &str) -> Self {
+        Self {
+            indexers: Indexers::new(client, config, rpc_url),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for NftMetadataTool {
+    fn name(&self) -> &str {
+        "nft_metadata"
+    }
+
+    fn description(&self) -> &str {
+        "Show a Solana NFT's metadata: name, collection, owner, traits, royalty, image and \
+         flags such as compressed, mutable or frozen."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "mint": {
+                    "type": "string",
+                    "description": "NFT mint / asset ID"
+                }
+            },
+            "required": ["mint"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(mint) = args.get("mint").and_then(|v| v.as_str()).map(str::trim) else {
+            return "Error: 'mint' parameter is required".into();
+        };
+        let asset = match self
+            .indexers
+            .das("getAsset", json!({"id": mint, "displayOptions": {"showCollectionMetadata": true}}))
+            .await
+        {
+            Ok(a) => a,
+            Err(e) => return e,
+        };
+        if asset.is_null() {
+            return format!("Error: no asset found for `{}`", mint);
+        }
+        let mut text = render_asset(&asset);
+        if let Some((symbol, floor)) = self.indexers.floor(mint).await {
+            text.push_str(&format!("\nCollection floor: {:.3} SOL ({})", floor, symbol));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nft(id: &str, name: &str, collection: Option<(&str, &str)>) -> Value {
+        let grouping = collection.map_or(json!([]), |(addr, cname)| {
+            json!([{"group_key": "collection", "group_value": addr, "collection_metadata": {"name": cname}}])
+        });
+        json!({
+            "interface": "ProgrammableNFT",
+            "id": id,
+            "content": {"metadata": {"name": name, "symbol": "MAD",
+                "attributes": [{"trait_type": "Background", "value": "Blue"}]}},
+            "grouping": grouping,
+            "royalty": {"basis_points": 500},
+            "ownership": {"owner": "Owner111", "frozen": false},
+            "mutable": true,
+            "burnt": false
+        })
+    }
+
+    #[test]
+    fn test_group_holdings() {
+        let items = vec![
+            nft("m1", "Mad Lad #1", Some(("C1", "Mad Lads"))),
+            nft("m2", "Mad Lad #2", Some(("C1", "Mad Lads"))),
+            nft("m3", "Solo", None),
+            json!({"interface": "FungibleToken", "id": "usdc"}),
+        ];
+        let holdings = group_holdings(&items);
+        assert_eq!(holdings.len(), 2);
+        assert_eq!(holdings[0].name, "Mad Lads");
+        assert_eq!(holdings[0].count, 2);
+        assert_eq!(holdings[0].sample_mint, "m1");
+        assert_eq!(holdings[1].name, "No collection");
+        assert_eq!(holdings[1].items, ["Solo"]);
+    }
+
+    #[test]
+    fn test_render_asset() {
+        let text = render_asset(&nft("m1", "Mad Lad #1", Some(("C1", "Mad Lads"))));
+        assert!(text.starts_with("🖼️ *Mad Lad #1* (MAD)"), "{text}");
+        assert!(text.contains("Collection: Mad Lads `C1`"), "{text}");
+        assert!(text.contains("Royalty: 5%"), "{text}");
+        assert!(text.contains("Traits: Background: Blue"), "{text}");
+        assert!(text.contains("Flags: mutable metadata"), "{text}");
+    }
+}
@@ -0,0 +1,500 @@
+//! `run_pipeline`: named chains of tool calls from `tools.pipelines`, run
+//! without the model.
+//!
+//! Each step calls one tool. Its arguments can point into the previous
+//! step's output with a small JSONPath subset — `$` is that output (the
+//! pipeline's input for the first step), `$input` the input and `$item` the
+//! element a `forEach` step is working on, followed by `.key`, `['key']`,
+//! `[0]` or `[*]`. Outputs that are JSON are navigated as such; anything
+//! else is a plain string.
+//!
+//! ```json
+//! "pipelines": {
+//!   "rug_report": {
+//!     "description": "Rugcheck the trending pump.fun tokens",
+//!     "steps": [
+//!       { "tool": "web_fetch", "args": { "url": "https://frontend-api-v3.pump.fun/coins/currently-live?limit=10" } },
+//!       { "tool": "rugcheck", "forEach": "$[*].mint", "args": { "address": "$item" } }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! A failing step stops the pipeline; inside a `forEach` a failing element
+//! only leaves its error in the results. Every call goes through the
+//! registry, so read-only mode, guardrails and approvals apply as usual.
+//! Since it is a tool, a pipeline can also be scheduled as a cron tool call.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{debug, warn};
+
+use super::stats::is_error_output;
+use super::{CostHint, CostTier, Tool, ToolContext, ToolRegistry};
+use crate::config::{PipelineConfig, PipelineStep};
+
+/// Name of the tool; pipelines can't call it themselves.
+pub const RUN_PIPELINE: &str = "run_pipeline";
+
+/// Most elements a `forEach` step calls its tool for.
+const MAX_ITEMS: usize = 25;
+
+/// Where a path starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Root {
+    Previous,
+    Input,
+    Item,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    All,
+}
+
+/// A parsed argument path such as `$.tokens[*].mint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Path {
+    root: Root,
+    segments: Vec<Segment>,
+}
+
+/// The values a step's paths are resolved against.
+struct Scope<'a> {
+    previous: &'a Value,
+    input: &'a Value,
+    item: Option<&'a Value>,
+}
+
+impl Path {
+    fn parse(raw: &str) -> Result<Self, String> {
+        let Some(rest) = raw.strip_prefix('$') else {
+            return Err(format!("'{}' must start with $", raw));
+        };
+        let starts_segment = |s: &str| s.is_empty() || s.starts_with('.') || s.starts_with('[');
+        let (root, mut rest) = match (rest.strip_prefix("input"), rest.strip_prefix("item")) {
+            (Some(after), _) if starts_segment(after) => (Root::Input, after),
+            (_, Some(after)) if starts_segment(after) => (Root::Item, after),
+            _ if starts_segment(rest) => (Root::Previous, rest),
+            _ => return Err(format!("'{}' must start with $, $input or $item", raw)),
+        };
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(format!("'{}' has an empty key", raw));
+                }
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let Some(end) = after.find(']') else {
+                    return Err(format!("'{}' has an unclosed [", raw));
+                };
+                let inner = after[..end].trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                segments.push(match (inner, quoted) {
+                    ("*", _) => Segment::All,
+                    (_, Some(key)) => Segment::Key(key.to_string()),
+                    _ => Segment::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| format!("'{}': [{}] is neither an index, * nor a quoted key", raw, inner))?,
+                    ),
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(format!("'{}': expected . or [ at '{}'", raw, rest));
+            }
+        }
+        Ok(Self { root, segments })
+    }
+
+    /// The value at this path. After a `[*]` the rest of the path is
+    /// applied to every element and the matches are collected in an array.
+    fn select(&self, raw: &str, scope: &Scope<'_>) -> Result<Value, String> {
+        let root = match self.root {
+            Root::Previous => scope.previous,
+            Root::Input => scope.input,
+            Root::Item => scope.item.ok_or_else(|| format!("'{}': $item is only set in forEach steps", raw))?,
+        };
+        let mut values = vec![root.clone()];
+        let mut spread = false;
+        for segment in &self.segments {
+            values = match segment {
+                Segment::All => {
+                    spread = true;
+                    values
+                        .into_iter()
+                        .flat_map(|v| match v {
+                            Value::Array(items) => items,
+                            Value::Object(map) => map.into_iter().map(|(_, v)| v).collect(),
+                            _ => Vec::new(),
+                        })
+                        .collect()
+                }
+                Segment::Key(key) => values.into_iter().filter_map(|mut v| v.get_mut(key).map(Value::take)).collect(),
+                Segment::Index(i) => values.into_iter().filter_map(|mut v| v.get_mut(*i).map(Value::take)).collect(),
+            };
+            if values.is_empty() && !spread {
+                return Err(format!("nothing at '{}'", raw));
+            }
+        }
+        Ok(if spread { Value::Array(values) } else { values.pop().unwrap_or(Value::Null) })
+    }
+}
+
+/// The path a string argument holds, or `None` for a literal.
+fn path_of(value: &str) -> Option<&str> {
+    (value.starts_with('$') && !value.starts_with("$$")).then_some(value)
+}
+
+/// `value` with every path in it replaced by what it points to.
+fn resolve(value: &Value, scope: &Scope<'_>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => match path_of(s) {
+            Some(raw) => Path::parse(raw)?.select(raw, scope)?,
+            None => Value::String(s.strip_prefix('$').unwrap_or(s).to_string()),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(|v| resolve(v, scope)).collect::<Result<_, _>>()?),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), resolve(v, scope)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Check a step's paths before anything runs, for `Config::validate`.
+pub fn check_step(step: &PipelineStep) -> Result<(), String> {
+    fn paths<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::String(s) => out.extend(path_of(s)),
+            Value::Array(items) => items.iter().for_each(|v| paths(v, out)),
+            Value::Object(map) => map.values().for_each(|v| paths(v, out)),
+            _ => {}
+        }
+    }
+    if let Some(raw) = step.for_each.as_deref() {
+        if Path::parse(raw)?.root == Root::Item {
+            return Err(format!("forEach '{}' can't use $item", raw));
+        }
+    }
+    let mut found = Vec::new();
+    step.args.values().for_each(|v| paths(v, &mut found));
+    for raw in found {
+        if Path::parse(raw)?.root == Root::Item && step.for_each.is_none() {
+            return Err(format!("'{}' uses $item without forEach", raw));
+        }
+    }
+    Ok(())
+}
+
+/// A step's output as a value paths can navigate: parsed JSON when it is
+/// JSON, the text otherwise.
+fn output_value(output: String) -> Value {
+    match serde_json::from_str::<Value>(output.trim()) {
+        Ok(value @ (Value::Object(_) | Value::Array(_))) => value,
+        _ => Value::String(output),
+    }
+}
+
+/// The pipeline's result as text: strings as they are, a list of strings
+/// one after another, anything else as JSON.
+fn render(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        Value::Array(items) if items.is_empty() => "The pipeline produced no results.".into(),
+        Value::Array(items) if items.iter().all(Value::is_string) => items
+            .into_iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        other => serde_json::to_string_pretty(&other).unwrap_or_default(),
+    }
+}
+
+pub struct RunPipelineTool {
+    pipelines: BTreeMap<String, PipelineConfig>,
+    description: String,
+}
+
+impl RunPipelineTool {
+    pub fn new(pipelines: BTreeMap<String, PipelineConfig>) -> Self {
+        let mut description = String::from(
+            "Run a predefined pipeline: a fixed chain of tool calls where each step feeds the next. \
+             Prefer it over calling the same tools one by one. Pipelines:",
+        );
+        for (name, pipeline) in &pipelines {
+            let steps: Vec<&str> = pipeline.steps.iter().map(|s| s.tool.as_str()).collect();
+            description.push_str(&format!("\n- {} ({})", name, steps.join(" → ")));
+            if !pipeline.description.is_empty() {
+                description.push_str(&format!(": {}", pipeline.description));
+            }
+        }
+        Self { pipelines, description }
+    }
+
+    /// Call a step's tool with its arguments resolved in `scope`.
+    async fn call(
+        tools: &ToolRegistry,
+        step: &PipelineStep,
+        scope: &Scope<'_>,
+        ctx: &ToolContext,
+    ) -> Result<Value, String> {
+        let args = step
+            .args
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), resolve(v, scope)?)))
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        let output = tools.try_execute(&step.tool, args, ctx).await.map_err(|e| e.to_string())?;
+        if is_error_output(&output) {
+            return Err(output);
+        }
+        Ok(output_value(output))
+    }
+
+    /// Run the steps in order; a failing step's error names it.
+    async fn run(
+        tools: &ToolRegistry,
+        name: &str,
+        pipeline: &PipelineConfig,
+        input: Value,
+        ctx: &ToolContext,
+    ) -> Result<Value, String> {
+        // Approving the pipeline doesn't approve what its steps do.
+        let ctx = &ctx.clone().with_approval(false).with_pipeline();
+        let mut previous = input.clone();
+        for (i, step) in pipeline.steps.iter().enumerate() {
+            let label = format!("step {} ({})", i + 1, step.tool);
+            if step.tool == RUN_PIPELINE {
+                return Err(format!("{}: pipelines can't run other pipelines", label));
+            }
+            ctx.progress(format!("⛓️ {}: {}/{} {}", name, i + 1, pipeline.steps.len(), step.tool))
+                .await;
+            let scope = Scope { previous: &previous, input: &input, item: None };
+            previous = match step.for_each.as_deref() {
+                None => Self::call(tools, step, &scope, ctx).await,
+                Some(raw) => match Path::parse(raw).and_then(|path| path.select(raw, &scope)) {
+                    Ok(Value::Array(items)) => {
+                        if items.len() > MAX_ITEMS {
+                            warn!(pipeline = name, step = i + 1, items = items.len(), "Only running the first {} elements", MAX_ITEMS);
+                        }
+                        let mut results = Vec::new();
+                        for item in items.iter().take(MAX_ITEMS) {
+                            let scope = Scope { item: Some(item), ..scope };
+                            results.push(Self::call(tools, step, &scope, ctx).await.unwrap_or_else(Value::String));
+                        }
+                        Ok(Value::Array(results))
+                    }
+                    Ok(_) => Err(format!("forEach '{}' is not a list", raw)),
+                    Err(e) => Err(e),
+                },
+            }
+            .map_err(|e| format!("{}: {}", label, e))?;
+            debug!(pipeline = name, step = i + 1, "Pipeline step done");
+        }
+        Ok(previous)
+    }
+}
+
+#[async_trait]
+impl Tool for RunPipelineTool {
+    fn name(&self) -> &str {
+        RUN_PIPELINE
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 10)
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "enum": self.pipelines.keys().collect::<Vec<_>>(),
+                    "description": "Pipeline to run"
+                },
+                "input": {
+                    "type": "object",
+                    "description": "Values the pipeline reads as $input, if it takes any"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(name) = args.get("name").and_then(|v| v.as_str()) else {
+            return "Error: 'name' is required".into();
+        };
+        let Some(pipeline) = self.pipelines.get(name) else {
+            return format!("Error: unknown pipeline '{}'", name);
+        };
+        let Some(tools) = ctx.tools() else {
+            return "Error: pipelines can't run here; no tools are available to this call".into();
+        };
+        let input = args.get("input").cloned().unwrap_or_else(|| json!({}));
+        match Self::run(tools, name, pipeline, input, ctx).await {
+            Ok(value) => render(value),
+            Err(e) => format!("Error: pipeline '{}' failed at {}", name, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::IntentCategory;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_paths() {
+        let previous = json!({"tokens": [{"mint": "A", "tags": ["new"]}, {"mint": "B"}], "odd key": 1});
+        let input = json!({"limit": 5});
+        let scope = Scope { previous: &previous, input: &input, item: None };
+        let at = |raw: &str| Path::parse(raw).and_then(|p| p.select(raw, &scope));
+
+        assert_eq!(at("$").unwrap(), previous);
+        assert_eq!(at("$.tokens[1].mint").unwrap(), json!("B"));
+        assert_eq!(at("$.tokens[*].mint").unwrap(), json!(["A", "B"]));
+        assert_eq!(at("$.tokens[*].tags[0]").unwrap(), json!(["new"]));
+        assert_eq!(at("$['odd key']").unwrap(), json!(1));
+        assert_eq!(at("$input.limit").unwrap(), json!(5));
+        assert_eq!(at("$.tokens[5]").unwrap_err(), "nothing at '$.tokens[5]'");
+        assert!(at("$item").unwrap_err().contains("only set in forEach"));
+        assert!(Path::parse("$tokens").is_err());
+        assert!(Path::parse("$.tokens[x]").is_err());
+        assert!(Path::parse("$.tokens[0").is_err());
+
+        let args = json!({"q": "$$5 off", "where": {"mint": "$.tokens[0].mint"}, "n": 3});
+        assert_eq!(resolve(&args, &scope).unwrap(), json!({"q": "$5 off", "where": {"mint": "A"}, "n": 3}));
+    }
+
+    #[test]
+    fn test_check_step() {
+        let step = |for_each: Option<&str>, arg: &str| PipelineStep {
+            tool: "rugcheck".into(),
+            args: json!({ "address": arg }).as_object().cloned().unwrap(),
+            for_each: for_each.map(str::to_string),
+        };
+        assert!(check_step(&step(Some("$[*].mint"), "$item")).is_ok());
+        assert!(check_step(&step(None, "$$item")).is_ok());
+        assert!(check_step(&step(None, "$item")).unwrap_err().contains("without forEach"));
+        assert!(check_step(&step(Some("$item"), "$item")).is_err());
+        assert!(check_step(&step(None, "$.a[")).is_err());
+    }
+
+    struct Trending;
+
+    #[async_trait]
+    impl Tool for Trending {
+        fn name(&self) -> &str {
+            "trending"
+        }
+        fn description(&self) -> &str {
+            "Trending tokens"
+        }
+        fn parameters(&self) -> Value {
+            json!({"type": "object"})
+        }
+        async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+            let limit = args.get("limit").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let coins: Vec<_> = ["AAA", "BBB", "CCC"].iter().take(limit).map(|m| json!({"mint": m})).collect();
+            Value::Array(coins).to_string()
+        }
+    }
+
+    struct Check;
+
+    #[async_trait]
+    impl Tool for Check {
+        fn name(&self) -> &str {
+            "check"
+        }
+        fn description(&self) -> &str {
+            "Check a token"
+        }
+        fn parameters(&self) -> Value {
+            json!({"type": "object"})
+        }
+        async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+            if ctx.approved || !ctx.in_pipeline {
+                return "Error: not called as an unapproved pipeline step".into();
+            }
+            match args.get("address").and_then(Value::as_str) {
+                Some("BBB") => "❌ Token `BBB` not found".into(),
+                Some(address) => format!("{}: safe", address),
+                None => "Error: 'address' parameter is required".into(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_feeds_each_step() {
+        let pipelines: BTreeMap<String, PipelineConfig> = serde_json::from_value(json!({
+            "report": {
+                "steps": [
+                    { "tool": "trending", "args": { "limit": "$input.limit" } },
+                    { "tool": "check", "forEach": "$[*].mint", "args": { "address": "$item" } }
+                ]
+            },
+            "broken": {
+                "steps": [
+                    { "tool": "check", "args": {} },
+                    { "tool": "trending", "args": {} }
+                ]
+            }
+        }))
+        .unwrap();
+        let tool = RunPipelineTool::new(pipelines);
+        assert!(tool.description().contains("- report (trending → check)"));
+
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Box::new(Trending), IntentCategory::General);
+        registry.register(Box::new(Check), IntentCategory::General);
+        let ctx = ToolContext::new("cli", "direct").with_tools(Arc::clone(&registry));
+        let run = |name: &str, input: Value| {
+            let args = HashMap::from([("name".to_string(), json!(name)), ("input".to_string(), input)]);
+            tool.execute(args, &ctx)
+        };
+
+        assert_eq!(
+            run("report", json!({"limit": 3})).await,
+            "AAA: safe\n\n❌ Token `BBB` not found\n\nCCC: safe"
+        );
+        let approved = ctx.clone().with_approval(true);
+        let args = HashMap::from([("name".to_string(), json!("report")), ("input".to_string(), json!({"limit": 1}))]);
+        assert_eq!(tool.execute(args, &approved).await, "AAA: safe");
+        assert_eq!(run("report", json!({"limit": 0})).await, "The pipeline produced no results.");
+        assert_eq!(
+            run("report", json!({})).await,
+            "Error: pipeline 'report' failed at step 1 (trending): nothing at '$input.limit'"
+        );
+        assert_eq!(
+            run("broken", json!({})).await,
+            "Error: pipeline 'broken' failed at step 1 (check): Error: 'address' parameter is required"
+        );
+        assert_eq!(run("missing", json!({})).await, "Error: unknown pipeline 'missing'");
+        let bare = ToolContext::new("cli", "direct");
+        let out = tool.execute(HashMap::from([("name".to_string(), json!("report"))]), &bare).await;
+        assert!(out.starts_with("Error: pipelines can't run here"), "{}", out);
+    }
+}
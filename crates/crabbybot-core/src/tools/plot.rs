@@ -0,0 +1,716 @@
+//! `plot`: render line, bar and candlestick charts to PNG.
+//!
+//! Data comes inline (`data` as JSON or `csv` as text) or from a CSV/JSON
+//! file in the workspace, so the agent can chart the output of price tools
+//! directly. Charts are written to `charts/` in the workspace and sent to the
+//! originating chat as an attachment. Models that can look at images also get
+//! the chart with the result, so they can read it back and describe it.
+//!
+//! Points are spaced evenly along the x axis and labelled with their x
+//! values. Unix timestamps (seconds or milliseconds) are shown as dates.
+//!
+//! Text is drawn with a system TrueType font. Where none of the usual fonts
+//! is installed the chart is still rendered, without title or axis labels.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use plotters::prelude::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{CostHint, CostTier, Tool, ToolContext};
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 576;
+/// Points beyond this are dropped from the start, keeping the latest data.
+const MAX_POINTS: usize = 5_000;
+/// Font family name registered with plotters.
+const FONT: &str = "sans-serif";
+
+/// Fonts tried in order when the first chart is drawn.
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/liberation-sans/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+const PALETTE: [RGBColor; 6] = [
+    RGBColor(43, 92, 214),
+    RGBColor(230, 126, 34),
+    RGBColor(39, 174, 96),
+    RGBColor(192, 57, 43),
+    RGBColor(142, 68, 173),
+    RGBColor(22, 160, 133),
+];
+
+/// Register the first available system font once. Returns whether text can
+/// be drawn.
+fn font_available() -> bool {
+    static LOADED: OnceLock<bool> = OnceLock::new();
+    *LOADED.get_or_init(|| {
+        FONT_CANDIDATES.iter().any(|path| {
+            let Ok(bytes) = std::fs::read(path) else {
+                return false;
+            };
+            // plotters keeps fonts for the life of the process.
+            let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+            plotters::style::register_font(FONT, FontStyle::Normal, bytes).is_ok()
+        })
+    })
+}
+
+// ── Data ────────────────────────────────────────────────────────────
+
+/// Named columns of equal length.
+#[derive(Debug, Default)]
+struct Frame {
+    columns: Vec<(String, Vec<Value>)>,
+}
+
+impl Frame {
+    fn len(&self) -> usize {
+        self.columns.first().map_or(0, |(_, v)| v.len())
+    }
+
+    fn column(&self, name: &str) -> Result<&[Value], String> {
+        self.columns
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_slice())
+            .ok_or_else(|| {
+                let names: Vec<_> = self.columns.iter().map(|(n, _)| n.as_str()).collect();
+                format!("Error: no column '{}' (columns: {})", name, names.join(", "))
+            })
+    }
+
+    fn is_numeric(&self, name: &str) -> bool {
+        self.column(name)
+            .is_ok_and(|v| v.iter().any(|x| number(x).is_some()))
+    }
+
+    /// Keep only the last `n` rows.
+    fn truncate_front(&mut self, n: usize) {
+        for (_, values) in &mut self.columns {
+            let excess = values.len().saturating_sub(n);
+            values.drain(..excess);
+        }
+    }
+}
+
+/// Build a frame from JSON rows: objects, `[x, y, …]` arrays, or bare
+/// numbers.
+fn frame_from_json(data: &Value) -> Result<Frame, String> {
+    let rows = data
+        .as_array()
+        .ok_or("Error: 'data' must be an array of rows")?;
+    let Some(first) = rows.first() else {
+        return Err("Error: no data to plot".into());
+    };
+
+    let mut frame = Frame::default();
+    match first {
+        Value::Object(obj) => {
+            for key in obj.keys() {
+                let values = rows
+                    .iter()
+                    .map(|r| r.get(key).cloned().unwrap_or(Value::Null))
+                    .collect();
+                frame.columns.push((key.clone(), values));
+            }
+        }
+        Value::Array(arr) => {
+            let names: Vec<String> = match arr.len() {
+                5 => ["x", "open", "high", "low", "close"].map(String::from).to_vec(),
+                2 => vec!["x".into(), "y".into()],
+                n => std::iter::once("x".to_string())
+                    .chain((1..n).map(|i| format!("y{}", i)))
+                    .collect(),
+            };
+            for (i, name) in names.into_iter().enumerate() {
+                let values = rows
+                    .iter()
+                    .map(|r| r.get(i).cloned().unwrap_or(Value::Null))
+                    .collect();
+                frame.columns.push((name, values));
+            }
+        }
+        _ => {
+            frame.columns.push(("x".into(), (0..rows.len()).map(|i| json!(i + 1)).collect()));
+            frame.columns.push(("y".into(), rows.clone()));
+        }
+    }
+    Ok(frame)
+}
+
+/// Build a frame from CSV text with a header row. Fields are split on
+/// commas; surrounding quotes are removed and numeric fields parsed.
+fn frame_from_csv(text: &str) -> Result<Frame, String> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let header = lines.next().ok_or("Error: CSV has no header row")?;
+    let split = |line: &str| -> Vec<String> {
+        line.split(',')
+            .map(|f| f.trim().trim_matches('"').to_string())
+            .collect()
+    };
+
+    let mut frame = Frame {
+        columns: split(header).into_iter().map(|n| (n, Vec::new())).collect(),
+    };
+    for line in lines {
+        let fields = split(line);
+        for (i, (_, values)) in frame.columns.iter_mut().enumerate() {
+            let field = fields.get(i).map(String::as_str).unwrap_or_default();
+            values.push(match field.parse::<f64>() {
+                Ok(n) => json!(n),
+                Err(_) if field.is_empty() => Value::Null,
+                Err(_) => Value::String(field.to_string()),
+            });
+        }
+    }
+    if frame.len() == 0 {
+        return Err("Error: CSV has no data rows".into());
+    }
+    Ok(frame)
+}
+
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|n: &f64| n.is_finite())
+}
+
+/// Axis label for an x value; epoch timestamps become dates.
+fn x_label(v: &Value, with_time: bool) -> String {
+    let date = |dt: Option<DateTime<Utc>>| {
+        dt.map(|d| d.format(if with_time { "%m-%d %H:%M" } else { "%Y-%m-%d" }).to_string())
+    };
+    match v {
+        Value::Number(n) => {
+            let f = n.as_f64().unwrap_or_default();
+            let as_date = if f > 1e12 {
+                date(DateTime::from_timestamp_millis(f as i64))
+            } else if f > 1e9 {
+                date(DateTime::from_timestamp(f as i64, 0))
+            } else {
+                None
+            };
+            as_date.unwrap_or_else(|| n.to_string())
+        }
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Pick the x column: the one asked for, or the first that looks like a
+/// time or label column.
+fn pick_x(frame: &Frame, requested: Option<&str>) -> Result<String, String> {
+    if let Some(name) = requested {
+        frame.column(name)?;
+        return Ok(name.to_string());
+    }
+    const PREFERRED: [&str; 8] = ["x", "time", "timestamp", "date", "t", "label", "name", "symbol"];
+    PREFERRED
+        .iter()
+        .find(|p| frame.column(p).is_ok())
+        .map(|p| p.to_string())
+        .or_else(|| {
+            frame
+                .columns
+                .iter()
+                .find(|(n, _)| !frame.is_numeric(n))
+                .map(|(n, _)| n.clone())
+        })
+        .or_else(|| frame.columns.first().map(|(n, _)| n.clone()))
+        .ok_or_else(|| "Error: no data to plot".into())
+}
+
+// ── Rendering ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Line,
+    Bar,
+    Candlestick,
+}
+
+struct Chart {
+    kind: Kind,
+    title: String,
+    labels: Vec<String>,
+    /// Named series for line and bar charts.
+    series: Vec<(String, Vec<Option<f64>>)>,
+    /// Open, high, low, close per point for candlesticks.
+    candles: Vec<Option<[f64; 4]>>,
+}
+
+impl Chart {
+    fn from_frame(kind: Kind, title: String, frame: &Frame, args: &HashMap<String, Value>) -> Result<Self, String> {
+        let x = pick_x(frame, args.get("x").and_then(|v| v.as_str()))?;
+        let xs = frame.column(&x)?;
+        let with_time = xs.len() > 1
+            && number(&xs[0])
+                .zip(number(&xs[xs.len() - 1]))
+                .is_some_and(|(a, b)| {
+                    let span = (b - a).abs();
+                    span > 0.0 && span < if a > 1e12 { 3 * 86_400_000 } else { 3 * 86_400 } as f64
+                });
+        let labels = xs.iter().map(|v| x_label(v, with_time)).collect();
+
+        let mut chart = Self {
+            kind,
+            title,
+            labels,
+            series: Vec::new(),
+            candles: Vec::new(),
+        };
+
+        if kind == Kind::Candlestick {
+            let field = |key: &str| -> Result<&[Value], String> {
+                let name = args.get(key).and_then(|v| v.as_str()).unwrap_or(key);
+                frame.column(name)
+            };
+            let (o, h, l, c) = (field("open")?, field("high")?, field("low")?, field("close")?);
+            chart.candles = (0..frame.len())
+                .map(|i| Some([number(&o[i])?, number(&h[i])?, number(&l[i])?, number(&c[i])?]))
+                .collect();
+            return Ok(chart);
+        }
+
+        let names: Vec<String> = match args.get("y") {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(a)) => a.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+            _ => frame
+                .columns
+                .iter()
+                .map(|(n, _)| n.clone())
+                .filter(|n| *n != x && frame.is_numeric(n))
+                .collect(),
+        };
+        if names.is_empty() {
+            return Err("Error: no numeric column to plot; name one with 'y'".into());
+        }
+        for name in names {
+            let values = frame.column(&name)?.iter().map(number).collect();
+            chart.series.push((name, values));
+        }
+        Ok(chart)
+    }
+
+    fn y_range(&self) -> Result<(f64, f64), String> {
+        let values: Vec<f64> = if self.kind == Kind::Candlestick {
+            self.candles.iter().flatten().flat_map(|c| [c[1], c[2]]).collect()
+        } else {
+            self.series.iter().flat_map(|(_, v)| v.iter().flatten().copied()).collect()
+        };
+        let (mut lo, mut hi) = values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        if !lo.is_finite() {
+            return Err("Error: the selected columns contain no numbers".into());
+        }
+        if self.kind == Kind::Bar {
+            lo = lo.min(0.0);
+            hi = hi.max(0.0);
+        }
+        let pad = if hi > lo { (hi - lo) * 0.05 } else { hi.abs().max(1.0) * 0.05 };
+        if self.kind != Kind::Bar || lo < 0.0 {
+            lo -= pad;
+        }
+        hi += pad;
+        Ok((lo, hi))
+    }
+
+    fn render(&self, path: &Path) -> Result<(), String> {
+        let text = font_available();
+        let (y_lo, y_hi) = self.y_range()?;
+        let n = self.labels.len();
+
+        let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+
+        let mut builder = ChartBuilder::on(&root);
+        builder.margin(20);
+        if text {
+            builder.x_label_area_size(40).y_label_area_size(70);
+            if !self.title.is_empty() {
+                builder.caption(&self.title, (FONT, 26));
+            }
+        }
+        let mut chart = builder
+            .build_cartesian_2d(-0.5f64..(n as f64 - 0.5), y_lo..y_hi)
+            .map_err(|e| e.to_string())?;
+
+        let x_fmt = |x: &f64| {
+            let i = x.round();
+            if (x - i).abs() < 1e-6 && i >= 0.0 {
+                self.labels.get(i as usize).cloned().unwrap_or_default()
+            } else {
+                String::new()
+            }
+        };
+        let y_fmt = |y: &f64| format_value(*y);
+        let mut mesh = chart.configure_mesh();
+        mesh.light_line_style(RGBColor(235, 235, 235))
+            .x_label_formatter(&x_fmt)
+            .y_label_formatter(&y_fmt);
+        if text {
+            mesh.x_labels(n.min(8)).y_labels(8).label_style((FONT, 14));
+        } else {
+            mesh.x_labels(0).y_labels(0);
+        }
+        mesh.draw().map_err(|e| e.to_string())?;
+
+        // Width of one slot in pixels, for bar and candle bodies.
+        let slot = chart.plotting_area().dim_in_pixel().0 as f64 / n.max(1) as f64;
+
+        match self.kind {
+            Kind::Line => {
+                for (i, (name, values)) in self.series.iter().enumerate() {
+                    let color = PALETTE[i % PALETTE.len()];
+                    let points = values
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(x, y)| y.map(|y| (x as f64, y)));
+                    chart
+                        .draw_series(LineSeries::new(points, color.stroke_width(2)))
+                        .map_err(|e| e.to_string())?
+                        .label(name.as_str())
+                        .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+                }
+            }
+            Kind::Bar => {
+                let count = self.series.len() as f64;
+                let width = 0.8 / count;
+                for (i, (name, values)) in self.series.iter().enumerate() {
+                    let color = PALETTE[i % PALETTE.len()];
+                    let bars = values.iter().enumerate().filter_map(|(x, y)| {
+                        let left = x as f64 - 0.4 + width * i as f64;
+                        y.map(|y| Rectangle::new([(left, 0.0), (left + width, y)], color.filled()))
+                    });
+                    chart
+                        .draw_series(bars)
+                        .map_err(|e| e.to_string())?
+                        .label(name.as_str())
+                        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+                }
+            }
+            Kind::Candlestick => {
+                let body = ((slot * 0.6) as u32).max(1);
+                let candles = self.candles.iter().enumerate().filter_map(|(x, c)| {
+                    c.map(|[o, h, l, c]| {
+                        CandleStick::new(x as f64, o, h, l, c, PALETTE[2].filled(), PALETTE[3].filled(), body)
+                    })
+                });
+                chart.draw_series(candles).map_err(|e| e.to_string())?;
+            }
+        }
+
+        if text && self.series.len() > 1 {
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(RGBColor(200, 200, 200))
+                .label_font((FONT, 14))
+                .draw()
+                .map_err(|e| e.to_string())?;
+        }
+        root.present().map_err(|e| e.to_string())
+    }
+}
+
+fn format_value(v: f64) -> String {
+    let a = v.abs();
+    if a >= 1e9 {
+        format!("{:.2}B", v / 1e9)
+    } else if a >= 1e6 {
+        format!("{:.2}M", v / 1e6)
+    } else if a >= 1e4 {
+        format!("{:.1}K", v / 1e3)
+    } else if a >= 1.0 || a == 0.0 {
+        format!("{:.2}", v)
+    } else {
+        format!("{:.6}", v)
+            .trim_end_matches('0')
+            .to_string()
+    }
+}
+
+/// File name for a chart: a slug of the title plus a timestamp.
+fn chart_file_name(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .take(6)
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "chart".to_string() } else { slug };
+    format!("{}-{}.png", slug, Utc::now().format("%Y%m%d-%H%M%S%3f"))
+}
+
+// ── PlotTool ────────────────────────────────────────────────────────
+
+pub struct PlotTool {
+    workspace: PathBuf,
+}
+
+impl PlotTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+
+    fn load(&self, args: &HashMap<String, Value>, workspace: &Path) -> Result<Frame, String> {
+        if let Some(data) = args.get("data") {
+            // Models sometimes pass the JSON as a string.
+            return match data {
+                Value::String(s) => {
+                    let parsed: Value =
+                        serde_json::from_str(s).map_err(|e| format!("Error: 'data' is not valid JSON: {}", e))?;
+                    frame_from_json(&parsed)
+                }
+                other => frame_from_json(other),
+            };
+        }
+        if let Some(csv) = args.get("csv").and_then(|v| v.as_str()) {
+            return frame_from_csv(csv);
+        }
+        let Some(raw) = args.get("path").and_then(|v| v.as_str()) else {
+            return Err("Error: provide 'data', 'csv' or 'path'".into());
+        };
+
+        let path = workspace.join(raw);
+        let root = workspace.canonicalize().unwrap_or_else(|_| workspace.to_path_buf());
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("Error: cannot read '{}': {}", raw, e))?;
+        if !path.starts_with(&root) {
+            return Err(format!("Error: '{}' is outside the workspace", raw));
+        }
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("Error reading '{}': {}", raw, e))?;
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+            let value: Value = serde_json::from_str(&text).map_err(|e| format!("Error: '{}' is not valid JSON: {}", raw, e))?;
+            frame_from_json(&value)
+        } else {
+            frame_from_csv(&text)
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for PlotTool {
+    fn name(&self) -> &str {
+        "plot"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
+    fn description(&self) -> &str {
+        "Render a line, bar or candlestick chart as a PNG and send it to the chat. \
+         Pass rows inline as `data` (objects, [x, y] pairs, [t, open, high, low, close] \
+         arrays, or plain numbers), as `csv` text, or as a `path` to a CSV/JSON file in the \
+         workspace. Unix timestamps on the x axis are shown as dates. Use it after fetching \
+         prices to answer requests like \"chart SOL over the last week\"."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {
+                    "type": "string",
+                    "enum": ["line", "bar", "candlestick"],
+                    "description": "Chart type (default: line)"
+                },
+                "title": { "type": "string", "description": "Chart title" },
+                "data": {
+                    "type": "array",
+                    "description": "Rows to plot, e.g. [{\"date\": \"2025-01-01\", \"price\": 180.2}] or [[1735689600000, 180.2], …]",
+                    "items": {}
+                },
+                "csv": { "type": "string", "description": "CSV text with a header row" },
+                "path": { "type": "string", "description": "CSV or JSON file in the workspace" },
+                "x": { "type": "string", "description": "Column for the x axis (default: a time/label column)" },
+                "y": {
+                    "description": "Column or columns to plot for line and bar charts (default: all numeric columns)",
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ]
+                },
+                "open": { "type": "string", "description": "Open column for candlesticks (default: open)" },
+                "high": { "type": "string", "description": "High column for candlesticks (default: high)" },
+                "low": { "type": "string", "description": "Low column for candlesticks (default: low)" },
+                "close": { "type": "string", "description": "Close column for candlesticks (default: close)" }
+            }
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let kind = match args.get("kind").and_then(|v| v.as_str()).unwrap_or("line") {
+            "line" => Kind::Line,
+            "bar" => Kind::Bar,
+            "candlestick" | "candle" | "ohlc" => Kind::Candlestick,
+            other => return format!("Error: unknown chart kind '{}' (use line, bar or candlestick)", other),
+        };
+        let title = args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let workspace = ctx.workspace_or(&self.workspace);
+        let mut frame = match self.load(&args, workspace) {
+            Ok(f) => f,
+            Err(e) => return e,
+        };
+        let dropped = frame.len().saturating_sub(MAX_POINTS);
+        frame.truncate_front(MAX_POINTS);
+        let chart = match Chart::from_frame(kind, title.clone(), &frame, &args) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+
+        let dir = workspace.join("charts");
+        let path = dir.join(chart_file_name(&title));
+        let out = path.clone();
+        // Rasterizing is CPU-bound; keep it off the runtime.
+        let rendered = tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&dir).map_err(|e| format!("Error creating {}: {}", dir.display(), e))?;
+            chart.render(&out).map_err(|e| format!("Error rendering chart: {}", e))
+        })
+        .await;
+        match rendered {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return e,
+            Err(e) => return format!("Error: chart task failed: {}", e),
+        }
+
+        let mut notes = Vec::new();
+        if dropped > 0 {
+            notes.push(format!("only the last {} of {} points were plotted", MAX_POINTS, MAX_POINTS + dropped));
+        }
+        if !font_available() {
+            notes.push("no system font was found, so the chart has no title or axis labels".into());
+        }
+        if ctx.show_image(&path) {
+            notes.push("the chart is attached, so check it before describing it".into());
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" Note: {}.", notes.join("; "))
+        };
+
+        if ctx.send_attachment(&path, title).await {
+            format!(
+                "Chart rendered and sent to the chat (saved at {}). Don't describe how to view it.{}",
+                path.display(),
+                notes
+            )
+        } else {
+            format!("Chart saved at {}.{}", path.display(), notes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::events::OutboundMessage;
+    use crate::bus::MessageBus;
+    use std::sync::Arc;
+
+    fn args(v: Value) -> HashMap<String, Value> {
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[test]
+    fn test_frames_from_json_and_csv() {
+        let pairs = frame_from_json(&json!([[1735689600000i64, 180.5], [1735776000000i64, 190.0]])).unwrap();
+        assert_eq!(pick_x(&pairs, None).unwrap(), "x");
+        assert_eq!(x_label(&pairs.column("x").unwrap()[0], false), "2025-01-01");
+
+        let ohlc = frame_from_json(&json!([[1, 1.0, 2.0, 0.5, 1.5]])).unwrap();
+        assert!(ohlc.column("close").is_ok());
+
+        let objects = frame_from_json(&json!([{"price": 1.0, "date": "2025-01-01"}])).unwrap();
+        assert_eq!(pick_x(&objects, None).unwrap(), "date");
+
+        let csv = frame_from_csv("token,\"volume\"\nSOL,120\nBONK,\n").unwrap();
+        assert_eq!(csv.len(), 2);
+        assert_eq!(csv.column("volume").unwrap()[1], Value::Null);
+        assert_eq!(pick_x(&csv, None).unwrap(), "token");
+        assert!(csv.column("price").unwrap_err().contains("columns: token, volume"));
+    }
+
+    #[tokio::test]
+    async fn test_plot_renders_png_and_sends_attachment() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_plot");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let tool = PlotTool::new(tmp.clone());
+
+        // Without a chat the file is only saved.
+        let out = tool
+            .execute(
+                args(json!({"kind": "bar", "csv": "token,volume\nSOL,120\nBONK,-30\n"})),
+                &ToolContext::default(),
+            )
+            .await;
+        assert!(out.starts_with("Chart saved at"), "{out}");
+
+        let (bus, mut receivers) = MessageBus::new(8);
+        let ctx = ToolContext::new("telegram", "42").with_bus(Some(Arc::new(bus)));
+        let out = tool
+            .execute(
+                args(json!({
+                    "kind": "candlestick",
+                    "title": "SOL 7d",
+                    "data": [[1735689600, 180, 185, 175, 182], [1735776000, 182, 190, 181, 188]]
+                })),
+                &ctx,
+            )
+            .await;
+        assert!(out.starts_with("Chart rendered and sent"), "{out}");
+
+        let Ok(OutboundMessage::Attachment { chat_id, path, caption, .. }) = receivers.outbound_rx.try_recv() else {
+            panic!("expected an attachment");
+        };
+        assert_eq!((chat_id.as_str(), caption.as_str()), ("42", "SOL 7d"));
+        assert!(path.starts_with(tmp.join("charts")));
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("sol-7d-"));
+        assert!(std::fs::read(&path).unwrap().starts_with(b"\x89PNG"));
+
+        // A model that can see gets the chart with the result.
+        let seeing = ctx.clone().with_images();
+        let out = tool.execute(args(json!({"csv": "day,price\n1,150\n2,160\n"})), &seeing).await;
+        assert!(out.contains("the chart is attached"), "{out}");
+        assert_eq!(seeing.take_images().len(), 1);
+
+        let out = tool
+            .execute(args(json!({"data": [{"date": "a", "label": "b"}]})), &ctx)
+            .await;
+        assert!(out.starts_with("Error: no numeric column"), "{out}");
+        let out = tool.execute(args(json!({"path": "../../etc/passwd"})), &ctx).await;
+        assert!(out.contains("outside the workspace") || out.contains("cannot read"), "{out}");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -17,7 +17,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{run_polymarket_cli, truncate};
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── Custom Types ───────────────────────────────────────────────────
@@ -140,7 +140,11 @@ impl Tool for PolymarketTrendingTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let limit = args
             .get("limit")
             .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
@@ -239,7 +243,11 @@ impl Tool for PolymarketSearchTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(query) = args.get("query").and_then(|v| v.as_str()) else {
             return "Error: 'query' parameter is required".into();
         };
@@ -325,7 +333,11 @@ impl Tool for PolymarketMarketTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(market_id) = args.get("market_id").and_then(|v| v.as_str()) else {
             return "Error: 'market_id' parameter is required".into();
         };
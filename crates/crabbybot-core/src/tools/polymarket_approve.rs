@@ -9,7 +9,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::require_wallet;
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── PolymarketApproveTool ──────────────────────────────────────────
@@ -31,6 +31,14 @@ impl Tool for PolymarketApproveTool {
         "polymarket_approve"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 15)
+    }
+
+    fn mutates(&self, args: &HashMap<String, Value>) -> bool {
+        matches!(args.get("action").and_then(|v| v.as_str()), Some("set"))
+    }
+
     fn description(&self) -> &str {
         "Check or set ERC-20 (USDC) and ERC-1155 (CTF) contract approvals \
          required before trading on Polymarket. Use 'check' to view status, \
@@ -56,7 +64,7 @@ impl Tool for PolymarketApproveTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(action) = args.get("action").and_then(|v| v.as_str()) else {
             return "Error: 'action' is required (check or set)".into();
         };
@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{build_http_client, truncate};
-use super::Tool;
+use super::{Tool, ToolContext};
 
 const BRIDGE_API_URL: &str = "https://bridge-api.polymarket.com";
 
@@ -84,7 +84,11 @@ impl Tool for PolymarketBridgeTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(action) = args.get("action").and_then(|v| v.as_str()) else {
             return "Error: 'action' is required".into();
         };
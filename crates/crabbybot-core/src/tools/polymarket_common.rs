@@ -3,8 +3,11 @@
 //! Provides HTTP client construction (rustls + DNS overrides), authenticated
 //! CLOB client builders, formatting helpers, and API constants.
 
-use crate::config::PolymarketConfig;
+use super::wallets::{Wallet, WalletBook};
+use crate::config::{PolymarketConfig, WalletChain};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fs;
 use std::net::SocketAddr;
 use std::path::PathBuf;
@@ -154,15 +157,46 @@ pub fn require_wallet(config: &PolymarketConfig) -> Result<String, String> {
     })
 }
 
+/// The wallet a trading tool should sign with: the one named by the
+/// `wallet` argument, else the default Polygon wallet. `None` when no
+/// Polygon wallets are configured, leaving key resolution to
+/// [`resolve_wallet_config`].
+pub fn trading_wallet<'a>(
+    wallets: &'a WalletBook,
+    args: &HashMap<String, Value>,
+) -> Result<Option<&'a Wallet>, String> {
+    let name = args.get("wallet").and_then(|v| v.as_str());
+    if name.is_none() && !wallets.wallets().iter().any(|w| w.chain == WalletChain::Polygon) {
+        return Ok(None);
+    }
+    let wallet = wallets.resolve(name, WalletChain::Polygon)?;
+    if wallet.private_key().is_none() {
+        return Err(format!("Error: wallet '{}' is watch-only and cannot trade", wallet.name));
+    }
+    Ok(Some(wallet))
+}
+
 pub async fn run_polymarket_cli(
     bot_config: &PolymarketConfig,
     args: &[&str],
+) -> anyhow::Result<String> {
+    run_polymarket_cli_as(bot_config, None, args).await
+}
+
+/// Like [`run_polymarket_cli`], signing with `wallet` when given.
+pub async fn run_polymarket_cli_as(
+    bot_config: &PolymarketConfig,
+    wallet: Option<&Wallet>,
+    args: &[&str],
 ) -> anyhow::Result<String> {
     let mut cmd = std::process::Command::new("cargo");
     cmd.args(["run", "-q", "-p", "polymarket-cli", "--", "-o", "compact"]);
     cmd.args(args);
 
-    let (key_opt, sig_type_str, _source) = resolve_wallet_config(bot_config);
+    let (mut key_opt, sig_type_str, _source) = resolve_wallet_config(bot_config);
+    if let Some(key) = wallet.and_then(|w| w.private_key()) {
+        key_opt = Some(key.to_string());
+    }
     if let Some(key) = key_opt {
         cmd.env("POLYMARKET_PRIVATE_KEY", key);
     }
@@ -10,7 +10,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::require_wallet;
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── PolymarketCtfSplitTool ─────────────────────────────────────────
@@ -32,6 +32,10 @@ impl Tool for PolymarketCtfSplitTool {
         "polymarket_ctf_split"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 15)
+    }
+
     fn description(&self) -> &str {
         "Split USDC collateral into conditional YES/NO tokens for a \
          Polymarket market. This is an on-chain Polygon transaction. \
@@ -55,7 +59,7 @@ impl Tool for PolymarketCtfSplitTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let _key = match require_wallet(&self.config) {
             Ok(k) => k,
             Err(e) => return e,
@@ -101,6 +105,10 @@ impl Tool for PolymarketCtfMergeTool {
         "polymarket_ctf_merge"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 15)
+    }
+
     fn description(&self) -> &str {
         "Merge conditional YES/NO tokens back into USDC collateral. \
          This is the reverse of split. On-chain Polygon transaction. \
@@ -124,7 +132,7 @@ impl Tool for PolymarketCtfMergeTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let _key = match require_wallet(&self.config) {
             Ok(k) => k,
             Err(e) => return e,
@@ -170,6 +178,10 @@ impl Tool for PolymarketCtfRedeemTool {
         "polymarket_ctf_redeem"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 15)
+    }
+
     fn description(&self) -> &str {
         "Redeem winning conditional tokens for USDC after a market has \
          resolved. On-chain Polygon transaction. Requires wallet + MATIC."
@@ -188,7 +200,7 @@ impl Tool for PolymarketCtfRedeemTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let _key = match require_wallet(&self.config) {
             Ok(k) => k,
             Err(e) => return e,
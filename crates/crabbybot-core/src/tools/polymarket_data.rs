@@ -10,7 +10,7 @@ use std::collections::HashMap;
 use tracing::{debug, error};
 
 use super::polymarket_common::{build_http_client, format_usd, truncate, DATA_API_URL};
-use super::Tool;
+use super::{Tool, ToolContext};
 
 // ── Types ──────────────────────────────────────────────────────────
 
@@ -89,7 +89,11 @@ impl Tool for PolymarketPositionsTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
             return "Error: 'address' parameter is required".into();
         };
@@ -233,7 +237,11 @@ impl Tool for PolymarketLeaderboardTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let period = args
             .get("period")
             .and_then(|v| v.as_str())
@@ -376,7 +384,11 @@ impl Tool for PolymarketClosedPositionsTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
             return "Error: 'address' is required".into();
         };
@@ -484,7 +496,11 @@ impl Tool for PolymarketTradesTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
             return "Error: 'address' is required".into();
         };
@@ -562,7 +578,11 @@ impl Tool for PolymarketActivityTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
             return "Error: 'address' is required".into();
         };
@@ -640,7 +660,11 @@ impl Tool for PolymarketHoldersTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(market) = args.get("market").and_then(|v| v.as_str()) else {
             return "Error: 'market' is required".into();
         };
@@ -713,7 +737,11 @@ impl Tool for PolymarketOpenInterestTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(market) = args.get("market").and_then(|v| v.as_str()) else {
             return "Error: 'market' is required".into();
         };
@@ -775,7 +803,11 @@ impl Tool for PolymarketVolumeTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(event_id) = args.get("event_id").and_then(|v| v.as_str()) else {
             return "Error: 'event_id' is required".into();
         };
@@ -842,7 +874,11 @@ impl Tool for PolymarketBuilderLeaderboardTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let period = args
             .get("period")
             .and_then(|v| v.as_str())
@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{run_polymarket_cli, truncate};
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── Types ──────────────────────────────────────────────────────────
@@ -94,7 +94,11 @@ impl Tool for PolymarketEventsTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let limit = args
             .get("limit")
             .and_then(|v| v.as_u64())
@@ -188,7 +192,11 @@ impl Tool for PolymarketEventDetailTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(event_id) = args.get("event_id").and_then(|v| v.as_str()) else {
             return "Error: 'event_id' parameter is required".into();
         };
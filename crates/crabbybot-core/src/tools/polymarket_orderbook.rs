@@ -10,7 +10,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{run_polymarket_cli, truncate};
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── Types ──────────────────────────────────────────────────────────
@@ -87,7 +87,11 @@ impl Tool for PolymarketOrderbookTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' is required".into();
         };
@@ -145,6 +149,221 @@ impl Tool for PolymarketOrderbookTool {
     }
 }
 
+// ── PolymarketDepthTool ────────────────────────────────────────────
+
+/// A price level as numbers: (price, size in shares).
+type Level = (f64, f64);
+
+/// Price levels as numbers: bids best (highest) first, asks best (lowest) first.
+fn sorted_levels(book: &OrderBookResponse) -> (Vec<Level>, Vec<Level>) {
+    let parse = |levels: &[OrderBookLevel]| -> Vec<Level> {
+        levels
+            .iter()
+            .filter_map(|l| Some((l.price.parse().ok()?, l.size.parse().ok()?)))
+            .filter(|(_, size): &Level| *size > 0.0)
+            .collect()
+    };
+    let mut bids = parse(&book.bids);
+    let mut asks = parse(&book.asks);
+    bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+    asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+    (bids, asks)
+}
+
+/// Result of walking the book with a hypothetical order.
+#[derive(Debug, PartialEq)]
+struct Fill {
+    shares: f64,
+    usdc: f64,
+    worst_price: f64,
+    /// Whether the book had enough liquidity for the whole order.
+    complete: bool,
+}
+
+impl Fill {
+    fn avg_price(&self) -> f64 {
+        if self.shares > 0.0 {
+            self.usdc / self.shares
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Spend (buy) or raise (sell) `usdc` against `levels`, best price first.
+fn walk_book(levels: &[Level], usdc: f64) -> Fill {
+    let mut fill = Fill { shares: 0.0, usdc: 0.0, worst_price: 0.0, complete: false };
+    for &(price, size) in levels {
+        let remaining = usdc - fill.usdc;
+        if remaining <= 1e-9 {
+            break;
+        }
+        let take = size.min(remaining / price);
+        fill.shares += take;
+        fill.usdc += take * price;
+        fill.worst_price = price;
+    }
+    fill.complete = usdc - fill.usdc <= 1e-6;
+    fill
+}
+
+/// Depth, spread and slippage summary of an order book.
+fn depth_report(book: &OrderBookResponse, within: f64, order: Option<(&str, f64)>) -> String {
+    let (bids, asks) = sorted_levels(book);
+    let (Some(&(best_bid, _)), Some(&(best_ask, _))) = (bids.first(), asks.first()) else {
+        return "❌ The order book is empty on at least one side.".into();
+    };
+    let mid = (best_bid + best_ask) / 2.0;
+    let spread = best_ask - best_bid;
+
+    let depth = |levels: &[Level]| {
+        levels
+            .iter()
+            .filter(|(price, _)| (price - mid).abs() <= within + 1e-9)
+            .fold((0.0, 0.0), |(shares, usdc), (price, size)| (shares + size, usdc + price * size))
+    };
+    let (bid_shares, bid_usdc) = depth(&bids);
+    let (ask_shares, ask_usdc) = depth(&asks);
+
+    let mut out = vec![
+        format!("Implied probability: *{:.1}%* (mid {:.3})", mid * 100.0, mid),
+        format!(
+            "Best bid {:.3} / best ask {:.3} · spread {:.1}¢ ({:.2}% of mid)",
+            best_bid,
+            best_ask,
+            spread * 100.0,
+            spread / mid * 100.0
+        ),
+        format!(
+            "Depth within ±{:.0}¢ of mid: bids {:.0} shares (${:.0}) · asks {:.0} shares (${:.0})",
+            within * 100.0,
+            bid_shares,
+            bid_usdc,
+            ask_shares,
+            ask_usdc
+        ),
+    ];
+
+    if let Some((side, usdc)) = order {
+        let fill = if side == "sell" { walk_book(&bids, usdc) } else { walk_book(&asks, usdc) };
+        let avg = fill.avg_price();
+        let slippage = if side == "sell" { mid - avg } else { avg - mid };
+        out.push(String::new());
+        out.push(format!("*{} ${:.2}*", if side == "sell" { "Sell" } else { "Buy" }, usdc));
+        if fill.shares == 0.0 {
+            out.push("No liquidity on that side.".into());
+        } else {
+            out.push(format!(
+                "Fills {:.1} shares at avg {:.4} (worst {:.3}) · slippage vs mid {:.2}¢ ({:.2}%)",
+                fill.shares,
+                avg,
+                fill.worst_price,
+                slippage * 100.0,
+                slippage / mid * 100.0
+            ));
+            if !fill.complete {
+                out.push(format!(
+                    "⚠️ Only ${:.2} of ${:.2} fills: the book is too thin for this size.",
+                    fill.usdc, usdc
+                ));
+            }
+        }
+    }
+    out.join("\n")
+}
+
+/// Liquidity analysis on top of the order book: implied probability,
+/// spread, depth near the mid and slippage for a hypothetical order.
+#[derive(Clone)]
+pub struct PolymarketDepthTool {
+    pub config: PolymarketConfig,
+}
+
+impl PolymarketDepthTool {
+    pub fn new(config: PolymarketConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for PolymarketDepthTool {
+    fn name(&self) -> &str {
+        "polymarket_depth"
+    }
+
+    fn description(&self) -> &str {
+        "Analyze a Polymarket token's order book: implied probability, bid/ask spread, \
+         liquidity within N cents of the midpoint, and the average fill price and slippage \
+         for a hypothetical order size. Prefer this over polymarket_orderbook for \
+         liquidity questions."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "token_id": {
+                    "type": "string",
+                    "description": "The token ID (numeric string)"
+                },
+                "within_cents": {
+                    "type": "number",
+                    "description": "Depth window around the midpoint in cents (default: 2)"
+                },
+                "order_usdc": {
+                    "type": "number",
+                    "description": "Hypothetical order size in USDC to estimate slippage for (optional)"
+                },
+                "side": {
+                    "type": "string",
+                    "enum": ["buy", "sell"],
+                    "description": "Side of the hypothetical order (default: buy)"
+                }
+            },
+            "required": ["token_id"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
+            return "Error: 'token_id' is required".into();
+        };
+        let within = args.get("within_cents").and_then(Value::as_f64).unwrap_or(2.0).max(0.0) / 100.0;
+        let side = args.get("side").and_then(|v| v.as_str()).unwrap_or("buy");
+        let order = args
+            .get("order_usdc")
+            .and_then(Value::as_f64)
+            .filter(|u| *u > 0.0)
+            .map(|usdc| (side, usdc));
+        debug!(token_id, "Analyzing order book depth");
+
+        let cli_args = vec!["clob", "book", "--token", token_id, "--output", "json"];
+        let output_json = match run_polymarket_cli(&self.config, &cli_args).await {
+            Ok(out) => out,
+            Err(e) => return format!("❌ Failed to fetch order book via CLI: {e}"),
+        };
+        let book: OrderBookResponse = match serde_json::from_str(&output_json) {
+            Ok(b) => b,
+            Err(e) => {
+                return format!(
+                    "❌ Failed to parse order book: {e}\nRaw: {}",
+                    truncate(&output_json, 200)
+                )
+            }
+        };
+
+        format!(
+            "📐 **Depth Analysis** (token: `{}`)\n\n{}",
+            truncate(token_id, 20),
+            depth_report(&book, within, order)
+        )
+    }
+}
+
 // ── PolymarketLastTradeTool ────────────────────────────────────────
 
 /// Get the last trade price for a Polymarket token.
@@ -183,7 +402,11 @@ impl Tool for PolymarketLastTradeTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' is required".into();
         };
@@ -251,7 +474,11 @@ impl Tool for PolymarketClobMarketTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(cid) = args.get("condition_id").and_then(|v| v.as_str()) else {
             return "Error: 'condition_id' is required".into();
         };
@@ -333,7 +560,11 @@ impl Tool for PolymarketTickSizeTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' is required".into();
         };
@@ -364,3 +595,36 @@ impl Tool for PolymarketTickSizeTool {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBookResponse {
+        let levels = |l: &[(&str, &str)]| {
+            l.iter()
+                .map(|(p, s)| OrderBookLevel { price: p.to_string(), size: s.to_string() })
+                .collect()
+        };
+        OrderBookResponse { bids: levels(bids), asks: levels(asks) }
+    }
+
+    #[test]
+    fn test_depth_report() {
+        // The CLOB lists bids ascending and asks descending; order must not matter.
+        let book = book(
+            &[("0.40", "500"), ("0.47", "100"), ("0.48", "200")],
+            &[("0.60", "1000"), ("0.52", "100"), ("0.50", "100")],
+        );
+        let report = depth_report(&book, 0.02, Some(("buy", 100.0)));
+        assert!(report.contains("Implied probability: *49.0%*"), "{report}");
+        assert!(report.contains("spread 2.0¢"), "{report}");
+        assert!(report.contains("bids 300 shares ($143) · asks 100 shares ($50)"), "{report}");
+        // $50 at 0.50, $50 at 0.52: 196.2 shares, avg ≈ 0.5098.
+        assert!(report.contains("Fills 196.2 shares at avg 0.5098 (worst 0.520)"), "{report}");
+
+        let fill = walk_book(&[(0.5, 10.0)], 100.0);
+        assert_eq!(fill, Fill { shares: 10.0, usdc: 5.0, worst_price: 0.5, complete: false });
+        assert!(depth_report(&book, 0.02, Some(("sell", 1_000.0))).contains("⚠️ Only $"));
+    }
+}
@@ -8,7 +8,7 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use tracing::debug;
 
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── PolymarketMyOrdersTool ─────────────────────────────────────────
@@ -48,7 +48,11 @@ impl Tool for PolymarketMyOrdersTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let market = args.get("market").and_then(|v| v.as_str());
         debug!(?market, "Fetching Polymarket orders");
 
@@ -116,7 +120,7 @@ impl Tool for PolymarketCancelOrderTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(order_id) = args.get("order_id").and_then(|v| v.as_str()) else {
             return "Error: 'order_id' is required".into();
         };
@@ -181,7 +185,11 @@ impl Tool for PolymarketBalanceTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let asset_type_str = args
             .get("asset_type")
             .and_then(|v| v.as_str())
@@ -261,7 +269,11 @@ impl Tool for PolymarketRewardsTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let action = args
             .get("action")
             .and_then(|v| v.as_str())
@@ -329,7 +341,11 @@ impl Tool for PolymarketNotificationsTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         debug!("Fetching notifications");
 
         let cli_args = vec!["clob", "notifications"];
@@ -362,6 +378,10 @@ impl Tool for PolymarketApiKeysTool {
         "polymarket_api_keys"
     }
 
+    fn mutates(&self, args: &HashMap<String, Value>) -> bool {
+        matches!(args.get("action").and_then(|v| v.as_str()), Some("create"))
+    }
+
     fn description(&self) -> &str {
         "Manage Polymarket CLOB API keys. List existing keys, create new ones, \
          or delete the current key. Requires a configured wallet."
@@ -381,7 +401,7 @@ impl Tool for PolymarketApiKeysTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let action = args
             .get("action")
             .and_then(|v| v.as_str())
@@ -434,7 +454,11 @@ impl Tool for PolymarketAccountStatusTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         debug!("Checking account status");
 
         let cli_args = vec!["clob", "account-status"];
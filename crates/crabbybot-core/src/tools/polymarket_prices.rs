@@ -10,7 +10,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::run_polymarket_cli;
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── Types ──────────────────────────────────────────────────────────
@@ -18,6 +18,9 @@ use crate::config::PolymarketConfig;
 
 #[derive(Debug, Deserialize)]
 struct PriceHistoryPoint {
+    /// Unix seconds.
+    #[serde(default, alias = "t")]
+    timestamp: Option<i64>,
     #[serde(default, alias = "p")]
     price: Option<f64>,
 }
@@ -72,7 +75,11 @@ impl Tool for PolymarketPriceTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' parameter is required".into();
         };
@@ -125,9 +132,99 @@ impl Tool for PolymarketPriceTool {
     }
 }
 
+// ── Trend stats ────────────────────────────────────────────────────
+
+const DAY_SECS: i64 = 86_400;
+
+/// Summary of a price series. Prices are probabilities (0–1).
+#[derive(Debug, Clone, PartialEq)]
+struct TrendStats {
+    first: f64,
+    last: f64,
+    low: f64,
+    high: f64,
+    /// Change over the last 24h / 7d; `None` when the history does not
+    /// reach back that far.
+    change_24h: Option<f64>,
+    change_7d: Option<f64>,
+    /// Standard deviation of point-to-point changes.
+    volatility: f64,
+}
+
+/// Change from `window` seconds before the last point to the last point.
+/// When the history starts slightly later (within 5% of the window) the
+/// first point stands in, so a `1w` history still yields a 7-day change.
+fn change_over(points: &[(i64, f64)], window: i64) -> Option<f64> {
+    let &(last_t, last_p) = points.last()?;
+    let cutoff = last_t - window;
+    let &(first_t, first_p) = points.first()?;
+    let base = match points.iter().rev().find(|(t, _)| *t <= cutoff) {
+        Some(&(_, p)) => p,
+        None if first_t <= cutoff + window / 20 => first_p,
+        None => return None,
+    };
+    Some(last_p - base)
+}
+
+/// Stats over `points`, which must be sorted by time.
+fn trend_stats(points: &[(i64, f64)]) -> Option<TrendStats> {
+    let first = points.first()?.1;
+    let last = points.last()?.1;
+    let steps: Vec<f64> = points.windows(2).map(|w| w[1].1 - w[0].1).collect();
+    let volatility = if steps.is_empty() {
+        0.0
+    } else {
+        let mean = steps.iter().sum::<f64>() / steps.len() as f64;
+        (steps.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / steps.len() as f64).sqrt()
+    };
+    Some(TrendStats {
+        first,
+        last,
+        low: points.iter().map(|p| p.1).fold(f64::MAX, f64::min),
+        high: points.iter().map(|p| p.1).fold(f64::MIN, f64::max),
+        change_24h: change_over(points, DAY_SECS),
+        change_7d: change_over(points, 7 * DAY_SECS),
+        volatility,
+    })
+}
+
+/// Split the covered time range into `n` equal buckets and keep the
+/// closing price of each, labelled with the bucket's start time. Empty
+/// buckets are skipped.
+fn bucket_closes(points: &[(i64, f64)], n: usize) -> Vec<(i64, f64)> {
+    let (Some(&(start, _)), Some(&(end, _))) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    let n = n.max(1) as i64;
+    let width = ((end - start) / n).max(1);
+    let mut closes: Vec<(i64, f64)> = Vec::new();
+    for &(t, p) in points {
+        let bucket = start + ((t - start) / width).min(n - 1) * width;
+        match closes.last_mut() {
+            Some(last) if last.0 == bucket => last.1 = p,
+            _ => closes.push((bucket, p)),
+        }
+    }
+    closes
+}
+
+fn format_change(change: Option<f64>) -> String {
+    match change {
+        Some(c) => format!("{:+.1} pts", c * 100.0),
+        None => "n/a".into(),
+    }
+}
+
+fn format_bucket_time(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|t| t.format("%b %d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
 // ── PolymarketPriceHistoryTool ─────────────────────────────────────
 
-/// Fetch historical price data for a Polymarket token.
+/// Fetch historical price data for a Polymarket token, bucketed over
+/// time with 24h/7d change and volatility.
 #[derive(Clone)]
 pub struct PolymarketPriceHistoryTool {
     pub config: PolymarketConfig,
@@ -146,9 +243,9 @@ impl Tool for PolymarketPriceHistoryTool {
     }
 
     fn description(&self) -> &str {
-        "Get historical price data for a Polymarket token. Returns price \
-         points over a specified time interval. Useful for seeing how odds \
-         have changed over time."
+        "Get historical price data for a Polymarket token. Returns the price \
+         at regular time buckets plus trend stats: change over the last 24h and \
+         7d and volatility. Useful for seeing how odds have moved over time."
     }
 
     fn parameters(&self) -> Value {
@@ -162,26 +259,39 @@ impl Tool for PolymarketPriceHistoryTool {
                 "interval": {
                     "type": "string",
                     "enum": ["1m", "1h", "6h", "1d", "1w", "max"],
-                    "description": "Time interval for history (default: 1d)"
+                    "description": "Time interval for history (default: 1w)"
                 },
                 "fidelity": {
                     "type": "number",
                     "description": "Number of data points to return (default: 20)"
+                },
+                "buckets": {
+                    "type": "integer",
+                    "description": "Number of time buckets to list (default: 7, max: 24)"
                 }
             },
             "required": ["token_id"]
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' parameter is required".into();
         };
         let interval = args
             .get("interval")
             .and_then(|v| v.as_str())
-            .unwrap_or("1d");
+            .unwrap_or("1w");
         let fidelity = args.get("fidelity").and_then(|v| v.as_u64()).unwrap_or(20);
+        let buckets = args
+            .get("buckets")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(7)
+            .clamp(1, 24) as usize;
 
         debug!(token_id, interval, fidelity, "Fetching price history");
 
@@ -209,16 +319,23 @@ impl Tool for PolymarketPriceHistoryTool {
             Err(e) => return format!("❌ Failed to parse price history: {e}"),
         };
 
-        if history.history.is_empty() {
+        // Points without a timestamp keep their order but cannot anchor
+        // a 24h/7d change.
+        let mut points: Vec<(i64, f64)> = history
+            .history
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| Some((p.timestamp.unwrap_or(i as i64), p.price?)))
+            .collect();
+        points.sort_by_key(|(t, _)| *t);
+
+        let Some(stats) = trend_stats(&points) else {
             return format!("No price history available for token `{token_id}`.");
-        }
+        };
 
-        let points = &history.history;
-        let first_price = points.first().and_then(|p| p.price).unwrap_or(0.0);
-        let last_price = points.last().and_then(|p| p.price).unwrap_or(0.0);
-        let change = last_price - first_price;
-        let change_pct = if first_price > 0.0 {
-            (change / first_price) * 100.0
+        let change = stats.last - stats.first;
+        let change_pct = if stats.first > 0.0 {
+            (change / stats.first) * 100.0
         } else {
             0.0
         };
@@ -226,39 +343,93 @@ impl Tool for PolymarketPriceHistoryTool {
         let arrow = if change >= 0.0 { "📈" } else { "📉" };
 
         // Build a simple ASCII sparkline
-        let prices: Vec<f64> = points.iter().filter_map(|p| p.price).collect();
-        let min = prices.iter().cloned().fold(f64::MAX, f64::min);
-        let max = prices.iter().cloned().fold(f64::MIN, f64::max);
+        let (min, max) = (stats.low, stats.high);
         let sparkline = if max > min {
             let blocks = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
-            prices
+            points
                 .iter()
-                .map(|p| {
+                .map(|(_, p)| {
                     let idx = ((p - min) / (max - min) * 7.0).round() as usize;
                     blocks[idx.min(7)]
                 })
                 .collect::<String>()
         } else {
-            "▅".repeat(prices.len().min(20))
+            "▅".repeat(points.len().min(20))
         };
 
+        let bucket_lines: Vec<String> = bucket_closes(&points, buckets)
+            .into_iter()
+            .map(|(t, p)| format!("- {}: {:.1}%", format_bucket_time(t), p * 100.0))
+            .collect();
+
         format!(
             "📈 **Price History** (token: `{token_id}`, interval: {interval})\n\n\
              {sparkline}\n\n\
              Start: {start:.1}% → End: {end:.1}%\n\
              {arrow} Change: {change:+.1}% ({change_pct:+.1}%)\n\
-             📊 {count} data points | Range: {min:.1}% – {max:.1}%",
+             🕐 24h: {change_24h} | 7d: {change_7d}\n\
+             〰️ Volatility: {volatility:.2} pts per step\n\
+             📊 {count} data points | Range: {min:.1}% – {max:.1}%\n\n\
+             {buckets}",
             token_id = token_id,
             interval = interval,
             sparkline = sparkline,
-            start = first_price * 100.0,
-            end = last_price * 100.0,
+            start = stats.first * 100.0,
+            end = stats.last * 100.0,
             arrow = arrow,
             change = change * 100.0,
             change_pct = change_pct,
+            change_24h = format_change(stats.change_24h),
+            change_7d = format_change(stats.change_7d),
+            volatility = stats.volatility * 100.0,
             count = points.len(),
             min = min * 100.0,
             max = max * 100.0,
+            buckets = bucket_lines.join("\n"),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Eight days of 6-hourly points rising 1 point per step from 40%.
+    fn rising() -> Vec<(i64, f64)> {
+        (0..=32).map(|i| (i * DAY_SECS / 4, 0.40 + i as f64 * 0.01)).collect()
+    }
+
+    #[test]
+    fn test_trend_stats() {
+        let stats = trend_stats(&rising()).unwrap();
+        assert!((stats.last - 0.72).abs() < 1e-9);
+        assert!((stats.change_24h.unwrap() - 0.04).abs() < 1e-9);
+        assert!((stats.change_7d.unwrap() - 0.28).abs() < 1e-9);
+        assert!(stats.volatility < 1e-9);
+
+        // Two days of history: no 7-day change.
+        let short = &rising()[..9];
+        assert!(trend_stats(short).unwrap().change_7d.is_none());
+        // Starting six hours short of a week still counts as a 7-day change.
+        let almost_week = &rising()[5..];
+        assert!((trend_stats(almost_week).unwrap().change_7d.unwrap() - 0.27).abs() < 1e-9);
+        assert!(trend_stats(&[]).is_none());
+
+        let choppy = [(0, 0.5), (60, 0.6), (120, 0.5), (180, 0.6)];
+        let stats = trend_stats(&choppy).unwrap();
+        assert!(stats.volatility > 0.09);
+        assert_eq!(format_change(stats.change_24h), "n/a");
+    }
+
+    #[test]
+    fn test_bucket_closes() {
+        let closes = bucket_closes(&rising(), 4);
+        assert_eq!(closes.len(), 4);
+        assert_eq!(closes[0].0, 0);
+        assert_eq!(closes[1].0, 2 * DAY_SECS);
+        // Each bucket closes on its last point; the final bucket takes the end.
+        assert!((closes[0].1 - 0.47).abs() < 1e-9);
+        assert!((closes[3].1 - 0.72).abs() < 1e-9);
+        assert_eq!(format_bucket_time(0), "Jan 01 00:00");
+    }
+}
@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{build_http_client, truncate, GAMMA_API_URL};
-use super::Tool;
+use super::{Tool, ToolContext};
 
 // ── PolymarketSportsTool ───────────────────────────────────────────
 
@@ -55,7 +55,11 @@ impl Tool for PolymarketSportsTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let action = args
             .get("action")
             .and_then(|v| v.as_str())
@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use tracing::debug;
 
 use super::polymarket_common::{build_http_client, CLOB_API_URL, GAMMA_API_URL};
-use super::Tool;
+use super::{Tool, ToolContext};
 
 // ── PolymarketStatusTool ───────────────────────────────────────────
 
@@ -41,7 +41,11 @@ impl Tool for PolymarketStatusTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         debug!("Checking Polymarket API status");
 
         let client = match build_http_client() {
@@ -22,7 +22,7 @@ use alloy::primitives::U256;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 
 // ── Constants ──────────────────────────────────────────────────────
 
@@ -87,6 +87,10 @@ impl Tool for PolymarketStreamTool {
         "polymarket_stream"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 15)
+    }
+
     fn description(&self) -> &str {
         "Stream real-time Polymarket market data via WebSocket. \
          Subscribes to the given token/asset IDs and collects up to \
@@ -117,7 +121,11 @@ impl Tool for PolymarketStreamTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         match self.run(args).await {
             Ok(output) => output,
             Err(e) => format!("❌ WebSocket stream error: {e}"),
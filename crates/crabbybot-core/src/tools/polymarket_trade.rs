@@ -6,21 +6,52 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::debug;
 
-use super::Tool;
+use super::polymarket_common::{run_polymarket_cli_as, trading_wallet};
+use super::wallets::WalletBook;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
+/// A text argument, or a number the model passed unquoted.
+fn text_arg(args: &HashMap<String, Value>, name: &str) -> Option<String> {
+    match args.get(name)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// USDC a limit order commits. Only buys commit new funds; sells are not
+/// counted against limits.
+fn limit_order_cost(side: &str, price: &str, size: &str) -> f64 {
+    match (side, price.parse::<f64>(), size.parse::<f64>()) {
+        ("buy", Ok(price), Ok(size)) => price * size,
+        _ => 0.0,
+    }
+}
+
+/// USDC a market order commits. Buy amounts are USDC; sell amounts are
+/// shares and not counted.
+fn market_order_cost(side: &str, amount: &str) -> f64 {
+    match side {
+        "buy" => amount.parse::<f64>().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
 // ── PolymarketCreateOrderTool ──────────────────────────────────────
 
 /// Place a limit order on the Polymarket CLOB.
 pub struct PolymarketCreateOrderTool {
     config: PolymarketConfig,
+    wallets: Arc<WalletBook>,
 }
 
 impl PolymarketCreateOrderTool {
-    pub fn new(config: PolymarketConfig) -> Self {
-        Self { config }
+    pub fn new(config: PolymarketConfig, wallets: Arc<WalletBook>) -> Self {
+        Self { config, wallets }
     }
 }
 
@@ -30,6 +61,11 @@ impl Tool for PolymarketCreateOrderTool {
         "polymarket_create_order"
     }
 
+    fn trade_usd(&self, args: &HashMap<String, Value>) -> Option<f64> {
+        let arg = |name| text_arg(args, name).unwrap_or_default();
+        Some(limit_order_cost(&arg("side"), &arg("price"), &arg("size")))
+    }
+
     fn description(&self) -> &str {
         "Place a limit order on Polymarket's CLOB. Specify a token ID, \
          side (buy/sell), price (0-1.00), and size (number of shares). \
@@ -62,28 +98,42 @@ impl Tool for PolymarketCreateOrderTool {
                     "type": "string",
                     "enum": ["GTC", "FOK", "GTD", "FAK"],
                     "description": "Order type (default: GTC). GTC=Good-Til-Cancelled, FOK=Fill-Or-Kill, GTD=Good-Til-Date, FAK=Fill-And-Kill"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of the Polygon wallet to trade from (default: the configured wallet)"
                 }
             },
             "required": ["token_id", "side", "price", "size"]
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id_str) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' is required".into();
         };
         let Some(side_str) = args.get("side").and_then(|v| v.as_str()) else {
             return "Error: 'side' is required".into();
         };
-        let Some(price_str) = args.get("price").and_then(|v| v.as_str()) else {
+        let Some(price) = text_arg(&args, "price") else {
             return "Error: 'price' is required".into();
         };
-        let Some(size_str) = args.get("size").and_then(|v| v.as_str()) else {
+        let Some(size) = text_arg(&args, "size") else {
             return "Error: 'size' is required".into();
         };
         let order_type_str = args.get("order_type").and_then(|v| v.as_str());
+        let wallet = match trading_wallet(&self.wallets, &args) {
+            Ok(w) => w,
+            Err(e) => return e,
+        };
+        let cost = limit_order_cost(side_str, &price, &size);
+        if let Some(w) = wallet {
+            if let Err(e) = self.wallets.reserve(w, cost) {
+                return e;
+            }
+        }
 
-        debug!(%token_id_str, ?side_str, %price_str, %size_str, "Creating Polymarket limit order");
+        debug!(%token_id_str, ?side_str, %price, %size, "Creating Polymarket limit order");
 
         let mut cli_args = vec![
             "clob",
@@ -93,9 +143,9 @@ impl Tool for PolymarketCreateOrderTool {
             "--side",
             side_str,
             "--price",
-            price_str,
+            &price,
             "--size",
-            size_str,
+            &size,
         ];
 
         if let Some(ot) = order_type_str {
@@ -103,9 +153,12 @@ impl Tool for PolymarketCreateOrderTool {
             cli_args.push(ot);
         }
 
-        match crate::tools::polymarket_common::run_polymarket_cli(&self.config, &cli_args).await {
+        match run_polymarket_cli_as(&self.config, wallet, &cli_args).await {
             Ok(output) => format!("✅ Limit Order Result:\n\n{}", output),
             Err(e) => {
+                if let Some(w) = wallet {
+                    self.wallets.refund(w, cost);
+                }
                 let err_msg = e.to_string();
                 if err_msg.contains("No API keys found")
                     || err_msg.contains("Failed to authenticate")
@@ -127,11 +180,12 @@ impl Tool for PolymarketCreateOrderTool {
 /// Place a market order on the Polymarket CLOB.
 pub struct PolymarketMarketOrderTool {
     config: PolymarketConfig,
+    wallets: Arc<WalletBook>,
 }
 
 impl PolymarketMarketOrderTool {
-    pub fn new(config: PolymarketConfig) -> Self {
-        Self { config }
+    pub fn new(config: PolymarketConfig, wallets: Arc<WalletBook>) -> Self {
+        Self { config, wallets }
     }
 }
 
@@ -141,6 +195,11 @@ impl Tool for PolymarketMarketOrderTool {
         "polymarket_market_order"
     }
 
+    fn trade_usd(&self, args: &HashMap<String, Value>) -> Option<f64> {
+        let arg = |name| text_arg(args, name).unwrap_or_default();
+        Some(market_order_cost(&arg("side"), &arg("amount")))
+    }
+
     fn description(&self) -> &str {
         "Place a market order on Polymarket's CLOB. Buys or sells at the \
          best available price. Specify a token ID, side, and dollar amount. \
@@ -164,24 +223,39 @@ impl Tool for PolymarketMarketOrderTool {
                 "amount": {
                     "type": "string",
                     "description": "Dollar amount for buys (e.g. '5' for $5 USDC), or share count for sells"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of the Polygon wallet to trade from (default: the configured wallet)"
                 }
             },
             "required": ["token_id", "side", "amount"]
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(token_id_str) = args.get("token_id").and_then(|v| v.as_str()) else {
             return "Error: 'token_id' is required".into();
         };
         let Some(side_str) = args.get("side").and_then(|v| v.as_str()) else {
             return "Error: 'side' is required".into();
         };
-        let Some(amount_str) = args.get("amount").and_then(|v| v.as_str()) else {
+        let Some(amount) = text_arg(&args, "amount") else {
             return "Error: 'amount' is required".into();
         };
 
-        debug!(%token_id_str, ?side_str, %amount_str, "Creating Polymarket market order");
+        let wallet = match trading_wallet(&self.wallets, &args) {
+            Ok(w) => w,
+            Err(e) => return e,
+        };
+        let cost = market_order_cost(side_str, &amount);
+        if let Some(w) = wallet {
+            if let Err(e) = self.wallets.reserve(w, cost) {
+                return e;
+            }
+        }
+
+        debug!(%token_id_str, ?side_str, %amount, "Creating Polymarket market order");
 
         let cli_args = vec![
             "clob",
@@ -191,12 +265,15 @@ impl Tool for PolymarketMarketOrderTool {
             "--side",
             side_str,
             "--amount",
-            amount_str,
+            &amount,
         ];
 
-        match crate::tools::polymarket_common::run_polymarket_cli(&self.config, &cli_args).await {
+        match run_polymarket_cli_as(&self.config, wallet, &cli_args).await {
             Ok(output) => format!("✅ Market Order Result:\n\n{}", output),
             Err(e) => {
+                if let Some(w) = wallet {
+                    self.wallets.refund(w, cost);
+                }
                 let err_msg = e.to_string();
                 if err_msg.contains("No API keys found")
                     || err_msg.contains("Failed to authenticate")
@@ -212,3 +289,25 @@ impl Tool for PolymarketMarketOrderTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolsConfig;
+
+    #[test]
+    fn test_trade_usd_accepts_numbers() {
+        let wallets = Arc::new(WalletBook::from_config(&ToolsConfig::default(), &std::env::temp_dir()));
+        let tool = PolymarketCreateOrderTool::new(PolymarketConfig::default(), Arc::clone(&wallets));
+        let args = HashMap::from([
+            ("side".to_string(), json!("buy")),
+            ("price".to_string(), json!(0.5)),
+            ("size".to_string(), json!(3000)),
+        ]);
+        assert_eq!(tool.trade_usd(&args), Some(1500.0));
+
+        let tool = PolymarketMarketOrderTool::new(PolymarketConfig::default(), wallets);
+        let args = HashMap::from([("side".to_string(), json!("buy")), ("amount".to_string(), json!(750))]);
+        assert_eq!(tool.trade_usd(&args), Some(750.0));
+    }
+}
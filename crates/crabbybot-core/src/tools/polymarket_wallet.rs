@@ -6,7 +6,7 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
-use super::Tool;
+use super::{Tool, ToolContext};
 use crate::config::PolymarketConfig;
 
 // ── PolymarketWalletTool ───────────────────────────────────────────
@@ -42,7 +42,11 @@ impl Tool for PolymarketWalletTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let (key, _sig, source) =
             crate::tools::polymarket_common::resolve_wallet_config(&self.config);
 
@@ -118,7 +122,7 @@ impl Tool for PolymarketWalletCreateTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         // Run with a dummy config since we don't need existing keys to create one
         let dummy_config = PolymarketConfig::default();
         let cli_args = vec!["wallet", "create"];
@@ -164,7 +168,7 @@ impl Tool for PolymarketWalletImportTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(key) = args.get("private_key").and_then(|v| v.as_str()) else {
             return "❌ Missing parameter `private_key`.".to_string();
         };
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::tools::Tool;
+use crate::tools::{Tool, ToolContext};
 
 use super::graph::KnowledgeGraph;
 
@@ -43,7 +43,11 @@ impl Tool for GraphQueryTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let query = args
             .get("query")
             .and_then(|v| v.as_str())
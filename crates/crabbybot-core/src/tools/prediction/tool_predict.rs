@@ -14,7 +14,7 @@ use tokio::sync::Mutex;
 use tracing::info;
 
 use crate::provider::LlmProvider;
-use crate::tools::Tool;
+use crate::tools::{CostHint, CostTier, Tool, ToolContext};
 
 use super::{graph_builder, ontology, profile_gen, report, simulation};
 use super::types::SimulationConfig;
@@ -37,6 +37,10 @@ impl Tool for PredictTool {
         "predict"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 120)
+    }
+
     fn description(&self) -> &str {
         "Run a multi-agent prediction simulation. Takes seed text (news, policies) and a \
          prediction requirement, builds a knowledge graph, simulates agent interactions, \
@@ -68,7 +72,7 @@ impl Tool for PredictTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let text = args
             .get("text")
             .and_then(|v| v.as_str())
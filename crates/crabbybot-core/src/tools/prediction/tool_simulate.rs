@@ -8,7 +8,7 @@ use serde_json::Value;
 use tracing::info;
 
 use crate::provider::LlmProvider;
-use crate::tools::Tool;
+use crate::tools::{CostHint, CostTier, Tool, ToolContext};
 
 use super::graph::KnowledgeGraph;
 use super::tool_predict::PredictionState;
@@ -26,6 +26,10 @@ impl Tool for SimulateTool {
         "simulate"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 60)
+    }
+
     fn description(&self) -> &str {
         "Run a multi-agent simulation on an existing prediction knowledge graph. \
          Use this after `predict` or `graph_query` if you want to re-run the simulation \
@@ -53,7 +57,11 @@ impl Tool for SimulateTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let requirement = args
             .get("requirement")
             .and_then(|v| v.as_str())
@@ -0,0 +1,142 @@
+//! `update_profile` tool: lets the agent maintain per-user preferences.
+//!
+//! Profiles are keyed by the calling user (from the [`ToolContext`]), so
+//! in a group chat each member keeps their own name, language, timezone,
+//! risk tolerance, and watched wallets/tokens.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::{Tool, ToolContext};
+use crate::agent::profile::{ProfileStore, UserProfile};
+
+pub struct UpdateProfileTool {
+    store: ProfileStore,
+}
+
+impl UpdateProfileTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self {
+            store: ProfileStore::new(&workspace),
+        }
+    }
+}
+
+/// Read an optional string field. An empty string clears the field.
+fn apply_field(args: &HashMap<String, Value>, key: &str, field: &mut Option<String>) {
+    if let Some(v) = args.get(key).and_then(|v| v.as_str()) {
+        let v = v.trim();
+        *field = (!v.is_empty()).then(|| v.to_string());
+    }
+}
+
+fn string_list(args: &HashMap<String, Value>, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn apply_list(args: &HashMap<String, Value>, add: &str, remove: &str, list: &mut Vec<String>) {
+    for item in string_list(args, add) {
+        if !list.iter().any(|x| x.eq_ignore_ascii_case(&item)) {
+            list.push(item);
+        }
+    }
+    let removed = string_list(args, remove);
+    list.retain(|x| !removed.iter().any(|r| r.eq_ignore_ascii_case(x)));
+}
+
+#[async_trait]
+impl Tool for UpdateProfileTool {
+    fn name(&self) -> &str {
+        "update_profile"
+    }
+
+    fn description(&self) -> &str {
+        "Update the long-term profile of the user you are talking to: preferred name, \
+         language, timezone, risk tolerance, and favorite wallets/tokens. Use this when \
+         the user states a lasting preference. Only the fields you pass are changed; \
+         pass an empty string to clear a field. Call with no fields to view the profile."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "preferred_name": { "type": "string", "description": "How to address the user" },
+                "language": { "type": "string", "description": "Preferred reply language" },
+                "timezone": { "type": "string", "description": "IANA timezone, e.g. 'America/New_York'" },
+                "risk_tolerance": {
+                    "type": "string",
+                    "description": "Risk tolerance for trading suggestions: 'low', 'medium' or 'high'"
+                },
+                "add_wallets": { "type": "array", "items": { "type": "string" }, "description": "Wallet addresses to remember" },
+                "remove_wallets": { "type": "array", "items": { "type": "string" }, "description": "Wallet addresses to forget" },
+                "add_tokens": { "type": "array", "items": { "type": "string" }, "description": "Tokens or markets to follow" },
+                "remove_tokens": { "type": "array", "items": { "type": "string" }, "description": "Tokens or markets to stop following" }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        if ctx.user_id.is_empty() {
+            return "Error: no user is associated with this conversation".into();
+        }
+
+        let result = self.store.update(&ctx.channel, &ctx.user_id, |p: &mut UserProfile| {
+            apply_field(&args, "preferred_name", &mut p.preferred_name);
+            apply_field(&args, "language", &mut p.language);
+            apply_field(&args, "timezone", &mut p.timezone);
+            apply_field(&args, "risk_tolerance", &mut p.risk_tolerance);
+            apply_list(&args, "add_wallets", "remove_wallets", &mut p.wallets);
+            apply_list(&args, "add_tokens", "remove_tokens", &mut p.tokens);
+        });
+
+        match result {
+            Ok(profile) if profile.is_empty() => "Profile is empty.".into(),
+            Ok(profile) => format!("✅ Profile updated:\n{}", profile.context()),
+            Err(e) => format!("Error saving profile: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_profile_uses_calling_user() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_update_profile");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        let tool = UpdateProfileTool::new(tmp.clone());
+
+        let args: HashMap<String, Value> = [
+            ("timezone".to_string(), json!("Asia/Tokyo")),
+            ("add_tokens".to_string(), json!(["SOL", "BONK", "sol"])),
+        ]
+        .into_iter()
+        .collect();
+        let ctx = ToolContext::new("telegram", "-100").with_user("7");
+        let out = tool.execute(args, &ctx).await;
+        assert!(out.contains("Asia/Tokyo"), "{out}");
+
+        let profile = ProfileStore::new(&tmp).get("telegram", "7").unwrap();
+        assert_eq!(profile.tokens, vec!["SOL", "BONK"]);
+
+        let out = tool.execute(HashMap::new(), &ToolContext::new("telegram", "-100")).await;
+        assert!(out.starts_with("Error"));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,246 @@
+//! `make_qr`: QR codes for addresses, payment URIs and links.
+//!
+//! The code is written as a PNG under `qr/` in the workspace and sent to the
+//! originating chat as an attachment. A bare Solana address combined with an
+//! `amount`, `label` or `message` becomes a Solana Pay transfer URI that
+//! wallets open as a prefilled payment.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use qrcode::{Color, EcLevel, QrCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{Tool, ToolContext};
+
+/// Decimal places of SOL (one lamport).
+const SOL_DECIMALS: usize = 9;
+/// Pixels per QR module.
+const SCALE: usize = 10;
+/// Light border around the code, in modules, required by scanners.
+const QUIET_ZONE: usize = 4;
+
+/// Whether `s` is a base58-encoded 32-byte Solana public key.
+fn is_solana_address(s: &str) -> bool {
+    bs58::decode(s).into_vec().is_ok_and(|b| b.len() == 32)
+}
+
+/// Percent-encode a Solana Pay query value.
+fn encode_query(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A SOL amount as Solana Pay wants it: plain decimal notation, never
+/// `1e-7`, rounded to whole lamports. `None` unless that leaves more than
+/// zero.
+fn format_amount(amount: f64) -> Option<String> {
+    if !amount.is_finite() {
+        return None;
+    }
+    let fixed = format!("{:.*}", SOL_DECIMALS, amount);
+    let trimmed = fixed.trim_end_matches('0').trim_end_matches('.');
+    (amount > 0.0 && trimmed != "0").then(|| trimmed.to_string())
+}
+
+/// Turn an address plus payment details into a Solana Pay URI; anything
+/// else is encoded as given.
+fn qr_content(args: &HashMap<String, Value>) -> Result<String, String> {
+    let content = args
+        .get("content")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or("Error: 'content' parameter is required")?;
+
+    let amount = match args.get("amount") {
+        Some(Value::Number(n)) => Some(n.to_string()),
+        Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        _ => None,
+    };
+    let text = |key: &str| args.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+    let (label, message) = (text("label"), text("message"));
+
+    if amount.is_none() && label.is_none() && message.is_none() {
+        return Ok(content.to_string());
+    }
+    if !is_solana_address(content) {
+        return Err("Error: 'amount', 'label' and 'message' need a Solana address as 'content'".into());
+    }
+    let mut query = Vec::new();
+    if let Some(amount) = amount {
+        match amount.parse::<f64>().ok().and_then(format_amount) {
+            Some(a) => query.push(format!("amount={}", a)),
+            None => return Err(format!("Error: invalid amount '{}'", amount)),
+        }
+    }
+    if let Some(label) = label {
+        query.push(format!("label={}", encode_query(label)));
+    }
+    if let Some(message) = message {
+        query.push(format!("message={}", encode_query(message)));
+    }
+    Ok(format!("solana:{}?{}", content, query.join("&")))
+}
+
+/// Encode `content` and write it as a grayscale PNG.
+fn write_qr_png(content: &str, path: &Path) -> Result<(), String> {
+    let code = QrCode::with_error_correction_level(content, EcLevel::M)
+        .map_err(|e| format!("Error: cannot encode as a QR code: {}", e))?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let side = (modules + 2 * QUIET_ZONE) * SCALE;
+
+    let mut pixels = vec![255u8; side * side];
+    for y in 0..modules {
+        for x in 0..modules {
+            if colors[y * modules + x] != Color::Dark {
+                continue;
+            }
+            let (px, py) = ((x + QUIET_ZONE) * SCALE, (y + QUIET_ZONE) * SCALE);
+            for row in py..py + SCALE {
+                pixels[row * side + px..row * side + px + SCALE].fill(0);
+            }
+        }
+    }
+
+    let file = std::fs::File::create(path).map_err(|e| format!("Error creating {}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), side as u32, side as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&pixels))
+        .map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+pub struct MakeQrTool {
+    workspace: PathBuf,
+}
+
+impl MakeQrTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for MakeQrTool {
+    fn name(&self) -> &str {
+        "make_qr"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a QR code PNG and send it to the chat. Encodes any text, link or wallet \
+         address. Given a Solana address plus `amount`/`label`/`message`, it encodes a \
+         Solana Pay URI that wallets open as a prefilled SOL transfer."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "content": {
+                    "type": "string",
+                    "description": "Text, URL, payment URI or wallet address to encode"
+                },
+                "amount": {
+                    "type": "number",
+                    "description": "SOL amount to request (Solana addresses only)"
+                },
+                "label": {
+                    "type": "string",
+                    "description": "Payee name shown by the wallet (Solana addresses only)"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "Payment note shown by the wallet (Solana addresses only)"
+                },
+                "caption": {
+                    "type": "string",
+                    "description": "Caption sent with the image (default: the encoded content)"
+                }
+            },
+            "required": ["content"]
+        })
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let content = match qr_content(&args) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        let dir = ctx.workspace_or(&self.workspace).join("qr");
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            return format!("Error creating {}: {}", dir.display(), e);
+        }
+        let path = dir.join(format!("qr-{}.png", Utc::now().format("%Y%m%d-%H%M%S%3f")));
+        if let Err(e) = write_qr_png(&content, &path) {
+            return e;
+        }
+
+        let caption = args
+            .get("caption")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map_or_else(|| content.clone(), String::from);
+        if ctx.send_attachment(&path, caption).await {
+            format!("QR code for `{}` sent to the chat (saved at {}).", content, path.display())
+        } else {
+            format!("QR code for `{}` saved at {}.", content, path.display())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: Value) -> HashMap<String, Value> {
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[test]
+    fn test_solana_pay_uri() {
+        let addr = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+        assert_eq!(qr_content(&args(json!({"content": addr}))).unwrap(), addr);
+        assert_eq!(
+            qr_content(&args(json!({"content": addr, "amount": 0.5, "label": "Crab Café"}))).unwrap(),
+            format!("solana:{addr}?amount=0.5&label=Crab%20Caf%C3%A9")
+        );
+        assert!(qr_content(&args(json!({"content": "https://example.com", "amount": 1}))).is_err());
+        assert!(qr_content(&args(json!({"content": addr, "amount": -1}))).is_err());
+        assert_eq!(
+            qr_content(&args(json!({"content": addr, "amount": 1e-7}))).unwrap(),
+            format!("solana:{addr}?amount=0.0000001")
+        );
+        assert_eq!(format_amount(2e21).as_deref(), Some("2000000000000000000000"));
+        assert_eq!(format_amount(1.5).as_deref(), Some("1.5"));
+        assert_eq!(format_amount(1e-12), None);
+        assert!(qr_content(&args(json!({"content": "  "}))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_make_qr_writes_png() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_qr");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let tool = MakeQrTool::new(tmp.clone());
+
+        let out = tool
+            .execute(args(json!({"content": "https://solana.com"})), &ToolContext::default())
+            .await;
+        assert!(out.starts_with("QR code for `https://solana.com` saved at"), "{out}");
+        let file = std::fs::read_dir(tmp.join("qr")).unwrap().next().unwrap().unwrap();
+        let decoder = png::Decoder::new(std::fs::File::open(file.path()).unwrap());
+        let info = decoder.read_info().unwrap();
+        // Version 2 (25 modules) plus the quiet zone on both sides.
+        assert_eq!(info.info().width as usize, (25 + 2 * QUIET_ZONE) * SCALE);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
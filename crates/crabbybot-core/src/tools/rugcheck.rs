@@ -2,7 +2,7 @@
 //!
 //! Provides token safety analysis to the agent.
 
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
@@ -79,6 +79,10 @@ impl Tool for RugCheckTool {
         "rugcheck"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
     fn description(&self) -> &str {
         "Analyze a Solana token contract address (CA) for safety and risk factors (rug pull check). \
          Returns the token's safety score and specific risk warnings (e.g. mint authority, mutable metadata, LP lock). \
@@ -98,7 +102,11 @@ impl Tool for RugCheckTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
             return "❌ Error: 'address' parameter is required".into();
         };
@@ -176,7 +184,7 @@ mod tests {
             "address".to_string(),
             Value::String("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string()),
         );
-        let result = tool.execute(args).await;
+        let result = tool.execute(args, &ToolContext::default()).await;
         println!("RUGCHECK RESULT:\n{}", result);
         assert!(result.contains("Score:"));
     }
@@ -9,8 +9,10 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::Tool;
-use crate::cron::{CronService, Schedule};
+use super::{Tool, ToolContext};
+use crate::agent::locale::LocaleSettings;
+use crate::agent::profile::ProfileStore;
+use crate::cron::{CronService, Schedule, ToolCall};
 
 // ── ScheduleTaskTool ────────────────────────────────────────────────
 
@@ -20,6 +22,8 @@ pub struct ScheduleTaskTool {
     default_channel: String,
     /// Default chat_id for jobs created in contexts where chat_id is unknown.
     default_chat_id: String,
+    /// Resolves the timezone cron expressions are evaluated in.
+    locale: LocaleSettings,
 }
 
 impl ScheduleTaskTool {
@@ -27,11 +31,13 @@ impl ScheduleTaskTool {
         cron: Arc<Mutex<CronService>>,
         default_channel: String,
         default_chat_id: String,
+        locale: LocaleSettings,
     ) -> Self {
         Self {
             cron,
             default_channel,
             default_chat_id,
+            locale,
         }
     }
 }
@@ -45,7 +51,10 @@ impl Tool for ScheduleTaskTool {
     fn description(&self) -> &str {
         "Schedule a recurring task. The task message will be sent to the agent \
          at the specified interval or cron schedule. Use this when the user asks \
-         to be reminded, wants periodic updates, or says 'every hour/day/etc'."
+         to be reminded, wants periodic updates, or says 'every hour/day/etc'. \
+         If the task is just posting one tool's output (e.g. a balance at 9am), set \
+         `tool` and `tool_args` instead of `message` so it runs without the LLM. \
+         Use the message `/digest` to post a summary of a listen-only group chat."
     }
 
     fn parameters(&self) -> Value {
@@ -58,29 +67,61 @@ impl Tool for ScheduleTaskTool {
                 },
                 "schedule": {
                     "type": "string",
-                    "description": "Cron expression (e.g., '0 9 * * *' for 9am daily) or interval with 's' suffix (e.g., '3600s' for every hour, '60s' for every minute)"
+                    "description": "Cron expression (e.g., '0 9 * * *' for 9am daily), evaluated in the user's timezone, or interval with 's' suffix (e.g., '3600s' for every hour, '60s' for every minute)"
                 },
                 "message": {
                     "type": "string",
                     "description": "The prompt/message to process when the task fires (e.g., 'What is the current SOL price?')"
+                },
+                "tool": {
+                    "type": "string",
+                    "description": "Run this tool directly when the task fires instead of prompting the agent"
+                },
+                "tool_args": {
+                    "type": "object",
+                    "description": "Arguments for `tool`"
+                },
+                "format_template": {
+                    "type": "string",
+                    "description": "Message template for `tool` output; {result}, {job} and {time} are substituted"
+                },
+                "cooldown_secs": {
+                    "type": "integer",
+                    "description": "For `tool` jobs: seconds before identical output is posted again (default from config)"
+                },
+                "template": {
+                    "type": "string",
+                    "description": "Name of a template in workspace/templates/ to render `tool` output with instead of format_template"
+                },
+                "critical": {
+                    "type": "boolean",
+                    "description": "Keep running when the daily token budget is nearly used up (default false)"
                 }
             },
-            "required": ["name", "schedule", "message"]
+            "required": ["name", "schedule"]
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(name) = args.get("name").and_then(|v| v.as_str()) else {
             return "Error: 'name' parameter is required".into();
         };
         let Some(schedule_str) = args.get("schedule").and_then(|v| v.as_str()) else {
             return "Error: 'schedule' parameter is required".into();
         };
-        let Some(message) = args.get("message").and_then(|v| v.as_str()) else {
-            return "Error: 'message' parameter is required".into();
+        let tool = args.get("tool").and_then(|v| v.as_str());
+        let message = args.get("message").and_then(|v| v.as_str());
+
+        // Deliver to the chat that asked, falling back to the configured target
+        // for calls made outside a chat (e.g. background services).
+        let (channel, chat_id) = if ctx.has_chat() {
+            (ctx.channel.as_str(), ctx.chat_id.as_str())
+        } else {
+            (self.default_channel.as_str(), self.default_chat_id.as_str())
         };
 
         // Parse schedule: "60s" → Interval, otherwise treat as cron expression
+        // in the requesting user's (or chat's) timezone.
         let schedule = if let Some(secs) = schedule_str.strip_suffix('s') {
             match secs.parse::<u64>() {
                 Ok(s) if s > 0 => Schedule::Interval { seconds: s },
@@ -92,25 +133,58 @@ impl Tool for ScheduleTaskTool {
                 }
             }
         } else {
+            let profile = (!ctx.user_id.is_empty())
+                .then(|| ProfileStore::new(&ctx.workspace).get(&ctx.channel, &ctx.user_id))
+                .flatten();
+            let locale = self.locale.resolve(
+                channel,
+                chat_id,
+                profile.as_ref().and_then(|p| p.timezone.as_deref()),
+            );
             Schedule::Cron {
                 expression: schedule_str.to_string(),
+                timezone: locale.timezone_name().map(String::from),
             }
         };
 
         let mut cron = self.cron.lock().await;
-        match cron.add_job(
-            name,
-            schedule,
-            message,
-            &self.default_channel,
-            &self.default_chat_id,
-        ) {
+        let (added, action) = match (tool, message) {
+            (Some(tool), _) => {
+                let call = ToolCall {
+                    name: tool.to_string(),
+                    args: args
+                        .get("tool_args")
+                        .and_then(|v| v.as_object())
+                        .map(|o| o.clone().into_iter().collect())
+                        .unwrap_or_default(),
+                    format_template: args
+                        .get("format_template")
+                        .and_then(|v| v.as_str())
+                        .map(String::from),
+                    template: args.get("template").and_then(|v| v.as_str()).map(String::from),
+                    cooldown_secs: args.get("cooldown_secs").and_then(|v| v.as_u64()),
+                    owner: ctx.user_id.clone(),
+                };
+                (cron.add_tool_job(name, schedule, call, channel, chat_id), format!("Tool: {}", tool))
+            }
+            (None, Some(message)) => (
+                cron.add_job(name, schedule, message, channel, chat_id),
+                format!("Message: {}", message),
+            ),
+            (None, None) => return "Error: either 'message' or 'tool' is required".into(),
+        };
+        let critical = args.get("critical").and_then(|v| v.as_bool()).unwrap_or(false);
+        let added = match added {
+            Ok(id) if critical => cron.set_critical(&id, true).map(|_| id),
+            other => other,
+        };
+        match added {
             Ok(id) => {
                 format!(
                     "✅ Scheduled task '{}' (ID: {})\n\
                      Schedule: {}\n\
-                     Message: {}",
-                    name, id, schedule_str, message
+                     {}",
+                    name, id, schedule_str, action
                 )
             }
             Err(e) => format!("Error scheduling task: {}", e),
@@ -148,8 +222,13 @@ impl Tool for ListSchedulesTool {
         })
     }
 
-    async fn execute(&self, _args: HashMap<String, Value>) -> String {
-        let cron = self.cron.lock().await;
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let mut cron = self.cron.lock().await;
+        cron.refresh();
         let jobs = cron.list_jobs(true);
 
         if jobs.is_empty() {
@@ -159,7 +238,10 @@ impl Tool for ListSchedulesTool {
         let mut output = format!("📋 {} scheduled task(s):\n\n", jobs.len());
         for job in jobs {
             let schedule_str = match &job.schedule {
-                Schedule::Cron { expression } => format!("cron: {}", expression),
+                Schedule::Cron { expression, timezone } => match timezone {
+                    Some(tz) => format!("cron: {} ({})", expression, tz),
+                    None => format!("cron: {}", expression),
+                },
                 Schedule::Interval { seconds } => format!("every {}s", seconds),
             };
             let status = if job.enabled {
@@ -214,7 +296,7 @@ impl Tool for CancelScheduleTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(job_id) = args.get("job_id").and_then(|v| v.as_str()) else {
             return "Error: 'job_id' parameter is required".into();
         };
@@ -227,3 +309,67 @@ impl Tool for CancelScheduleTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_schedule_task_routes_to_calling_chat() {
+        let dir = std::env::temp_dir().join(format!("crabbybot_sched_ctx_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cron = Arc::new(Mutex::new(CronService::new(&dir)));
+        let mut agents = crate::config::AgentsConfig::default();
+        agents.chats.insert(
+            "discord:42".into(),
+            crate::config::ChatConfig {
+                timezone: Some("Europe/Berlin".into()),
+                locale: None,
+                language: None,
+                ..Default::default()
+            },
+        );
+        let locale = LocaleSettings::from_config(&agents);
+        let tool = ScheduleTaskTool::new(cron.clone(), "telegram".into(), "default".into(), locale);
+
+        let args: HashMap<String, Value> = [
+            ("name".to_string(), json!("ping")),
+            ("schedule".to_string(), json!("60s")),
+            ("message".to_string(), json!("ping")),
+        ]
+        .into_iter()
+        .collect();
+
+        tool.execute(args.clone(), &ToolContext::new("discord", "42")).await;
+        tool.execute(args.clone(), &ToolContext::system()).await;
+
+        // Cron expressions take the chat's timezone.
+        let mut daily = args;
+        daily.insert("schedule".into(), json!("0 0 9 * * *"));
+        tool.execute(daily.clone(), &ToolContext::new("discord", "42")).await;
+
+        // Tool jobs run as the user who scheduled them.
+        daily.remove("message");
+        daily.insert("tool".into(), json!("get_price"));
+        tool.execute(daily, &ToolContext::new("discord", "42").with_user("7")).await;
+
+        let cron = cron.lock().await;
+        let targets: Vec<_> = cron
+            .list_jobs(true)
+            .iter()
+            .map(|j| (j.channel.clone(), j.chat_id.clone()))
+            .collect();
+        assert!(targets.contains(&("discord".into(), "42".into())));
+        assert!(targets.contains(&("telegram".into(), "default".into())));
+        assert!(cron.list_jobs(true).iter().any(|j| matches!(
+            &j.schedule,
+            Schedule::Cron { timezone: Some(tz), .. } if tz == "Europe/Berlin"
+        )));
+        assert!(cron.list_jobs(true).iter().any(|j| matches!(
+            &j.kind,
+            crate::cron::JobKind::ToolCall(call) if call.owner == "7"
+        )));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
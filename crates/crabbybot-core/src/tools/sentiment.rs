@@ -3,7 +3,7 @@
 //! Uses social information from DexScreener/Mobula or other sources to gauge
 //! "Community Pulse" (bullish vs bearish signals).
 
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
@@ -49,6 +49,10 @@ impl Tool for SentimentTool {
         "sentiment"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 4)
+    }
+
     fn description(&self) -> &str {
         "Analyze social sentiment and community health for a Solana token. \
          Checks social presence (Twitter, Telegram), volume trends, and community pulse. \
@@ -68,7 +72,11 @@ impl Tool for SentimentTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(mint) = args.get("mint").and_then(|v| v.as_str()) else {
             return "❌ Error: 'mint' parameter is required".into();
         };
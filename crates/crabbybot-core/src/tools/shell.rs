@@ -11,7 +11,7 @@ use std::time::Duration;
 use tokio::process::Command;
 use tracing::debug;
 
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
 
 pub struct ExecTool {
     workspace: PathBuf,
@@ -35,6 +35,10 @@ impl Tool for ExecTool {
         "shell_exec"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 5)
+    }
+
     fn description(&self) -> &str {
         "Execute a shell command and return its stdout and stderr output."
     }
@@ -60,23 +64,23 @@ impl Tool for ExecTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(command) = args.get("command").and_then(|v| v.as_str()) else {
             return "Error: 'command' parameter is required".into();
         };
 
+        let workspace = ctx.workspace_or(&self.workspace);
         let cwd = args
             .get("cwd")
             .and_then(|v| v.as_str())
-            .map(PathBuf::from)
-            .unwrap_or_else(|| self.workspace.clone());
+            .map(|c| workspace.join(c))
+            .unwrap_or_else(|| workspace.to_path_buf());
 
         // Workspace restriction check
         if self.restrict {
-            let ws = self
-                .workspace
+            let ws = workspace
                 .canonicalize()
-                .unwrap_or_else(|_| self.workspace.clone());
+                .unwrap_or_else(|_| workspace.to_path_buf());
             let cwd_canon = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
             if !cwd_canon.starts_with(&ws) {
                 return format!(
@@ -106,6 +110,8 @@ impl Tool for ExecTool {
                 .arg(flag)
                 .arg(command)
                 .current_dir(&cwd)
+                // Turns cancelled by the bridge watchdog take the command with them.
+                .kill_on_drop(true)
                 .output(),
         )
         .await;
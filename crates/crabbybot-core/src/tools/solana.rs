@@ -15,9 +15,12 @@ use reqwest::Client;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::debug;
 
-use super::Tool;
+use super::wallets::WalletBook;
+use super::{CostHint, CostTier, Tool, ToolContext};
+use crate::config::WalletChain;
 
 /// Lamports per SOL.
 const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
@@ -31,13 +34,13 @@ const SOLSCAN_BASE: &str = "https://solscan.io";
 ///
 /// Provides connection reuse, address validation, and consistent error
 /// handling across all Solana tools.
-struct SolanaRpc {
+pub(super) struct SolanaRpc {
     client: Client,
     rpc_url: String,
 }
 
 impl SolanaRpc {
-    fn new(client: Client, rpc_url: &str) -> Self {
+    pub(super) fn new(client: Client, rpc_url: &str) -> Self {
         Self {
             client,
             rpc_url: rpc_url.to_string(),
@@ -62,7 +65,7 @@ impl SolanaRpc {
     }
 
     /// Execute a JSON-RPC call and return the parsed response.
-    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+    pub(super) async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
         let body = json!({
             "jsonrpc": "2.0",
             "id": 1,
@@ -99,16 +102,34 @@ impl SolanaRpc {
     }
 }
 
+/// The `address` argument, or else the address of the wallet named by
+/// `wallet` (or the default Solana wallet).
+fn target_address(args: &HashMap<String, Value>, wallets: &WalletBook) -> Result<String, String> {
+    if let Some(address) = args.get("address").and_then(|v| v.as_str()).filter(|a| !a.is_empty()) {
+        return Ok(address.to_string());
+    }
+    let name = args.get("wallet").and_then(|v| v.as_str());
+    if name.is_none() && !wallets.wallets().iter().any(|w| w.chain == WalletChain::Solana) {
+        return Err("Error: 'address' parameter is required".into());
+    }
+    wallets
+        .resolve(name, WalletChain::Solana)?
+        .require_address()
+        .map(String::from)
+}
+
 // ── SolanaBalanceTool ───────────────────────────────────────────────
 
 pub struct SolanaBalanceTool {
     rpc: SolanaRpc,
+    wallets: Arc<WalletBook>,
 }
 
 impl SolanaBalanceTool {
-    pub fn new(client: Client, rpc_url: &str) -> Self {
+    pub fn new(client: Client, rpc_url: &str, wallets: Arc<WalletBook>) -> Self {
         Self {
             rpc: SolanaRpc::new(client, rpc_url),
+            wallets,
         }
     }
 }
@@ -130,17 +151,27 @@ impl Tool for SolanaBalanceTool {
             "properties": {
                 "address": {
                     "type": "string",
-                    "description": "Solana wallet address (base58 public key)"
+                    "description": "Solana wallet address (base58 public key); defaults to the configured wallet"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of a configured wallet to use instead of an address (see list_wallets)"
                 }
             },
-            "required": ["address"]
+            "required": []
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
-        let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
-            return "Error: 'address' parameter is required".into();
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let address = match target_address(&args, &self.wallets) {
+            Ok(a) => a,
+            Err(e) => return e,
         };
+        let address = address.as_str();
 
         if let Err(e) = SolanaRpc::validate_address(address) {
             return format!("❌ {}", e);
@@ -169,12 +200,14 @@ impl Tool for SolanaBalanceTool {
 
 pub struct SolanaTransactionsTool {
     rpc: SolanaRpc,
+    wallets: Arc<WalletBook>,
 }
 
 impl SolanaTransactionsTool {
-    pub fn new(client: Client, rpc_url: &str) -> Self {
+    pub fn new(client: Client, rpc_url: &str, wallets: Arc<WalletBook>) -> Self {
         Self {
             rpc: SolanaRpc::new(client, rpc_url),
+            wallets,
         }
     }
 }
@@ -195,6 +228,10 @@ impl Tool for SolanaTransactionsTool {
         "solana_transactions"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
     fn description(&self) -> &str {
         "Get recent transaction history for a Solana wallet address. \
          Returns the latest transactions with signatures, timestamps, and explorer links."
@@ -206,21 +243,31 @@ impl Tool for SolanaTransactionsTool {
             "properties": {
                 "address": {
                     "type": "string",
-                    "description": "Solana wallet address (base58 public key)"
+                    "description": "Solana wallet address (base58 public key); defaults to the configured wallet"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of a configured wallet to use instead of an address (see list_wallets)"
                 },
                 "limit": {
                     "type": "number",
                     "description": "Number of transactions to return (default: 10, max: 20)"
                 }
             },
-            "required": ["address"]
+            "required": []
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
-        let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
-            return "Error: 'address' parameter is required".into();
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let address = match target_address(&args, &self.wallets) {
+            Ok(a) => a,
+            Err(e) => return e,
         };
+        let address = address.as_str();
 
         if let Err(e) = SolanaRpc::validate_address(address) {
             return format!("❌ {}", e);
@@ -300,12 +347,14 @@ impl Tool for SolanaTransactionsTool {
 
 pub struct SolanaTokenBalancesTool {
     rpc: SolanaRpc,
+    wallets: Arc<WalletBook>,
 }
 
 impl SolanaTokenBalancesTool {
-    pub fn new(client: Client, rpc_url: &str) -> Self {
+    pub fn new(client: Client, rpc_url: &str, wallets: Arc<WalletBook>) -> Self {
         Self {
             rpc: SolanaRpc::new(client, rpc_url),
+            wallets,
         }
     }
 }
@@ -327,17 +376,27 @@ impl Tool for SolanaTokenBalancesTool {
             "properties": {
                 "address": {
                     "type": "string",
-                    "description": "Solana wallet address (base58 public key)"
+                    "description": "Solana wallet address (base58 public key); defaults to the configured wallet"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of a configured wallet to use instead of an address (see list_wallets)"
                 }
             },
-            "required": ["address"]
+            "required": []
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
-        let Some(address) = args.get("address").and_then(|v| v.as_str()) else {
-            return "Error: 'address' parameter is required".into();
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let address = match target_address(&args, &self.wallets) {
+            Ok(a) => a,
+            Err(e) => return e,
         };
+        let address = address.as_str();
 
         if let Err(e) = SolanaRpc::validate_address(address) {
             return format!("❌ {}", e);
@@ -409,38 +468,38 @@ impl Tool for SolanaTokenBalancesTool {
 
 // ── Well-known token registry ──────────────────────────────────────
 
+/// Well-known Solana token mints and their human-readable labels.
+pub(super) const WELL_KNOWN_TOKENS: &[(&str, &str)] = &[
+    // Stablecoins
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC"),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "USDT"),
+    // SOL variants
+    ("So11111111111111111111111111111111111111112", "Wrapped SOL"),
+    ("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", "mSOL"),
+    ("7dHbWXmci3dT8UFYWYZweBLXgycu7Y3iL6trKn1Y7ARj", "stSOL"),
+    ("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn", "jitoSOL"),
+    ("bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1", "bSOL"),
+    // DeFi & Ecosystem
+    ("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", "JUP"),
+    ("7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs", "RAY"),
+    ("orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE", "ORCA"),
+    ("jtojtomepa8beP8AuQc6eXt5FriJwfFMwQx2v2f9mCL", "JTO"),
+    ("85VBFQZC9TZkfaptBWjvUw7YbZjy52A6mjtPGjstQAmQ", "W"),
+    // Memecoins
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "BONK"),
+    ("EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm", "WIF"),
+    // Infrastructure
+    ("HZ1JovNiVvGrGNiiYvEozEVgZ58xaU3RKwX8eACQBCt3", "PYTH"),
+    ("hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux", "HNT"),
+    ("rndrizKT3MK1iimdxRdWabcF7Zg7AR5T4nud4EkHBof", "RNDR"),
+];
+
 /// Map well-known Solana token mint addresses to human-readable labels.
 fn well_known_token(mint: &str) -> &str {
-    match mint {
-        // Stablecoins
-        "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v" => "USDC",
-        "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB" => "USDT",
-
-        // SOL variants
-        "So11111111111111111111111111111111111111112" => "Wrapped SOL",
-        "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So" => "mSOL",
-        "7dHbWXmci3dT8UFYWYZweBLXgycu7Y3iL6trKn1Y7ARj" => "stSOL",
-        "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn" => "jitoSOL",
-        "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1" => "bSOL",
-
-        // DeFi & Ecosystem
-        "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN" => "JUP",
-        "7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs" => "RAY",
-        "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE" => "ORCA",
-        "jtojtomepa8beP8AuQc6eXt5FriJwfFMwQx2v2f9mCL" => "JTO",
-        "85VBFQZC9TZkfaptBWjvUw7YbZjy52A6mjtPGjstQAmQ" => "W",
-
-        // Memecoins
-        "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263" => "BONK",
-        "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm" => "WIF",
-
-        // Infrastructure
-        "HZ1JovNiVvGrGNiiYvEozEVgZ58xaU3RKwX8eACQBCt3" => "PYTH",
-        "hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux" => "HNT",
-        "rndrizKT3MK1iimdxRdWabcF7Zg7AR5T4nud4EkHBof" => "RNDR",
-
-        _ => "Unknown Token",
-    }
+    WELL_KNOWN_TOKENS
+        .iter()
+        .find(|(m, _)| *m == mint)
+        .map_or("Unknown Token", |(_, label)| label)
 }
 
 #[cfg(test)]
@@ -0,0 +1,168 @@
+//! `solana_stream`: live account and log updates over the RPC websocket.
+//!
+//! Like `polymarket_stream`, the tool subscribes, collects a bounded number
+//! of events within a timeout and returns them as text. All calls share one
+//! [`SolanaWs`] connection, opened on first use.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::wallets::WalletBook;
+use super::{CostHint, CostTier, Tool, ToolContext};
+use crate::config::WalletChain;
+use crate::service::solana_ws::{SolanaWs, Subscription, Update};
+
+/// How long to wait for events before returning what arrived.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_EVENTS: u64 = 5;
+
+pub struct SolanaStreamTool {
+    rpc_url: String,
+    wallets: Arc<WalletBook>,
+    ws: OnceCell<SolanaWs>,
+}
+
+impl SolanaStreamTool {
+    pub fn new(rpc_url: &str, wallets: Arc<WalletBook>) -> Self {
+        Self {
+            rpc_url: rpc_url.to_string(),
+            wallets,
+            ws: OnceCell::new(),
+        }
+    }
+}
+
+fn format_update(kind: &str, update: &Update) -> String {
+    let value = &update.value;
+    if kind == "logs" {
+        let signature = value["signature"].as_str().unwrap_or("?");
+        let status = if value["err"].is_null() { "✅" } else { "❌" };
+        let logs: Vec<&str> = value["logs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l.as_str())
+            .take(3)
+            .collect();
+        format!(
+            "slot {} {} [{}](https://solscan.io/tx/{})\n  {}",
+            update.slot,
+            status,
+            &signature[..signature.len().min(16)],
+            signature,
+            logs.join("\n  ")
+        )
+    } else {
+        let lamports = value["lamports"].as_u64().unwrap_or_default();
+        let owner = value["owner"].as_str().unwrap_or("?");
+        format!(
+            "slot {}: {:.9} SOL (owner {})",
+            update.slot,
+            lamports as f64 / 1_000_000_000.0,
+            owner
+        )
+    }
+}
+
+#[async_trait]
+impl Tool for SolanaStreamTool {
+    fn name(&self) -> &str {
+        "solana_stream"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 30)
+    }
+
+    fn description(&self) -> &str {
+        "Watch a Solana account live over the RPC websocket. `account` streams balance/data \
+         changes of the address; `logs` streams transactions that mention it. Collects up to \
+         `max_events` updates within 30 seconds and returns them."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "string",
+                    "description": "Solana address to watch; defaults to the configured wallet"
+                },
+                "wallet": {
+                    "type": "string",
+                    "description": "Name of a configured wallet to watch instead of an address"
+                },
+                "kind": {
+                    "type": "string",
+                    "enum": ["account", "logs"],
+                    "description": "account: balance/data changes (default); logs: transactions mentioning the address"
+                },
+                "max_events": {
+                    "type": "integer",
+                    "description": "Updates to collect before returning (default: 5, max: 20)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let address = match args.get("address").and_then(|v| v.as_str()).filter(|a| !a.is_empty()) {
+            Some(a) => a.to_string(),
+            None => match self
+                .wallets
+                .resolve(args.get("wallet").and_then(|v| v.as_str()), WalletChain::Solana)
+                .and_then(|w| w.require_address().map(String::from))
+            {
+                Ok(a) => a,
+                Err(e) => return e,
+            },
+        };
+        let kind = args.get("kind").and_then(|v| v.as_str()).unwrap_or("account");
+        let subscription = match kind {
+            "account" => Subscription::Account(address.clone()),
+            "logs" => Subscription::Logs(address.clone()),
+            other => return format!("Error: unknown kind '{}'", other),
+        };
+        let max_events = args
+            .get("max_events")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_MAX_EVENTS)
+            .clamp(1, 20) as usize;
+
+        let ws = self.ws.get_or_init(|| async { SolanaWs::connect(&self.rpc_url) }).await;
+        let mut updates = ws.subscribe(subscription);
+        let mut events = Vec::new();
+        let deadline = tokio::time::Instant::now() + STREAM_TIMEOUT;
+        while events.len() < max_events {
+            match tokio::time::timeout_at(deadline, updates.recv()).await {
+                Ok(Some(update)) => events.push(format_update(kind, &update)),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if events.is_empty() {
+            return format!(
+                "No {} updates for `{}` in {}s.",
+                kind,
+                address,
+                STREAM_TIMEOUT.as_secs()
+            );
+        }
+        format!(
+            "📡 *{} {} update(s) for* `{}`\n\n{}",
+            events.len(),
+            kind,
+            address,
+            events.join("\n")
+        )
+    }
+}
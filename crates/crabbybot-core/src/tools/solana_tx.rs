@@ -0,0 +1,653 @@
+//! Transaction previews: decode and simulate before anything is signed.
+//!
+//! Blind signing is the riskiest thing the agent can do, so every tool that
+//! signs or submits a Solana transaction must first build a
+//! [`TransactionPreview`], refuse to continue when
+//! [`TransactionPreview::safe_to_sign`] is false, and put
+//! [`TransactionPreview::render`] into the approval request it sends the
+//! user. The preview has two parts:
+//!
+//! - the instructions decoded into plain language (program, accounts,
+//!   amounts) for the programs we know, with warnings for risky ones such as
+//!   token approvals and authority changes;
+//! - a `simulateTransaction` run against the configured RPC, reporting
+//!   errors, compute units, and the SOL and token balance changes of every
+//!   writable account.
+//!
+//! `solana_simulate_transaction` exposes the same preview to the agent for
+//! unsigned transactions returned by swap and launchpad APIs.
+
+use async_trait::async_trait;
+use base64::Engine;
+use reqwest::Client;
+use serde_json::{json, Value};
+use solana_message::VersionedMessage;
+use solana_transaction::versioned::VersionedTransaction;
+use std::collections::HashMap;
+
+use super::solana::SolanaRpc;
+use super::{Tool, ToolContext};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Writable accounts whose balances are compared; the RPC caps this too.
+const MAX_WATCHED_ACCOUNTS: usize = 20;
+
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Display names for well-known programs.
+fn program_name(id: &str) -> Option<&'static str> {
+    Some(match id {
+        SYSTEM_PROGRAM => "System",
+        TOKEN_PROGRAM => "SPL Token",
+        TOKEN_2022_PROGRAM => "Token-2022",
+        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" => "Associated Token Account",
+        "ComputeBudget111111111111111111111111111111" => "Compute Budget",
+        "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr" => "Memo",
+        "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo" => "Memo (v1)",
+        "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4" => "Jupiter Aggregator v6",
+        "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P" => "Pump.fun",
+        "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA" => "PumpSwap AMM",
+        "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8" => "Raydium AMM v4",
+        "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK" => "Raydium CLMM",
+        "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc" => "Orca Whirlpools",
+        "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s" => "Metaplex Token Metadata",
+        _ => return None,
+    })
+}
+
+/// `Abcd…wxyz` form of an address.
+fn short(address: &str) -> String {
+    if address.len() <= 10 {
+        address.to_string()
+    } else {
+        format!("{}…{}", &address[..4], &address[address.len() - 4..])
+    }
+}
+
+fn sol(lamports: f64) -> String {
+    format!("{} SOL", trim_float(lamports / LAMPORTS_PER_SOL, 9))
+}
+
+fn trim_float(v: f64, decimals: usize) -> String {
+    let s = format!("{:.*}", decimals, v);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+fn read_u64(data: &[u8], at: usize) -> Option<u64> {
+    data.get(at..at + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default()))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap_or_default()))
+}
+
+// ── Decoding ────────────────────────────────────────────────────────
+
+/// Parse a serialized transaction given as base64 or base58.
+pub fn decode_transaction(encoded: &str) -> Result<VersionedTransaction, String> {
+    let encoded = encoded.trim();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .or_else(|_| bs58::decode(encoded).into_vec())
+        .map_err(|_| "transaction is neither valid base64 nor base58".to_string())?;
+    bincode::deserialize::<VersionedTransaction>(&bytes)
+        .map_err(|e| format!("not a serialized Solana transaction: {}", e))
+}
+
+/// One instruction in plain language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub program: String,
+    pub summary: String,
+    /// Set for instructions that hand over control of funds.
+    pub warning: Option<String>,
+}
+
+/// Decode every instruction of `message`. Accounts loaded from address
+/// lookup tables can't be resolved offline and are shown by index.
+pub fn decode_instructions(message: &VersionedMessage) -> Vec<DecodedInstruction> {
+    let keys: Vec<String> = message.static_account_keys().iter().map(|k| k.to_string()).collect();
+    let key = |i: u8| {
+        keys.get(usize::from(i))
+            .map(|k| short(k))
+            .unwrap_or_else(|| format!("lookup-table account #{}", i))
+    };
+
+    message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            let program_id = keys
+                .get(usize::from(ix.program_id_index))
+                .cloned()
+                .unwrap_or_else(|| format!("lookup-table program #{}", ix.program_id_index));
+            let program = program_name(&program_id)
+                .map(String::from)
+                .unwrap_or_else(|| format!("Unknown program {}", short(&program_id)));
+            let acct = |n: usize| ix.accounts.get(n).map(|i| key(*i)).unwrap_or_else(|| "?".into());
+            let data = ix.data.as_slice();
+
+            let (summary, warning) = match program_id.as_str() {
+                SYSTEM_PROGRAM => match read_u32(data, 0) {
+                    Some(0) => (
+                        format!(
+                            "create account {} funded with {} by {}",
+                            acct(1),
+                            sol(read_u64(data, 4).unwrap_or_default() as f64),
+                            acct(0)
+                        ),
+                        None,
+                    ),
+                    Some(2) => (
+                        format!(
+                            "transfer {} from {} to {}",
+                            sol(read_u64(data, 4).unwrap_or_default() as f64),
+                            acct(0),
+                            acct(1)
+                        ),
+                        None,
+                    ),
+                    Some(1) => (
+                        format!("assign {} to a new owner program", acct(0)),
+                        Some("changes which program owns your account".into()),
+                    ),
+                    Some(n) => (format!("instruction {}", n), None),
+                    None => ("malformed instruction".into(), None),
+                },
+                TOKEN_PROGRAM | TOKEN_2022_PROGRAM => match data.first() {
+                    Some(3) => (
+                        format!(
+                            "transfer {} raw units from {} to {} (authority {})",
+                            read_u64(data, 1).unwrap_or_default(),
+                            acct(0),
+                            acct(1),
+                            acct(2)
+                        ),
+                        None,
+                    ),
+                    Some(12) => {
+                        let decimals = data.get(9).copied().unwrap_or_default();
+                        let amount = read_u64(data, 1).unwrap_or_default() as f64 / 10f64.powi(decimals.into());
+                        (
+                            format!(
+                                "transfer {} of mint {} from {} to {} (authority {})",
+                                trim_float(amount, decimals.into()),
+                                acct(1),
+                                acct(0),
+                                acct(2),
+                                acct(3)
+                            ),
+                            None,
+                        )
+                    }
+                    Some(4) | Some(13) => (
+                        format!("approve {} as delegate of {}", acct(if data[0] == 4 { 1 } else { 2 }), acct(0)),
+                        Some("lets another account spend these tokens later".into()),
+                    ),
+                    Some(6) => (
+                        format!("change an authority of {}", acct(0)),
+                        Some("transfers control of a token account or mint".into()),
+                    ),
+                    Some(9) => (format!("close token account {}, rent to {}", acct(0), acct(1)), None),
+                    Some(7) | Some(14) => (format!("mint tokens of {} to {}", acct(0), acct(1)), None),
+                    Some(8) | Some(15) => (format!("burn tokens from {}", acct(0)), None),
+                    Some(1) | Some(16) | Some(18) => (format!("initialize token account {}", acct(0)), None),
+                    Some(17) => (format!("sync native SOL balance of {}", acct(0)), None),
+                    Some(n) => (format!("instruction {}", n), None),
+                    None => ("malformed instruction".into(), None),
+                },
+                "ComputeBudget111111111111111111111111111111" => match data.first() {
+                    Some(2) => (
+                        format!("set compute unit limit to {}", read_u32(data, 1).unwrap_or_default()),
+                        None,
+                    ),
+                    Some(3) => (
+                        format!(
+                            "set priority fee to {} micro-lamports per compute unit",
+                            read_u64(data, 1).unwrap_or_default()
+                        ),
+                        None,
+                    ),
+                    _ => ("compute budget setting".into(), None),
+                },
+                "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" => (
+                    format!("create token account {} for owner {}", acct(1), acct(2)),
+                    None,
+                ),
+                "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr" | "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo" => {
+                    (format!("memo \"{}\"", String::from_utf8_lossy(data)), None)
+                }
+                _ if program_name(&program_id).is_some() => (
+                    format!("{} accounts, {} bytes of data", ix.accounts.len(), data.len()),
+                    None,
+                ),
+                _ => (
+                    format!("{} accounts, {} bytes of data", ix.accounts.len(), data.len()),
+                    Some("unknown program; its effects can only be judged from the simulation".into()),
+                ),
+            };
+            DecodedInstruction {
+                program,
+                summary,
+                warning,
+            }
+        })
+        .collect()
+}
+
+// ── Simulation ──────────────────────────────────────────────────────
+
+/// Net change of one watched account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceChange {
+    pub account: String,
+    /// Change in lamports.
+    pub lamports: i128,
+    /// Token balance change for SPL token accounts: (mint, owner, amount).
+    pub token: Option<(String, String, f64)>,
+}
+
+/// Outcome of `simulateTransaction`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    /// Program error, if the transaction would fail.
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub changes: Vec<BalanceChange>,
+    /// Last program log lines, shown when the simulation fails.
+    pub logs: Vec<String>,
+}
+
+/// SPL token account fields: (mint, owner, raw amount).
+type TokenState = (String, String, u64);
+
+/// Lamports, and for token accounts their [`TokenState`], from an RPC
+/// account object with base64 data.
+fn account_state(account: &Value) -> Option<(u64, Option<TokenState>)> {
+    if account.is_null() {
+        return None;
+    }
+    let lamports = account["lamports"].as_u64().unwrap_or_default();
+    let owner = account["owner"].as_str().unwrap_or_default();
+    let token = if owner == TOKEN_PROGRAM || owner == TOKEN_2022_PROGRAM {
+        account["data"][0]
+            .as_str()
+            .and_then(|d| base64::engine::general_purpose::STANDARD.decode(d).ok())
+            .filter(|d| d.len() >= 72)
+            .map(|d| {
+                (
+                    bs58::encode(&d[0..32]).into_string(),
+                    bs58::encode(&d[32..64]).into_string(),
+                    read_u64(&d, 64).unwrap_or_default(),
+                )
+            })
+    } else {
+        None
+    };
+    Some((lamports, token))
+}
+
+async fn simulate(rpc: &SolanaRpc, tx: &VersionedTransaction, encoded: &str) -> Result<Simulation, String> {
+    let message = &tx.message;
+    let watched: Vec<String> = message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_maybe_writable(*i, None))
+        .map(|(_, k)| k.to_string())
+        .take(MAX_WATCHED_ACCOUNTS)
+        .collect();
+
+    let before = rpc
+        .call("getMultipleAccounts", json!([watched, {"encoding": "base64"}]))
+        .await?;
+    let sim = rpc
+        .call(
+            "simulateTransaction",
+            json!([encoded, {
+                "encoding": "base64",
+                "sigVerify": false,
+                "replaceRecentBlockhash": true,
+                "commitment": "confirmed",
+                "accounts": {"encoding": "base64", "addresses": watched}
+            }]),
+        )
+        .await?;
+    let value = &sim["result"]["value"];
+    let pre = before["result"]["value"].as_array().cloned().unwrap_or_default();
+    let post = value["accounts"].as_array().cloned().unwrap_or_default();
+
+    // Decimals of every mint involved, for readable token amounts.
+    let mut mints: Vec<String> = pre
+        .iter()
+        .chain(&post)
+        .filter_map(|a| account_state(a).and_then(|(_, t)| t).map(|(mint, _, _)| mint))
+        .collect();
+    mints.sort();
+    mints.dedup();
+    let mut decimals = HashMap::new();
+    if !mints.is_empty() {
+        let infos = rpc
+            .call("getMultipleAccounts", json!([mints, {"encoding": "base64"}]))
+            .await?;
+        for (mint, info) in mints.iter().zip(infos["result"]["value"].as_array().into_iter().flatten()) {
+            let d = info["data"][0]
+                .as_str()
+                .and_then(|d| base64::engine::general_purpose::STANDARD.decode(d).ok())
+                .and_then(|d| d.get(44).copied())
+                .unwrap_or_default();
+            decimals.insert(mint.clone(), d);
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (i, account) in watched.iter().enumerate() {
+        let before = pre.get(i).and_then(account_state);
+        let after = post.get(i).and_then(account_state);
+        let lamports = i128::from(after.as_ref().map_or(0, |a| a.0)) - i128::from(before.as_ref().map_or(0, |b| b.0));
+        let token_of = |s: &Option<(u64, Option<TokenState>)>| s.as_ref().and_then(|(_, t)| t.clone());
+        let (token_before, token_after) = (token_of(&before), token_of(&after));
+        // A closed token account has no post state; keep its mint and owner.
+        let token = token_after.clone().or(token_before.clone()).and_then(|(mint, owner, _)| {
+            let raw = |t: &Option<TokenState>| t.as_ref().map_or(0, |(_, _, v)| *v) as f64;
+            let scale = 10f64.powi(decimals.get(&mint).copied().unwrap_or_default().into());
+            let diff = (raw(&token_after) - raw(&token_before)) / scale;
+            (diff != 0.0).then_some((mint, owner, diff))
+        });
+        if lamports != 0 || token.is_some() {
+            changes.push(BalanceChange {
+                account: account.clone(),
+                lamports,
+                token,
+            });
+        }
+    }
+
+    let logs: Vec<String> = value["logs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|l| l.as_str().map(String::from))
+        .collect();
+    Ok(Simulation {
+        error: (!value["err"].is_null()).then(|| value["err"].to_string()),
+        units_consumed: value["unitsConsumed"].as_u64(),
+        changes,
+        logs: logs.iter().rev().take(5).rev().cloned().collect(),
+    })
+}
+
+// ── Preview ─────────────────────────────────────────────────────────
+
+/// Everything the user needs to see before approving a signature.
+#[derive(Debug, Clone)]
+pub struct TransactionPreview {
+    pub fee_payer: String,
+    pub signers: Vec<String>,
+    pub instructions: Vec<DecodedInstruction>,
+    /// Whether accounts come from address lookup tables (v0 transactions).
+    pub uses_lookup_tables: bool,
+    /// The simulation, or why it couldn't be run.
+    pub simulation: Result<Simulation, String>,
+}
+
+impl TransactionPreview {
+    /// Decode `encoded` (base64 or base58) and simulate it.
+    pub async fn build(client: Client, rpc_url: &str, encoded: &str) -> Result<Self, String> {
+        let tx = decode_transaction(encoded)?;
+        let base64_tx = base64::engine::general_purpose::STANDARD
+            .encode(bincode::serialize(&tx).map_err(|e| e.to_string())?);
+        let rpc = SolanaRpc::new(client, rpc_url);
+        let simulation = simulate(&rpc, &tx, &base64_tx).await;
+        Ok(Self::from_parts(&tx, simulation))
+    }
+
+    fn from_parts(tx: &VersionedTransaction, simulation: Result<Simulation, String>) -> Self {
+        let message = &tx.message;
+        let keys: Vec<String> = message.static_account_keys().iter().map(|k| k.to_string()).collect();
+        let signers = keys
+            .iter()
+            .take(usize::from(message.header().num_required_signatures))
+            .cloned()
+            .collect();
+        Self {
+            fee_payer: keys.first().cloned().unwrap_or_default(),
+            signers,
+            instructions: decode_instructions(message),
+            uses_lookup_tables: message.address_table_lookups().is_some_and(|l| !l.is_empty()),
+            simulation,
+        }
+    }
+
+    /// Whether signing may go ahead: the simulation ran and succeeded.
+    pub fn safe_to_sign(&self) -> bool {
+        matches!(self.simulation, Ok(Simulation { error: None, .. }))
+    }
+
+    /// Human-readable preview for the approval request.
+    pub fn render(&self) -> String {
+        let mut out = vec!["🔍 *Transaction preview*".to_string()];
+        out.push(format!("Fee payer: `{}`", self.fee_payer));
+        if self.signers.len() > 1 {
+            let others: Vec<String> = self.signers[1..].iter().map(|s| short(s)).collect();
+            out.push(format!("Other signers: {}", others.join(", ")));
+        }
+
+        out.push(String::new());
+        out.push("*Instructions*".into());
+        let mut warnings = Vec::new();
+        for (i, ix) in self.instructions.iter().enumerate() {
+            out.push(format!("{}. {}: {}", i + 1, ix.program, ix.summary));
+            if let Some(ref w) = ix.warning {
+                warnings.push(format!("Instruction {} ({}): {}", i + 1, ix.program, w));
+            }
+        }
+        if self.uses_lookup_tables {
+            warnings.push("some accounts come from address lookup tables and are shown by index".into());
+        }
+
+        out.push(String::new());
+        match &self.simulation {
+            Ok(sim) => {
+                let units = sim
+                    .units_consumed
+                    .map(|u| format!(" ({} compute units)", u))
+                    .unwrap_or_default();
+                match &sim.error {
+                    None => out.push(format!("*Simulation:* ✅ succeeded{}", units)),
+                    Some(e) => {
+                        out.push(format!("*Simulation:* ❌ would fail: {}{}", e, units));
+                        out.extend(sim.logs.iter().map(|l| format!("  {}", l)));
+                        warnings.push("the transaction fails in simulation; do not sign it".into());
+                    }
+                }
+                if !sim.changes.is_empty() {
+                    out.push("*Balance changes*".into());
+                    for c in &sim.changes {
+                        let who = if c.account == self.fee_payer {
+                            format!("{} (you)", short(&c.account))
+                        } else {
+                            short(&c.account)
+                        };
+                        if let Some((mint, owner, amount)) = &c.token {
+                            out.push(format!(
+                                "- {} token {}: {}{} (owner {})",
+                                who,
+                                short(mint),
+                                if *amount > 0.0 { "+" } else { "" },
+                                trim_float(*amount, 9),
+                                short(owner)
+                            ));
+                        }
+                        if c.lamports != 0 {
+                            out.push(format!(
+                                "- {}: {}{}",
+                                who,
+                                if c.lamports > 0 { "+" } else { "" },
+                                sol(c.lamports as f64)
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                out.push(format!("*Simulation:* ⚠️ could not run: {}", e));
+                warnings.push("effects are unverified because simulation failed".into());
+            }
+        }
+
+        if !warnings.is_empty() {
+            out.push(String::new());
+            out.push("⚠️ *Warnings*".into());
+            out.extend(warnings.into_iter().map(|w| format!("- {}", w)));
+        }
+        out.join("\n")
+    }
+}
+
+// ── SolanaSimulateTool ──────────────────────────────────────────────
+
+pub struct SolanaSimulateTool {
+    client: Client,
+    rpc_url: String,
+}
+
+impl SolanaSimulateTool {
+    pub fn new(client: Client, rpc_url: &str) -> Self {
+        Self {
+            client,
+            rpc_url: rpc_url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SolanaSimulateTool {
+    fn name(&self) -> &str {
+        "solana_simulate_transaction"
+    }
+
+    fn description(&self) -> &str {
+        "Decode a serialized Solana transaction into plain language and simulate it against \
+         the RPC without signing, showing errors and the SOL/token balance changes it would \
+         cause. Use it on any unsigned transaction (e.g. from a swap or launchpad API) before \
+         the user signs it."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "transaction": {
+                    "type": "string",
+                    "description": "Serialized transaction, base64 or base58 encoded"
+                }
+            },
+            "required": ["transaction"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(encoded) = args.get("transaction").and_then(|v| v.as_str()) else {
+            return "Error: 'transaction' parameter is required".into();
+        };
+        match TransactionPreview::build(self.client.clone(), &self.rpc_url, encoded).await {
+            Ok(preview) => preview.render(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_message::compiled_instruction::CompiledInstruction;
+    use solana_message::{legacy, MessageHeader};
+    use solana_pubkey::Pubkey;
+    use solana_signature::Signature;
+
+    fn transfer_tx() -> VersionedTransaction {
+        let payer = Pubkey::new_from_array([1; 32]);
+        let to = Pubkey::new_from_array([2; 32]);
+        let system: Pubkey = SYSTEM_PROGRAM.parse().unwrap();
+        let mut transfer = vec![2, 0, 0, 0];
+        transfer.extend(500_000_000u64.to_le_bytes());
+        let mut approve = vec![4];
+        approve.extend(1_000u64.to_le_bytes());
+        let token: Pubkey = TOKEN_PROGRAM.parse().unwrap();
+
+        let message = legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 2,
+            },
+            account_keys: vec![payer, to, system, token],
+            instructions: vec![
+                CompiledInstruction {
+                    program_id_index: 2,
+                    accounts: vec![0, 1],
+                    data: transfer,
+                },
+                CompiledInstruction {
+                    program_id_index: 3,
+                    accounts: vec![1, 1, 0],
+                    data: approve,
+                },
+            ],
+            ..Default::default()
+        };
+        VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        }
+    }
+
+    #[test]
+    fn test_decode_and_render_preview() {
+        let tx = transfer_tx();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+        let decoded = decode_transaction(&encoded).unwrap();
+        assert_eq!(decoded, tx);
+        let base58 = bs58::encode(bincode::serialize(&tx).unwrap()).into_string();
+        assert_eq!(decode_transaction(&base58).unwrap(), tx);
+        assert!(decode_transaction("not a tx").is_err());
+
+        let ixs = decode_instructions(&decoded.message);
+        assert_eq!(ixs[0].program, "System");
+        assert_eq!(ixs[0].summary, "transfer 0.5 SOL from 4vJ9…kLKi to 8qbH…VfeR");
+        assert!(ixs[1].warning.as_deref().unwrap().contains("spend these tokens"));
+
+        let payer = decoded.message.static_account_keys()[0].to_string();
+        let simulation = Ok(Simulation {
+            error: None,
+            units_consumed: Some(450),
+            changes: vec![BalanceChange {
+                account: payer.clone(),
+                lamports: -500_005_000,
+                token: None,
+            }],
+            logs: Vec::new(),
+        });
+        let preview = TransactionPreview::from_parts(&decoded, simulation);
+        assert!(preview.safe_to_sign());
+        let text = preview.render();
+        assert!(text.contains("*Simulation:* ✅ succeeded (450 compute units)"), "{text}");
+        assert!(text.contains("- 4vJ9…kLKi (you): -0.500005 SOL"), "{text}");
+        assert!(text.contains("Instruction 2 (SPL Token): lets another account spend"), "{text}");
+
+        let failed = TransactionPreview::from_parts(&decoded, Err("RPC unreachable".into()));
+        assert!(!failed.safe_to_sign());
+        assert!(failed.render().contains("could not run: RPC unreachable"));
+    }
+}
@@ -0,0 +1,415 @@
+//! `sports_scores`: live scores and schedules for the leagues Polymarket
+//! lists sports markets on.
+//!
+//! Scores come from ESPN's public site API, which needs no key. When
+//! `tools.sports.apiFootballKey` is set, soccer is served from API-Football
+//! instead, which covers far more competitions.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::debug;
+
+use super::{Tool, ToolContext};
+use crate::config::SportsConfig;
+
+/// League shorthand → (ESPN `sport/league` path, API-Football league id).
+const LEAGUES: &[(&str, &str, Option<u64>)] = &[
+    ("nfl", "football/nfl", None),
+    ("ncaaf", "football/college-football", None),
+    ("nba", "basketball/nba", None),
+    ("wnba", "basketball/wnba", None),
+    ("ncaab", "basketball/mens-college-basketball", None),
+    ("mlb", "baseball/mlb", None),
+    ("nhl", "hockey/nhl", None),
+    ("ufc", "mma/ufc", None),
+    ("epl", "soccer/eng.1", Some(39)),
+    ("laliga", "soccer/esp.1", Some(140)),
+    ("bundesliga", "soccer/ger.1", Some(78)),
+    ("seriea", "soccer/ita.1", Some(135)),
+    ("ligue1", "soccer/fra.1", Some(61)),
+    ("mls", "soccer/usa.1", Some(253)),
+    ("ucl", "soccer/uefa.champions", Some(2)),
+];
+
+/// Where to fetch a league from.
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    /// ESPN `sport/league` path.
+    Espn(String),
+    /// API-Football: a league id, or a name to match when the league has
+    /// no shorthand.
+    ApiFootball { id: Option<u64>, name: String },
+}
+
+/// Resolve a `league` argument. Shorthands from [`LEAGUES`] and raw ESPN
+/// paths like `soccer/ned.1` work without a key; soccer shorthands and
+/// free-form league names go to API-Football when a key is configured.
+fn resolve_league(league: &str, has_football_key: bool) -> Option<Source> {
+    let key = league.trim().to_lowercase();
+    if let Some((_, path, football_id)) = LEAGUES.iter().find(|(k, _, _)| *k == key) {
+        return Some(match football_id {
+            Some(id) if has_football_key => Source::ApiFootball { id: Some(*id), name: key },
+            _ => Source::Espn(path.to_string()),
+        });
+    }
+    if key.contains('/') {
+        return Some(Source::Espn(key));
+    }
+    has_football_key.then_some(Source::ApiFootball { id: None, name: key })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GameState {
+    Scheduled,
+    Live,
+    Final,
+}
+
+/// One game, normalised across providers.
+#[derive(Debug, Clone, PartialEq)]
+struct Game {
+    league: String,
+    home: String,
+    away: String,
+    home_score: Option<String>,
+    away_score: Option<String>,
+    state: GameState,
+    /// Clock or status text, e.g. "Q4 2:31" or "67'".
+    detail: String,
+    start: Option<DateTime<Utc>>,
+}
+
+impl Game {
+    fn render(&self) -> String {
+        let score = match (&self.home_score, &self.away_score) {
+            (Some(h), Some(a)) if self.state != GameState::Scheduled => {
+                format!("{} {} – {} {}", self.away, a, h, self.home)
+            }
+            _ => format!("{} @ {}", self.away, self.home),
+        };
+        let status = match self.state {
+            GameState::Live => format!("🔴 {}", self.detail),
+            GameState::Final => "✅ Final".to_string(),
+            GameState::Scheduled => self
+                .start
+                .map(|t| t.format("%a %H:%M UTC").to_string())
+                .unwrap_or_else(|| self.detail.clone()),
+        };
+        format!("- {} · {}", score, status)
+    }
+}
+
+fn parse_time(raw: Option<&str>) -> Option<DateTime<Utc>> {
+    let raw = raw?;
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .or_else(|| DateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M%#z").ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Games from an ESPN `scoreboard` response.
+fn parse_espn(data: &Value) -> Vec<Game> {
+    let league = data["leagues"][0]["abbreviation"].as_str().unwrap_or_default().to_string();
+    data["events"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|event| {
+            let competitors = event["competitions"][0]["competitors"].as_array()?;
+            let side = |home_away: &str| {
+                competitors.iter().find(|c| c["homeAway"] == home_away)
+            };
+            let (home, away) = (side("home")?, side("away")?);
+            let name = |c: &Value| {
+                c["team"]["displayName"]
+                    .as_str()
+                    .or(c["athlete"]["displayName"].as_str())
+                    .unwrap_or("?")
+                    .to_string()
+            };
+            let status = &event["status"]["type"];
+            let state = match status["state"].as_str() {
+                Some("in") => GameState::Live,
+                Some("post") => GameState::Final,
+                _ => GameState::Scheduled,
+            };
+            Some(Game {
+                league: league.clone(),
+                home: name(home),
+                away: name(away),
+                home_score: home["score"].as_str().map(String::from),
+                away_score: away["score"].as_str().map(String::from),
+                state,
+                detail: status["shortDetail"].as_str().unwrap_or_default().to_string(),
+                start: parse_time(event["date"].as_str()),
+            })
+        })
+        .collect()
+}
+
+/// Games from an API-Football `fixtures` response, keeping those of
+/// league `id`, or whose league name contains `name` when there is no id.
+fn parse_api_football(data: &Value, id: Option<u64>, name: &str) -> Vec<Game> {
+    data["response"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|f| match id {
+            Some(id) => f["league"]["id"].as_u64() == Some(id),
+            None => f["league"]["name"]
+                .as_str()
+                .is_some_and(|n| n.to_lowercase().contains(name)),
+        })
+        .map(|f| {
+            let status = f["fixture"]["status"]["short"].as_str().unwrap_or_default();
+            let state = match status {
+                "FT" | "AET" | "PEN" => GameState::Final,
+                "1H" | "HT" | "2H" | "ET" | "BT" | "P" | "LIVE" | "INT" => GameState::Live,
+                _ => GameState::Scheduled,
+            };
+            let detail = match f["fixture"]["status"]["elapsed"].as_u64() {
+                Some(min) if state == GameState::Live && status != "HT" => format!("{}'", min),
+                _ => status.to_string(),
+            };
+            let goals = |side: &str| f["goals"][side].as_u64().map(|g| g.to_string());
+            Game {
+                league: f["league"]["name"].as_str().unwrap_or_default().to_string(),
+                home: f["teams"]["home"]["name"].as_str().unwrap_or("?").to_string(),
+                away: f["teams"]["away"]["name"].as_str().unwrap_or("?").to_string(),
+                home_score: goals("home"),
+                away_score: goals("away"),
+                state,
+                detail,
+                start: parse_time(f["fixture"]["date"].as_str()),
+            }
+        })
+        .collect()
+}
+
+// ── SportsScoresTool ────────────────────────────────────────────────
+
+pub struct SportsScoresTool {
+    client: Client,
+    espn_url: String,
+    api_football_url: String,
+    api_football_key: String,
+}
+
+impl SportsScoresTool {
+    /// `api_football_key` must already be decrypted.
+    pub fn new(client: Client, config: &SportsConfig, api_football_key: &str) -> Self {
+        Self {
+            client,
+            espn_url: config.espn_url.trim_end_matches('/').to_string(),
+            api_football_url: config.api_football_url.trim_end_matches('/').to_string(),
+            api_football_key: api_football_key.to_string(),
+        }
+    }
+
+    async fn get_json(&self, req: reqwest::RequestBuilder) -> Result<Value, String> {
+        let resp = req.send().await.map_err(|e| format!("network error: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()));
+        }
+        resp.json().await.map_err(|e| format!("invalid response: {}", e))
+    }
+
+    async fn fetch(&self, source: &Source, date: NaiveDate) -> Result<Vec<Game>, String> {
+        match source {
+            Source::Espn(path) => {
+                let url = format!("{}/{}/scoreboard", self.espn_url, path);
+                let req = self
+                    .client
+                    .get(&url)
+                    .query(&[("dates", date.format("%Y%m%d").to_string())]);
+                Ok(parse_espn(&self.get_json(req).await?))
+            }
+            Source::ApiFootball { id, name } => {
+                let req = self
+                    .client
+                    .get(format!("{}/fixtures", self.api_football_url))
+                    .header("x-apisports-key", &self.api_football_key)
+                    .query(&[("date", date.format("%Y-%m-%d").to_string())]);
+                let data = self.get_json(req).await?;
+                if let Some(errors) = data["errors"].as_object().filter(|e| !e.is_empty()) {
+                    let msgs: Vec<String> = errors.values().map(|v| v.to_string()).collect();
+                    return Err(msgs.join("; "));
+                }
+                Ok(parse_api_football(&data, *id, name))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SportsScoresTool {
+    fn name(&self) -> &str {
+        "sports_scores"
+    }
+
+    fn description(&self) -> &str {
+        "Live scores and schedules for a league on a given day: nfl, ncaaf, nba, wnba, ncaab, \
+         mlb, nhl, ufc, epl, laliga, bundesliga, seriea, ligue1, mls, ucl, or an ESPN path like \
+         'soccer/ned.1'. Combine with Polymarket sports markets to compare odds with the game state."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "league": {
+                    "type": "string",
+                    "description": "League shorthand (e.g. 'nba', 'epl') or ESPN 'sport/league' path"
+                },
+                "date": {
+                    "type": "string",
+                    "description": "Day in YYYY-MM-DD, UTC (default: today)"
+                },
+                "team": {
+                    "type": "string",
+                    "description": "Only games involving a team whose name contains this (optional)"
+                },
+                "live_only": {
+                    "type": "boolean",
+                    "description": "Only games in progress (default: false)"
+                }
+            },
+            "required": ["league"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let Some(league) = args.get("league").and_then(|v| v.as_str()) else {
+            return "Error: 'league' parameter is required".into();
+        };
+        let Some(source) = resolve_league(league, !self.api_football_key.is_empty()) else {
+            return format!(
+                "Error: unknown league '{}'. Use one of: {}, or an ESPN path like 'soccer/ned.1'.",
+                league,
+                LEAGUES.iter().map(|(k, _, _)| *k).collect::<Vec<_>>().join(", ")
+            );
+        };
+        let date = match args.get("date").and_then(|v| v.as_str()) {
+            Some(d) => match NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+                Ok(d) => d,
+                Err(_) => return format!("Error: invalid date '{}', expected YYYY-MM-DD", d),
+            },
+            None => Utc::now().date_naive(),
+        };
+        let team = args
+            .get("team")
+            .and_then(|v| v.as_str())
+            .map(str::to_lowercase)
+            .filter(|t| !t.is_empty());
+        let live_only = args.get("live_only").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        debug!(league, %date, ?source, "Fetching sports scores");
+
+        let games = match self.fetch(&source, date).await {
+            Ok(games) => games,
+            Err(e) => return format!("❌ Failed to fetch {} scores: {}", league, e),
+        };
+        let games: Vec<&Game> = games
+            .iter()
+            .filter(|g| !live_only || g.state == GameState::Live)
+            .filter(|g| {
+                team.as_ref().is_none_or(|t| {
+                    g.home.to_lowercase().contains(t) || g.away.to_lowercase().contains(t)
+                })
+            })
+            .take(40)
+            .collect();
+
+        if games.is_empty() {
+            return format!("No {} games found for {}.", league, date);
+        }
+        let title = games
+            .first()
+            .map(|g| g.league.as_str())
+            .filter(|l| !l.is_empty())
+            .unwrap_or(league);
+        let lines: Vec<String> = games.iter().map(|g| g.render()).collect();
+        format!("🏟️ *{} — {}*\n\n{}", title, date, lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_league() {
+        assert_eq!(resolve_league("NBA", false), Some(Source::Espn("basketball/nba".into())));
+        assert_eq!(resolve_league("epl", false), Some(Source::Espn("soccer/eng.1".into())));
+        assert_eq!(
+            resolve_league("epl", true),
+            Some(Source::ApiFootball { id: Some(39), name: "epl".into() })
+        );
+        assert_eq!(resolve_league("soccer/ned.1", true), Some(Source::Espn("soccer/ned.1".into())));
+        assert_eq!(resolve_league("Eredivisie", false), None);
+        assert_eq!(
+            resolve_league("Eredivisie", true),
+            Some(Source::ApiFootball { id: None, name: "eredivisie".into() })
+        );
+    }
+
+    #[test]
+    fn test_parse_espn() {
+        let data = json!({
+            "leagues": [{"abbreviation": "NBA"}],
+            "events": [
+                {
+                    "date": "2026-10-17T23:30Z",
+                    "status": {"type": {"state": "in", "shortDetail": "Q4 2:31"}},
+                    "competitions": [{"competitors": [
+                        {"homeAway": "home", "score": "98", "team": {"displayName": "Boston Celtics"}},
+                        {"homeAway": "away", "score": "102", "team": {"displayName": "Los Angeles Lakers"}}
+                    ]}]
+                },
+                {
+                    "date": "2026-10-18T02:00Z",
+                    "status": {"type": {"state": "pre", "shortDetail": "10/18 - 10:00 PM EDT"}},
+                    "competitions": [{"competitors": [
+                        {"homeAway": "home", "score": "0", "team": {"displayName": "Denver Nuggets"}},
+                        {"homeAway": "away", "score": "0", "team": {"displayName": "Phoenix Suns"}}
+                    ]}]
+                }
+            ]
+        });
+        let games = parse_espn(&data);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].league, "NBA");
+        assert_eq!(games[0].render(), "- Los Angeles Lakers 102 – 98 Boston Celtics · 🔴 Q4 2:31");
+        assert_eq!(games[1].render(), "- Phoenix Suns @ Denver Nuggets · Sun 02:00 UTC");
+    }
+
+    #[test]
+    fn test_parse_api_football() {
+        let data = json!({"response": [
+            {
+                "fixture": {"date": "2026-10-17T14:00:00+00:00", "status": {"short": "2H", "elapsed": 67}},
+                "league": {"id": 39, "name": "Premier League"},
+                "teams": {"home": {"name": "Arsenal"}, "away": {"name": "Chelsea"}},
+                "goals": {"home": 2, "away": 1}
+            },
+            {
+                "fixture": {"date": "2026-10-17T12:30:00+00:00", "status": {"short": "FT", "elapsed": 90}},
+                "league": {"id": 88, "name": "Eredivisie"},
+                "teams": {"home": {"name": "Ajax"}, "away": {"name": "PSV"}},
+                "goals": {"home": 0, "away": 0}
+            }
+        ]});
+        let epl = parse_api_football(&data, Some(39), "epl");
+        assert_eq!(epl.len(), 1);
+        assert_eq!(epl[0].render(), "- Chelsea 1 – 2 Arsenal · 🔴 67'");
+        let ere = parse_api_football(&data, None, "eredivisie");
+        assert_eq!(ere[0].render(), "- PSV 0 – 0 Ajax · ✅ Final");
+    }
+}
@@ -0,0 +1,343 @@
+//! Per-tool usage statistics.
+//!
+//! The registry records every call's latency and whether it failed. Counts
+//! are kept in fixed latency buckets so aggregates from different runs can be
+//! merged, at the cost of percentiles being bucket upper bounds rather than
+//! exact values.
+//!
+//! Aggregates are persisted per UTC day to `stats/tools/YYYY-MM-DD.json` in
+//! the workspace, at most once a minute and when the stats are dropped.
+//! A warning is logged when a tool's p95 latency over the current process
+//! lifetime crosses `tools.slowToolP95Ms`.
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Upper bounds (ms) of the latency buckets. The last bucket is unbounded.
+const BUCKETS_MS: [u64; 10] = [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000];
+
+/// Calls needed before a tool's p95 is trusted for slow-tool warnings.
+const MIN_SAMPLES_FOR_WARNING: u64 = 5;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Mergeable call counts and latency histogram for one tool.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolAggregate {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+    /// Call counts per entry of the latency buckets, plus one overflow bucket.
+    pub buckets: Vec<u64>,
+}
+
+impl ToolAggregate {
+    fn record(&mut self, ms: u64, is_error: bool) {
+        self.calls += 1;
+        self.errors += u64::from(is_error);
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.buckets.resize(BUCKETS_MS.len() + 1, 0);
+        let idx = BUCKETS_MS
+            .iter()
+            .position(|&b| ms <= b)
+            .unwrap_or(BUCKETS_MS.len());
+        self.buckets[idx] += 1;
+    }
+
+    pub fn merge(&mut self, other: &ToolAggregate) {
+        self.calls += other.calls;
+        self.errors += other.errors;
+        self.total_ms += other.total_ms;
+        self.max_ms = self.max_ms.max(other.max_ms);
+        self.buckets.resize(BUCKETS_MS.len() + 1, 0);
+        for (mine, theirs) in self.buckets.iter_mut().zip(&other.buckets) {
+            *mine += theirs;
+        }
+    }
+
+    /// Fraction of calls that returned an error, in `0.0..=1.0`.
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.calls as f64
+        }
+    }
+
+    /// Latency percentile (`p` in `0.0..=1.0`) in milliseconds, as the upper
+    /// bound of the bucket it falls in, capped at the slowest observed call.
+    pub fn percentile_ms(&self, p: f64) -> u64 {
+        if self.calls == 0 {
+            return 0;
+        }
+        let rank = ((p * self.calls as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKETS_MS.get(i).copied().unwrap_or(self.max_ms);
+                return bound.min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+}
+
+/// Aggregates for every tool on one day, keyed by tool name.
+pub type DailyStats = BTreeMap<String, ToolAggregate>;
+
+#[derive(Default)]
+struct Inner {
+    /// Calls not yet written to disk, by day.
+    pending: HashMap<NaiveDate, DailyStats>,
+    /// Everything recorded by this process, for slow-tool warnings.
+    lifetime: HashMap<String, ToolAggregate>,
+    /// Tools currently over the slow threshold (warned once per crossing).
+    slow: HashSet<String>,
+    last_flush: Option<Instant>,
+}
+
+/// Thread-safe recorder owned by the [`ToolRegistry`](super::ToolRegistry).
+///
+/// The default instance keeps stats in memory only and never warns.
+#[derive(Default)]
+pub struct ToolStats {
+    dir: Option<PathBuf>,
+    slow_p95_ms: u64,
+    inner: Mutex<Inner>,
+}
+
+impl ToolStats {
+    /// Persist daily aggregates under `workspace` and warn when a tool's p95
+    /// exceeds `slow_p95_ms` (0 disables the warning).
+    pub fn new(workspace: &Path, slow_p95_ms: u64) -> Self {
+        Self {
+            dir: Some(Self::dir(workspace)),
+            slow_p95_ms,
+            inner: Mutex::default(),
+        }
+    }
+
+    fn dir(workspace: &Path) -> PathBuf {
+        workspace.join("stats").join("tools")
+    }
+
+    fn day_path(dir: &Path, day: NaiveDate) -> PathBuf {
+        dir.join(format!("{}.json", day.format("%Y-%m-%d")))
+    }
+
+    /// Record one call.
+    pub fn record(&self, tool: &str, elapsed: Duration, is_error: bool) {
+        let ms = elapsed.as_millis() as u64;
+        let today = Utc::now().date_naive();
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        inner
+            .pending
+            .entry(today)
+            .or_default()
+            .entry(tool.to_string())
+            .or_default()
+            .record(ms, is_error);
+
+        let lifetime = inner.lifetime.entry(tool.to_string()).or_default();
+        lifetime.record(ms, is_error);
+        let p95 = lifetime.percentile_ms(0.95);
+        let enough = lifetime.calls >= MIN_SAMPLES_FOR_WARNING;
+        if self.slow_p95_ms > 0 && enough && p95 > self.slow_p95_ms {
+            if inner.slow.insert(tool.to_string()) {
+                warn!(tool, p95_ms = p95, threshold_ms = self.slow_p95_ms, "Tool is slow");
+            }
+        } else {
+            inner.slow.remove(tool);
+        }
+
+        let due = inner.last_flush.is_none_or(|t| t.elapsed() >= FLUSH_INTERVAL);
+        if due {
+            inner.last_flush = Some(Instant::now());
+            if let Err(e) = self.flush_locked(&mut inner) {
+                warn!("Failed to persist tool stats: {}", e);
+            }
+        }
+    }
+
+    /// Write pending calls to the daily files.
+    pub fn flush(&self) -> std::io::Result<()> {
+        match self.inner.lock() {
+            Ok(mut inner) => self.flush_locked(&mut inner),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn flush_locked(&self, inner: &mut Inner) -> std::io::Result<()> {
+        let Some(ref dir) = self.dir else {
+            return Ok(());
+        };
+        if inner.pending.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(dir)?;
+        for (day, stats) in inner.pending.drain() {
+            let mut merged = Self::read_day(dir, day);
+            for (tool, agg) in &stats {
+                merged.entry(tool.clone()).or_default().merge(agg);
+            }
+            std::fs::write(Self::day_path(dir, day), serde_json::to_string_pretty(&merged)?)?;
+        }
+        Ok(())
+    }
+
+    /// Today's aggregates: what's on disk plus calls not yet flushed.
+    pub fn today(&self) -> DailyStats {
+        let today = Utc::now().date_naive();
+        // Hold the lock while reading so a concurrent flush can't move
+        // pending calls to disk between the two reads.
+        let inner = self.inner.lock();
+        let mut stats = match self.dir {
+            Some(ref dir) => Self::read_day(dir, today),
+            None => DailyStats::new(),
+        };
+        if let Ok(inner) = inner {
+            for (tool, agg) in inner.pending.get(&today).into_iter().flatten() {
+                stats.entry(tool.clone()).or_default().merge(agg);
+            }
+        }
+        stats
+    }
+
+    fn read_day(dir: &Path, day: NaiveDate) -> DailyStats {
+        std::fs::read_to_string(Self::day_path(dir, day))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// The persisted aggregates of one day from a workspace.
+    pub fn load_day(workspace: &Path, day: NaiveDate) -> DailyStats {
+        Self::read_day(&Self::dir(workspace), day)
+    }
+
+    /// Merge the persisted aggregates for the last `days` days (including
+    /// today) from a workspace.
+    pub fn load_recent(workspace: &Path, days: u32) -> DailyStats {
+        let dir = Self::dir(workspace);
+        let today = Utc::now().date_naive();
+        let mut stats = DailyStats::new();
+        for offset in 0..days.max(1) {
+            let day = today - chrono::Duration::days(offset.into());
+            for (tool, agg) in Self::read_day(&dir, day) {
+                stats.entry(tool).or_default().merge(&agg);
+            }
+        }
+        stats
+    }
+}
+
+impl Drop for ToolStats {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Whether a tool's output reports a failure. Tools return errors as text,
+/// conventionally prefixed with `Error` or `❌`.
+pub fn is_error_output(output: &str) -> bool {
+    let s = output.trim_start();
+    s.starts_with("Error") || s.starts_with("❌")
+}
+
+/// Render aggregates as a plain-text table, busiest tools first.
+pub fn render(stats: &DailyStats) -> String {
+    if stats.is_empty() {
+        return "No tool calls recorded.".into();
+    }
+    let mut rows: Vec<_> = stats.iter().collect();
+    rows.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(b.0)));
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Tool".len());
+
+    let mut out = format!(
+        "{:<width$}  {:>6}  {:>6}  {:>7}  {:>7}  {:>7}\n",
+        "Tool", "Calls", "Err%", "p50", "p95", "Max"
+    );
+    for (name, agg) in rows {
+        out.push_str(&format!(
+            "{:<width$}  {:>6}  {:>5.1}%  {:>5}ms  {:>5}ms  {:>5}ms\n",
+            name,
+            agg.calls,
+            agg.error_rate() * 100.0,
+            agg.percentile_ms(0.5),
+            agg.percentile_ms(0.95),
+            agg.max_ms,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_error_rate() {
+        let mut agg = ToolAggregate::default();
+        for ms in [10, 20, 30, 40, 80, 90, 120, 200, 700, 4_000] {
+            agg.record(ms, false);
+        }
+        agg.record(30, true);
+        assert_eq!(agg.calls, 11);
+        assert_eq!(agg.percentile_ms(0.5), 100);
+        assert_eq!(agg.percentile_ms(0.95), 4_000);
+        assert!((agg.error_rate() - 1.0 / 11.0).abs() < 1e-9);
+
+        let mut total = ToolAggregate::default();
+        total.merge(&agg);
+        total.merge(&agg);
+        assert_eq!(total.calls, 22);
+        assert_eq!(total.percentile_ms(0.5), agg.percentile_ms(0.5));
+    }
+
+    #[test]
+    fn test_stats_persist_across_instances() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_tool_stats");
+        let _ = std::fs::remove_dir_all(&tmp);
+
+        {
+            let stats = ToolStats::new(&tmp, 0);
+            stats.record("web_fetch", Duration::from_millis(300), false);
+            stats.record("web_fetch", Duration::from_millis(900), true);
+            stats.record("read_file", Duration::from_millis(2), false);
+            assert_eq!(stats.today()["web_fetch"].calls, 2);
+        }
+
+        let stats = ToolStats::new(&tmp, 0);
+        stats.record("web_fetch", Duration::from_millis(100), false);
+        assert_eq!(stats.today()["web_fetch"].calls, 3);
+        drop(stats);
+
+        let recent = ToolStats::load_recent(&tmp, 7);
+        assert_eq!(recent["web_fetch"].errors, 1);
+        assert_eq!(recent["read_file"].calls, 1);
+        let table = render(&recent);
+        assert!(table.lines().nth(1).unwrap().starts_with("web_fetch"), "{table}");
+
+        assert!(is_error_output("Error: boom"));
+        assert!(is_error_output("❌ failed"));
+        assert!(!is_error_output("ok"));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,471 @@
+//! `table_analyze`: CSV/TSV/XLSX analysis on polars.
+//!
+//! Without a query the tool describes the table: row count, each column's
+//! type and null count, and summary statistics (mean, std, min, max for
+//! numeric columns; distinct values for the rest). With `filters`,
+//! `group_by`, `aggregations` or `sort_by` it runs that query instead and
+//! returns the resulting rows, so the agent doesn't have to improvise shell
+//! one-liners for data work.
+//!
+//! Spreadsheets are read with calamine and handed to polars' CSV reader so
+//! both formats get the same type inference.
+
+use async_trait::async_trait;
+use polars::prelude::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use super::filesystem::resolve_path;
+use super::{CostHint, CostTier, Tool, ToolContext};
+
+/// Rows returned by a query unless `limit` says otherwise.
+const DEFAULT_LIMIT: u32 = 20;
+/// Hard cap on returned rows, to keep results readable.
+const MAX_LIMIT: u32 = 200;
+/// Widest rendered cell, in characters.
+const MAX_CELL_CHARS: usize = 40;
+
+// ── Loading ─────────────────────────────────────────────────────────
+
+fn csv_options(separator: u8) -> CsvReadOptions {
+    CsvReadOptions::default()
+        .with_has_header(true)
+        .with_infer_schema_length(Some(10_000))
+        .map_parse_options(move |o| o.with_separator(separator))
+}
+
+fn load(path: &Path, sheet: Option<&str>) -> Result<DataFrame, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match ext.as_str() {
+        "csv" | "tsv" => {
+            let separator = if ext == "tsv" { b'\t' } else { b',' };
+            csv_options(separator)
+                .try_into_reader_with_file_path(Some(path.to_path_buf()))
+                .and_then(|r| r.finish())
+                .map_err(|e| format!("Error reading '{}': {}", path.display(), e))
+        }
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            let csv = sheet_to_csv(path, sheet)?;
+            csv_options(b',')
+                .into_reader_with_file_handle(Cursor::new(csv.into_bytes()))
+                .finish()
+                .map_err(|e| format!("Error parsing sheet: {}", e))
+        }
+        _ => Err("Error: unsupported file type (expected .csv, .tsv, .xlsx, .xls or .ods)".into()),
+    }
+}
+
+/// Render one worksheet (the first unless `sheet` is given) as CSV text.
+fn sheet_to_csv(path: &Path, sheet: Option<&str>) -> Result<String, String> {
+    use calamine::Reader;
+
+    let mut workbook =
+        calamine::open_workbook_auto(path).map_err(|e| format!("Error opening '{}': {}", path.display(), e))?;
+    let names = workbook.sheet_names();
+    let name = match sheet {
+        Some(s) if names.iter().any(|n| n == s) => s.to_string(),
+        Some(s) => return Err(format!("Error: no sheet '{}' (sheets: {})", s, names.join(", "))),
+        None => names.first().cloned().ok_or("Error: workbook has no sheets")?,
+    };
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e| format!("Error reading sheet '{}': {}", name, e))?;
+
+    let mut out = String::new();
+    for row in range.rows() {
+        let cells: Vec<String> = row.iter().map(|c| csv_field(&c.to_string())).collect();
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// ── Describing ──────────────────────────────────────────────────────
+
+fn describe(df: &DataFrame) -> String {
+    let mut out = format!("{} rows × {} columns\n\n", df.height(), df.width());
+    let mut rows = vec![vec![
+        "column".to_string(),
+        "type".into(),
+        "nulls".into(),
+        "summary".into(),
+    ]];
+    for column in df.get_columns() {
+        let series = column.as_materialized_series();
+        let summary = if series.dtype().is_primitive_numeric() {
+            let num = |v: Option<f64>| v.map_or("-".to_string(), format_number);
+            format!(
+                "mean {}, std {}, min {}, max {}",
+                num(series.mean()),
+                num(series.std(1)),
+                num(series.min::<f64>().ok().flatten()),
+                num(series.max::<f64>().ok().flatten()),
+            )
+        } else {
+            match series.n_unique() {
+                Ok(n) => format!("{} distinct", n),
+                Err(_) => String::new(),
+            }
+        };
+        rows.push(vec![
+            series.name().to_string(),
+            series.dtype().to_string(),
+            series.null_count().to_string(),
+            summary,
+        ]);
+    }
+    out.push_str(&render_rows(&rows));
+    out
+}
+
+fn format_number(v: f64) -> String {
+    if v.fract() == 0.0 && v.abs() < 1e15 {
+        format!("{}", v as i64)
+    } else {
+        format!("{:.4}", v)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+// ── Querying ────────────────────────────────────────────────────────
+
+fn literal(value: &Value) -> Result<Expr, String> {
+    match value {
+        Value::Number(n) if n.is_i64() => Ok(lit(n.as_i64().unwrap_or_default())),
+        Value::Number(n) => Ok(lit(n.as_f64().unwrap_or_default())),
+        Value::String(s) => Ok(lit(s.clone())),
+        Value::Bool(b) => Ok(lit(*b)),
+        other => Err(format!("Error: unsupported filter value {}", other)),
+    }
+}
+
+fn filter_expr(filter: &Value) -> Result<Expr, String> {
+    let column = filter
+        .get("column")
+        .and_then(|v| v.as_str())
+        .ok_or("Error: each filter needs a 'column'")?;
+    let op = filter.get("op").and_then(|v| v.as_str()).unwrap_or("eq");
+    let value = literal(filter.get("value").unwrap_or(&Value::Null))?;
+    let c = col(column);
+    Ok(match op {
+        "eq" | "==" => c.eq(value),
+        "ne" | "!=" => c.neq(value),
+        "gt" | ">" => c.gt(value),
+        "ge" | ">=" => c.gt_eq(value),
+        "lt" | "<" => c.lt(value),
+        "le" | "<=" => c.lt_eq(value),
+        other => return Err(format!("Error: unknown filter op '{}'", other)),
+    })
+}
+
+fn aggregation_expr(agg: &Value) -> Result<Expr, String> {
+    let column = agg
+        .get("column")
+        .and_then(|v| v.as_str())
+        .ok_or("Error: each aggregation needs a 'column'")?;
+    let func = agg.get("fn").and_then(|v| v.as_str()).unwrap_or("sum");
+    let c = col(column);
+    let expr = match func {
+        "sum" => c.sum(),
+        "mean" | "avg" => c.mean(),
+        "median" => c.median(),
+        "min" => c.min(),
+        "max" => c.max(),
+        "count" => c.count(),
+        "n_unique" | "distinct" => c.n_unique(),
+        other => return Err(format!("Error: unknown aggregation '{}'", other)),
+    };
+    Ok(expr.alias(format!("{}_{}", column, func)))
+}
+
+fn run_query(df: DataFrame, args: &HashMap<String, Value>) -> Result<DataFrame, String> {
+    let list = |key: &str| args.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let mut lf = df.lazy();
+
+    for filter in list("filters") {
+        lf = lf.filter(filter_expr(&filter)?);
+    }
+
+    let group_by: Vec<Expr> = list("group_by")
+        .iter()
+        .filter_map(|v| v.as_str().map(col))
+        .collect();
+    let aggs = list("aggregations")
+        .iter()
+        .map(aggregation_expr)
+        .collect::<Result<Vec<_>, _>>()?;
+    if !group_by.is_empty() {
+        let aggs = if aggs.is_empty() { vec![len().alias("count")] } else { aggs };
+        lf = lf.group_by(group_by).agg(aggs);
+    } else if !aggs.is_empty() {
+        lf = lf.select(aggs);
+    }
+
+    if let Some(by) = args.get("sort_by").and_then(|v| v.as_str()) {
+        let descending = args.get("descending").and_then(|v| v.as_bool()).unwrap_or(false);
+        let options = SortMultipleOptions::default()
+            .with_order_descending(descending)
+            .with_nulls_last(true);
+        lf = lf.sort([by], options);
+    }
+
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_LIMIT, |n| (n as u32).min(MAX_LIMIT));
+    lf.limit(limit as IdxSize)
+        .collect()
+        .map_err(|e| format!("Error: {}", e))
+}
+
+fn render_frame(df: &DataFrame) -> String {
+    let mut rows = vec![df
+        .get_column_names()
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()];
+    for i in 0..df.height() {
+        rows.push(
+            df.get_columns()
+                .iter()
+                .map(|c| match c.get(i) {
+                    Ok(AnyValue::Null) => String::new(),
+                    Ok(AnyValue::String(s)) => s.to_string(),
+                    Ok(AnyValue::Float64(v)) => format_number(v),
+                    Ok(AnyValue::Float32(v)) => format_number(v.into()),
+                    Ok(v) => v.to_string(),
+                    Err(_) => "?".into(),
+                })
+                .collect(),
+        );
+    }
+    format!("{} rows\n\n{}", df.height(), render_rows(&rows))
+}
+
+/// Left-aligned plain-text table; the first row is the header.
+fn render_rows(rows: &[Vec<String>]) -> String {
+    let clip = |s: &str| -> String {
+        if s.chars().count() > MAX_CELL_CHARS {
+            let mut t: String = s.chars().take(MAX_CELL_CHARS - 1).collect();
+            t.push('…');
+            t
+        } else {
+            s.to_string()
+        }
+    };
+    let rows: Vec<Vec<String>> = rows.iter().map(|r| r.iter().map(|c| clip(c)).collect()).collect();
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..cols)
+        .map(|i| rows.iter().filter_map(|r| r.get(i)).map(|c| c.chars().count()).max().unwrap_or(0))
+        .collect();
+
+    let mut out = String::new();
+    for (n, row) in rows.iter().enumerate() {
+        let line: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{:<w$}", c, w = widths[i]))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+        if n == 0 {
+            let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            out.push_str(&rule.join("  "));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// ── TableAnalyzeTool ────────────────────────────────────────────────
+
+pub struct TableAnalyzeTool {
+    workspace: PathBuf,
+    restrict: bool,
+}
+
+impl TableAnalyzeTool {
+    pub fn new(workspace: PathBuf, restrict: bool) -> Self {
+        Self { workspace, restrict }
+    }
+}
+
+#[async_trait]
+impl Tool for TableAnalyzeTool {
+    fn name(&self) -> &str {
+        "table_analyze"
+    }
+
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 2)
+    }
+
+    fn description(&self) -> &str {
+        "Analyze a CSV, TSV or Excel file. With only `path` it returns the schema, row count and \
+         summary statistics per column. Add `filters`, `group_by`, `aggregations` and `sort_by` \
+         to run a query and get the resulting rows. Prefer this over shell one-liners for data work."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to a .csv, .tsv, .xlsx, .xls or .ods file"
+                },
+                "sheet": {
+                    "type": "string",
+                    "description": "Worksheet name for spreadsheets (default: the first sheet)"
+                },
+                "filters": {
+                    "type": "array",
+                    "description": "Row filters, all of which must match",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "column": { "type": "string" },
+                            "op": { "type": "string", "enum": ["eq", "ne", "gt", "ge", "lt", "le"] },
+                            "value": { "description": "Number, string or boolean to compare with" }
+                        },
+                        "required": ["column", "value"]
+                    }
+                },
+                "group_by": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Columns to group by; without aggregations each group's row count is returned"
+                },
+                "aggregations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "column": { "type": "string" },
+                            "fn": { "type": "string", "enum": ["sum", "mean", "median", "min", "max", "count", "n_unique"] }
+                        },
+                        "required": ["column", "fn"]
+                    }
+                },
+                "sort_by": {
+                    "type": "string",
+                    "description": "Column to sort the result by (aggregated columns are named `<column>_<fn>`)"
+                },
+                "descending": { "type": "boolean" },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum rows to return (default 20, at most 200)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(raw_path) = args.get("path").and_then(|v| v.as_str()) else {
+            return "Error: 'path' parameter is required".into();
+        };
+        let path = match resolve_path(raw_path, ctx.workspace_or(&self.workspace), self.restrict) {
+            Ok(p) => p,
+            Err(e) => return e,
+        };
+
+        let is_query = ["filters", "group_by", "aggregations", "sort_by"]
+            .iter()
+            .any(|k| args.contains_key(*k));
+        // Polars parses on the calling thread; keep it off the runtime.
+        let result = tokio::task::spawn_blocking(move || {
+            let sheet = args.get("sheet").and_then(|v| v.as_str());
+            let df = load(&path, sheet)?;
+            if is_query {
+                run_query(df, &args).map(|df| render_frame(&df))
+            } else {
+                Ok(describe(&df))
+            }
+        })
+        .await;
+        match result {
+            Ok(Ok(out)) | Ok(Err(out)) => out,
+            Err(e) => format!("Error: analysis task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: Value) -> HashMap<String, Value> {
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_describe_and_query_csv() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_table");
+        let _ = std::fs::remove_dir_all(&tmp);
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(
+            tmp.join("trades.csv"),
+            "token,side,amount\nSOL,buy,10\nSOL,sell,4\nBONK,buy,2.5\nSOL,buy,6\nJUP,buy,\n",
+        )
+        .unwrap();
+        let tool = TableAnalyzeTool::new(tmp.clone(), true);
+        let ctx = ToolContext::default();
+        let csv = tmp.join("trades.csv").to_string_lossy().to_string();
+
+        let out = tool.execute(args(json!({"path": csv})), &ctx).await;
+        assert!(out.starts_with("5 rows × 3 columns"), "{out}");
+        assert!(out.contains("token   str"), "{out}");
+        assert!(out.contains("3 distinct"), "{out}");
+        assert!(out.contains("amount  f64   1      mean 5.625, std"), "{out}");
+
+        let out = tool
+            .execute(
+                args(json!({
+                    "path": csv,
+                    "filters": [{"column": "side", "op": "eq", "value": "buy"}],
+                    "group_by": ["token"],
+                    "aggregations": [{"column": "amount", "fn": "sum"}],
+                    "sort_by": "amount_sum",
+                    "descending": true
+                })),
+                &ctx,
+            )
+            .await;
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], "3 rows", "{out}");
+        assert_eq!(lines[2], "token  amount_sum", "{out}");
+        assert_eq!(lines[4], "SOL    16", "{out}");
+        assert_eq!(lines[5], "BONK   2.5", "{out}");
+
+        let out = tool
+            .execute(args(json!({"path": csv, "filters": [{"column": "amount", "op": "like", "value": 1}]})), &ctx)
+            .await;
+        assert!(out.starts_with("Error: unknown filter op"), "{out}");
+
+        let out = tool.execute(args(json!({"path": "/etc/passwd.csv"})), &ctx).await;
+        assert!(out.contains("outside workspace"), "{out}");
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
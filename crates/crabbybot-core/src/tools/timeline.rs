@@ -0,0 +1,399 @@
+//! `recall_entity`: everything on record about a token, wallet or person.
+//!
+//! Searches past conversations, long-term memory and daily notes, the
+//! decision journal and the prediction knowledge graph for mentions of a
+//! name (or any of its aliases, such as a ticker and its mint address) and
+//! puts them in date order, so earlier conclusions are found even when the
+//! current session never mentioned them.
+//!
+//! A chat only sees what belongs to its own workspace: chats with
+//! `agents.chats.<key>.workspace` set get their own sessions, memory and
+//! decisions, everyone else shares the main workspace's.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::prediction::graph::KnowledgeGraph;
+use super::{Tool, ToolContext};
+use crate::agent::memory::MemoryStore;
+use crate::journal::DecisionJournal;
+use crate::session::{self, SessionManager};
+
+/// Days searched when the call doesn't say.
+const DEFAULT_DAYS: i64 = 90;
+
+/// Mentions listed when the call doesn't say; the most recent are kept.
+const DEFAULT_LIMIT: usize = 30;
+
+/// Session messages looked at before the workspace and date filters.
+const SESSION_HITS: usize = 500;
+
+/// Characters of a memory line or decision kept in the brief.
+const ENTRY_CHARS: usize = 300;
+
+/// One mention, with where and when it was made.
+struct Mention {
+    at: DateTime<Utc>,
+    /// Whether `at` has a time of day, or only a date.
+    timed: bool,
+    source: String,
+    text: String,
+}
+
+pub struct RecallEntityTool {
+    workspace: PathBuf,
+    chat_workspaces: BTreeMap<String, PathBuf>,
+    journal: Arc<DecisionJournal>,
+}
+
+impl RecallEntityTool {
+    pub fn new(workspace: PathBuf, chat_workspaces: BTreeMap<String, PathBuf>, journal: Arc<DecisionJournal>) -> Self {
+        Self {
+            workspace,
+            chat_workspaces,
+            journal,
+        }
+    }
+
+    /// The workspace the chat `channel:chat_id` keeps its state in.
+    fn workspace_of(&self, channel: &str, chat_id: &str) -> &Path {
+        self.chat_workspaces
+            .get(&format!("{}:{}", channel, chat_id))
+            .unwrap_or(&self.workspace)
+    }
+
+    fn sessions(&self, names: &[String], workspace: &Path, since: DateTime<Utc>, out: &mut Vec<Mention>) {
+        let sessions = SessionManager::new(&self.workspace).with_chat_workspaces(self.chat_workspaces.clone());
+        let mut seen = std::collections::HashSet::new();
+        for name in names {
+            for hit in sessions.search(name, None, SESSION_HITS) {
+                let (channel, chat_id) = session::chat_of(&hit.key);
+                let Ok(at) = DateTime::parse_from_rfc3339(&hit.timestamp) else {
+                    continue;
+                };
+                let at = at.with_timezone(&Utc);
+                if at < since
+                    || self.workspace_of(channel, chat_id) != workspace
+                    || !mentions(&hit.snippet, names)
+                    || !seen.insert((hit.key.clone(), hit.timestamp.clone()))
+                {
+                    continue;
+                }
+                out.push(Mention {
+                    at,
+                    timed: true,
+                    source: format!("chat {} ({})", hit.key, hit.role),
+                    text: hit.snippet,
+                });
+            }
+        }
+    }
+
+    fn journal(&self, names: &[String], workspace: &Path, since: DateTime<Utc>, out: &mut Vec<Mention>) {
+        for decision in self.journal.since(since) {
+            let d = &decision.draft;
+            let text = format!(
+                "{} — thesis: {}; inputs: {}; expected: {}; stop: {}",
+                d.trade,
+                d.thesis,
+                d.inputs.join("; "),
+                d.expected_outcome,
+                d.stop_condition
+            );
+            if self.workspace_of(&decision.channel, &decision.chat_id) != workspace || !mentions(&text, names) {
+                continue;
+            }
+            let outcome = match &decision.execution {
+                Some(e) => format!(" Executed via `{}`.", e.tool),
+                None => " Not executed.".to_string(),
+            };
+            out.push(Mention {
+                at: decision.recorded_at,
+                timed: true,
+                source: format!("decision #{}", decision.id),
+                text: format!("{}{}", clip(&text), outcome),
+            });
+        }
+    }
+}
+
+/// Whether `text` mentions any of `names` as a whole word, ignoring case:
+/// `SOL` matches "$SOL is up" but not "console".
+fn mentions(text: &str, names: &[String]) -> bool {
+    let text = text.to_lowercase();
+    names.iter().any(|name| {
+        let name = name.to_lowercase();
+        text.match_indices(&name).any(|(at, _)| {
+            let before = text[..at].chars().next_back();
+            let after = text[at + name.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+    })
+}
+
+fn clip(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(ENTRY_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+/// Noon of a local date, so the day survives conversion to the chat's
+/// timezone.
+fn local_noon(date: NaiveDate) -> Option<DateTime<Utc>> {
+    date.and_hms_opt(12, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn memory(names: &[String], workspace: &Path, days: i64, out: &mut Vec<Mention>, undated: &mut Vec<String>) {
+    let store = MemoryStore::new(workspace);
+    for (key, fact) in store.facts() {
+        let line = format!("{}: {}", key, fact.value);
+        if !mentions(&line, names) {
+            continue;
+        }
+        match NaiveDate::parse_from_str(&fact.updated, "%Y-%m-%d").ok().and_then(local_noon) {
+            Some(at) => out.push(Mention {
+                at,
+                timed: false,
+                source: "memory fact".into(),
+                text: clip(&line),
+            }),
+            None => undated.push(clip(&line)),
+        }
+    }
+    for line in store.read_long_term().lines().map(str::trim) {
+        if !line.starts_with('#') && mentions(line, names) {
+            undated.push(clip(line.trim_start_matches("- ")));
+        }
+    }
+    for (date, notes) in store.daily_notes(days as u32) {
+        let Some(at) = local_noon(date) else {
+            continue;
+        };
+        for line in notes.lines().map(str::trim) {
+            if !line.starts_with('#') && mentions(line, names) {
+                out.push(Mention {
+                    at,
+                    timed: false,
+                    source: "daily notes".into(),
+                    text: clip(line.trim_start_matches("- ")),
+                });
+            }
+        }
+    }
+}
+
+/// Entities of the knowledge graph named after or described with one of
+/// `names`, dated when the graph was last built.
+fn knowledge(names: &[String], workspace: &Path, out: &mut Vec<Mention>) {
+    let path = workspace.join("prediction_graph.json");
+    let Ok(graph) = KnowledgeGraph::load(&path) else {
+        return;
+    };
+    let Some(at) = std::fs::metadata(&path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from) else {
+        return;
+    };
+    for entity in graph.all_entities() {
+        if !mentions(&entity.name, names) && !mentions(&entity.summary, names) {
+            continue;
+        }
+        let relations: Vec<String> = graph
+            .neighbors(&entity.id)
+            .iter()
+            .take(3)
+            .map(|(target, relation)| format!("{} {}", relation.relation_type, target.name))
+            .collect();
+        let mut text = format!("{} ({}): {}", entity.name, entity.entity_type, clip(&entity.summary));
+        if !relations.is_empty() {
+            text.push_str(&format!(" [{}]", relations.join(", ")));
+        }
+        out.push(Mention {
+            at,
+            timed: true,
+            source: "knowledge graph".into(),
+            text,
+        });
+    }
+}
+
+#[async_trait]
+impl Tool for RecallEntityTool {
+    fn name(&self) -> &str {
+        "recall_entity"
+    }
+
+    fn description(&self) -> &str {
+        "Gather everything on record about a token, wallet, market or person: mentions in \
+         past conversations (all sessions, not just this one), long-term memory and daily \
+         notes, the decision journal and the prediction knowledge graph, in date order. \
+         Use it for questions like 'what did we conclude about ABC?' before answering from \
+         the current history alone. Pass other names for the same thing, such as a ticker \
+         and its contract address, as `aliases`."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "entity": { "type": "string", "description": "Name, ticker or address to look for, matched as a whole word" },
+                "aliases": { "type": "array", "items": { "type": "string" }, "description": "Other names for the same entity" },
+                "days": { "type": "integer", "description": "How far back to look (default 90)" },
+                "limit": { "type": "integer", "description": "Most recent mentions to list (default 30)" }
+            },
+            "required": ["entity"]
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
+        let Some(entity) = args.get("entity").and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty()) else {
+            return "Error: 'entity' is required".into();
+        };
+        let mut names = vec![entity.to_string()];
+        if let Some(aliases) = args.get("aliases").and_then(|v| v.as_array()) {
+            names.extend(
+                aliases
+                    .iter()
+                    .filter_map(|a| a.as_str())
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(String::from),
+            );
+        }
+        let days = args.get("days").and_then(|v| v.as_i64()).unwrap_or(DEFAULT_DAYS).clamp(1, 3650);
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_LIMIT, |l| l as usize)
+            .clamp(1, 200);
+        let since = Utc::now() - Duration::days(days);
+        let workspace = ctx.workspace_or(&self.workspace).to_path_buf();
+
+        let mut found = Vec::new();
+        let mut undated = Vec::new();
+        self.sessions(&names, &workspace, since, &mut found);
+        memory(&names, &workspace, days, &mut found, &mut undated);
+        self.journal(&names, &workspace, since, &mut found);
+        knowledge(&names, &workspace, &mut found);
+
+        if found.is_empty() && undated.is_empty() {
+            return format!("Nothing on record about '{}' in the last {} days.", names.join("' / '"), days);
+        }
+        found.sort_by_key(|m| m.at);
+        let total = found.len();
+        let shown = &found[total.saturating_sub(limit)..];
+
+        let mut out = format!("🗂️ On record about {}", names.join(" / "));
+        if let (Some(first), Some(last)) = (found.first(), found.last()) {
+            out.push_str(&format!(
+                " — {} mention(s), {} to {}",
+                total,
+                ctx.locale.date(first.at),
+                ctx.locale.date(last.at)
+            ));
+        }
+        out.push_str(":\n");
+        if !undated.is_empty() {
+            out.push_str("\nLong-term memory:\n");
+            for note in &undated {
+                out.push_str(&format!("• {}\n", note));
+            }
+        }
+        if !shown.is_empty() {
+            out.push_str("\nTimeline:\n");
+            if shown.len() < total {
+                out.push_str(&format!("(the latest {} of {})\n", shown.len(), total));
+            }
+            for m in shown {
+                let when = match m.timed {
+                    true => format!("{} {}", ctx.locale.date(m.at), ctx.locale.time(m.at)),
+                    false => ctx.locale.date(m.at),
+                };
+                out.push_str(&format!("• {} — {}: {}\n", when, m.source, m.text));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::DecisionDraft;
+
+    #[test]
+    fn test_mentions_whole_words() {
+        let names = vec!["SOL".to_string(), "7xKXtg2CW".to_string()];
+        assert!(mentions("Bought $sol at 140", &names));
+        assert!(mentions("wallet 7xkxtg2cw…", &names));
+        assert!(!mentions("Open the console", &names));
+        assert!(!mentions("solana", &names));
+    }
+
+    #[tokio::test]
+    async fn test_timeline_from_every_store_of_the_chat_workspace() {
+        let tmp = std::env::temp_dir().join(format!("CrabbyBot_test_recall_entity_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let chat = tmp.join("chat");
+        let chat_workspaces: BTreeMap<_, _> = [
+            ("telegram:7".to_string(), chat.clone()),
+            ("telegram:8".to_string(), tmp.join("other")),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut sessions = SessionManager::new(&tmp).with_chat_workspaces(chat_workspaces.clone());
+        sessions.get_or_create("telegram:7").add_message("assistant", "ABC looks like a rug: mint authority is live.");
+        sessions.get_or_create("telegram:7").add_message("user", "What about ABCD?");
+        sessions.save("telegram:7").unwrap();
+        sessions.get_or_create("telegram:8").add_message("user", "I hold a lot of ABC");
+        sessions.save("telegram:8").unwrap();
+
+        let store = MemoryStore::new(&chat);
+        store.set_fact("abc_verdict", "avoid ABC until the mint is revoked").unwrap();
+        store.append_long_term("ABC dev wallet is 9WzDX").unwrap();
+        store.append_today("Checked ABC holders again");
+
+        let journal = Arc::new(DecisionJournal::new(&tmp));
+        let draft = |trade: &str| DecisionDraft {
+            trade: trade.into(),
+            thesis: "Momentum".into(),
+            expected_outcome: "Up 20%".into(),
+            stop_condition: "-10%".into(),
+            ..Default::default()
+        };
+        journal.record(draft("sell 9WzDX tokens"), "telegram", "7").unwrap();
+        journal.record(draft("buy ABC"), "telegram", "8").unwrap();
+
+        let tool = RecallEntityTool::new(tmp.clone(), chat_workspaces, journal);
+        let ctx = ToolContext::new("telegram", "7").with_workspace(&chat);
+        let args: HashMap<String, Value> = [("entity".to_string(), json!("abc")), ("aliases".to_string(), json!(["9WzDX"]))]
+            .into_iter()
+            .collect();
+        let out = tool.execute(args, &ctx).await;
+
+        assert!(out.contains("• ABC dev wallet is 9WzDX"), "{}", out);
+        assert!(out.contains("memory fact: abc_verdict: avoid ABC"), "{}", out);
+        assert!(out.contains("daily notes: Checked ABC holders again"), "{}", out);
+        assert!(out.contains("chat telegram:7 (assistant): ABC looks like a rug"), "{}", out);
+        assert!(out.contains("sell 9WzDX tokens"), "{}", out);
+        assert!(!out.contains("ABCD"), "{}", out);
+        assert!(!out.contains("I hold a lot"), "another workspace's chat: {}", out);
+        assert!(!out.contains("buy ABC"), "another workspace's decision: {}", out);
+
+        let args: HashMap<String, Value> = [("entity".to_string(), json!("XYZ"))].into_iter().collect();
+        assert_eq!(tool.execute(args, &ctx).await, "Nothing on record about 'XYZ' in the last 90 days.");
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
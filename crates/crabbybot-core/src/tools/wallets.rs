@@ -0,0 +1,439 @@
+//! Named wallets shared by the on-chain tools.
+//!
+//! `tools.wallets` in `config.json` maps names such as `main` or `degen` to
+//! a chain, an address and optionally a signing key, each with its own risk
+//! limits. [`WalletBook`] resolves the `wallet` parameter that balance and
+//! trading tools accept, enforces the limits before a trade, and keeps the
+//! per-wallet spend for the current UTC day in `wallets/spent.json` in the
+//! workspace so a restart doesn't reset the daily limit. `list_wallets`
+//! shows every wallet with its live balance.
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use super::{Tool, ToolContext};
+use crate::config::{ToolsConfig, WalletChain};
+
+/// Bridged USDC on Polygon, the collateral Polymarket trades in.
+const POLYGON_USDC: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+/// A configured wallet with its key decrypted and address resolved.
+#[derive(Debug, Clone)]
+pub struct Wallet {
+    pub name: String,
+    pub chain: WalletChain,
+    /// Public address; `None` when it is neither configured nor derivable.
+    pub address: Option<String>,
+    private_key: Option<String>,
+    pub max_trade_usd: f64,
+    pub daily_limit_usd: f64,
+}
+
+impl Wallet {
+    pub fn private_key(&self) -> Option<&str> {
+        self.private_key.as_deref()
+    }
+
+    /// Address or a user-facing error naming the wallet.
+    pub fn require_address(&self) -> Result<&str, String> {
+        self.address.as_deref().ok_or_else(|| {
+            format!(
+                "Error: wallet '{}' has no address. Set `tools.wallets.{}.address` in config.json.",
+                self.name, self.name
+            )
+        })
+    }
+}
+
+/// Public key of a Solana keypair given as base58 or as a JSON byte array
+/// (the `solana-keygen` file format).
+fn solana_address(key: &str) -> Option<String> {
+    let key = key.trim();
+    let bytes = if key.starts_with('[') {
+        serde_json::from_str::<Vec<u8>>(key).ok()?
+    } else {
+        bs58::decode(key).into_vec().ok()?
+    };
+    let secret: [u8; 32] = bytes.get(..32)?.try_into().ok()?;
+    let public = ed25519_dalek::SigningKey::from_bytes(&secret).verifying_key();
+    Some(bs58::encode(public.as_bytes()).into_string())
+}
+
+#[cfg(feature = "polymarket")]
+fn polygon_address(key: &str) -> Option<String> {
+    key.trim()
+        .parse::<alloy::signers::local::PrivateKeySigner>()
+        .ok()
+        .map(|signer| signer.address().to_checksum(None))
+}
+
+#[cfg(not(feature = "polymarket"))]
+fn polygon_address(_key: &str) -> Option<String> {
+    None
+}
+
+/// USD traded per wallet on one UTC day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Spent {
+    day: NaiveDate,
+    wallets: HashMap<String, f64>,
+}
+
+impl Spent {
+    fn today() -> Self {
+        Self {
+            day: Utc::now().date_naive(),
+            wallets: HashMap::new(),
+        }
+    }
+}
+
+/// Every configured wallet plus today's spend per wallet.
+#[derive(Debug)]
+pub struct WalletBook {
+    wallets: Vec<Wallet>,
+    /// Where the spend is kept; `None` keeps it in memory only.
+    path: Option<PathBuf>,
+    spent: Mutex<Spent>,
+}
+
+impl WalletBook {
+    /// Load wallets from `tools.wallets` and the legacy key fields,
+    /// decrypting vault-encrypted keys, and today's spend from `workspace`.
+    pub fn from_config(tools: &ToolsConfig, workspace: &Path) -> Self {
+        let wallets = tools
+            .named_wallets()
+            .into_iter()
+            .map(|(name, cfg)| {
+                let private_key = cfg.private_key.as_ref().map(|k| {
+                    crate::vault::decrypt(k).unwrap_or_else(|e| {
+                        warn!("Failed to decrypt private key of wallet '{}': {}", name, e);
+                        k.clone()
+                    })
+                });
+                let address = cfg.address.clone().or_else(|| {
+                    private_key.as_deref().and_then(|k| match cfg.chain {
+                        WalletChain::Solana => solana_address(k),
+                        WalletChain::Polygon => polygon_address(k),
+                    })
+                });
+                Wallet {
+                    name,
+                    chain: cfg.chain,
+                    address,
+                    private_key,
+                    max_trade_usd: cfg.max_trade_usd,
+                    daily_limit_usd: cfg.daily_limit_usd,
+                }
+            })
+            .collect();
+        Self::with_path(wallets, Some(workspace.join("wallets").join("spent.json")))
+    }
+
+    fn with_path(wallets: Vec<Wallet>, path: Option<PathBuf>) -> Self {
+        let spent = path
+            .as_deref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_else(Spent::today);
+        Self {
+            wallets,
+            path,
+            spent: Mutex::new(spent),
+        }
+    }
+
+    pub fn wallets(&self) -> &[Wallet] {
+        &self.wallets
+    }
+
+    /// Find the wallet a tool should use on `chain`. Without a name, the
+    /// only wallet on that chain is used, or the one called `main`.
+    pub fn resolve(&self, name: Option<&str>, chain: WalletChain) -> Result<&Wallet, String> {
+        let on_chain: Vec<&Wallet> = self.wallets.iter().filter(|w| w.chain == chain).collect();
+        let names = || on_chain.iter().map(|w| w.name.as_str()).collect::<Vec<_>>().join(", ");
+
+        match name.map(str::trim).filter(|n| !n.is_empty()) {
+            Some(name) => match self.wallets.iter().find(|w| w.name.eq_ignore_ascii_case(name)) {
+                Some(w) if w.chain == chain => Ok(w),
+                Some(w) => Err(format!(
+                    "Error: wallet '{}' is a {} wallet, not {}",
+                    w.name,
+                    w.chain.as_str(),
+                    chain.as_str()
+                )),
+                None if on_chain.is_empty() => Err(format!("Error: no {} wallets configured", chain.as_str())),
+                None => Err(format!("Error: unknown wallet '{}'. {} wallets: {}", name, chain.as_str(), names())),
+            },
+            None => match on_chain.as_slice() {
+                [] => Err(format!(
+                    "Error: no {} wallet configured. Add one under `tools.wallets` in config.json.",
+                    chain.as_str()
+                )),
+                [only] => Ok(only),
+                many => many.iter().find(|w| w.name == "main").copied().ok_or_else(|| {
+                    format!(
+                        "Error: several {} wallets are configured ({}); pass `wallet` to pick one",
+                        chain.as_str(),
+                        names()
+                    )
+                }),
+            },
+        }
+    }
+
+    /// USD traded from `wallet` so far today (UTC).
+    pub fn spent_today(&self, wallet: &str) -> f64 {
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        Self::roll_day(&mut spent);
+        spent.wallets.get(wallet).copied().unwrap_or_default()
+    }
+
+    /// Count a trade of `usd` against the wallet's limits, or refuse it if
+    /// it would break them. Checking and counting happen under one lock, so
+    /// concurrent trades can't both fit under the daily limit; call
+    /// [`refund`](Self::refund) if the trade then fails.
+    pub fn reserve(&self, wallet: &Wallet, usd: f64) -> Result<(), String> {
+        if wallet.max_trade_usd > 0.0 && usd > wallet.max_trade_usd {
+            return Err(format!(
+                "Error: ${:.2} exceeds the ${:.2} per-trade limit of wallet '{}'",
+                usd, wallet.max_trade_usd, wallet.name
+            ));
+        }
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        Self::roll_day(&mut spent);
+        let used = spent.wallets.get(&wallet.name).copied().unwrap_or_default();
+        if wallet.daily_limit_usd > 0.0 && used + usd > wallet.daily_limit_usd {
+            return Err(format!(
+                "Error: ${:.2} would exceed the ${:.2} daily limit of wallet '{}' (${:.2} used today)",
+                usd, wallet.daily_limit_usd, wallet.name, used
+            ));
+        }
+        *spent.wallets.entry(wallet.name.clone()).or_default() += usd;
+        self.persist(&spent);
+        Ok(())
+    }
+
+    /// Give back a reservation whose trade didn't go through.
+    pub fn refund(&self, wallet: &Wallet, usd: f64) {
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        Self::roll_day(&mut spent);
+        if let Some(used) = spent.wallets.get_mut(&wallet.name) {
+            *used = (*used - usd).max(0.0);
+        }
+        self.persist(&spent);
+    }
+
+    fn persist(&self, spent: &Spent) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(spent)?));
+        if let Err(e) = written {
+            warn!("Failed to persist wallet spend: {}", e);
+        }
+    }
+
+    fn roll_day(spent: &mut Spent) {
+        if spent.day != Utc::now().date_naive() {
+            *spent = Spent::today();
+        }
+    }
+}
+
+// ── ListWalletsTool ─────────────────────────────────────────────────
+
+pub struct ListWalletsTool {
+    client: Client,
+    wallets: Arc<WalletBook>,
+    solana_rpc_url: String,
+    polygon_rpc_url: String,
+}
+
+impl ListWalletsTool {
+    pub fn new(client: Client, wallets: Arc<WalletBook>, solana_rpc_url: &str, polygon_rpc_url: &str) -> Self {
+        Self {
+            client,
+            wallets,
+            solana_rpc_url: solana_rpc_url.to_string(),
+            polygon_rpc_url: polygon_rpc_url.to_string(),
+        }
+    }
+
+    async fn rpc(&self, url: &str, method: &str, params: Value) -> Option<Value> {
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let resp: Value = self.client.post(url).json(&body).send().await.ok()?.json().await.ok()?;
+        resp.get("result").cloned()
+    }
+
+    async fn balance(&self, wallet: &Wallet) -> String {
+        let Some(address) = wallet.address.as_deref() else {
+            return "address unknown".into();
+        };
+        match wallet.chain {
+            WalletChain::Solana => self
+                .rpc(&self.solana_rpc_url, "getBalance", json!([address]))
+                .await
+                .and_then(|r| r["value"].as_u64())
+                .map(|l| format!("{:.4} SOL", l as f64 / 1_000_000_000.0))
+                .unwrap_or_else(|| "balance unavailable".into()),
+            WalletChain::Polygon => {
+                let hex = |v: Option<Value>| {
+                    v.and_then(|v| v.as_str().map(String::from))
+                        .and_then(|s| u128::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+                };
+                let pol = hex(self.rpc(&self.polygon_rpc_url, "eth_getBalance", json!([address, "latest"])).await);
+                // balanceOf(address)
+                let call = format!("0x70a08231{:0>64}", address.trim_start_matches("0x").to_lowercase());
+                let usdc = hex(self
+                    .rpc(
+                        &self.polygon_rpc_url,
+                        "eth_call",
+                        json!([{"to": POLYGON_USDC, "data": call}, "latest"]),
+                    )
+                    .await);
+                match (usdc, pol) {
+                    (None, None) => "balance unavailable".into(),
+                    (usdc, pol) => format!(
+                        "{} USDC, {} POL",
+                        usdc.map_or("?".into(), |u| format!("{:.2}", u as f64 / 1e6)),
+                        pol.map_or("?".into(), |p| format!("{:.4}", p as f64 / 1e18))
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ListWalletsTool {
+    fn name(&self) -> &str {
+        "list_wallets"
+    }
+
+    fn description(&self) -> &str {
+        "List the user's configured wallets by name (e.g. main, degen) with chain, address, \
+         live balance, risk limits and today's trading volume. Use the names as the `wallet` \
+         parameter of balance and trading tools."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, _args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
+        let wallets = self.wallets.wallets();
+        if wallets.is_empty() {
+            return "No wallets configured. Add them under `tools.wallets` in config.json, e.g. \
+                    `\"wallets\": {\"main\": {\"chain\": \"solana\", \"address\": \"...\"}}`."
+                .into();
+        }
+
+        let balances = futures::future::join_all(wallets.iter().map(|w| self.balance(w))).await;
+        let mut out = vec![format!("👛 *Wallets* ({})", wallets.len())];
+        for (w, balance) in wallets.iter().zip(balances) {
+            out.push(String::new());
+            let mode = if w.private_key.is_some() { "" } else { " · watch-only" };
+            out.push(format!("*{}* ({}{})", w.name, w.chain.as_str(), mode));
+            if let Some(ref address) = w.address {
+                out.push(format!("  `{}`", address));
+            }
+            out.push(format!("  Balance: {}", balance));
+            let limit = |v: f64| if v > 0.0 { format!("${:.2}", v) } else { "none".into() };
+            out.push(format!(
+                "  Limits: {} per trade, {} per day (${:.2} used today)",
+                limit(w.max_trade_usd),
+                limit(w.daily_limit_usd),
+                self.wallets.spent_today(&w.name)
+            ));
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet(name: &str, chain: WalletChain) -> Wallet {
+        Wallet {
+            name: name.into(),
+            chain,
+            address: None,
+            private_key: None,
+            max_trade_usd: 10.0,
+            daily_limit_usd: 25.0,
+        }
+    }
+
+    #[test]
+    fn test_resolve_wallet_by_name_and_default() {
+        let book = WalletBook::with_path(vec![
+            wallet("main", WalletChain::Solana),
+            wallet("degen", WalletChain::Solana),
+            wallet("poly", WalletChain::Polygon),
+        ], None);
+        assert_eq!(book.resolve(Some("Degen"), WalletChain::Solana).unwrap().name, "degen");
+        assert_eq!(book.resolve(None, WalletChain::Solana).unwrap().name, "main");
+        assert_eq!(book.resolve(None, WalletChain::Polygon).unwrap().name, "poly");
+        assert!(book.resolve(Some("poly"), WalletChain::Solana).unwrap_err().contains("polygon wallet"));
+        assert!(book.resolve(Some("nope"), WalletChain::Solana).unwrap_err().contains("main, degen"));
+
+        let book = WalletBook::with_path(vec![wallet("a", WalletChain::Solana), wallet("b", WalletChain::Solana)], None);
+        assert!(book.resolve(None, WalletChain::Solana).unwrap_err().contains("pass `wallet`"));
+    }
+
+    #[test]
+    fn test_wallet_risk_limits() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_wallet_limits_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("wallets").join("spent.json");
+        let book = WalletBook::with_path(vec![wallet("main", WalletChain::Polygon)], Some(path.clone()));
+        let w = book.resolve(None, WalletChain::Polygon).unwrap().clone();
+        assert!(book.reserve(&w, 12.0).unwrap_err().contains("per-trade limit"));
+        book.reserve(&w, 10.0).unwrap();
+        book.reserve(&w, 10.0).unwrap();
+        assert_eq!(book.spent_today("main"), 20.0);
+        assert!(book.reserve(&w, 6.0).unwrap_err().contains("daily limit"));
+        assert_eq!(book.spent_today("main"), 20.0, "a refused trade isn't counted");
+        book.refund(&w, 10.0);
+        assert_eq!(book.spent_today("main"), 10.0);
+
+        // The spend survives a restart.
+        let book = WalletBook::with_path(vec![w.clone()], Some(path));
+        assert_eq!(book.spent_today("main"), 10.0);
+        book.reserve(&w, 10.0).unwrap();
+        assert!(book.reserve(&w, 6.0).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_solana_address_from_keypair() {
+        let secret = [7u8; 32];
+        let public = ed25519_dalek::SigningKey::from_bytes(&secret).verifying_key();
+        let mut keypair = secret.to_vec();
+        keypair.extend_from_slice(public.as_bytes());
+        let expected = bs58::encode(public.as_bytes()).into_string();
+
+        assert_eq!(solana_address(&bs58::encode(&keypair).into_string()), Some(expected.clone()));
+        assert_eq!(solana_address(&serde_json::to_string(&keypair).unwrap()), Some(expected));
+        assert_eq!(solana_address("not a key"), None);
+    }
+}
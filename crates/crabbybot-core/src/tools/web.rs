@@ -7,7 +7,15 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use tracing::debug;
 
-use super::Tool;
+use super::{CostHint, CostTier, Tool, ToolContext};
+
+/// Largest JSON response `web_fetch` returns unaltered to the model; bigger
+/// ones are reduced to text like pages.
+const MAX_JSON_BYTES: usize = 20_000;
+
+/// The same limit for pipeline steps, which navigate the JSON instead of
+/// putting it in front of the model.
+const MAX_PIPELINE_JSON_BYTES: usize = 100_000;
 
 // ── WebSearchTool ───────────────────────────────────────────────────
 
@@ -50,6 +58,10 @@ impl Tool for WebSearchTool {
         "web_search"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Moderate, 3)
+    }
+
     fn description(&self) -> &str {
         "Search the web using Brave Search API. Returns titles, URLs, and descriptions."
     }
@@ -71,7 +83,11 @@ impl Tool for WebSearchTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, _ctx: &ToolContext) -> String {
         let Some(query) = args.get("query").and_then(|v| v.as_str()) else {
             return "Error: 'query' parameter is required".into();
         };
@@ -147,8 +163,12 @@ impl Tool for WebFetchTool {
         "web_fetch"
     }
 
+    fn cost(&self) -> CostHint {
+        CostHint::new(CostTier::Expensive, 8)
+    }
+
     fn description(&self) -> &str {
-        "Fetch a web page and extract its text content."
+        "Fetch a web page and extract its text content. JSON APIs are returned as JSON."
     }
 
     fn parameters(&self) -> Value {
@@ -164,10 +184,15 @@ impl Tool for WebFetchTool {
         })
     }
 
-    async fn execute(&self, args: HashMap<String, Value>) -> String {
+    fn mutates(&self, _args: &HashMap<String, Value>) -> bool {
+        false
+    }
+
+    async fn execute(&self, args: HashMap<String, Value>, ctx: &ToolContext) -> String {
         let Some(url) = args.get("url").and_then(|v| v.as_str()) else {
             return "Error: 'url' parameter is required".into();
         };
+        let max_json = if ctx.in_pipeline { MAX_PIPELINE_JSON_BYTES } else { MAX_JSON_BYTES };
 
         debug!(url, "Fetching web page");
 
@@ -182,10 +207,19 @@ impl Tool for WebFetchTool {
             .await;
 
         match response {
-            Ok(resp) if resp.status().is_success() => match resp.text().await {
-                Ok(html) => extract_text_from_html(&html),
-                Err(e) => format!("Error reading response body: {}", e),
-            },
+            Ok(resp) if resp.status().is_success() => {
+                let json = resp
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.contains("json"));
+                match resp.text().await {
+                    // Kept as is so pipelines can navigate it
+                    Ok(body) if json && body.len() <= max_json => body,
+                    Ok(html) => extract_text_from_html(&html),
+                    Err(e) => format!("Error reading response body: {}", e),
+                }
+            }
             Ok(resp) => format!("HTTP error: {}", resp.status()),
             Err(e) => format!("Request failed: {}", e),
         }
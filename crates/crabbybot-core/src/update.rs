@@ -0,0 +1,380 @@
+//! Self-update from GitHub releases.
+//!
+//! The `stable` channel follows the newest non-prerelease with a higher
+//! version than the running binary; `nightly` follows the newest prerelease
+//! not installed yet. Each release carries one binary per platform
+//! ([`asset_name`]), a `SHA256SUMS` file and a detached ed25519 signature
+//! `<asset>.sig` (base64). Nothing is installed unless the signature checks
+//! out against [`UpdateConfig::public_key`], so a self-update needs that key.
+//!
+//! [`install`] writes the verified binary next to the current one, copies the
+//! previous binary to `<exe>.old` and renames the new one over `exe`. The CLI
+//! then starts the new binary once with `--version`; if that fails,
+//! [`rollback`] restores the old one. `~/.CrabbyBot/update.json` remembers
+//! which release is installed so nightly builds are not re-downloaded.
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config::{Config, UpdateConfig};
+
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateError {
+    #[error("unknown release channel '{0}' (expected stable or nightly)")]
+    UnknownChannel(String),
+
+    #[error("release {tag} has no {asset} asset")]
+    MissingAsset { tag: String, asset: String },
+
+    #[error("checksum mismatch for {0}")]
+    ChecksumMismatch(String),
+
+    #[error("signature check failed: {0}")]
+    BadSignature(String),
+
+    #[error("no update.publicKey configured; refusing to install an unsigned binary")]
+    NoPublicKey,
+
+    #[error("no previous binary to roll back to")]
+    NoBackup,
+
+    #[error("GitHub request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("unexpected GitHub response: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Release channel to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Stable,
+    Nightly,
+}
+
+impl FromStr for Channel {
+    type Err = UpdateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "stable" => Ok(Self::Stable),
+            "nightly" => Ok(Self::Nightly),
+            other => Err(UpdateError::UnknownChannel(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// A GitHub release, as returned by the releases API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset, UpdateError> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| UpdateError::MissingAsset {
+                tag: self.tag_name.clone(),
+                asset: name.to_string(),
+            })
+    }
+}
+
+/// The release currently installed by `self-update`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledRelease {
+    pub tag: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+fn state_path() -> PathBuf {
+    Config::config_dir().join("update.json")
+}
+
+impl InstalledRelease {
+    pub fn load() -> Option<Self> {
+        let data = std::fs::read_to_string(state_path()).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = state_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Release binary name for this platform, e.g. `crabbybot-linux-x86_64`.
+pub fn asset_name() -> String {
+    format!(
+        "crabbybot-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// `v1.2.3` / `1.2.3-beta` → `[1, 2, 3]`.
+fn parse_version(tag: &str) -> Vec<u64> {
+    tag.trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// The release on `channel` that should replace what is installed, if any.
+/// `releases` is in the API's newest-first order.
+pub fn pick_release<'a>(
+    releases: &'a [Release],
+    channel: Channel,
+    current_version: &str,
+    installed: Option<&InstalledRelease>,
+) -> Option<&'a Release> {
+    let candidate = releases
+        .iter()
+        .filter(|r| !r.draft)
+        .find(|r| r.prerelease == (channel == Channel::Nightly))?;
+    let newer = match channel {
+        Channel::Stable => parse_version(&candidate.tag_name) > parse_version(current_version),
+        Channel::Nightly => match installed {
+            Some(installed) if installed.tag == candidate.tag_name => false,
+            Some(InstalledRelease {
+                published_at: Some(at), ..
+            }) => candidate.published_at.is_none_or(|p| p > *at),
+            _ => true,
+        },
+    };
+    newer.then_some(candidate)
+}
+
+fn client() -> Result<reqwest::Client, UpdateError> {
+    Ok(reqwest::Client::builder()
+        .user_agent(concat!("CrabbyBot/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+/// Look up the release that would be installed for `channel`.
+pub async fn check(config: &UpdateConfig, channel: Channel) -> Result<Option<Release>, UpdateError> {
+    let url = format!("https://api.github.com/repos/{}/releases?per_page=30", config.repo);
+    let body = client()?.get(url).send().await?.error_for_status()?.text().await?;
+    let releases: Vec<Release> = serde_json::from_str(&body)?;
+    let installed = InstalledRelease::load();
+    Ok(pick_release(&releases, channel, env!("CARGO_PKG_VERSION"), installed.as_ref()).cloned())
+}
+
+/// Hex digest listed for `name` in a `sha256sum`-style file.
+pub fn find_checksum<'a>(sums: &'a str, name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (digest, file) = line.split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then_some(digest)
+    })
+}
+
+/// Check a base64 ed25519 `signature` of `data` against base64 `public_key`.
+pub fn verify_signature(data: &[u8], signature: &str, public_key: &str) -> Result<(), UpdateError> {
+    let bad = |e: &dyn std::fmt::Display| UpdateError::BadSignature(e.to_string());
+    let key: [u8; 32] = B64
+        .decode(public_key.trim())
+        .map_err(|e| bad(&e))?
+        .try_into()
+        .map_err(|_| bad(&"public key must be 32 bytes"))?;
+    let sig: [u8; 64] = B64
+        .decode(signature.trim())
+        .map_err(|e| bad(&e))?
+        .try_into()
+        .map_err(|_| bad(&"signature must be 64 bytes"))?;
+    ed25519_dalek::VerifyingKey::from_bytes(&key)
+        .map_err(|e| bad(&e))?
+        .verify_strict(data, &ed25519_dalek::Signature::from_bytes(&sig))
+        .map_err(|e| bad(&e))
+}
+
+/// Download this platform's binary from `release` and verify its checksum
+/// and signature. Fails before downloading anything when no public key is
+/// configured.
+pub async fn download(release: &Release, config: &UpdateConfig) -> Result<Vec<u8>, UpdateError> {
+    if config.public_key.trim().is_empty() {
+        return Err(UpdateError::NoPublicKey);
+    }
+    let client = client()?;
+    let fetch = |url: String| {
+        let client = client.clone();
+        async move { client.get(url).send().await?.error_for_status()?.bytes().await }
+    };
+    let name = asset_name();
+    let binary = fetch(release.asset(&name)?.browser_download_url.clone()).await?;
+
+    let sums = fetch(release.asset(CHECKSUMS_ASSET)?.browser_download_url.clone()).await?;
+    let expected = find_checksum(&String::from_utf8_lossy(&sums), &name)
+        .map(str::to_lowercase)
+        .ok_or_else(|| UpdateError::ChecksumMismatch(format!("{} is not listed in {}", name, CHECKSUMS_ASSET)))?;
+    if format!("{:x}", Sha256::digest(&binary)) != expected {
+        return Err(UpdateError::ChecksumMismatch(name));
+    }
+
+    let sig = fetch(release.asset(&format!("{}.sig", name))?.browser_download_url.clone()).await?;
+    verify_signature(&binary, &String::from_utf8_lossy(&sig), &config.public_key)?;
+    Ok(binary.to_vec())
+}
+
+fn backup_path(exe: &Path) -> PathBuf {
+    let mut name = exe.as_os_str().to_owned();
+    name.push(".old");
+    PathBuf::from(name)
+}
+
+/// Replace the binary at `exe` with `binary`, keeping a copy of the current
+/// one as `<exe>.old`. The new binary is staged in the same directory and
+/// renamed over `exe` in one step, so `exe` always points at a complete
+/// binary. Windows can't replace a running executable, so there the current
+/// one is moved aside first.
+pub fn install(binary: &[u8], exe: &Path) -> Result<(), UpdateError> {
+    let mut staged = exe.as_os_str().to_owned();
+    staged.push(".new");
+    let staged = PathBuf::from(staged);
+    std::fs::write(&staged, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(windows))]
+    std::fs::copy(exe, backup_path(exe))?;
+    #[cfg(windows)]
+    std::fs::rename(exe, backup_path(exe))?;
+    if let Err(e) = std::fs::rename(&staged, exe) {
+        #[cfg(windows)]
+        let _ = std::fs::rename(backup_path(exe), exe);
+        let _ = std::fs::remove_file(&staged);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Put `<exe>.old` back in place of `exe`.
+pub fn rollback(exe: &Path) -> Result<(), UpdateError> {
+    let backup = backup_path(exe);
+    if !backup.exists() {
+        return Err(UpdateError::NoBackup);
+    }
+    std::fs::rename(backup, exe)?;
+    Ok(())
+}
+
+/// Whether the binary at `exe` starts and exits cleanly with `--version`.
+pub fn probe(exe: &Path) -> bool {
+    std::process::Command::new(exe)
+        .arg("--version")
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool, day: u32) -> Release {
+        Release {
+            tag_name: tag.into(),
+            prerelease,
+            draft: false,
+            published_at: Some(format!("2026-01-{:02}T00:00:00Z", day).parse().unwrap()),
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_pick_release_per_channel() {
+        let releases = [
+            release("nightly-20260105", true, 5),
+            release("v0.3.0", false, 4),
+            release("nightly-20260103", true, 3),
+        ];
+        let stable = pick_release(&releases, Channel::Stable, "0.2.9", None).unwrap();
+        assert_eq!(stable.tag_name, "v0.3.0");
+        assert!(pick_release(&releases, Channel::Stable, "0.3.0", None).is_none());
+
+        let nightly = pick_release(&releases, Channel::Nightly, "0.3.0", None).unwrap();
+        assert_eq!(nightly.tag_name, "nightly-20260105");
+        let installed = InstalledRelease {
+            tag: "nightly-20260105".into(),
+            published_at: releases[0].published_at,
+        };
+        assert!(pick_release(&releases, Channel::Nightly, "0.3.0", Some(&installed)).is_none());
+        assert!("beta".parse::<Channel>().is_err());
+    }
+
+    #[test]
+    fn test_find_checksum() {
+        let sums = "abc123  crabbybot-linux-x86_64\ndef456 *crabbybot-windows-x86_64.exe\n";
+        assert_eq!(find_checksum(sums, "crabbybot-linux-x86_64"), Some("abc123"));
+        assert_eq!(find_checksum(sums, "crabbybot-windows-x86_64.exe"), Some("def456"));
+        assert_eq!(find_checksum(sums, "crabbybot-macos-aarch64"), None);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        use ed25519_dalek::Signer;
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let public = B64.encode(key.verifying_key().to_bytes());
+        let sig = B64.encode(key.sign(b"binary").to_bytes());
+        assert!(verify_signature(b"binary", &sig, &public).is_ok());
+        assert!(verify_signature(b"tampered", &sig, &public).is_err());
+        assert!(verify_signature(b"binary", "not base64!", &public).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_requires_public_key() {
+        let config = UpdateConfig::default();
+        let result = download(&release("v9.9.9", false, 1), &config).await;
+        assert!(matches!(result, Err(UpdateError::NoPublicKey)));
+    }
+
+    #[test]
+    fn test_install_and_rollback() {
+        let dir = std::env::temp_dir().join(format!("CrabbyBot_test_update_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("crabbybot");
+        std::fs::write(&exe, b"old").unwrap();
+
+        install(b"new", &exe).unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), b"new");
+        assert_eq!(std::fs::read(backup_path(&exe)).unwrap(), b"old");
+
+        rollback(&exe).unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), b"old");
+        assert!(matches!(rollback(&exe), Err(UpdateError::NoBackup)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,288 @@
+//! Daily token accounting and the background-work budget.
+//!
+//! The agent loop records the token usage of every LLM call. Totals are kept
+//! per UTC day in `usage/YYYY-MM-DD.json` in the workspace so they survive
+//! restarts.
+//!
+//! When `usage.dailyTokenBudget` is set, low-priority background runs
+//! (heartbeats and cron jobs not marked `critical`) ask
+//! [`UsageTracker::admit_background`] before starting. Once the day's usage
+//! reaches `usage.backgroundCutoffPercent` of the budget they are skipped,
+//! leaving the remainder for the user, and the admin chat is told once per
+//! day. A `budget_alert` workspace template (see [`crate::templates`]), given
+//! `used`, `budget`, `percent` and `what`, replaces the built-in notice.
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::bus::events::OutboundMessage;
+use crate::bus::MessageBus;
+use crate::config::UsageConfig;
+use crate::provider::types::Usage;
+use crate::templates::TemplateRegistry;
+
+/// Token totals for one day.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyUsage {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl DailyUsage {
+    fn add(&mut self, usage: &Usage) {
+        self.requests += 1;
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+    }
+}
+
+/// Where budget notices go.
+struct Notifier {
+    bus: Arc<MessageBus>,
+    channel: String,
+    chat_id: String,
+}
+
+struct Inner {
+    day: NaiveDate,
+    usage: DailyUsage,
+    /// Whether the admin was already told about skipped runs today.
+    notified: bool,
+}
+
+/// Shared recorder of daily token usage.
+///
+/// The default instance keeps totals in memory only and has no budget.
+pub struct UsageTracker {
+    dir: Option<PathBuf>,
+    /// Templates of the configured workspace, for the budget notice.
+    templates: Option<TemplateRegistry>,
+    config: UsageConfig,
+    notifier: Option<Notifier>,
+    inner: Mutex<Inner>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self::with_dir(None, UsageConfig::default())
+    }
+}
+
+impl UsageTracker {
+    /// Persist totals under `workspace`, starting from today's file if one
+    /// exists.
+    pub fn new(workspace: &Path, config: UsageConfig) -> Self {
+        Self {
+            templates: Some(TemplateRegistry::new(workspace)),
+            ..Self::with_dir(Some(workspace.join("usage")), config)
+        }
+    }
+
+    fn with_dir(dir: Option<PathBuf>, config: UsageConfig) -> Self {
+        let day = Utc::now().date_naive();
+        let usage = dir.as_deref().map(|d| Self::read_day(d, day)).unwrap_or_default();
+        Self {
+            dir,
+            templates: None,
+            config,
+            notifier: None,
+            inner: Mutex::new(Inner {
+                day,
+                usage,
+                notified: false,
+            }),
+        }
+    }
+
+    /// Send the daily "background work paused" notice to this chat.
+    pub fn notify(mut self, bus: Arc<MessageBus>, channel: impl Into<String>, chat_id: impl Into<String>) -> Self {
+        self.notifier = Some(Notifier {
+            bus,
+            channel: channel.into(),
+            chat_id: chat_id.into(),
+        });
+        self
+    }
+
+    fn day_path(dir: &Path, day: NaiveDate) -> PathBuf {
+        dir.join(format!("{}.json", day.format("%Y-%m-%d")))
+    }
+
+    fn read_day(dir: &Path, day: NaiveDate) -> DailyUsage {
+        std::fs::read_to_string(Self::day_path(dir, day))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// The persisted totals of one day from a workspace.
+    pub fn load_day(workspace: &Path, day: NaiveDate) -> DailyUsage {
+        Self::read_day(&workspace.join("usage"), day)
+    }
+
+    /// Start a fresh day if the date changed since the last access.
+    fn roll_over(inner: &mut Inner) {
+        let today = Utc::now().date_naive();
+        if inner.day != today {
+            *inner = Inner {
+                day: today,
+                usage: DailyUsage::default(),
+                notified: false,
+            };
+        }
+    }
+
+    /// Add one LLM call's usage to today's totals.
+    pub fn record(&self, usage: &Usage) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        Self::roll_over(&mut inner);
+        inner.usage.add(usage);
+        if let Some(ref dir) = self.dir {
+            let written = std::fs::create_dir_all(dir).and_then(|_| {
+                let json = serde_json::to_string_pretty(&inner.usage)?;
+                std::fs::write(Self::day_path(dir, inner.day), json)
+            });
+            if let Err(e) = written {
+                warn!("Failed to persist token usage: {}", e);
+            }
+        }
+    }
+
+    /// Today's totals.
+    pub fn today(&self) -> DailyUsage {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                Self::roll_over(&mut inner);
+                inner.usage.clone()
+            }
+            Err(_) => DailyUsage::default(),
+        }
+    }
+
+    /// The configured daily budget, if any.
+    pub fn budget(&self) -> Option<u64> {
+        (self.config.daily_token_budget > 0).then_some(self.config.daily_token_budget)
+    }
+
+    /// Whether low-priority background work may still run today.
+    pub fn background_allowed(&self) -> bool {
+        let Some(budget) = self.budget() else {
+            return true;
+        };
+        let cutoff = budget * u64::from(self.config.background_cutoff_percent.min(100)) / 100;
+        self.today().total_tokens < cutoff
+    }
+
+    /// Gate a low-priority background run described by `what`.
+    ///
+    /// Returns `false` when the run should be skipped. The first skip of the
+    /// day is reported to the admin chat.
+    pub async fn admit_background(&self, what: &str) -> bool {
+        if self.background_allowed() {
+            return true;
+        }
+        let (used, first) = match self.inner.lock() {
+            Ok(mut inner) => (inner.usage.total_tokens, !std::mem::replace(&mut inner.notified, true)),
+            Err(_) => (0, false),
+        };
+        let budget = self.budget().unwrap_or_default();
+        info!(what, used, budget, "Token budget nearly exhausted; skipping background run");
+
+        if first {
+            warn!(used, budget, "Pausing low-priority background work for the rest of the day");
+            if let Some(ref n) = self.notifier {
+                let context = serde_json::json!({
+                    "used": used,
+                    "budget": budget,
+                    "percent": used * 100 / budget.max(1),
+                    "what": what,
+                });
+                let notice = match &self.templates {
+                    Some(t) => t.render_or("budget_alert", &n.channel, &context, || budget_notice(used, budget, what)),
+                    None => budget_notice(used, budget, what),
+                };
+                n.bus
+                    .publish_outbound(OutboundMessage::reply(&n.channel, &n.chat_id, notice))
+                    .await;
+            }
+        }
+        false
+    }
+
+}
+
+fn budget_notice(used: u64, budget: u64, what: &str) -> String {
+    format!(
+        "⏸️ *Token budget nearly used up*\n\n\
+         {} of {} tokens used today ({}%). Heartbeats and non-critical \
+         scheduled jobs are paused until the budget resets at 00:00 UTC.\n\
+         First skipped: {}",
+        used,
+        budget,
+        used * 100 / budget.max(1),
+        what
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(total: u32) -> Usage {
+        Usage {
+            prompt_tokens: total - total / 4,
+            completion_tokens: total / 4,
+            total_tokens: total,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_background_work_pauses_near_budget() {
+        let tmp = std::env::temp_dir().join("CrabbyBot_test_usage");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let config = UsageConfig {
+            daily_token_budget: 1_000,
+            background_cutoff_percent: 90,
+            ..Default::default()
+        };
+        let (bus, mut receivers) = MessageBus::new(8);
+        let tracker = UsageTracker::new(&tmp, config.clone()).notify(Arc::new(bus), "telegram", "42");
+
+        tracker.record(&usage(800));
+        assert!(tracker.admit_background("heartbeat").await);
+        tracker.record(&usage(100));
+        assert!(!tracker.admit_background("heartbeat").await);
+        assert!(!tracker.admit_background("cron job \"digest\"").await);
+
+        // One notice per day, naming the first skipped run.
+        let Ok(OutboundMessage::Reply { chat_id, content, .. }) = receivers.outbound_rx.try_recv() else {
+            panic!("expected a budget notice");
+        };
+        assert_eq!(chat_id, "42");
+        assert!(content.contains("900 of 1000 tokens used today (90%)"), "{content}");
+        assert!(content.contains("First skipped: heartbeat"), "{content}");
+        assert!(receivers.outbound_rx.try_recv().is_err());
+
+        // Totals survive a restart.
+        let reloaded = UsageTracker::new(&tmp, config);
+        assert_eq!(reloaded.today().requests, 2);
+        assert_eq!(reloaded.today().total_tokens, 900);
+        assert!(!reloaded.background_allowed());
+
+        // Without a budget nothing is ever skipped.
+        let unlimited = UsageTracker::default();
+        unlimited.record(&usage(1_000_000));
+        assert!(unlimited.admit_background("heartbeat").await);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
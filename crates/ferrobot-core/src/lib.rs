@@ -0,0 +1,6 @@
+//! Compatibility facade for code written against `ferrobot_core`.
+//!
+//! The sibling core crates were merged into [`crabbybot_core`]; this crate
+//! re-exports it unchanged and forwards its cargo features.
+
+pub use crabbybot_core::*;